@@ -0,0 +1,984 @@
+use crate::draw::*;
+use crate::path::*;
+use crate::font::*;
+use crate::color::*;
+use crate::sprite::*;
+use crate::texture::*;
+use crate::decoding::*;
+use crate::gradient::*;
+use crate::font_face::*;
+use crate::namespace::*;
+use crate::transform2d::*;
+use crate::binary_encoding::*;
+
+use futures::*;
+use futures::stream;
+use futures::task::{Poll};
+
+use uuid::*;
+
+use std::sync::*;
+use std::convert::{TryFrom};
+use std::collections::{VecDeque};
+use std::result::Result;
+
+///
+/// Reasons that the binary reader might fail to read a value
+///
+enum ReadError {
+    /// More bytes are needed: the value is the total number of bytes that must be in the buffer before the instruction can be read
+    Incomplete(usize),
+
+    /// The bytes could not be decoded
+    Invalid(DecoderError),
+}
+
+impl From<DecoderError> for ReadError {
+    fn from(err: DecoderError) -> ReadError {
+        ReadError::Invalid(err)
+    }
+}
+
+///
+/// Reads the values that make up a single binary-encoded instruction from a buffer
+///
+struct BinaryReader<'a> {
+    /// The bytes being read
+    bytes: &'a [u8],
+
+    /// The position of the next byte to read
+    pos: usize,
+
+    /// The last path coordinate that was read
+    last_point: (f32, f32),
+
+    /// The glyphs read so far from an incomplete `DrawGlyphs` instruction, along with the position of the first glyph that wasn't read
+    partial_glyphs: Option<(usize, Vec<GlyphPosition>)>,
+}
+
+impl<'a> BinaryReader<'a> {
+    ///
+    /// Reads a number of bytes from the buffer
+    ///
+    #[inline]
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ReadError> {
+        let end = self.pos.saturating_add(len);
+
+        if end > self.bytes.len() {
+            Err(ReadError::Incomplete(end))
+        } else {
+            let result  = &self.bytes[self.pos..end];
+            self.pos    = end;
+
+            Ok(result)
+        }
+    }
+
+    #[inline]
+    fn byte(&mut self) -> Result<u8, ReadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    ///
+    /// Reads a variable-length integer
+    ///
+    fn varint(&mut self) -> Result<u64, ReadError> {
+        let mut result  = 0u64;
+        let mut shift   = 0;
+
+        loop {
+            let next_byte   = self.byte()?;
+            let seven_bits  = (next_byte & 0x7f) as u64;
+
+            // Values that would not fit in a u64 are invalid
+            if shift > 63 || (shift == 63 && seven_bits > 1) {
+                return Err(DecoderError::BadNumber.into());
+            }
+
+            result  |= seven_bits << shift;
+            shift   += 7;
+
+            if (next_byte & 0x80) == 0 {
+                return Ok(result);
+            }
+        }
+    }
+
+    #[inline]
+    fn u32(&mut self) -> Result<u32, ReadError> {
+        u32::try_from(self.varint()?).map_err(|_| DecoderError::BadNumber.into())
+    }
+
+    #[inline]
+    fn len(&mut self) -> Result<usize, ReadError> {
+        usize::try_from(self.varint()?).map_err(|_| DecoderError::BadNumber.into())
+    }
+
+    #[inline]
+    fn u64_raw(&mut self) -> Result<u64, ReadError> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]]))
+    }
+
+    ///
+    /// Reads a 4-byte OpenType tag
+    ///
+    #[inline]
+    fn tag(&mut self) -> Result<[u8; 4], ReadError> {
+        let bytes = self.take(4)?;
+        Ok([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    ///
+    /// Reads an f32 value encoded relative to a base value
+    ///
+    fn compact_f32(&mut self, base: f32) -> Result<f32, ReadError> {
+        let tag = self.varint()?;
+
+        if tag == 1 {
+            // Raw f32 value
+            let bytes = self.take(4)?;
+            Ok(f32::from_bits(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])))
+        } else if (tag & 1) == 0 {
+            // Number of steps from the base value
+            let zigzag  = tag >> 1;
+            let steps   = ((zigzag >> 1) as i64) ^ -((zigzag & 1) as i64);
+
+            if (steps.abs() as f32) > COMPACT_F32_MAX_STEPS {
+                Err(DecoderError::BadNumber.into())
+            } else {
+                Ok(base + (steps as f32) / COMPACT_F32_STEPS)
+            }
+        } else {
+            Err(DecoderError::BadNumber.into())
+        }
+    }
+
+    #[inline]
+    fn f32(&mut self) -> Result<f32, ReadError> {
+        self.compact_f32(0.0)
+    }
+
+    #[inline]
+    fn pair(&mut self) -> Result<(f32, f32), ReadError> {
+        Ok((self.f32()?, self.f32()?))
+    }
+
+    ///
+    /// Reads a path coordinate (encoded relative to the last coordinate)
+    ///
+    fn point(&mut self) -> Result<(f32, f32), ReadError> {
+        let x = self.compact_f32(self.last_point.0)?;
+        let y = self.compact_f32(self.last_point.1)?;
+
+        self.last_point = (x, y);
+
+        Ok((x, y))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], ReadError> {
+        let len = self.len()?;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<String, ReadError> {
+        let bytes = self.bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| DecoderError::InvalidString.into())
+    }
+
+    fn color(&mut self) -> Result<Color, ReadError> {
+        match self.byte()? {
+            0   => Ok(Color::Rgba(self.f32()?, self.f32()?, self.f32()?, self.f32()?)),
+            1   => Ok(Color::Hsluv(self.f32()?, self.f32()?, self.f32()?, self.f32()?)),
+            _   => Err(DecoderError::UnknownColorType.into())
+        }
+    }
+
+    fn line_join(&mut self) -> Result<LineJoin, ReadError> {
+        match self.byte()? {
+            0       => Ok(LineJoin::Miter),
+            1       => Ok(LineJoin::Round),
+            2       => Ok(LineJoin::Bevel),
+            other   => Err(DecoderError::InvalidByte(other).into())
+        }
+    }
+
+    fn line_cap(&mut self) -> Result<LineCap, ReadError> {
+        match self.byte()? {
+            0       => Ok(LineCap::Butt),
+            1       => Ok(LineCap::Round),
+            2       => Ok(LineCap::Square),
+            other   => Err(DecoderError::InvalidByte(other).into())
+        }
+    }
+
+    fn winding_rule(&mut self) -> Result<WindingRule, ReadError> {
+        match self.byte()? {
+            0       => Ok(WindingRule::NonZero),
+            1       => Ok(WindingRule::EvenOdd),
+            other   => Err(DecoderError::InvalidByte(other).into())
+        }
+    }
+
+    fn path_arithmetic(&mut self) -> Result<PathArithmetic, ReadError> {
+        match self.byte()? {
+            0       => Ok(PathArithmetic::Union),
+            1       => Ok(PathArithmetic::Intersection),
+            2       => Ok(PathArithmetic::Difference),
+            3       => Ok(PathArithmetic::Xor),
+            other   => Err(DecoderError::InvalidByte(other).into())
+        }
+    }
+
+    fn gradient_spread(&mut self) -> Result<GradientSpread, ReadError> {
+        match self.byte()? {
+            0       => Ok(GradientSpread::Pad),
+            1       => Ok(GradientSpread::Repeat),
+            2       => Ok(GradientSpread::Reflect),
+            other   => Err(DecoderError::InvalidByte(other).into())
+        }
+    }
+
+    fn blend_mode(&mut self) -> Result<BlendMode, ReadError> {
+        use self::BlendMode::*;
+
+        match self.byte()? {
+            0       => Ok(SourceOver),
+            1       => Ok(SourceIn),
+            2       => Ok(SourceOut),
+            3       => Ok(DestinationOver),
+            4       => Ok(DestinationIn),
+            5       => Ok(DestinationOut),
+            6       => Ok(SourceAtop),
+            7       => Ok(DestinationAtop),
+
+            8       => Ok(Multiply),
+            9       => Ok(Screen),
+            10      => Ok(Darken),
+            11      => Ok(Lighten),
+            12      => Ok(Overlay),
+            13      => Ok(ColorDodge),
+            14      => Ok(ColorBurn),
+            15      => Ok(HardLight),
+            16      => Ok(SoftLight),
+            17      => Ok(Difference),
+            18      => Ok(Exclusion),
+
+            19      => Ok(Hue),
+            20      => Ok(Saturation),
+            21      => Ok(Color),
+            22      => Ok(Luminosity),
+
+            other   => Err(DecoderError::InvalidByte(other).into())
+        }
+    }
+
+    fn text_alignment(&mut self) -> Result<TextAlignment, ReadError> {
+        match self.byte()? {
+            0       => Ok(TextAlignment::Left),
+            1       => Ok(TextAlignment::Right),
+            2       => Ok(TextAlignment::Center),
+            other   => Err(DecoderError::InvalidByte(other).into())
+        }
+    }
+
+    fn paragraph_style(&mut self) -> Result<ParagraphStyle, ReadError> {
+        let width       = self.f32()?;
+        let alignment   = match self.byte()? {
+            0       => ParagraphAlignment::Left,
+            1       => ParagraphAlignment::Right,
+            2       => ParagraphAlignment::Center,
+            3       => ParagraphAlignment::Justify,
+            other   => { return Err(DecoderError::InvalidByte(other).into()); }
+        };
+        let line_height = self.f32()?;
+        let spacing     = self.f32()?;
+        let overflow    = match self.byte()? {
+            0       => TextOverflow::Visible,
+            1       => TextOverflow::Clip(self.f32()?),
+            2       => TextOverflow::Ellipsis(self.f32()?),
+            other   => { return Err(DecoderError::InvalidByte(other).into()); }
+        };
+
+        Ok(ParagraphStyle {
+            width:              width,
+            alignment:          alignment,
+            line_height:        line_height,
+            paragraph_spacing:  spacing,
+            overflow:           overflow,
+        })
+    }
+
+    fn transform(&mut self) -> Result<Transform2D, ReadError> {
+        let mut matrix = [[0.0; 3]; 3];
+
+        for row in matrix.iter_mut() {
+            for val in row.iter_mut() {
+                *val = self.f32()?;
+            }
+        }
+
+        Ok(Transform2D(matrix))
+    }
+
+    fn optional_pair(&mut self) -> Result<Option<(f32, f32)>, ReadError> {
+        match self.byte()? {
+            0       => Ok(None),
+            1       => Ok(Some(self.pair()?)),
+            other   => Err(DecoderError::InvalidByte(other).into())
+        }
+    }
+
+    fn sprite_transform(&mut self) -> Result<SpriteTransform, ReadError> {
+        match self.byte()? {
+            0       => Ok(SpriteTransform::Identity),
+            1       => Ok(SpriteTransform::Translate(self.f32()?, self.f32()?)),
+            2       => Ok(SpriteTransform::Scale(self.f32()?, self.f32()?)),
+            3       => Ok(SpriteTransform::Rotate(self.f32()?)),
+            4       => Ok(SpriteTransform::Transform2D(self.transform()?)),
+            other   => Err(DecoderError::InvalidByte(other).into())
+        }
+    }
+
+    fn namespace_id(&mut self) -> Result<NamespaceId, ReadError> {
+        let global_a = self.u64_raw()?;
+        let global_b = self.u64_raw()?;
+
+        Ok(NamespaceId::with_id(Uuid::from_u64_pair(global_a, global_b)))
+    }
+
+    fn sprite_bounds(&mut self) -> Result<SpriteBounds, ReadError> {
+        let (x, y) = self.pair()?;
+        let (w, h) = self.pair()?;
+
+        Ok(SpriteBounds(SpritePosition(x, y), SpriteSize(w, h)))
+    }
+
+    fn texture_filter(&mut self) -> Result<TextureFilter, ReadError> {
+        match self.byte()? {
+            0       => Ok(TextureFilter::GaussianBlur(self.f32()?)),
+            1       => Ok(TextureFilter::AlphaBlend(self.f32()?)),
+            2       => Ok(TextureFilter::Mask(TextureId(self.varint()?))),
+            3       => Ok(TextureFilter::DisplacementMap(TextureId(self.varint()?), self.f32()?, self.f32()?)),
+            other   => Err(DecoderError::InvalidByte(other).into())
+        }
+    }
+
+    fn texture_filters(&mut self) -> Result<Vec<TextureFilter>, ReadError> {
+        let num_filters = self.len()?;
+        let mut filters = vec![];
+
+        for _ in 0..num_filters {
+            filters.push(self.texture_filter()?);
+        }
+
+        Ok(filters)
+    }
+
+    fn texture_op(&mut self) -> Result<TextureOp, ReadError> {
+        match self.byte()? {
+            0       => {
+                let width   = self.u32()?;
+                let height  = self.u32()?;
+
+                match self.byte()? {
+                    0       => Ok(TextureOp::Create(TextureSize(width, height), TextureFormat::Rgba)),
+                    other   => Err(DecoderError::InvalidByte(other).into())
+                }
+            }
+
+            1       => Ok(TextureOp::Free),
+            2       => {
+                let (x, y)          = (self.u32()?, self.u32()?);
+                let (width, height) = (self.u32()?, self.u32()?);
+                let bytes           = self.bytes()?;
+
+                Ok(TextureOp::SetBytes(TexturePosition(x, y), TextureSize(width, height), Arc::new(bytes.to_vec())))
+            }
+            3       => Ok(TextureOp::SetFromSprite(SpriteId(self.varint()?), self.sprite_bounds()?)),
+            4       => {
+                let sprite_id   = SpriteId(self.varint()?);
+                let bounds      = self.sprite_bounds()?;
+                let (w, h)      = self.pair()?;
+
+                Ok(TextureOp::CreateDynamicSprite(sprite_id, bounds, CanvasSize(w, h)))
+            }
+            5       => Ok(TextureOp::FillTransparency(self.f32()?)),
+            6       => Ok(TextureOp::Copy(TextureId(self.varint()?))),
+            7       => Ok(TextureOp::Filter(self.texture_filter()?)),
+            other   => Err(DecoderError::InvalidByte(other).into())
+        }
+    }
+
+    fn gradient_op(&mut self) -> Result<GradientOp, ReadError> {
+        match self.byte()? {
+            0       => Ok(GradientOp::Create(self.color()?)),
+            1       => Ok(GradientOp::AddStop(self.f32()?, self.color()?)),
+            2       => Ok(GradientOp::Spread(self.gradient_spread()?)),
+            other   => Err(DecoderError::InvalidByte(other).into())
+        }
+    }
+
+    fn glyph(&mut self) -> Result<GlyphPosition, ReadError> {
+        let id          = GlyphId(self.u32()?);
+        let location    = self.pair()?;
+        let em_size     = self.f32()?;
+
+        Ok(GlyphPosition { id: id, location: location, em_size: em_size })
+    }
+
+    fn font_op(&mut self) -> Result<FontOp, ReadError> {
+        match self.byte()? {
+            0       => Ok(FontOp::UseFontDefinition(CanvasFontFace::from_slice(self.bytes()?))),
+            1       => Ok(FontOp::FontSize(self.f32()?)),
+            2       => Ok(FontOp::LayoutText(self.string()?)),
+            3       => {
+                let num_glyphs  = self.len()?;
+
+                // Carry on from the glyph where an earlier attempt to read this instruction ran out of bytes
+                let mut glyphs  = match self.partial_glyphs.take() {
+                    Some((glyph_pos, glyphs))   => { self.pos = glyph_pos; glyphs }
+                    None                        => vec![]
+                };
+
+                while glyphs.len() < num_glyphs {
+                    let glyph_pos = self.pos;
+
+                    match self.glyph() {
+                        Ok(glyph)                           => { glyphs.push(glyph); }
+                        Err(ReadError::Incomplete(needed))  => {
+                            self.partial_glyphs = Some((glyph_pos, glyphs));
+                            return Err(ReadError::Incomplete(needed));
+                        }
+                        Err(err)                            => { return Err(err); }
+                    }
+                }
+
+                Ok(FontOp::DrawGlyphs(glyphs))
+            }
+            4       => {
+                let num_fonts   = self.len()?;
+                let mut fonts   = vec![];
+
+                for _ in 0..num_fonts {
+                    fonts.push(FontId(self.varint()?));
+                }
+
+                Ok(FontOp::UseFallbackFonts(fonts))
+            }
+            5       => {
+                let tag         = self.tag()?;
+                let enabled     = match self.byte()? {
+                    0       => false,
+                    1       => true,
+                    other   => { return Err(DecoderError::InvalidByte(other).into()); }
+                };
+
+                Ok(FontOp::FontFeature(tag, enabled))
+            }
+            6       => Ok(FontOp::FontVariation(self.tag()?, self.f32()?)),
+            other   => Err(DecoderError::InvalidByte(other).into())
+        }
+    }
+
+    ///
+    /// Reads the next drawing instruction
+    ///
+    fn draw(&mut self) -> Result<Draw, ReadError> {
+        use self::Draw::*;
+        use self::PathOp::*;
+
+        let draw = match self.byte()? {
+            OP_START_FRAME                  => StartFrame,
+            OP_SHOW_FRAME                   => ShowFrame,
+            OP_RESET_FRAME                  => ResetFrame,
+
+            OP_NEW_PATH                     => Path(NewPath),
+            OP_MOVE                         => { let (x, y) = self.point()?; Path(Move(x, y)) }
+            OP_LINE                         => { let (x, y) = self.point()?; Path(Line(x, y)) }
+            OP_BEZIER_CURVE                 => { let cp1 = self.point()?; let cp2 = self.point()?; let p = self.point()?; Path(BezierCurve((cp1, cp2), p)) }
+            OP_QUADRATIC_CURVE              => { let cp = self.point()?; let p = self.point()?; Path(QuadraticCurve(cp, p)) }
+            OP_ARC                          => Path(Arc(self.point()?, self.pair()?, self.f32()?, self.f32()?, self.f32()?)),
+            OP_CLOSE_PATH                   => Path(ClosePath),
+            OP_STORE_PATH                   => StorePath,
+            OP_COMBINE_PATH                 => CombinePath(self.path_arithmetic()?),
+
+            OP_FILL                         => Fill,
+            OP_STROKE                       => Stroke,
+            OP_LINE_WIDTH                   => LineWidth(self.f32()?),
+            OP_LINE_WIDTH_PIXELS            => LineWidthPixels(self.f32()?),
+            OP_LINE_JOIN                    => LineJoin(self.line_join()?),
+            OP_MITER_LIMIT                  => MiterLimit(self.f32()?),
+            OP_LINE_CAP                     => LineCap(self.line_cap()?),
+            OP_NEW_DASH_PATTERN             => NewDashPattern,
+            OP_DASH_LENGTH                  => DashLength(self.f32()?),
+            OP_DASH_OFFSET                  => DashOffset(self.f32()?),
+
+            OP_FILL_COLOR                   => FillColor(self.color()?),
+            OP_FILL_TEXTURE                 => FillTexture(TextureId(self.varint()?), self.pair()?, self.pair()?),
+            OP_FILL_GRADIENT                => FillGradient(GradientId(self.varint()?), self.pair()?, self.pair()?),
+            OP_FILL_RADIAL_GRADIENT         => FillRadialGradient(GradientId(self.varint()?), self.pair()?, self.f32()?, self.optional_pair()?),
+            OP_FILL_CONIC_GRADIENT          => FillConicGradient(GradientId(self.varint()?), self.pair()?, self.f32()?),
+            OP_FILL_TRANSFORM               => FillTransform(self.transform()?),
+            OP_STROKE_COLOR                 => StrokeColor(self.color()?),
+            OP_STROKE_TEXTURE               => StrokeTexture(TextureId(self.varint()?), self.pair()?, self.pair()?),
+            OP_STROKE_GRADIENT              => StrokeGradient(GradientId(self.varint()?), self.pair()?, self.pair()?),
+            OP_WINDING_RULE                 => WindingRule(self.winding_rule()?),
+            OP_BLEND_MODE                   => BlendMode(self.blend_mode()?),
+
+            OP_IDENTITY_TRANSFORM           => IdentityTransform,
+            OP_CANVAS_HEIGHT                => CanvasHeight(self.f32()?),
+            OP_CENTER_REGION                => CenterRegion(self.pair()?, self.pair()?),
+            OP_MULTIPLY_TRANSFORM           => MultiplyTransform(self.transform()?),
+            OP_UNCLIP                       => Unclip,
+            OP_CLIP                         => Clip,
+            OP_STORE                        => Store,
+            OP_RESTORE                      => Restore,
+            OP_FREE_STORED_BUFFER           => FreeStoredBuffer,
+            OP_PUSH_STATE                   => PushState,
+            OP_POP_STATE                    => PopState,
+
+            OP_CLEAR_CANVAS                 => ClearCanvas(self.color()?),
+            OP_LAYER                        => Layer(LayerId(self.varint()?)),
+            OP_LAYER_BLEND                  => LayerBlend(LayerId(self.varint()?), self.blend_mode()?),
+            OP_LAYER_ALPHA                  => LayerAlpha(LayerId(self.varint()?), self.f32()?),
+            OP_CLEAR_LAYER                  => ClearLayer,
+            OP_CLEAR_ALL_LAYERS             => ClearAllLayers,
+            OP_SWAP_LAYERS                  => SwapLayers(LayerId(self.varint()?), LayerId(self.varint()?)),
+
+            OP_SPRITE                       => Sprite(SpriteId(self.varint()?)),
+            OP_MOVE_SPRITE_FROM             => MoveSpriteFrom(SpriteId(self.varint()?)),
+            OP_CLEAR_SPRITE                 => ClearSprite,
+            OP_SPRITE_TRANSFORM             => SpriteTransform(self.sprite_transform()?),
+            OP_DRAW_SPRITE                  => DrawSprite(SpriteId(self.varint()?)),
+            OP_DRAW_SPRITE_WITH_FILTERS     => DrawSpriteWithFilters(SpriteId(self.varint()?), self.texture_filters()?),
+
+            OP_TEXTURE                      => Texture(TextureId(self.varint()?), self.texture_op()?),
+            OP_FONT                         => Font(FontId(self.varint()?), self.font_op()?),
+            OP_BEGIN_LINE_LAYOUT            => BeginLineLayout(self.f32()?, self.f32()?, self.text_alignment()?),
+            OP_BEGIN_PARAGRAPH_LAYOUT       => BeginParagraphLayout(self.f32()?, self.f32()?, self.paragraph_style()?),
+            OP_DRAW_LAID_OUT_TEXT           => DrawLaidOutText,
+            OP_DRAW_TEXT                    => DrawText(FontId(self.varint()?), self.string()?, self.f32()?, self.f32()?),
+            OP_GRADIENT                     => Gradient(GradientId(self.varint()?), self.gradient_op()?),
+            OP_NAMESPACE                    => Namespace(self.namespace_id()?),
+
+            other                           => { return Err(DecoderError::InvalidByte(other).into()); }
+        };
+
+        Ok(draw)
+    }
+}
+
+///
+/// Decodes drawing instructions written by a `BinaryCanvasEncoder`
+///
+/// Bytes can be supplied in chunks of any size: the decoder keeps any partial instruction at the end of a chunk until the
+/// rest of it arrives.
+///
+pub struct BinaryCanvasDecoder {
+    /// Bytes that have not been decoded yet (the start of an incomplete instruction)
+    buffer: Vec<u8>,
+
+    /// The number of bytes that must be in the buffer before the next instruction can be decoded
+    needed: usize,
+
+    /// The last path coordinate that was decoded
+    last_point: (f32, f32),
+
+    /// The glyphs decoded so far from the incomplete instruction at the start of the buffer, if it's a `DrawGlyphs` instruction
+    /// (so long lists of glyphs aren't decoded again from the start every time more bytes arrive)
+    partial_glyphs: Option<(usize, Vec<GlyphPosition>)>,
+
+    /// Set to true if the decoder has encountered an error
+    failed: bool,
+}
+
+impl BinaryCanvasDecoder {
+    ///
+    /// Creates a new binary decoder
+    ///
+    pub fn new() -> BinaryCanvasDecoder {
+        BinaryCanvasDecoder {
+            buffer:         vec![],
+            needed:         0,
+            last_point:     (0.0, 0.0),
+            partial_glyphs: None,
+            failed:         false,
+        }
+    }
+
+    ///
+    /// True if the decoder is holding the start of an instruction that hasn't been completed yet
+    ///
+    pub (crate) fn has_partial_instruction(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+
+    ///
+    /// Decodes some bytes, adding any drawing instructions that are completed to the output
+    ///
+    /// If an error is returned, the instructions that were decoded before the error are still added to the output, and
+    /// the decoder will return `IsInErrorState` for any further bytes.
+    ///
+    pub fn decode(&mut self, bytes: &[u8], output: &mut Vec<Draw>) -> Result<(), DecoderError> {
+        if self.failed {
+            return Err(DecoderError::IsInErrorState);
+        }
+
+        self.buffer.extend_from_slice(bytes);
+
+        // Avoid re-reading a partial instruction until enough bytes have arrived to complete it
+        if self.buffer.len() < self.needed {
+            return Ok(());
+        }
+
+        let mut pos = 0;
+        let mut result = Ok(());
+
+        while pos < self.buffer.len() {
+            // Only the instruction at the start of the buffer can have been partially decoded before
+            let mut reader = BinaryReader {
+                bytes:          &self.buffer,
+                pos:            pos,
+                last_point:     self.last_point,
+                partial_glyphs: if pos == 0 { self.partial_glyphs.take() } else { None },
+            };
+
+            match reader.draw() {
+                Ok(draw) => {
+                    pos             = reader.pos;
+                    self.last_point = reader.last_point;
+                    output.push(draw);
+                }
+
+                Err(ReadError::Incomplete(needed)) => {
+                    // The incomplete instruction is moved to the start of the buffer
+                    self.needed         = needed - pos;
+                    self.partial_glyphs = reader.partial_glyphs.take().map(|(glyph_pos, glyphs)| (glyph_pos - pos, glyphs));
+                    break;
+                }
+
+                Err(ReadError::Invalid(err)) => {
+                    self.failed = true;
+                    result      = Err(err);
+                    break;
+                }
+            }
+        }
+
+        if pos >= self.buffer.len() {
+            self.needed = 0;
+        }
+
+        self.buffer.drain(0..pos);
+
+        result
+    }
+}
+
+///
+/// Decodes a canvas drawing represented as an iterator of bytes produced by the binary encoder. If there's an error in the
+/// stream, it will be the last item decoded.
+///
+pub fn decode_binary_drawing<In: IntoIterator<Item=u8>>(source: In) -> impl Iterator<Item=Result<Draw, DecoderError>> {
+    let mut decoder     = BinaryCanvasDecoder::new();
+    let mut seen_error  = false;
+
+    source.into_iter()
+        .flat_map(move |byte| {
+            let mut draws   = vec![];
+            let result      = decoder.decode(&[byte], &mut draws);
+            let mut draws   = draws.into_iter().map(|draw| Ok(draw)).collect::<Vec<_>>();
+
+            // The decoder will just return errors once it hits a failure: only return the initial error
+            if let Err(err) = result {
+                if !seen_error {
+                    seen_error = true;
+                    draws.push(Err(err));
+                }
+            }
+
+            draws
+        })
+}
+
+///
+/// Decodes a canvas drawing represented as a stream of byte buffers produced by the binary encoder
+///
+/// The buffers can be split at any point: instructions that span more than one buffer are decoded when the last part of
+/// them arrives.
+///
+pub fn decode_binary_drawing_stream<In: Unpin+Stream<Item=Result<Chunk, E>>, Chunk: AsRef<[u8]>, E>(source: In) -> impl Unpin+Stream<Item=Result<Draw, StreamDecoderError<E>>> {
+    let mut source      = source;
+    let mut decoder     = BinaryCanvasDecoder::new();
+    let mut ready       = VecDeque::new();
+    let mut seen_error  = false;
+
+    stream::poll_fn(move |context| {
+        loop {
+            // Return any instructions that have already been decoded
+            if let Some(next) = ready.pop_front() {
+                return Poll::Ready(Some(next));
+            }
+
+            // Only allow one error from the decoder (it remains in an error state after this)
+            if seen_error {
+                return Poll::Ready(None);
+            }
+
+            match source.poll_next_unpin(context) {
+                Poll::Ready(None)               => { return Poll::Ready(None); },
+                Poll::Pending                   => { return Poll::Pending; },
+                Poll::Ready(Some(Ok(chunk)))    => {
+                    let mut draws   = vec![];
+                    let result      = decoder.decode(chunk.as_ref(), &mut draws);
+
+                    ready.extend(draws.into_iter().map(|draw| Ok(draw)));
+
+                    if let Err(err) = result {
+                        seen_error = true;
+                        ready.push_back(Err(StreamDecoderError::Decoder(err)));
+                    }
+                },
+
+                Poll::Ready(Some(Err(err)))     => { return Poll::Ready(Some(Err(StreamDecoderError::Stream(err)))); }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoding::*;
+
+    use futures::executor;
+
+    fn encode(instructions: &Vec<Draw>) -> Vec<u8> {
+        let mut encoder = BinaryCanvasEncoder::new();
+        let mut encoded = vec![];
+        instructions.iter().for_each(|draw| encoder.encode(draw, &mut encoded));
+        encoded
+    }
+
+    ///
+    /// Checks if a particular string of drawing operations can be both encoded and decoded
+    ///
+    fn check_round_trip(instructions: Vec<Draw>) {
+        let encoded = encode(&instructions);
+        let decoded = decode_binary_drawing(encoded.into_iter()).collect::<Vec<_>>();
+
+        println!("{:?}", instructions);
+        println!("  -> {:?}", decoded);
+
+        assert!(decoded.len() == instructions.len());
+        assert!(decoded == instructions.into_iter().map(|draw| Ok(draw)).collect::<Vec<_>>());
+    }
+
+    fn all_instructions() -> Vec<Draw> {
+        vec![
+            Draw::StartFrame,
+            Draw::ShowFrame,
+            Draw::ResetFrame,
+            Draw::Path(PathOp::NewPath),
+            Draw::Path(PathOp::Move(10.0, 15.0)),
+            Draw::Path(PathOp::Line(20.0, 42.0)),
+            Draw::Path(PathOp::BezierCurve(((1.0, 2.0), (3.0, 4.0)), (5.0, 6.0))),
+            Draw::Path(PathOp::QuadraticCurve((1.0, 2.0), (3.0, 4.0))),
+            Draw::Path(PathOp::Arc((1.0, 2.0), (3.0, 4.0), 0.5, 1.0, 2.0)),
+            Draw::Path(PathOp::ClosePath),
+            Draw::StorePath,
+            Draw::CombinePath(PathArithmetic::Xor),
+            Draw::Fill,
+            Draw::Stroke,
+            Draw::LineWidth(23.0),
+            Draw::LineWidthPixels(43.0),
+            Draw::LineJoin(LineJoin::Bevel),
+            Draw::MiterLimit(10.0),
+            Draw::LineCap(LineCap::Round),
+            Draw::WindingRule(WindingRule::EvenOdd),
+            Draw::NewDashPattern,
+            Draw::DashLength(56.0),
+            Draw::DashOffset(13.0),
+            Draw::StrokeColor(Color::Rgba(0.1, 0.2, 0.3, 0.4)),
+            Draw::FillColor(Color::Hsluv(120.0, 50.0, 60.0, 1.0)),
+            Draw::FillTexture(TextureId(23), (42.0, 43.0), (44.0, 45.0)),
+            Draw::FillGradient(GradientId(24), (42.0, 43.0), (44.0, 45.0)),
+            Draw::FillRadialGradient(GradientId(25), (42.0, 43.0), 44.0, None),
+            Draw::FillRadialGradient(GradientId(26), (42.0, 43.0), 44.0, Some((45.0, 46.0))),
+            Draw::FillConicGradient(GradientId(27), (42.0, 43.0), 1.5),
+            Draw::StrokeTexture(TextureId(28), (42.0, 43.0), (44.0, 45.0)),
+            Draw::StrokeGradient(GradientId(29), (42.0, 43.0), (44.0, 45.0)),
+            Draw::FillTransform(Transform2D::identity()),
+            Draw::BlendMode(BlendMode::Lighten),
+            Draw::IdentityTransform,
+            Draw::CanvasHeight(81.0),
+            Draw::CenterRegion((6.0, 7.0), (8.0, 9.0)),
+            Draw::MultiplyTransform(Transform2D([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]])),
+            Draw::Unclip,
+            Draw::Clip,
+            Draw::Store,
+            Draw::Restore,
+            Draw::FreeStoredBuffer,
+            Draw::PushState,
+            Draw::PopState,
+            Draw::ClearCanvas(Color::Rgba(0.1, 0.2, 0.3, 0.4)),
+            Draw::Namespace(NamespaceId::default()),
+            Draw::Layer(LayerId(21)),
+            Draw::LayerBlend(LayerId(22), BlendMode::Luminosity),
+            Draw::LayerAlpha(LayerId(23), 0.5),
+            Draw::ClearLayer,
+            Draw::ClearAllLayers,
+            Draw::SwapLayers(LayerId(1), LayerId(2)),
+            Draw::Sprite(SpriteId(1000)),
+            Draw::ClearSprite,
+            Draw::SpriteTransform(SpriteTransform::Identity),
+            Draw::SpriteTransform(SpriteTransform::Translate(4.0, 5.0)),
+            Draw::SpriteTransform(SpriteTransform::Scale(6.0, 7.0)),
+            Draw::SpriteTransform(SpriteTransform::Rotate(45.0)),
+            Draw::SpriteTransform(SpriteTransform::Transform2D(Transform2D::scale(3.0, 4.0))),
+            Draw::MoveSpriteFrom(SpriteId(48)),
+            Draw::DrawSprite(SpriteId(1300)),
+            Draw::DrawSpriteWithFilters(SpriteId(10), vec![]),
+            Draw::DrawSpriteWithFilters(SpriteId(10), vec![TextureFilter::GaussianBlur(4.0), TextureFilter::AlphaBlend(0.5)]),
+
+            Draw::Texture(TextureId(42), TextureOp::Create(TextureSize(1024, 768), TextureFormat::Rgba)),
+            Draw::Texture(TextureId(43), TextureOp::Free),
+            Draw::Texture(TextureId(44), TextureOp::SetBytes(TexturePosition(2, 3), TextureSize(4, 5), Arc::new(vec![1,2,3,4,5]))),
+            Draw::Texture(TextureId(44), TextureOp::SetFromSprite(SpriteId(42), SpriteBounds(SpritePosition(20.0, 30.0), SpriteSize(40.0, 50.0)))),
+            Draw::Texture(TextureId(44), TextureOp::CreateDynamicSprite(SpriteId(42), SpriteBounds(SpritePosition(20.0, 30.0), SpriteSize(40.0, 50.0)), CanvasSize(60.0, 70.0))),
+            Draw::Texture(TextureId(45), TextureOp::FillTransparency(0.5)),
+            Draw::Texture(TextureId(46), TextureOp::Copy(TextureId(47))),
+            Draw::Texture(TextureId(47), TextureOp::Filter(TextureFilter::Mask(TextureId(48)))),
+            Draw::Texture(TextureId(47), TextureOp::Filter(TextureFilter::DisplacementMap(TextureId(48), 1.0, 2.0))),
+
+            Draw::Font(FontId(1), FontOp::UseFontDefinition(CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf")))),
+            Draw::Font(FontId(1), FontOp::FontSize(12.0)),
+            Draw::Font(FontId(1), FontOp::UseFallbackFonts(vec![FontId(2), FontId(300)])),
+            Draw::Font(FontId(1), FontOp::FontFeature(*b"tnum", true)),
+            Draw::Font(FontId(1), FontOp::FontVariation(*b"wght", 700.0)),
+            Draw::Font(FontId(1), FontOp::LayoutText("Unicode: \u{00e9}\u{4e2d}".to_string())),
+            Draw::Font(FontId(1), FontOp::DrawGlyphs(vec![GlyphPosition { id: GlyphId(20), location: (2.0, 3.0), em_size: 18.0 }, GlyphPosition { id: GlyphId(21), location: (4.0, 5.0), em_size: 19.0 }])),
+            Draw::BeginLineLayout(1.0, 2.0, TextAlignment::Center),
+            Draw::BeginParagraphLayout(1.0, 2.0, ParagraphStyle::new(100.0).with_alignment(ParagraphAlignment::Justify).with_overflow(TextOverflow::Clip(40.0))),
+            Draw::DrawLaidOutText,
+            Draw::DrawText(FontId(2), "Hello".to_string(), 100.0, 200.0),
+
+            Draw::Gradient(GradientId(42), GradientOp::Create(Color::Rgba(0.1, 0.2, 0.3, 0.4))),
+            Draw::Gradient(GradientId(44), GradientOp::AddStop(0.5, Color::Rgba(0.1, 0.2, 0.3, 0.4))),
+            Draw::Gradient(GradientId(45), GradientOp::Spread(GradientSpread::Repeat)),
+        ]
+    }
+
+    #[test]
+    fn decode_all_iter() {
+        check_round_trip(all_instructions());
+    }
+
+    #[test]
+    fn decode_long_glyph_list_incrementally() {
+        let glyphs          = (0..1000).map(|idx| GlyphPosition { id: GlyphId(idx), location: (idx as f32, 2.0), em_size: 12.0 }).collect::<Vec<_>>();
+        let instructions    = vec![Draw::Font(FontId(1), FontOp::DrawGlyphs(glyphs))];
+        let encoded         = encode(&instructions);
+
+        let mut decoder     = BinaryCanvasDecoder::new();
+        let mut decoded     = vec![];
+
+        for byte in encoded[0..encoded.len()-1].iter() {
+            decoder.decode(&[*byte], &mut decoded).unwrap();
+        }
+
+        // Every glyph apart from the last one has been decoded already, so they aren't decoded again when the last byte arrives
+        assert!(decoded.is_empty());
+        assert!(decoder.partial_glyphs.as_ref().map(|(_, glyphs)| glyphs.len()) == Some(999));
+
+        decoder.decode(&encoded[encoded.len()-1..], &mut decoded).unwrap();
+
+        assert!(decoded == instructions);
+        assert!(decoder.partial_glyphs.is_none());
+    }
+
+    #[test]
+    fn decode_all_stream_split_anywhere() {
+        let instructions    = all_instructions();
+        let encoded         = encode(&instructions);
+
+        for split_pos in 0..encoded.len() {
+            let chunks  = vec![Ok::<_, ()>(encoded[0..split_pos].to_vec()), Ok(encoded[split_pos..].to_vec())];
+            let decoded = executor::block_on(async { decode_binary_drawing_stream(stream::iter(chunks)).collect::<Vec<_>>().await });
+
+            assert!(decoded == instructions.iter().cloned().map(|draw| Ok(draw)).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn decode_coordinates_exactly() {
+        let coords = vec![0.0, -0.0, 0.1, -0.1, 1e-30, 1e30, 1000000.5, 16777217.0, f32::MAX, f32::MIN_POSITIVE, f32::INFINITY, f32::NEG_INFINITY, f32::NAN, 1.0/3.0];
+
+        let instructions = coords.iter()
+            .flat_map(|x| coords.iter().map(move |y| Draw::Path(PathOp::Line(*x, *y))))
+            .collect::<Vec<_>>();
+        let decoded = decode_binary_drawing(encode(&instructions).into_iter()).collect::<Vec<_>>();
+
+        assert!(decoded.len() == instructions.len());
+        for (original, decoded) in instructions.iter().zip(decoded.iter()) {
+            match (original, decoded) {
+                (Draw::Path(PathOp::Line(x1, y1)), Ok(Draw::Path(PathOp::Line(x2, y2)))) => {
+                    assert!(x1.to_bits() == x2.to_bits());
+                    assert!(y1.to_bits() == y2.to_bits());
+                }
+
+                _ => { assert!(false, "{:?} decoded as {:?}", original, decoded); }
+            }
+        }
+    }
+
+    #[test]
+    fn decode_invalid_opcode() {
+        let mut encoded = encode(&vec![Draw::Fill, Draw::Stroke]);
+        encoded.push(255);
+        encoded.push(OP_FILL);
+
+        let decoded = decode_binary_drawing(encoded.into_iter()).collect::<Vec<_>>();
+
+        assert!(decoded == vec![Ok(Draw::Fill), Ok(Draw::Stroke), Err(DecoderError::InvalidByte(255))]);
+    }
+
+    #[test]
+    fn decode_invalid_string() {
+        let encoded = vec![OP_FONT, 1, 2, 2, 0xff, 0xfe];
+        let decoded = decode_binary_drawing(encoded.into_iter()).collect::<Vec<_>>();
+
+        assert!(decoded == vec![Err(DecoderError::InvalidString)]);
+    }
+
+    #[test]
+    fn decode_two_batches_as_one_stream() {
+        let first_batch     = vec![Draw::Path(PathOp::Move(100.0, 100.0)), Draw::Path(PathOp::Line(200.0, 100.0))];
+        let second_batch    = vec![Draw::Path(PathOp::Line(200.0, 200.0)), Draw::Path(PathOp::Line(100.0, 200.0)), Draw::Path(PathOp::ClosePath)];
+
+        // The same encoder is used for both batches, so the points in the second batch are relative to the end of the first
+        let mut encoder         = BinaryCanvasEncoder::new();
+        let mut first_bytes     = vec![];
+        let mut second_bytes    = vec![];
+        first_batch.iter().for_each(|draw| encoder.encode(draw, &mut first_bytes));
+        second_batch.iter().for_each(|draw| encoder.encode(draw, &mut second_bytes));
+
+        let mut decoder = BinaryCanvasDecoder::new();
+        let mut decoded = vec![];
+        decoder.decode(&first_bytes, &mut decoded).unwrap();
+        decoder.decode(&second_bytes, &mut decoded).unwrap();
+
+        assert!(decoded == first_batch.into_iter().chain(second_batch.into_iter()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn smaller_than_text_encoding() {
+        let instructions = all_instructions();
+
+        let mut text = String::new();
+        instructions.encode_canvas(&mut text);
+
+        assert!(encode(&instructions).len() < text.len());
+    }
+}
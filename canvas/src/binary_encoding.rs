@@ -0,0 +1,685 @@
+use crate::draw::*;
+use crate::path::*;
+use crate::font::*;
+use crate::color::*;
+use crate::sprite::*;
+use crate::texture::*;
+use crate::gradient::*;
+use crate::namespace::*;
+use crate::transform2d::*;
+
+//
+// Opcodes used for the binary encoding (each instruction starts with one of these)
+//
+
+pub (crate) const OP_START_FRAME: u8               = 0;
+pub (crate) const OP_SHOW_FRAME: u8                = 1;
+pub (crate) const OP_RESET_FRAME: u8               = 2;
+pub (crate) const OP_NEW_PATH: u8                  = 3;
+pub (crate) const OP_MOVE: u8                      = 4;
+pub (crate) const OP_LINE: u8                      = 5;
+pub (crate) const OP_BEZIER_CURVE: u8              = 6;
+pub (crate) const OP_QUADRATIC_CURVE: u8           = 7;
+pub (crate) const OP_ARC: u8                       = 8;
+pub (crate) const OP_CLOSE_PATH: u8                = 9;
+pub (crate) const OP_STORE_PATH: u8                = 10;
+pub (crate) const OP_COMBINE_PATH: u8              = 11;
+pub (crate) const OP_FILL: u8                      = 12;
+pub (crate) const OP_STROKE: u8                    = 13;
+pub (crate) const OP_LINE_WIDTH: u8                = 14;
+pub (crate) const OP_LINE_WIDTH_PIXELS: u8         = 15;
+pub (crate) const OP_LINE_JOIN: u8                 = 16;
+pub (crate) const OP_MITER_LIMIT: u8               = 17;
+pub (crate) const OP_LINE_CAP: u8                  = 18;
+pub (crate) const OP_NEW_DASH_PATTERN: u8          = 19;
+pub (crate) const OP_DASH_LENGTH: u8               = 20;
+pub (crate) const OP_DASH_OFFSET: u8               = 21;
+pub (crate) const OP_FILL_COLOR: u8                = 22;
+pub (crate) const OP_FILL_TEXTURE: u8              = 23;
+pub (crate) const OP_FILL_GRADIENT: u8             = 24;
+pub (crate) const OP_FILL_RADIAL_GRADIENT: u8      = 25;
+pub (crate) const OP_FILL_CONIC_GRADIENT: u8       = 26;
+pub (crate) const OP_FILL_TRANSFORM: u8            = 27;
+pub (crate) const OP_STROKE_COLOR: u8              = 28;
+pub (crate) const OP_STROKE_TEXTURE: u8            = 29;
+pub (crate) const OP_STROKE_GRADIENT: u8           = 30;
+pub (crate) const OP_WINDING_RULE: u8              = 31;
+pub (crate) const OP_BLEND_MODE: u8                = 32;
+pub (crate) const OP_IDENTITY_TRANSFORM: u8        = 33;
+pub (crate) const OP_CANVAS_HEIGHT: u8             = 34;
+pub (crate) const OP_CENTER_REGION: u8             = 35;
+pub (crate) const OP_MULTIPLY_TRANSFORM: u8        = 36;
+pub (crate) const OP_UNCLIP: u8                    = 37;
+pub (crate) const OP_CLIP: u8                      = 38;
+pub (crate) const OP_STORE: u8                     = 39;
+pub (crate) const OP_RESTORE: u8                   = 40;
+pub (crate) const OP_FREE_STORED_BUFFER: u8        = 41;
+pub (crate) const OP_PUSH_STATE: u8                = 42;
+pub (crate) const OP_POP_STATE: u8                 = 43;
+pub (crate) const OP_CLEAR_CANVAS: u8              = 44;
+pub (crate) const OP_LAYER: u8                     = 45;
+pub (crate) const OP_LAYER_BLEND: u8               = 46;
+pub (crate) const OP_LAYER_ALPHA: u8               = 47;
+pub (crate) const OP_CLEAR_LAYER: u8               = 48;
+pub (crate) const OP_CLEAR_ALL_LAYERS: u8          = 49;
+pub (crate) const OP_SWAP_LAYERS: u8               = 50;
+pub (crate) const OP_SPRITE: u8                    = 51;
+pub (crate) const OP_MOVE_SPRITE_FROM: u8          = 52;
+pub (crate) const OP_CLEAR_SPRITE: u8              = 53;
+pub (crate) const OP_SPRITE_TRANSFORM: u8          = 54;
+pub (crate) const OP_DRAW_SPRITE: u8               = 55;
+pub (crate) const OP_DRAW_SPRITE_WITH_FILTERS: u8  = 56;
+pub (crate) const OP_TEXTURE: u8                   = 57;
+pub (crate) const OP_FONT: u8                      = 58;
+pub (crate) const OP_BEGIN_LINE_LAYOUT: u8         = 59;
+pub (crate) const OP_DRAW_LAID_OUT_TEXT: u8        = 60;
+pub (crate) const OP_DRAW_TEXT: u8                 = 61;
+pub (crate) const OP_GRADIENT: u8                  = 62;
+pub (crate) const OP_NAMESPACE: u8                 = 63;
+pub (crate) const OP_BEGIN_PARAGRAPH_LAYOUT: u8    = 64;
+
+/// The highest opcode used by this version of the encoder
+pub (crate) const OP_LAST: u8                      = OP_BEGIN_PARAGRAPH_LAYOUT;
+
+/// Number of steps per unit used for encoding compact numbers (numbers that are a multiple of 1/256th of a unit are encoded as integers)
+pub (crate) const COMPACT_F32_STEPS: f32 = 256.0;
+
+/// The largest number of steps that can be stored exactly in an f32
+pub (crate) const COMPACT_F32_MAX_STEPS: f32 = 16_777_216.0;
+
+///
+/// Encodes drawing instructions in a compact binary format
+///
+/// Path coordinates are encoded relative to the previous coordinate, so this encoder has to see every instruction in a
+/// drawing in order, and the result has to be decoded with a `BinaryCanvasDecoder` that has seen every preceding instruction.
+/// Numbers are stored as variable-length integers where this is exact, and as raw `f32` values otherwise, so the binary
+/// encoding always round-trips exactly.
+///
+#[derive(Clone, Debug)]
+pub struct BinaryCanvasEncoder {
+    /// The last path coordinate that was encoded
+    last_point: (f32, f32),
+}
+
+///
+/// Writes a u64 as a variable-length integer (7 bits per byte, with the top bit set if there are more bytes to follow)
+///
+#[inline]
+pub (crate) fn write_varint(val: u64, append_to: &mut Vec<u8>) {
+    let mut val = val;
+
+    loop {
+        let seven_bits  = (val & 0x7f) as u8;
+        val             = val >> 7;
+
+        if val != 0 {
+            append_to.push(seven_bits | 0x80);
+        } else {
+            append_to.push(seven_bits);
+            break;
+        }
+    }
+}
+
+///
+/// Writes an f32 value relative to a base value
+///
+/// Values that differ from the base by a multiple of 1/256 are written as a varint number of steps (with the bottom bit clear),
+/// and other values are written as a varint `1` followed by the bits of the f32 value.
+///
+pub (crate) fn write_compact_f32(val: f32, base: f32, append_to: &mut Vec<u8>) {
+    let steps = (val - base) * COMPACT_F32_STEPS;
+
+    if steps.is_finite() && steps.abs() <= COMPACT_F32_MAX_STEPS && steps == steps.trunc() {
+        let steps = steps as i64;
+
+        // Only use the compact form if it decodes to exactly the same value
+        if (base + (steps as f32) / COMPACT_F32_STEPS).to_bits() == val.to_bits() {
+            let zigzag = ((steps << 1) ^ (steps >> 63)) as u64;
+            write_varint(zigzag << 1, append_to);
+            return;
+        }
+    }
+
+    write_varint(1, append_to);
+    append_to.extend(val.to_bits().to_le_bytes().iter());
+}
+
+///
+/// Trait implemented by the values that make up the binary encoding of a drawing instruction
+///
+/// This is separate from `CanvasEncoding` so that it doesn't change how method calls on references are resolved by the text encoder
+///
+pub (crate) trait BinaryEncoding {
+    ///
+    /// Appends the binary encoding of this value to a byte buffer
+    ///
+    fn encode_binary(&self, append_to: &mut Vec<u8>);
+}
+
+impl BinaryEncoding for u8 {
+    #[inline]
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        append_to.push(*self);
+    }
+}
+
+impl BinaryEncoding for u32 {
+    #[inline]
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        write_varint(*self as u64, append_to);
+    }
+}
+
+impl BinaryEncoding for u64 {
+    #[inline]
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        write_varint(*self, append_to);
+    }
+}
+
+impl BinaryEncoding for f32 {
+    #[inline]
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        write_compact_f32(*self, 0.0, append_to);
+    }
+}
+
+impl BinaryEncoding for (f32, f32) {
+    #[inline]
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        self.0.encode_binary(append_to);
+        self.1.encode_binary(append_to);
+    }
+}
+
+impl BinaryEncoding for Color {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        // Unlike the text encoding, HSLUV colours are not converted, so they round-trip exactly
+        let (tag, a, b, c, d) = match self {
+            Color::Rgba(r, g, b, a)     => (0, r, g, b, a),
+            Color::Hsluv(h, s, l, a)    => (1, h, s, l, a),
+        };
+
+        append_to.push(tag);
+        a.encode_binary(append_to);
+        b.encode_binary(append_to);
+        c.encode_binary(append_to);
+        d.encode_binary(append_to);
+    }
+}
+
+impl BinaryEncoding for LineJoin {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        append_to.push(match self {
+            LineJoin::Miter => 0,
+            LineJoin::Round => 1,
+            LineJoin::Bevel => 2,
+        });
+    }
+}
+
+impl BinaryEncoding for LineCap {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        append_to.push(match self {
+            LineCap::Butt   => 0,
+            LineCap::Round  => 1,
+            LineCap::Square => 2,
+        });
+    }
+}
+
+impl BinaryEncoding for WindingRule {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        append_to.push(match self {
+            WindingRule::NonZero => 0,
+            WindingRule::EvenOdd => 1,
+        });
+    }
+}
+
+impl BinaryEncoding for PathArithmetic {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        append_to.push(match self {
+            PathArithmetic::Union           => 0,
+            PathArithmetic::Intersection    => 1,
+            PathArithmetic::Difference      => 2,
+            PathArithmetic::Xor             => 3,
+        });
+    }
+}
+
+impl BinaryEncoding for GradientSpread {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        append_to.push(match self {
+            GradientSpread::Pad     => 0,
+            GradientSpread::Repeat  => 1,
+            GradientSpread::Reflect => 2,
+        });
+    }
+}
+
+impl BinaryEncoding for BlendMode {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        use self::BlendMode::*;
+
+        append_to.push(match self {
+            SourceOver      => 0,
+            SourceIn        => 1,
+            SourceOut       => 2,
+            DestinationOver => 3,
+            DestinationIn   => 4,
+            DestinationOut  => 5,
+            SourceAtop      => 6,
+            DestinationAtop => 7,
+
+            Multiply        => 8,
+            Screen          => 9,
+            Darken          => 10,
+            Lighten         => 11,
+            Overlay         => 12,
+            ColorDodge      => 13,
+            ColorBurn       => 14,
+            HardLight       => 15,
+            SoftLight       => 16,
+            Difference      => 17,
+            Exclusion       => 18,
+
+            Hue             => 19,
+            Saturation      => 20,
+            Color           => 21,
+            Luminosity      => 22,
+        });
+    }
+}
+
+impl BinaryEncoding for TextAlignment {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        append_to.push(match self {
+            TextAlignment::Left     => 0,
+            TextAlignment::Right    => 1,
+            TextAlignment::Center   => 2,
+        });
+    }
+}
+
+impl BinaryEncoding for ParagraphStyle {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        self.width.encode_binary(append_to);
+        append_to.push(match self.alignment {
+            ParagraphAlignment::Left    => 0,
+            ParagraphAlignment::Right   => 1,
+            ParagraphAlignment::Center  => 2,
+            ParagraphAlignment::Justify => 3,
+        });
+        (self.line_height, self.paragraph_spacing).encode_binary(append_to);
+
+        match self.overflow {
+            TextOverflow::Visible               => { append_to.push(0); }
+            TextOverflow::Clip(max_height)      => { append_to.push(1); max_height.encode_binary(append_to); }
+            TextOverflow::Ellipsis(max_height)  => { append_to.push(2); max_height.encode_binary(append_to); }
+        }
+    }
+}
+
+impl BinaryEncoding for Transform2D {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        let Transform2D(matrix) = self;
+
+        for row in matrix.iter() {
+            for val in row.iter() {
+                val.encode_binary(append_to);
+            }
+        }
+    }
+}
+
+impl BinaryEncoding for LayerId {
+    #[inline]
+    fn encode_binary(&self, append_to: &mut Vec<u8>) { write_varint(self.0, append_to); }
+}
+
+impl BinaryEncoding for SpriteId {
+    #[inline]
+    fn encode_binary(&self, append_to: &mut Vec<u8>) { write_varint(self.0, append_to); }
+}
+
+impl BinaryEncoding for TextureId {
+    #[inline]
+    fn encode_binary(&self, append_to: &mut Vec<u8>) { write_varint(self.0, append_to); }
+}
+
+impl BinaryEncoding for FontId {
+    #[inline]
+    fn encode_binary(&self, append_to: &mut Vec<u8>) { write_varint(self.0, append_to); }
+}
+
+impl BinaryEncoding for GradientId {
+    #[inline]
+    fn encode_binary(&self, append_to: &mut Vec<u8>) { write_varint(self.0, append_to); }
+}
+
+impl BinaryEncoding for NamespaceId {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        // Namespace IDs are random, so they're written as raw bytes
+        let (global_a, global_b) = self.global_id().as_u64_pair();
+
+        append_to.extend(global_a.to_le_bytes().iter());
+        append_to.extend(global_b.to_le_bytes().iter());
+    }
+}
+
+impl BinaryEncoding for Option<(f32, f32)> {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        match self {
+            None            => { append_to.push(0); }
+            Some(point)     => { append_to.push(1); point.encode_binary(append_to); }
+        }
+    }
+}
+
+impl BinaryEncoding for SpriteTransform {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        use self::SpriteTransform::*;
+
+        match self {
+            Identity                => { append_to.push(0); }
+            Translate(x, y)         => { append_to.push(1); (*x, *y).encode_binary(append_to); }
+            Scale(x, y)             => { append_to.push(2); (*x, *y).encode_binary(append_to); }
+            Rotate(degrees)         => { append_to.push(3); degrees.encode_binary(append_to); }
+            Transform2D(transform)  => { append_to.push(4); transform.encode_binary(append_to); }
+        }
+    }
+}
+
+impl BinaryEncoding for [u8] {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        write_varint(self.len() as u64, append_to);
+        append_to.extend_from_slice(self);
+    }
+}
+
+impl BinaryEncoding for str {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        self.as_bytes().encode_binary(append_to);
+    }
+}
+
+impl BinaryEncoding for SpriteBounds {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        let SpriteBounds(SpritePosition(x, y), SpriteSize(w, h)) = self;
+
+        (*x, *y).encode_binary(append_to);
+        (*w, *h).encode_binary(append_to);
+    }
+}
+
+impl BinaryEncoding for TextureFilter {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        use self::TextureFilter::*;
+
+        match self {
+            GaussianBlur(radius)                => { append_to.push(0); radius.encode_binary(append_to); }
+            AlphaBlend(alpha)                   => { append_to.push(1); alpha.encode_binary(append_to); }
+            Mask(texture)                       => { append_to.push(2); texture.encode_binary(append_to); }
+            DisplacementMap(texture, xr, yr)    => { append_to.push(3); texture.encode_binary(append_to); (*xr, *yr).encode_binary(append_to); }
+        }
+    }
+}
+
+impl BinaryEncoding for Vec<TextureFilter> {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        write_varint(self.len() as u64, append_to);
+        self.iter().for_each(|filter| filter.encode_binary(append_to));
+    }
+}
+
+impl BinaryEncoding for TextureOp {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        use self::TextureOp::*;
+
+        match self {
+            Create(TextureSize(width, height), TextureFormat::Rgba)             => { append_to.push(0); width.encode_binary(append_to); height.encode_binary(append_to); append_to.push(0); }
+            Free                                                                => { append_to.push(1); }
+            SetBytes(TexturePosition(x, y), TextureSize(width, height), bytes) => {
+                append_to.push(2);
+                x.encode_binary(append_to);
+                y.encode_binary(append_to);
+                width.encode_binary(append_to);
+                height.encode_binary(append_to);
+                bytes.as_slice().encode_binary(append_to);
+            }
+            SetFromSprite(sprite_id, bounds)                                    => { append_to.push(3); sprite_id.encode_binary(append_to); bounds.encode_binary(append_to); }
+            CreateDynamicSprite(sprite_id, bounds, CanvasSize(w, h))            => { append_to.push(4); sprite_id.encode_binary(append_to); bounds.encode_binary(append_to); (*w, *h).encode_binary(append_to); }
+            FillTransparency(alpha)                                             => { append_to.push(5); alpha.encode_binary(append_to); }
+            Copy(target_texture)                                                => { append_to.push(6); target_texture.encode_binary(append_to); }
+            Filter(filter)                                                      => { append_to.push(7); filter.encode_binary(append_to); }
+        }
+    }
+}
+
+impl BinaryEncoding for GradientOp {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        use self::GradientOp::*;
+
+        match self {
+            Create(color)       => { append_to.push(0); color.encode_binary(append_to); }
+            AddStop(pos, color) => { append_to.push(1); pos.encode_binary(append_to); color.encode_binary(append_to); }
+            Spread(spread)      => { append_to.push(2); spread.encode_binary(append_to); }
+        }
+    }
+}
+
+impl BinaryEncoding for GlyphPosition {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        self.id.0.encode_binary(append_to);
+        self.location.encode_binary(append_to);
+        self.em_size.encode_binary(append_to);
+    }
+}
+
+impl BinaryEncoding for FontOp {
+    fn encode_binary(&self, append_to: &mut Vec<u8>) {
+        use self::FontOp::*;
+
+        match self {
+            UseFontDefinition(font)     => { append_to.push(0); font.font_data().encode_binary(append_to); }
+            FontSize(font_size)         => { append_to.push(1); font_size.encode_binary(append_to); }
+            LayoutText(text)            => { append_to.push(2); text.as_str().encode_binary(append_to); }
+            DrawGlyphs(glyphs)          => {
+                append_to.push(3);
+                write_varint(glyphs.len() as u64, append_to);
+                glyphs.iter().for_each(|glyph| glyph.encode_binary(append_to));
+            }
+            UseFallbackFonts(font_ids)  => {
+                append_to.push(4);
+                write_varint(font_ids.len() as u64, append_to);
+                font_ids.iter().for_each(|font_id| font_id.encode_binary(append_to));
+            }
+            FontFeature(tag, enabled)   => { append_to.push(5); append_to.extend_from_slice(tag); append_to.push(if *enabled { 1 } else { 0 }); }
+            FontVariation(tag, value)   => { append_to.push(6); append_to.extend_from_slice(tag); value.encode_binary(append_to); }
+        }
+    }
+}
+
+impl BinaryCanvasEncoder {
+    ///
+    /// Creates a new binary encoder
+    ///
+    pub fn new() -> BinaryCanvasEncoder {
+        BinaryCanvasEncoder {
+            last_point: (0.0, 0.0)
+        }
+    }
+
+    ///
+    /// Writes a path coordinate relative to the last coordinate
+    ///
+    #[inline]
+    fn encode_point(&mut self, (x, y): (f32, f32), append_to: &mut Vec<u8>) {
+        write_compact_f32(x, self.last_point.0, append_to);
+        write_compact_f32(y, self.last_point.1, append_to);
+
+        self.last_point = (x, y);
+    }
+
+    ///
+    /// Encodes a drawing instruction, appending it to a buffer
+    ///
+    pub fn encode(&mut self, draw: &Draw, append_to: &mut Vec<u8>) {
+        use self::Draw::*;
+        use self::PathOp::*;
+
+        match draw {
+            StartFrame                                  => { append_to.push(OP_START_FRAME); }
+            ShowFrame                                   => { append_to.push(OP_SHOW_FRAME); }
+            ResetFrame                                  => { append_to.push(OP_RESET_FRAME); }
+
+            Path(NewPath)                               => { append_to.push(OP_NEW_PATH); }
+            Path(Move(x, y))                            => { append_to.push(OP_MOVE); self.encode_point((*x, *y), append_to); }
+            Path(Line(x, y))                            => { append_to.push(OP_LINE); self.encode_point((*x, *y), append_to); }
+            Path(BezierCurve((cp1, cp2), p))            => { append_to.push(OP_BEZIER_CURVE); self.encode_point(*cp1, append_to); self.encode_point(*cp2, append_to); self.encode_point(*p, append_to); }
+            Path(QuadraticCurve(cp, p))                 => { append_to.push(OP_QUADRATIC_CURVE); self.encode_point(*cp, append_to); self.encode_point(*p, append_to); }
+            Path(Arc(center, radii, rotation, start, sweep)) => {
+                append_to.push(OP_ARC);
+                self.encode_point(*center, append_to);
+                radii.encode_binary(append_to);
+                rotation.encode_binary(append_to);
+                start.encode_binary(append_to);
+                sweep.encode_binary(append_to);
+            }
+            Path(ClosePath)                             => { append_to.push(OP_CLOSE_PATH); }
+            StorePath                                   => { append_to.push(OP_STORE_PATH); }
+            CombinePath(operation)                      => { append_to.push(OP_COMBINE_PATH); operation.encode_binary(append_to); }
+
+            Fill                                        => { append_to.push(OP_FILL); }
+            Stroke                                      => { append_to.push(OP_STROKE); }
+            LineWidth(width)                            => { append_to.push(OP_LINE_WIDTH); width.encode_binary(append_to); }
+            LineWidthPixels(width)                      => { append_to.push(OP_LINE_WIDTH_PIXELS); width.encode_binary(append_to); }
+            LineJoin(join)                              => { append_to.push(OP_LINE_JOIN); join.encode_binary(append_to); }
+            MiterLimit(limit)                           => { append_to.push(OP_MITER_LIMIT); limit.encode_binary(append_to); }
+            LineCap(cap)                                => { append_to.push(OP_LINE_CAP); cap.encode_binary(append_to); }
+            NewDashPattern                              => { append_to.push(OP_NEW_DASH_PATTERN); }
+            DashLength(length)                          => { append_to.push(OP_DASH_LENGTH); length.encode_binary(append_to); }
+            DashOffset(offset)                          => { append_to.push(OP_DASH_OFFSET); offset.encode_binary(append_to); }
+
+            FillColor(color)                            => { append_to.push(OP_FILL_COLOR); color.encode_binary(append_to); }
+            FillTexture(texture, p1, p2)                => { append_to.push(OP_FILL_TEXTURE); texture.encode_binary(append_to); p1.encode_binary(append_to); p2.encode_binary(append_to); }
+            FillGradient(gradient, p1, p2)              => { append_to.push(OP_FILL_GRADIENT); gradient.encode_binary(append_to); p1.encode_binary(append_to); p2.encode_binary(append_to); }
+            FillRadialGradient(gradient, pos, r, focus) => { append_to.push(OP_FILL_RADIAL_GRADIENT); gradient.encode_binary(append_to); pos.encode_binary(append_to); r.encode_binary(append_to); focus.encode_binary(append_to); }
+            FillConicGradient(gradient, pos, angle)     => { append_to.push(OP_FILL_CONIC_GRADIENT); gradient.encode_binary(append_to); pos.encode_binary(append_to); angle.encode_binary(append_to); }
+            FillTransform(transform)                    => { append_to.push(OP_FILL_TRANSFORM); transform.encode_binary(append_to); }
+            StrokeColor(color)                          => { append_to.push(OP_STROKE_COLOR); color.encode_binary(append_to); }
+            StrokeTexture(texture, p1, p2)              => { append_to.push(OP_STROKE_TEXTURE); texture.encode_binary(append_to); p1.encode_binary(append_to); p2.encode_binary(append_to); }
+            StrokeGradient(gradient, p1, p2)            => { append_to.push(OP_STROKE_GRADIENT); gradient.encode_binary(append_to); p1.encode_binary(append_to); p2.encode_binary(append_to); }
+            WindingRule(rule)                           => { append_to.push(OP_WINDING_RULE); rule.encode_binary(append_to); }
+            BlendMode(mode)                             => { append_to.push(OP_BLEND_MODE); mode.encode_binary(append_to); }
+
+            IdentityTransform                           => { append_to.push(OP_IDENTITY_TRANSFORM); }
+            CanvasHeight(height)                        => { append_to.push(OP_CANVAS_HEIGHT); height.encode_binary(append_to); }
+            CenterRegion(min, max)                      => { append_to.push(OP_CENTER_REGION); min.encode_binary(append_to); max.encode_binary(append_to); }
+            MultiplyTransform(transform)                => { append_to.push(OP_MULTIPLY_TRANSFORM); transform.encode_binary(append_to); }
+            Unclip                                      => { append_to.push(OP_UNCLIP); }
+            Clip                                        => { append_to.push(OP_CLIP); }
+            Store                                       => { append_to.push(OP_STORE); }
+            Restore                                     => { append_to.push(OP_RESTORE); }
+            FreeStoredBuffer                            => { append_to.push(OP_FREE_STORED_BUFFER); }
+            PushState                                   => { append_to.push(OP_PUSH_STATE); }
+            PopState                                    => { append_to.push(OP_POP_STATE); }
+
+            ClearCanvas(color)                          => { append_to.push(OP_CLEAR_CANVAS); color.encode_binary(append_to); }
+            Layer(layer_id)                             => { append_to.push(OP_LAYER); layer_id.encode_binary(append_to); }
+            LayerBlend(layer_id, blend_mode)            => { append_to.push(OP_LAYER_BLEND); layer_id.encode_binary(append_to); blend_mode.encode_binary(append_to); }
+            LayerAlpha(layer_id, alpha)                 => { append_to.push(OP_LAYER_ALPHA); layer_id.encode_binary(append_to); alpha.encode_binary(append_to); }
+            ClearLayer                                  => { append_to.push(OP_CLEAR_LAYER); }
+            ClearAllLayers                              => { append_to.push(OP_CLEAR_ALL_LAYERS); }
+            SwapLayers(layer1, layer2)                  => { append_to.push(OP_SWAP_LAYERS); layer1.encode_binary(append_to); layer2.encode_binary(append_to); }
+
+            Sprite(sprite_id)                           => { append_to.push(OP_SPRITE); sprite_id.encode_binary(append_to); }
+            MoveSpriteFrom(sprite_id)                   => { append_to.push(OP_MOVE_SPRITE_FROM); sprite_id.encode_binary(append_to); }
+            ClearSprite                                 => { append_to.push(OP_CLEAR_SPRITE); }
+            SpriteTransform(sprite_transform)           => { append_to.push(OP_SPRITE_TRANSFORM); sprite_transform.encode_binary(append_to); }
+            DrawSprite(sprite_id)                       => { append_to.push(OP_DRAW_SPRITE); sprite_id.encode_binary(append_to); }
+            DrawSpriteWithFilters(sprite_id, filters)   => { append_to.push(OP_DRAW_SPRITE_WITH_FILTERS); sprite_id.encode_binary(append_to); filters.encode_binary(append_to); }
+
+            Texture(texture_id, op)                     => { append_to.push(OP_TEXTURE); texture_id.encode_binary(append_to); op.encode_binary(append_to); }
+            Font(font_id, op)                           => { append_to.push(OP_FONT); font_id.encode_binary(append_to); op.encode_binary(append_to); }
+            BeginLineLayout(x, y, align)                => { append_to.push(OP_BEGIN_LINE_LAYOUT); (*x, *y).encode_binary(append_to); align.encode_binary(append_to); }
+            BeginParagraphLayout(x, y, style)           => { append_to.push(OP_BEGIN_PARAGRAPH_LAYOUT); (*x, *y).encode_binary(append_to); style.encode_binary(append_to); }
+            DrawLaidOutText                             => { append_to.push(OP_DRAW_LAID_OUT_TEXT); }
+            DrawText(font_id, string, x, y)             => { append_to.push(OP_DRAW_TEXT); font_id.encode_binary(append_to); string.as_str().encode_binary(append_to); (*x, *y).encode_binary(append_to); }
+            Gradient(gradient_id, op)                   => { append_to.push(OP_GRADIENT); gradient_id.encode_binary(append_to); op.encode_binary(append_to); }
+            Namespace(namespace_id)                     => { append_to.push(OP_NAMESPACE); namespace_id.encode_binary(append_to); }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoding::*;
+
+    fn encode_all(drawing: &[Draw]) -> Vec<u8> {
+        let mut encoder = BinaryCanvasEncoder::new();
+        let mut result  = vec![];
+        drawing.iter().for_each(|draw| encoder.encode(draw, &mut result));
+        result
+    }
+
+    fn encode_draw(item: Draw) -> Vec<u8> {
+        encode_all(&[item])
+    }
+
+    #[test]
+    fn encode_varint() {
+        let mut encoded = vec![];
+        write_varint(300, &mut encoded);
+
+        assert!(encoded == vec![0xac, 0x02]);
+    }
+
+    #[test]
+    fn encode_compact_whole_number() {
+        let mut encoded = vec![];
+        write_compact_f32(2.0, 0.0, &mut encoded);
+
+        // 2.0 is 512 steps, which is 1024 after zigzag encoding and 2048 after adding the tag bit
+        assert!(encoded == vec![0x80, 0x10]);
+    }
+
+    #[test]
+    fn encode_compact_raw_number() {
+        let mut encoded = vec![];
+        write_compact_f32(0.1, 0.0, &mut encoded);
+
+        assert!(encoded.len() == 5);
+        assert!(encoded[0] == 1);
+        assert!(encoded[1..5] == 0.1f32.to_bits().to_le_bytes());
+    }
+
+    #[test]
+    fn encode_fill() { assert!(encode_draw(Draw::Fill) == vec![OP_FILL]) }
+    #[test]
+    fn encode_layer() { assert!(encode_draw(Draw::Layer(LayerId(2))) == vec![OP_LAYER, 2]) }
+    #[test]
+    fn encode_move() { assert!(encode_draw(Draw::Path(PathOp::Move(0.5, -0.5))) == vec![OP_MOVE, 0x80, 0x04, 0xfe, 0x03]) }
+
+    #[test]
+    fn line_is_relative_to_move() {
+        let encoded = encode_all(&[Draw::Path(PathOp::Move(1000.0, 1000.0)), Draw::Path(PathOp::Line(1001.0, 1000.0))]);
+
+        // Line is encoded as a difference of 1.0 in x and 0.0 in y
+        assert!(encoded[encoded.len()-4..] == [OP_LINE, 0x80, 0x08, 0x00]);
+    }
+
+    #[test]
+    fn binary_is_smaller_than_text() {
+        let drawing = (0..100).map(|idx| Draw::Path(PathOp::Line(idx as f32, (idx*2) as f32))).collect::<Vec<_>>();
+
+        let binary = encode_all(&drawing);
+        let mut text = String::new();
+        drawing.encode_canvas(&mut text);
+
+        assert!(binary.len() * 2 < text.len());
+    }
+}
@@ -0,0 +1,807 @@
+use crate::draw::*;
+use crate::texture::*;
+use crate::encoding::*;
+use crate::decoding::*;
+use crate::binary_encoding::*;
+use crate::binary_decoding::*;
+
+use futures::*;
+use futures::stream;
+use futures::task::{Poll};
+
+use std::ops::{BitOr};
+use std::convert::{TryFrom};
+use std::collections::{VecDeque};
+use std::result::Result;
+
+/// The bytes that every canvas file starts with
+pub const CANVAS_FILE_MAGIC: [u8; 8] = *b"FLOCANVS";
+
+/// The major version of the canvas file format written by this version of flo_canvas (files with a different major version cannot be read)
+pub const CANVAS_FILE_MAJOR_VERSION: u16 = 1;
+
+/// The minor version of the canvas file format written by this version of flo_canvas (newer minor versions may add new instructions)
+///
+/// * 1 - adds paragraph layout (`BeginParagraphLayout` and the `PARAGRAPHS` feature)
+/// * 2 - adds fallback fonts (`FontOp::UseFallbackFonts` and the `FONT_FALLBACK` feature)
+/// * 3 - adds OpenType features and variation axes (`FontOp::FontFeature`, `FontOp::FontVariation` and the `FONT_FEATURES` feature)
+///
+pub const CANVAS_FILE_MINOR_VERSION: u16 = 3;
+
+/// The length of the header at the start of a canvas file
+const HEADER_LENGTH: usize = 21;
+
+///
+/// The encoding used for the drawing instructions in a canvas file
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CanvasFileEncoding {
+    /// Instructions are encoded using `CanvasEncoding<String>` (and decoded with `CanvasDecoder`)
+    Text,
+
+    /// Instructions are encoded using `BinaryCanvasEncoder` (and decoded with `BinaryCanvasDecoder`)
+    Binary,
+}
+
+///
+/// Flags indicating the optional groups of instructions that a canvas file may contain
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct CanvasFileFeatures(pub u64);
+
+impl CanvasFileFeatures {
+    /// A file that only uses the basic drawing instructions
+    pub const NONE: CanvasFileFeatures              = CanvasFileFeatures(0);
+
+    /// Font and text instructions (`Font`, `DrawText`, `BeginLineLayout`, `DrawLaidOutText`)
+    pub const FONTS: CanvasFileFeatures             = CanvasFileFeatures(1<<0);
+
+    /// Texture instructions (`Texture`, `FillTexture`, `StrokeTexture`)
+    pub const TEXTURES: CanvasFileFeatures          = CanvasFileFeatures(1<<1);
+
+    /// Gradient instructions (`Gradient`, `FillGradient`, `FillRadialGradient`, `FillConicGradient`, `StrokeGradient`)
+    pub const GRADIENTS: CanvasFileFeatures         = CanvasFileFeatures(1<<2);
+
+    /// Sprite instructions (`Sprite`, `MoveSpriteFrom`, `ClearSprite`, `SpriteTransform`, `DrawSprite`)
+    pub const SPRITES: CanvasFileFeatures           = CanvasFileFeatures(1<<3);
+
+    /// Path arithmetic instructions (`StorePath`, `CombinePath`)
+    pub const PATH_ARITHMETIC: CanvasFileFeatures   = CanvasFileFeatures(1<<4);
+
+    /// Texture filters (`DrawSpriteWithFilters` and `TextureOp::Filter`)
+    pub const TEXTURE_FILTERS: CanvasFileFeatures   = CanvasFileFeatures(1<<5);
+
+    /// Namespace instructions (`Namespace`)
+    pub const NAMESPACES: CanvasFileFeatures        = CanvasFileFeatures(1<<6);
+
+    /// Paragraph layout instructions (`BeginParagraphLayout`)
+    pub const PARAGRAPHS: CanvasFileFeatures        = CanvasFileFeatures(1<<7);
+
+    /// Font fallback instructions (`FontOp::UseFallbackFonts`)
+    pub const FONT_FALLBACK: CanvasFileFeatures     = CanvasFileFeatures(1<<8);
+
+    /// OpenType feature and variation axis instructions (`FontOp::FontFeature`, `FontOp::FontVariation`)
+    pub const FONT_FEATURES: CanvasFileFeatures     = CanvasFileFeatures(1<<9);
+
+    /// All of the features that are supported by this version of flo_canvas
+    pub const ALL: CanvasFileFeatures               = CanvasFileFeatures((1<<10) - 1);
+
+    ///
+    /// True if all of the features in `other` are also in this set of features
+    ///
+    #[inline]
+    pub fn contains(&self, other: CanvasFileFeatures) -> bool {
+        (self.0 & other.0) == other.0
+    }
+
+    ///
+    /// Returns the features in this set that are not supported by this version of flo_canvas
+    ///
+    #[inline]
+    pub fn unsupported(&self) -> CanvasFileFeatures {
+        CanvasFileFeatures(self.0 & !Self::ALL.0)
+    }
+
+    ///
+    /// Returns the features that are needed to store a drawing instruction
+    ///
+    pub fn required_by(draw: &Draw) -> CanvasFileFeatures {
+        use self::Draw::*;
+
+        match draw {
+            Font(_, FontOp::UseFallbackFonts(_))                                                    => Self::FONTS | Self::FONT_FALLBACK,
+            Font(_, FontOp::FontFeature(_, _)) | Font(_, FontOp::FontVariation(_, _))               => Self::FONTS | Self::FONT_FEATURES,
+            Font(_, _) | DrawText(_, _, _, _) | BeginLineLayout(_, _, _) | DrawLaidOutText          => Self::FONTS,
+            BeginParagraphLayout(_, _, _)                                                           => Self::FONTS | Self::PARAGRAPHS,
+            Texture(_, TextureOp::Filter(_))                                                        => Self::TEXTURES | Self::TEXTURE_FILTERS,
+            Texture(_, TextureOp::SetFromSprite(_, _)) | Texture(_, TextureOp::CreateDynamicSprite(_, _, _)) => Self::TEXTURES | Self::SPRITES,
+            Texture(_, _) | FillTexture(_, _, _) | StrokeTexture(_, _, _)                           => Self::TEXTURES,
+            Gradient(_, _) | FillGradient(_, _, _) | FillRadialGradient(_, _, _, _) |
+                FillConicGradient(_, _, _) | StrokeGradient(_, _, _)                                => Self::GRADIENTS,
+            DrawSpriteWithFilters(_, _)                                                             => Self::SPRITES | Self::TEXTURE_FILTERS,
+            Sprite(_) | MoveSpriteFrom(_) | ClearSprite | SpriteTransform(_) | DrawSprite(_)        => Self::SPRITES,
+            StorePath | CombinePath(_)                                                              => Self::PATH_ARITHMETIC,
+            Namespace(_)                                                                            => Self::NAMESPACES,
+            _                                                                                       => Self::NONE,
+        }
+    }
+}
+
+impl BitOr for CanvasFileFeatures {
+    type Output = CanvasFileFeatures;
+
+    #[inline]
+    fn bitor(self, other: CanvasFileFeatures) -> CanvasFileFeatures {
+        CanvasFileFeatures(self.0 | other.0)
+    }
+}
+
+///
+/// The header at the start of a canvas file
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CanvasFileHeader {
+    /// The major version of the file format (files with a different major version cannot be read)
+    pub major_version: u16,
+
+    /// The minor version of the file format
+    pub minor_version: u16,
+
+    /// How the drawing instructions in the file are encoded
+    pub encoding: CanvasFileEncoding,
+
+    /// The optional groups of instructions that the file may contain
+    pub features: CanvasFileFeatures,
+}
+
+impl CanvasFileHeader {
+    ///
+    /// Creates a header for a file written by this version of flo_canvas
+    ///
+    pub fn new(encoding: CanvasFileEncoding, features: CanvasFileFeatures) -> CanvasFileHeader {
+        CanvasFileHeader {
+            major_version:  CANVAS_FILE_MAJOR_VERSION,
+            minor_version:  CANVAS_FILE_MINOR_VERSION,
+            encoding:       encoding,
+            features:       features,
+        }
+    }
+
+    ///
+    /// Appends the bytes for this header to a buffer
+    ///
+    pub fn write(&self, append_to: &mut Vec<u8>) {
+        append_to.extend_from_slice(&CANVAS_FILE_MAGIC);
+        append_to.extend_from_slice(&self.major_version.to_le_bytes());
+        append_to.extend_from_slice(&self.minor_version.to_le_bytes());
+        append_to.push(match self.encoding {
+            CanvasFileEncoding::Text    => 0,
+            CanvasFileEncoding::Binary  => 1,
+        });
+        append_to.extend_from_slice(&self.features.0.to_le_bytes());
+    }
+
+    ///
+    /// Reads a header from the start of some bytes (which must be at least `HEADER_LENGTH` bytes long)
+    ///
+    fn read(bytes: &[u8]) -> Result<CanvasFileHeader, DecoderError> {
+        if bytes[0..8] != CANVAS_FILE_MAGIC {
+            return Err(DecoderError::NotACanvasFile);
+        }
+
+        let major_version   = u16::from_le_bytes([bytes[8], bytes[9]]);
+        let minor_version   = u16::from_le_bytes([bytes[10], bytes[11]]);
+        let encoding        = match bytes[12] {
+            0       => CanvasFileEncoding::Text,
+            1       => CanvasFileEncoding::Binary,
+            other   => { return Err(DecoderError::InvalidByte(other)); }
+        };
+        let features        = CanvasFileFeatures(u64::from_le_bytes([bytes[13], bytes[14], bytes[15], bytes[16], bytes[17], bytes[18], bytes[19], bytes[20]]));
+
+        Ok(CanvasFileHeader {
+            major_version:  major_version,
+            minor_version:  minor_version,
+            encoding:       encoding,
+            features:       features,
+        })
+    }
+}
+
+///
+/// Writes drawing instructions in the canvas file format
+///
+/// A canvas file is a header followed by the encoded drawing instructions. Each instruction is preceded by its length,
+/// so readers can skip instructions that were added in later versions of the format. Instructions are encoded independently
+/// of each other (binary path coordinates are not relative to the previous instruction), so skipping one does not change how
+/// the rest of the file is decoded.
+///
+/// Encoding each instruction independently makes binary files larger than a plain `BinaryCanvasEncoder` stream: every path
+/// coordinate is written relative to the origin rather than to the previous point, which usually takes an extra byte or two
+/// per coordinate for paths made up of many short segments.
+///
+pub struct CanvasFileWriter {
+    /// The header for this file
+    header: CanvasFileHeader,
+
+    /// True if the header has been written
+    header_written: bool,
+}
+
+impl CanvasFileWriter {
+    ///
+    /// Creates a writer that will write instructions using the specified encoding
+    ///
+    /// The header will indicate that the file may use any of the features supported by this version of flo_canvas: use
+    /// `with_features()` to declare a smaller set.
+    ///
+    pub fn new(encoding: CanvasFileEncoding) -> CanvasFileWriter {
+        CanvasFileWriter {
+            header:         CanvasFileHeader::new(encoding, CanvasFileFeatures::ALL),
+            header_written: false,
+        }
+    }
+
+    ///
+    /// Sets the features declared in the header of the file (this has no effect once the header has been written)
+    ///
+    pub fn with_features(mut self, features: CanvasFileFeatures) -> CanvasFileWriter {
+        self.header.features = features;
+        self
+    }
+
+    ///
+    /// Returns the header that this writer will write
+    ///
+    pub fn header(&self) -> CanvasFileHeader {
+        self.header
+    }
+
+    ///
+    /// Writes the file header, if it hasn't been written already
+    ///
+    pub fn write_header(&mut self, append_to: &mut Vec<u8>) {
+        if !self.header_written {
+            self.header.write(append_to);
+            self.header_written = true;
+        }
+    }
+
+    ///
+    /// Writes a drawing instruction to the file (writing the header first if needed)
+    ///
+    pub fn write(&mut self, draw: &Draw, append_to: &mut Vec<u8>) {
+        self.write_header(append_to);
+
+        let instruction = match self.header.encoding {
+            CanvasFileEncoding::Text    => { let mut text = String::new(); draw.encode_canvas(&mut text); text.into_bytes() }
+            CanvasFileEncoding::Binary  => { let mut bytes = vec![]; BinaryCanvasEncoder::new().encode(draw, &mut bytes); bytes }
+        };
+
+        write_varint(instruction.len() as u64, append_to);
+        append_to.extend(instruction);
+    }
+}
+
+///
+/// Encodes a drawing as a canvas file (with the features in the header set to the ones the drawing uses)
+///
+pub fn encode_canvas_file(drawing: &[Draw], encoding: CanvasFileEncoding) -> Vec<u8> {
+    let features    = drawing.iter().fold(CanvasFileFeatures::NONE, |features, draw| features | CanvasFileFeatures::required_by(draw));
+    let mut writer  = CanvasFileWriter::new(encoding).with_features(features);
+    let mut result  = vec![];
+
+    writer.write_header(&mut result);
+    drawing.iter().for_each(|draw| writer.write(draw, &mut result));
+
+    result
+}
+
+///
+/// Converts a stream of drawing instructions to the bytes of a canvas file
+///
+pub fn encode_canvas_file_stream<InStream: Unpin+Stream<Item=Draw>>(draw_stream: InStream, encoding: CanvasFileEncoding) -> impl Unpin+Stream<Item=Vec<u8>> {
+    let mut writer = CanvasFileWriter::new(encoding);
+    let mut header = vec![];
+    writer.write_header(&mut header);
+
+    stream::once(future::ready(header))
+        .chain(draw_stream.map(move |draw| {
+            let mut bytes = vec![];
+            writer.write(&draw, &mut bytes);
+            bytes
+        }))
+}
+
+///
+/// How a `CanvasFileReader` deals with instructions that it doesn't recognise
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum UnknownInstructions {
+    /// Stop reading with a `DecoderError::UnknownOpcode` error (files that declare unsupported features will also be rejected)
+    Reject,
+
+    /// Skip any instruction that can't be decoded and carry on reading the rest of the file
+    Skip,
+}
+
+///
+/// Reads drawing instructions from a file written by a `CanvasFileWriter`
+///
+pub struct CanvasFileReader {
+    /// What to do with instructions that can't be decoded
+    unknown_instructions: UnknownInstructions,
+
+    /// Bytes that have not been decoded yet
+    buffer: Vec<u8>,
+
+    /// The header, once it has been read
+    header: Option<CanvasFileHeader>,
+
+    /// The decoder for text instructions
+    text_decoder: CanvasDecoder,
+
+    /// The number of instructions that were skipped because they were not recognised
+    skipped: usize,
+
+    /// Set to true if the reader has encountered an error
+    failed: bool,
+}
+
+impl CanvasFileReader {
+    ///
+    /// Creates a new reader that will reject unknown instructions
+    ///
+    pub fn new() -> CanvasFileReader {
+        CanvasFileReader {
+            unknown_instructions:   UnknownInstructions::Reject,
+            buffer:                 vec![],
+            header:                 None,
+            text_decoder:           CanvasDecoder::new(),
+            skipped:                0,
+            failed:                 false,
+        }
+    }
+
+    ///
+    /// Sets how this reader deals with instructions that it doesn't recognise
+    ///
+    pub fn with_unknown_instructions(mut self, unknown_instructions: UnknownInstructions) -> CanvasFileReader {
+        self.unknown_instructions = unknown_instructions;
+        self
+    }
+
+    ///
+    /// Returns the header of the file, if it has been read
+    ///
+    pub fn header(&self) -> Option<CanvasFileHeader> {
+        self.header
+    }
+
+    ///
+    /// Returns the number of instructions that were skipped because they were not recognised
+    ///
+    pub fn skipped_instructions(&self) -> usize {
+        self.skipped
+    }
+
+    ///
+    /// Decodes some bytes from the file, adding any drawing instructions that are completed to the output
+    ///
+    /// If an error is returned, the instructions that were decoded before the error are still added to the output, and
+    /// the reader will return `IsInErrorState` for any further bytes.
+    ///
+    pub fn decode(&mut self, bytes: &[u8], output: &mut Vec<Draw>) -> Result<(), DecoderError> {
+        if self.failed {
+            return Err(DecoderError::IsInErrorState);
+        }
+
+        self.buffer.extend_from_slice(bytes);
+
+        let result = self.decode_buffer(output);
+        if result.is_err() {
+            self.failed = true;
+        }
+
+        result
+    }
+
+    ///
+    /// Decodes as much of the buffer as possible
+    ///
+    fn decode_buffer(&mut self, output: &mut Vec<Draw>) -> Result<(), DecoderError> {
+        // Read the header
+        let header = match self.header {
+            Some(header)    => header,
+            None            => {
+                if self.buffer.len() < HEADER_LENGTH {
+                    return Ok(());
+                }
+
+                let header = CanvasFileHeader::read(&self.buffer)?;
+                self.buffer.drain(0..HEADER_LENGTH);
+
+                if header.major_version != CANVAS_FILE_MAJOR_VERSION {
+                    return Err(DecoderError::UnsupportedVersion(header.major_version, header.minor_version));
+                }
+
+                if header.features.unsupported() != CanvasFileFeatures::NONE && self.unknown_instructions == UnknownInstructions::Reject {
+                    return Err(DecoderError::UnsupportedFeatures(header.features.unsupported().0));
+                }
+
+                self.header = Some(header);
+                header
+            }
+        };
+
+        // Read as many instructions as possible
+        let mut pos = 0;
+        let mut result = Ok(());
+
+        while let Some((length, length_len)) = read_frame_length(&self.buffer[pos..]) {
+            let length = match length {
+                Ok(length)  => length,
+                Err(err)    => { result = Err(err); break; }
+            };
+
+            let start   = pos + length_len;
+            let end     = start.saturating_add(length);
+            if end > self.buffer.len() {
+                break;
+            }
+
+            let frame = &self.buffer[start..end];
+            pos = end;
+
+            let decoded = match header.encoding {
+                CanvasFileEncoding::Text    => Self::decode_text_frame(&mut self.text_decoder, frame),
+                CanvasFileEncoding::Binary  => Self::decode_binary_frame(frame),
+            };
+
+            // The frame length is known, so instructions that aren't recognised (eg, because they use a new opcode or a new form of an existing one) can be skipped
+            match decoded {
+                Ok(draw)                                                                                        => { output.push(draw); }
+                Err(err) if Self::is_unknown_instruction(&err) && self.unknown_instructions == UnknownInstructions::Skip => { self.skipped += 1; }
+                Err(err)                                                                                        => { result = Err(err); break; }
+            }
+        }
+
+        self.buffer.drain(0..pos);
+
+        result
+    }
+
+    ///
+    /// True if an error from decoding a frame means that it contains an instruction that this version of flo_canvas doesn't recognise
+    ///
+    /// Other errors (such as invalid strings or exceeded limits) mean that the file is damaged, so they are never skipped.
+    ///
+    fn is_unknown_instruction(err: &DecoderError) -> bool {
+        match err {
+            DecoderError::UnknownOpcode(_)      |
+            DecoderError::InvalidByte(_)        |
+            DecoderError::InvalidCharacter(_)   |
+            DecoderError::UnknownColorType      |
+            DecoderError::InvalidFrame          => true,
+
+            _                                   => false,
+        }
+    }
+
+    ///
+    /// Decodes a frame containing a single text-encoded instruction
+    ///
+    fn decode_text_frame(decoder: &mut CanvasDecoder, frame: &[u8]) -> Result<Draw, DecoderError> {
+        let text        = std::str::from_utf8(frame).map_err(|_| DecoderError::InvalidString)?;
+        let mut result  = None;
+
+        for (idx, chr) in text.chars().enumerate() {
+            if result.is_some() {
+                // Frame continues after the end of the instruction
+                return Err(DecoderError::InvalidFrame);
+            }
+
+            match decoder.decode(chr) {
+                Ok(draw) => { result = draw; }
+
+                Err(err) => {
+                    // The text decoder has to be replaced once it has failed
+                    *decoder = CanvasDecoder::new();
+
+                    // Instructions are identified by their first two characters: a failure here is an unknown instruction
+                    return match err {
+                        DecoderError::InvalidCharacter(_) if idx <= 1   => Err(DecoderError::UnknownOpcode(frame[0])),
+                        other                                           => Err(other),
+                    };
+                }
+            }
+        }
+
+        match result {
+            Some(draw)  => Ok(draw),
+            None        => {
+                *decoder = CanvasDecoder::new();
+                Err(DecoderError::InvalidFrame)
+            }
+        }
+    }
+
+    ///
+    /// Decodes a frame containing a single binary-encoded instruction
+    ///
+    fn decode_binary_frame(frame: &[u8]) -> Result<Draw, DecoderError> {
+        match frame.first() {
+            None                                    => { return Err(DecoderError::InvalidFrame); }
+            Some(opcode) if *opcode > OP_LAST       => { return Err(DecoderError::UnknownOpcode(*opcode)); }
+            Some(_)                                 => { }
+        }
+
+        // Each frame is encoded by a new encoder, so it's also decoded with a new decoder
+        let mut decoder = BinaryCanvasDecoder::new();
+        let mut draws   = vec![];
+        decoder.decode(frame, &mut draws)?;
+
+        if draws.len() != 1 || decoder.has_partial_instruction() {
+            Err(DecoderError::InvalidFrame)
+        } else {
+            Ok(draws.pop().unwrap())
+        }
+    }
+}
+
+///
+/// Reads the length of the next frame, returning the length and the number of bytes it occupies, or None if more bytes are needed
+///
+fn read_frame_length(bytes: &[u8]) -> Option<(Result<usize, DecoderError>, usize)> {
+    let mut result  = 0u64;
+    let mut shift   = 0;
+
+    for (idx, next_byte) in bytes.iter().enumerate() {
+        let seven_bits = (next_byte & 0x7f) as u64;
+
+        if shift > 63 || (shift == 63 && seven_bits > 1) {
+            return Some((Err(DecoderError::BadNumber), idx+1));
+        }
+
+        result  |= seven_bits << shift;
+        shift   += 7;
+
+        if (next_byte & 0x80) == 0 {
+            return Some((usize::try_from(result).map_err(|_| DecoderError::BadNumber), idx+1));
+        }
+    }
+
+    None
+}
+
+///
+/// Decodes a canvas file represented as an iterator of bytes. If there's an error in the file, it will be the last item decoded.
+///
+pub fn decode_canvas_file<In: IntoIterator<Item=u8>>(source: In, unknown_instructions: UnknownInstructions) -> impl Iterator<Item=Result<Draw, DecoderError>> {
+    let mut reader      = CanvasFileReader::new().with_unknown_instructions(unknown_instructions);
+    let mut seen_error  = false;
+
+    source.into_iter()
+        .flat_map(move |byte| {
+            let mut draws   = vec![];
+            let result      = reader.decode(&[byte], &mut draws);
+            let mut draws   = draws.into_iter().map(|draw| Ok(draw)).collect::<Vec<_>>();
+
+            // The reader will just return errors once it hits a failure: only return the initial error
+            if let Err(err) = result {
+                if !seen_error {
+                    seen_error = true;
+                    draws.push(Err(err));
+                }
+            }
+
+            draws
+        })
+}
+
+///
+/// Decodes a canvas file represented as a stream of byte buffers
+///
+pub fn decode_canvas_file_stream<In: Unpin+Stream<Item=Result<Chunk, E>>, Chunk: AsRef<[u8]>, E>(source: In, unknown_instructions: UnknownInstructions) -> impl Unpin+Stream<Item=Result<Draw, StreamDecoderError<E>>> {
+    let mut source      = source;
+    let mut reader      = CanvasFileReader::new().with_unknown_instructions(unknown_instructions);
+    let mut ready       = VecDeque::new();
+    let mut seen_error  = false;
+
+    stream::poll_fn(move |context| {
+        loop {
+            // Return any instructions that have already been decoded
+            if let Some(next) = ready.pop_front() {
+                return Poll::Ready(Some(next));
+            }
+
+            // Only allow one error from the reader (it remains in an error state after this)
+            if seen_error {
+                return Poll::Ready(None);
+            }
+
+            match source.poll_next_unpin(context) {
+                Poll::Ready(None)               => { return Poll::Ready(None); },
+                Poll::Pending                   => { return Poll::Pending; },
+                Poll::Ready(Some(Ok(chunk)))    => {
+                    let mut draws   = vec![];
+                    let result      = reader.decode(chunk.as_ref(), &mut draws);
+
+                    ready.extend(draws.into_iter().map(|draw| Ok(draw)));
+
+                    if let Err(err) = result {
+                        seen_error = true;
+                        ready.push_back(Err(StreamDecoderError::Decoder(err)));
+                    }
+                },
+
+                Poll::Ready(Some(Err(err)))     => { return Poll::Ready(Some(Err(StreamDecoderError::Stream(err)))); }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::path::*;
+    use crate::font::*;
+    use crate::color::*;
+    use crate::sprite::*;
+
+    use futures::executor;
+
+    fn drawing() -> Vec<Draw> {
+        vec![
+            Draw::ClearCanvas(Color::Rgba(1.0, 1.0, 1.0, 1.0)),
+            Draw::Path(PathOp::NewPath),
+            Draw::Path(PathOp::Move(10.0, 15.0)),
+            Draw::Path(PathOp::Line(20.0, 42.0)),
+            Draw::Path(PathOp::ClosePath),
+            Draw::FillColor(Color::Rgba(0.1, 0.2, 0.3, 0.4)),
+            Draw::Fill,
+            Draw::Sprite(SpriteId(2)),
+            Draw::Font(FontId(1), FontOp::FontSize(12.0)),
+        ]
+    }
+
+    fn read_all(file: Vec<u8>, unknown_instructions: UnknownInstructions) -> Vec<Result<Draw, DecoderError>> {
+        decode_canvas_file(file.into_iter(), unknown_instructions).collect()
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let header      = CanvasFileHeader::new(CanvasFileEncoding::Binary, CanvasFileFeatures::SPRITES | CanvasFileFeatures::FONTS);
+        let mut bytes   = vec![];
+        header.write(&mut bytes);
+
+        assert!(bytes.len() == HEADER_LENGTH);
+        assert!(bytes[0..8] == CANVAS_FILE_MAGIC);
+        assert!(CanvasFileHeader::read(&bytes) == Ok(header));
+    }
+
+    #[test]
+    fn features_from_drawing() {
+        let file = encode_canvas_file(&drawing(), CanvasFileEncoding::Binary);
+        let header = CanvasFileHeader::read(&file).unwrap();
+
+        assert!(header.features == CanvasFileFeatures::SPRITES | CanvasFileFeatures::FONTS);
+    }
+
+    #[test]
+    fn font_instructions_require_features() {
+        assert!(CanvasFileFeatures::required_by(&Draw::Font(FontId(1), FontOp::UseFallbackFonts(vec![FontId(2)]))) == CanvasFileFeatures::FONTS | CanvasFileFeatures::FONT_FALLBACK);
+        assert!(CanvasFileFeatures::required_by(&Draw::Font(FontId(1), FontOp::FontFeature(*b"liga", false))) == CanvasFileFeatures::FONTS | CanvasFileFeatures::FONT_FEATURES);
+        assert!(CanvasFileFeatures::required_by(&Draw::Font(FontId(1), FontOp::FontVariation(*b"wght", 700.0))) == CanvasFileFeatures::FONTS | CanvasFileFeatures::FONT_FEATURES);
+    }
+
+    #[test]
+    fn round_trip_text() {
+        let file = encode_canvas_file(&drawing(), CanvasFileEncoding::Text);
+        assert!(read_all(file, UnknownInstructions::Reject) == drawing().into_iter().map(|draw| Ok(draw)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn round_trip_binary() {
+        let file = encode_canvas_file(&drawing(), CanvasFileEncoding::Binary);
+        assert!(read_all(file, UnknownInstructions::Reject) == drawing().into_iter().map(|draw| Ok(draw)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn round_trip_stream() {
+        let file    = executor::block_on(async { encode_canvas_file_stream(stream::iter(drawing()), CanvasFileEncoding::Binary).collect::<Vec<_>>().await });
+        let decoded = executor::block_on(async { decode_canvas_file_stream(stream::iter(file.into_iter().map(|bytes| Ok::<_, ()>(bytes))), UnknownInstructions::Reject).collect::<Vec<_>>().await });
+
+        assert!(decoded == drawing().into_iter().map(|draw| Ok(draw)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn reject_bad_magic() {
+        let mut file = encode_canvas_file(&drawing(), CanvasFileEncoding::Binary);
+        file[0] = b'X';
+
+        assert!(read_all(file, UnknownInstructions::Skip) == vec![Err(DecoderError::NotACanvasFile)]);
+    }
+
+    #[test]
+    fn reject_new_major_version() {
+        let mut file = vec![];
+        CanvasFileHeader { major_version: 2, minor_version: 3, encoding: CanvasFileEncoding::Binary, features: CanvasFileFeatures::NONE }.write(&mut file);
+
+        assert!(read_all(file, UnknownInstructions::Skip) == vec![Err(DecoderError::UnsupportedVersion(2, 3))]);
+    }
+
+    #[test]
+    fn reject_unsupported_features() {
+        let mut file = vec![];
+        CanvasFileHeader::new(CanvasFileEncoding::Binary, CanvasFileFeatures(1<<40)).write(&mut file);
+
+        assert!(read_all(file.clone(), UnknownInstructions::Reject) == vec![Err(DecoderError::UnsupportedFeatures(1<<40))]);
+        assert!(read_all(file, UnknownInstructions::Skip) == vec![]);
+    }
+
+    fn file_with_unknown_instruction(encoding: CanvasFileEncoding, unknown_instruction: &[u8]) -> Vec<u8> {
+        let mut writer  = CanvasFileWriter::new(encoding);
+        let mut file    = vec![];
+
+        writer.write(&Draw::Path(PathOp::Move(10.0, 20.0)), &mut file);
+        write_varint(unknown_instruction.len() as u64, &mut file);
+        file.extend_from_slice(unknown_instruction);
+        writer.write(&Draw::Path(PathOp::Line(30.0, 40.0)), &mut file);
+
+        file
+    }
+
+    #[test]
+    fn unknown_binary_opcode() {
+        let file = file_with_unknown_instruction(CanvasFileEncoding::Binary, &[200, 1, 2, 3]);
+
+        assert!(read_all(file.clone(), UnknownInstructions::Reject) == vec![Ok(Draw::Path(PathOp::Move(10.0, 20.0))), Err(DecoderError::UnknownOpcode(200))]);
+        assert!(read_all(file, UnknownInstructions::Skip) == vec![Ok(Draw::Path(PathOp::Move(10.0, 20.0))), Ok(Draw::Path(PathOp::Line(30.0, 40.0)))]);
+    }
+
+    #[test]
+    fn unknown_text_opcode() {
+        let file = file_with_unknown_instruction(CanvasFileEncoding::Text, b"xAAAAAA");
+
+        assert!(read_all(file.clone(), UnknownInstructions::Reject) == vec![Ok(Draw::Path(PathOp::Move(10.0, 20.0))), Err(DecoderError::UnknownOpcode(b'x'))]);
+        assert!(read_all(file, UnknownInstructions::Skip) == vec![Ok(Draw::Path(PathOp::Move(10.0, 20.0))), Ok(Draw::Path(PathOp::Line(30.0, 40.0)))]);
+    }
+
+    #[test]
+    fn unknown_binary_font_op() {
+        let file = file_with_unknown_instruction(CanvasFileEncoding::Binary, &[OP_FONT, 1, 200]);
+
+        assert!(read_all(file.clone(), UnknownInstructions::Reject) == vec![Ok(Draw::Path(PathOp::Move(10.0, 20.0))), Err(DecoderError::InvalidByte(200))]);
+        assert!(read_all(file, UnknownInstructions::Skip) == vec![Ok(Draw::Path(PathOp::Move(10.0, 20.0))), Ok(Draw::Path(PathOp::Line(30.0, 40.0)))]);
+    }
+
+    #[test]
+    fn bad_frame_length() {
+        let file = file_with_unknown_instruction(CanvasFileEncoding::Binary, &[OP_FILL, OP_STROKE]);
+
+        assert!(read_all(file.clone(), UnknownInstructions::Reject) == vec![Ok(Draw::Path(PathOp::Move(10.0, 20.0))), Err(DecoderError::InvalidFrame)]);
+        assert!(read_all(file, UnknownInstructions::Skip) == vec![Ok(Draw::Path(PathOp::Move(10.0, 20.0))), Ok(Draw::Path(PathOp::Line(30.0, 40.0)))]);
+    }
+
+    #[test]
+    fn invalid_string_is_not_skipped() {
+        let file = file_with_unknown_instruction(CanvasFileEncoding::Text, &[0xff, 0xfe]);
+
+        assert!(read_all(file.clone(), UnknownInstructions::Reject) == vec![Ok(Draw::Path(PathOp::Move(10.0, 20.0))), Err(DecoderError::InvalidString)]);
+        assert!(read_all(file, UnknownInstructions::Skip) == vec![Ok(Draw::Path(PathOp::Move(10.0, 20.0))), Err(DecoderError::InvalidString)]);
+    }
+
+    #[test]
+    fn binary_frames_do_not_depend_on_earlier_frames() {
+        let mut writer      = CanvasFileWriter::new(CanvasFileEncoding::Binary);
+        let mut file        = vec![];
+        let mut move_frame  = vec![];
+
+        // Leave out the move frame: the line should still decode to the same coordinates
+        writer.write_header(&mut file);
+        writer.write(&Draw::Path(PathOp::Move(10.0, 20.0)), &mut move_frame);
+        writer.write(&Draw::Path(PathOp::Line(30.0, 40.0)), &mut file);
+
+        assert!(read_all(file, UnknownInstructions::Reject) == vec![Ok(Draw::Path(PathOp::Line(30.0, 40.0)))]);
+    }
+}
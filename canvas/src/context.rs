@@ -45,9 +45,25 @@ pub trait GraphicsContext {
         self.draw(Draw::Path(PathOp::BezierCurve(((cp1_x, cp1_y), (cp2_x, cp2_y)), (x, y)))); 
     }
 
+    /// Adds a quadratic curve to the current path
+    fn quadratic_curve_to(&mut self, x: f32, y: f32, cp_x: f32, cp_y: f32) {
+        self.draw(Draw::Path(PathOp::QuadraticCurve((cp_x, cp_y), (x, y))));
+    }
+
+    /// Adds an elliptical arc to the current path (angles are in radians, and a line is drawn from the current point to the start of the arc)
+    fn arc_to(&mut self, center_x: f32, center_y: f32, radius_x: f32, radius_y: f32, rotation: f32, start_angle: f32, sweep_angle: f32) {
+        self.draw(Draw::Path(PathOp::Arc((center_x, center_y), (radius_x, radius_y), rotation, start_angle, sweep_angle)));
+    }
+
     /// Closes the current path (adds a line to the last move point)
     fn close_path(&mut self)                                { self.draw(Draw::Path(PathOp::ClosePath)); }
 
+    /// Saves a copy of the current path so it can be combined with a later path using `combine_path()`
+    fn store_path(&mut self)                                { self.draw(Draw::StorePath); }
+
+    /// Replaces the current path with the result of combining it with the path saved by the last `store_path()`
+    fn combine_path(&mut self, operation: PathArithmetic)   { self.draw(Draw::CombinePath(operation)); }
+
     /// Fills the currently defined path
     fn fill(&mut self)                                      { self.draw(Draw::Fill); }
 
@@ -63,6 +79,9 @@ pub trait GraphicsContext {
     /// Sets the line join style for the next stroke() operation
     fn line_join(&mut self, join: LineJoin)                 { self.draw(Draw::LineJoin(join)); }
 
+    /// Sets the maximum ratio of the miter length to the line width before a mitered join is bevelled instead
+    fn miter_limit(&mut self, limit: f32)                   { self.draw(Draw::MiterLimit(limit)); }
+
     /// Sets the style of the start and end cap of the next line drawn by the stroke() operation
     fn line_cap(&mut self, cap: LineCap)                    { self.draw(Draw::LineCap(cap)); }
 
@@ -94,12 +113,45 @@ pub trait GraphicsContext {
         self.draw(Draw::FillGradient(gradient_id, (x1, y1), (x2, y2)));
     }
 
+    /// Sets a radial gradient to use for the next fill() operation
+    ///
+    /// The gradient starts at the centre of the circle and ends at the specified radius
+    fn fill_radial_gradient(&mut self, gradient_id: GradientId, center_x: f32, center_y: f32, radius: f32) {
+        self.draw(Draw::FillRadialGradient(gradient_id, (center_x, center_y), radius, None));
+    }
+
+    /// Sets a radial gradient with a focal point to use for the next fill() operation
+    ///
+    /// The gradient starts at the focal point and ends at the edge of the circle. The focal point should be inside the circle.
+    fn fill_radial_gradient_with_focus(&mut self, gradient_id: GradientId, center_x: f32, center_y: f32, radius: f32, focus_x: f32, focus_y: f32) {
+        self.draw(Draw::FillRadialGradient(gradient_id, (center_x, center_y), radius, Some((focus_x, focus_y))));
+    }
+
+    /// Sets a conic (sweep) gradient to use for the next fill() operation
+    ///
+    /// The gradient sweeps anticlockwise around the centre point, starting at the specified angle (in radians)
+    fn fill_conic_gradient(&mut self, gradient_id: GradientId, center_x: f32, center_y: f32, start_angle: f32) {
+        self.draw(Draw::FillConicGradient(gradient_id, (center_x, center_y), start_angle));
+    }
+
     /// Applies a transformation to the fill texture or gradient
     fn fill_transform(&mut self, transform: Transform2D)    { self.draw(Draw::FillTransform(transform)); }
 
     /// Sets the colour to use for the next stroke() operation
     fn stroke_color(&mut self, col: Color)                  { self.draw(Draw::StrokeColor(col)); }
 
+    /// Sets the texture to use for the next stroke() operation
+    ///
+    /// The coordinates here specify the lower-left and upper-left position on the canvas where the texture will appear.
+    fn stroke_texture(&mut self, texture_id: TextureId, x1: f32, y1: f32, x2: f32, y2: f32) {
+        self.draw(Draw::StrokeTexture(texture_id, (x1, y1), (x2, y2)));
+    }
+
+    /// Sets the gradient to use for the next stroke() operation
+    fn stroke_gradient(&mut self, gradient_id: GradientId, x1: f32, y1: f32, x2: f32, y2: f32) {
+        self.draw(Draw::StrokeGradient(gradient_id, (x1, y1), (x2, y2)));
+    }
+
     /// Sets the blend mode of the next fill or stroke operation
     fn blend_mode(&mut self, mode: BlendMode)               { self.draw(Draw::BlendMode(mode)); }
 
@@ -220,6 +272,21 @@ pub trait GraphicsContext {
         self.draw(Draw::Font(font_id, FontOp::FontSize(size)));
     }
 
+    /// Sets the fonts to try, in order, when laying out characters that are missing from the specified font
+    fn set_fallback_fonts(&mut self, font_id: FontId, fallback_fonts: Vec<FontId>) {
+        self.draw(Draw::Font(font_id, FontOp::UseFallbackFonts(fallback_fonts)));
+    }
+
+    /// Turns an OpenType feature (such as `*b"liga"` or `*b"tnum"`) on or off for the specified font
+    fn set_font_feature(&mut self, font_id: FontId, feature: [u8; 4], enabled: bool) {
+        self.draw(Draw::Font(font_id, FontOp::FontFeature(feature, enabled)));
+    }
+
+    /// Sets the value of a variation axis (such as `*b"wght"` or `*b"wdth"`) for the specified font
+    fn set_font_variation(&mut self, font_id: FontId, axis: [u8; 4], value: f32) {
+        self.draw(Draw::Font(font_id, FontOp::FontVariation(axis, value)));
+    }
+
     /// Draws a text string using a font
     fn draw_text(&mut self, font_id: FontId, text: String, baseline_x: f32, baseline_y: f32) {
         self.draw(Draw::DrawText(font_id, text, baseline_x, baseline_y));
@@ -235,6 +302,11 @@ pub trait GraphicsContext {
         self.draw(Draw::BeginLineLayout(x, y, align));
     }
 
+    /// Starts laying out a paragraph of text that wraps to a width, with its top-left corner at the specified position
+    fn begin_paragraph_layout(&mut self, x: f32, y: f32, style: ParagraphStyle) {
+        self.draw(Draw::BeginParagraphLayout(x, y, style));
+    }
+
     /// Adds text to the current line layout
     fn layout_text(&mut self, font_id: FontId, text: String) {
         self.draw(Draw::Font(font_id, FontOp::LayoutText(text)));
@@ -316,6 +388,11 @@ pub trait GraphicsContext {
         self.draw(Draw::Gradient(gradient_id, GradientOp::AddStop(pos, color)));
     }
 
+    /// Sets how a gradient is extended beyond its first and last stops
+    fn gradient_spread(&mut self, gradient_id: GradientId, spread: GradientSpread) {
+        self.draw(Draw::Gradient(gradient_id, GradientOp::Spread(spread)));
+    }
+
 
 
     /// Sends a single drawing instruction to this graphics context
@@ -194,6 +194,46 @@ pub fn drawing_without_dashed_lines<InStream: 'static+Send+Unpin+Stream<Item=Dra
                     yield_value(Path(BezierCurve(((cp1x, cp1y), (cp2x, cp2y)), (x, y)))).await;
                 }
 
+                Path(QuadraticCurve((cpx, cpy), (x, y))) => {
+                    let ((cp1x, cp1y), (cp2x, cp2y)) = quadratic_to_bezier((last_point.0 as _, last_point.1 as _), (cpx, cpy), (x, y));
+                    let curve = (Coord2(cp1x as _, cp1y as _), Coord2(cp2x as _, cp2y as _), Coord2(x as _, y as _));
+                    current_path.last_mut().map(|path| path.1.push(curve));
+
+                    last_point      = Coord2(x as _, y as _);
+
+                    yield_value(Path(QuadraticCurve((cpx, cpy), (x, y)))).await;
+                }
+
+                Path(Arc(center, radii, rotation, start_angle, sweep_angle)) => {
+                    // The arc begins with a line from the current point, or starts a new subpath
+                    let (x, y)      = arc_point(center, radii, rotation, start_angle);
+                    let arc_start   = Coord2(x as _, y as _);
+
+                    if let Some(path) = current_path.last_mut() {
+                        if arc_start != last_point {
+                            let cp1         = (arc_start - last_point) * (1.0/3.0) + last_point;
+                            let cp2         = (arc_start - last_point) * (2.0/3.0) + last_point;
+                            path.1.push((cp1, cp2, arc_start));
+                        }
+                    } else {
+                        current_path.push((arc_start, vec![]));
+                        start_point = arc_start;
+                    }
+
+                    // Add the curves that make up the arc
+                    let curves      = arc_to_bezier_curves(center, radii, rotation, start_angle, sweep_angle);
+                    last_point      = arc_start;
+
+                    for ((cp1x, cp1y), (cp2x, cp2y), (x, y)) in curves {
+                        let curve = (Coord2(cp1x as _, cp1y as _), Coord2(cp2x as _, cp2y as _), Coord2(x as _, y as _));
+                        current_path.last_mut().map(|path| path.1.push(curve));
+
+                        last_point  = Coord2(x as _, y as _);
+                    }
+
+                    yield_value(Path(Arc(center, radii, rotation, start_angle, sweep_angle))).await;
+                }
+
                 Path(ClosePath) => {
                     let end_point   = start_point;
                     let cp1         = (end_point - last_point) * (1.0/3.0) + last_point;
@@ -0,0 +1,360 @@
+use super::drawing_shapes::*;
+
+#[cfg(feature = "outline-fonts")] use super::glyph_layout::*;
+#[cfg(feature = "outline-fonts")] use super::outline_fonts::*;
+
+use crate::draw::*;
+use crate::sprite::*;
+use crate::transform2d::*;
+
+use futures::prelude::*;
+
+use std::collections::{HashMap};
+
+///
+/// A rectangular region of a drawing
+///
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DrawingBounds {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl Default for DrawingBounds {
+    fn default() -> Self {
+        DrawingBounds::empty()
+    }
+}
+
+impl DrawingBounds {
+    ///
+    /// Creates a bounding box that contains nothing
+    ///
+    #[inline]
+    pub fn empty() -> DrawingBounds {
+        DrawingBounds {
+            min_x:  f32::MAX,
+            min_y:  f32::MAX,
+            max_x:  f32::MIN,
+            max_y:  f32::MIN
+        }
+    }
+
+    ///
+    /// True if these bounds contain no points
+    ///
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.min_x > self.max_x || self.min_y > self.max_y
+    }
+
+    ///
+    /// The width of these bounds (0 if they are empty)
+    ///
+    #[inline]
+    pub fn width(&self) -> f32 {
+        if self.is_empty() { 0.0 } else { self.max_x - self.min_x }
+    }
+
+    ///
+    /// The height of these bounds (0 if they are empty)
+    ///
+    #[inline]
+    pub fn height(&self) -> f32 {
+        if self.is_empty() { 0.0 } else { self.max_y - self.min_y }
+    }
+
+    ///
+    /// Extends these bounds so they contain the specified point
+    ///
+    #[inline]
+    pub fn add_point(&mut self, (x, y): (f32, f32)) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x);
+        self.max_y = self.max_y.max(y);
+    }
+
+    ///
+    /// Returns the bounds that contain both this and another set of bounds
+    ///
+    #[inline]
+    pub fn union(&self, other: &DrawingBounds) -> DrawingBounds {
+        if self.is_empty() {
+            *other
+        } else if other.is_empty() {
+            *self
+        } else {
+            DrawingBounds {
+                min_x: self.min_x.min(other.min_x),
+                min_y: self.min_y.min(other.min_y),
+                max_x: self.max_x.max(other.max_x),
+                max_y: self.max_y.max(other.max_y),
+            }
+        }
+    }
+
+    ///
+    /// Returns the area covered by both this and another set of bounds
+    ///
+    #[inline]
+    pub fn intersect(&self, other: &DrawingBounds) -> DrawingBounds {
+        let intersection = DrawingBounds {
+            min_x: self.min_x.max(other.min_x),
+            min_y: self.min_y.max(other.min_y),
+            max_x: self.max_x.min(other.max_x),
+            max_y: self.max_y.min(other.max_y),
+        };
+
+        if intersection.is_empty() { DrawingBounds::empty() } else { intersection }
+    }
+
+    ///
+    /// Returns the bounds of this region after it has been transformed
+    ///
+    pub fn transform(&self, transform: &Transform2D) -> DrawingBounds {
+        if self.is_empty() {
+            return *self;
+        }
+
+        let mut bounds = DrawingBounds::empty();
+        for (x, y) in [(self.min_x, self.min_y), (self.max_x, self.min_y), (self.max_x, self.max_y), (self.min_x, self.max_y)] {
+            bounds.add_point(transform.transform_point(x, y));
+        }
+
+        bounds
+    }
+}
+
+///
+/// The bounds of a drawing, along with the bounds of each of its layers and sprites
+///
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct MeasuredDrawing {
+    /// The bounds of everything drawn on all of the layers
+    pub bounds: DrawingBounds,
+
+    /// The bounds of each layer that has something drawn on it
+    pub layers: HashMap<LayerId, DrawingBounds>,
+
+    /// The bounds of each sprite that has something drawn in it, in the sprite's own coordinates
+    pub sprites: HashMap<SpriteId, DrawingBounds>,
+}
+
+///
+/// Returns the bounds of a region in canvas coordinates
+///
+fn region_bounds(region: &ShapeRegion) -> DrawingBounds {
+    let mut bounds = DrawingBounds::empty();
+
+    if let Some(transform) = region.inverse_transform.invert() {
+        for (x, y) in region.polygons.iter().flatten() {
+            bounds.add_point(transform.transform_point(*x, *y));
+        }
+    }
+
+    bounds
+}
+
+///
+/// Restricts a set of bounds to the area that's inside a clipping path
+///
+fn clip_bounds(bounds: DrawingBounds, clip: &[ShapeRegion]) -> DrawingBounds {
+    clip.iter().fold(bounds, |bounds, clip_region| bounds.intersect(&region_bounds(clip_region)))
+}
+
+///
+/// Returns the bounds of the specified sprite (caching the result)
+///
+fn sprite_bounds(sprite_id: SpriteId, all_entities: &HashMap<ShapeTarget, Vec<DrawnEntity>>, measured_sprites: &mut HashMap<SpriteId, DrawingBounds>, in_progress: &mut Vec<SpriteId>) -> DrawingBounds {
+    if let Some(bounds) = measured_sprites.get(&sprite_id) {
+        return *bounds;
+    }
+
+    // Sprites that draw themselves don't contribute to their own bounds
+    if in_progress.contains(&sprite_id) {
+        return DrawingBounds::empty();
+    }
+
+    in_progress.push(sprite_id);
+    let bounds = all_entities.get(&ShapeTarget::Sprite(sprite_id))
+        .map(|entities| entities_bounds(entities, all_entities, measured_sprites, in_progress))
+        .unwrap_or_default();
+    in_progress.pop();
+
+    measured_sprites.insert(sprite_id, bounds);
+    bounds
+}
+
+///
+/// Returns the bounds of a list of entities
+///
+fn entities_bounds(entities: &[DrawnEntity], all_entities: &HashMap<ShapeTarget, Vec<DrawnEntity>>, measured_sprites: &mut HashMap<SpriteId, DrawingBounds>, in_progress: &mut Vec<SpriteId>) -> DrawingBounds {
+    let mut bounds = DrawingBounds::empty();
+
+    for entity in entities.iter() {
+        let entity_bounds = match entity {
+            DrawnEntity::Shape { region, clip, .. } => clip_bounds(region_bounds(region), clip),
+
+            DrawnEntity::Sprite { sprite_id, inverse_transform, clip, .. } => {
+                let sprite_bounds = sprite_bounds(*sprite_id, all_entities, measured_sprites, in_progress);
+
+                if let Some(transform) = inverse_transform.invert() {
+                    clip_bounds(sprite_bounds.transform(&transform), clip)
+                } else {
+                    DrawingBounds::empty()
+                }
+            }
+        };
+
+        bounds = bounds.union(&entity_bounds);
+    }
+
+    bounds
+}
+
+///
+/// Measures the bounds of everything drawn by a stream of drawing instructions
+///
+/// The bounds are in canvas coordinates: that is, the coordinate scheme set up by `CanvasHeight` or `IdentityTransform`, with any
+/// transforms from `MultiplyTransform` applied to the shapes being measured. This means that the result can be passed to
+/// `CenterRegion` to fit the drawing into a view. Strokes are measured using their line width, joins, caps and miter limit, and
+/// any clipping path is taken into account. Widths set by `LineWidthPixels` depend on the size of the window, so they're treated
+/// as widths in canvas units here.
+///
+/// Text is measured if the `outline-fonts` feature is enabled: otherwise it's ignored.
+///
+pub fn measure_drawing<InStream: 'static+Send+Unpin+Stream<Item=Draw>>(draw_stream: InStream) -> impl Send+Future<Output=MeasuredDrawing> {
+    // Convert any text to paths so it can be measured
+    #[cfg(feature = "outline-fonts")]
+    let draw_stream = drawing_with_text_as_paths(drawing_with_laid_out_text(draw_stream));
+
+    async move {
+        let drawing             = draw_stream.collect::<Vec<_>>().await;
+        let shapes              = drawn_shapes(drawing);
+
+        // Measure all of the sprites
+        let mut sprites         = HashMap::new();
+        let mut in_progress     = vec![];
+
+        for target in shapes.entities.keys() {
+            if let ShapeTarget::Sprite(sprite_id) = target {
+                sprite_bounds(*sprite_id, &shapes.entities, &mut sprites, &mut in_progress);
+            }
+        }
+
+        // Measure the layers
+        let mut layers          = HashMap::new();
+        let mut bounds          = DrawingBounds::empty();
+
+        for layer_id in shapes.layer_order.iter() {
+            if let Some(entities) = shapes.entities.get(&ShapeTarget::Layer(*layer_id)) {
+                let layer_bounds = entities_bounds(entities, &shapes.entities, &mut sprites, &mut in_progress);
+
+                if !layer_bounds.is_empty() {
+                    bounds = bounds.union(&layer_bounds);
+                    layers.insert(*layer_id, layer_bounds);
+                }
+            }
+        }
+
+        // Only report sprites that have something in them
+        sprites.retain(|_, sprite_bounds| !sprite_bounds.is_empty());
+
+        MeasuredDrawing {
+            bounds:     bounds,
+            layers:     layers,
+            sprites:    sprites,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::context::*;
+    use crate::primitives::*;
+
+    use futures::stream;
+    use futures::executor;
+
+    fn measure(drawing: Vec<Draw>) -> MeasuredDrawing {
+        executor::block_on(measure_drawing(stream::iter(drawing.into_iter())))
+    }
+
+    fn bounds_near(bounds: &DrawingBounds, (min_x, min_y): (f32, f32), (max_x, max_y): (f32, f32)) -> bool {
+        (bounds.min_x-min_x).abs() < 0.01 && (bounds.min_y-min_y).abs() < 0.01 && (bounds.max_x-max_x).abs() < 0.01 && (bounds.max_y-max_y).abs() < 0.01
+    }
+
+    #[test]
+    fn measure_filled_rectangle() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.rect(10.0, 20.0, 100.0, 200.0);
+        drawing.fill();
+
+        let measured = measure(drawing);
+
+        assert!(bounds_near(&measured.bounds, (10.0, 20.0), (100.0, 200.0)));
+        assert!(bounds_near(&measured.layers[&LayerId(0)], (10.0, 20.0), (100.0, 200.0)));
+    }
+
+    #[test]
+    fn measure_stroke_width() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.move_to(0.0, 0.0);
+        drawing.line_to(100.0, 0.0);
+        drawing.line_width(10.0);
+        drawing.line_cap(LineCap::Square);
+        drawing.stroke();
+
+        let measured = measure(drawing);
+
+        assert!(bounds_near(&measured.bounds, (-5.0, -5.0), (105.0, 5.0)));
+    }
+
+    #[test]
+    fn measure_transformed_and_clipped() {
+        let mut drawing = vec![];
+        drawing.transform(Transform2D::translate(100.0, 0.0));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 50.0, 50.0);
+        drawing.clip();
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 100.0, 100.0);
+        drawing.fill();
+
+        let measured = measure(drawing);
+
+        assert!(bounds_near(&measured.bounds, (100.0, 0.0), (150.0, 50.0)));
+    }
+
+    #[test]
+    fn measure_layers_and_sprites() {
+        let mut drawing = vec![];
+        drawing.sprite(SpriteId(1));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 10.0);
+        drawing.fill();
+
+        drawing.layer(LayerId(1));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 10.0);
+        drawing.fill();
+
+        drawing.layer(LayerId(2));
+        drawing.sprite_transform(SpriteTransform::Translate(100.0, 100.0));
+        drawing.draw_sprite(SpriteId(1));
+
+        let measured = measure(drawing);
+
+        assert!(bounds_near(&measured.sprites[&SpriteId(1)], (0.0, 0.0), (10.0, 10.0)));
+        assert!(bounds_near(&measured.layers[&LayerId(1)], (0.0, 0.0), (10.0, 10.0)));
+        assert!(bounds_near(&measured.layers[&LayerId(2)], (100.0, 100.0), (110.0, 110.0)));
+        assert!(bounds_near(&measured.bounds, (0.0, 0.0), (110.0, 110.0)));
+        assert!(!measured.layers.contains_key(&LayerId(0)));
+    }
+}
@@ -0,0 +1,399 @@
+use super::stroke_outline::{polyline_stroke_outline, flatten_curve};
+use super::path_arithmetic::{combine_paths};
+
+use crate::draw::*;
+use crate::path::*;
+use crate::sprite::*;
+use crate::transform2d::*;
+
+use std::mem;
+use std::collections::{HashMap};
+
+///
+/// Whether a shape was drawn using a fill or a stroke
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub (super) enum ShapeType {
+    Fill,
+    Stroke,
+}
+
+///
+/// Where drawing instructions are being sent
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub (super) enum ShapeTarget {
+    Layer(LayerId),
+    Sprite(SpriteId),
+}
+
+///
+/// An area covered by a shape or a clip path
+///
+#[derive(Clone, Debug)]
+pub (super) struct ShapeRegion {
+    /// Transforms from canvas coordinates to the coordinates of the polygons in this region
+    pub (super) inverse_transform:  Transform2D,
+
+    /// The polygons making up this region
+    pub (super) polygons:           Vec<Vec<(f32, f32)>>,
+
+    /// The winding rule used to determine which parts of the polygons are inside the region
+    pub (super) winding_rule:       WindingRule,
+}
+
+///
+/// Something that was drawn on a layer or a sprite
+///
+#[derive(Clone, Debug)]
+pub (super) enum DrawnEntity {
+    /// A filled or stroked shape
+    Shape { draw_index: usize, shape_type: ShapeType, region: ShapeRegion, clip: Vec<ShapeRegion> },
+
+    /// A sprite (inverse transform maps canvas coordinates to sprite coordinates)
+    Sprite { draw_index: usize, sprite_id: SpriteId, inverse_transform: Transform2D, clip: Vec<ShapeRegion> },
+}
+
+///
+/// The shapes that make up a drawing
+///
+pub (super) struct DrawnShapes {
+    /// The entities drawn on each layer and sprite, in the order they were drawn
+    pub (super) entities:       HashMap<ShapeTarget, Vec<DrawnEntity>>,
+
+    /// The layers in the order that they're drawn
+    pub (super) layer_order:    Vec<LayerId>,
+}
+
+///
+/// The drawing state for a layer or a sprite
+///
+#[derive(Clone, Debug)]
+struct ShapeState {
+    winding_rule:       WindingRule,
+    line_width:         f32,
+    line_join:          LineJoin,
+    line_cap:           LineCap,
+    miter_limit:        f32,
+    sprite_transform:   Transform2D,
+    clip:               Vec<ShapeRegion>,
+}
+
+impl Default for ShapeState {
+    fn default() -> ShapeState {
+        ShapeState {
+            winding_rule:       WindingRule::NonZero,
+            line_width:         1.0,
+            line_join:          LineJoin::Round,
+            line_cap:           LineCap::Butt,
+            miter_limit:        4.0,
+            sprite_transform:   Transform2D::identity(),
+            clip:               vec![],
+        }
+    }
+}
+
+impl ShapeRegion {
+    ///
+    /// True if the specified point (in canvas coordinates) is within this region
+    ///
+    pub (super) fn contains(&self, (x, y): (f32, f32)) -> bool {
+        let point   = self.inverse_transform.transform_point(x, y);
+        let winding = self.polygons.iter().map(|polygon| winding_number(polygon, point)).sum::<i32>();
+
+        match self.winding_rule {
+            WindingRule::NonZero    => winding != 0,
+            WindingRule::EvenOdd    => winding % 2 != 0,
+        }
+    }
+}
+
+///
+/// Returns the winding number of a closed polygon around a point
+///
+fn winding_number(polygon: &[(f32, f32)], (x, y): (f32, f32)) -> i32 {
+    let mut winding = 0;
+
+    for idx in 0..polygon.len() {
+        let (x1, y1)    = polygon[idx];
+        let (x2, y2)    = polygon[(idx+1) % polygon.len()];
+        let side        = (x2-x1)*(y-y1) - (x-x1)*(y2-y1);
+
+        if y1 <= y {
+            if y2 > y && side > 0.0 { winding += 1; }
+        } else {
+            if y2 <= y && side < 0.0 { winding -= 1; }
+        }
+    }
+
+    winding
+}
+
+///
+/// Converts a path into a list of subpaths made up of straight lines, along with whether or not each subpath is closed
+///
+pub (super) fn flatten_path(path_ops: &[PathOp]) -> Vec<(Vec<(f32, f32)>, bool)> {
+    // Choose a tolerance based on the size of the path
+    let coords = path_ops.iter()
+        .flat_map(|path_op| match path_op {
+            PathOp::NewPath | PathOp::ClosePath                 => vec![],
+            PathOp::Move(x, y) | PathOp::Line(x, y)             => vec![(*x, *y)],
+            PathOp::BezierCurve((cp1, cp2), end_point)          => vec![*cp1, *cp2, *end_point],
+            PathOp::QuadraticCurve(cp, end_point)               => vec![*cp, *end_point],
+            PathOp::Arc((cx, cy), (rx, ry), _, _, _)            => { let radius = rx.abs().max(ry.abs()); vec![(cx-radius, cy-radius), (cx+radius, cy+radius)] },
+        })
+        .collect::<Vec<_>>();
+    let extent      = coords.iter().fold(0.0f32, |extent, (x, y)| extent.max(x.abs()).max(y.abs()));
+    let tolerance   = (extent * 0.001).max(1e-6);
+
+    // Generate the subpaths
+    let mut subpaths    = vec![];
+    let mut points      = vec![];
+    let mut start_point = None;
+
+    for path_op in path_ops.iter() {
+        // Segments following a ClosePath start at the beginning of the closed subpath
+        let segment_start = |points: &mut Vec<(f32, f32)>, start_point: &mut Option<(f32, f32)>, default_start: (f32, f32)| {
+            if points.is_empty() {
+                let start = *start_point.get_or_insert(default_start);
+                points.push(start);
+            }
+
+            *points.last().unwrap()
+        };
+
+        match path_op {
+            PathOp::NewPath => {
+                subpaths.clear();
+                points.clear();
+                start_point = None;
+            }
+
+            PathOp::Move(x, y) => {
+                if !points.is_empty() { subpaths.push((mem::take(&mut points), false)); }
+
+                points.push((*x, *y));
+                start_point = Some((*x, *y));
+            }
+
+            PathOp::Line(x, y) => {
+                segment_start(&mut points, &mut start_point, (*x, *y));
+                points.push((*x, *y));
+            }
+
+            PathOp::BezierCurve((cp1, cp2), end_point) => {
+                let last_point = segment_start(&mut points, &mut start_point, *end_point);
+                flatten_curve(last_point, *cp1, *cp2, *end_point, tolerance, &mut points);
+            }
+
+            PathOp::QuadraticCurve(cp, end_point) => {
+                let last_point  = segment_start(&mut points, &mut start_point, *end_point);
+                let (cp1, cp2)  = quadratic_to_bezier(last_point, *cp, *end_point);
+                flatten_curve(last_point, cp1, cp2, *end_point, tolerance, &mut points);
+            }
+
+            PathOp::Arc(center, radii, rotation, start_angle, sweep_angle) => {
+                let arc_start   = arc_point(*center, *radii, *rotation, *start_angle);
+                let mut last    = segment_start(&mut points, &mut start_point, arc_start);
+
+                if last != arc_start {
+                    points.push(arc_start);
+                    last = arc_start;
+                }
+
+                for (cp1, cp2, end_point) in arc_to_bezier_curves(*center, *radii, *rotation, *start_angle, *sweep_angle) {
+                    flatten_curve(last, cp1, cp2, end_point, tolerance, &mut points);
+                    last = end_point;
+                }
+            }
+
+            PathOp::ClosePath => {
+                if !points.is_empty() { subpaths.push((mem::take(&mut points), true)); }
+            }
+        }
+    }
+
+    if !points.is_empty() { subpaths.push((points, false)); }
+
+    subpaths
+}
+
+///
+/// Reads a drawing and returns the shapes that it draws on each layer and sprite
+///
+/// Shapes are described in canvas coordinates: that is, the coordinate scheme set up by `CanvasHeight` or `IdentityTransform`,
+/// with any transforms from `MultiplyTransform` applied. Sprites are in their own coordinate scheme. Strokes are converted
+/// to the area covered by the line (ignoring any dash pattern, and treating widths set by `LineWidthPixels` as canvas units).
+///
+pub (super) fn drawn_shapes<DrawIter: IntoIterator<Item=Draw>>(drawing: DrawIter) -> DrawnShapes {
+    // Where the drawing is going and its state
+    let mut target                                      = ShapeTarget::Layer(LayerId(0));
+    let mut state                                       = ShapeState::default();
+    let mut target_state                                = HashMap::new();
+
+    // The transform from shape coordinates to canvas coordinates (sprites have their own transform, so we store the layer transform while they're being drawn)
+    let mut transform                                   = Transform2D::identity();
+    let mut layer_transform                             = Transform2D::identity();
+
+    // The current path
+    let mut current_path                                = vec![];
+    let mut stored_path                                 = vec![];
+    let mut state_stack                                 = vec![];
+
+    // The things that have been drawn on each layer and sprite
+    let mut entities: HashMap<ShapeTarget, Vec<DrawnEntity>> = HashMap::new();
+    let mut layer_order                                 = vec![LayerId(0)];
+
+    for (draw_index, draw) in drawing.into_iter().enumerate() {
+        use self::Draw::*;
+
+        match draw {
+            Path(PathOp::NewPath)                   => { current_path = vec![]; }
+            Path(path_op)                           => { current_path.push(path_op); }
+            StorePath                               => { stored_path = current_path.clone(); }
+            CombinePath(operation)                  => { current_path = combine_paths(operation, state.winding_rule, &stored_path, &current_path); }
+
+            LineWidth(width)                        => { state.line_width = width; }
+            LineWidthPixels(width)                  => { state.line_width = width; }
+            LineJoin(join)                          => { state.line_join = join; }
+            MiterLimit(limit)                       => { state.miter_limit = limit; }
+            LineCap(cap)                            => { state.line_cap = cap; }
+            WindingRule(winding_rule)               => { state.winding_rule = winding_rule; }
+
+            IdentityTransform                       |
+            CanvasHeight(_)                         => { transform = Transform2D::identity(); }
+            MultiplyTransform(new_transform)        => { transform = transform * new_transform; }
+
+            Fill                                    |
+            Clip                                    => {
+                if let Some(inverse_transform) = transform.invert() {
+                    let region = ShapeRegion {
+                        inverse_transform:  inverse_transform,
+                        polygons:           flatten_path(&current_path).into_iter().map(|(points, _)| points).filter(|points| points.len() > 2).collect(),
+                        winding_rule:       state.winding_rule,
+                    };
+
+                    if draw == Fill {
+                        entities.entry(target).or_insert_with(|| vec![])
+                            .push(DrawnEntity::Shape { draw_index: draw_index, shape_type: ShapeType::Fill, region: region, clip: state.clip.clone() });
+                    } else {
+                        state.clip.push(region);
+                    }
+                }
+            }
+
+            Stroke                                  => {
+                if let Some(inverse_transform) = transform.invert() {
+                    let tolerance   = (state.line_width * 0.02).max(1e-6);
+                    let polygons    = flatten_path(&current_path).into_iter()
+                        .flat_map(|(points, closed)| polyline_stroke_outline(&points, closed, state.line_width, state.line_join, state.line_cap, state.miter_limit, tolerance))
+                        .collect();
+                    let region      = ShapeRegion {
+                        inverse_transform:  inverse_transform,
+                        polygons:           polygons,
+                        winding_rule:       self::WindingRule::NonZero,
+                    };
+
+                    entities.entry(target).or_insert_with(|| vec![])
+                        .push(DrawnEntity::Shape { draw_index: draw_index, shape_type: ShapeType::Stroke, region: region, clip: state.clip.clone() });
+                }
+            }
+
+            Unclip                                  => { state.clip = vec![]; }
+
+            PushState                               => { state_stack.push((transform, state.clone(), current_path.clone())); }
+            PopState                                => {
+                if let Some((old_transform, old_state, old_path)) = state_stack.pop() {
+                    transform       = old_transform;
+                    state           = old_state;
+                    current_path    = old_path;
+                }
+            }
+
+            Layer(layer_id)                         => {
+                if let ShapeTarget::Sprite(_) = target {
+                    transform = layer_transform;
+                }
+
+                target_state.insert(target, mem::take(&mut state));
+                target  = ShapeTarget::Layer(layer_id);
+                state   = target_state.remove(&target).unwrap_or_default();
+
+                // Layers are ordered by ID unless they've been swapped
+                if !layer_order.contains(&layer_id) {
+                    let pos = layer_order.iter().position(|existing| existing.0 > layer_id.0).unwrap_or(layer_order.len());
+                    layer_order.insert(pos, layer_id);
+                }
+            }
+
+            Sprite(sprite_id)                       => {
+                if let ShapeTarget::Layer(_) = target {
+                    layer_transform = transform;
+                }
+
+                // Sprites are drawn in their own coordinate scheme
+                transform = Transform2D::identity();
+
+                target_state.insert(target, mem::take(&mut state));
+                target  = ShapeTarget::Sprite(sprite_id);
+                state   = target_state.remove(&target).unwrap_or_default();
+            }
+
+            SwapLayers(layer1, layer2)              => {
+                for layer_id in [layer1, layer2] {
+                    if !layer_order.contains(&layer_id) {
+                        let pos = layer_order.iter().position(|existing| existing.0 > layer_id.0).unwrap_or(layer_order.len());
+                        layer_order.insert(pos, layer_id);
+                    }
+                }
+
+                let pos1 = layer_order.iter().position(|existing| existing == &layer1).unwrap();
+                let pos2 = layer_order.iter().position(|existing| existing == &layer2).unwrap();
+                layer_order.swap(pos1, pos2);
+            }
+
+            SpriteTransform(self::SpriteTransform::Identity)    => { state.sprite_transform = Transform2D::identity(); }
+            SpriteTransform(sprite_transform)                   => { state.sprite_transform = Transform2D::from(sprite_transform) * state.sprite_transform; }
+
+            DrawSprite(sprite_id)                   |
+            DrawSpriteWithFilters(sprite_id, _)     => {
+                if let Some(inverse_transform) = (transform * state.sprite_transform).invert() {
+                    entities.entry(target).or_insert_with(|| vec![])
+                        .push(DrawnEntity::Sprite { draw_index: draw_index, sprite_id: sprite_id, inverse_transform: inverse_transform, clip: state.clip.clone() });
+                }
+            }
+
+            MoveSpriteFrom(sprite_id)               => {
+                if let ShapeTarget::Sprite(current_sprite_id) = target {
+                    if current_sprite_id != sprite_id {
+                        let sprite_entities = entities.remove(&ShapeTarget::Sprite(sprite_id)).unwrap_or_default();
+                        entities.insert(target, sprite_entities);
+                    }
+                }
+            }
+
+            ClearLayer                              |
+            ClearSprite                             => { entities.remove(&target); }
+            ClearAllLayers                          => { entities.retain(|target, _| if let ShapeTarget::Sprite(_) = target { true } else { false }); }
+
+            ClearCanvas(_)                          => {
+                target          = ShapeTarget::Layer(LayerId(0));
+                state           = ShapeState::default();
+                target_state    = HashMap::new();
+                transform       = Transform2D::identity();
+                current_path    = vec![];
+                stored_path     = vec![];
+                state_stack     = vec![];
+                entities        = HashMap::new();
+                layer_order     = vec![LayerId(0)];
+            }
+
+            _                                       => { }
+        }
+    }
+
+    DrawnShapes {
+        entities:       entities,
+        layer_order:    layer_order,
+    }
+}
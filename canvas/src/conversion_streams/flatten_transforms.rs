@@ -0,0 +1,837 @@
+#[cfg(feature = "outline-fonts")] use super::glyph_layout::*;
+#[cfg(feature = "outline-fonts")] use super::outline_fonts::*;
+
+use super::path_arithmetic::*;
+
+use crate::draw::*;
+use crate::path::*;
+use crate::font::*;
+use crate::color::*;
+use crate::sprite::*;
+use crate::transform2d::*;
+
+use flo_stream::*;
+use futures::prelude::*;
+
+use std::mem;
+use std::collections::{HashMap};
+
+///
+/// Where the drawing instructions are currently being sent
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum FlattenTarget {
+    Layer(LayerId),
+    Sprite(SpriteId),
+}
+
+///
+/// The drawing state that depends on the transform (and the values last written to the output for that state)
+///
+#[derive(Clone, Debug)]
+struct FlattenState {
+    /// The line width in the current coordinate scheme, or None if the line width is in pixels
+    line_width:             Option<f32>,
+
+    /// The dash pattern and offset in the current coordinate scheme
+    dash_pattern:           Vec<f32>,
+    dash_offset:            f32,
+
+    /// The texture or gradient fill instruction (None if the fill is a solid colour)
+    fill:                   Option<Draw>,
+
+    /// The transform applied to the texture or gradient fill by `FillTransform`
+    fill_transform:         Transform2D,
+
+    /// The texture or gradient stroke instruction (None if the stroke is a solid colour)
+    stroke:                 Option<Draw>,
+
+    /// The transform applied to sprites drawn in this state
+    sprite_transform:       Transform2D,
+
+    /// The winding rule used when combining paths
+    winding_rule:           WindingRule,
+
+    /// The line width last written to the output (None if the width is unknown or in pixels)
+    output_line_width:      Option<f32>,
+
+    /// The dash pattern last written to the output
+    output_dash_pattern:    Option<(Vec<f32>, f32)>,
+}
+
+impl Default for FlattenState {
+    fn default() -> FlattenState {
+        FlattenState {
+            line_width:             Some(1.0),
+            dash_pattern:           vec![],
+            dash_offset:            0.0,
+            fill:                   None,
+            fill_transform:         Transform2D::identity(),
+            stroke:                 None,
+            sprite_transform:       Transform2D::identity(),
+            winding_rule:           WindingRule::NonZero,
+            output_line_width:      Some(1.0),
+            output_dash_pattern:    Some((vec![], 0.0)),
+        }
+    }
+}
+
+///
+/// Tracks the transform from drawing coordinates to canvas coordinates as the instructions in a drawing are processed
+///
+/// Canvas coordinates are the ones set up by `IdentityTransform`, where the view is 2.0 units high with (0, 0) at the center.
+/// Sprites are drawn in their own coordinate scheme, which isn't affected by `CanvasHeight` or `CenterRegion`, so the layer
+/// transform is kept while a sprite is selected.
+///
+#[derive(Clone, Copy, Debug)]
+pub (crate) struct CanvasTransform {
+    /// The transform for the layer or sprite that is being drawn on
+    transform:          Transform2D,
+
+    /// The transform to restore when a layer is selected after a sprite
+    layer_transform:    Transform2D,
+
+    /// True if a sprite is selected
+    in_sprite:          bool,
+}
+
+impl CanvasTransform {
+    ///
+    /// Creates the transform for a new canvas
+    ///
+    pub (crate) fn new() -> CanvasTransform {
+        CanvasTransform {
+            transform:          Transform2D::identity(),
+            layer_transform:    Transform2D::identity(),
+            in_sprite:          false,
+        }
+    }
+
+    ///
+    /// The transform from the current drawing coordinates to canvas coordinates
+    ///
+    #[inline]
+    pub (crate) fn transform(&self) -> Transform2D {
+        self.transform
+    }
+
+    ///
+    /// Replaces the current transform (eg, when it's restored by `PopState`)
+    ///
+    #[inline]
+    pub (crate) fn set_transform(&mut self, transform: Transform2D) {
+        self.transform = transform;
+    }
+
+    ///
+    /// Updates the transform for a drawing instruction
+    ///
+    /// Returns true if the instruction is one of the transform instructions, which have no other effect. `Layer`, `Sprite`
+    /// and `ClearCanvas` also update the transform, but return false as they need to be processed further.
+    ///
+    pub (crate) fn update(&mut self, drawing: &Draw) -> bool {
+        match drawing {
+            Draw::IdentityTransform                     => { self.transform = Transform2D::identity(); true }
+            Draw::MultiplyTransform(transform)          => { self.transform = self.transform * *transform; true }
+
+            Draw::CanvasHeight(height)                  => {
+                // Sprites aren't affected by the canvas height
+                if !self.in_sprite {
+                    // The canvas coordinates are 2.0 units high
+                    let scale       = 2.0 / f32::max(1.0, *height);
+                    self.transform  = Transform2D::scale(scale, scale);
+                }
+
+                true
+            }
+
+            Draw::CenterRegion((x1, y1), (x2, y2))      => {
+                // Sprites aren't affected by center region
+                if !self.in_sprite {
+                    if let Some(inverse_transform) = self.transform.invert() {
+                        // Move the point at the center of the canvas to the center of the region
+                        let (center_x, center_y)    = inverse_transform.transform_point(0.0, 0.0);
+                        let (new_x, new_y)          = ((x1+x2)/2.0, (y1+y2)/2.0);
+
+                        self.transform = self.transform * Transform2D::translate(-(new_x - center_x), -(new_y - center_y));
+                    }
+                }
+
+                true
+            }
+
+            Draw::Layer(_)                              => {
+                if self.in_sprite {
+                    self.transform  = self.layer_transform;
+                    self.in_sprite  = false;
+                }
+
+                false
+            }
+
+            Draw::Sprite(_)                             => {
+                if !self.in_sprite {
+                    self.layer_transform    = self.transform;
+                    self.in_sprite          = true;
+                }
+
+                // Sprites are drawn in their own coordinate scheme
+                self.transform = Transform2D::identity();
+
+                false
+            }
+
+            Draw::ClearCanvas(_)                        => { *self = CanvasTransform::new(); false }
+
+            _                                           => false,
+        }
+    }
+}
+
+///
+/// Tracks the transforms applied to a drawing and writes out the drawing with the transforms applied to its coordinates
+///
+struct TransformFlattener {
+    /// Where the drawing is going and its state
+    target:             FlattenTarget,
+    state:              FlattenState,
+    target_state:       HashMap<FlattenTarget, FlattenState>,
+
+    /// The transform from the drawing coordinates to the output coordinates
+    canvas_transform:   CanvasTransform,
+
+    /// The current path (in drawing coordinates) and the path saved by `StorePath`
+    current_path:       Vec<PathOp>,
+    stored_path:        Vec<PathOp>,
+
+    /// The transform that the current path was last written to the output with (None if the output doesn't contain the current path)
+    output_path:        Option<Transform2D>,
+
+    /// The states stored by `PushState`
+    state_stack:        Vec<(Transform2D, FlattenState, Vec<PathOp>)>,
+
+    /// The instructions that make up each sprite (these are drawn inline when the sprite is drawn)
+    sprites:            HashMap<SpriteId, Vec<Draw>>,
+
+    /// The number of sprites that are currently being drawn inline
+    sprite_depth:       usize,
+}
+
+///
+/// Returns the amount that a transform scales lengths by (used for line widths and dash patterns)
+///
+fn transform_scale(transform: &Transform2D) -> f32 {
+    let Transform2D(a) = transform;
+
+    // The determinant is the factor that areas are scaled by, so its square root is the average factor that lengths are
+    // scaled by (this is exact for uniform scales and rotations, and a compromise for non-uniform scales and skews)
+    (a[0][0]*a[1][1] - a[0][1]*a[1][0]).abs().sqrt()
+}
+
+///
+/// Scales the distances in a paragraph style by the scale of a transform
+///
+fn transform_paragraph_style(style: ParagraphStyle, transform: &Transform2D) -> ParagraphStyle {
+    let scale = transform_scale(transform);
+
+    let overflow = match style.overflow {
+        TextOverflow::Visible               => TextOverflow::Visible,
+        TextOverflow::Clip(max_height)      => TextOverflow::Clip(max_height * scale),
+        TextOverflow::Ellipsis(max_height)  => TextOverflow::Ellipsis(max_height * scale),
+    };
+
+    ParagraphStyle {
+        width:              style.width * scale,
+        paragraph_spacing:  style.paragraph_spacing * scale,
+        overflow:           overflow,
+        ..style
+    }
+}
+
+///
+/// Applies a transform to a list of path operations
+///
+fn transform_path(path: &[PathOp], transform: &Transform2D) -> Vec<PathOp> {
+    let mut transformed_path    = vec![];
+    let mut has_current_point   = false;
+    let point                   = |(x, y): (f32, f32)| transform.transform_point(x, y);
+
+    for path_op in path.iter() {
+        match path_op {
+            PathOp::NewPath                         => { transformed_path.push(PathOp::NewPath); has_current_point = false; }
+            PathOp::Move(x, y)                      => { let (x, y) = point((*x, *y)); transformed_path.push(PathOp::Move(x, y)); has_current_point = true; }
+            PathOp::Line(x, y)                      => { let (x, y) = point((*x, *y)); transformed_path.push(PathOp::Line(x, y)); has_current_point = true; }
+            PathOp::BezierCurve((cp1, cp2), end)    => { transformed_path.push(PathOp::BezierCurve((point(*cp1), point(*cp2)), point(*end))); has_current_point = true; }
+            PathOp::QuadraticCurve(cp, end)         => { transformed_path.push(PathOp::QuadraticCurve(point(*cp), point(*end))); has_current_point = true; }
+            PathOp::ClosePath                       => { transformed_path.push(PathOp::ClosePath); }
+
+            PathOp::Arc(center, radii, rotation, start_angle, sweep_angle) => {
+                // Arcs can't be transformed directly (the transform might skew them), so they're converted to bezier curves
+                let (x, y) = point(arc_point(*center, *radii, *rotation, *start_angle));
+
+                if has_current_point {
+                    transformed_path.push(PathOp::Line(x, y));
+                } else {
+                    transformed_path.push(PathOp::Move(x, y));
+                }
+
+                for (cp1, cp2, end) in arc_to_bezier_curves(*center, *radii, *rotation, *start_angle, *sweep_angle) {
+                    transformed_path.push(PathOp::BezierCurve((point(cp1), point(cp2)), point(end)));
+                }
+
+                has_current_point = true;
+            }
+        }
+    }
+
+    transformed_path
+}
+
+impl TransformFlattener {
+    ///
+    /// Creates a new transform flattener
+    ///
+    fn new() -> TransformFlattener {
+        TransformFlattener {
+            target:             FlattenTarget::Layer(LayerId(0)),
+            state:              FlattenState::default(),
+            target_state:       HashMap::new(),
+            canvas_transform:   CanvasTransform::new(),
+            current_path:       vec![],
+            stored_path:        vec![],
+            output_path:        None,
+            state_stack:        vec![],
+            sprites:            HashMap::new(),
+            sprite_depth:       0,
+        }
+    }
+
+    ///
+    /// Processes an instruction from the input stream, adding the resulting instructions to the output
+    ///
+    fn process(&mut self, drawing: Draw, output: &mut Vec<Draw>) {
+        // Record the instructions that make up each sprite so they can be drawn inline later on
+        if let FlattenTarget::Sprite(sprite_id) = self.target {
+            match &drawing {
+                Draw::Layer(_)                  |
+                Draw::Sprite(_)                 |
+                Draw::ClearCanvas(_)            => { }
+
+                Draw::ClearLayer                |
+                Draw::ClearSprite               => { self.sprites.insert(sprite_id, vec![]); }
+
+                Draw::MoveSpriteFrom(source_id) => {
+                    if *source_id != sprite_id {
+                        let source_drawing = self.sprites.remove(source_id).unwrap_or_default();
+                        self.sprites.insert(sprite_id, source_drawing);
+                    }
+                }
+
+                other                           => { self.sprites.entry(sprite_id).or_insert_with(|| vec![]).push(other.clone()); }
+            }
+        }
+
+        self.draw(drawing, output);
+    }
+
+    ///
+    /// Writes out the current path, transformed into the output coordinates (if it's not already in the output)
+    ///
+    fn write_path(&mut self, output: &mut Vec<Draw>) {
+        let transform = self.canvas_transform.transform();
+
+        if self.output_path != Some(transform) {
+            output.push(Draw::Path(PathOp::NewPath));
+            output.extend(transform_path(&self.current_path, &transform).into_iter().map(|path_op| Draw::Path(path_op)));
+
+            self.output_path = Some(transform);
+        }
+    }
+
+    ///
+    /// Writes out the fill for a `Fill` instruction, if it's a texture or a gradient
+    ///
+    fn write_fill(&mut self, output: &mut Vec<Draw>) {
+        if let Some(fill) = &self.state.fill {
+            // Fills are specified in drawing coordinates, so the canvas transform is applied after the fill transform to map them to the output coordinates
+            output.push(fill.clone());
+            output.push(Draw::FillTransform(self.canvas_transform.transform() * self.state.fill_transform));
+        }
+    }
+
+    ///
+    /// Writes out the line width, dash pattern and texture or gradient for a `Stroke` instruction, if they've changed
+    ///
+    fn write_stroke(&mut self, output: &mut Vec<Draw>) {
+        let scale = transform_scale(&self.canvas_transform.transform());
+
+        // Line width
+        if let Some(line_width) = self.state.line_width {
+            let line_width = line_width * scale;
+
+            if self.state.output_line_width != Some(line_width) {
+                output.push(Draw::LineWidth(line_width));
+                self.state.output_line_width = Some(line_width);
+            }
+        }
+
+        // Dash pattern
+        let dash_pattern = (self.state.dash_pattern.iter().map(|length| length * scale).collect::<Vec<_>>(), self.state.dash_offset * scale);
+
+        if self.state.output_dash_pattern.as_ref() != Some(&dash_pattern) {
+            output.push(Draw::NewDashPattern);
+            output.extend(dash_pattern.0.iter().map(|length| Draw::DashLength(*length)));
+            output.push(Draw::DashOffset(dash_pattern.1));
+
+            self.state.output_dash_pattern = Some(dash_pattern);
+        }
+
+        // Texture or gradient
+        let transform   = self.canvas_transform.transform();
+        let point       = |(x, y): (f32, f32)| transform.transform_point(x, y);
+
+        match &self.state.stroke {
+            Some(Draw::StrokeTexture(texture_id, p1, p2))   => { output.push(Draw::StrokeTexture(*texture_id, point(*p1), point(*p2))); }
+            Some(Draw::StrokeGradient(gradient_id, p1, p2)) => { output.push(Draw::StrokeGradient(*gradient_id, point(*p1), point(*p2))); }
+            _                                               => { }
+        }
+    }
+
+    ///
+    /// Draws the instructions for a sprite inline, with the current sprite transform applied
+    ///
+    fn draw_sprite_inline(&mut self, sprite_id: SpriteId, output: &mut Vec<Draw>) {
+        // Sprites that draw themselves are only followed to a limited depth
+        if self.sprite_depth >= MAX_SPRITE_DEPTH {
+            return;
+        }
+
+        let sprite_drawing = if let Some(sprite_drawing) = self.sprites.get(&sprite_id) { sprite_drawing.clone() } else { return; };
+
+        // Store the state (the sprite uses its own state, but the output values are the same as the values for the current state)
+        let old_transform   = self.canvas_transform.transform();
+        let old_state       = self.state.clone();
+        let old_path        = mem::take(&mut self.current_path);
+
+        self.canvas_transform.set_transform(old_transform * self.state.sprite_transform);
+        self.state          = FlattenState {
+            output_line_width:      self.state.output_line_width,
+            output_dash_pattern:    self.state.output_dash_pattern.clone(),
+            ..FlattenState::default()
+        };
+        self.output_path    = None;
+
+        // Sprites start with the default state
+        output.extend(vec![
+            Draw::PushState,
+            Draw::FillColor(Color::Rgba(0.0, 0.0, 0.0, 1.0)),
+            Draw::StrokeColor(Color::Rgba(0.0, 0.0, 0.0, 1.0)),
+            Draw::LineJoin(LineJoin::Round),
+            Draw::MiterLimit(4.0),
+            Draw::LineCap(LineCap::Butt),
+            Draw::WindingRule(WindingRule::NonZero),
+            Draw::BlendMode(BlendMode::SourceOver),
+        ]);
+
+        // Draw the sprite
+        self.sprite_depth += 1;
+
+        for drawing in sprite_drawing {
+            match drawing {
+                // Instructions that change the sprite itself or define resources have already been sent to the output when the sprite was defined
+                Draw::StartFrame            |
+                Draw::ShowFrame             |
+                Draw::ResetFrame            |
+                Draw::Texture(_, _)         |
+                Draw::Font(_, _)            |
+                Draw::Gradient(_, _)        |
+                Draw::Namespace(_)          |
+                Draw::LayerBlend(_, _)      |
+                Draw::LayerAlpha(_, _)      |
+                Draw::SwapLayers(_, _)      |
+                Draw::ClearAllLayers        |
+                Draw::FreeStoredBuffer      => { }
+
+                drawing                     => { self.draw(drawing, output); }
+            }
+        }
+
+        self.sprite_depth -= 1;
+
+        // Restore the state
+        output.push(Draw::PopState);
+
+        self.canvas_transform.set_transform(old_transform);
+        self.state          = old_state;
+        self.current_path   = old_path;
+        self.output_path    = None;
+    }
+
+    ///
+    /// Draws an instruction to the output, applying the current transform
+    ///
+    fn draw(&mut self, drawing: Draw, output: &mut Vec<Draw>) {
+        use self::Draw::*;
+
+        // Transforms only change how the coordinates are written out
+        if self.canvas_transform.update(&drawing) {
+            return;
+        }
+
+        match drawing {
+            // Paths are written out in output coordinates when they're used
+            Path(PathOp::NewPath)                   => { self.current_path = vec![]; self.output_path = None; }
+            Path(path_op)                           => { self.current_path.push(path_op); self.output_path = None; }
+            StorePath                               => { self.stored_path = self.current_path.clone(); }
+            CombinePath(operation)                  => { self.current_path = combine_paths(operation, self.state.winding_rule, &self.stored_path, &self.current_path); self.output_path = None; }
+
+            Fill                                    => { self.write_path(output); self.write_fill(output); output.push(Fill); }
+            Stroke                                  => { self.write_path(output); self.write_stroke(output); output.push(Stroke); }
+            Clip                                    => { self.write_path(output); output.push(Clip); }
+
+            // Line widths and dash patterns are written out when the path is stroked
+            LineWidth(width)                        => { self.state.line_width = Some(width); }
+            LineWidthPixels(width)                  => { self.state.line_width = None; self.state.output_line_width = None; output.push(LineWidthPixels(width)); }
+            NewDashPattern                          => { self.state.dash_pattern = vec![]; self.state.dash_offset = 0.0; }
+            DashLength(length)                      => { self.state.dash_pattern.push(length); }
+            DashOffset(offset)                      => { self.state.dash_offset = offset; }
+            WindingRule(winding_rule)               => { self.state.winding_rule = winding_rule; output.push(WindingRule(winding_rule)); }
+
+            // Textures and gradients are written out when the path is filled or stroked
+            FillColor(color)                        => { self.state.fill = None; self.state.fill_transform = Transform2D::identity(); output.push(FillColor(color)); }
+            FillTexture(_, _, _)                    |
+            FillGradient(_, _, _)                   |
+            FillRadialGradient(_, _, _, _)          |
+            FillConicGradient(_, _, _)              => { self.state.fill = Some(drawing); self.state.fill_transform = Transform2D::identity(); }
+            FillTransform(transform)                => { self.state.fill_transform = self.state.fill_transform * transform; }
+
+            StrokeColor(color)                      => { self.state.stroke = None; output.push(StrokeColor(color)); }
+            StrokeTexture(_, _, _)                  |
+            StrokeGradient(_, _, _)                 => { self.state.stroke = Some(drawing); }
+
+            // Text is drawn at a transformed position
+            DrawText(font_id, text, x, y)           => { let (x, y) = self.canvas_transform.transform().transform_point(x, y); output.push(DrawText(font_id, text, x, y)); }
+            BeginLineLayout(x, y, alignment)        => { let (x, y) = self.canvas_transform.transform().transform_point(x, y); output.push(BeginLineLayout(x, y, alignment)); }
+            BeginParagraphLayout(x, y, style)       => { let (x, y) = self.canvas_transform.transform().transform_point(x, y); output.push(BeginParagraphLayout(x, y, transform_paragraph_style(style, &self.canvas_transform.transform()))); }
+
+            // The state stack also stores the transform and the path
+            PushState                               => {
+                self.state_stack.push((self.canvas_transform.transform(), self.state.clone(), self.current_path.clone()));
+                output.push(PushState);
+            }
+
+            PopState                                => {
+                if let Some((transform, state, path)) = self.state_stack.pop() {
+                    self.canvas_transform.set_transform(transform);
+                    self.state          = state;
+                    self.current_path   = path;
+                }
+
+                self.output_path = None;
+                output.push(PopState);
+            }
+
+            // Layers and sprites
+            Layer(layer_id)                         => {
+                let new_state = self.target_state.remove(&FlattenTarget::Layer(layer_id)).unwrap_or_default();
+                self.target_state.insert(self.target, mem::replace(&mut self.state, new_state));
+                self.target = FlattenTarget::Layer(layer_id);
+
+                output.push(Layer(layer_id));
+            }
+
+            Sprite(sprite_id)                       => {
+                let new_state = self.target_state.remove(&FlattenTarget::Sprite(sprite_id)).unwrap_or_default();
+                self.target_state.insert(self.target, mem::replace(&mut self.state, new_state));
+                self.target = FlattenTarget::Sprite(sprite_id);
+
+                output.push(Sprite(sprite_id));
+            }
+
+            SpriteTransform(self::SpriteTransform::Identity)    => { self.state.sprite_transform = Transform2D::identity(); }
+            SpriteTransform(sprite_transform)                   => { self.state.sprite_transform = Transform2D::from(sprite_transform) * self.state.sprite_transform; }
+
+            DrawSprite(sprite_id)                   => { self.draw_sprite_inline(sprite_id, output); }
+
+            DrawSpriteWithFilters(sprite_id, filters) => {
+                // Filters are applied to the sprite as a whole, so these are drawn using the sprite transform instead of inline
+                let sprite_transform = self.canvas_transform.transform() * self.state.sprite_transform;
+
+                output.push(SpriteTransform(self::SpriteTransform::Identity));
+                output.push(SpriteTransform(self::SpriteTransform::Transform2D(sprite_transform)));
+                output.push(DrawSpriteWithFilters(sprite_id, filters));
+            }
+
+            ClearCanvas(color)                      => {
+                *self = TransformFlattener::new();
+                output.push(ClearCanvas(color));
+            }
+
+            drawing                                 => { output.push(drawing); }
+        }
+    }
+}
+
+///
+/// Converts a stream of drawing instructions so that all of the transforms are applied to the coordinates of the drawing
+///
+/// The result contains no `IdentityTransform`, `CanvasHeight`, `CenterRegion`, `MultiplyTransform` or `SpriteTransform` instructions,
+/// and its coordinates are in the scheme set up by `IdentityTransform`, where the view is 2.0 units high with (0, 0) at the center.
+/// This is useful for exporters and other consumers that can't deal with the transform stack.
+///
+/// Paths are written out when they're filled, stroked or used as a clipping path, and line widths, dash patterns, texture
+/// and gradient fills are all adjusted to match the transform. Arcs are converted to bezier curves. Widths set by
+/// `LineWidthPixels` are left alone.
+///
+/// Sprites drawn by `DrawSprite` are drawn inline, so the result doesn't depend on the sprite transform. `DrawSpriteWithFilters`
+/// has to render the sprite as a whole, so it's the one exception: it's preceded by a `SpriteTransform` that has the same effect
+/// as the transforms in the original drawing.
+///
+/// If the `outline-fonts` feature is enabled, text is converted to paths so that it's transformed correctly: otherwise
+/// only the position of the text is transformed.
+///
+pub fn drawing_without_transforms<InStream: 'static+Send+Unpin+Stream<Item=Draw>>(draw_stream: InStream) -> impl Send+Unpin+Stream<Item=Draw> {
+    // Text needs to be converted to paths to be transformed
+    #[cfg(feature = "outline-fonts")]
+    let draw_stream = drawing_with_text_as_paths(drawing_with_laid_out_text(draw_stream));
+
+    generator_stream(move |yield_value| async move {
+        let mut draw_stream = draw_stream;
+        let mut flattener   = TransformFlattener::new();
+
+        while let Some(drawing) = draw_stream.next().await {
+            let mut output = vec![];
+            flattener.process(drawing, &mut output);
+
+            for drawing in output {
+                yield_value(drawing).await;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::context::*;
+    use crate::primitives::*;
+
+    use futures::stream;
+    use futures::executor;
+
+    fn flatten(drawing: Vec<Draw>) -> Vec<Draw> {
+        executor::block_on(drawing_without_transforms(stream::iter(drawing.into_iter())).collect())
+    }
+
+    fn is_transform(drawing: &Draw) -> bool {
+        match drawing {
+            Draw::IdentityTransform         |
+            Draw::CanvasHeight(_)           |
+            Draw::CenterRegion(_, _)        |
+            Draw::MultiplyTransform(_)      |
+            Draw::SpriteTransform(_)        => true,
+            _                               => false
+        }
+    }
+
+    fn points(drawing: &[Draw]) -> Vec<(f32, f32)> {
+        drawing.iter()
+            .flat_map(|drawing| match drawing {
+                Draw::Path(PathOp::Move(x, y))  |
+                Draw::Path(PathOp::Line(x, y))  => vec![(*x, *y)],
+                _                               => vec![]
+            })
+            .collect()
+    }
+
+    fn points_near(points: Vec<(f32, f32)>, expected: Vec<(f32, f32)>) -> bool {
+        points.len() == expected.len() && points.iter().zip(expected.iter()).all(|((x1, y1), (x2, y2))| (x1-x2).abs() < 0.001 && (y1-y2).abs() < 0.001)
+    }
+
+    #[test]
+    fn translated_rectangle() {
+        let mut drawing = vec![];
+        drawing.transform(Transform2D::translate(10.0, 20.0));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 100.0, 100.0);
+        drawing.fill();
+
+        let flattened = flatten(drawing);
+
+        assert!(!flattened.iter().any(is_transform));
+        assert!(points_near(points(&flattened), vec![(10.0, 20.0), (10.0, 120.0), (110.0, 120.0), (110.0, 20.0), (10.0, 20.0)]));
+        assert!(flattened.last() == Some(&Draw::Fill));
+    }
+
+    #[test]
+    fn canvas_height_and_center_region() {
+        let mut drawing = vec![];
+        drawing.canvas_height(1000.0);
+        drawing.center_region(0.0, 0.0, 1000.0, 1000.0);
+        drawing.new_path();
+        drawing.move_to(0.0, 0.0);
+        drawing.line_to(1000.0, 1000.0);
+        drawing.stroke();
+
+        let flattened = flatten(drawing);
+
+        assert!(!flattened.iter().any(is_transform));
+        assert!(points_near(points(&flattened), vec![(-1.0, -1.0), (1.0, 1.0)]));
+    }
+
+    #[test]
+    fn transform_applies_when_path_is_drawn() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.move_to(1.0, 1.0);
+        drawing.line_to(2.0, 2.0);
+        drawing.transform(Transform2D::scale(2.0, 2.0));
+        drawing.stroke();
+
+        let flattened = flatten(drawing);
+
+        assert!(points_near(points(&flattened), vec![(2.0, 2.0), (4.0, 4.0)]));
+    }
+
+    #[test]
+    fn line_width_is_scaled() {
+        let mut drawing = vec![];
+        drawing.transform(Transform2D::scale(3.0, 3.0));
+        drawing.line_width(2.0);
+        drawing.new_path();
+        drawing.move_to(0.0, 0.0);
+        drawing.line_to(10.0, 0.0);
+        drawing.stroke();
+
+        let flattened = flatten(drawing);
+
+        assert!(flattened.contains(&Draw::LineWidth(6.0)));
+        assert!(!flattened.contains(&Draw::LineWidth(2.0)));
+    }
+
+    #[test]
+    fn dash_pattern_is_scaled() {
+        let mut drawing = vec![];
+        drawing.transform(Transform2D::scale(2.0, 2.0));
+        drawing.new_dash_pattern();
+        drawing.dash_length(5.0);
+        drawing.dash_length(10.0);
+        drawing.new_path();
+        drawing.move_to(0.0, 0.0);
+        drawing.line_to(100.0, 0.0);
+        drawing.stroke();
+
+        let flattened = flatten(drawing);
+
+        assert!(flattened.contains(&Draw::DashLength(10.0)));
+        assert!(flattened.contains(&Draw::DashLength(20.0)));
+        assert!(!flattened.contains(&Draw::DashLength(5.0)));
+    }
+
+    #[test]
+    fn gradient_fill_is_transformed() {
+        let mut drawing = vec![];
+        drawing.transform(Transform2D::translate(100.0, 0.0));
+        drawing.fill_gradient(GradientId(0), 0.0, 0.0, 10.0, 0.0);
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 10.0);
+        drawing.fill();
+
+        let flattened = flatten(drawing);
+
+        assert!(flattened.contains(&Draw::FillGradient(GradientId(0), (0.0, 0.0), (10.0, 0.0))));
+        assert!(flattened.contains(&Draw::FillTransform(Transform2D::translate(100.0, 0.0))));
+    }
+
+    #[test]
+    fn fill_transform_is_applied_before_canvas_transform() {
+        let mut drawing = vec![];
+        drawing.transform(Transform2D::scale(2.0, 2.0));
+        drawing.fill_gradient(GradientId(0), 0.0, 0.0, 10.0, 0.0);
+        drawing.fill_transform(Transform2D::translate(5.0, 0.0));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 10.0);
+        drawing.fill();
+
+        let flattened       = flatten(drawing);
+        let fill_transform  = flattened.iter()
+            .filter_map(|draw| match draw { Draw::FillTransform(transform) => Some(*transform), _ => None })
+            .last()
+            .unwrap();
+
+        // The start of the gradient is moved by the fill transform to (5, 0), then scaled to (10, 0) by the canvas transform
+        let (x, y) = fill_transform.transform_point(0.0, 0.0);
+        assert!((x-10.0).abs() < 0.01, "{:?}", (x, y));
+        assert!(y.abs() < 0.01, "{:?}", (x, y));
+    }
+
+    #[test]
+    fn push_and_pop_restore_transform() {
+        let mut drawing = vec![];
+        drawing.push_state();
+        drawing.transform(Transform2D::translate(100.0, 0.0));
+        drawing.pop_state();
+        drawing.new_path();
+        drawing.move_to(0.0, 0.0);
+        drawing.line_to(10.0, 0.0);
+        drawing.stroke();
+
+        let flattened = flatten(drawing);
+
+        assert!(points_near(points(&flattened), vec![(0.0, 0.0), (10.0, 0.0)]));
+    }
+
+    #[test]
+    fn sprites_are_drawn_inline() {
+        let mut drawing = vec![];
+        drawing.sprite(SpriteId(0));
+        drawing.new_path();
+        drawing.move_to(0.0, 0.0);
+        drawing.line_to(10.0, 0.0);
+        drawing.stroke();
+
+        drawing.layer(LayerId(0));
+        drawing.transform(Transform2D::scale(2.0, 2.0));
+        drawing.sprite_transform(SpriteTransform::Translate(100.0, 0.0));
+        drawing.draw_sprite(SpriteId(0));
+
+        let flattened = flatten(drawing);
+
+        assert!(!flattened.iter().any(is_transform));
+        assert!(!flattened.iter().any(|drawing| if let Draw::DrawSprite(_) = drawing { true } else { false }));
+
+        // Sprite definition, then the inline copy
+        assert!(points_near(points(&flattened), vec![(0.0, 0.0), (10.0, 0.0), (200.0, 0.0), (220.0, 0.0)]));
+        assert!(flattened.contains(&Draw::LineWidth(2.0)));
+    }
+
+    #[test]
+    fn paragraph_style_is_scaled() {
+        let mut drawing = vec![];
+        drawing.transform(Transform2D::scale(2.0, 2.0));
+        drawing.begin_paragraph_layout(10.0, 20.0, ParagraphStyle::new(100.0).with_paragraph_spacing(5.0).with_overflow(TextOverflow::Ellipsis(50.0)));
+
+        // Text layouts are replaced by glyphs when outline fonts are enabled, so this uses the flattener directly
+        let mut flattener = TransformFlattener::new();
+        let mut flattened = vec![];
+        for draw in drawing {
+            flattener.process(draw, &mut flattened);
+        }
+
+        assert!(flattened.contains(&Draw::BeginParagraphLayout(20.0, 40.0, ParagraphStyle::new(200.0).with_paragraph_spacing(10.0).with_overflow(TextOverflow::Ellipsis(100.0)))), "{:?}", flattened);
+    }
+
+    #[test]
+    fn arcs_are_converted_to_curves() {
+        let mut drawing = vec![];
+        drawing.transform(Transform2D::scale(2.0, 1.0));
+        drawing.new_path();
+        drawing.arc_to(0.0, 0.0, 10.0, 10.0, 0.0, 0.0, 2.0 * std::f32::consts::PI);
+        drawing.fill();
+
+        let flattened = flatten(drawing);
+
+        assert!(!flattened.iter().any(|drawing| if let Draw::Path(PathOp::Arc(..)) = drawing { true } else { false }));
+        assert!(points_near(points(&flattened), vec![(20.0, 0.0)]));
+    }
+}
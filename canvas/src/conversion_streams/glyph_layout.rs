@@ -1,7 +1,9 @@
 use crate::draw::*;
 use crate::font::*;
+use crate::font_face::*;
 use crate::namespace::*;
 use crate::font_line_layout::*;
+use crate::font_paragraph_layout::*;
 
 use flo_stream::*;
 
@@ -12,6 +14,17 @@ use std::iter;
 use std::sync::*;
 use std::collections::{HashMap};
 
+///
+/// Looks up the fallback fonts that have been set for a font ID
+///
+fn fallback_fonts(font_map: &HashMap<(usize, FontId), Arc<CanvasFontFace>>, font_fallbacks: &HashMap<(usize, FontId), Vec<FontId>>, namespace_id: usize, font_id: FontId) -> Vec<(FontId, Arc<CanvasFontFace>)> {
+    font_fallbacks.get(&(namespace_id, font_id))
+        .into_iter()
+        .flatten()
+        .flat_map(|fallback_id| font_map.get(&(namespace_id, *fallback_id)).map(|font| (*fallback_id, Arc::clone(font))))
+        .collect()
+}
+
 ///
 /// Given a stream with font instructions, replaces any layout instruction (eg, `Draw::DrawText()`) with glyph
 /// rendering instructions
@@ -28,8 +41,11 @@ where
         let mut namespace_stack     = vec![];
         let mut font_map            = HashMap::new();
         let mut font_size           = HashMap::new();
+        let mut font_fallbacks      = HashMap::new();
         let mut current_line        = None;
         let mut current_font        = None;
+        let mut current_paragraph   = None;
+        let mut deferred_font_ops   = vec![];
         let (mut x_pos, mut y_pos)  = (0.0, 0.0);
         let mut alignment           = TextAlignment::Left;
 
@@ -55,8 +71,13 @@ where
 
                 Draw::Font(font_id, FontOp::UseFontDefinition(font_defn)) => {
                     // Defining new fonts interrupts any existing text layout
-                    current_line = None;
-                    current_font = None;
+                    current_line        = None;
+                    current_font        = None;
+                    current_paragraph   = None;
+
+                    for font_op in mem::take(&mut deferred_font_ops) {
+                        yield_value(font_op).await;
+                    }
 
                     // Store this font definition
                     font_map.insert((namespace_id, font_id), Arc::clone(&font_defn));
@@ -82,10 +103,54 @@ where
                     yield_value(Draw::Font(font_id, FontOp::FontSize(new_size))).await;
                 }
 
+                Draw::Font(font_id, FontOp::UseFallbackFonts(fallback_ids)) => {
+                    // The fallback fonts are picked up when the font is next used for laying out text
+                    font_fallbacks.insert((namespace_id, font_id), fallback_ids.clone());
+
+                    yield_value(Draw::Font(font_id, FontOp::UseFallbackFonts(fallback_ids))).await;
+                }
+
+                Draw::Font(font_id, font_op @ FontOp::FontFeature(_, _))    |
+                Draw::Font(font_id, font_op @ FontOp::FontVariation(_, _))  => {
+                    // Text that's laid out after this uses the font with the new feature or variation setting
+                    if let Some(font) = font_map.get_mut(&(namespace_id, font_id)) {
+                        *font = match &font_op {
+                            FontOp::FontFeature(tag, enabled)   => font.with_feature(*tag, *enabled),
+                            FontOp::FontVariation(tag, value)   => font.with_variation(*tag, *value),
+                            _                                   => Arc::clone(font)
+                        };
+
+                        // If we're changing the active font, restart the layout with the updated font
+                        if current_font == Some(font_id) {
+                            let new_font    = Arc::clone(font);
+                            let em_size     = font_size.get(&font_id).copied().unwrap_or(12.0);
+
+                            current_line = current_line
+                                .map(|line: CanvasFontLineLayout| line.continue_with_new_font(font_id, &new_font, em_size));
+                        }
+                    }
+
+                    if current_line.is_some() || current_paragraph.is_some() {
+                        // The glyphs in the current layout are drawn when the layout finishes, so the setting is changed between the glyphs
+                        // that were laid out before and after it (it's sent again once the layout finishes, in case it's clipped or discarded)
+                        current_line.as_mut().map(|line| line.draw(iter::once(Draw::Font(font_id, font_op.clone()))));
+                        current_paragraph.as_mut().map(|paragraph| paragraph.draw(iter::once(Draw::Font(font_id, font_op.clone()))));
+
+                        deferred_font_ops.push(Draw::Font(font_id, font_op));
+                    } else {
+                        yield_value(Draw::Font(font_id, font_op)).await;
+                    }
+                }
+
                 Draw::BeginLineLayout(x, y, align)   => {
                     // If we're laying out text already, this discards that layout
-                    current_line    = None;
-                    current_font    = None;
+                    current_line        = None;
+                    current_font        = None;
+                    current_paragraph   = None;
+
+                    for font_op in mem::take(&mut deferred_font_ops) {
+                        yield_value(font_op).await;
+                    }
 
                     // Set up the layout for the next set of text
                     x_pos           = x;
@@ -93,6 +158,25 @@ where
                     alignment       = align;
                 }
 
+                Draw::BeginParagraphLayout(x, y, style) => {
+                    // If we're laying out text already, this discards that layout
+                    current_line        = None;
+                    current_font        = None;
+                    current_paragraph   = Some(CanvasFontParagraphLayout::new(x, y, style));
+
+                    for font_op in mem::take(&mut deferred_font_ops) {
+                        yield_value(font_op).await;
+                    }
+                }
+
+                Draw::Font(font_id, FontOp::LayoutText(text)) if current_paragraph.is_some() => {
+                    // Paragraphs store the font along with the text, so they can be laid out once all of the text is known
+                    if let (Some(font), Some(font_size), Some(paragraph)) = (font_map.get(&(namespace_id, font_id)), font_size.get(&font_id), current_paragraph.as_mut()) {
+                        paragraph.set_fallback_fonts(font_id, fallback_fonts(&font_map, &font_fallbacks, namespace_id, font_id));
+                        paragraph.add_text(font_id, font, *font_size, &text);
+                    }
+                }
+
                 Draw::Font(font_id, FontOp::LayoutText(text)) => {
                     // Update the current font
                     if current_font != Some(font_id) {
@@ -108,6 +192,10 @@ where
                                     Some(CanvasFontLineLayout::new(&new_font, font_size))
                                 });
                             current_font = Some(font_id);
+
+                            if let Some(line) = &mut current_line {
+                                line.set_fallback_fonts(fallback_fonts(&font_map, &font_fallbacks, namespace_id, font_id));
+                            }
                         }
                     }
 
@@ -116,6 +204,13 @@ where
                 }
 
                 Draw::DrawLaidOutText => {
+                    if let Some(paragraph) = mem::take(&mut current_paragraph) {
+                        // Paragraphs are wrapped and aligned when they're drawn
+                        for draw in paragraph.to_drawing() {
+                            yield_value(draw).await;
+                        }
+                    }
+
                     if let Some(layout) = mem::take(&mut current_line) {
                         // Align the layout
                         let mut layout = layout;
@@ -130,6 +225,10 @@ where
                             }
                         }
                     }
+
+                    for font_op in mem::take(&mut deferred_font_ops) {
+                        yield_value(font_op).await;
+                    }
                 },
 
                 Draw::FillColor(fill_color) => {
@@ -137,6 +236,9 @@ where
                     if let Some(current_line) = &mut current_line {
                         current_line.draw(iter::once(Draw::FillColor(fill_color.clone())));
                     }
+                    if let Some(current_paragraph) = &mut current_paragraph {
+                        current_paragraph.draw(iter::once(Draw::FillColor(fill_color.clone())));
+                    }
 
                     yield_value(Draw::FillColor(fill_color)).await;
                 },
@@ -145,6 +247,7 @@ where
                     if let (Some(font), Some(font_size)) = (font_map.get(&(namespace_id, font_id)), font_size.get(&font_id)) {
                         // This is just a straightforward immediate layout of the text as glyphs
                         let mut layout = CanvasFontLineLayout::new(font, *font_size);
+                        layout.set_fallback_fonts(fallback_fonts(&font_map, &font_fallbacks, namespace_id, font_id));
 
                         // Lay out the text
                         layout.add_text(&text);
@@ -162,34 +265,52 @@ where
 
                 Draw::Layer(_) => {
                     // These instructions interrupt text layout
-                    current_line = None;
-                    current_font = None;
+                    current_line        = None;
+                    current_font        = None;
+                    current_paragraph   = None;
+
+                    for font_op in mem::take(&mut deferred_font_ops) {
+                        yield_value(font_op).await;
+                    }
 
                     yield_value(draw).await;
                 }
 
                 Draw::Sprite(_) => {
                     // These instructions interrupt text layout
-                    current_line = None;
-                    current_font = None;
+                    current_line        = None;
+                    current_font        = None;
+                    current_paragraph   = None;
+
+                    for font_op in mem::take(&mut deferred_font_ops) {
+                        yield_value(font_op).await;
+                    }
 
                     yield_value(draw).await;
                 }
 
                 Draw::ClearLayer => {
                     // These instructions interrupt text layout
-                    current_line = None;
-                    current_font = None;
+                    current_line        = None;
+                    current_font        = None;
+                    current_paragraph   = None;
+
+                    for font_op in mem::take(&mut deferred_font_ops) {
+                        yield_value(font_op).await;
+                    }
 
                     yield_value(draw).await;
                 }
 
                 Draw::ClearCanvas(_) => {
                     // Clear state
-                    font_map        = HashMap::new();
-                    current_line    = None;
-                    current_font    = None;
-                    namespace_id    = NamespaceId::default().local_id();
+                    font_map            = HashMap::new();
+                    font_fallbacks      = HashMap::new();
+                    current_line        = None;
+                    current_font        = None;
+                    current_paragraph   = None;
+                    deferred_font_ops   = vec![];
+                    namespace_id        = NamespaceId::default().local_id();
 
                     yield_value(draw).await;
                 }
@@ -357,4 +478,155 @@ mod test {
             }
         });
     }
+
+    #[test]
+    fn layout_paragraph() {
+        executor::block_on(async {
+            let lato            = CanvasFontFace::from_slice(include_bytes!("../../test_data/Lato-Regular.ttf"));
+
+            let instructions    = vec![
+                Draw::Font(FontId(1), FontOp::UseFontDefinition(lato)),
+                Draw::Font(FontId(1), FontOp::FontSize(100.0)),
+                Draw::BeginParagraphLayout(500.0, 500.0, ParagraphStyle::new(400.0)),
+                Draw::Font(FontId(1), FontOp::LayoutText("Hello, world".to_string())),
+                Draw::DrawLaidOutText
+            ];
+            let instructions    = stream::iter(instructions);
+            let instructions    = drawing_with_laid_out_text(instructions);
+
+            let instructions    = instructions.collect::<Vec<_>>().await;
+
+            // 'Hello, world' is too wide for the paragraph, so it should be wrapped onto two lines below the top of the paragraph
+            let glyphs          = instructions.iter()
+                .flat_map(|draw| match draw {
+                    Draw::Font(FontId(1), FontOp::DrawGlyphs(glyphs))   => glyphs.clone(),
+                    _                                                   => vec![]
+                })
+                .collect::<Vec<_>>();
+
+            assert!(glyphs.len() == "Hello,world".len(), "{:?}", glyphs);
+            assert!(glyphs[0].location.1 < 500.0);
+            assert!(glyphs[0].location.1 == glyphs[5].location.1);
+            assert!(glyphs[6].location.1 < glyphs[5].location.1);
+            assert!((glyphs[0].location.0 - 500.0).abs() < 1.0 && (glyphs[6].location.0 - 500.0).abs() < 1.0);
+        });
+    }
+
+    #[test]
+    fn layout_with_ligatures_turned_off() {
+        executor::block_on(async {
+            let lato            = CanvasFontFace::from_slice(include_bytes!("../../test_data/Lato-Regular.ttf"));
+
+            let instructions    = vec![
+                Draw::Font(FontId(1), FontOp::UseFontDefinition(lato)),
+                Draw::Font(FontId(1), FontOp::FontSize(100.0)),
+                Draw::BeginLineLayout(500.0, 500.0, TextAlignment::Left),
+                Draw::Font(FontId(1), FontOp::LayoutText("fi".to_string())),
+                Draw::Font(FontId(1), FontOp::FontFeature(*b"liga", false)),
+                Draw::Font(FontId(1), FontOp::LayoutText("fi".to_string())),
+                Draw::DrawLaidOutText
+            ];
+            let instructions    = stream::iter(instructions);
+            let instructions    = drawing_with_laid_out_text(instructions);
+
+            let instructions    = instructions.collect::<Vec<_>>().await;
+
+            // The feature instruction is passed on so later stages can use the same font settings
+            assert!(instructions.iter().any(|draw| draw == &Draw::Font(FontId(1), FontOp::FontFeature(*b"liga", false))));
+
+            // The first 'fi' is drawn as a ligature and the second as two separate glyphs
+            let glyphs          = instructions.iter()
+                .flat_map(|draw| match draw {
+                    Draw::Font(FontId(1), FontOp::DrawGlyphs(glyphs))   => glyphs.clone(),
+                    _                                                   => vec![]
+                })
+                .collect::<Vec<_>>();
+
+            assert!(glyphs.len() == 3, "{:?}", glyphs);
+        });
+    }
+
+    #[test]
+    fn variation_changes_between_glyphs() {
+        executor::block_on(async {
+            let variable_test   = CanvasFontFace::from_slice(include_bytes!("../../test_data/VariableTest.ttf"));
+
+            let instructions    = vec![
+                Draw::Font(FontId(1), FontOp::UseFontDefinition(variable_test)),
+                Draw::Font(FontId(1), FontOp::FontSize(100.0)),
+                Draw::BeginLineLayout(0.0, 0.0, TextAlignment::Left),
+                Draw::Font(FontId(1), FontOp::LayoutText("aa".to_string())),
+                Draw::Font(FontId(1), FontOp::FontVariation(*b"wght", 900.0)),
+                Draw::Font(FontId(1), FontOp::LayoutText("aa".to_string())),
+                Draw::DrawLaidOutText
+            ];
+            let instructions    = stream::iter(instructions);
+            let instructions    = drawing_with_laid_out_text(instructions);
+
+            let instructions    = instructions.collect::<Vec<_>>().await;
+
+            // The glyphs laid out before the variation changes are drawn before the variation instruction, so later stages draw them with the original outlines
+            let variation_pos   = instructions.iter().position(|draw| draw == &Draw::Font(FontId(1), FontOp::FontVariation(*b"wght", 900.0))).unwrap();
+            let glyph_runs      = instructions.iter().enumerate()
+                .flat_map(|(pos, draw)| match draw {
+                    Draw::Font(FontId(1), FontOp::DrawGlyphs(glyphs))   => Some((pos, glyphs.clone())),
+                    _                                                   => None
+                })
+                .collect::<Vec<_>>();
+
+            assert!(glyph_runs.len() == 2, "{:?}", instructions);
+            assert!(glyph_runs[0].0 < variation_pos && glyph_runs[1].0 > variation_pos, "{:?}", instructions);
+
+            // The advance is 1000 units at the default weight and 1500 units at the maximum weight
+            let locations       = glyph_runs.iter().flat_map(|(_, glyphs)| glyphs.iter().map(|glyph| glyph.location.0)).collect::<Vec<_>>();
+            assert!((locations[1] - 100.0).abs() < 0.1, "{:?}", locations);
+            assert!((locations[2] - 200.0).abs() < 0.1, "{:?}", locations);
+            assert!((locations[3] - 350.0).abs() < 0.1, "{:?}", locations);
+        });
+    }
+
+    #[test]
+    fn layout_with_fallback_font() {
+        executor::block_on(async {
+            let lato            = CanvasFontFace::from_slice(include_bytes!("../../test_data/Lato-Regular.ttf"));
+            let dejavu          = CanvasFontFace::from_slice(include_bytes!("../../test_data/DejaVuSans-Hebrew.ttf"));
+            let shin_id         = GlyphId(dejavu.ttf_font().glyph_index('\u{05e9}').unwrap().0 as u32);
+
+            // Lato has no Hebrew glyphs, but DejaVu Sans does
+            assert!(lato.ttf_font().glyph_index('\u{05e9}').is_none());
+
+            let instructions    = vec![
+                Draw::Font(FontId(1), FontOp::UseFontDefinition(lato)),
+                Draw::Font(FontId(2), FontOp::UseFontDefinition(dejavu)),
+                Draw::Font(FontId(1), FontOp::FontSize(100.0)),
+                Draw::Font(FontId(1), FontOp::UseFallbackFonts(vec![FontId(2)])),
+                Draw::BeginLineLayout(500.0, 500.0, TextAlignment::Left),
+                Draw::Font(FontId(1), FontOp::LayoutText("a \u{05e9}".to_string())),
+                Draw::DrawLaidOutText
+            ];
+            let instructions    = stream::iter(instructions);
+            let instructions    = drawing_with_laid_out_text(instructions);
+
+            let instructions    = instructions.collect::<Vec<_>>().await;
+
+            // The Hebrew letter is drawn using the fallback font, and the rest of the text is drawn using the main font
+            let fallback_glyphs = instructions.iter()
+                .flat_map(|draw| match draw {
+                    Draw::Font(FontId(2), FontOp::DrawGlyphs(glyphs))   => glyphs.clone(),
+                    _                                                   => vec![]
+                })
+                .collect::<Vec<_>>();
+            let main_glyphs     = instructions.iter()
+                .flat_map(|draw| match draw {
+                    Draw::Font(FontId(1), FontOp::DrawGlyphs(glyphs))   => glyphs.clone(),
+                    _                                                   => vec![]
+                })
+                .collect::<Vec<_>>();
+
+            assert!(fallback_glyphs.len() == 1, "{:?}", instructions);
+            assert!(fallback_glyphs[0].id == shin_id, "{:?}", fallback_glyphs);
+            assert!(fallback_glyphs[0].em_size == 100.0, "{:?}", fallback_glyphs);
+            assert!(main_glyphs.len() == 2, "{:?}", instructions);
+        });
+    }
 }
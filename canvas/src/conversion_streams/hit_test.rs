@@ -0,0 +1,271 @@
+use super::drawing_shapes::*;
+
+use crate::draw::*;
+use crate::sprite::*;
+
+use std::collections::{HashMap};
+
+///
+/// The part of a shape that was found by a hit test
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DrawingHitType {
+    /// The point is inside the area filled by a `Fill` instruction
+    Fill,
+
+    /// The point is inside the line drawn by a `Stroke` instruction
+    Stroke,
+}
+
+///
+/// Describes a shape found by `drawing_hit_test()`
+///
+#[derive(Clone, PartialEq, Debug)]
+pub struct DrawingHit {
+    /// The index in the drawing of the `Fill` or `Stroke` instruction that drew the shape
+    pub draw_index: usize,
+
+    /// Whether the fill or the stroke of the shape was hit
+    pub hit_type: DrawingHitType,
+
+    /// The layer that the shape appears on
+    pub layer: LayerId,
+
+    /// If the shape was drawn as part of a sprite, the sprite IDs and the indexes of the `DrawSprite` instructions that drew them (outermost sprite first)
+    pub sprites: Vec<(SpriteId, usize)>,
+}
+
+///
+/// Adds the hits for a list of entities to a list of results (topmost entity first)
+///
+fn hit_test_entities(entities: &[DrawnEntity], point: (f32, f32), layer: LayerId, sprites: &Vec<(SpriteId, usize)>, all_entities: &HashMap<ShapeTarget, Vec<DrawnEntity>>, hits: &mut Vec<DrawingHit>) {
+    for entity in entities.iter().rev() {
+        match entity {
+            DrawnEntity::Shape { draw_index, shape_type, region, clip } => {
+                if clip.iter().all(|clip_region| clip_region.contains(point)) && region.contains(point) {
+                    hits.push(DrawingHit {
+                        draw_index: *draw_index,
+                        hit_type:   match shape_type { ShapeType::Fill => DrawingHitType::Fill, ShapeType::Stroke => DrawingHitType::Stroke },
+                        layer:      layer,
+                        sprites:    sprites.clone(),
+                    });
+                }
+            }
+
+            DrawnEntity::Sprite { draw_index, sprite_id, inverse_transform, clip } => {
+                // Sprites that (directly or indirectly) draw themselves are not followed
+                if sprites.len() >= MAX_SPRITE_DEPTH || sprites.iter().any(|(parent_id, _)| parent_id == sprite_id) {
+                    continue;
+                }
+
+                if !clip.iter().all(|clip_region| clip_region.contains(point)) {
+                    continue;
+                }
+
+                if let Some(sprite_entities) = all_entities.get(&ShapeTarget::Sprite(*sprite_id)) {
+                    let sprite_point    = inverse_transform.transform_point(point.0, point.1);
+                    let mut sprites     = sprites.clone();
+                    sprites.push((*sprite_id, *draw_index));
+
+                    hit_test_entities(sprite_entities, sprite_point, layer, &sprites, all_entities, hits);
+                }
+            }
+        }
+    }
+}
+
+///
+/// Finds the shapes in a drawing that contain a point, returning the topmost shape first
+///
+/// The point is in canvas coordinates: that is, the coordinate scheme set up by `CanvasHeight` or `IdentityTransform`, with any
+/// changes made by `MultiplyTransform` applied to the shapes that are tested. Sprites are tested using the sprite transform that
+/// was active when they were drawn, and will return a hit for each shape within the sprite that contains the point.
+///
+/// Fills are tested using the winding rule that was active when they were drawn, and strokes are tested using the line width, join,
+/// cap and miter limit settings. Dash patterns are ignored, so points in the gaps of a dashed line are considered to be within the
+/// line. Widths set by `LineWidthPixels` depend on the size of the window, so they're treated as widths in canvas units here. Text
+/// is not tested.
+///
+pub fn drawing_hit_test<DrawIter: IntoIterator<Item=Draw>>(drawing: DrawIter, point: (f32, f32)) -> Vec<DrawingHit> {
+    let shapes      = drawn_shapes(drawing);
+
+    // Test the layers from the top down
+    let mut hits    = vec![];
+
+    for layer_id in shapes.layer_order.into_iter().rev() {
+        if let Some(layer_entities) = shapes.entities.get(&ShapeTarget::Layer(layer_id)) {
+            hit_test_entities(layer_entities, point, layer_id, &vec![], &shapes.entities, &mut hits);
+        }
+    }
+
+    hits
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::context::*;
+    use crate::primitives::*;
+    use crate::transform2d::*;
+
+    #[test]
+    fn hit_filled_rectangle() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 100.0, 100.0);
+        drawing.fill();
+
+        let hits = drawing_hit_test(drawing.clone(), (50.0, 50.0));
+        assert!(hits.len() == 1);
+        assert!(hits[0].hit_type == DrawingHitType::Fill);
+        assert!(hits[0].draw_index == drawing.len()-1);
+        assert!(hits[0].layer == LayerId(0));
+
+        let misses = drawing_hit_test(drawing, (150.0, 50.0));
+        assert!(misses.len() == 0);
+    }
+
+    #[test]
+    fn hit_stroke_outside_fill() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 100.0, 100.0);
+        drawing.line_width(10.0);
+        drawing.fill();
+        drawing.stroke();
+
+        let hits = drawing_hit_test(drawing.clone(), (103.0, 50.0));
+        assert!(hits.len() == 1);
+        assert!(hits[0].hit_type == DrawingHitType::Stroke);
+
+        let hits = drawing_hit_test(drawing, (98.0, 50.0));
+        assert!(hits.len() == 2);
+        assert!(hits[0].hit_type == DrawingHitType::Stroke);
+        assert!(hits[1].hit_type == DrawingHitType::Fill);
+    }
+
+    #[test]
+    fn hit_transformed_shape() {
+        let mut drawing = vec![];
+        drawing.transform(Transform2D::translate(200.0, 0.0));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 100.0, 100.0);
+        drawing.fill();
+
+        assert!(drawing_hit_test(drawing.clone(), (50.0, 50.0)).len() == 0);
+        assert!(drawing_hit_test(drawing, (250.0, 50.0)).len() == 1);
+    }
+
+    #[test]
+    fn even_odd_hole() {
+        let mut drawing = vec![];
+        drawing.winding_rule(WindingRule::EvenOdd);
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 100.0, 100.0);
+        drawing.rect(25.0, 25.0, 75.0, 75.0);
+        drawing.fill();
+
+        assert!(drawing_hit_test(drawing.clone(), (10.0, 10.0)).len() == 1);
+        assert!(drawing_hit_test(drawing, (50.0, 50.0)).len() == 0);
+    }
+
+    #[test]
+    fn clipped_shape() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 50.0, 100.0);
+        drawing.clip();
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 100.0, 100.0);
+        drawing.fill();
+
+        assert!(drawing_hit_test(drawing.clone(), (25.0, 50.0)).len() == 1);
+        assert!(drawing_hit_test(drawing, (75.0, 50.0)).len() == 0);
+    }
+
+    #[test]
+    fn layers_in_z_order() {
+        let mut drawing = vec![];
+        drawing.layer(LayerId(2));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 100.0, 100.0);
+        drawing.fill();
+        drawing.layer(LayerId(1));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 100.0, 100.0);
+        drawing.fill();
+
+        let hits = drawing_hit_test(drawing.clone(), (50.0, 50.0));
+        assert!(hits.len() == 2);
+        assert!(hits[0].layer == LayerId(2));
+        assert!(hits[1].layer == LayerId(1));
+
+        drawing.swap_layers(LayerId(1), LayerId(2));
+
+        let hits = drawing_hit_test(drawing, (50.0, 50.0));
+        assert!(hits[0].layer == LayerId(1));
+        assert!(hits[1].layer == LayerId(2));
+    }
+
+    #[test]
+    fn hit_sprite() {
+        let mut drawing = vec![];
+        drawing.sprite(SpriteId(0));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 10.0);
+        drawing.fill();
+        drawing.layer(LayerId(0));
+        drawing.sprite_transform(SpriteTransform::Translate(100.0, 100.0));
+        drawing.draw_sprite(SpriteId(0));
+
+        let hits = drawing_hit_test(drawing.clone(), (105.0, 105.0));
+        assert!(hits.len() == 1);
+        assert!(hits[0].sprites == vec![(SpriteId(0), drawing.len()-1)]);
+
+        assert!(drawing_hit_test(drawing, (5.0, 5.0)).len() == 0);
+    }
+
+    #[test]
+    fn sprite_transforms_are_applied_in_order() {
+        let mut drawing = vec![];
+        drawing.sprite(SpriteId(0));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 10.0);
+        drawing.fill();
+        drawing.layer(LayerId(0));
+        drawing.sprite_transform(SpriteTransform::Scale(2.0, 2.0));
+        drawing.sprite_transform(SpriteTransform::Translate(100.0, 100.0));
+        drawing.draw_sprite(SpriteId(0));
+
+        // The sprite is scaled and then moved, so it covers (100, 100) to (120, 120)
+        assert!(drawing_hit_test(drawing.clone(), (115.0, 115.0)).len() == 1);
+        assert!(drawing_hit_test(drawing.clone(), (125.0, 105.0)).len() == 0);
+        assert!(drawing_hit_test(drawing, (205.0, 205.0)).len() == 0);
+    }
+
+    #[test]
+    fn nested_sprite_transforms() {
+        let mut drawing = vec![];
+        drawing.sprite(SpriteId(0));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 10.0);
+        drawing.fill();
+        drawing.sprite(SpriteId(1));
+        drawing.sprite_transform(SpriteTransform::Translate(10.0, 0.0));
+        drawing.draw_sprite(SpriteId(0));
+        let inner_index = drawing.len()-1;
+        drawing.layer(LayerId(0));
+        drawing.sprite_transform(SpriteTransform::Identity);
+        drawing.sprite_transform(SpriteTransform::Scale(2.0, 2.0));
+        drawing.draw_sprite(SpriteId(1));
+        let outer_index = drawing.len()-1;
+
+        // The inner sprite is moved within the outer sprite, and then both are scaled, so the rectangle covers (20, 0) to (40, 20)
+        let hits = drawing_hit_test(drawing.clone(), (30.0, 5.0));
+        assert!(hits.len() == 1);
+        assert!(hits[0].sprites == vec![(SpriteId(1), outer_index), (SpriteId(0), inner_index)]);
+
+        assert!(drawing_hit_test(drawing.clone(), (5.0, 5.0)).len() == 0);
+        assert!(drawing_hit_test(drawing, (15.0, 5.0)).len() == 0);
+    }
+}
@@ -9,5 +9,18 @@ pub use self::path_stream::*;
 #[cfg(feature = "outline-fonts")] pub use self::outline_fonts::*;
 
 mod dashed_lines;
+mod stroke_outline;
+mod path_arithmetic;
+mod drawing_shapes;
+mod hit_test;
+mod drawing_bounds;
+mod flatten_transforms;
+mod redundant_state;
 
 pub use self::dashed_lines::*;
+pub use self::stroke_outline::*;
+pub use self::path_arithmetic::*;
+pub use self::hit_test::*;
+pub use self::drawing_bounds::*;
+pub use self::flatten_transforms::*;
+pub use self::redundant_state::*;
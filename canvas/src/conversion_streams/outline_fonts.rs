@@ -2,6 +2,7 @@ use crate::draw::*;
 use crate::path::*;
 use crate::font::*;
 use crate::namespace::*;
+use crate::font_face::*;
 
 use flo_stream::*;
 
@@ -14,7 +15,7 @@ use std::collections::{HashMap};
 /// Structure used to receive outlining instructions from FontKit
 ///
 struct FontOutliner<'a> {
-    drawing:        &'a mut Vec<Draw>,
+    drawing:        &'a mut Vec<PathOp>,
     scale_factor:   f32,
     x_pos:          f32,
     y_pos:          f32,
@@ -27,7 +28,7 @@ impl<'a> ttf_parser::OutlineBuilder for FontOutliner<'a> {
 
         self.last   = (x, y);
 
-        self.drawing.push(Draw::Path(PathOp::Move(self.x_pos + x, self.y_pos + y)));
+        self.drawing.push(PathOp::Move(self.x_pos + x, self.y_pos + y));
     }
 
     fn line_to(&mut self, x: f32, y: f32) {
@@ -35,7 +36,7 @@ impl<'a> ttf_parser::OutlineBuilder for FontOutliner<'a> {
 
         self.last   = (x, y);
 
-        self.drawing.push(Draw::Path(PathOp::Line(self.x_pos + x, self.y_pos + y)));
+        self.drawing.push(PathOp::Line(self.x_pos + x, self.y_pos + y));
     }
 
     fn quad_to(&mut self, cp_x1: f32, cp_y1: f32, to_x: f32, to_y:f32) {
@@ -52,11 +53,11 @@ impl<'a> ttf_parser::OutlineBuilder for FontOutliner<'a> {
         let (x2, y2)    = (x0q + (2.0/3.0) * (x2q-x0q), y0q + (2.0/3.0) * (y2q-y0q));
         let (x3, y3)    = (x1q + (2.0/3.0) * (x2q-x1q), y1q + (2.0/3.0) * (y2q-y1q));
 
-        self.drawing.push(Draw::Path(PathOp::BezierCurve(
+        self.drawing.push(PathOp::BezierCurve(
             ((self.x_pos + x2, self.y_pos + y2), 
              (self.x_pos + x3, self.y_pos + y3)),
             (self.x_pos + x1q, self.y_pos + y1q), 
-        )));
+        ));
     }
 
     fn curve_to(&mut self, cp_x1: f32, cp_y1: f32, cp_x2: f32, cp_y2: f32, to_x: f32, to_y: f32) {
@@ -70,18 +71,43 @@ impl<'a> ttf_parser::OutlineBuilder for FontOutliner<'a> {
 
         self.last       = (x1, y1);
 
-        self.drawing.push(Draw::Path(PathOp::BezierCurve(
+        self.drawing.push(PathOp::BezierCurve(
             ((self.x_pos + x2, self.y_pos + y2), 
              (self.x_pos + x3, self.y_pos + y3)),
             (self.x_pos + x1, self.y_pos + y1), 
-        )));
+        ));
     }
 
     fn close(&mut self) {
-        self.drawing.push(Draw::Path(PathOp::ClosePath));
+        self.drawing.push(PathOp::ClosePath);
     }
 }
 
+///
+/// Returns the path that outlines a glyph from a font face (using the face's variation settings)
+///
+pub (crate) fn glyph_outline(font: &CanvasFontFace, glyph: &GlyphPosition) -> Vec<PathOp> {
+    let ttf_font            = font.ttf_font();
+    let units_per_em        = ttf_font.units_per_em() as f32;
+
+    let GlyphId(glyph_id)   = glyph.id;
+    let glyph_id            = ttf_parser::GlyphId(glyph_id as _);
+
+    // Generate the outline
+    let mut path            = vec![];
+    let mut outliner        = FontOutliner { 
+        drawing:        &mut path,
+        scale_factor:   glyph.em_size / units_per_em,
+        x_pos:          glyph.location.0,
+        y_pos:          glyph.location.1,
+        last:           (0.0, 0.0)
+    };
+
+    ttf_font.outline_glyph(glyph_id, &mut outliner);
+
+    path
+}
+
 ///
 /// Given a stream of drawing instructions (such as is returned by `Canvas::stream()`), turns any glyph drawing instructions 
 /// into the equivalent path drawing instructions.
@@ -133,34 +159,30 @@ where
                     yield_value(Draw::Font(font_id, FontOp::UseFontDefinition(data))).await;
                 }
 
+                Draw::Font(font_id, font_op @ FontOp::FontFeature(_, _))    |
+                Draw::Font(font_id, font_op @ FontOp::FontVariation(_, _))  => {
+                    // Glyphs drawn after this use the new settings (variations change the outlines of the glyphs)
+                    if let Some(font) = font_map.get_mut(&(namespace_id, font_id)) {
+                        *font = match &font_op {
+                            FontOp::FontFeature(tag, enabled)   => font.with_feature(*tag, *enabled),
+                            FontOp::FontVariation(tag, value)   => font.with_variation(*tag, *value),
+                            _                                   => Arc::clone(font)
+                        };
+                    }
+
+                    yield_value(Draw::Font(font_id, font_op)).await;
+                }
+
                 Draw::Font(font_id, FontOp::DrawGlyphs(glyphs)) => {
                     if let Some(font) = font_map.get(&(namespace_id, font_id)) {
                         // Use this font to generate the glyphs
-                        let ttf_font        = font.ttf_font();
-                        let units_per_em    = ttf_font.units_per_em() as f32;
-
                         for glyph in glyphs {
                             // Start rendering this glyph
                             yield_value(Draw::Path(PathOp::NewPath)).await;
 
-                            let GlyphId(glyph_id)   = glyph.id;
-                            let glyph_id            = ttf_parser::GlyphId(glyph_id as _);
-
-                            // Generate the outline
-                            let mut drawing         = vec![];
-                            let mut outliner        = FontOutliner { 
-                                drawing:        &mut drawing,
-                                scale_factor:   glyph.em_size / units_per_em,
-                                x_pos:          glyph.location.0,
-                                y_pos:          glyph.location.1,
-                                last:           (0.0, 0.0)
-                            };
-
-                            ttf_font.outline_glyph(glyph_id, &mut outliner);
-
-                            // Render the drawing
-                            for draw in drawing {
-                                yield_value(draw).await;
+                            // Render the outline
+                            for path_op in glyph_outline(font, &glyph) {
+                                yield_value(Draw::Path(path_op)).await;
                             }
 
                             // Fill the path
@@ -223,4 +245,43 @@ mod test {
             assert!(instructions.len() != 0);
         });
     }
+
+    #[test]
+    fn change_variation_mid_line() {
+        executor::block_on(async {
+            let variable_test   = CanvasFontFace::from_slice(include_bytes!("../../test_data/VariableTest.ttf"));
+
+            let instructions    = vec![
+                Draw::Font(FontId(1), FontOp::UseFontDefinition(variable_test)),
+                Draw::Font(FontId(1), FontOp::FontSize(100.0)),
+                Draw::BeginLineLayout(0.0, 0.0, TextAlignment::Left),
+                Draw::Font(FontId(1), FontOp::LayoutText("aa".to_string())),
+                Draw::Font(FontId(1), FontOp::FontVariation(*b"wght", 900.0)),
+                Draw::Font(FontId(1), FontOp::LayoutText("aa".to_string())),
+                Draw::DrawLaidOutText,
+            ];
+            let instructions    = stream::iter(instructions);
+            let instructions    = drawing_with_laid_out_text(instructions);
+            let instructions    = drawing_with_text_as_paths(instructions);
+
+            let instructions    = instructions.collect::<Vec<_>>().await;
+
+            // Each 'a' is a rectangle: find where the right-hand edge of each one is
+            let mut right_edges = vec![];
+            for draw in instructions.iter() {
+                match draw {
+                    Draw::Path(PathOp::Move(x, _))  => { right_edges.push(*x); }
+                    Draw::Path(PathOp::Line(x, _))  => { right_edges.last_mut().map(|edge| *edge = edge.max(*x)); }
+                    _                               => { }
+                }
+            }
+
+            // The glyphs before the variation are 80 units wide, and the glyphs after it are 130 units wide
+            assert!(right_edges.len() == 4, "{:?}", instructions);
+            assert!((right_edges[0] - 90.0).abs() < 0.1, "{:?}", right_edges);
+            assert!((right_edges[1] - 190.0).abs() < 0.1, "{:?}", right_edges);
+            assert!((right_edges[2] - 340.0).abs() < 0.1, "{:?}", right_edges);
+            assert!((right_edges[3] - 490.0).abs() < 0.1, "{:?}", right_edges);
+        });
+    }
 }
@@ -0,0 +1,401 @@
+use crate::draw::*;
+use crate::path::*;
+
+use flo_stream::*;
+use flo_curves::geo::*;
+use flo_curves::bezier::path::*;
+
+use futures::prelude::*;
+
+/// How accurately paths are combined (the maximum distance between points that are considered to be the same)
+const PATH_ARITHMETIC_ACCURACY: f64 = 0.01;
+
+///
+/// Converts a list of path operations into a set of closed bezier paths
+///
+fn path_ops_to_bezier_paths(path_ops: &[PathOp]) -> Vec<SimpleBezierPath> {
+    let mut paths           = vec![];
+    let mut start_point     = None;
+    let mut last_point      = Coord2(0.0, 0.0);
+    let mut curves          = vec![];
+
+    // Paths are always closed when they're combined, in the same way as they are when they're filled
+    fn finish_subpath(start_point: Option<Coord2>, last_point: Coord2, curves: &mut Vec<(Coord2, Coord2, Coord2)>, paths: &mut Vec<SimpleBezierPath>) {
+        if let Some(start_point) = start_point {
+            if !curves.is_empty() {
+                if last_point != start_point {
+                    curves.push((last_point, start_point, start_point));
+                }
+
+                paths.push((start_point, curves.drain(..).collect()));
+            }
+        }
+
+        curves.clear();
+    }
+
+    for path_op in path_ops.iter() {
+        use self::PathOp::*;
+
+        match path_op {
+            NewPath => {
+                finish_subpath(start_point, last_point, &mut curves, &mut paths);
+                paths.clear();
+                start_point = None;
+            }
+
+            Move(x, y) => {
+                finish_subpath(start_point, last_point, &mut curves, &mut paths);
+                start_point = Some(Coord2(*x as f64, *y as f64));
+                last_point  = Coord2(*x as f64, *y as f64);
+            }
+
+            Line(x, y) => {
+                let end_point = Coord2(*x as f64, *y as f64);
+
+                if start_point.is_some() {
+                    curves.push((last_point, end_point, end_point));
+                } else {
+                    start_point = Some(end_point);
+                }
+                last_point = end_point;
+            }
+
+            BezierCurve(((cp1x, cp1y), (cp2x, cp2y)), (x, y)) => {
+                let end_point = Coord2(*x as f64, *y as f64);
+
+                if start_point.is_some() {
+                    curves.push((Coord2(*cp1x as f64, *cp1y as f64), Coord2(*cp2x as f64, *cp2y as f64), end_point));
+                } else {
+                    start_point = Some(end_point);
+                }
+                last_point = end_point;
+            }
+
+            QuadraticCurve(cp, (x, y)) => {
+                let end_point = Coord2(*x as f64, *y as f64);
+
+                if start_point.is_some() {
+                    let ((cp1x, cp1y), (cp2x, cp2y)) = quadratic_to_bezier((last_point.0 as f32, last_point.1 as f32), *cp, (*x, *y));
+                    curves.push((Coord2(cp1x as f64, cp1y as f64), Coord2(cp2x as f64, cp2y as f64), end_point));
+                } else {
+                    start_point = Some(end_point);
+                }
+                last_point = end_point;
+            }
+
+            Arc(center, radii, rotation, start_angle, sweep_angle) => {
+                // Line to the start of the arc, or begin a new subpath there
+                let (x, y)      = arc_point(*center, *radii, *rotation, *start_angle);
+                let arc_start   = Coord2(x as f64, y as f64);
+
+                if start_point.is_some() {
+                    if last_point != arc_start {
+                        curves.push((last_point, arc_start, arc_start));
+                    }
+                } else {
+                    start_point = Some(arc_start);
+                }
+                last_point = arc_start;
+
+                for ((cp1x, cp1y), (cp2x, cp2y), (x, y)) in arc_to_bezier_curves(*center, *radii, *rotation, *start_angle, *sweep_angle) {
+                    last_point = Coord2(x as f64, y as f64);
+                    curves.push((Coord2(cp1x as f64, cp1y as f64), Coord2(cp2x as f64, cp2y as f64), last_point));
+                }
+            }
+
+            ClosePath => {
+                // Further path operations start from the beginning of the closed subpath
+                finish_subpath(start_point, last_point, &mut curves, &mut paths);
+                if let Some(start_point) = start_point {
+                    last_point = start_point;
+                }
+            }
+        }
+    }
+
+    finish_subpath(start_point, last_point, &mut curves, &mut paths);
+
+    paths
+}
+
+///
+/// Converts a set of bezier paths filled using a winding rule into a set of paths that describe the same region using the even-odd rule
+///
+/// The flo_curves arithmetic operations treat their inputs as even-odd paths, so paths that use the non-zero rule have their interior
+/// points removed before they are combined.
+///
+fn even_odd_bezier_paths(paths: Vec<SimpleBezierPath>, winding_rule: WindingRule) -> Vec<SimpleBezierPath> {
+    match winding_rule {
+        WindingRule::EvenOdd    => paths,
+        WindingRule::NonZero    => if paths.is_empty() { paths } else { path_remove_interior_points(&paths, PATH_ARITHMETIC_ACCURACY) },
+    }
+}
+
+///
+/// Converts a set of bezier paths back into path operations
+///
+fn bezier_paths_to_path_ops(paths: Vec<SimpleBezierPath>) -> Vec<PathOp> {
+    let mut path_ops = vec![];
+
+    for (start_point, curves) in paths {
+        path_ops.push(PathOp::Move(start_point.0 as f32, start_point.1 as f32));
+
+        for (cp1, cp2, end_point) in curves {
+            path_ops.push(PathOp::BezierCurve(((cp1.0 as f32, cp1.1 as f32), (cp2.0 as f32, cp2.1 as f32)), (end_point.0 as f32, end_point.1 as f32)));
+        }
+
+        path_ops.push(PathOp::ClosePath);
+    }
+
+    path_ops
+}
+
+///
+/// Combines two paths, returning the path operations for the result
+///
+/// `stored_path` is the path saved by `StorePath` and `current_path` is the path that is being combined with it. Any open subpaths
+/// are closed before they're combined, in the same way as they would be if they were filled. The winding rule decides which parts
+/// of each path are inside it, in the same way as for `Fill`.
+///
+pub fn combine_paths(operation: PathArithmetic, winding_rule: WindingRule, stored_path: &[PathOp], current_path: &[PathOp]) -> Vec<PathOp> {
+    let stored_path     = even_odd_bezier_paths(path_ops_to_bezier_paths(stored_path), winding_rule);
+    let current_path    = even_odd_bezier_paths(path_ops_to_bezier_paths(current_path), winding_rule);
+
+    // Combining with an empty path has a trivial result
+    if stored_path.is_empty() || current_path.is_empty() {
+        return match operation {
+            PathArithmetic::Union | PathArithmetic::Xor => {
+                let mut result = stored_path;
+                result.extend(current_path);
+                bezier_paths_to_path_ops(result)
+            }
+
+            PathArithmetic::Intersection                => vec![],
+            PathArithmetic::Difference                  => bezier_paths_to_path_ops(stored_path),
+        };
+    }
+
+    let result: Vec<SimpleBezierPath> = match operation {
+        PathArithmetic::Union           => path_add(&stored_path, &current_path, PATH_ARITHMETIC_ACCURACY),
+        PathArithmetic::Intersection    => path_intersect(&stored_path, &current_path, PATH_ARITHMETIC_ACCURACY),
+        PathArithmetic::Difference      => path_sub(&stored_path, &current_path, PATH_ARITHMETIC_ACCURACY),
+
+        PathArithmetic::Xor             => {
+            // The two differences don't overlap, so they can just be drawn together
+            let mut stored_only: Vec<SimpleBezierPath>  = path_sub(&stored_path, &current_path, PATH_ARITHMETIC_ACCURACY);
+            let current_only: Vec<SimpleBezierPath>     = path_sub(&current_path, &stored_path, PATH_ARITHMETIC_ACCURACY);
+
+            stored_only.extend(current_only);
+            stored_only
+        }
+    };
+
+    bezier_paths_to_path_ops(result)
+}
+
+///
+/// Converts a stream of drawing instructions so that any `StorePath` and `CombinePath` instructions are replaced by the paths that they generate
+///
+/// This is useful for rendering back-ends that don't support path arithmetic: the result of each `CombinePath` instruction is
+/// written out as a new path made up of bezier curves.
+///
+pub fn drawing_with_path_arithmetic<InStream: 'static+Send+Unpin+Stream<Item=Draw>>(draw_stream: InStream) -> impl Send+Unpin+Stream<Item=Draw> {
+    generator_stream(move |yield_value| async move {
+        let mut draw_stream = draw_stream;
+
+        // The operations making up the current path, and the path saved by the last StorePath
+        let mut current_path    = vec![];
+        let mut stored_path     = vec![];
+
+        // The winding rule decides which parts of the paths are combined
+        let mut winding_rule    = WindingRule::NonZero;
+
+        // The current path and winding rule are restored by PopState
+        let mut path_stack      = vec![];
+
+        while let Some(drawing) = draw_stream.next().await {
+            match drawing {
+                Draw::Path(PathOp::NewPath) => {
+                    current_path = vec![];
+                    yield_value(drawing).await;
+                }
+
+                Draw::Path(path_op) => {
+                    current_path.push(path_op);
+                    yield_value(Draw::Path(path_op)).await;
+                }
+
+                Draw::StorePath => {
+                    stored_path = current_path.clone();
+                }
+
+                Draw::CombinePath(operation) => {
+                    // Replace the current path with the combined path
+                    current_path = combine_paths(operation, winding_rule, &stored_path, &current_path);
+
+                    yield_value(Draw::Path(PathOp::NewPath)).await;
+                    for path_op in current_path.iter() {
+                        yield_value(Draw::Path(*path_op)).await;
+                    }
+                }
+
+                Draw::WindingRule(rule) => {
+                    winding_rule = rule;
+                    yield_value(drawing).await;
+                }
+
+                Draw::PushState => {
+                    path_stack.push((current_path.clone(), winding_rule));
+                    yield_value(drawing).await;
+                }
+
+                Draw::PopState => {
+                    if let Some((path, rule)) = path_stack.pop() {
+                        current_path = path;
+                        winding_rule = rule;
+                    }
+                    yield_value(drawing).await;
+                }
+
+                Draw::ClearCanvas(_) => {
+                    current_path    = vec![];
+                    stored_path     = vec![];
+                    winding_rule    = WindingRule::NonZero;
+                    path_stack      = vec![];
+                    yield_value(drawing).await;
+                }
+
+                drawing => {
+                    // Pass the drawing on
+                    yield_value(drawing).await;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::stream;
+    use futures::executor;
+
+    fn rectangle(x1: f32, y1: f32, x2: f32, y2: f32) -> Vec<PathOp> {
+        vec![
+            PathOp::Move(x1, y1),
+            PathOp::Line(x2, y1),
+            PathOp::Line(x2, y2),
+            PathOp::Line(x1, y2),
+            PathOp::ClosePath,
+        ]
+    }
+
+    fn num_subpaths(path_ops: &[PathOp]) -> usize {
+        path_ops.iter().filter(|op| match op { PathOp::Move(_, _) => true, _ => false }).count()
+    }
+
+    fn bounds(path_ops: &[PathOp]) -> ((f32, f32), (f32, f32)) {
+        path_ops.iter()
+            .flat_map(|op| match op {
+                PathOp::Move(x, y)              => Some((*x, *y)),
+                PathOp::BezierCurve(_, (x, y))  => Some((*x, *y)),
+                _                               => None
+            })
+            .fold(((f32::MAX, f32::MAX), (f32::MIN, f32::MIN)), |((min_x, min_y), (max_x, max_y)), (x, y)| {
+                ((min_x.min(x), min_y.min(y)), (max_x.max(x), max_y.max(y)))
+            })
+    }
+
+    #[test]
+    fn union_overlapping_rectangles() {
+        let result = combine_paths(PathArithmetic::Union, WindingRule::NonZero, &rectangle(0.0, 0.0, 10.0, 10.0), &rectangle(5.0, 5.0, 15.0, 15.0));
+
+        assert!(num_subpaths(&result) == 1);
+
+        let ((min_x, min_y), (max_x, max_y)) = bounds(&result);
+        assert!((min_x-0.0).abs() < 0.01 && (min_y-0.0).abs() < 0.01);
+        assert!((max_x-15.0).abs() < 0.01 && (max_y-15.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn intersect_overlapping_rectangles() {
+        let result = combine_paths(PathArithmetic::Intersection, WindingRule::NonZero, &rectangle(0.0, 0.0, 10.0, 10.0), &rectangle(5.0, 5.0, 15.0, 15.0));
+
+        assert!(num_subpaths(&result) == 1);
+
+        let ((min_x, min_y), (max_x, max_y)) = bounds(&result);
+        assert!((min_x-5.0).abs() < 0.01 && (min_y-5.0).abs() < 0.01);
+        assert!((max_x-10.0).abs() < 0.01 && (max_y-10.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn intersect_with_empty_path() {
+        let result = combine_paths(PathArithmetic::Intersection, WindingRule::NonZero, &rectangle(0.0, 0.0, 10.0, 10.0), &[]);
+
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn subtract_from_stored_path() {
+        let result = combine_paths(PathArithmetic::Difference, WindingRule::NonZero, &rectangle(0.0, 0.0, 10.0, 10.0), &rectangle(5.0, -5.0, 15.0, 15.0));
+
+        assert!(num_subpaths(&result) == 1);
+
+        let ((min_x, min_y), (max_x, max_y)) = bounds(&result);
+        assert!((min_x-0.0).abs() < 0.01 && (min_y-0.0).abs() < 0.01);
+        assert!((max_x-5.0).abs() < 0.01 && (max_y-10.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn overlapping_subpaths_use_winding_rule() {
+        // Two overlapping rectangles going in the same direction: the overlap is only inside the path for the non-zero rule
+        let mut stored_path = rectangle(0.0, 0.0, 10.0, 10.0);
+        stored_path.extend(rectangle(5.0, 5.0, 15.0, 15.0));
+
+        let non_zero    = combine_paths(PathArithmetic::Intersection, WindingRule::NonZero, &stored_path, &rectangle(6.0, 6.0, 9.0, 9.0));
+        let even_odd    = combine_paths(PathArithmetic::Intersection, WindingRule::EvenOdd, &stored_path, &rectangle(6.0, 6.0, 9.0, 9.0));
+
+        assert!(num_subpaths(&non_zero) == 1);
+        assert!(num_subpaths(&even_odd) == 0);
+
+        let ((min_x, min_y), (max_x, max_y)) = bounds(&non_zero);
+        assert!((min_x-6.0).abs() < 0.01 && (min_y-6.0).abs() < 0.01);
+        assert!((max_x-9.0).abs() < 0.01 && (max_y-9.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn xor_overlapping_rectangles() {
+        let result = combine_paths(PathArithmetic::Xor, WindingRule::NonZero, &rectangle(0.0, 0.0, 10.0, 10.0), &rectangle(5.0, 5.0, 15.0, 15.0));
+
+        assert!(num_subpaths(&result) == 2);
+    }
+
+    #[test]
+    fn stream_replaces_path_arithmetic() {
+        let mut input_drawing = vec![Draw::Path(PathOp::NewPath)];
+        input_drawing.extend(rectangle(0.0, 0.0, 10.0, 10.0).into_iter().map(|op| Draw::Path(op)));
+        input_drawing.push(Draw::StorePath);
+        input_drawing.push(Draw::Path(PathOp::NewPath));
+        input_drawing.extend(rectangle(5.0, 5.0, 15.0, 15.0).into_iter().map(|op| Draw::Path(op)));
+        input_drawing.push(Draw::CombinePath(PathArithmetic::Union));
+        input_drawing.push(Draw::Fill);
+
+        executor::block_on(async move {
+            let output_drawing = drawing_with_path_arithmetic(stream::iter(input_drawing.into_iter())).collect::<Vec<_>>().await;
+
+            assert!(!output_drawing.contains(&Draw::StorePath));
+            assert!(!output_drawing.contains(&Draw::CombinePath(PathArithmetic::Union)));
+
+            // The combined path should be defined just before the fill
+            let last_new_path   = output_drawing.iter().rposition(|draw| draw == &Draw::Path(PathOp::NewPath)).unwrap();
+            let combined_path   = output_drawing[(last_new_path+1)..(output_drawing.len()-1)].iter()
+                .flat_map(|draw| match draw { Draw::Path(op) => Some(*op), _ => None })
+                .collect::<Vec<_>>();
+
+            assert!(output_drawing.last() == Some(&Draw::Fill));
+            assert!(num_subpaths(&combined_path) == 1);
+        });
+    }
+}
@@ -31,7 +31,13 @@ pub enum PathAttribute {
     FillTexture(TextureId, (f32, f32), (f32, f32), Option<Transform2D>),
 
     /// Path is filled with the specified gradient
-    FillGradient(GradientId, (f32, f32), (f32, f32), Option<Transform2D>)
+    FillGradient(GradientId, (f32, f32), (f32, f32), Option<Transform2D>),
+
+    /// Path is filled with the specified radial gradient (centre, radius and optional focal point)
+    FillRadialGradient(GradientId, (f32, f32), f32, Option<(f32, f32)>, Option<Transform2D>),
+
+    /// Path is filled with the specified conic gradient (centre and start angle)
+    FillConicGradient(GradientId, (f32, f32), f32, Option<Transform2D>)
 }
 
 ///
@@ -55,6 +61,7 @@ BezierPath::Point:  Send+Coordinate2D {
         let mut current_path: Option<BezierPathBuilder<_>>  = None;
         let mut current_components                          = vec![];
         let mut start_point                                 = None;
+        let mut last_point                                  = None;
         let mut current_transform: Option<Transform2D>      = None;
         let mut transform_stack                             = vec![];
 
@@ -76,6 +83,8 @@ BezierPath::Point:  Send+Coordinate2D {
 
                         yield_value(next_path).await;
                     }
+
+                    last_point = None;
                 }
 
                 Draw::Path(Move(x, y))                                          => {
@@ -89,6 +98,7 @@ BezierPath::Point:  Send+Coordinate2D {
                     // Start a new path
                     current_path = Some(BezierPathBuilder::start(BezierPath::Point::from_components(&[x as _, y as _])));
                     start_point  = Some(BezierPath::Point::from_components(&[x as _, y as _]));
+                    last_point   = Some((x, y));
                 }
 
                 Draw::Path(Line(x, y))                                          => {
@@ -98,6 +108,7 @@ BezierPath::Point:  Send+Coordinate2D {
                     current_path = current_path.map(|current_path| {
                         current_path.line_to(BezierPath::Point::from_components(&[x as _, y as _]))
                     });
+                    last_point = Some((x, y));
                 }
 
                 Draw::Path(BezierCurve(((cp1x, cp1y), (cp2x, cp2y)), (x1, y1))) => {
@@ -115,6 +126,57 @@ BezierPath::Point:  Send+Coordinate2D {
                             BezierPath::Point::from_components(&[x1 as _, y1 as _])
                         )
                     });
+                    last_point = Some((x1, y1));
+                }
+
+                Draw::Path(QuadraticCurve((cpx, cpy), (x1, y1)))                => {
+                    let ((cpx, cpy), (x1, y1)) = if let Some(transform) = &current_transform { 
+                        (transform.transform_point(cpx, cpy), transform.transform_point(x1, y1))
+                    } else { 
+                        ((cpx, cpy), (x1, y1))
+                    };
+
+                    // Add as a bezier curve from the last point
+                    if let Some(last_point) = last_point {
+                        let ((cp1x, cp1y), (cp2x, cp2y)) = quadratic_to_bezier(last_point, (cpx, cpy), (x1, y1));
+
+                        current_path = current_path.map(|current_path| {
+                            current_path.curve_to(
+                                (BezierPath::Point::from_components(&[cp1x as _, cp1y as _]), BezierPath::Point::from_components(&[cp2x as _, cp2y as _])),
+                                BezierPath::Point::from_components(&[x1 as _, y1 as _])
+                            )
+                        });
+                    }
+                    last_point = Some((x1, y1));
+                }
+
+                Draw::Path(Arc(center, radii, rotation, start_angle, sweep_angle)) => {
+                    let transform_point = |(x, y): (f32, f32)| if let Some(transform) = &current_transform { transform.transform_point(x, y) } else { (x, y) };
+
+                    // Draw a line to the start of the arc, or start a new path there if there's no current path
+                    let (x, y)      = transform_point(arc_point(center, radii, rotation, start_angle));
+                    let arc_start   = BezierPath::Point::from_components(&[x as _, y as _]);
+
+                    if let Some(path) = current_path.take() {
+                        current_path = Some(if last_point == Some((x, y)) { path } else { path.line_to(arc_start) });
+                    } else {
+                        current_path = Some(BezierPathBuilder::start(arc_start.clone()));
+                        start_point  = Some(arc_start);
+                    }
+                    last_point = Some((x, y));
+
+                    // Add the arc as a series of bezier curves
+                    for (cp1, cp2, end_point) in arc_to_bezier_curves(center, radii, rotation, start_angle, sweep_angle) {
+                        let ((cp1x, cp1y), (cp2x, cp2y), (x1, y1)) = (transform_point(cp1), transform_point(cp2), transform_point(end_point));
+
+                        current_path = current_path.map(|current_path| {
+                            current_path.curve_to(
+                                (BezierPath::Point::from_components(&[cp1x as _, cp1y as _]), BezierPath::Point::from_components(&[cp2x as _, cp2y as _])),
+                                BezierPath::Point::from_components(&[x1 as _, y1 as _])
+                            )
+                        });
+                        last_point = Some((x1, y1));
+                    }
                 }
 
                 Draw::Path(ClosePath)                                           => {
@@ -123,6 +185,7 @@ BezierPath::Point:  Send+Coordinate2D {
                             current_path.line_to(start_point.clone())
                         })
                     }
+                    last_point = start_point.as_ref().map(|start_point| (start_point.x() as f32, start_point.y() as f32));
                 }
 
                 Draw::MultiplyTransform(transform)                              => {
@@ -179,6 +242,7 @@ BezierPath::Point:  Send+Coordinate2D {
         let mut current_components                          = vec![];
         let mut current_attributes                          = vec![];
         let mut start_point                                 = None;
+        let mut last_point                                  = None;
 
         let mut fill_color                                  = PathAttribute::Fill(Color::Rgba(0.0, 0.0, 0.0, 1.0));
         let mut stroke_color                                = Color::Rgba(0.0, 0.0, 0.0, 1.0);
@@ -209,6 +273,7 @@ BezierPath::Point:  Send+Coordinate2D {
 
                     current_attributes = vec![];
                     current_components = vec![];
+                    last_point         = None;
                 }
 
                 Draw::Path(Move(x, y))                                          => {
@@ -222,6 +287,7 @@ BezierPath::Point:  Send+Coordinate2D {
                     // Start a new path
                     current_path = Some(BezierPathBuilder::start(BezierPath::Point::from_components(&[x as _, y as _])));
                     start_point  = Some(BezierPath::Point::from_components(&[x as _, y as _]));
+                    last_point   = Some((x, y));
                 }
 
                 Draw::Path(Line(x, y))                                          => {
@@ -231,6 +297,7 @@ BezierPath::Point:  Send+Coordinate2D {
                     current_path = current_path.map(|current_path| {
                         current_path.line_to(BezierPath::Point::from_components(&[x as _, y as _]))
                     });
+                    last_point = Some((x, y));
                 }
 
                 Draw::Path(BezierCurve(((cp1x, cp1y), (cp2x, cp2y)), (x1, y1))) => {
@@ -247,6 +314,57 @@ BezierPath::Point:  Send+Coordinate2D {
                             BezierPath::Point::from_components(&[x1 as _, y1 as _])
                         )
                     });
+                    last_point = Some((x1, y1));
+                }
+
+                Draw::Path(QuadraticCurve((cpx, cpy), (x1, y1)))                => {
+                    let ((cpx, cpy), (x1, y1)) = if let Some(transform) = &current_transform { 
+                        (transform.transform_point(cpx, cpy), transform.transform_point(x1, y1))
+                    } else { 
+                        ((cpx, cpy), (x1, y1))
+                    };
+
+                    // Add as a bezier curve from the last point
+                    if let Some(last_point) = last_point {
+                        let ((cp1x, cp1y), (cp2x, cp2y)) = quadratic_to_bezier(last_point, (cpx, cpy), (x1, y1));
+
+                        current_path = current_path.map(|current_path| {
+                            current_path.curve_to(
+                                (BezierPath::Point::from_components(&[cp1x as _, cp1y as _]), BezierPath::Point::from_components(&[cp2x as _, cp2y as _])),
+                                BezierPath::Point::from_components(&[x1 as _, y1 as _])
+                            )
+                        });
+                    }
+                    last_point = Some((x1, y1));
+                }
+
+                Draw::Path(Arc(center, radii, rotation, start_angle, sweep_angle)) => {
+                    let transform_point = |(x, y): (f32, f32)| if let Some(transform) = &current_transform { transform.transform_point(x, y) } else { (x, y) };
+
+                    // Draw a line to the start of the arc, or start a new path there if there's no current path
+                    let (x, y)      = transform_point(arc_point(center, radii, rotation, start_angle));
+                    let arc_start   = BezierPath::Point::from_components(&[x as _, y as _]);
+
+                    if let Some(path) = current_path.take() {
+                        current_path = Some(if last_point == Some((x, y)) { path } else { path.line_to(arc_start) });
+                    } else {
+                        current_path = Some(BezierPathBuilder::start(arc_start.clone()));
+                        start_point  = Some(arc_start);
+                    }
+                    last_point = Some((x, y));
+
+                    // Add the arc as a series of bezier curves
+                    for (cp1, cp2, end_point) in arc_to_bezier_curves(center, radii, rotation, start_angle, sweep_angle) {
+                        let ((cp1x, cp1y), (cp2x, cp2y), (x1, y1)) = (transform_point(cp1), transform_point(cp2), transform_point(end_point));
+
+                        current_path = current_path.map(|current_path| {
+                            current_path.curve_to(
+                                (BezierPath::Point::from_components(&[cp1x as _, cp1y as _]), BezierPath::Point::from_components(&[cp2x as _, cp2y as _])),
+                                BezierPath::Point::from_components(&[x1 as _, y1 as _])
+                            )
+                        });
+                        last_point = Some((x1, y1));
+                    }
                 }
 
                 Draw::Path(ClosePath)                                           => {
@@ -255,6 +373,7 @@ BezierPath::Point:  Send+Coordinate2D {
                             current_path.line_to(start_point.clone())
                         })
                     }
+                    last_point = start_point.as_ref().map(|start_point| (start_point.x() as f32, start_point.y() as f32));
                 }
 
                 Draw::FillColor(new_fill_color)                                 => {
@@ -265,6 +384,14 @@ BezierPath::Point:  Send+Coordinate2D {
                     fill_color = PathAttribute::FillGradient(gradient, (x1, y1), (x2, y2), None);
                 }
 
+                Draw::FillRadialGradient(gradient, center, radius, focus)       => {
+                    fill_color = PathAttribute::FillRadialGradient(gradient, center, radius, focus, None);
+                }
+
+                Draw::FillConicGradient(gradient, center, angle)                => {
+                    fill_color = PathAttribute::FillConicGradient(gradient, center, angle, None);
+                }
+
                 Draw::FillTexture(texture, (x1, y1), (x2, y2))                  => {
                     fill_color = PathAttribute::FillTexture(texture, (x1, y1), (x2, y2), None);
                 }
@@ -276,6 +403,11 @@ BezierPath::Point:  Send+Coordinate2D {
                         PathAttribute::FillGradient(gradient, coord1, coord2, Some(existing_transform)) => PathAttribute::FillGradient(gradient, coord1, coord2, Some(existing_transform * transform)),
                         PathAttribute::FillTexture(texture, coord1, coord2, Some(existing_transform))   => PathAttribute::FillTexture(texture, coord1, coord2, Some(existing_transform * transform)),
 
+                        PathAttribute::FillRadialGradient(gradient, center, radius, focus, None)                     => PathAttribute::FillRadialGradient(gradient, center, radius, focus, Some(transform)),
+                        PathAttribute::FillRadialGradient(gradient, center, radius, focus, Some(existing_transform)) => PathAttribute::FillRadialGradient(gradient, center, radius, focus, Some(existing_transform * transform)),
+                        PathAttribute::FillConicGradient(gradient, center, angle, None)                             => PathAttribute::FillConicGradient(gradient, center, angle, Some(transform)),
+                        PathAttribute::FillConicGradient(gradient, center, angle, Some(existing_transform))         => PathAttribute::FillConicGradient(gradient, center, angle, Some(existing_transform * transform)),
+
                         other_fill_color                                                                => other_fill_color
                     };
                 }
@@ -390,4 +522,60 @@ mod test {
             assert!(curves.len() == 4);
         });
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn quadratic_curve_path() {
+        executor::block_on(async {
+            // A quadratic curve following a move
+            let curve           = vec![
+                Draw::Path(PathOp::NewPath),
+                Draw::Path(PathOp::Move(0.0, 0.0)), 
+                Draw::Path(PathOp::QuadraticCurve((30.0, 60.0), (60.0, 0.0))), 
+            ];
+
+            let curve_stream    = stream::iter(curve);
+            let path_stream     = drawing_to_paths::<SimpleBezierPath, _>(curve_stream);
+            let paths           = path_stream.collect::<Vec<_>>().await;
+
+            // Should be converted to a bezier curve with the equivalent control points
+            assert!(paths.len() == 1);
+            assert!(paths[0].len() == 1);
+
+            let (start, curves) = &paths[0][0];
+
+            assert!(start == &Coord2(0.0, 0.0));
+            assert!(curves.len() == 1);
+            assert!(curves[0].0.distance_to(&Coord2(20.0, 40.0)) < 0.001);
+            assert!(curves[0].1.distance_to(&Coord2(40.0, 40.0)) < 0.001);
+            assert!(curves[0].2 == Coord2(60.0, 0.0));
+        });
+    }
+
+    #[test]
+    pub fn arc_path() {
+        executor::block_on(async {
+            // A semicircle from (100, 0) to (-100, 0), followed by a line back to the start
+            let arc             = vec![
+                Draw::Path(PathOp::NewPath),
+                Draw::Path(PathOp::Arc((0.0, 0.0), (100.0, 100.0), 0.0, 0.0, std::f32::consts::PI)), 
+                Draw::Path(PathOp::ClosePath),
+            ];
+
+            let arc_stream      = stream::iter(arc);
+            let path_stream     = drawing_to_paths::<SimpleBezierPath, _>(arc_stream);
+            let paths           = path_stream.collect::<Vec<_>>().await;
+
+            // The arc should start a new path at its start point
+            assert!(paths.len() == 1);
+            assert!(paths[0].len() == 1);
+
+            let (start, curves) = &paths[0][0];
+
+            assert!(start.distance_to(&Coord2(100.0, 0.0)) < 0.001);
+            assert!(curves.len() == 3);
+            assert!(curves[0].2.distance_to(&Coord2(0.0, 100.0)) < 0.001);
+            assert!(curves[1].2.distance_to(&Coord2(-100.0, 0.0)) < 0.001);
+            assert!(curves[2].2.distance_to(&Coord2(100.0, 0.0)) < 0.001);
+        });
+    }
+}
@@ -0,0 +1,766 @@
+use super::drawing_bounds::*;
+
+use crate::draw::*;
+use crate::path::*;
+use crate::sprite::*;
+
+use flo_stream::*;
+use futures::prelude::*;
+
+use std::mem;
+use std::collections::{HashMap};
+
+///
+/// The layer or sprite that state changes apply to
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum StateTarget {
+    Layer(LayerId),
+    Sprite(SpriteId),
+}
+
+///
+/// Values for the state of a layer (None for values that are unknown or unchanged)
+///
+/// Texture and gradient fills and pixel line widths depend on things other than the instruction that sets them (the texture
+/// contents and the transform), so these are never treated as a known value.
+///
+#[derive(Clone, Default, Debug)]
+struct LayerDrawingState {
+    fill:           Option<Vec<Draw>>,
+    stroke:         Option<Draw>,
+    line_width:     Option<Draw>,
+    line_join:      Option<LineJoin>,
+    line_cap:       Option<LineCap>,
+    miter_limit:    Option<f32>,
+    winding_rule:   Option<WindingRule>,
+    blend_mode:     Option<BlendMode>,
+    dash_pattern:   Option<Vec<f32>>,
+    dash_offset:    Option<f32>,
+}
+
+///
+/// A `Fill` or `Stroke` that hasn't been written to the output yet (so that the following shapes can be merged into it)
+///
+struct HeldShape {
+    /// The path to draw
+    path:   Vec<PathOp>,
+
+    /// The `Fill` or `Stroke` instruction
+    paint:  Draw,
+
+    /// The bounds of the control points in the path
+    bounds: DrawingBounds,
+}
+
+///
+/// Removes instructions that have no effect from a drawing
+///
+struct RedundantStateOptimiser {
+    /// The layer or sprite that's being drawn on
+    target:             StateTarget,
+
+    /// The state that the output is known to be in for each layer or sprite
+    known:              HashMap<StateTarget, LayerDrawingState>,
+
+    /// State changes that haven't been written to the output yet
+    pending:            LayerDrawingState,
+
+    /// The current path in the input
+    path:               Vec<PathOp>,
+
+    /// False if the output path might contain instructions that aren't in `path` (eg, after `PopState`)
+    path_known:         bool,
+
+    /// The number of instructions from `path` that are in the output path (None if `NewPath` needs to be written first)
+    path_output:        Option<usize>,
+
+    /// Changes any time the path changes
+    path_generation:    usize,
+
+    /// A shape that has not been written to the output yet
+    held:               Option<HeldShape>,
+
+    /// True if the held shape is the current path
+    held_is_current:    bool,
+
+    /// The known states, pending state changes and path generations when `PushState` was called
+    state_stack:        Vec<(HashMap<StateTarget, LayerDrawingState>, LayerDrawingState, usize)>,
+
+    /// The number of `PushState` instructions that have not been written to the output yet
+    deferred_pushes:    usize,
+}
+
+///
+/// Returns the bounds of the control points in a path
+///
+fn path_bounds(path: &[PathOp]) -> DrawingBounds {
+    let mut bounds = DrawingBounds::empty();
+
+    for path_op in path.iter() {
+        match path_op {
+            PathOp::NewPath                         |
+            PathOp::ClosePath                       => { }
+            PathOp::Move(x, y)                      |
+            PathOp::Line(x, y)                      => { bounds.add_point((*x, *y)); }
+            PathOp::BezierCurve((cp1, cp2), end)    => { bounds.add_point(*cp1); bounds.add_point(*cp2); bounds.add_point(*end); }
+            PathOp::QuadraticCurve(cp, end)         => { bounds.add_point(*cp); bounds.add_point(*end); }
+
+            PathOp::Arc((cx, cy), (rx, ry), _, _, _) => {
+                let radius = rx.abs().max(ry.abs());
+                bounds.add_point((cx-radius, cy-radius));
+                bounds.add_point((cx+radius, cy+radius));
+            }
+        }
+    }
+
+    bounds
+}
+
+///
+/// True if two sets of bounds are separated by more than the specified distance
+///
+fn bounds_are_separate(a: &DrawingBounds, b: &DrawingBounds, distance: f32) -> bool {
+    a.max_x + distance < b.min_x || b.max_x + distance < a.min_x ||
+    a.max_y + distance < b.min_y || b.max_y + distance < a.min_y
+}
+
+///
+/// Returns the instructions needed to apply the pending changes to a known state, along with the new known state
+///
+fn state_changes(pending: &LayerDrawingState, known: &LayerDrawingState) -> (Vec<Draw>, LayerDrawingState) {
+    let mut changes = vec![];
+    let mut new     = known.clone();
+
+    if let Some(fill) = &pending.fill {
+        if known.fill.as_ref() != Some(fill) {
+            changes.extend(fill.iter().cloned());
+            new.fill = if let [Draw::FillColor(_)] = fill.as_slice() { Some(fill.clone()) } else { None };
+        }
+    }
+
+    if let Some(stroke) = &pending.stroke {
+        if known.stroke.as_ref() != Some(stroke) {
+            changes.push(stroke.clone());
+            new.stroke = if let Draw::StrokeColor(_) = stroke { Some(stroke.clone()) } else { None };
+        }
+    }
+
+    if let Some(line_width) = &pending.line_width {
+        if known.line_width.as_ref() != Some(line_width) {
+            changes.push(line_width.clone());
+            new.line_width = if let Draw::LineWidth(_) = line_width { Some(line_width.clone()) } else { None };
+        }
+    }
+
+    if let Some(line_join) = pending.line_join {
+        if known.line_join != Some(line_join) { changes.push(Draw::LineJoin(line_join)); new.line_join = Some(line_join); }
+    }
+
+    if let Some(line_cap) = pending.line_cap {
+        if known.line_cap != Some(line_cap) { changes.push(Draw::LineCap(line_cap)); new.line_cap = Some(line_cap); }
+    }
+
+    if let Some(miter_limit) = pending.miter_limit {
+        if known.miter_limit != Some(miter_limit) { changes.push(Draw::MiterLimit(miter_limit)); new.miter_limit = Some(miter_limit); }
+    }
+
+    if let Some(winding_rule) = pending.winding_rule {
+        if known.winding_rule != Some(winding_rule) { changes.push(Draw::WindingRule(winding_rule)); new.winding_rule = Some(winding_rule); }
+    }
+
+    if let Some(blend_mode) = pending.blend_mode {
+        if known.blend_mode != Some(blend_mode) { changes.push(Draw::BlendMode(blend_mode)); new.blend_mode = Some(blend_mode); }
+    }
+
+    if let Some(dash_pattern) = &pending.dash_pattern {
+        if known.dash_pattern.as_ref() != Some(dash_pattern) {
+            changes.push(Draw::NewDashPattern);
+            changes.extend(dash_pattern.iter().map(|length| Draw::DashLength(*length)));
+            new.dash_pattern = Some(dash_pattern.clone());
+        }
+    }
+
+    if let Some(dash_offset) = pending.dash_offset {
+        if known.dash_offset != Some(dash_offset) { changes.push(Draw::DashOffset(dash_offset)); new.dash_offset = Some(dash_offset); }
+    }
+
+    (changes, new)
+}
+
+impl RedundantStateOptimiser {
+    ///
+    /// Creates a new optimiser
+    ///
+    fn new() -> RedundantStateOptimiser {
+        RedundantStateOptimiser {
+            target:             StateTarget::Layer(LayerId(0)),
+            known:              HashMap::new(),
+            pending:            LayerDrawingState::default(),
+            path:               vec![],
+            path_known:         false,
+            path_output:        Some(0),
+            path_generation:    0,
+            held:               None,
+            held_is_current:    false,
+            state_stack:        vec![],
+            deferred_pushes:    0,
+        }
+    }
+
+    ///
+    /// The state that the output is in for the current layer
+    ///
+    fn known(&mut self) -> &mut LayerDrawingState {
+        self.known.entry(self.target).or_insert_with(|| LayerDrawingState::default())
+    }
+
+    ///
+    /// Writes an instruction to the output, along with any `PushState` instructions that were held back
+    ///
+    fn emit(&mut self, drawing: Draw, output: &mut Vec<Draw>) {
+        self.write_deferred_pushes(output);
+        output.push(drawing);
+    }
+
+    ///
+    /// Writes out any `PushState` instructions that were held back, along with the state changes that were pending when they were made
+    ///
+    fn write_deferred_pushes(&mut self, output: &mut Vec<Draw>) {
+        let first_deferred      = self.state_stack.len() - self.deferred_pushes;
+        self.deferred_pushes    = 0;
+
+        for stack_pos in first_deferred..self.state_stack.len() {
+            let (changes, new) = state_changes(&self.state_stack[stack_pos].1, self.known());
+            output.extend(changes);
+            *self.known() = new;
+
+            // The state that will be restored is the state after the pending changes were made
+            self.state_stack[stack_pos].0 = self.known.clone();
+            output.push(Draw::PushState);
+        }
+    }
+
+    ///
+    /// Writes out the held shape, if there is one
+    ///
+    fn write_held(&mut self, output: &mut Vec<Draw>) {
+        if let Some(held) = self.held.take() {
+            self.emit(Draw::Path(PathOp::NewPath), output);
+            for path_op in held.path {
+                self.emit(Draw::Path(path_op), output);
+            }
+            self.emit(held.paint, output);
+
+            // The output path is now the held path
+            self.path_output        = if self.held_is_current { Some(self.path.len()) } else { None };
+            self.held_is_current    = false;
+        }
+    }
+
+    ///
+    /// Writes out any part of the current path that's not in the output
+    ///
+    fn write_path(&mut self, output: &mut Vec<Draw>) {
+        let path        = mem::take(&mut self.path);
+        let first_op    = match self.path_output {
+            None            => { self.emit(Draw::Path(PathOp::NewPath), output); 0 },
+            Some(first_op)  => first_op,
+        };
+
+        for path_op in path.iter().skip(first_op) {
+            self.emit(Draw::Path(*path_op), output);
+        }
+
+        self.path_output    = Some(path.len());
+        self.path           = path;
+    }
+
+    ///
+    /// Writes out any pending state changes that change the state of the output
+    ///
+    fn write_state(&mut self, output: &mut Vec<Draw>) {
+        let (changes, _) = state_changes(&self.pending, self.known());
+
+        if !changes.is_empty() {
+            // Shapes that are being held use the old state
+            self.write_held(output);
+            self.write_deferred_pushes(output);
+
+            let (changes, new) = state_changes(&self.pending, self.known());
+            output.extend(changes);
+            *self.known() = new;
+        }
+
+        self.pending = LayerDrawingState::default();
+    }
+
+    ///
+    /// Writes out everything that's been held back, ready to write an instruction that isn't handled by the optimiser
+    ///
+    fn write_all(&mut self, output: &mut Vec<Draw>) {
+        self.write_state(output);
+        self.write_held(output);
+    }
+
+    ///
+    /// Marks the output path as unknown (for after an instruction that changes the path in a way that isn't tracked)
+    ///
+    fn path_unknown(&mut self) {
+        self.path               = vec![];
+        self.path_known         = false;
+        self.path_output        = Some(0);
+        self.held_is_current    = false;
+        self.path_generation    += 1;
+    }
+
+    ///
+    /// True if strokes made with the current state can be merged, along with the distance that strokes can extend outside of their path
+    ///
+    fn stroke_margin(&mut self) -> Option<f32> {
+        let known = self.known();
+
+        match (&known.line_width, known.miter_limit, &known.dash_pattern) {
+            (Some(Draw::LineWidth(width)), Some(miter_limit), Some(dash_pattern)) => {
+                // Dashes might not restart when the paths are merged, so only solid lines are merged
+                if dash_pattern.is_empty() {
+                    Some(width.abs() * miter_limit.max(2.0))
+                } else {
+                    None
+                }
+            }
+
+            _ => None
+        }
+    }
+
+    ///
+    /// Processes a `Fill` or `Stroke` instruction
+    ///
+    fn paint(&mut self, paint: Draw, output: &mut Vec<Draw>) {
+        self.write_state(output);
+
+        if self.path_known && self.path.is_empty() {
+            // Filling or stroking an empty path draws nothing
+            return;
+        }
+
+        if self.path_known && self.path_output.is_none() {
+            // Shapes can be merged into the held shape if they're not the same path and don't overlap it
+            let bounds      = path_bounds(&self.path);
+            let margin      = if paint == Draw::Stroke { self.stroke_margin() } else { Some(0.0) };
+            let starts_new  = if let Some(PathOp::Move(_, _)) = self.path.first() { true } else { false };
+
+            let can_merge   = match (&self.held, margin) {
+                (Some(held), Some(margin))  => !self.held_is_current && starts_new && held.paint == paint && bounds_are_separate(&held.bounds, &bounds, margin),
+                _                           => false
+            };
+
+            if can_merge {
+                let held = self.held.as_mut().unwrap();
+
+                held.path.extend(self.path.iter().cloned());
+                held.bounds = held.bounds.union(&bounds);
+                return;
+            }
+
+            // Writing the held shape might write the current path (eg, when filling and stroking the same path)
+            self.write_held(output);
+        }
+
+        if self.path_known && self.path_output.is_none() {
+            // Hold this shape in case the next shape can be merged with it
+            let bounds              = path_bounds(&self.path);
+            self.held               = Some(HeldShape { path: self.path.clone(), paint: paint, bounds: bounds });
+            self.held_is_current    = true;
+        } else {
+            self.write_held(output);
+            self.write_path(output);
+            self.emit(paint, output);
+        }
+    }
+
+    ///
+    /// Processes an instruction from the input stream
+    ///
+    fn process(&mut self, drawing: Draw, output: &mut Vec<Draw>) {
+        use self::Draw::*;
+
+        match drawing {
+            // Paths are only written out when they're used
+            Path(PathOp::NewPath)                   => {
+                self.path               = vec![];
+                self.path_known         = true;
+                self.path_output        = None;
+                self.held_is_current    = false;
+                self.path_generation    += 1;
+            }
+
+            Path(path_op)                           => {
+                // The held shape can't be merged with anything once its path is changed
+                if self.held_is_current {
+                    self.write_held(output);
+                }
+
+                self.path.push(path_op);
+                self.path_generation += 1;
+            }
+
+            Fill                                    |
+            Stroke                                  => { self.paint(drawing, output); }
+
+            Clip                                    |
+            StorePath                               => {
+                self.write_all(output);
+                self.write_path(output);
+                self.emit(drawing, output);
+            }
+
+            CombinePath(_)                          => {
+                self.write_all(output);
+                self.write_path(output);
+                self.emit(drawing, output);
+                self.path_unknown();
+            }
+
+            // State changes are only written out when they change the output state
+            FillColor(_)                            |
+            FillTexture(_, _, _)                    |
+            FillGradient(_, _, _)                   |
+            FillRadialGradient(_, _, _, _)          |
+            FillConicGradient(_, _, _)              => { self.pending.fill = Some(vec![drawing]); }
+
+            FillTransform(_)                        => {
+                match self.pending.fill.clone().or_else(|| self.known().fill.clone()) {
+                    Some(mut fill)  => { fill.push(drawing); self.pending.fill = Some(fill); }
+                    None            => { self.write_all(output); self.emit(drawing, output); }
+                }
+            }
+
+            StrokeColor(_)                          |
+            StrokeTexture(_, _, _)                  |
+            StrokeGradient(_, _, _)                 => { self.pending.stroke = Some(drawing); }
+
+            LineWidth(_)                            |
+            LineWidthPixels(_)                      => { self.pending.line_width = Some(drawing); }
+            LineJoin(line_join)                     => { self.pending.line_join = Some(line_join); }
+            LineCap(line_cap)                       => { self.pending.line_cap = Some(line_cap); }
+            MiterLimit(miter_limit)                 => { self.pending.miter_limit = Some(miter_limit); }
+            WindingRule(winding_rule)               => { self.pending.winding_rule = Some(winding_rule); }
+            BlendMode(blend_mode)                   => { self.pending.blend_mode = Some(blend_mode); }
+            NewDashPattern                          => { self.pending.dash_pattern = Some(vec![]); }
+            DashOffset(offset)                      => { self.pending.dash_offset = Some(offset); }
+
+            DashLength(length)                      => {
+                match self.pending.dash_pattern.clone().or_else(|| self.known().dash_pattern.clone()) {
+                    Some(mut dash_pattern)  => { dash_pattern.push(length); self.pending.dash_pattern = Some(dash_pattern); }
+                    None                    => { self.write_all(output); self.emit(drawing, output); }
+                }
+            }
+
+            // PushState is held back until something is drawn, so pairs with nothing between them can be removed
+            PushState                               => {
+                self.write_held(output);
+
+                self.state_stack.push((self.known.clone(), self.pending.clone(), self.path_generation));
+                self.deferred_pushes += 1;
+            }
+
+            PopState                                => {
+                // Changes to the state just before it's popped have no effect
+                self.pending = LayerDrawingState::default();
+                self.write_held(output);
+
+                if let Some(path_generation) = self.state_stack.last().map(|(_, _, path_generation)| *path_generation) {
+                    if self.deferred_pushes > 0 && path_generation == self.path_generation {
+                        // Nothing happened after the PushState, so both instructions can be removed
+                        self.deferred_pushes -= 1;
+                    } else if path_generation != self.path_generation {
+                        // The path was changed, so make sure that the output has the same path
+                        self.write_path(output);
+                        self.emit(PopState, output);
+                        self.path_unknown();
+                    } else {
+                        self.emit(PopState, output);
+                    }
+
+                    // Restore the state (any changes that were pending when the state was pushed are still pending)
+                    let (known, pending, _) = self.state_stack.pop().unwrap();
+                    self.known      = known;
+                    self.pending    = pending;
+                } else {
+                    self.known = HashMap::new();
+                    self.emit(PopState, output);
+                    self.path_unknown();
+                }
+            }
+
+            // Layers and sprites have their own state
+            Layer(layer_id)                         => {
+                self.write_all(output);
+                self.emit(drawing, output);
+                self.target = StateTarget::Layer(layer_id);
+            }
+
+            Sprite(sprite_id)                       => {
+                self.write_all(output);
+                self.emit(drawing, output);
+                self.target = StateTarget::Sprite(sprite_id);
+            }
+
+            ClearLayer                              |
+            ClearSprite                             => {
+                // Clearing a layer resets its state, so any pending changes have no effect
+                self.pending = LayerDrawingState::default();
+                self.write_held(output);
+                self.emit(drawing, output);
+
+                self.known.remove(&self.target);
+                self.path_unknown();
+            }
+
+            ClearAllLayers                          => {
+                self.write_all(output);
+                self.emit(drawing, output);
+
+                self.known.retain(|target, _| if let StateTarget::Sprite(_) = target { true } else { false });
+                self.path_unknown();
+            }
+
+            MoveSpriteFrom(_)                       => {
+                self.write_all(output);
+                self.emit(drawing, output);
+
+                self.known.remove(&self.target);
+            }
+
+            Namespace(_)                            => {
+                // Sprite IDs refer to different sprites in different namespaces
+                self.write_all(output);
+                self.emit(drawing, output);
+
+                let target = self.target;
+                self.known.retain(|known_target, _| if let StateTarget::Sprite(_) = known_target { *known_target == target } else { true });
+            }
+
+            ClearCanvas(_)                          => {
+                self.pending = LayerDrawingState::default();
+                self.write_held(output);
+                self.emit(drawing, output);
+
+                *self = RedundantStateOptimiser::new();
+            }
+
+            drawing                                 => {
+                self.write_all(output);
+                self.emit(drawing, output);
+            }
+        }
+    }
+}
+
+///
+/// Removes instructions that have no effect from a stream of drawing instructions
+///
+/// State changes (colours, line widths, blend modes and so on) that set the state to its current value or that are replaced
+/// before they're used are removed, as are `PushState`/`PopState` pairs with nothing between them and fills or strokes of empty
+/// paths. Shapes that are drawn one after the other with the same attributes are merged into a single path when their bounds
+/// don't overlap, so they render identically to the original shapes.
+///
+/// Paths are only written when they're used, and some instructions are held back until the next instruction is known, so this
+/// reads as many instructions as are ready from the input stream before writing out the result.
+///
+pub fn drawing_without_redundant_state<InStream: 'static+Send+Unpin+Stream<Item=Draw>>(draw_stream: InStream) -> impl Send+Unpin+Stream<Item=Draw> {
+    generator_stream(move |yield_value| async move {
+        let mut draw_stream = draw_stream.ready_chunks(1000);
+        let mut optimiser   = RedundantStateOptimiser::new();
+
+        while let Some(drawing) = draw_stream.next().await {
+            let mut output = vec![];
+
+            for draw in drawing {
+                optimiser.process(draw, &mut output);
+            }
+
+            // Shapes can't be held back any longer than this: the next instruction might not arrive for a while
+            optimiser.write_held(&mut output);
+
+            for draw in output {
+                yield_value(draw).await;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::color::*;
+    use crate::context::*;
+    use crate::primitives::*;
+
+    use futures::stream;
+    use futures::executor;
+
+    fn optimise(drawing: Vec<Draw>) -> Vec<Draw> {
+        executor::block_on(drawing_without_redundant_state(stream::iter(drawing.into_iter())).collect())
+    }
+
+    fn count(drawing: &[Draw], item: &Draw) -> usize {
+        drawing.iter().filter(|draw| *draw == item).count()
+    }
+
+    #[test]
+    fn repeated_fill_color_is_removed() {
+        let mut drawing = vec![];
+        drawing.fill_color(Color::Rgba(1.0, 0.0, 0.0, 1.0));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 10.0);
+        drawing.fill();
+        drawing.fill_color(Color::Rgba(1.0, 0.0, 0.0, 1.0));
+        drawing.new_path();
+        drawing.rect(5.0, 5.0, 15.0, 15.0);
+        drawing.fill();
+
+        let optimised = optimise(drawing);
+
+        assert!(count(&optimised, &Draw::FillColor(Color::Rgba(1.0, 0.0, 0.0, 1.0))) == 1);
+        assert!(count(&optimised, &Draw::Fill) == 2);
+    }
+
+    #[test]
+    fn replaced_state_is_removed() {
+        let mut drawing = vec![];
+        drawing.line_width(1.0);
+        drawing.blend_mode(BlendMode::Multiply);
+        drawing.line_width(2.0);
+        drawing.blend_mode(BlendMode::SourceOver);
+        drawing.new_path();
+        drawing.move_to(0.0, 0.0);
+        drawing.line_to(10.0, 10.0);
+        drawing.stroke();
+
+        let optimised = optimise(drawing);
+
+        assert!(!optimised.contains(&Draw::LineWidth(1.0)));
+        assert!(!optimised.contains(&Draw::BlendMode(BlendMode::Multiply)));
+        assert!(optimised.contains(&Draw::LineWidth(2.0)));
+        assert!(optimised.contains(&Draw::BlendMode(BlendMode::SourceOver)));
+    }
+
+    #[test]
+    fn empty_push_pop_is_removed() {
+        let mut drawing = vec![];
+        drawing.push_state();
+        drawing.fill_color(Color::Rgba(1.0, 0.0, 0.0, 1.0));
+        drawing.push_state();
+        drawing.pop_state();
+        drawing.pop_state();
+
+        let optimised = optimise(drawing);
+
+        assert!(optimised.len() == 0);
+    }
+
+    #[test]
+    fn push_pop_around_drawing_is_kept() {
+        let mut drawing = vec![];
+        drawing.push_state();
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 10.0);
+        drawing.fill();
+        drawing.pop_state();
+
+        let optimised = optimise(drawing);
+
+        assert!(optimised.first() == Some(&Draw::PushState));
+        assert!(optimised.last() == Some(&Draw::PopState));
+        assert!(count(&optimised, &Draw::Fill) == 1);
+    }
+
+    #[test]
+    fn state_is_restored_by_pop() {
+        let mut drawing = vec![];
+        drawing.fill_color(Color::Rgba(1.0, 0.0, 0.0, 1.0));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 10.0);
+        drawing.fill();
+        drawing.push_state();
+        drawing.fill_color(Color::Rgba(0.0, 0.0, 1.0, 1.0));
+        drawing.fill();
+        drawing.pop_state();
+        drawing.fill_color(Color::Rgba(1.0, 0.0, 0.0, 1.0));
+        drawing.new_path();
+        drawing.rect(5.0, 5.0, 15.0, 15.0);
+        drawing.fill();
+
+        let optimised = optimise(drawing);
+
+        assert!(count(&optimised, &Draw::FillColor(Color::Rgba(1.0, 0.0, 0.0, 1.0))) == 1);
+        assert!(count(&optimised, &Draw::Fill) == 3);
+    }
+
+    #[test]
+    fn empty_fill_is_removed() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.fill();
+        drawing.new_path();
+        drawing.stroke();
+
+        let optimised = optimise(drawing);
+
+        assert!(optimised.len() == 0);
+    }
+
+    #[test]
+    fn separate_fills_are_merged() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 10.0);
+        drawing.fill();
+        drawing.new_path();
+        drawing.rect(20.0, 0.0, 30.0, 10.0);
+        drawing.fill();
+        drawing.new_path();
+        drawing.rect(40.0, 0.0, 50.0, 10.0);
+        drawing.fill();
+
+        let optimised = optimise(drawing);
+
+        assert!(count(&optimised, &Draw::Fill) == 1);
+        assert!(count(&optimised, &Draw::Path(PathOp::NewPath)) == 1);
+        assert!(count(&optimised, &Draw::Path(PathOp::ClosePath)) == 3);
+    }
+
+    #[test]
+    fn overlapping_fills_are_not_merged() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 10.0);
+        drawing.fill();
+        drawing.new_path();
+        drawing.rect(5.0, 5.0, 15.0, 15.0);
+        drawing.fill();
+
+        let optimised = optimise(drawing);
+
+        assert!(count(&optimised, &Draw::Fill) == 2);
+    }
+
+    #[test]
+    fn fill_then_stroke_same_path() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 10.0);
+        drawing.fill();
+        drawing.stroke();
+
+        let optimised = optimise(drawing);
+
+        assert!(count(&optimised, &Draw::Path(PathOp::NewPath)) == 1);
+        assert!(count(&optimised, &Draw::Fill) == 1);
+        assert!(count(&optimised, &Draw::Stroke) == 1);
+        assert!(optimised.last() == Some(&Draw::Stroke));
+    }
+}
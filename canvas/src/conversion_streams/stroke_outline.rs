@@ -0,0 +1,644 @@
+use super::dashed_lines::*;
+
+use crate::draw::*;
+use crate::path::*;
+use crate::color::*;
+use crate::texture::*;
+use crate::gradient::*;
+use crate::transform2d::*;
+
+use flo_stream::*;
+use futures::prelude::*;
+
+use std::f32::consts::{PI};
+use std::collections::{HashMap};
+
+///
+/// A section of a subpath
+///
+#[derive(Clone, Copy, Debug)]
+enum StrokeSegment {
+    /// A straight line to an end point
+    Line((f32, f32)),
+
+    /// A bezier curve as (cp1, cp2, end_point)
+    Curve((f32, f32), (f32, f32), (f32, f32)),
+}
+
+///
+/// A subpath that is waiting to be stroked
+///
+#[derive(Clone, Debug)]
+struct StrokeSubpath {
+    start_point:    (f32, f32),
+    curves:         Vec<StrokeSegment>,
+    closed:         bool,
+}
+
+impl StrokeSegment {
+    ///
+    /// The point where this segment finishes
+    ///
+    fn end_point(&self) -> (f32, f32) {
+        match self {
+            StrokeSegment::Line(end_point)          => *end_point,
+            StrokeSegment::Curve(_, _, end_point)   => *end_point,
+        }
+    }
+}
+
+///
+/// How the outline of a stroke should be filled
+///
+#[derive(Clone, Copy, Debug)]
+enum StrokePaint {
+    Color(Color),
+    Texture(TextureId, (f32, f32), (f32, f32)),
+    Gradient(GradientId, (f32, f32), (f32, f32)),
+}
+
+///
+/// The stroke settings for a layer
+///
+#[derive(Clone, Copy, Debug)]
+struct StrokeState {
+    paint:          StrokePaint,
+    width:          f32,
+    width_pixels:   Option<f32>,
+    join:           LineJoin,
+    cap:            LineCap,
+    miter_limit:    f32,
+}
+
+impl Default for StrokeState {
+    fn default() -> StrokeState {
+        StrokeState {
+            paint:          StrokePaint::Color(Color::Rgba(0.0, 0.0, 0.0, 1.0)),
+            width:          1.0,
+            width_pixels:   None,
+            join:           LineJoin::Round,
+            cap:            LineCap::Butt,
+            miter_limit:    4.0,
+        }
+    }
+}
+
+impl StrokeSubpath {
+    ///
+    /// Converts this subpath into a series of points, with curves subdivided so they are within `tolerance` of the original path
+    ///
+    fn flatten(&self, tolerance: f32) -> Vec<(f32, f32)> {
+        let mut points      = vec![self.start_point];
+        let mut last_point  = self.start_point;
+
+        for segment in self.curves.iter() {
+            match segment {
+                StrokeSegment::Line(end_point)              => points.push(*end_point),
+                StrokeSegment::Curve(cp1, cp2, end_point)   => flatten_curve(last_point, *cp1, *cp2, *end_point, tolerance, &mut points),
+            }
+
+            last_point = segment.end_point();
+        }
+
+        points
+    }
+
+    ///
+    /// Returns the path operations that describe this subpath
+    ///
+    fn to_path_ops(&self) -> Vec<PathOp> {
+        let mut path_ops = vec![PathOp::Move(self.start_point.0, self.start_point.1)];
+        path_ops.extend(self.curves.iter().map(|segment| match segment {
+            StrokeSegment::Line((x, y))                 => PathOp::Line(*x, *y),
+            StrokeSegment::Curve(cp1, cp2, end_point)   => PathOp::BezierCurve((*cp1, *cp2), *end_point),
+        }));
+
+        if self.closed {
+            path_ops.push(PathOp::ClosePath);
+        }
+
+        path_ops
+    }
+}
+
+#[inline] fn add(a: (f32, f32), b: (f32, f32)) -> (f32, f32)    { (a.0+b.0, a.1+b.1) }
+#[inline] fn sub(a: (f32, f32), b: (f32, f32)) -> (f32, f32)    { (a.0-b.0, a.1-b.1) }
+#[inline] fn mul(a: (f32, f32), factor: f32) -> (f32, f32)      { (a.0*factor, a.1*factor) }
+#[inline] fn dot(a: (f32, f32), b: (f32, f32)) -> f32           { a.0*b.0 + a.1*b.1 }
+#[inline] fn cross(a: (f32, f32), b: (f32, f32)) -> f32         { a.0*b.1 - a.1*b.0 }
+#[inline] fn length(a: (f32, f32)) -> f32                       { dot(a, a).sqrt() }
+
+#[inline] fn normalize(a: (f32, f32)) -> (f32, f32) {
+    let len = length(a);
+    if len > 0.0 { mul(a, 1.0/len) } else { (0.0, 0.0) }
+}
+
+///
+/// Adds the points from a bezier curve (excluding the start point) to a list of points
+///
+pub (super) fn flatten_curve(start_point: (f32, f32), cp1: (f32, f32), cp2: (f32, f32), end_point: (f32, f32), tolerance: f32, points: &mut Vec<(f32, f32)>) {
+    // Estimate the number of subdivisions from how far the control points are from being a straight line
+    let dd1         = add(sub(start_point, mul(cp1, 2.0)), cp2);
+    let dd2         = add(sub(cp1, mul(cp2, 2.0)), end_point);
+    let flatness    = f32::max(length(dd1), length(dd2));
+    let num_points  = ((0.75 * flatness / tolerance).sqrt().ceil() as usize).max(1).min(1000);
+
+    for idx in 1..=num_points {
+        let t   = (idx as f32) / (num_points as f32);
+        let mt  = 1.0 - t;
+
+        let a   = mt*mt*mt;
+        let b   = 3.0*mt*mt*t;
+        let c   = 3.0*mt*t*t;
+        let d   = t*t*t;
+
+        points.push((
+            a*start_point.0 + b*cp1.0 + c*cp2.0 + d*end_point.0,
+            a*start_point.1 + b*cp1.1 + c*cp2.1 + d*end_point.1,
+        ));
+    }
+}
+
+///
+/// Adds the points for an arc around a center point (excluding the start point) to a list of points
+///
+fn flatten_arc(center: (f32, f32), radius: f32, start_angle: f32, sweep_angle: f32, tolerance: f32, points: &mut Vec<(f32, f32)>) {
+    let max_step    = if radius > tolerance { 2.0 * (1.0 - tolerance/radius).acos() } else { PI / 2.0 };
+    let max_step    = if max_step > 0.0 { max_step } else { PI / 2.0 };
+    let num_points  = ((sweep_angle.abs() / max_step).ceil() as usize).max(1).min(256);
+
+    for idx in 1..=num_points {
+        let angle = start_angle + sweep_angle * (idx as f32) / (num_points as f32);
+        points.push((center.0 + radius*angle.cos(), center.1 + radius*angle.sin()));
+    }
+}
+
+///
+/// Adds the points that join two line segments meeting at `point` to one side of a stroke outline
+///
+/// `side` is 1.0 for the left-hand side of the line and -1.0 for the right-hand side. The incoming and outgoing
+/// directions should be normalized.
+///
+fn add_join(point: (f32, f32), dir_in: (f32, f32), dir_out: (f32, f32), side: f32, half_width: f32, join: LineJoin, miter_limit: f32, tolerance: f32, points: &mut Vec<(f32, f32)>) {
+    let normal_in   = (-dir_in.1, dir_in.0);
+    let normal_out  = (-dir_out.1, dir_out.0);
+    let offset_in   = mul(normal_in, side*half_width);
+    let offset_out  = mul(normal_out, side*half_width);
+    let turn        = cross(dir_in, dir_out);
+    let is_reversal = turn.abs() < 1e-6 && dot(dir_in, dir_out) < 0.0;
+
+    // Straight lines need no join
+    if turn.abs() < 1e-6 && !is_reversal {
+        points.push(add(point, offset_in));
+        return;
+    }
+
+    // On the inside of the turn, the outline pivots around the point itself
+    if side * turn > 0.0 && !is_reversal {
+        points.push(add(point, offset_in));
+        points.push(point);
+        points.push(add(point, offset_out));
+        return;
+    }
+
+    // Outside of the turn, apply the join style
+    let start   = add(point, offset_in);
+    let end     = add(point, offset_out);
+
+    match join {
+        LineJoin::Bevel => {
+            points.push(start);
+            points.push(end);
+        }
+
+        LineJoin::Miter => {
+            let miter_dir   = normalize(add(normal_in, normal_out));
+            let miter_cos   = dot(miter_dir, normal_in);
+
+            points.push(start);
+            if !is_reversal && miter_cos > 0.0 && 1.0/miter_cos <= miter_limit {
+                points.push(add(point, mul(miter_dir, side*half_width/miter_cos)));
+            }
+            points.push(end);
+        }
+
+        LineJoin::Round => {
+            let start_angle = offset_in.1.atan2(offset_in.0);
+            let sweep_angle = if is_reversal { -side * PI } else { turn.atan2(dot(dir_in, dir_out)) };
+
+            points.push(start);
+            flatten_arc(point, half_width, start_angle, sweep_angle, tolerance, points);
+        }
+    }
+}
+
+///
+/// Adds the points for a line cap to a stroke outline. The cap goes from the left-hand side to the right-hand side of the line
+/// at the specified point, where `direction` is the (normalized) direction leaving the end of the line.
+///
+fn add_cap(point: (f32, f32), direction: (f32, f32), half_width: f32, cap: LineCap, tolerance: f32, points: &mut Vec<(f32, f32)>) {
+    let normal  = (-direction.1, direction.0);
+    let left    = add(point, mul(normal, half_width));
+    let right   = sub(point, mul(normal, half_width));
+
+    match cap {
+        LineCap::Butt => {
+            points.push(left);
+            points.push(right);
+        }
+
+        LineCap::Square => {
+            let extend = mul(direction, half_width);
+
+            points.push(add(left, extend));
+            points.push(add(right, extend));
+        }
+
+        LineCap::Round => {
+            points.push(left);
+            flatten_arc(point, half_width, normal.1.atan2(normal.0), -PI, tolerance, points);
+        }
+    }
+}
+
+///
+/// Generates the outline of a stroked polyline, as a set of closed polygons
+///
+/// The polygons should be filled using the non-zero winding rule: they always have the same orientation so overlapping
+/// sections of the stroke will be combined. Joins and caps are subdivided so they are within `tolerance` of a true curve.
+///
+pub fn polyline_stroke_outline(points: &[(f32, f32)], closed: bool, width: f32, join: LineJoin, cap: LineCap, miter_limit: f32, tolerance: f32) -> Vec<Vec<(f32, f32)>> {
+    let half_width = width / 2.0;
+
+    if !(half_width > 0.0) {
+        return vec![];
+    }
+
+    // Remove any zero-length segments
+    let mut points = points.iter().fold(Vec::<(f32, f32)>::new(), |mut points, point| {
+        if points.last().map(|last| length(sub(*point, *last)) > 1e-6).unwrap_or(true) {
+            points.push(*point);
+        }
+        points
+    });
+
+    if closed && points.len() > 1 && length(sub(points[0], points[points.len()-1])) <= 1e-6 {
+        points.pop();
+    }
+
+    // Points with no length are drawn using just the cap
+    if points.len() < 2 {
+        return match (points.get(0), cap) {
+            (Some(point), LineCap::Round)   => {
+                let mut circle = vec![(point.0 + half_width, point.1)];
+                flatten_arc(*point, half_width, 0.0, -2.0 * PI, tolerance, &mut circle);
+                vec![circle]
+            }
+
+            (Some(point), LineCap::Square)  => {
+                let (x, y) = *point;
+                vec![vec![(x-half_width, y-half_width), (x-half_width, y+half_width), (x+half_width, y+half_width), (x+half_width, y-half_width)]]
+            }
+
+            _                               => vec![]
+        };
+    }
+
+    // Directions for each segment
+    let num_points  = points.len();
+    let num_lines   = if closed { num_points } else { num_points-1 };
+    let directions  = (0..num_lines)
+        .map(|idx| normalize(sub(points[(idx+1)%num_points], points[idx])))
+        .collect::<Vec<_>>();
+
+    // Generate the points on each side of the line
+    let mut left    = vec![];
+    let mut right   = vec![];
+
+    if closed {
+        for idx in 0..num_points {
+            let dir_in  = directions[(idx + num_lines - 1) % num_lines];
+            let dir_out = directions[idx];
+
+            add_join(points[idx], dir_in, dir_out, 1.0, half_width, join, miter_limit, tolerance, &mut left);
+            add_join(points[idx], dir_in, dir_out, -1.0, half_width, join, miter_limit, tolerance, &mut right);
+        }
+
+        // Closed paths have an outline on each side, which needs to be in opposite directions to fill the stroke
+        right.reverse();
+        vec![left, right]
+    } else {
+        let first_normal    = (-directions[0].1, directions[0].0);
+        let last_dir        = directions[num_lines-1];
+        let last_normal     = (-last_dir.1, last_dir.0);
+
+        left.push(add(points[0], mul(first_normal, half_width)));
+        right.push(sub(points[0], mul(first_normal, half_width)));
+
+        for idx in 1..(num_points-1) {
+            add_join(points[idx], directions[idx-1], directions[idx], 1.0, half_width, join, miter_limit, tolerance, &mut left);
+            add_join(points[idx], directions[idx-1], directions[idx], -1.0, half_width, join, miter_limit, tolerance, &mut right);
+        }
+
+        left.push(add(points[num_points-1], mul(last_normal, half_width)));
+        right.push(sub(points[num_points-1], mul(last_normal, half_width)));
+
+        // Open paths go up the left side, around the end cap, back down the right side and around the start cap
+        let mut outline = left;
+        add_cap(points[num_points-1], last_dir, half_width, cap, tolerance, &mut outline);
+        outline.extend(right.into_iter().rev());
+        add_cap(points[0], mul(directions[0], -1.0), half_width, cap, tolerance, &mut outline);
+
+        vec![outline]
+    }
+}
+
+///
+/// Converts a stream of drawing instructions so that any `Stroke` instruction is replaced by a filled path that describes the outline of the stroke
+///
+/// The outline honours the line width, join, cap and miter limit settings along with any dash pattern. It's generated in the same
+/// coordinate scheme as the original path, so the current transform applies to it in the same way as it would for the stroke. Strokes
+/// with a width set using `LineWidthPixels` depend on the size of the window they're rendered in, so these are left unchanged.
+///
+/// The fill is performed within a `PushState`/`PopState` pair, and the original path is restored afterwards.
+///
+pub fn drawing_with_strokes_as_fills<InStream: 'static+Send+Unpin+Stream<Item=Draw>>(draw_stream: InStream) -> impl Send+Unpin+Stream<Item=Draw> {
+    // Dashed lines are converted to paths first
+    let draw_stream = drawing_without_dashed_lines(draw_stream);
+
+    generator_stream(move |yield_value| async move {
+        let mut draw_stream = draw_stream;
+
+        // The path that will be stroked
+        let mut current_path: Vec<StrokeSubpath>    = vec![];
+
+        // The stroke settings for the current layer, and the settings for any other layer that has been used
+        let mut stroke_state                        = StrokeState::default();
+        let mut current_layer                       = LayerId(0);
+        let mut layer_state                         = HashMap::new();
+
+        // States stored by PushState
+        let mut state_stack                         = vec![];
+
+        while let Some(drawing) = draw_stream.next().await {
+            use self::Draw::*;
+            use self::PathOp::*;
+
+            // Update the path
+            if let Path(path_op) = &drawing {
+                let last_point = current_path.last()
+                    .map(|subpath| if subpath.closed { subpath.start_point } else { subpath.curves.last().map(|segment| segment.end_point()).unwrap_or(subpath.start_point) });
+
+                // Closed subpaths start a new subpath if more drawing instructions are added
+                let needs_new_subpath = current_path.last().map(|subpath| subpath.closed).unwrap_or(true);
+
+                match path_op {
+                    NewPath         => { current_path = vec![]; },
+                    Move(x, y)      => { current_path.push(StrokeSubpath { start_point: (*x, *y), curves: vec![], closed: false }); },
+                    ClosePath       => { if let Some(subpath) = current_path.last_mut() { subpath.closed = true; } },
+
+                    Line(x, y)      => {
+                        let start_point = last_point.unwrap_or((*x, *y));
+                        if needs_new_subpath { current_path.push(StrokeSubpath { start_point, curves: vec![], closed: false }); }
+
+                        if let Some(subpath) = current_path.last_mut() { subpath.curves.push(StrokeSegment::Line((*x, *y))); }
+                    }
+
+                    BezierCurve((cp1, cp2), end_point) => {
+                        let start_point = last_point.unwrap_or(*end_point);
+                        if needs_new_subpath { current_path.push(StrokeSubpath { start_point, curves: vec![], closed: false }); }
+
+                        if let Some(subpath) = current_path.last_mut() { subpath.curves.push(StrokeSegment::Curve(*cp1, *cp2, *end_point)); }
+                    }
+
+                    QuadraticCurve(cp, end_point) => {
+                        let start_point = last_point.unwrap_or(*cp);
+                        if needs_new_subpath { current_path.push(StrokeSubpath { start_point, curves: vec![], closed: false }); }
+
+                        let (cp1, cp2) = quadratic_to_bezier(start_point, *cp, *end_point);
+                        if let Some(subpath) = current_path.last_mut() { subpath.curves.push(StrokeSegment::Curve(cp1, cp2, *end_point)); }
+                    }
+
+                    Arc(center, radii, rotation, start_angle, sweep_angle) => {
+                        // The arc begins with a line from the current point, or starts a new subpath if there's no current point
+                        let arc_start   = arc_point(*center, *radii, *rotation, *start_angle);
+                        let start_point = last_point.unwrap_or(arc_start);
+
+                        if needs_new_subpath {
+                            let subpath_start = if current_path.is_empty() { arc_start } else { start_point };
+                            current_path.push(StrokeSubpath { start_point: subpath_start, curves: vec![], closed: false });
+                        }
+
+                        if let Some(subpath) = current_path.last_mut() {
+                            let last_point = subpath.curves.last().map(|segment| segment.end_point()).unwrap_or(subpath.start_point);
+                            if last_point != arc_start {
+                                subpath.curves.push(StrokeSegment::Line(arc_start));
+                            }
+
+                            subpath.curves.extend(arc_to_bezier_curves(*center, *radii, *rotation, *start_angle, *sweep_angle)
+                                .into_iter()
+                                .map(|(cp1, cp2, end_point)| StrokeSegment::Curve(cp1, cp2, end_point)));
+                        }
+                    }
+                }
+
+                yield_value(drawing).await;
+                continue;
+            }
+
+            match drawing {
+                Stroke => {
+                    if stroke_state.width_pixels.is_some() {
+                        // Pixel widths can't be converted, so pass these through
+                        yield_value(Stroke).await;
+                        continue;
+                    }
+
+                    // Generate the outline for the stroke
+                    let tolerance   = (stroke_state.width * 0.02).max(0.001);
+                    let outlines    = current_path.iter()
+                        .flat_map(|subpath| polyline_stroke_outline(&subpath.flatten(tolerance), subpath.closed, stroke_state.width, stroke_state.join, stroke_state.cap, stroke_state.miter_limit, tolerance))
+                        .filter(|outline| outline.len() > 2)
+                        .collect::<Vec<_>>();
+
+                    if outlines.is_empty() {
+                        continue;
+                    }
+
+                    // Fill the outline
+                    yield_value(PushState).await;
+                    yield_value(Path(NewPath)).await;
+
+                    for outline in outlines {
+                        let (x, y) = outline[0];
+                        yield_value(Path(Move(x, y))).await;
+
+                        for (x, y) in outline.into_iter().skip(1) {
+                            yield_value(Path(Line(x, y))).await;
+                        }
+
+                        yield_value(Path(ClosePath)).await;
+                    }
+
+                    match stroke_state.paint {
+                        StrokePaint::Color(color)                   => { yield_value(FillColor(color)).await; }
+                        StrokePaint::Texture(texture_id, min, max)  => { yield_value(FillTexture(texture_id, min, max)).await; yield_value(FillTransform(Transform2D::identity())).await; }
+                        StrokePaint::Gradient(gradient_id, min, max)=> { yield_value(FillGradient(gradient_id, min, max)).await; yield_value(FillTransform(Transform2D::identity())).await; }
+                    }
+
+                    yield_value(WindingRule(self::WindingRule::NonZero)).await;
+                    yield_value(Fill).await;
+                    yield_value(PopState).await;
+
+                    // Restore the original path
+                    yield_value(Path(NewPath)).await;
+
+                    for path_op in current_path.iter().flat_map(|subpath| subpath.to_path_ops()) {
+                        yield_value(Path(path_op)).await;
+                    }
+                }
+
+                LineWidth(width)                            => { stroke_state.width = width; stroke_state.width_pixels = None; yield_value(LineWidth(width)).await; }
+                LineWidthPixels(width)                      => { stroke_state.width_pixels = Some(width); yield_value(LineWidthPixels(width)).await; }
+                LineJoin(join)                              => { stroke_state.join = join; yield_value(LineJoin(join)).await; }
+                LineCap(cap)                                => { stroke_state.cap = cap; yield_value(LineCap(cap)).await; }
+                MiterLimit(limit)                           => { stroke_state.miter_limit = limit; yield_value(MiterLimit(limit)).await; }
+                StrokeColor(color)                          => { stroke_state.paint = StrokePaint::Color(color); yield_value(StrokeColor(color)).await; }
+                StrokeTexture(texture_id, min, max)         => { stroke_state.paint = StrokePaint::Texture(texture_id, min, max); yield_value(StrokeTexture(texture_id, min, max)).await; }
+                StrokeGradient(gradient_id, min, max)       => { stroke_state.paint = StrokePaint::Gradient(gradient_id, min, max); yield_value(StrokeGradient(gradient_id, min, max)).await; }
+
+                PushState => {
+                    state_stack.push((current_path.clone(), stroke_state));
+                    yield_value(PushState).await;
+                }
+
+                PopState => {
+                    if let Some((path, state)) = state_stack.pop() {
+                        current_path = path;
+                        stroke_state = state;
+                    }
+                    yield_value(PopState).await;
+                }
+
+                Layer(layer_id) => {
+                    // Each layer has its own stroke settings
+                    layer_state.insert(current_layer, stroke_state);
+                    stroke_state    = layer_state.remove(&layer_id).unwrap_or_default();
+                    current_layer   = layer_id;
+
+                    yield_value(Layer(layer_id)).await;
+                }
+
+                ClearCanvas(color) => {
+                    current_path    = vec![];
+                    stroke_state    = StrokeState::default();
+                    current_layer   = LayerId(0);
+                    layer_state     = HashMap::new();
+                    state_stack     = vec![];
+
+                    yield_value(ClearCanvas(color)).await;
+                }
+
+                drawing => {
+                    // Pass the drawing on
+                    yield_value(drawing).await;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::stream;
+    use futures::executor;
+
+    #[test]
+    fn fill_path_is_unchanged() {
+        let input_drawing = vec![
+            Draw::Path(PathOp::NewPath),
+            Draw::Path(PathOp::Move(10.0, 10.0)),
+            Draw::Path(PathOp::Line(10.0, 100.0)),
+            Draw::Path(PathOp::Line(100.0, 100.0)),
+            Draw::Path(PathOp::ClosePath),
+            Draw::Fill,
+        ];
+
+        executor::block_on(async move {
+            let output_drawing = drawing_with_strokes_as_fills(stream::iter(input_drawing.clone().into_iter())).collect::<Vec<_>>().await;
+
+            assert!(output_drawing == input_drawing);
+        });
+    }
+
+    #[test]
+    fn stroke_straight_line() {
+        let input_drawing = vec![
+            Draw::LineWidth(10.0),
+            Draw::LineCap(LineCap::Butt),
+            Draw::StrokeColor(Color::Rgba(1.0, 0.0, 0.0, 1.0)),
+            Draw::Path(PathOp::NewPath),
+            Draw::Path(PathOp::Move(0.0, 0.0)),
+            Draw::Path(PathOp::Line(100.0, 0.0)),
+            Draw::Stroke,
+        ];
+
+        executor::block_on(async move {
+            let output_drawing = drawing_with_strokes_as_fills(stream::iter(input_drawing.into_iter())).collect::<Vec<_>>().await;
+
+            // No stroke instructions should be left
+            assert!(!output_drawing.contains(&Draw::Stroke));
+
+            // Should fill a rectangle using the stroke colour
+            let fill_pos = output_drawing.iter().position(|draw| draw == &Draw::Fill).unwrap();
+            assert!(output_drawing[0..fill_pos].contains(&Draw::FillColor(Color::Rgba(1.0, 0.0, 0.0, 1.0))));
+            assert!(output_drawing[0..fill_pos].contains(&Draw::WindingRule(WindingRule::NonZero)));
+
+            let outline = output_drawing[0..fill_pos].iter()
+                .flat_map(|draw| match draw {
+                    Draw::Path(PathOp::Move(x, y))  => Some((*x, *y)),
+                    Draw::Path(PathOp::Line(x, y))  => Some((*x, *y)),
+                    _                               => None
+                })
+                .collect::<Vec<_>>();
+
+            assert!(outline == vec![(0.0, 5.0), (100.0, 5.0), (100.0, 5.0), (100.0, -5.0), (100.0, -5.0), (0.0, -5.0), (0.0, -5.0), (0.0, 5.0)]);
+
+            // The original path should be restored afterwards
+            assert!(output_drawing[fill_pos+1] == Draw::PopState);
+            assert!(output_drawing[fill_pos+2] == Draw::Path(PathOp::NewPath));
+            assert!(output_drawing[fill_pos+3] == Draw::Path(PathOp::Move(0.0, 0.0)));
+            assert!(output_drawing[fill_pos+4] == Draw::Path(PathOp::Line(100.0, 0.0)));
+        });
+    }
+
+    #[test]
+    fn miter_join_outline() {
+        let outline = polyline_stroke_outline(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0)], false, 10.0, LineJoin::Miter, LineCap::Butt, 4.0, 0.1);
+
+        assert!(outline.len() == 1);
+
+        // The outside of the corner is on the right-hand side of the line, and should be mitered
+        assert!(outline[0].iter().any(|(x, y)| (x-105.0).abs() < 0.001 && (y+5.0).abs() < 0.001));
+
+        // The inside of the corner pivots around the corner point
+        assert!(outline[0].iter().any(|(x, y)| (x-100.0).abs() < 0.001 && y.abs() < 0.001));
+    }
+
+    #[test]
+    fn closed_path_has_two_outlines() {
+        let outline = polyline_stroke_outline(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)], true, 10.0, LineJoin::Bevel, LineCap::Butt, 4.0, 0.1);
+
+        assert!(outline.len() == 2);
+    }
+
+    #[test]
+    fn round_cap_on_point() {
+        let outline = polyline_stroke_outline(&[(10.0, 10.0)], false, 10.0, LineJoin::Round, LineCap::Round, 4.0, 0.1);
+
+        assert!(outline.len() == 1);
+        assert!(outline[0].iter().all(|(x, y)| ((x-10.0)*(x-10.0) + (y-10.0)*(y-10.0)).sqrt() - 5.0 < 0.001));
+    }
+}
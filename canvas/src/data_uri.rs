@@ -0,0 +1,69 @@
+use crate::texture::*;
+
+/// The characters used for base64 encoding
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+///
+/// Encodes a set of bytes as base64 (for use in a data URI)
+///
+pub (crate) fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len()+2)/3*4);
+
+    for chunk in bytes.chunks(3) {
+        let b0  = chunk[0] as u32;
+        let b1  = chunk.get(1).copied().unwrap_or(0) as u32;
+        let b2  = chunk.get(2).copied().unwrap_or(0) as u32;
+        let val = (b0<<16) | (b1<<8) | b2;
+
+        encoded.push(BASE64_CHARS[((val>>18) & 0x3f) as usize] as char);
+        encoded.push(BASE64_CHARS[((val>>12) & 0x3f) as usize] as char);
+        encoded.push(if chunk.len() > 1 { BASE64_CHARS[((val>>6) & 0x3f) as usize] as char } else { '=' });
+        encoded.push(if chunk.len() > 2 { BASE64_CHARS[(val & 0x3f) as usize] as char } else { '=' });
+    }
+
+    encoded
+}
+
+///
+/// Encodes the pixels of a RGBA texture as a PNG data URI
+///
+pub (crate) fn png_data_uri(TextureSize(width, height): TextureSize, pixels: &[u8]) -> Option<String> {
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let mut png_data = vec![];
+
+    {
+        let mut encoder = png::Encoder::new(&mut png_data, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().ok()?;
+        writer.write_image_data(pixels).ok()?;
+    }
+
+    Some(format!("data:image/png;base64,{}", base64(&png_data)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_base64() {
+        assert!(base64(b"") == "");
+        assert!(base64(b"f") == "Zg==");
+        assert!(base64(b"fo") == "Zm8=");
+        assert!(base64(b"foo") == "Zm9v");
+        assert!(base64(b"foobar") == "Zm9vYmFy");
+    }
+
+    #[test]
+    fn encode_png() {
+        let uri = png_data_uri(TextureSize(2, 2), &[255; 16]).unwrap();
+
+        assert!(uri.starts_with("data:image/png;base64,iVBORw0KGgo"));
+        assert!(png_data_uri(TextureSize(0, 2), &[]).is_none());
+    }
+}
@@ -62,8 +62,18 @@ struct DecodeString {
 /// Represents the state of an operation decoding a list of glyph positions
 ///
 struct DecodeGlyphPositions {
-    length: PartialResult<u64>,
-    glyphs: PartialResult<Vec<GlyphPosition>>
+    length:     PartialResult<u64>,
+    glyphs:     PartialResult<Vec<GlyphPosition>>,
+    decoded:    Vec<GlyphPosition>
+}
+
+///
+/// Represents the state of an operation decoding a list of font IDs
+///
+struct DecodeFontIds {
+    length:     PartialResult<u64>,
+    next_id:    String,
+    font_ids:   Vec<FontId>
 }
 
 ///
@@ -101,6 +111,16 @@ impl DecodeString {
         }
     }
 
+    ///
+    /// Returns the length of the string, if it has been decoded
+    ///
+    #[inline] fn declared_length(&self) -> Option<u64> {
+        match &self.length {
+            PartialResult::FullMatch(length)    => Some(*length),
+            PartialResult::MatchMore(_)         => None
+        }
+    }
+
     ///
     /// Returns the string matched by this decoder (once it's ready)
     ///
@@ -175,6 +195,16 @@ impl DecodeBytes {
         }
     }
 
+    ///
+    /// Returns the number of bytes being decoded, if it has been decoded
+    ///
+    #[inline] fn declared_length(&self) -> Option<u64> {
+        match &self.length {
+            PartialResult::FullMatch(length)    => Some(*length),
+            PartialResult::MatchMore(_)         => None
+        }
+    }
+
     ///
     /// Returns the string matched by this decoder (once it's ready)
     ///
@@ -260,14 +290,68 @@ impl DecodeBytes {
     }
 }
 
+impl DecodeFontIds {
+    ///
+    /// Creates a new font ID list decoder that has matched 0 characters
+    ///
+    fn new() -> DecodeFontIds {
+        DecodeFontIds {
+            length:     PartialResult::new(),
+            next_id:    String::new(),
+            font_ids:   vec![]
+        }
+    }
+
+    ///
+    /// Indicates if this font ID list decoder is ready or not
+    ///
+    #[inline] fn ready(&self) -> bool {
+        match &self.length {
+            PartialResult::FullMatch(length)    => (self.font_ids.len() as u64) >= *length,
+            PartialResult::MatchMore(_)         => false
+        }
+    }
+
+    ///
+    /// Returns the font IDs matched by this decoder
+    ///
+    #[inline] fn to_font_ids(self) -> Vec<FontId> {
+        self.font_ids
+    }
+
+    ///
+    /// Decodes a single character and returns the new state of the decoder
+    ///
+    fn decode(mut self, chr: char) -> Result<DecodeFontIds, DecoderError> {
+        match self.length {
+            PartialResult::MatchMore(so_far)    => {
+                self.length = CanvasDecoder::decode_compact_id(chr, so_far)?;
+            }
+
+            PartialResult::FullMatch(length)    => {
+                self.length = PartialResult::FullMatch(length);
+
+                // Each font ID is added to the list as soon as it's complete
+                match CanvasDecoder::decode_compact_id(chr, mem::take(&mut self.next_id))? {
+                    PartialResult::FullMatch(font_id)   => { self.font_ids.push(FontId(font_id)); }
+                    PartialResult::MatchMore(so_far)    => { self.next_id = so_far; }
+                }
+            }
+        }
+
+        Ok(self)
+    }
+}
+
 impl DecodeGlyphPositions {
     ///
     /// Creates a new string decoder that has matched 0 characters
     ///
     fn new() -> DecodeGlyphPositions {
         DecodeGlyphPositions {
-            length: PartialResult::new(),
-            glyphs: PartialResult::new()
+            length:     PartialResult::new(),
+            glyphs:     PartialResult::new(),
+            decoded:    vec![]
         }
     }
 
@@ -281,6 +365,16 @@ impl DecodeGlyphPositions {
         }
     }
 
+    ///
+    /// Returns the number of glyphs being decoded, if it has been decoded
+    ///
+    #[inline] fn declared_length(&self) -> Option<u64> {
+        match &self.length {
+            PartialResult::FullMatch(length)    => Some(*length),
+            PartialResult::MatchMore(_)         => None
+        }
+    }
+
     ///
     /// Returns the string matched by this decoder (once it's ready)
     ///
@@ -322,25 +416,24 @@ impl DecodeGlyphPositions {
             PartialResult::MatchMore(mut string)    => {
                 string.push(chr);
 
-                if string.len() >= length * 24 {
+                // Each glyph consists of a glyph ID, an x and y coord and a em_size, and is decoded as soon as all of its characters have arrived
+                if string.len() >= 24 {
                     let mut chrs    = string.chars();
-                    let mut glyphs  = vec![];
-
-                    // Each glyph consists of a glyph ID, an x and y coord and a em_size
-                    for _ in 0..length {
-                        let id      = CanvasDecoder::decode_u32(&mut chrs)?;
-                        let x       = CanvasDecoder::decode_f32(&mut chrs)?;
-                        let y       = CanvasDecoder::decode_f32(&mut chrs)?;
-                        let em_size = CanvasDecoder::decode_f32(&mut chrs)?;
-
-                        glyphs.push(GlyphPosition {
-                            id:         GlyphId(id),
-                            location:   (x, y),
-                            em_size:    em_size
-                        });
-                    }
+                    let id          = CanvasDecoder::decode_u32(&mut chrs)?;
+                    let x           = CanvasDecoder::decode_f32(&mut chrs)?;
+                    let y           = CanvasDecoder::decode_f32(&mut chrs)?;
+                    let em_size     = CanvasDecoder::decode_f32(&mut chrs)?;
+
+                    self.decoded.push(GlyphPosition {
+                        id:         GlyphId(id),
+                        location:   (x, y),
+                        em_size:    em_size
+                    });
+                    string.clear();
+                }
 
-                    self.glyphs = PartialResult::FullMatch(glyphs);
+                if self.decoded.len() >= length {
+                    self.glyphs = PartialResult::FullMatch(mem::take(&mut self.decoded));
                 } else {
                     self.glyphs = PartialResult::MatchMore(string);
                 }
@@ -357,6 +450,7 @@ impl DecodeGlyphPositions {
 enum DecoderState {
     None,
     Error,
+    Resync,
 
     New,                                        // 'N'
     LineStyle,                                  // 'L'
@@ -365,27 +459,35 @@ enum DecoderState {
     Sprite,                                     // 's'
     Transform,                                  // 'T'
     State,                                      // 'Z'
+    StatePathArithmetic,                        // 'ZA' (op)
 
     ClearCanvas(String),                        // 'NA' (r, g, b, a)
 
     Move(String),                               // m (x, y)
     Line(String),                               // l (x, y)
     BezierCurve(String),                        // c (x, y, x, y, x, y)
+    QuadraticCurve(String),                     // q (x, y, x, y)
+    Arc(String),                                // a (x, y, rx, ry, rotation, start, sweep)
 
     LineStyleWidth(String),                     // 'Lw' (w)
     LineStyleWidthPixels(String),               // 'Lp' (w)
     LineStyleJoin(String),                      // 'Lj' (j)
+    LineStyleMiterLimit(String),                // 'Lm' (limit)
     LineStyleCap(String),                       // 'Lc' (c)
     WindingRule,                                // 'W' (r)
 
     DashLength(String),                         // 'Dl' (len)
     DashOffset(String),                         // 'Do' (offset)
 
-    ColorStroke(String),                        // 'Cs' (r, g, b, a)
-    ColorFill(String),                          // 'Cf' (r, g, b, a)
-    ColorTexture(DecodeTextureId, String),      // 'Ct' (texture_id, x1, y1, x2, y2)
-    ColorGradient(DecodeGradientId, String),    // 'Cg' (gradient_id, x1, y1, x2, y2)
-    ColorTransform(String),                     // 'CT' (transform)
+    ColorStroke(String),                           // 'Cs' (r, g, b, a)
+    ColorStrokeTexture(DecodeTextureId, String),   // 'CX' (texture_id, x1, y1, x2, y2)
+    ColorStrokeGradient(DecodeGradientId, String), // 'CG' (gradient_id, x1, y1, x2, y2)
+    ColorFill(String),                             // 'Cf' (r, g, b, a)
+    ColorTexture(DecodeTextureId, String),         // 'Ct' (texture_id, x1, y1, x2, y2)
+    ColorGradient(DecodeGradientId, String),       // 'Cg' (gradient_id, x1, y1, x2, y2)
+    ColorRadialGradient(DecodeGradientId, String), // 'Cr' (gradient_id, x, y, radius, focus)
+    ColorConicGradient(DecodeGradientId, String),  // 'Cc' (gradient_id, x, y, start_angle)
+    ColorTransform(String),                        // 'CT' (transform)
 
     BlendMode(String),                          // 'M' (mode)
 
@@ -416,9 +518,13 @@ enum DecoderState {
     FontDrawing,                                                        // 't'
     FontDrawText(DecodeFontId, DecodeString, String),                   // 'tT' (font_id, string, x, y)
     FontBeginLayout(String),                                            // 'tl' (x, y, align)
+    FontBeginParagraph(String),                                         // 'tp' (x, y, width, align, line_height, spacing, overflow, max_height)
 
     FontOp(DecodeFontId),                                               // 'f' (id, op)
     FontOpSize(FontId, String),                                         // 'f<id>S' (size)
+    FontOpFallback(FontId, DecodeFontIds),                              // 'f<id>F' (len) (font ids)
+    FontOpFeature(FontId, String),                                      // 'f<id>O' (tag, 'Y' or 'N')
+    FontOpVariation(FontId, String),                                    // 'f<id>V' (tag, value)
     FontOpData(FontId),                                                 // 'f<id>d'
     FontOpTtf(FontId, DecodeBytes),                                     // 'f<id>dT' (bytes)
     FontOpLayoutText(FontId, DecodeString),                             // 'f<id>L' (string)
@@ -436,6 +542,7 @@ enum DecoderState {
     GradientOp(DecodeGradientId),                                       // 'G' (id, op)
     GradientOpNew(GradientId, String),                                  // 'G<id>N' (r, g, b, a)
     GradientOpAddStop(GradientId, String),                              // 'G<id>S' (pos, r, g, b, a)
+    GradientOpSpread(GradientId),                                       // 'G<id>P' (spread)
 }
 
 ///
@@ -463,13 +570,137 @@ pub enum DecoderError {
 
     /// The decoder was expecting a state as a partial match but it was completed
     UnexpectedlyComplete,
+
+    /// A byte in a binary-encoded drawing was not valid for the current state of the decoder
+    InvalidByte(u8),
+
+    /// A string in a binary-encoded drawing was not valid UTF-8
+    InvalidString,
+
+    /// A canvas file did not start with the expected header
+    NotACanvasFile,
+
+    /// A canvas file was written using a version of the file format that is not supported (major, minor)
+    UnsupportedVersion(u16, u16),
+
+    /// A canvas file declares that it uses features that are not supported (the flags for the unsupported features)
+    UnsupportedFeatures(u64),
+
+    /// A canvas file contains an instruction that is not supported (the first byte of the instruction)
+    UnknownOpcode(u8),
+
+    /// An instruction in a canvas file did not match the length that was recorded for it
+    InvalidFrame,
+
+    /// The drawing exceeded one of the limits set for the decoder
+    LimitExceeded(DecoderLimit),
+}
+
+///
+/// The limits that can be set on a `CanvasDecoder`
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecoderLimit {
+    /// A texture was too large (either in a `Create` operation or in the bytes supplied to `SetBytes`)
+    TextureBytes,
+
+    /// The data for a font was too large
+    FontBytes,
+
+    /// A string or a list of glyphs was too long
+    StringLength,
+
+    /// A path contained too many operations
+    PathLength,
+}
+
+///
+/// Limits on the size of the data that a `CanvasDecoder` will accept
+///
+/// These are used to reject drawings from untrusted sources that would otherwise make the decoder (or whatever is rendering
+/// the drawing) allocate an excessive amount of memory. Sizes are checked as soon as they are decoded, before the data that
+/// follows them is read.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecoderLimits {
+    /// The maximum number of bytes in a texture (this is checked against the bytes sent to `SetBytes`, and the size of textures that are created)
+    pub max_texture_bytes: u64,
+
+    /// The maximum number of bytes in the data for a font
+    pub max_font_bytes: u64,
+
+    /// The maximum length of a string, or the maximum number of glyphs in a `DrawGlyphs` operation
+    pub max_string_length: u64,
+
+    /// The maximum number of path operations that can follow a `NewPath`
+    pub max_path_length: u64,
+}
+
+impl DecoderLimits {
+    ///
+    /// Limits that allow drawings of any size (this is the default for a `CanvasDecoder`)
+    ///
+    pub fn unlimited() -> DecoderLimits {
+        DecoderLimits {
+            max_texture_bytes:  u64::MAX,
+            max_font_bytes:     u64::MAX,
+            max_string_length:  u64::MAX,
+            max_path_length:    u64::MAX,
+        }
+    }
+
+    ///
+    /// Limits suitable for decoding drawings from an untrusted source
+    ///
+    pub fn untrusted() -> DecoderLimits {
+        DecoderLimits {
+            max_texture_bytes:  4096 * 4096 * 4,
+            max_font_bytes:     16 * 1024 * 1024,
+            max_string_length:  65536,
+            max_path_length:    1_000_000,
+        }
+    }
+}
+
+impl Default for DecoderLimits {
+    fn default() -> DecoderLimits {
+        DecoderLimits::unlimited()
+    }
+}
+
+///
+/// An error from the decoder, along with the position of the character that caused it
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PositionedDecoderError {
+    /// The index of the character (counting from 0) where the error was found
+    pub position: usize,
+
+    /// The error that occurred
+    pub error: DecoderError,
 }
 
 ///
 /// Represents a (stateful) canvas decoder
 ///
 pub struct CanvasDecoder {
-    state: DecoderState
+    /// The current state of the decoder
+    state: DecoderState,
+
+    /// The limits on the size of the data that this decoder will accept
+    limits: DecoderLimits,
+
+    /// True if the decoder should skip to the next command after an error instead of stopping
+    resync: bool,
+
+    /// The number of characters that have been passed to the decoder
+    position: usize,
+
+    /// The position of the character that caused the most recent error
+    error_position: Option<usize>,
+
+    /// The number of path operations since the last `NewPath`
+    path_length: u64,
 }
 
 impl CanvasDecoder {
@@ -478,14 +709,129 @@ impl CanvasDecoder {
     ///
     pub fn new() -> CanvasDecoder {
         CanvasDecoder {
-            state: DecoderState::None
+            state:          DecoderState::None,
+            limits:         DecoderLimits::unlimited(),
+            resync:         false,
+            position:       0,
+            error_position: None,
+            path_length:    0,
         }
     }
 
+    ///
+    /// Sets the limits on the size of the data that this decoder will accept
+    ///
+    pub fn with_limits(mut self, limits: DecoderLimits) -> CanvasDecoder {
+        self.limits = limits;
+        self
+    }
+
+    ///
+    /// Sets whether or not the decoder should carry on after an error
+    ///
+    /// By default, the decoder stops after the first error and returns `IsInErrorState` for any further characters. In
+    /// resync mode, the decoder reports the error and then skips characters until the next whitespace character, which
+    /// is where the next command starts if the drawing was encoded from a `Vec<Draw>` (or otherwise separates its
+    /// commands with newlines).
+    ///
+    pub fn with_resync(mut self, resync: bool) -> CanvasDecoder {
+        self.resync = resync;
+        self
+    }
+
+    ///
+    /// Returns the number of characters that have been passed to this decoder
+    ///
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    ///
+    /// Returns the position of the character that caused the most recent error
+    ///
+    pub fn error_position(&self) -> Option<usize> {
+        self.error_position
+    }
+
     ///
     /// Decodes a character, returning the next Draw operation if there is one
     ///
     pub fn decode(&mut self, next_chr: char) -> Result<Option<Draw>, DecoderError> {
+        let position    = self.position;
+        self.position   += 1;
+
+        let result = self.decode_character(next_chr)
+            .and_then(|draw| self.check_limits(draw));
+
+        if let Err(err) = &result {
+            if *err != DecoderError::IsInErrorState {
+                self.error_position = Some(position);
+            }
+
+            self.state = if !self.resync {
+                DecoderState::Error
+            } else if matches!(self.state, DecoderState::None) || next_chr == '\n' || next_chr == '\r' || next_chr == ' ' {
+                // The command is already finished, so there's nothing to skip
+                DecoderState::None
+            } else {
+                DecoderState::Resync
+            };
+        }
+
+        result
+    }
+
+    ///
+    /// Returns an error if a length in the current state or the drawing instruction that was just decoded exceeds the limits for this decoder
+    ///
+    fn check_limits(&mut self, draw: Option<Draw>) -> Result<Option<Draw>, DecoderError> {
+        // Check the lengths of any data that's being decoded
+        let (length, max_length, limit) = match &self.state {
+            DecoderState::FontOpTtf(_, bytes)               => (bytes.declared_length(), self.limits.max_font_bytes, DecoderLimit::FontBytes),
+            DecoderState::TextureOpSetBytes(_, _, bytes)    => (bytes.declared_length(), self.limits.max_texture_bytes, DecoderLimit::TextureBytes),
+            DecoderState::FontDrawText(_, string, _)        => (string.declared_length(), self.limits.max_string_length, DecoderLimit::StringLength),
+            DecoderState::FontOpLayoutText(_, string)       => (string.declared_length(), self.limits.max_string_length, DecoderLimit::StringLength),
+            DecoderState::FontOpDrawGlyphs(_, glyphs)       => (glyphs.declared_length(), self.limits.max_string_length, DecoderLimit::StringLength),
+            _                                               => (None, u64::MAX, DecoderLimit::StringLength)
+        };
+
+        if let Some(length) = length {
+            if length > max_length {
+                return Err(DecoderError::LimitExceeded(limit));
+            }
+        }
+
+        // Check the instruction that was decoded
+        match &draw {
+            Some(Draw::Path(PathOp::NewPath))   => { self.path_length = 0; }
+
+            Some(Draw::Path(_))                 => {
+                self.path_length = self.path_length.saturating_add(1);
+
+                if self.path_length > self.limits.max_path_length {
+                    return Err(DecoderError::LimitExceeded(DecoderLimit::PathLength));
+                }
+            }
+
+            Some(Draw::Texture(_, TextureOp::Create(TextureSize(w, h), _))) => {
+                // A texture whose size can't be represented is always over the limit
+                let texture_bytes = (*w as u64).checked_mul(*h as u64).and_then(|pixels| pixels.checked_mul(4));
+
+                if texture_bytes.map(|bytes| bytes > self.limits.max_texture_bytes).unwrap_or(true) {
+                    return Err(DecoderError::LimitExceeded(DecoderLimit::TextureBytes));
+                }
+            }
+
+            _ => { }
+        }
+
+        Ok(draw)
+    }
+
+    ///
+    /// Decodes a character (without checking the limits), returning the next Draw operation if there is one
+    ///
+    fn decode_character(&mut self, next_chr: char) -> Result<Option<Draw>, DecoderError> {
         use self::DecoderState::*;
 
         // Next state depends on the character and the current state
@@ -495,6 +841,7 @@ impl CanvasDecoder {
         let (next_state, result) = match state {
             None                            => Self::decode_none(next_chr)?,
             Error                           => Err(DecoderError::IsInErrorState)?,
+            Resync                          => Self::decode_resync(next_chr)?,
 
             New                             => Self::decode_new(next_chr)?,
             LineStyle                       => Self::decode_line_style(next_chr)?,
@@ -503,14 +850,18 @@ impl CanvasDecoder {
             Sprite                          => Self::decode_sprite(next_chr)?,
             Transform                       => Self::decode_transform(next_chr)?,
             State                           => Self::decode_state(next_chr)?,
+            StatePathArithmetic             => Self::decode_path_arithmetic(next_chr)?,
 
             Move(param)                     => Self::decode_move(next_chr, param)?,
             Line(param)                     => Self::decode_line(next_chr, param)?,
             BezierCurve(param)              => Self::decode_bezier_curve(next_chr, param)?,
+            QuadraticCurve(param)           => Self::decode_quadratic_curve(next_chr, param)?,
+            Arc(param)                      => Self::decode_arc(next_chr, param)?,
 
             LineStyleWidth(param)           => Self::decode_line_width(next_chr, param)?,
             LineStyleWidthPixels(param)     => Self::decode_line_width_pixels(next_chr, param)?,
             LineStyleJoin(param)            => Self::decode_line_style_join(next_chr, param)?,
+            LineStyleMiterLimit(param)      => Self::decode_line_style_miter_limit(next_chr, param)?,
             LineStyleCap(param)             => Self::decode_line_style_cap(next_chr, param)?,
             WindingRule                     => Self::decode_winding_rule(next_chr)?,
 
@@ -520,9 +871,13 @@ impl CanvasDecoder {
             ClearCanvas(param)              => Self::decode_clear_canvas(next_chr, param)?,
 
             ColorStroke(param)              => Self::decode_color_stroke(next_chr, param)?,
+            ColorStrokeTexture(id, param)   => Self::decode_color_stroke_texture(next_chr, id, param)?,
+            ColorStrokeGradient(id, param)  => Self::decode_color_stroke_gradient(next_chr, id, param)?,
             ColorFill(param)                => Self::decode_color_fill(next_chr, param)?,
             ColorTexture(id, param)         => Self::decode_color_texture(next_chr, id, param)?,
             ColorGradient(id, param)        => Self::decode_color_gradient(next_chr, id, param)?,
+            ColorRadialGradient(id, param)  => Self::decode_color_radial_gradient(next_chr, id, param)?,
+            ColorConicGradient(id, param)   => Self::decode_color_conic_gradient(next_chr, id, param)?,
             ColorTransform(param)           => Self::decode_color_transform(next_chr, param)?,
 
             BlendMode(param)                => Self::decode_blend_mode(next_chr, param)?,
@@ -554,9 +909,13 @@ impl CanvasDecoder {
             FontDrawing                                             => Self::decode_font_drawing(next_chr)?,
             FontDrawText(font_id, string_decode, coords)            => Self::decode_font_draw_text(next_chr, font_id, string_decode, coords)?,
             FontBeginLayout(param)                                  => Self::decode_font_begin_layout(next_chr, param)?,
+            FontBeginParagraph(param)                               => Self::decode_font_begin_paragraph(next_chr, param)?,
 
             FontOp(font_id)                                         => Self::decode_font_op(next_chr, font_id)?,
             FontOpSize(font_id, size)                               => Self::decode_font_op_size(next_chr, font_id, size)?,
+            FontOpFallback(font_id, font_ids)                       => Self::decode_font_op_fallback(next_chr, font_id, font_ids)?,
+            FontOpFeature(font_id, param)                           => Self::decode_font_op_feature(next_chr, font_id, param)?,
+            FontOpVariation(font_id, param)                         => Self::decode_font_op_variation(next_chr, font_id, param)?,
             FontOpData(font_id)                                     => Self::decode_font_op_data(next_chr, font_id)?,
             FontOpTtf(font_id, bytes)                               => Self::decode_font_data_ttf(next_chr, font_id, bytes)?,
             FontOpLayoutText(font_id, string)                       => Self::decode_font_op_layout(next_chr, font_id, string)?,
@@ -574,12 +933,23 @@ impl CanvasDecoder {
             GradientOp(gradient_id)                                 => Self::decode_gradient_op(next_chr, gradient_id)?,     
             GradientOpNew(gradient_id, param)                       => Self::decode_gradient_new(next_chr, gradient_id, param)?,
             GradientOpAddStop(gradient_id, param)                   => Self::decode_gradient_add_stop(next_chr, gradient_id, param)?,
+            GradientOpSpread(gradient_id)                           => Self::decode_gradient_spread(next_chr, gradient_id)?,
         };
 
         self.state = next_state;
         Ok(result)
     }
 
+    ///
+    /// Skips characters after an error until the start of the next command
+    ///
+    #[inline] fn decode_resync(next_chr: char) -> Result<(DecoderState, Option<Draw>), DecoderError> {
+        match next_chr {
+            '\n' | '\r' | ' ' => Ok((DecoderState::None, None)),
+            _               => Ok((DecoderState::Resync, None)),
+        }
+    }
+
     ///
     /// Matches the first character of a canvas item
     ///
@@ -609,6 +979,8 @@ impl CanvasDecoder {
             'm' => Ok((DecoderState::Move(String::new()), None)),
             'l' => Ok((DecoderState::Line(String::new()), None)),
             'c' => Ok((DecoderState::BezierCurve(String::new()), None)),
+            'q' => Ok((DecoderState::QuadraticCurve(String::new()), None)),
+            'a' => Ok((DecoderState::Arc(String::new()), None)),
             'M' => Ok((DecoderState::BlendMode(String::new()), None)),
 
             't' => Ok((DecoderState::FontDrawing, None)),
@@ -654,6 +1026,7 @@ impl CanvasDecoder {
             'w'     => Ok((DecoderState::LineStyleWidth(String::new()), None)),
             'p'     => Ok((DecoderState::LineStyleWidthPixels(String::new()), None)),
             'j'     => Ok((DecoderState::LineStyleJoin(String::new()), None)),
+            'm'     => Ok((DecoderState::LineStyleMiterLimit(String::new()), None)),
             'c'     => Ok((DecoderState::LineStyleCap(String::new()), None)),
 
             _       => Err(DecoderError::InvalidCharacter(next_chr))
@@ -676,9 +1049,13 @@ impl CanvasDecoder {
         // Matched 'C' so far
         match next_chr {
             's'     => Ok((DecoderState::ColorStroke(String::new()), None)),
+            'X'     => Ok((DecoderState::ColorStrokeTexture(DecodeTextureId::new(), String::new()), None)),
+            'G'     => Ok((DecoderState::ColorStrokeGradient(DecodeGradientId::new(), String::new()), None)),
             'f'     => Ok((DecoderState::ColorFill(String::new()), None)),
             't'     => Ok((DecoderState::ColorTexture(DecodeTextureId::new(), String::new()), None)),
             'g'     => Ok((DecoderState::ColorGradient(DecodeGradientId::new(), String::new()), None)),
+            'r'     => Ok((DecoderState::ColorRadialGradient(DecodeGradientId::new(), String::new()), None)),
+            'c'     => Ok((DecoderState::ColorConicGradient(DecodeGradientId::new(), String::new()), None)),
             'T'     => Ok((DecoderState::ColorTransform(String::new()), None)),
 
             _       => Err(DecoderError::InvalidCharacter(next_chr))
@@ -718,6 +1095,20 @@ impl CanvasDecoder {
             's'     => Ok((DecoderState::None, Some(Draw::Store))),
             'r'     => Ok((DecoderState::None, Some(Draw::Restore))),
             'f'     => Ok((DecoderState::None, Some(Draw::FreeStoredBuffer))),
+            'P'     => Ok((DecoderState::None, Some(Draw::StorePath))),
+            'A'     => Ok((DecoderState::StatePathArithmetic, None)),
+
+            _       => Err(DecoderError::InvalidCharacter(next_chr))
+        }
+    }
+
+    #[inline] fn decode_path_arithmetic(next_chr: char) -> Result<(DecoderState, Option<Draw>), DecoderError> {
+        // Matched 'ZA' so far
+        match next_chr {
+            'u'     => Ok((DecoderState::None, Some(Draw::CombinePath(PathArithmetic::Union)))),
+            'i'     => Ok((DecoderState::None, Some(Draw::CombinePath(PathArithmetic::Intersection)))),
+            'd'     => Ok((DecoderState::None, Some(Draw::CombinePath(PathArithmetic::Difference)))),
+            'x'     => Ok((DecoderState::None, Some(Draw::CombinePath(PathArithmetic::Xor)))),
 
             _       => Err(DecoderError::InvalidCharacter(next_chr))
         }
@@ -786,6 +1177,43 @@ impl CanvasDecoder {
         }
     }
 
+    #[inline] fn decode_quadratic_curve(next_chr: char, mut param: String) -> Result<(DecoderState, Option<Draw>), DecoderError> {
+        if param.len() < 23 {
+            param.push(next_chr);
+            Ok((DecoderState::QuadraticCurve(param), None))
+        } else {
+            param.push(next_chr);
+
+            let mut param   = param.chars();
+            let x1          = Self::decode_f32(&mut param)?;
+            let y1          = Self::decode_f32(&mut param)?;
+            let cpx         = Self::decode_f32(&mut param)?;
+            let cpy         = Self::decode_f32(&mut param)?;
+
+            Ok((DecoderState::None, Some(Draw::Path(PathOp::QuadraticCurve((cpx, cpy), (x1, y1))))))
+        }
+    }
+
+    #[inline] fn decode_arc(next_chr: char, mut param: String) -> Result<(DecoderState, Option<Draw>), DecoderError> {
+        if param.len() < 41 {
+            param.push(next_chr);
+            Ok((DecoderState::Arc(param), None))
+        } else {
+            param.push(next_chr);
+
+            let mut param   = param.chars();
+            let cx          = Self::decode_f32(&mut param)?;
+            let cy          = Self::decode_f32(&mut param)?;
+            let rx          = Self::decode_f32(&mut param)?;
+            let ry          = Self::decode_f32(&mut param)?;
+            let rotation    = Self::decode_f32(&mut param)?;
+            let start       = Self::decode_f32(&mut param)?;
+            let sweep       = Self::decode_f32(&mut param)?;
+
+            Ok((DecoderState::None, Some(Draw::Path(PathOp::Arc((cx, cy), (rx, ry), rotation, start, sweep)))))
+        }
+    }
+
     #[inline] fn decode_line_width(next_chr: char, mut param: String) -> Result<(DecoderState, Option<Draw>), DecoderError> {
         if param.len() < 5 {
             param.push(next_chr);
@@ -800,6 +1228,20 @@ impl CanvasDecoder {
         }
     }
 
+    #[inline] fn decode_line_style_miter_limit(next_chr: char, mut param: String) -> Result<(DecoderState, Option<Draw>), DecoderError> {
+        if param.len() < 5 {
+            param.push(next_chr);
+            Ok((DecoderState::LineStyleMiterLimit(param), None))
+        } else {
+            param.push(next_chr);
+
+            let mut param   = param.chars();
+            let limit       = Self::decode_f32(&mut param)?;
+
+            Ok((DecoderState::None, Some(Draw::MiterLimit(limit))))
+        }
+    }
+
     #[inline] fn decode_line_style_join(next_chr: char, _param: String) -> Result<(DecoderState, Option<Draw>), DecoderError> {
         match next_chr {
             'M' => Ok((DecoderState::None, Some(Draw::LineJoin(LineJoin::Miter)))),
@@ -886,6 +1328,68 @@ impl CanvasDecoder {
         }
     }
 
+    #[inline] fn decode_color_stroke_texture(next_chr: char, texture_id: DecodeTextureId, mut param: String) -> Result<(DecoderState, Option<Draw>), DecoderError> {
+        use self::PartialResult::*;
+
+        // Decode the texture ID first
+        let texture_id = match texture_id {
+            MatchMore(texture_id) => { 
+                let texture_id = Self::decode_texture_id(next_chr, texture_id)?;
+                return Ok((DecoderState::ColorStrokeTexture(texture_id, param), None));
+            }
+
+            FullMatch(texture_id) => texture_id
+        };
+
+        // There are 4 coordinates following the texture ID (at 6 bytes each)
+        param.push(next_chr);
+
+        if param.len() < 24 {
+            // More characters required
+            Ok((DecoderState::ColorStrokeTexture(FullMatch(texture_id), param), None))
+        } else {
+            // Decode the coordinates
+            let mut param   = param.chars();
+            let x1          = Self::decode_f32(&mut param)?;
+            let y1          = Self::decode_f32(&mut param)?;
+            let x2          = Self::decode_f32(&mut param)?;
+            let y2          = Self::decode_f32(&mut param)?;
+
+            Ok((DecoderState::None, Some(Draw::StrokeTexture(texture_id, (x1, y1), (x2, y2)))))
+        }
+    }
+
+    #[inline] fn decode_color_stroke_gradient(next_chr: char, gradient_id: DecodeGradientId, mut param: String) -> Result<(DecoderState, Option<Draw>), DecoderError> {
+        use self::PartialResult::*;
+
+        // Decode the gradient ID first
+        let gradient_id = match gradient_id {
+            MatchMore(gradient_id) => { 
+                let gradient_id = Self::decode_gradient_id(next_chr, gradient_id)?;
+                return Ok((DecoderState::ColorStrokeGradient(gradient_id, param), None));
+            }
+
+            FullMatch(gradient_id) => gradient_id
+        };
+
+        // There are 4 coordinates following the gradient ID (at 6 bytes each)
+        param.push(next_chr);
+
+        if param.len() < 24 {
+            // More characters required
+            Ok((DecoderState::ColorStrokeGradient(FullMatch(gradient_id), param), None))
+        } else {
+            // Decode the coordinates
+            let mut param   = param.chars();
+            let x1          = Self::decode_f32(&mut param)?;
+            let y1          = Self::decode_f32(&mut param)?;
+            let x2          = Self::decode_f32(&mut param)?;
+            let y2          = Self::decode_f32(&mut param)?;
+
+            Ok((DecoderState::None, Some(Draw::StrokeGradient(gradient_id, (x1, y1), (x2, y2)))))
+        }
+    }
+
     #[inline] fn decode_color_fill(next_chr: char, mut param: String) -> Result<(DecoderState, Option<Draw>), DecoderError> {
         if param.len() < 24 {
             param.push(next_chr);
@@ -970,6 +1474,88 @@ impl CanvasDecoder {
         }
     }
 
+    #[inline] fn decode_color_radial_gradient(next_chr: char, gradient_id: DecodeGradientId, mut param: String) -> Result<(DecoderState, Option<Draw>), DecoderError> {
+        use self::PartialResult::*;
+
+        // Decode the gradient ID first
+        let gradient_id = match gradient_id {
+            MatchMore(gradient_id) => { 
+                let gradient_id = Self::decode_gradient_id(next_chr, gradient_id)?;
+                return Ok((DecoderState::ColorRadialGradient(gradient_id, param), None));
+            }
+
+            FullMatch(gradient_id) => gradient_id
+        };
+
+        // The center and radius are 3 f32s, then the focus is either 'n' or 'p' followed by two more f32s
+        param.push(next_chr);
+
+        if param.len() < 19 {
+            // More characters required
+            return Ok((DecoderState::ColorRadialGradient(FullMatch(gradient_id), param), None));
+        }
+
+        let focus_len = match param.chars().nth(18) {
+            Some('n')   => 19,
+            Some('p')   => 31,
+            Some(c)     => return Err(DecoderError::InvalidCharacter(c)),
+            None        => return Err(DecoderError::MissingCharacter)
+        };
+
+        if param.len() < focus_len {
+            // More characters required
+            Ok((DecoderState::ColorRadialGradient(FullMatch(gradient_id), param), None))
+        } else {
+            // Decode the coordinates
+            let mut param   = param.chars();
+            let x           = Self::decode_f32(&mut param)?;
+            let y           = Self::decode_f32(&mut param)?;
+            let radius      = Self::decode_f32(&mut param)?;
+
+            let focus       = match param.next() {
+                Some('p')   => {
+                    let fx  = Self::decode_f32(&mut param)?;
+                    let fy  = Self::decode_f32(&mut param)?;
+                    Some((fx, fy))
+                }
+
+                _           => None
+            };
+
+            Ok((DecoderState::None, Some(Draw::FillRadialGradient(gradient_id, (x, y), radius, focus))))
+        }
+    }
+
+    #[inline] fn decode_color_conic_gradient(next_chr: char, gradient_id: DecodeGradientId, mut param: String) -> Result<(DecoderState, Option<Draw>), DecoderError> {
+        use self::PartialResult::*;
+
+        // Decode the gradient ID first
+        let gradient_id = match gradient_id {
+            MatchMore(gradient_id) => { 
+                let gradient_id = Self::decode_gradient_id(next_chr, gradient_id)?;
+                return Ok((DecoderState::ColorConicGradient(gradient_id, param), None));
+            }
+
+            FullMatch(gradient_id) => gradient_id
+        };
+
+        // The center and the start angle follow the gradient ID (at 6 bytes each)
+        param.push(next_chr);
+
+        if param.len() < 18 {
+            // More characters required
+            Ok((DecoderState::ColorConicGradient(FullMatch(gradient_id), param), None))
+        } else {
+            // Decode the coordinates
+            let mut param   = param.chars();
+            let x           = Self::decode_f32(&mut param)?;
+            let y           = Self::decode_f32(&mut param)?;
+            let angle       = Self::decode_f32(&mut param)?;
+
+            Ok((DecoderState::None, Some(Draw::FillConicGradient(gradient_id, (x, y), angle))))
+        }
+    }
+
     #[inline] fn decode_color_transform(next_chr: char, mut param: String) -> Result<(DecoderState, Option<Draw>), DecoderError> {
         if param.len() < 53 {
             param.push(next_chr);
@@ -1272,6 +1858,18 @@ impl CanvasDecoder {
             ('E', 'S') => Ok(BlendMode::Screen),
             ('E', 'D') => Ok(BlendMode::Darken),
             ('E', 'L') => Ok(BlendMode::Lighten),
+            ('E', 'O') => Ok(BlendMode::Overlay),
+            ('E', 'C') => Ok(BlendMode::ColorDodge),
+            ('E', 'B') => Ok(BlendMode::ColorBurn),
+            ('E', 'H') => Ok(BlendMode::HardLight),
+            ('E', 'T') => Ok(BlendMode::SoftLight),
+            ('E', 'F') => Ok(BlendMode::Difference),
+            ('E', 'X') => Ok(BlendMode::Exclusion),
+
+            ('N', 'H') => Ok(BlendMode::Hue),
+            ('N', 'S') => Ok(BlendMode::Saturation),
+            ('N', 'C') => Ok(BlendMode::Color),
+            ('N', 'L') => Ok(BlendMode::Luminosity),
 
             _          => Err(DecoderError::InvalidCharacter(a))
         }
@@ -1388,6 +1986,7 @@ impl CanvasDecoder {
             'T' => Ok((DecoderState::FontDrawText(PartialResult::new(), DecodeString::new(), String::new()), None)),
             'R' => Ok((DecoderState::None, Some(Draw::DrawLaidOutText))),
             'l' => Ok((DecoderState::FontBeginLayout(String::new()), None)),
+            'p' => Ok((DecoderState::FontBeginParagraph(String::new()), None)),
             _   => Err(DecoderError::InvalidCharacter(chr))
         }
     }
@@ -1457,6 +2056,59 @@ impl CanvasDecoder {
         Ok((DecoderState::None, Some(Draw::BeginLineLayout(x, y, align))))
     }
 
+    ///
+    /// Decodes the 'begin paragraph' instruction
+    ///
+    fn decode_font_begin_paragraph(chr: char, param: String) -> Result<(DecoderState, Option<Draw>), DecoderError> {
+        // Push the character
+        let mut param = param;
+        param.push(chr);
+
+        // 6x f32 + 2 characters
+        if param.len() < 38 {
+            return Ok((DecoderState::FontBeginParagraph(param), None));
+        }
+
+        // Decode
+        let mut chrs    = param.chars();
+
+        let x           = Self::decode_f32(&mut chrs)?;
+        let y           = Self::decode_f32(&mut chrs)?;
+        let width       = Self::decode_f32(&mut chrs)?;
+
+        let alignment   = match chrs.next() {
+            Some('l')   => Ok(ParagraphAlignment::Left),
+            Some('r')   => Ok(ParagraphAlignment::Right),
+            Some('c')   => Ok(ParagraphAlignment::Center),
+            Some('j')   => Ok(ParagraphAlignment::Justify),
+            Some(other) => Err(DecoderError::InvalidCharacter(other)),
+            None        => Err(DecoderError::NotReady)
+        }?;
+
+        let line_height = Self::decode_f32(&mut chrs)?;
+        let spacing     = Self::decode_f32(&mut chrs)?;
+        let overflow    = chrs.next();
+        let max_height  = Self::decode_f32(&mut chrs)?;
+
+        let overflow    = match overflow {
+            Some('v')   => Ok(TextOverflow::Visible),
+            Some('c')   => Ok(TextOverflow::Clip(max_height)),
+            Some('e')   => Ok(TextOverflow::Ellipsis(max_height)),
+            Some(other) => Err(DecoderError::InvalidCharacter(other)),
+            None        => Err(DecoderError::NotReady)
+        }?;
+
+        let style       = ParagraphStyle {
+            width:              width,
+            alignment:          alignment,
+            line_height:        line_height,
+            paragraph_spacing:  spacing,
+            overflow:           overflow,
+        };
+
+        Ok((DecoderState::None, Some(Draw::BeginParagraphLayout(x, y, style))))
+    }
+
     ///
     /// Decodes a FontOp command
     ///
@@ -1480,6 +2132,9 @@ impl CanvasDecoder {
         match chr {
             'd' => Ok((DecoderState::FontOpData(font_id), None)),
             'S' => Ok((DecoderState::FontOpSize(font_id, String::new()), None)),
+            'F' => Ok((DecoderState::FontOpFallback(font_id, DecodeFontIds::new()), None)),
+            'O' => Ok((DecoderState::FontOpFeature(font_id, String::new()), None)),
+            'V' => Ok((DecoderState::FontOpVariation(font_id, String::new()), None)),
             'L' => Ok((DecoderState::FontOpLayoutText(font_id, DecodeString::new()), None)),
             'G' => Ok((DecoderState::FontOpDrawGlyphs(font_id, DecodeGlyphPositions::new()), None)),
 
@@ -1504,6 +2159,63 @@ impl CanvasDecoder {
         }
     }
 
+    ///
+    /// Decodes a UseFallbackFonts fontop
+    ///
+    fn decode_font_op_fallback(chr: char, font_id: FontId, font_ids: DecodeFontIds) -> Result<(DecoderState, Option<Draw>), DecoderError> {
+        let font_ids = font_ids.decode(chr)?;
+
+        if font_ids.ready() {
+            Ok((DecoderState::None, Some(Draw::Font(font_id, FontOp::UseFallbackFonts(font_ids.to_font_ids())))))
+        } else {
+            Ok((DecoderState::FontOpFallback(font_id, font_ids), None))
+        }
+    }
+
+    ///
+    /// Decodes a FontFeature fontop
+    ///
+    fn decode_font_op_feature(chr: char, font_id: FontId, param: String) -> Result<(DecoderState, Option<Draw>), DecoderError> {
+        // Add the character to the parameter
+        let mut param = param;
+        param.push(chr);
+
+        // Can decode once we have the tag and the 'Y' or 'N' character
+        if param.len() < 7 {
+            return Ok((DecoderState::FontOpFeature(font_id, param), None));
+        }
+
+        let mut chars   = param.chars();
+        let tag         = Self::decode_u32(&mut chars)?.to_be_bytes();
+        let enabled     = match chars.next() {
+            Some('Y')   => true,
+            Some('N')   => false,
+            _           => { return Err(DecoderError::InvalidCharacter(chr)); }
+        };
+
+        Ok((DecoderState::None, Some(Draw::Font(font_id, FontOp::FontFeature(tag, enabled)))))
+    }
+
+    ///
+    /// Decodes a FontVariation fontop
+    ///
+    fn decode_font_op_variation(chr: char, font_id: FontId, param: String) -> Result<(DecoderState, Option<Draw>), DecoderError> {
+        // Add the character to the parameter
+        let mut param = param;
+        param.push(chr);
+
+        // Can decode once we have the tag and the value
+        if param.len() < 12 {
+            return Ok((DecoderState::FontOpVariation(font_id, param), None));
+        }
+
+        let mut chars   = param.chars();
+        let tag         = Self::decode_u32(&mut chars)?.to_be_bytes();
+        let value       = Self::decode_f32(&mut chars)?;
+
+        Ok((DecoderState::None, Some(Draw::Font(font_id, FontOp::FontVariation(tag, value)))))
+    }
+
     ///
     /// Decodes a font data item
     ///
@@ -1847,6 +2559,7 @@ impl CanvasDecoder {
         match chr {
             'N' => Ok((DecoderState::GradientOpNew(gradient_id, String::new()), None)),
             'S' => Ok((DecoderState::GradientOpAddStop(gradient_id, String::new()), None)),
+            'P' => Ok((DecoderState::GradientOpSpread(gradient_id), None)),
 
             _   => Err(DecoderError::InvalidCharacter(chr))
         }
@@ -1909,6 +2622,18 @@ impl CanvasDecoder {
         }
     }
 
+    ///
+    /// Decodes the GradientOp::Spread instruction
+    ///
+    #[inline] fn decode_gradient_spread(next_chr: char, gradient_id: GradientId) -> Result<(DecoderState, Option<Draw>), DecoderError> {
+        match next_chr {
+            'p' => Ok((DecoderState::None, Some(Draw::Gradient(gradient_id, GradientOp::Spread(GradientSpread::Pad))))),
+            'r' => Ok((DecoderState::None, Some(Draw::Gradient(gradient_id, GradientOp::Spread(GradientSpread::Repeat))))),
+            'f' => Ok((DecoderState::None, Some(Draw::Gradient(gradient_id, GradientOp::Spread(GradientSpread::Reflect))))),
+            _   => Err(DecoderError::InvalidCharacter(next_chr))
+        }
+    }
+
     ///
     /// Decodes the Namespace instruction
     ///
@@ -2052,6 +2777,28 @@ pub fn decode_drawing<In: IntoIterator<Item=char>>(source: In) -> impl Iterator<
         })
 }
 
+///
+/// Decodes a canvas drawing from an untrusted source, represented as an iterator of characters
+///
+/// The decoder will reject any data that exceeds the specified limits. Errors are returned along with the position of the
+/// character where they were found, and the decoder skips to the next command after an error (see `CanvasDecoder::with_resync()`),
+/// so every error in the drawing is returned.
+///
+pub fn decode_untrusted_drawing<In: IntoIterator<Item=char>>(source: In, limits: DecoderLimits) -> impl Iterator<Item=Result<Draw, PositionedDecoderError>> {
+    let mut decoder = CanvasDecoder::new().with_limits(limits).with_resync(true);
+
+    source.into_iter()
+        .filter_map(move |chr| {
+            let position = decoder.position();
+
+            match decoder.decode(chr) {
+                Ok(Some(draw))  => Some(Ok(draw)),
+                Ok(None)        => None,
+                Err(err)        => Some(Err(PositionedDecoderError { position: position, error: err })),
+            }
+        })
+}
+
 ///
 /// Error from either a decoder or the stream that's feeding it
 ///
@@ -2169,6 +2916,16 @@ mod test {
         check_round_trip_single(Draw::Path(PathOp::BezierCurve(((1.0, 2.0), (3.0, 4.0)), (5.0, 6.0))));
     }
 
+    #[test]
+    fn decode_quadratic_curve() {
+        check_round_trip_single(Draw::Path(PathOp::QuadraticCurve((1.0, 2.0), (3.0, 4.0))));
+    }
+
+    #[test]
+    fn decode_arc() {
+        check_round_trip_single(Draw::Path(PathOp::Arc((1.0, 2.0), (3.0, 4.0), 0.5, 1.0, -2.0)));
+    }
+
     #[test]
     fn decode_close_path() {
         check_round_trip_single(Draw::Path(PathOp::ClosePath));
@@ -2199,6 +2956,11 @@ mod test {
         check_round_trip_single(Draw::LineJoin(LineJoin::Bevel));
     }
 
+    #[test]
+    fn decode_miter_limit() {
+        check_round_trip_single(Draw::MiterLimit(10.0));
+    }
+
     #[test]
     fn decode_line_cap() {
         check_round_trip_single(Draw::LineCap(LineCap::Round));
@@ -2239,6 +3001,24 @@ mod test {
         check_round_trip_single(Draw::BlendMode(BlendMode::Lighten));
     }
 
+    #[test]
+    fn decode_shader_blend_modes() {
+        check_round_trip(vec![
+            Draw::BlendMode(BlendMode::Overlay),
+            Draw::BlendMode(BlendMode::ColorDodge),
+            Draw::BlendMode(BlendMode::ColorBurn),
+            Draw::BlendMode(BlendMode::HardLight),
+            Draw::BlendMode(BlendMode::SoftLight),
+            Draw::BlendMode(BlendMode::Difference),
+            Draw::BlendMode(BlendMode::Exclusion),
+            Draw::BlendMode(BlendMode::Hue),
+            Draw::BlendMode(BlendMode::Saturation),
+            Draw::BlendMode(BlendMode::Color),
+            Draw::BlendMode(BlendMode::Luminosity),
+            Draw::LayerBlend(LayerId(3), BlendMode::SoftLight),
+        ]);
+    }
+
     #[test]
     fn decode_identity_transform() {
         check_round_trip_single(Draw::IdentityTransform);
@@ -2368,6 +3148,15 @@ mod test {
         check_round_trip_single(Draw::WindingRule(WindingRule::EvenOdd));
     }
 
+    #[test]
+    fn decode_path_arithmetic() {
+        check_round_trip_single(Draw::StorePath);
+        check_round_trip_single(Draw::CombinePath(PathArithmetic::Union));
+        check_round_trip_single(Draw::CombinePath(PathArithmetic::Intersection));
+        check_round_trip_single(Draw::CombinePath(PathArithmetic::Difference));
+        check_round_trip_single(Draw::CombinePath(PathArithmetic::Xor));
+    }
+
     #[test]
     fn decode_draw_sprite() {
         check_round_trip_single(Draw::DrawSprite(SpriteId(0)));
@@ -2412,9 +3201,30 @@ mod test {
         check_round_trip_single(Draw::Font(FontId(42), FontOp::FontSize(32.0)));
     }
 
+    #[test]
+    fn decode_fallback_fonts() {
+        check_round_trip_single(Draw::Font(FontId(42), FontOp::UseFallbackFonts(vec![])));
+        check_round_trip_single(Draw::Font(FontId(42), FontOp::UseFallbackFonts(vec![FontId(1), FontId(1000), FontId(3)])));
+        check_round_trip_single(Draw::Font(FontId(42), FontOp::UseFallbackFonts((0..10000).map(|id| FontId(id)).collect())));
+    }
+
+    #[test]
+    fn decode_font_feature() {
+        check_round_trip_single(Draw::Font(FontId(42), FontOp::FontFeature(*b"liga", false)));
+        check_round_trip_single(Draw::Font(FontId(42), FontOp::FontFeature(*b"ss01", true)));
+    }
+
+    #[test]
+    fn decode_font_variation() {
+        check_round_trip_single(Draw::Font(FontId(42), FontOp::FontVariation(*b"wght", 650.0)));
+        check_round_trip_single(Draw::Font(FontId(42), FontOp::FontVariation(*b"slnt", -12.5)));
+    }
+
     #[test]
     fn decode_begin_line_layout() {
         check_round_trip_single(Draw::BeginLineLayout(1.0, 2.0, TextAlignment::Center));
+        check_round_trip_single(Draw::BeginParagraphLayout(1.0, 2.0, ParagraphStyle::new(100.0)));
+        check_round_trip_single(Draw::BeginParagraphLayout(1.0, 2.0, ParagraphStyle::new(100.0).with_alignment(ParagraphAlignment::Justify).with_line_height(1.5).with_paragraph_spacing(4.0).with_overflow(TextOverflow::Ellipsis(30.0))));
     }
 
     #[test]
@@ -2493,11 +3303,43 @@ mod test {
         check_round_trip_single(Draw::Gradient(GradientId(44), GradientOp::AddStop(0.5, Color::Rgba(0.1, 0.2, 0.3, 0.4))));
     }
 
+    #[test]
+    fn decode_gradient_spread() {
+        check_round_trip_single(Draw::Gradient(GradientId(45), GradientOp::Spread(GradientSpread::Pad)));
+        check_round_trip_single(Draw::Gradient(GradientId(45), GradientOp::Spread(GradientSpread::Repeat)));
+        check_round_trip_single(Draw::Gradient(GradientId(45), GradientOp::Spread(GradientSpread::Reflect)));
+    }
+
     #[test]
     fn decode_gradient_fill() {
         check_round_trip_single(Draw::FillGradient(GradientId(24), (42.0, 43.0), (44.0, 45.0)));
     }
 
+    #[test]
+    fn decode_radial_gradient_fill() {
+        check_round_trip_single(Draw::FillRadialGradient(GradientId(24), (42.0, 43.0), 44.0, None));
+    }
+
+    #[test]
+    fn decode_radial_gradient_fill_with_focus() {
+        check_round_trip_single(Draw::FillRadialGradient(GradientId(24), (42.0, 43.0), 44.0, Some((45.0, 46.0))));
+    }
+
+    #[test]
+    fn decode_conic_gradient_fill() {
+        check_round_trip_single(Draw::FillConicGradient(GradientId(24), (42.0, 43.0), 1.5));
+    }
+
+    #[test]
+    fn decode_stroke_texture() {
+        check_round_trip_single(Draw::StrokeTexture(TextureId(23), (42.0, 43.0), (44.0, 45.0)));
+    }
+
+    #[test]
+    fn decode_stroke_gradient() {
+        check_round_trip_single(Draw::StrokeGradient(GradientId(24), (42.0, 43.0), (44.0, 45.0)));
+    }
+
     #[test]
     fn decode_fill_transform() {
         check_round_trip_single(Draw::FillTransform(Transform2D::identity()));
@@ -2545,12 +3387,17 @@ mod test {
             Draw::Path(PathOp::Move(10.0, 15.0)),
             Draw::Path(PathOp::Line(20.0, 42.0)),
             Draw::Path(PathOp::BezierCurve(((1.0, 2.0), (3.0, 4.0)), (5.0, 6.0))),
+            Draw::Path(PathOp::QuadraticCurve((1.0, 2.0), (3.0, 4.0))),
+            Draw::Path(PathOp::Arc((1.0, 2.0), (3.0, 4.0), 0.5, 1.0, 2.0)),
             Draw::Path(PathOp::ClosePath),
+            Draw::StorePath,
+            Draw::CombinePath(PathArithmetic::Xor),
             Draw::Fill,
             Draw::Stroke,
             Draw::LineWidth(23.0),
             Draw::LineWidthPixels(43.0),
             Draw::LineJoin(LineJoin::Bevel),
+            Draw::MiterLimit(10.0),
             Draw::LineCap(LineCap::Round),
             Draw::WindingRule(WindingRule::NonZero),
             Draw::NewDashPattern,
@@ -2560,6 +3407,11 @@ mod test {
             Draw::FillColor(Color::Rgba(0.2, 0.3, 0.4, 0.5)),
             Draw::FillTexture(TextureId(23), (42.0, 43.0), (44.0, 45.0)),
             Draw::FillGradient(GradientId(24), (42.0, 43.0), (44.0, 45.0)),
+            Draw::FillRadialGradient(GradientId(25), (42.0, 43.0), 44.0, None),
+            Draw::FillRadialGradient(GradientId(26), (42.0, 43.0), 44.0, Some((45.0, 46.0))),
+            Draw::FillConicGradient(GradientId(27), (42.0, 43.0), 1.5),
+            Draw::StrokeTexture(TextureId(28), (42.0, 43.0), (44.0, 45.0)),
+            Draw::StrokeGradient(GradientId(29), (42.0, 43.0), (44.0, 45.0)),
             Draw::FillTransform(Transform2D::identity()),
             Draw::BlendMode(BlendMode::Lighten),
             Draw::IdentityTransform,
@@ -2603,6 +3455,7 @@ mod test {
 
             Draw::Gradient(GradientId(42), GradientOp::Create(Color::Rgba(0.1, 0.2, 0.3, 0.4))),
             Draw::Gradient(GradientId(44), GradientOp::AddStop(0.5, Color::Rgba(0.1, 0.2, 0.3, 0.4))),
+            Draw::Gradient(GradientId(45), GradientOp::Spread(GradientSpread::Repeat)),
         ]);
     }
 
@@ -2613,13 +3466,18 @@ mod test {
             Draw::Path(PathOp::Move(10.0, 15.0)),
             Draw::Path(PathOp::Line(20.0, 42.0)),
             Draw::Path(PathOp::BezierCurve(((1.0, 2.0), (3.0, 4.0)), (5.0, 6.0))),
+            Draw::Path(PathOp::QuadraticCurve((1.0, 2.0), (3.0, 4.0))),
+            Draw::Path(PathOp::Arc((1.0, 2.0), (3.0, 4.0), 0.5, 1.0, 2.0)),
             Draw::Path(PathOp::ClosePath),
+            Draw::StorePath,
+            Draw::CombinePath(PathArithmetic::Xor),
             Draw::Fill,
             Draw::FillTexture(TextureId(42), (1.0, 2.0), (3.0, 4.0)),
             Draw::Stroke,
             Draw::LineWidth(23.0),
             Draw::LineWidthPixels(43.0),
             Draw::LineJoin(LineJoin::Bevel),
+            Draw::MiterLimit(10.0),
             Draw::LineCap(LineCap::Round),
             Draw::WindingRule(WindingRule::EvenOdd),
             Draw::NewDashPattern,
@@ -2629,6 +3487,11 @@ mod test {
             Draw::FillColor(Color::Rgba(0.2, 0.3, 0.4, 0.5)),
             Draw::FillTexture(TextureId(23), (42.0, 43.0), (44.0, 45.0)),
             Draw::FillGradient(GradientId(24), (42.0, 43.0), (44.0, 45.0)),
+            Draw::FillRadialGradient(GradientId(25), (42.0, 43.0), 44.0, None),
+            Draw::FillRadialGradient(GradientId(26), (42.0, 43.0), 44.0, Some((45.0, 46.0))),
+            Draw::FillConicGradient(GradientId(27), (42.0, 43.0), 1.5),
+            Draw::StrokeTexture(TextureId(28), (42.0, 43.0), (44.0, 45.0)),
+            Draw::StrokeGradient(GradientId(29), (42.0, 43.0), (44.0, 45.0)),
             Draw::FillTransform(Transform2D::identity()),
             Draw::BlendMode(BlendMode::Lighten),
             Draw::IdentityTransform,
@@ -2673,6 +3536,7 @@ mod test {
 
             Draw::Gradient(GradientId(42), GradientOp::Create(Color::Rgba(0.1, 0.2, 0.3, 0.4))),
             Draw::Gradient(GradientId(44), GradientOp::AddStop(0.5, Color::Rgba(0.1, 0.2, 0.3, 0.4))),
+            Draw::Gradient(GradientId(45), GradientOp::Spread(GradientSpread::Repeat)),
         ];
         let mut encoded = String::new();
         all.encode_canvas(&mut encoded);
@@ -2695,4 +3559,130 @@ mod test {
             assert!(all == decoded);
         });
     }
+
+    #[test]
+    fn error_position() {
+        let mut decoder = CanvasDecoder::new();
+        let results     = "F\nX\nS".chars().map(|chr| decoder.decode(chr)).collect::<Vec<_>>();
+
+        assert!(results[2] == Err(DecoderError::InvalidCharacter('X')));
+        assert!(results[4] == Err(DecoderError::IsInErrorState));
+        assert!(decoder.error_position() == Some(2));
+    }
+
+    #[test]
+    fn resync_after_bad_command() {
+        let decoded = decode_untrusted_drawing("F\nX\nLzABC\nS\n".chars(), DecoderLimits::untrusted()).collect::<Vec<_>>();
+
+        assert!(decoded == vec![
+            Ok(Draw::Fill),
+            Err(PositionedDecoderError { position: 2, error: DecoderError::InvalidCharacter('X') }),
+            Err(PositionedDecoderError { position: 5, error: DecoderError::InvalidCharacter('z') }),
+            Ok(Draw::Stroke),
+        ]);
+    }
+
+    fn decode_with_limits(drawing: Vec<Draw>, limits: DecoderLimits) -> Vec<Result<Draw, PositionedDecoderError>> {
+        let mut encoded = String::new();
+        drawing.encode_canvas(&mut encoded);
+
+        decode_untrusted_drawing(encoded.chars(), limits).collect()
+    }
+
+    #[test]
+    fn limit_texture_bytes() {
+        let limits  = DecoderLimits { max_texture_bytes: 10, ..DecoderLimits::untrusted() };
+        let decoded = decode_with_limits(vec![Draw::Texture(TextureId(1), TextureOp::SetBytes(TexturePosition(0, 0), TextureSize(5, 5), Arc::new(vec![0; 100]))), Draw::Fill], limits);
+
+        // The error should be detected when the length is decoded, before any of the bytes are read
+        assert!(decoded.len() == 2);
+        match decoded[0] {
+            Err(PositionedDecoderError { position, error: DecoderError::LimitExceeded(DecoderLimit::TextureBytes) }) => { assert!(position < 40); }
+            _ => { assert!(false, "{:?}", decoded); }
+        }
+        assert!(decoded[1] == Ok(Draw::Fill));
+    }
+
+    #[test]
+    fn limit_texture_size() {
+        let limits  = DecoderLimits { max_texture_bytes: 1000*1000*4, ..DecoderLimits::untrusted() };
+        let decoded = decode_with_limits(vec![
+            Draw::Texture(TextureId(1), TextureOp::Create(TextureSize(1000, 1000), TextureFormat::Rgba)),
+            Draw::Texture(TextureId(2), TextureOp::Create(TextureSize(1000, 1001), TextureFormat::Rgba)),
+            ], limits);
+
+        assert!(decoded.len() == 2);
+        assert!(decoded[0].is_ok());
+        assert!(decoded[1].clone().map_err(|err| err.error) == Err(DecoderError::LimitExceeded(DecoderLimit::TextureBytes)));
+    }
+
+    #[test]
+    fn limit_huge_texture_size() {
+        let decoded = decode_with_limits(vec![
+            Draw::Texture(TextureId(1), TextureOp::Create(TextureSize(u32::MAX, u32::MAX), TextureFormat::Rgba)),
+            Draw::Texture(TextureId(2), TextureOp::Create(TextureSize(1<<31, 1<<31), TextureFormat::Rgba)),
+            Draw::Fill,
+            ], DecoderLimits::untrusted());
+
+        assert!(decoded.len() == 3);
+        assert!(decoded[0].clone().map_err(|err| err.error) == Err(DecoderError::LimitExceeded(DecoderLimit::TextureBytes)));
+        assert!(decoded[1].clone().map_err(|err| err.error) == Err(DecoderError::LimitExceeded(DecoderLimit::TextureBytes)));
+        assert!(decoded[2] == Ok(Draw::Fill));
+    }
+
+    #[test]
+    fn limit_texture_size_overflow() {
+        // The size of these textures overflows a u64, so they're rejected even when there's no limit
+        let limits  = DecoderLimits { max_texture_bytes: u64::MAX, ..DecoderLimits::untrusted() };
+        let decoded = decode_with_limits(vec![
+            Draw::Texture(TextureId(1), TextureOp::Create(TextureSize(u32::MAX, u32::MAX), TextureFormat::Rgba)),
+            Draw::Texture(TextureId(2), TextureOp::Create(TextureSize(1<<31, 1<<31), TextureFormat::Rgba)),
+            ], limits);
+
+        assert!(decoded.len() == 2);
+        assert!(decoded[0].clone().map_err(|err| err.error) == Err(DecoderError::LimitExceeded(DecoderLimit::TextureBytes)));
+        assert!(decoded[1].clone().map_err(|err| err.error) == Err(DecoderError::LimitExceeded(DecoderLimit::TextureBytes)));
+    }
+
+    #[test]
+    fn limit_font_bytes() {
+        let limits  = DecoderLimits { max_font_bytes: 1000, ..DecoderLimits::untrusted() };
+        let font    = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+        let decoded = decode_with_limits(vec![Draw::Font(FontId(1), FontOp::UseFontDefinition(font))], limits);
+
+        assert!(decoded.len() == 1);
+        assert!(decoded[0].clone().map_err(|err| err.error) == Err(DecoderError::LimitExceeded(DecoderLimit::FontBytes)));
+    }
+
+    #[test]
+    fn limit_string_length() {
+        let limits  = DecoderLimits { max_string_length: 5, ..DecoderLimits::untrusted() };
+        let decoded = decode_with_limits(vec![
+            Draw::Font(FontId(1), FontOp::LayoutText("Hello".to_string())),
+            Draw::Font(FontId(1), FontOp::LayoutText("Hello, world".to_string())),
+            ], limits);
+
+        assert!(decoded.len() == 2);
+        assert!(decoded[0] == Ok(Draw::Font(FontId(1), FontOp::LayoutText("Hello".to_string()))));
+        assert!(decoded[1].clone().map_err(|err| err.error) == Err(DecoderError::LimitExceeded(DecoderLimit::StringLength)));
+    }
+
+    #[test]
+    fn limit_path_length() {
+        let limits  = DecoderLimits { max_path_length: 3, ..DecoderLimits::untrusted() };
+        let decoded = decode_with_limits(vec![
+            Draw::Path(PathOp::NewPath),
+            Draw::Path(PathOp::Move(0.0, 0.0)),
+            Draw::Path(PathOp::Line(1.0, 0.0)),
+            Draw::Path(PathOp::Line(1.0, 1.0)),
+            Draw::Path(PathOp::Line(0.0, 1.0)),
+            Draw::Path(PathOp::NewPath),
+            Draw::Path(PathOp::Move(0.0, 0.0)),
+            ], limits);
+
+        assert!(decoded.len() == 7);
+        assert!(decoded[3] == Ok(Draw::Path(PathOp::Line(1.0, 1.0))));
+        assert!(decoded[4].clone().map_err(|err| err.error) == Err(DecoderError::LimitExceeded(DecoderLimit::PathLength)));
+        assert!(decoded[6] == Ok(Draw::Path(PathOp::Move(0.0, 0.0))));
+    }
 }
@@ -48,7 +48,19 @@ pub enum BlendMode {
     Multiply,
     Screen,
     Darken,
-    Lighten
+    Lighten,
+    Overlay,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+
+    Hue,
+    Saturation,
+    Color,
+    Luminosity
 }
 
 ///
@@ -63,6 +75,24 @@ pub enum WindingRule {
     EvenOdd
 }
 
+///
+/// Ways that the current path can be combined with a path saved by `Draw::StorePath`
+///
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum PathArithmetic {
+    /// The areas covered by either path
+    Union,
+
+    /// The areas covered by both paths
+    Intersection,
+
+    /// The areas covered by the stored path that are not covered by the current path
+    Difference,
+
+    /// The areas covered by exactly one of the two paths
+    Xor
+}
+
 ///
 /// Identifier of a canvas layer
 ///
@@ -132,6 +162,14 @@ pub enum Draw {
     /// Performs an operation on the currently defined path
     Path(PathOp),
 
+    /// Saves a copy of the current path so it can be combined with a later path using `CombinePath`
+    StorePath,
+
+    /// Replaces the current path with the result of combining it with the path saved by the last `StorePath`
+    ///
+    /// Paths are combined in the coordinates they were defined in, so the transform has no effect on the result.
+    CombinePath(PathArithmetic),
+
     /// Fill the current path
     Fill,
 
@@ -147,6 +185,9 @@ pub enum Draw {
     /// Line join
     LineJoin(LineJoin),
 
+    /// The limit on the ratio of the miter length to the line width for mitered joins (joins that exceed this are bevelled instead)
+    MiterLimit(f32),
+
     /// The cap to use on lines
     LineCap(LineCap),
 
@@ -168,12 +209,24 @@ pub enum Draw {
     /// Sets the fill to be a gradient (coordinates are the start and end of the gradient)
     FillGradient(GradientId, (f32, f32), (f32, f32)),
 
+    /// Sets the fill to be a radial gradient (the centre and radius of the outer circle, and an optional focal point where the gradient starts from)
+    FillRadialGradient(GradientId, (f32, f32), f32, Option<(f32, f32)>),
+
+    /// Sets the fill to be a conic (sweep) gradient (the centre of the gradient and the angle in radians where it starts)
+    FillConicGradient(GradientId, (f32, f32), f32),
+
     /// For a gradient or texture fill, apply a transformation matrix
     FillTransform(Transform2D),
 
     /// Set the line color
     StrokeColor(Color),
 
+    /// Sets the line to be drawn using a texture (coordinates are the lower-left and upper-right coordinates where the image should appear)
+    StrokeTexture(TextureId, (f32, f32), (f32, f32)),
+
+    /// Sets the line to be drawn using a gradient (coordinates are the start and end of the gradient)
+    StrokeGradient(GradientId, (f32, f32), (f32, f32)),
+
     /// Set the winding rule for fill operations
     WindingRule(WindingRule),
 
@@ -287,6 +340,9 @@ pub enum Draw {
     /// Begins laying out text on a line: the coordinates specify the baseline position
     BeginLineLayout(f32, f32, TextAlignment),
 
+    /// Begins laying out text as a paragraph that wraps to a width: the coordinates specify the top-left corner of the paragraph
+    BeginParagraphLayout(f32, f32, ParagraphStyle),
+
     /// Renders the text in the current layout
     DrawLaidOutText,
 
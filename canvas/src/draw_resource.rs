@@ -22,10 +22,12 @@ pub (crate) enum DrawResource {
     Gradient(GradientId),
     Font(FontId),
     FontSize(FontId),
+    FontFallback(FontId),
     
     StrokeLineWidth,
     StrokeLineCap,
     StrokeLineJoin,
+    StrokeMiterLimit,
     StrokeDash,
     StrokeColor,
 
@@ -50,7 +52,7 @@ impl Draw {
 
             // The fill and stroke operations depend on multiple resources, so their resource is 'special'
             Fill                                    => match resource { DrawResource::CanvasTransform | DrawResource::FillWindingRule | DrawResource::FillBlend | DrawResource::FillColor => true, _ => false },
-            Stroke                                  => match resource { DrawResource::CanvasTransform | DrawResource::StrokeLineWidth | DrawResource::StrokeLineCap | DrawResource::StrokeLineJoin | DrawResource::StrokeDash | DrawResource::StrokeColor | DrawResource::FillBlend => true, _ => false },
+            Stroke                                  => match resource { DrawResource::CanvasTransform | DrawResource::StrokeLineWidth | DrawResource::StrokeLineCap | DrawResource::StrokeLineJoin | DrawResource::StrokeMiterLimit | DrawResource::StrokeDash | DrawResource::StrokeColor | DrawResource::FillBlend => true, _ => false },
 
             // Texture and font operations generally alter the existing resource so they have a dependency
             Texture(texture_id, TextureOp::CreateDynamicSprite(sprite_id, _, _)) => {
@@ -67,7 +69,13 @@ impl Draw {
 
             Texture(texture_id, _)                  => resource == &DrawResource::Texture(*texture_id),
             Gradient(gradient_id, _)                => resource == &DrawResource::Gradient(*gradient_id),
-            Font(font_id, FontOp::LayoutText(_))    |
+            Font(font_id, FontOp::FontFeature(_, _))    |
+            Font(font_id, FontOp::FontVariation(_, _))  => resource == &DrawResource::Font(*font_id),
+            Font(font_id, FontOp::LayoutText(_))    => match resource { 
+                DrawResource::Font(resource_font_id) | DrawResource::FontSize(resource_font_id) | DrawResource::FontFallback(resource_font_id) => font_id == resource_font_id,
+                DrawResource::CanvasTransform | DrawResource::FillWindingRule | DrawResource::FillBlend | DrawResource::FillColor => true,
+                _ => false
+            },
             Font(font_id, FontOp::DrawGlyphs(_))    => match resource { 
                 DrawResource::Font(resource_font_id) | DrawResource::FontSize(resource_font_id) => font_id == resource_font_id,
                 DrawResource::CanvasTransform | DrawResource::FillWindingRule | DrawResource::FillBlend | DrawResource::FillColor => true,
@@ -78,12 +86,16 @@ impl Draw {
 
             // DrawText and FillTexture use the corresponding resource
             DrawText(font_id, _, _, _)              => match resource {
-                DrawResource::Font(resource_font_id) | DrawResource::FontSize(resource_font_id) => font_id == resource_font_id,
+                DrawResource::Font(resource_font_id) | DrawResource::FontSize(resource_font_id) | DrawResource::FontFallback(resource_font_id) => font_id == resource_font_id,
                 DrawResource::CanvasTransform => true,
                 _ => false 
             },
             FillTexture(texture_id, _, _)           => resource == &DrawResource::Texture(*texture_id),
             FillGradient(gradient_id, _, _)         => resource == &DrawResource::Gradient(*gradient_id),
+            FillRadialGradient(gradient_id, ..)     => resource == &DrawResource::Gradient(*gradient_id),
+            FillConicGradient(gradient_id, ..)      => resource == &DrawResource::Gradient(*gradient_id),
+            StrokeTexture(texture_id, _, _)         => resource == &DrawResource::Texture(*texture_id),
+            StrokeGradient(gradient_id, _, _)       => resource == &DrawResource::Gradient(*gradient_id),
 
             // Transforms use the 'canvas' resource (setting the height or the identity transform resets any previous transform)
             CenterRegion(_, _)                      |
@@ -119,6 +131,7 @@ impl Draw {
             LineWidth(_)                            |
             LineWidthPixels(_)                      |
             LineJoin(_)                             |
+            MiterLimit(_)                           |
             LineCap(_)                              |
             NewDashPattern                          |
             StrokeColor(_)                          => smallvec![],
@@ -136,21 +149,30 @@ impl Draw {
 
             // The fill and stroke operations depend on multiple resources, so their resource is 'special'
             Fill                                    => smallvec![*active_resource, DrawResource::CanvasTransform, DrawResource::FillWindingRule, DrawResource::FillBlend, DrawResource::FillColor],
-            Stroke                                  => smallvec![*active_resource, DrawResource::CanvasTransform, DrawResource::StrokeLineWidth, DrawResource::StrokeLineCap, DrawResource::StrokeLineJoin, DrawResource::StrokeDash, DrawResource::StrokeColor, DrawResource::FillBlend],
+            Stroke                                  => smallvec![*active_resource, DrawResource::CanvasTransform, DrawResource::StrokeLineWidth, DrawResource::StrokeLineCap, DrawResource::StrokeLineJoin, DrawResource::StrokeMiterLimit, DrawResource::StrokeDash, DrawResource::StrokeColor, DrawResource::FillBlend],
 
             // Texture and font operations generally alter the existing resource so they have a dependency
             Texture(texture_id, TextureOp::CreateDynamicSprite(sprite_id, _, _)) => smallvec![DrawResource::Texture(*texture_id), DrawResource::Sprite(*sprite_id), DrawResource::CanvasTransform],
 
             Texture(texture_id, _)                  => smallvec![DrawResource::Texture(*texture_id)],
-            Font(font_id, FontOp::LayoutText(_))    |
+            Font(font_id, FontOp::FontFeature(_, _))    => smallvec![DrawResource::Font(*font_id)],
+            Font(font_id, FontOp::FontVariation(_, _))  => smallvec![DrawResource::Font(*font_id)],
+
+            // The fallback fonts are needed by anything that uses the fallback list
+            Font(_, FontOp::UseFallbackFonts(fallback_ids)) => fallback_ids.iter().map(|fallback_id| DrawResource::Font(*fallback_id)).collect(),
+            Font(font_id, FontOp::LayoutText(_))    => smallvec![*active_resource, DrawResource::Font(*font_id), DrawResource::FontSize(*font_id), DrawResource::FontFallback(*font_id), DrawResource::CanvasTransform, DrawResource::FillWindingRule, DrawResource::FillBlend, DrawResource::FillColor],
             Font(font_id, FontOp::DrawGlyphs(_))    => smallvec![*active_resource, DrawResource::Font(*font_id), DrawResource::FontSize(*font_id), DrawResource::CanvasTransform, DrawResource::FillWindingRule, DrawResource::FillBlend, DrawResource::FillColor],
 
             DrawSprite(sprite_id)                   => smallvec![DrawResource::CanvasTransform, DrawResource::Sprite(*sprite_id)],
 
             // DrawText and FillTexture use the corresponding resource
-            DrawText(font_id, _, _, _)              => smallvec![*active_resource, DrawResource::CanvasTransform, DrawResource::Font(*font_id), DrawResource::FontSize(*font_id)],
+            DrawText(font_id, _, _, _)              => smallvec![*active_resource, DrawResource::CanvasTransform, DrawResource::Font(*font_id), DrawResource::FontSize(*font_id), DrawResource::FontFallback(*font_id)],
             FillTexture(texture_id, _, _)           => smallvec![DrawResource::Texture(*texture_id)],
             FillGradient(gradient_id, _, _)         => smallvec![DrawResource::Gradient(*gradient_id)],
+            FillRadialGradient(gradient_id, ..)     => smallvec![DrawResource::Gradient(*gradient_id)],
+            FillConicGradient(gradient_id, ..)      => smallvec![DrawResource::Gradient(*gradient_id)],
+            StrokeTexture(texture_id, _, _)         => smallvec![DrawResource::Texture(*texture_id)],
+            StrokeGradient(gradient_id, _, _)       => smallvec![DrawResource::Gradient(*gradient_id)],
             FillTransform(_)                        => smallvec![DrawResource::FillColor],
 
             // Transforms use the 'canvas' resource (setting the height or the identity transform resets any previous transform)
@@ -196,16 +218,21 @@ impl Draw {
             LineWidth(_)                        |
             LineWidthPixels(_)                  => DrawResource::StrokeLineWidth,
             LineJoin(_)                         => DrawResource::StrokeLineJoin,
+            MiterLimit(_)                       => DrawResource::StrokeMiterLimit,
             LineCap(_)                          => DrawResource::StrokeLineCap,
             NewDashPattern                      |
             DashLength(_)                       |
             DashOffset(_)                       => DrawResource::StrokeDash,
-            StrokeColor(_)                      => DrawResource::StrokeColor,
+            StrokeColor(_)                      |
+            StrokeTexture(_, _, _)              |
+            StrokeGradient(_, _, _)             => DrawResource::StrokeColor,
 
             WindingRule(_)                      => DrawResource::FillWindingRule,
             BlendMode(_)                        => DrawResource::FillBlend,
             FillColor(_)                        |
             FillGradient(_, _, _)               |
+            FillRadialGradient(_, _, _, _)      |
+            FillConicGradient(_, _, _)          |
             FillTexture(_, _, _)                |
             FillTransform(_)                    => DrawResource::FillColor,
 
@@ -213,6 +240,7 @@ impl Draw {
             LayerBlend(layer_id, _)             => DrawResource::Layer(*layer_id),
             LayerAlpha(layer_id, _)             => DrawResource::Layer(*layer_id),
             Font(font_id, FontOp::FontSize(_))  => DrawResource::FontSize(*font_id),
+            Font(font_id, FontOp::UseFallbackFonts(_)) => DrawResource::FontFallback(*font_id),
             Font(font_id, _)                    => DrawResource::Font(*font_id),
             Texture(texture_id, _)              => DrawResource::Texture(*texture_id),
 
@@ -242,16 +270,21 @@ impl Draw {
             LineWidth(_)                        |
             LineWidthPixels(_)                  |
             LineJoin(_)                         |
+            MiterLimit(_)                       |
             LineCap(_)                          |
             NewDashPattern                      |
             DashLength(_)                       |
             DashOffset(_)                       |
             StrokeColor(_)                      |
+            StrokeTexture(_, _, _)              |
+            StrokeGradient(_, _, _)             |
 
             WindingRule(_)                      |
             BlendMode(_)                        |
             FillColor(_)                        |
             FillGradient(_, _, _)               |
+            FillRadialGradient(_, _, _, _)      |
+            FillConicGradient(_, _, _)          |
             FillTexture(_, _, _)                |
             FillTransform(_)                    => true,
 
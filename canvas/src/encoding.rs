@@ -213,6 +213,31 @@ impl CanvasEncoding<String> for &WindingRule {
     }
 }
 
+impl CanvasEncoding<String> for &PathArithmetic {
+    fn encode_canvas(&self, append_to: &mut String) {
+        use self::PathArithmetic::*;
+
+        match self {
+            &Union          => 'u',
+            &Intersection   => 'i',
+            &Difference     => 'd',
+            &Xor            => 'x'
+        }.encode_canvas(append_to)
+    }
+}
+
+impl CanvasEncoding<String> for &GradientSpread {
+    fn encode_canvas(&self, append_to: &mut String) {
+        use self::GradientSpread::*;
+
+        match self {
+            &Pad     => 'p',
+            &Repeat  => 'r',
+            &Reflect => 'f'
+        }.encode_canvas(append_to)
+    }
+}
+
 impl CanvasEncoding<String> for &BlendMode {
     fn encode_canvas(&self, append_to: &mut String) {
         use self::BlendMode::*;
@@ -230,7 +255,19 @@ impl CanvasEncoding<String> for &BlendMode {
             &Multiply           => ('E', 'M'),
             &Screen             => ('E', 'S'),
             &Darken             => ('E', 'D'),
-            &Lighten            => ('E', 'L')
+            &Lighten            => ('E', 'L'),
+            &Overlay            => ('E', 'O'),
+            &ColorDodge         => ('E', 'C'),
+            &ColorBurn          => ('E', 'B'),
+            &HardLight          => ('E', 'H'),
+            &SoftLight          => ('E', 'T'),
+            &Difference         => ('E', 'F'),
+            &Exclusion          => ('E', 'X'),
+
+            &Hue                => ('N', 'H'),
+            &Saturation         => ('N', 'S'),
+            &Color              => ('N', 'C'),
+            &Luminosity         => ('N', 'L'),
         }.encode_canvas(append_to)
     }
 }
@@ -277,6 +314,13 @@ impl CanvasEncoding<String> for &FontId {
     }
 }
 
+impl<'a> CanvasEncoding<String> for &'a Vec<FontId> {
+    fn encode_canvas(&self, append_to: &mut String) {
+        encode_compact_u64(&(self.len() as u64), append_to);
+        self.iter().for_each(|font_id| font_id.encode_canvas(append_to));
+    }
+}
+
 impl CanvasEncoding<String> for &GradientId {
     #[inline]
     fn encode_canvas(&self, append_to: &mut String) {
@@ -285,6 +329,15 @@ impl CanvasEncoding<String> for &GradientId {
     }
 }
 
+impl CanvasEncoding<String> for &Option<(f32, f32)> {
+    fn encode_canvas(&self, append_to: &mut String) {
+        match self {
+            None            => 'n'.encode_canvas(append_to),
+            Some((x, y))    => ('p', x, y).encode_canvas(append_to),
+        }
+    }
+}
+
 impl CanvasEncoding<String> for &SpriteTransform {
     fn encode_canvas(&self, append_to: &mut String) {
         use self::SpriteTransform::*;
@@ -352,7 +405,8 @@ impl<'a> CanvasEncoding<String> for &'a GradientOp {
 
         match self {
             Create(color)       => ('N', color).encode_canvas(append_to),
-            AddStop(pos, color) => ('S', pos, color).encode_canvas(append_to)
+            AddStop(pos, color) => ('S', pos, color).encode_canvas(append_to),
+            Spread(spread)      => ('P', spread).encode_canvas(append_to)
         }
     }
 }
@@ -363,6 +417,9 @@ impl<'a> CanvasEncoding<String> for &'a FontOp {
 
         match self {
             FontSize(font_size)                     => ('S', *font_size).encode_canvas(append_to),
+            UseFallbackFonts(font_ids)              => ('F', font_ids).encode_canvas(append_to),
+            FontFeature(tag, enabled)               => ('O', u32::from_be_bytes(*tag), if *enabled { 'Y' } else { 'N' }).encode_canvas(append_to),
+            FontVariation(tag, value)               => ('V', u32::from_be_bytes(*tag), *value).encode_canvas(append_to),
 
             UseFontDefinition(data)                 => ('d', 'T', data.font_data()).encode_canvas(append_to),
             DrawGlyphs(glyphs)                      => ('G', glyphs).encode_canvas(append_to),
@@ -383,6 +440,28 @@ impl<'a> CanvasEncoding<String> for &TextAlignment {
     }
 }
 
+impl<'a> CanvasEncoding<String> for &'a ParagraphStyle {
+    fn encode_canvas(&self, append_to: &mut String) {
+        use ParagraphAlignment::*;
+        use TextOverflow::*;
+
+        let alignment = match self.alignment {
+            Left        => 'l',
+            Right       => 'r',
+            Center      => 'c',
+            Justify     => 'j',
+        };
+
+        let (overflow, max_height) = match self.overflow {
+            Visible                 => ('v', 0.0),
+            Clip(max_height)        => ('c', max_height),
+            Ellipsis(max_height)    => ('e', max_height),
+        };
+
+        (self.width, alignment, self.line_height, self.paragraph_spacing, overflow, max_height).encode_canvas(append_to);
+    }
+}
+
 impl<'a> CanvasEncoding<String> for &'a FontStyle {
     fn encode_canvas(&self, append_to: &mut String) {
         use FontStyle::*;
@@ -514,21 +593,30 @@ impl CanvasEncoding<String> for Draw {
             Path(Move(x, y))                            => ('m', x, y).encode_canvas(append_to),
             Path(Line(x, y))                            => ('l', x, y).encode_canvas(append_to),
             Path(BezierCurve((cp1, cp2), p))            => ('c', *p, *cp1, *cp2).encode_canvas(append_to),
+            Path(QuadraticCurve(cp, p))                 => ('q', *p, *cp).encode_canvas(append_to),
+            Path(Arc(center, radii, rotation, start, sweep)) => ('a', *center, *radii, *rotation, *start, *sweep).encode_canvas(append_to),
             Path(ClosePath)                             => ('.').encode_canvas(append_to),
+            StorePath                                   => ('Z', 'P').encode_canvas(append_to),
+            CombinePath(operation)                      => ('Z', 'A', operation).encode_canvas(append_to),
             Fill                                        => 'F'.encode_canvas(append_to),
             Stroke                                      => 'S'.encode_canvas(append_to),
             LineWidth(width)                            => ('L', 'w', width).encode_canvas(append_to),
             LineWidthPixels(width)                      => ('L', 'p', width).encode_canvas(append_to),
             LineJoin(join)                              => ('L', 'j', join).encode_canvas(append_to),
+            MiterLimit(limit)                           => ('L', 'm', limit).encode_canvas(append_to),
             LineCap(cap)                                => ('L', 'c', cap).encode_canvas(append_to),
             WindingRule(rule)                           => ('W', rule).encode_canvas(append_to),
             NewDashPattern                              => ('D', 'n').encode_canvas(append_to),
             DashLength(length)                          => ('D', 'l', length).encode_canvas(append_to),
             DashOffset(offset)                          => ('D', 'o', offset).encode_canvas(append_to),
             StrokeColor(col)                            => ('C', 's', col).encode_canvas(append_to),
+            StrokeTexture(texture, (x1, y1), (x2, y2))  => ('C', 'X', texture, (x1, y1), (x2, y2)).encode_canvas(append_to),
+            StrokeGradient(gradient, start, end)        => ('C', 'G', gradient, *start, *end).encode_canvas(append_to),
             FillColor(col)                              => ('C', 'f', col).encode_canvas(append_to),
             FillTexture(texture, (x1, y1), (x2, y2))    => ('C', 't', texture, (x1, y1), (x2, y2)).encode_canvas(append_to),
             FillGradient(gradient, (x1, y1), (x2, y2))  => ('C', 'g', gradient, (x1, y1), (x2, y2)).encode_canvas(append_to),
+            FillRadialGradient(gradient, pos, r, focus) => ('C', 'r', gradient, *pos, r, focus).encode_canvas(append_to),
+            FillConicGradient(gradient, pos, angle)     => ('C', 'c', gradient, *pos, angle).encode_canvas(append_to),
             FillTransform(transform)                    => ('C', 'T', transform).encode_canvas(append_to),
             BlendMode(mode)                             => ('M', mode).encode_canvas(append_to),
             IdentityTransform                           => ('T', 'i').encode_canvas(append_to),
@@ -559,6 +647,7 @@ impl CanvasEncoding<String> for Draw {
             Font(font_id, ref op)                       => ('f', font_id, op).encode_canvas(append_to),
             DrawText(font_id, ref string, x, y)         => ('t', 'T', font_id, string, x, y).encode_canvas(append_to),
             BeginLineLayout(x, y, align)                => ('t', 'l', x, y, align).encode_canvas(append_to),
+            BeginParagraphLayout(x, y, style)           => ('t', 'p', x, y, style).encode_canvas(append_to),
             DrawLaidOutText                             => ('t', 'R').encode_canvas(append_to),
             Gradient(gradient_id, ref gradient_op)      => ('G', gradient_id, gradient_op).encode_canvas(append_to),
             Namespace(namespace_id)                     => ('N', 'N', namespace_id).encode_canvas(append_to),
@@ -611,6 +700,10 @@ mod test {
     #[test]
     fn encode_bezier() { assert!(&encode_draw(Draw::Path(PathOp::BezierCurve(((20.0, 20.0), (20.0, 20.0)), (20.0, 20.0)))) == "cAAAoBBAAAoBBAAAoBBAAAoBBAAAoBBAAAoBB") }
     #[test]
+    fn encode_quadratic() { assert!(&encode_draw(Draw::Path(PathOp::QuadraticCurve((20.0, 20.0), (20.0, 20.0)))) == "qAAAoBBAAAoBBAAAoBBAAAoBB") }
+    #[test]
+    fn encode_arc() { assert!(&encode_draw(Draw::Path(PathOp::Arc((20.0, 20.0), (20.0, 20.0), 20.0, 20.0, 20.0))) == "aAAAoBBAAAoBBAAAoBBAAAoBBAAAoBBAAAoBBAAAoBB") }
+    #[test]
     fn encode_close_path() { assert!(&encode_draw(Draw::Path(PathOp::ClosePath)) == ".") }
     #[test]
     fn encode_fill() { assert!(&encode_draw(Draw::Fill) == "F") }
@@ -623,6 +716,8 @@ mod test {
     #[test]
     fn encode_linejoin() { assert!(&encode_draw(Draw::LineJoin(LineJoin::Bevel)) == "LjB") }
     #[test]
+    fn encode_miterlimit() { assert!(&encode_draw(Draw::MiterLimit(20.0)) == "LmAAAoBB") }
+    #[test]
     fn encode_linecap() { assert!(&encode_draw(Draw::LineCap(LineCap::Butt)) == "LcB") }
     #[test]
     fn encode_newdashpattern() { assert!(&encode_draw(Draw::NewDashPattern) == "Dn") }
@@ -637,6 +732,8 @@ mod test {
     #[test]
     fn encode_blendmode() { assert!(&encode_draw(Draw::BlendMode(BlendMode::SourceOver)) == "MSV") }
     #[test]
+    fn encode_blendmode_luminosity() { assert!(&encode_draw(Draw::BlendMode(BlendMode::Luminosity)) == "MNL") }
+    #[test]
     fn encode_identity_transform() { assert!(&encode_draw(Draw::IdentityTransform) == "Ti") }
     #[test]
     fn encode_canvas_height() { assert!(&encode_draw(Draw::CanvasHeight(20.0)) == "ThAAAoBB") }
@@ -675,5 +772,11 @@ mod test {
     #[test]
     fn encode_evenodd_winding_rule() { assert!(&encode_draw(Draw::WindingRule(WindingRule::EvenOdd)) == "We") }
     #[test]
+    fn encode_store_path() { assert!(&encode_draw(Draw::StorePath) == "ZP") }
+    #[test]
+    fn encode_combine_path() { assert!(&encode_draw(Draw::CombinePath(PathArithmetic::Difference)) == "ZAd") }
+    #[test]
+    fn encode_gradient_spread() { assert!(&encode_draw(Draw::Gradient(GradientId(1), GradientOp::Spread(GradientSpread::Repeat))) == "GBPr") }
+    #[test]
     fn encode_default_namespace() { assert!(&encode_draw(Draw::Namespace(NamespaceId::default())) == "NNAAAAAAAAAAAAAAAAAAAAAA", "{}", encode_draw(Draw::Namespace(NamespaceId::default()))) }
 }
@@ -52,6 +52,9 @@ impl FontProperties {
 ///
 /// Determines how text is drawn relative to its alignment's origin point
 ///
+/// Alignments follow the direction of the paragraph: left-aligned text in a right-to-left paragraph ends at the
+/// origin point and right-aligned text starts there.
+///
 #[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum TextAlignment {
     Left,
@@ -59,6 +62,104 @@ pub enum TextAlignment {
     Center
 }
 
+///
+/// How the lines of a paragraph are aligned within its width
+///
+/// As with `TextAlignment`, left and right alignment follow the direction of the paragraph.
+///
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ParagraphAlignment {
+    Left,
+    Right,
+    Center,
+
+    /// Lines are stretched to fill the width of the paragraph (except the last line of each paragraph, which is left-aligned)
+    Justify
+}
+
+///
+/// What happens to the lines of a paragraph that extend past its maximum height
+///
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum TextOverflow {
+    /// All of the lines are drawn
+    Visible,
+
+    /// Lines that don't fit within the specified height are left out
+    Clip(f32),
+
+    /// Lines that don't fit within the specified height are left out, and the last line is ended with an ellipsis
+    Ellipsis(f32),
+}
+
+///
+/// Describes how text is wrapped into a paragraph
+///
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ParagraphStyle {
+    /// The width that lines are wrapped to
+    pub width: f32,
+
+    /// How lines are aligned within the width
+    pub alignment: ParagraphAlignment,
+
+    /// The spacing between lines, as a multiple of the height of the fonts on the line
+    pub line_height: f32,
+
+    /// Extra space to leave after each paragraph (which ends with a newline)
+    pub paragraph_spacing: f32,
+
+    /// What happens to text that doesn't fit in the paragraph
+    pub overflow: TextOverflow,
+}
+
+impl ParagraphStyle {
+    ///
+    /// Creates a style for left-aligned paragraphs with the specified width
+    ///
+    pub fn new(width: f32) -> ParagraphStyle {
+        ParagraphStyle {
+            width:              width,
+            alignment:          ParagraphAlignment::Left,
+            line_height:        1.0,
+            paragraph_spacing:  0.0,
+            overflow:           TextOverflow::Visible,
+        }
+    }
+
+    ///
+    /// Returns an updated style with a new alignment
+    ///
+    pub fn with_alignment(mut self, alignment: ParagraphAlignment) -> ParagraphStyle {
+        self.alignment = alignment;
+        self
+    }
+
+    ///
+    /// Returns an updated style with a new line height (as a multiple of the font's line height)
+    ///
+    pub fn with_line_height(mut self, line_height: f32) -> ParagraphStyle {
+        self.line_height = line_height;
+        self
+    }
+
+    ///
+    /// Returns an updated style with a new amount of space between paragraphs
+    ///
+    pub fn with_paragraph_spacing(mut self, paragraph_spacing: f32) -> ParagraphStyle {
+        self.paragraph_spacing = paragraph_spacing;
+        self
+    }
+
+    ///
+    /// Returns an updated style with a new way to deal with overflowing text
+    ///
+    pub fn with_overflow(mut self, overflow: TextOverflow) -> ParagraphStyle {
+        self.overflow = overflow;
+        self
+    }
+}
+
 ///
 /// Operations that can be performed on a font
 ///
@@ -70,6 +171,15 @@ pub enum FontOp {
     /// Sets the font size to use for this font ID (in canvas units)
     FontSize(f32),
 
+    /// Sets the fonts (in order of preference) to use for any characters that are missing from this font
+    UseFallbackFonts(Vec<FontId>),
+
+    /// Turns an OpenType feature (such as `*b"liga"`, `*b"tnum"` or `*b"ss01"`) on or off for this font ID
+    FontFeature([u8; 4], bool),
+
+    /// Sets the value of a variation axis (such as `*b"wght"` or `*b"wdth"`) for this font ID, if it's a variable font
+    FontVariation([u8; 4], f32),
+
     /// Lays out some text in the active layout, to be rendered in the current fill style
     LayoutText(String),
 
@@ -148,6 +258,24 @@ pub struct TextLayoutMetrics {
     pub pos: Coord2
 }
 
+///
+/// The layout metrics for a paragraph of text
+///
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ParagraphLayoutMetrics {
+    /// The number of lines that will be drawn
+    pub lines: usize,
+
+    /// The height of the lines that will be drawn
+    pub height: f32,
+
+    /// The height that all of the lines would need to be drawn without clipping
+    pub full_height: f32,
+
+    /// True if some of the text doesn't fit in the paragraph
+    pub overflowed: bool,
+}
+
 ///
 /// ID for a glyph within a font
 ///
@@ -43,6 +43,15 @@ mod canvas_font_face {
     pub struct CanvasFontFace {
         /// Data for this font face
         data: Arc<Pin<Box<[u8]>>>,
+
+        /// The index of this font face within the data
+        font_index: u32,
+
+        /// The OpenType features that have been turned on or off for this font face
+        features: Vec<([u8; 4], bool)>,
+
+        /// The values of the variation axes for this font face
+        variations: Vec<([u8; 4], f32)>,
     }
 
     impl CanvasFontFace {
@@ -68,10 +77,17 @@ mod canvas_font_face {
             Arc::new(Self::from_pinned(Arc::new(data.into()), 0))
         }
 
-        pub (crate) fn from_pinned(data: Arc<Pin<Box<[u8]>>>, _font_index: u32) -> CanvasFontFace {
+        pub (crate) fn from_pinned(data: Arc<Pin<Box<[u8]>>>, font_index: u32) -> CanvasFontFace {
+            Self::from_settings(data, font_index, vec![], vec![])
+        }
+
+        pub (crate) fn from_settings(data: Arc<Pin<Box<[u8]>>>, font_index: u32, features: Vec<([u8; 4], bool)>, variations: Vec<([u8; 4], f32)>) -> CanvasFontFace {
             // Generate the font face
             CanvasFontFace {
                 data:       data,
+                font_index: font_index,
+                features:   features,
+                variations: variations,
             }
         }
 
@@ -81,6 +97,42 @@ mod canvas_font_face {
         pub fn font_data<'a>(&'a self) -> &'a [u8] {
             &***self.borrow_data()
         }
+
+        ///
+        /// The OpenType features that have been turned on or off for this font face
+        ///
+        pub fn features(&self) -> &[([u8; 4], bool)] {
+            &self.features
+        }
+
+        ///
+        /// The values that have been set for the variation axes of this font face
+        ///
+        pub fn variations(&self) -> &[([u8; 4], f32)] {
+            &self.variations
+        }
+
+        ///
+        /// Returns a copy of this font face with an OpenType feature (such as `*b"liga"` or `*b"tnum"`) turned on or off
+        ///
+        pub fn with_feature(&self, tag: [u8; 4], enabled: bool) -> Arc<CanvasFontFace> {
+            let mut features = self.features.clone();
+            features.retain(|(feature, _)| feature != &tag);
+            features.push((tag, enabled));
+
+            Arc::new(Self::from_settings(Arc::clone(&self.data), self.font_index, features, self.variations.clone()))
+        }
+
+        ///
+        /// Returns a copy of this font face with a new value for one of its variation axes (such as `*b"wght"` or `*b"wdth"`)
+        ///
+        pub fn with_variation(&self, tag: [u8; 4], value: f32) -> Arc<CanvasFontFace> {
+            let mut variations = self.variations.clone();
+            variations.retain(|(axis, _)| axis != &tag);
+            variations.push((tag, value));
+
+            Arc::new(Self::from_settings(Arc::clone(&self.data), self.font_index, self.features.clone(), variations))
+        }
     }
 }
 
@@ -109,6 +161,15 @@ mod canvas_font_face {
         /// Data for this font face
         data: Arc<Pin<Box<[u8]>>>,
 
+        /// The index of this font face within the data
+        font_index: u32,
+
+        /// The OpenType features that have been turned on or off for this font face
+        features: Vec<([u8; 4], bool)>,
+
+        /// The values of the variation axes for this font face
+        variations: Vec<([u8; 4], f32)>,
+
         /// The font face for the data
         #[borrows(data)] #[covariant] ttf_font: ttf_parser::Face<'this>,
     }
@@ -132,10 +193,29 @@ mod canvas_font_face {
 
         #[cfg(feature = "outline-fonts")]
         pub (crate) fn from_pinned(data: Arc<Pin<Box<[u8]>>>, font_index: u32) -> CanvasFontFace {
+            Self::from_settings(data, font_index, vec![], vec![])
+        }
+
+        pub (crate) fn from_settings(data: Arc<Pin<Box<[u8]>>>, font_index: u32, features: Vec<([u8; 4], bool)>, variations: Vec<([u8; 4], f32)>) -> CanvasFontFace {
+            // The variation axes are applied to the TTF face, so the metrics and outlines it returns are for the varied font
+            let axes = variations.clone();
+
             // Load into the TTF parser with scary self-referential data
             let font_face = CanvasFontFaceBuilder {
                 data:               data,
-                ttf_font_builder:   |data: &Arc<Pin<Box<[u8]>>>| { ttf_parser::Face::parse(&**data, font_index as _).unwrap() },
+                font_index:         font_index,
+                features:           features,
+                variations:         variations,
+                ttf_font_builder:   move |data: &Arc<Pin<Box<[u8]>>>| {
+                    let mut face = ttf_parser::Face::parse(&**data, font_index as _).unwrap();
+
+                    // Axes that the font doesn't have are ignored
+                    for (axis, value) in axes.iter() {
+                        face.set_variation(ttf_parser::Tag::from_bytes(axis), *value);
+                    }
+
+                    face
+                },
             }.build();
 
             // Generate the font face
@@ -148,6 +228,48 @@ mod canvas_font_face {
         pub fn font_data<'a>(&'a self) -> &'a [u8] {
             &***self.borrow_data()
         }
+
+        ///
+        /// The OpenType features that have been turned on or off for this font face
+        ///
+        pub fn features(&self) -> &[([u8; 4], bool)] {
+            self.borrow_features()
+        }
+
+        ///
+        /// The values that have been set for the variation axes of this font face
+        ///
+        pub fn variations(&self) -> &[([u8; 4], f32)] {
+            self.borrow_variations()
+        }
+
+        ///
+        /// Returns a copy of this font face with an OpenType feature (such as `*b"liga"` or `*b"tnum"`) turned on or off
+        ///
+        /// The features are used when shaping text with this font face
+        ///
+        pub fn with_feature(&self, tag: [u8; 4], enabled: bool) -> Arc<CanvasFontFace> {
+            let mut features = self.features().to_vec();
+            features.retain(|(feature, _)| feature != &tag);
+            features.push((tag, enabled));
+
+            Arc::new(Self::from_settings(Arc::clone(self.borrow_data()), *self.borrow_font_index(), features, self.variations().to_vec()))
+        }
+
+        ///
+        /// Returns a copy of this font face with a new value for one of its variation axes (such as `*b"wght"` or `*b"wdth"`)
+        ///
+        /// The metrics, advances and outlines returned by `ttf_font()` are all adjusted for the variation axes. allsorts
+        /// can't vary the OpenType layout tables, so substitutions and GPOS positioning (kerning and mark placement) always
+        /// use the values from the default instance of the font.
+        ///
+        pub fn with_variation(&self, tag: [u8; 4], value: f32) -> Arc<CanvasFontFace> {
+            let mut variations = self.variations().to_vec();
+            variations.retain(|(axis, _)| axis != &tag);
+            variations.push((tag, value));
+
+            Arc::new(Self::from_settings(Arc::clone(self.borrow_data()), *self.borrow_font_index(), self.features().to_vec(), variations))
+        }
     }
 
     ///
@@ -223,6 +345,8 @@ pub use self::canvas_font_face::*;
 impl PartialEq for CanvasFontFace {
     fn eq(&self, other: &CanvasFontFace) -> bool {
         self.font_data().eq(other.font_data())
+            && self.features() == other.features()
+            && self.variations() == other.variations()
     }
 }
 
@@ -230,6 +354,8 @@ impl fmt::Debug for CanvasFontFace {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("CanvasFontFace")
          .field("data", &self.font_data())
+         .field("features", &self.features())
+         .field("variations", &self.variations())
          .finish()
     }
 }
@@ -238,8 +364,10 @@ impl Serialize for CanvasFontFace {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
     S: Serializer {
-        let mut s = serializer.serialize_struct("CanvasFontFace", 1)?;
+        let mut s = serializer.serialize_struct("CanvasFontFace", 3)?;
         s.serialize_field("data", self.font_data())?;
+        s.serialize_field("features", self.features())?;
+        s.serialize_field("variations", self.variations())?;
         s.end()
     }
 }
@@ -248,8 +376,8 @@ impl<'de> Deserialize<'de> for CanvasFontFace {
     fn deserialize<D>(deserializer: D) -> Result<CanvasFontFace, D::Error>
     where D: Deserializer<'de> {
         // Field deserializer
-        enum Field { Data }
-        const FIELDS: &'static [&'static str] = &["data"];
+        enum Field { Data, Features, Variations }
+        const FIELDS: &'static [&'static str] = &["data", "features", "variations"];
 
         impl<'de> Deserialize<'de> for Field {
             fn deserialize<D>(deserializer: D) -> Result<Field, D::Error>
@@ -259,14 +387,16 @@ impl<'de> Deserialize<'de> for CanvasFontFace {
                     type Value = Field;
 
                     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("`data`")
+                        formatter.write_str("`data`, `features` or `variations`")
                     }
 
                     fn visit_str<E>(self, value: &str) -> Result<Field, E>
                     where E: de::Error {
                         match value {
-                            "data"  => Ok(Field::Data),
-                            _       => Err(de::Error::unknown_field(value, FIELDS)),
+                            "data"          => Ok(Field::Data),
+                            "features"      => Ok(Field::Features),
+                            "variations"    => Ok(Field::Variations),
+                            _               => Err(de::Error::unknown_field(value, FIELDS)),
                         }
                     }
                 }
@@ -286,15 +416,20 @@ impl<'de> Deserialize<'de> for CanvasFontFace {
 
             fn visit_seq<V>(self, mut seq: V) -> Result<CanvasFontFace, V::Error>
             where V: SeqAccess<'de> {
+                // Font faces serialized before features and variations were added only have the data
                 let bytes: Vec<u8>  = seq.next_element()? .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let features        = seq.next_element()?.unwrap_or_else(|| vec![]);
+                let variations      = seq.next_element()?.unwrap_or_else(|| vec![]);
                 let data            = bytes.into_boxed_slice();
                 let data            = Arc::new(data.into());
-                Ok(CanvasFontFace::from_pinned(data, 0))
+                Ok(CanvasFontFace::from_settings(data, 0, features, variations))
             }
 
             fn visit_map<V>(self, mut map: V) -> Result<CanvasFontFace, V::Error>
             where V: MapAccess<'de> {
-                let mut data: Option<Vec<u8>> = None;
+                let mut data: Option<Vec<u8>>                       = None;
+                let mut features: Option<Vec<([u8; 4], bool)>>      = None;
+                let mut variations: Option<Vec<([u8; 4], f32)>>     = None;
                 while let Some(key) = map.next_key()? {
                     match key {
                         Field::Data => {
@@ -303,13 +438,28 @@ impl<'de> Deserialize<'de> for CanvasFontFace {
                             }
                             data = Some(map.next_value()?);
                         }
+
+                        Field::Features => {
+                            if features.is_some() {
+                                return Err(de::Error::duplicate_field("features"));
+                            }
+                            features = Some(map.next_value()?);
+                        }
+
+                        Field::Variations => {
+                            if variations.is_some() {
+                                return Err(de::Error::duplicate_field("variations"));
+                            }
+                            variations = Some(map.next_value()?);
+                        }
                     }
                 }
 
+                // Font faces serialized before features and variations were added only have the data
                 let data            = data.ok_or_else(|| de::Error::missing_field("data"))?;
                 let data            = data.into_boxed_slice();
                 let data            = Arc::new(data.into());
-                Ok(CanvasFontFace::from_pinned(data, 0))
+                Ok(CanvasFontFace::from_settings(data, 0, features.unwrap_or_else(|| vec![]), variations.unwrap_or_else(|| vec![])))
             }
         }
 
@@ -336,6 +486,31 @@ mod test {
         font.allsorts_font();
     }
 
+    #[test]
+    fn font_face_with_settings_shares_data() {
+        let font        = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+        let with_tnum   = font.with_feature(*b"tnum", true);
+        let with_weight = with_tnum.with_variation(*b"wght", 700.0).with_variation(*b"wght", 300.0);
+
+        assert!(with_weight.font_data().as_ptr() == font.font_data().as_ptr());
+        assert!(with_weight.features() == &[(*b"tnum", true)]);
+        assert!(with_weight.variations() == &[(*b"wght", 300.0)]);
+        assert!(*with_tnum != *font);
+        assert!(*with_tnum == *font.with_feature(*b"tnum", true));
+    }
+
+    #[cfg(feature = "outline-fonts")]
+    #[test]
+    fn variation_is_applied_to_ttf_font() {
+        let font        = CanvasFontFace::from_slice(include_bytes!("../test_data/VariableTest.ttf"));
+        let glyph       = font.ttf_font().glyph_index('a').unwrap();
+
+        // The test font's HVAR table adds 500 units to the advance of 'a' at the maximum weight
+        assert!(font.ttf_font().glyph_hor_advance(glyph) == Some(1000));
+        assert!(font.with_variation(*b"wght", 900.0).ttf_font().glyph_hor_advance(glyph) == Some(1500));
+        assert!(font.with_variation(*b"wght", 650.0).ttf_font().glyph_hor_advance(glyph) == Some(1250));
+    }
+
     #[test]
     fn serialize_deserialize_font_face() {
         let font    = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
@@ -344,4 +519,27 @@ mod test {
 
         assert!(font == decoded);
     }
+
+    #[test]
+    fn serialize_deserialize_font_settings() {
+        let font    = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+        let font    = font.with_feature(*b"liga", false).with_variation(*b"wght", 700.0);
+        let encoded = serde_json::to_string(&font).unwrap();
+        let decoded = serde_json::from_str::<Arc<CanvasFontFace>>(&encoded).unwrap();
+
+        assert!(decoded.features() == &[(*b"liga", false)]);
+        assert!(decoded.variations() == &[(*b"wght", 700.0)]);
+        assert!(font == decoded);
+    }
+
+    #[test]
+    fn deserialize_font_face_without_settings() {
+        let font    = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+        let encoded = serde_json::to_string(&serde_json::json!({ "data": font.font_data() })).unwrap();
+        let decoded = serde_json::from_str::<Arc<CanvasFontFace>>(&encoded).unwrap();
+
+        assert!(decoded.font_data() == font.font_data());
+        assert!(decoded.features().is_empty());
+        assert!(decoded.variations().is_empty());
+    }
 }
@@ -11,9 +11,302 @@ use allsorts::font::{MatchingPresentation};
 use allsorts::gpos;
 use allsorts::gsub;
 
+use unicode_bidi::{BidiInfo, BidiClass, Level, bidi_class};
+use unicode_bidi_mirroring::{get_mirrored};
+
 use std::mem;
+use std::iter;
 use std::sync::*;
 
+/// OpenType script tags for the scripts that are detected from text
+const LATN: u32 = u32::from_be_bytes(*b"latn");
+const GREK: u32 = u32::from_be_bytes(*b"grek");
+const CYRL: u32 = u32::from_be_bytes(*b"cyrl");
+const ARMN: u32 = u32::from_be_bytes(*b"armn");
+const HEBR: u32 = u32::from_be_bytes(*b"hebr");
+const ARAB: u32 = u32::from_be_bytes(*b"arab");
+const SYRC: u32 = u32::from_be_bytes(*b"syrc");
+const THAA: u32 = u32::from_be_bytes(*b"thaa");
+const DEVA: u32 = u32::from_be_bytes(*b"deva");
+const BENG: u32 = u32::from_be_bytes(*b"beng");
+const GURU: u32 = u32::from_be_bytes(*b"guru");
+const GUJR: u32 = u32::from_be_bytes(*b"gujr");
+const ORYA: u32 = u32::from_be_bytes(*b"orya");
+const TAML: u32 = u32::from_be_bytes(*b"taml");
+const TELU: u32 = u32::from_be_bytes(*b"telu");
+const KNDA: u32 = u32::from_be_bytes(*b"knda");
+const MLYM: u32 = u32::from_be_bytes(*b"mlym");
+const SINH: u32 = u32::from_be_bytes(*b"sinh");
+const THAI: u32 = u32::from_be_bytes(*b"thai");
+const LAO:  u32 = u32::from_be_bytes(*b"lao ");
+const TIBT: u32 = u32::from_be_bytes(*b"tibt");
+const MYMR: u32 = u32::from_be_bytes(*b"mymr");
+const GEOR: u32 = u32::from_be_bytes(*b"geor");
+const HANG: u32 = u32::from_be_bytes(*b"hang");
+const ETHI: u32 = u32::from_be_bytes(*b"ethi");
+const KHMR: u32 = u32::from_be_bytes(*b"khmr");
+const MONG: u32 = u32::from_be_bytes(*b"mong");
+const KANA: u32 = u32::from_be_bytes(*b"kana");
+const HANI: u32 = u32::from_be_bytes(*b"hani");
+
+///
+/// Returns the OpenType script tag for a character, or None for characters that are used by many scripts (such as
+/// spaces, digits, punctuation and combining marks), which take on the script of the text around them
+///
+fn script_for_char(chr: char) -> Option<u32> {
+    match chr as u32 {
+        0x0041..=0x005a | 0x0061..=0x007a | 0x00aa | 0x00ba    => Some(LATN),
+        0x00c0..=0x00d6 | 0x00d8..=0x00f6 | 0x00f8..=0x024f    => Some(LATN),
+        0x1e00..=0x1eff | 0x2c60..=0x2c7f | 0xa720..=0xa7ff    => Some(LATN),
+        0xfb00..=0xfb06 | 0xff21..=0xff3a | 0xff41..=0xff5a    => Some(LATN),
+
+        0x0370..=0x03ff | 0x1f00..=0x1fff                      => Some(GREK),
+        0x0400..=0x052f | 0x2de0..=0x2dff | 0xa640..=0xa69f    => Some(CYRL),
+        0x0530..=0x058f                                         => Some(ARMN),
+        0x0590..=0x05ff | 0xfb1d..=0xfb4f                      => Some(HEBR),
+        0x0600..=0x06ff | 0x0750..=0x077f | 0x08a0..=0x08ff    => Some(ARAB),
+        0xfb50..=0xfdff | 0xfe70..=0xfeff                      => Some(ARAB),
+        0x0700..=0x074f                                         => Some(SYRC),
+        0x0780..=0x07bf                                         => Some(THAA),
+
+        0x0900..=0x097f | 0xa8e0..=0xa8ff                      => Some(DEVA),
+        0x0980..=0x09ff                                         => Some(BENG),
+        0x0a00..=0x0a7f                                         => Some(GURU),
+        0x0a80..=0x0aff                                         => Some(GUJR),
+        0x0b00..=0x0b7f                                         => Some(ORYA),
+        0x0b80..=0x0bff                                         => Some(TAML),
+        0x0c00..=0x0c7f                                         => Some(TELU),
+        0x0c80..=0x0cff                                         => Some(KNDA),
+        0x0d00..=0x0d7f                                         => Some(MLYM),
+        0x0d80..=0x0dff                                         => Some(SINH),
+
+        0x0e00..=0x0e7f                                         => Some(THAI),
+        0x0e80..=0x0eff                                         => Some(LAO),
+        0x0f00..=0x0fff                                         => Some(TIBT),
+        0x1000..=0x109f                                         => Some(MYMR),
+        0x10a0..=0x10ff                                         => Some(GEOR),
+        0x1100..=0x11ff | 0x3130..=0x318f | 0xac00..=0xd7af    => Some(HANG),
+        0x1200..=0x137f                                         => Some(ETHI),
+        0x1780..=0x17ff                                         => Some(KHMR),
+        0x1800..=0x18af                                         => Some(MONG),
+
+        0x3040..=0x30ff | 0x31f0..=0x31ff                      => Some(KANA),
+        0x2e80..=0x2fdf | 0x3400..=0x4dbf | 0x4e00..=0x9fff    => Some(HANI),
+        0xf900..=0xfaff | 0x20000..=0x2ffff                    => Some(HANI),
+
+        _                                                       => None
+    }
+}
+
+///
+/// Returns the character to use in place of a character in right-to-left text (brackets and similar characters are mirrored,
+/// using the Bidi_Mirroring_Glyph property from the Unicode character database)
+///
+fn mirrored_char(chr: char) -> char {
+    get_mirrored(chr).unwrap_or(chr)
+}
+
+///
+/// Splits text into runs that are each in a single script (characters that are in no particular script join the run
+/// they're in, or the following run if they're at the start of the text)
+///
+fn script_runs(text: &str) -> Vec<(u32, &str)> {
+    let mut runs        = vec![];
+    let mut run_start   = 0;
+    let mut run_script  = None;
+
+    for (idx, chr) in text.char_indices() {
+        let script = if let Some(script) = script_for_char(chr) { script } else { continue; };
+
+        match run_script {
+            None                                => { run_script = Some(script); }
+            Some(current) if current != script  => {
+                runs.push((current, &text[run_start..idx]));
+
+                run_start   = idx;
+                run_script  = Some(script);
+            }
+            _                                   => { }
+        }
+    }
+
+    if run_start < text.len() {
+        runs.push((run_script.unwrap_or(LATN), &text[run_start..]));
+    }
+
+    runs
+}
+
+///
+/// True if a character is drawn as part of the character before it (combining marks, joiners, variation selectors
+/// and emoji modifiers), so it needs to be in the same font
+///
+fn extends_cluster(chr: char) -> bool {
+    match chr as u32 {
+        0x200c | 0x200d                                         => true,
+        0xfe00..=0xfe0f | 0xe0100..=0xe01ef                     => true,
+        0x1f3fb..=0x1f3ff | 0xe0020..=0xe007f                   => true,
+        _                                                       => bidi_class(chr) == BidiClass::NSM
+    }
+}
+
+/// The OpenType features that are used to shape text when a font face turns on a feature that allsorts has no mask for
+/// The features that can be turned on or off in an allsorts feature mask, in the order they're applied
+const MASK_FEATURES: [[u8; 4]; 23] = [
+    *b"ccmp", *b"locl", *b"rlig", *b"liga", *b"clig", *b"calt", *b"dlig", *b"hlig", *b"afrc", *b"frac", *b"smcp", *b"c2sc", *b"case",
+    *b"lnum", *b"onum", *b"pnum", *b"tnum", *b"ordn", *b"sups", *b"subs", *b"zero", *b"vert", *b"vrt2",
+];
+
+///
+/// Returns the features to use when shaping text in a font face, and whether or not the text should be kerned
+///
+fn shaping_features(font: &CanvasFontFace) -> (gsub::Features, bool) {
+    // Kerning is turned on and off separately from the other features when shaping
+    let kerning     = !font.features().iter().any(|(tag, enabled)| tag == b"kern" && !*enabled);
+    let settings    = font.features().iter().filter(|(tag, _)| tag != b"kern").collect::<Vec<_>>();
+
+    // Features are turned on or off in the default mask, so allsorts still applies the features required by each script
+    let mut mask    = gsub::FeatureMask::default();
+    let mut custom  = false;
+
+    for (tag, enabled) in settings.iter() {
+        let feature = gsub::FeatureMask::from_tag(u32::from_be_bytes(*tag));
+
+        match (feature.is_empty(), *enabled) {
+            (false, true)   => mask.insert(feature),
+            (false, false)  => mask.remove(feature),
+            (true, true)    => { custom = true; }
+            (true, false)   => { }
+        }
+    }
+
+    if !custom {
+        (gsub::Features::Mask(mask), kerning)
+    } else {
+        // Features that aren't in the mask need a custom list, which only contains the features that are asked for: this
+        // starts with the features from the mask (the defaults with any changes the font makes) and adds the features that
+        // have no mask bit after them
+        let mut tags = MASK_FEATURES.iter()
+            .filter(|tag| {
+                let feature = gsub::FeatureMask::from_tag(u32::from_be_bytes(**tag));
+                !feature.is_empty() && mask.contains(feature)
+            })
+            .copied()
+            .collect::<Vec<_>>();
+
+        for (tag, enabled) in settings {
+            let has_mask_bit = !gsub::FeatureMask::from_tag(u32::from_be_bytes(*tag)).is_empty();
+
+            if *enabled && !has_mask_bit && !tags.contains(tag) {
+                tags.push(*tag);
+            }
+        }
+
+        let features = tags.into_iter()
+            .map(|tag| gsub::FeatureInfo { feature_tag: u32::from_be_bytes(tag), alternate: None })
+            .collect();
+
+        (gsub::Features::Custom(features), kerning)
+    }
+}
+
+///
+/// Works out where the glyphs in a shaped run go, relative to the start of the run
+///
+/// Each glyph is described by its advance (in font units, including any kerning) and the placement that GPOS assigned it.
+/// Returns the location of each glyph and the position of the pen after the last one: right-to-left runs extend leftwards.
+///
+fn position_glyphs(glyphs: &[((f32, f32), gpos::Placement)], scale_factor: f32, right_to_left: bool) -> (Vec<(f32, f32)>, (f32, f32)) {
+    let mut pen         = (0.0, 0.0);
+    let mut positions   = Vec::<(f32, f32)>::with_capacity(glyphs.len());
+
+    // The glyph that each mark or cursive glyph is attached to (always a glyph that comes before it)
+    let mut attached_to = Vec::<Option<usize>>::with_capacity(glyphs.len());
+
+    // Marks the glyphs in the cursive chain that's being moved (cleared again once the chain has been moved)
+    let mut in_chain    = vec![false; glyphs.len()];
+
+    for ((advance_x, advance_y), placement) in glyphs.iter() {
+        let advance_x   = advance_x * scale_factor;
+        let advance_y   = advance_y * scale_factor;
+
+        // Unattached glyphs are placed at the pen position, and move the pen by their advance
+        let (origin, next_pen) = if right_to_left {
+            ((pen.0 - advance_x, pen.1), (pen.0 - advance_x, pen.1 + advance_y))
+        } else {
+            (pen, (pen.0 + advance_x, pen.1 + advance_y))
+        };
+
+        // Work out where the glyph goes, where the next glyph will go and which glyph this one is attached to
+        let (location, next_pen, attachment) = match placement {
+            gpos::Placement::None               => (origin, next_pen, None),
+            gpos::Placement::Distance(x, y)     => ((origin.0 + (*x as f32) * scale_factor, origin.1 + (*y as f32) * scale_factor), next_pen, None),
+
+            gpos::Placement::MarkAnchor(base_idx, base_anchor, mark_anchor) => {
+                // Mark-to-base, mark-to-ligature and mark-to-mark attachment: the mark's anchor is placed on the anchor of the glyph it's attached to, and the pen doesn't move
+                let (base_x, base_y)    = positions.get(*base_idx).copied().unwrap_or(origin);
+                let offset_x            = (base_anchor.x as f32) - (mark_anchor.x as f32);
+                let offset_y            = (base_anchor.y as f32) - (mark_anchor.y as f32);
+
+                ((base_x + offset_x * scale_factor, base_y + offset_y * scale_factor), pen, Some(*base_idx))
+            }
+
+            gpos::Placement::MarkOverprint(base_idx) => {
+                // Mark drawn over the glyph it's attached to without any adjustment
+                (positions.get(*base_idx).copied().unwrap_or(origin), pen, Some(*base_idx))
+            }
+
+            gpos::Placement::CursiveAnchor(exit_idx, right_to_left_flag, exit_anchor, entry_anchor) => {
+                // Cursive attachment: this glyph's entry anchor is placed on the exit anchor of the glyph before it, which replaces that glyph's advance
+                let (exit_x, exit_y)    = positions.get(*exit_idx).copied().unwrap_or(origin);
+                let offset_x            = (exit_anchor.x as f32) - (entry_anchor.x as f32);
+                let offset_y            = (exit_anchor.y as f32) - (entry_anchor.y as f32);
+                let mut location        = (exit_x + offset_x * scale_factor, exit_y + offset_y * scale_factor);
+
+                if *right_to_left_flag {
+                    // The RIGHT_TO_LEFT lookup flag keeps the last glyph of a chain on the baseline, so the glyphs that come before it (and their marks) move to meet it instead
+                    let shift_y         = origin.1 - location.1;
+                    let mut chain_start = positions.len();
+                    let mut next        = Some(*exit_idx).filter(|idx| *idx < positions.len());
+
+                    while let Some(chain_idx) = next {
+                        in_chain[chain_idx] = true;
+                        chain_start         = chain_idx;
+                        next                = attached_to[chain_idx];
+                    }
+
+                    // Glyphs are only attached to glyphs that come before them, so nothing before the start of the chain moves
+                    for glyph_idx in chain_start..positions.len() {
+                        if in_chain[glyph_idx] || attached_to[glyph_idx].map(|attached_idx| in_chain[attached_idx]).unwrap_or(false) {
+                            positions[glyph_idx].1 += shift_y;
+                        }
+                    }
+
+                    in_chain[chain_start..].iter_mut().for_each(|in_chain| *in_chain = false);
+
+                    location.1 = origin.1;
+                }
+
+                let next_pen = if right_to_left {
+                    (location.0, pen.1 + advance_y)
+                } else {
+                    (location.0 + advance_x, pen.1 + advance_y)
+                };
+
+                (location, next_pen, Some(*exit_idx))
+            }
+        };
+
+        let attachment = attachment.filter(|idx| *idx < positions.len());
+
+        positions.push(location);
+        attached_to.push(attachment);
+        pen = next_pen;
+    }
+
+    (positions, pen)
+}
+
 /// Actions that can be performed in a layout
 #[derive(Clone)]
 enum LayoutAction {
@@ -24,6 +317,125 @@ enum LayoutAction {
     Draw(Draw)
 }
 
+/// The character that stands in for an advance in the pending text (this is a neutral character, so the advance takes the direction of the text around it)
+const ADVANCE_CHAR: char    = '\u{fffc}';
+const ADVANCE_STR: &str     = "\u{fffc}";
+
+/// Actions that are waiting to be laid out along with the pending text
+#[derive(Clone)]
+enum PendingAction {
+    /// Render drawing instructions after the text that comes before them
+    Draw(Draw),
+
+    /// Move the position of the following text (an `ADVANCE_CHAR` is added to the pending text for these)
+    Advance(f32, f32)
+}
+
+///
+/// A span of the pending text that's laid out using a particular font and settings
+///
+#[derive(Clone)]
+struct LineSpan {
+    /// The offset in the pending text where this span starts (it ends where the next span starts)
+    start: usize,
+
+    /// The ID of the font that this span is drawn in, or None if it's drawn in the font passed to `to_drawing()`
+    font_id: Option<FontId>,
+
+    /// The font and em-size for the text in this span
+    font: Arc<CanvasFontFace>,
+    em_size: f32,
+
+    /// The OpenType script and language tags for this span (None to detect the script or use the default language)
+    script: Option<u32>,
+    language: Option<u32>,
+
+    /// The fonts to use for characters that are missing from the main font, in order of preference
+    fallback_fonts: Vec<(FontId, Arc<CanvasFontFace>)>,
+}
+
+impl LineSpan {
+    ///
+    /// Returns true if the main font (or the fallback font with the specified index) has a glyph for a character
+    ///
+    fn has_glyph(&self, fallback: Option<usize>, chr: char) -> bool {
+        let font = match fallback {
+            None            => &self.font,
+            Some(fallback)  => &self.fallback_fonts[fallback].1
+        };
+
+        font.ttf_font().glyph_index(chr).is_some()
+    }
+
+    ///
+    /// Splits text into runs that are each in a single font: None for the main font, or the index of a fallback font
+    ///
+    /// Characters that aren't in any of the fonts are left in the main font, and characters that aren't letters or digits stay
+    /// in the font of the run they're in if it has a glyph for them.
+    ///
+    fn font_runs<'a>(&self, text: &'a str) -> Vec<(Option<usize>, &'a str)> {
+        if self.fallback_fonts.is_empty() { return vec![(None, text)]; }
+
+        let mut runs        = vec![];
+        let mut run_start   = 0;
+        let mut run_font    = None;
+
+        for (idx, chr) in text.char_indices() {
+            let font = if idx > 0 && (extends_cluster(chr) || (!chr.is_alphanumeric() && self.has_glyph(run_font, chr))) {
+                run_font
+            } else if self.has_glyph(None, chr) {
+                None
+            } else {
+                (0..self.fallback_fonts.len()).find(|fallback| self.has_glyph(Some(*fallback), chr))
+            };
+
+            if font != run_font && idx > run_start {
+                runs.push((run_font, &text[run_start..idx]));
+                run_start = idx;
+            }
+
+            run_font = font;
+        }
+
+        if run_start < text.len() {
+            runs.push((run_font, &text[run_start..]));
+        }
+
+        runs
+    }
+}
+
+///
+/// Converts a layout to drawing instructions, drawing the glyphs in the layout's main font using the specified font ID
+///
+fn layout_drawing(layout: Vec<LayoutAction>, font_id: FontId) -> Vec<Draw> {
+    let mut draw    = vec![];
+    let mut glyphs  = vec![];
+
+    for action in layout.into_iter() {
+        match action {
+            LayoutAction::Glyph(glyph)  => glyphs.push(glyph),
+            LayoutAction::Draw(drawing) => {
+                // Draw any glyphs that are pending
+                let draw_glyphs = mem::take(&mut glyphs);
+                if draw_glyphs.len() > 0 {
+                    draw.push(Draw::Font(font_id, FontOp::DrawGlyphs(draw_glyphs)));
+                }
+
+                // Followed up by the drawing action
+                draw.push(drawing);
+            }
+        }
+    }
+
+    // Remaining glyphs
+    if glyphs.len() > 0 {
+        draw.push(Draw::Font(font_id, FontOp::DrawGlyphs(glyphs)));
+    }
+
+    draw
+}
+
 ///
 /// Performs layout of text along a line. The `outline_fonts` feature must be enabled to use this data type.
 ///
@@ -37,9 +449,6 @@ pub struct CanvasFontLineLayout {
     /// Metrics for the text we've laid out
     metrics: TextLayoutMetrics,
 
-    /// Number of font units per em
-    units_per_em: f32,
-
     /// X-offset
     x_off: f32,
 
@@ -49,9 +458,27 @@ pub struct CanvasFontLineLayout {
     /// em-size
     em_size: f32,
 
-    /// Characters still pending layout
+    /// Characters still pending layout (these are laid out together so the bidirectional algorithm applies to the whole line)
     pending: String,
 
+    /// The fonts and settings for the pending text, in the order they appear in the text
+    pending_spans: Vec<LineSpan>,
+
+    /// Drawing instructions and advances that are laid out with the pending text, with the offset in the text where they appear
+    pending_actions: Vec<(usize, PendingAction)>,
+
+    /// The OpenType script tag to shape new text with (or None to detect the script from the text)
+    script: Option<u32>,
+
+    /// The OpenType language tag to shape new text with (or None to use the default language for the script)
+    language: Option<u32>,
+
+    /// True if the paragraph is right-to-left (None until a character with a strong direction has been laid out)
+    right_to_left: Option<bool>,
+
+    /// The fonts to use for characters that are missing from the main font, in order of preference
+    fallback_fonts: Vec<(FontId, Arc<CanvasFontFace>)>,
+
     /// Layout so far
     layout: Vec<LayoutAction>
 }
@@ -61,30 +488,76 @@ impl CanvasFontLineLayout {
     /// Creates a new line layout.
     ///
     pub fn new(font: &Arc<CanvasFontFace>, em_size: f32) -> CanvasFontLineLayout {
-        // Gather font info
+        // Generate the initial font metrics
+        let initial_metrics     = TextLayoutMetrics {
+            inner_bounds:   Self::font_bounds(font, em_size),
+            pos:            Coord2(0.0, 0.0)
+        };
+
+        let mut layout          = CanvasFontLineLayout {
+            font:               Arc::clone(font),
+            metrics:            initial_metrics,
+            x_off:              0.0,
+            y_off:              0.0,
+            em_size:            em_size,
+            pending:            String::new(),
+            pending_spans:      vec![],
+            pending_actions:    vec![],
+            script:             None,
+            language:           None,
+            right_to_left:      None,
+            fallback_fonts:     vec![],
+            layout:             vec![]
+        };
+
+        layout.start_span();
+        layout
+    }
+
+    ///
+    /// Returns the bounds of a line of text in a font that has no width
+    ///
+    fn font_bounds(font: &Arc<CanvasFontFace>, em_size: f32) -> (Coord2, Coord2) {
         let ttf_font            = font.ttf_font();
         let units_per_em        = ttf_font.units_per_em() as f32;
 
-        // Generate the initial font metrics
         let scale_factor        = (em_size / units_per_em) as f64;
         let ascent              = ttf_font.ascender() as f64;
         let descent             = ttf_font.descender() as f64;
-        let inner_bounds        = (Coord2(0.0, descent * scale_factor), Coord2(0.0, ascent * scale_factor));
 
-        let initial_metrics     = TextLayoutMetrics {
-            inner_bounds:   inner_bounds,
-            pos:            Coord2(0.0, 0.0)
-        };
+        (Coord2(0.0, descent * scale_factor), Coord2(0.0, ascent * scale_factor))
+    }
+
+    ///
+    /// Starts a new span for the text that's added after this call, using the current font and settings
+    ///
+    fn start_span(&mut self) {
+        let start = self.pending.len();
+
+        // A span with no text in it is replaced
+        if self.pending_spans.last().map(|span| span.start == start).unwrap_or(false) {
+            self.pending_spans.pop();
+        }
+
+        self.pending_spans.push(LineSpan {
+            start:          start,
+            font_id:        None,
+            font:           Arc::clone(&self.font),
+            em_size:        self.em_size,
+            script:         self.script,
+            language:       self.language,
+            fallback_fonts: self.fallback_fonts.clone(),
+        });
+    }
 
-        CanvasFontLineLayout {
-            font:           Arc::clone(font),
-            units_per_em:   units_per_em,
-            metrics:        initial_metrics,
-            x_off:          0.0,
-            y_off:          0.0,
-            em_size:        em_size,
-            pending:        String::new(),
-            layout:         vec![]
+    ///
+    /// Adds a drawing instruction to the layout, after any text that's been added so far
+    ///
+    fn add_drawing(&mut self, drawing: Draw) {
+        if self.pending.is_empty() {
+            self.layout.push(LayoutAction::Draw(drawing));
+        } else {
+            self.pending_actions.push((self.pending.len(), PendingAction::Draw(drawing)));
         }
     }
 
@@ -99,8 +572,7 @@ impl CanvasFontLineLayout {
     /// Update the rendering between the glyphs
     ///
     pub fn draw<DrawIter: IntoIterator<Item=Draw>>(&mut self, drawing: DrawIter) {
-        self.layout_pending();
-        self.layout.extend(drawing.into_iter().map(|item| LayoutAction::Draw(item)));
+        drawing.into_iter().for_each(|item| self.add_drawing(item));
     }
 
     ///
@@ -110,19 +582,48 @@ impl CanvasFontLineLayout {
         self.pending.extend(text.chars())
     }
 
+    ///
+    /// Sets the OpenType script and language used to shape any text that's added after this call
+    ///
+    /// These are 4-character OpenType tags (eg, `Some(*b"arab")` and `Some(*b"URD ")`). If no script is set, the script is
+    /// detected from the text, which is shaped in a separate run wherever it changes.
+    ///
+    pub fn set_script(&mut self, script: Option<[u8; 4]>, language: Option<[u8; 4]>) {
+        self.script     = script.map(u32::from_be_bytes);
+        self.language   = language.map(u32::from_be_bytes);
+
+        self.start_span();
+    }
+
+    ///
+    /// Sets the fonts to use, in order of preference, for any characters in text added after this call that are missing from the main font
+    ///
+    /// Text in a fallback font is laid out with the same em size as the main font, and is drawn using the font ID supplied with it. The
+    /// fallback fonts are kept by `continue_with_new_font()`, so this should be called again if the new font needs different fallbacks.
+    ///
+    pub fn set_fallback_fonts(&mut self, fallback_fonts: Vec<(FontId, Arc<CanvasFontFace>)>) {
+        self.fallback_fonts = fallback_fonts;
+
+        self.start_span();
+    }
+
     ///
     /// Manually advance where the next glyph will be placed after the current position
     ///
+    /// The advance is placed where it falls when the line is reordered for display, so a positive x offset always adds a gap
+    /// between the text on either side of it (in right-to-left text, the text that follows moves further to the left).
+    ///
     pub fn advance(&mut self, x_offset: f32, y_offset: f32) {
-        self.layout_pending();
-        self.x_off += x_offset;
-        self.y_off += y_offset;
+        // Advances are laid out along with the text around them, so they end up in the right place in bidirectional text
+        self.pending_actions.push((self.pending.len(), PendingAction::Advance(x_offset, y_offset)));
+        self.pending.push(ADVANCE_CHAR);
     }
 
     ///
     /// Measures the text that's been laid out so far
     ///
-    /// (Note that this will perform a layout so it's usually best to call before converting to drawing instructions or glyphs)
+    /// (Note that this will perform a layout so it's usually best to call before converting to drawing instructions or glyphs. Text
+    /// that's added after this call is shaped and ordered separately from the text that was added before it)
     ///
     pub fn measure(&mut self) -> TextLayoutMetrics {
         self.layout_pending();
@@ -131,6 +632,36 @@ impl CanvasFontLineLayout {
         self.metrics.clone()
     }
 
+    ///
+    /// Returns the x offset to apply to the text laid out so far to align it around a position
+    ///
+    /// Alignments are relative to the direction of the paragraph, so left-aligned text in a right-to-left paragraph
+    /// ends at the position rather than starting there.
+    ///
+    fn alignment_offset(&self, x: f32, align: TextAlignment) -> f32 {
+        let (Coord2(min_x, _min_y), Coord2(max_x, _max_y))  = self.metrics.inner_bounds;
+        let (min_x, max_x)                                  = (min_x as f32, max_x as f32);
+
+        match (align, self.right_to_left.unwrap_or(false)) {
+            (TextAlignment::Left, false)    => x,
+            (TextAlignment::Right, false)   => x - max_x,
+            (TextAlignment::Left, true)     => x - max_x,
+            (TextAlignment::Right, true)    => x - min_x,
+            (TextAlignment::Center, _)      => x - (max_x+min_x)/2.0
+        }
+    }
+
+    ///
+    /// Returns true if the text laid out so far is in a right-to-left paragraph
+    ///
+    /// The direction is set by the first character with a strong direction (so this will return false until one has been
+    /// laid out). Right-to-left paragraphs are laid out leftwards from the starting position.
+    ///
+    pub fn is_right_to_left(&mut self) -> bool {
+        self.layout_pending();
+        self.right_to_left.unwrap_or(false)
+    }
+
     ///
     /// Aligns the glyphs according to a text alignment around a specific position
     ///
@@ -143,15 +674,8 @@ impl CanvasFontLineLayout {
         self.layout_pending();
 
         // We want to apply a constant offset to all of the glyphs: we can calculate this based on the inner bounds of the text
-        let (Coord2(min_x, _min_y), Coord2(max_x, _max_y))  = self.metrics.inner_bounds;
-        let (min_x, max_x)                                  = (min_x as f32, max_x as f32);
-
         let y_offset = y;
-        let x_offset = match align {
-            TextAlignment::Left     => x,
-            TextAlignment::Right    => x - max_x,
-            TextAlignment::Center   => x - (max_x+min_x)/2.0
-        };
+        let x_offset = self.alignment_offset(x, align);
 
         // Move all of the glyph positions
         self.layout.iter_mut()
@@ -192,15 +716,8 @@ impl CanvasFontLineLayout {
         self.layout_pending();
 
         // We want to apply a constant offset to all of the glyphs: we can calculate this based on the inner bounds of the text
-        let (Coord2(min_x, _min_y), Coord2(max_x, _max_y))  = self.metrics.inner_bounds;
-        let (min_x, max_x)                                  = (min_x as f32, max_x as f32);
-
         let y_offset = y;
-        let x_offset = match align {
-            TextAlignment::Left     => x,
-            TextAlignment::Right    => x - max_x,
-            TextAlignment::Center   => x - (max_x+min_x)/2.0
-        };
+        let x_offset = self.alignment_offset(x, align);
 
         // Add transform instructions at the start of the drawing, then restore the previous state at the end
         self.layout.splice(0..0, vec![LayoutAction::Draw(Draw::PushState), LayoutAction::Draw(Draw::MultiplyTransform(Transform2D::translate(x_offset, y_offset)))]);
@@ -210,6 +727,8 @@ impl CanvasFontLineLayout {
     ///
     /// Finishes the layout and returns a list of glyph positions (any drawing instructions are discarded)
     ///
+    /// Glyphs from fallback fonts are stored as drawing instructions, so these are discarded too.
+    ///
     pub fn to_glyphs(mut self) -> Vec<GlyphPosition> {
         // Finish the layout
         self.layout_pending();
@@ -230,31 +749,7 @@ impl CanvasFontLineLayout {
         // Finish the layout
         self.layout_pending();
 
-        let mut draw    = vec![];
-        let mut glyphs  = vec![];
-
-        for action in self.layout.into_iter() {
-            match action {
-                LayoutAction::Glyph(glyph)  => glyphs.push(glyph),
-                LayoutAction::Draw(drawing) => {
-                    // Draw any glyphs that are pending
-                    let draw_glyphs = mem::take(&mut glyphs);
-                    if draw_glyphs.len() > 0 {
-                        draw.push(Draw::Font(font_id, FontOp::DrawGlyphs(draw_glyphs)));
-                    }
-
-                    // Followed up by the drawing action
-                    draw.push(drawing);
-                }
-            }
-        }
-
-        // Remaining glyphs
-        if glyphs.len() > 0 {
-            draw.push(Draw::Font(font_id, FontOp::DrawGlyphs(glyphs)));
-        }
-
-        draw
+        layout_drawing(self.layout, font_id)
     }
 
     ///
@@ -263,97 +758,553 @@ impl CanvasFontLineLayout {
     /// `last_font_id` should be the ID of the font that the glyphs that have been rendered so far should be rendered in
     ///
     pub fn continue_with_new_font(mut self, last_font_id: FontId, new_font: &Arc<CanvasFontFace>, new_em_size: f32) -> CanvasFontLineLayout {
-        // Layout the pending text before continuing
-        self.layout_pending();
+        // Glyphs that have been laid out so far are drawn using the last font ID, along with any pending text in the current font
+        // (pending text is laid out along with the text that follows it, so the whole line is ordered correctly)
+        self.layout = layout_drawing(mem::take(&mut self.layout), last_font_id).into_iter().map(|draw| LayoutAction::Draw(draw)).collect();
+        self.pending_spans.iter_mut()
+            .filter(|span| span.font_id.is_none())
+            .for_each(|span| span.font_id = Some(last_font_id));
 
-        // Finish the current layout by generating the drawing actions, and remember the state
-        let x_off           = self.x_off;
-        let y_off           = self.y_off;
-        let metrics         = self.metrics.clone();
-        let drawing         = self.to_drawing(last_font_id);
+        // Continue with the new font
+        self.font                   = Arc::clone(new_font);
+        self.em_size                = new_em_size;
+        self.metrics.inner_bounds   = self.metrics.inner_bounds.union_bounds(Self::font_bounds(new_font, new_em_size));
+        self.start_span();
 
-        // Create a new layout with the new font
-        let mut new_layout  = CanvasFontLineLayout::new(new_font, new_em_size);
-
-        // Set it up to continue where the existing layout left off
-        new_layout.layout   = drawing.into_iter().map(|draw| LayoutAction::Draw(draw)).collect();
-        new_layout.x_off    = x_off;
-        new_layout.y_off    = y_off;
-
-        new_layout.metrics.inner_bounds = new_layout.metrics.inner_bounds.union_bounds(metrics.inner_bounds);
-
-        new_layout
+        self
     }
 
     ///
-    /// Performs layout on the pending string
+    /// Performs layout on the pending text
+    ///
+    /// The bidirectional algorithm is run over all of the pending text at once, so runs in the opposite direction to the
+    /// paragraph are reordered correctly even when they're in several fonts or separated by advances or drawing instructions.
+    /// The glyphs are positioned in visual order, but are added to the layout in logical order so that any drawing instructions
+    /// between them still apply to the same text.
     ///
     fn layout_pending(&mut self) {
         // Nothing to do if nothing is pending
         if self.pending.len() == 0 { return; }
 
-        // Take the pending characters to be processed
+        // Take the pending text to be processed (text added later is laid out using the current settings)
         let pending         = mem::take(&mut self.pending);
+        let spans           = mem::take(&mut self.pending_spans);
+        let actions         = mem::take(&mut self.pending_actions);
+        self.start_span();
+
+        // The direction of the paragraph is set by the first character with a strong direction
+        if self.right_to_left.is_none() {
+            self.right_to_left = pending.chars()
+                .map(bidi_class)
+                .find(|class| *class == BidiClass::L || *class == BidiClass::R || *class == BidiClass::AL)
+                .map(|class| class != BidiClass::L);
+        }
+
+        let right_to_left   = self.right_to_left.unwrap_or(false);
+        let paragraph_level = if right_to_left { Level::rtl() } else { Level::ltr() };
+
+        // The text is split into pieces wherever the span changes, and around each drawing instruction and advance
+        let mut breaks      = spans.iter().map(|span| span.start)
+            .chain(actions.iter().flat_map(|(offset, action)| match action {
+                PendingAction::Draw(_)          => vec![*offset],
+                PendingAction::Advance(_, _)    => vec![*offset, *offset + ADVANCE_CHAR.len_utf8()],
+            }))
+            .collect::<Vec<_>>();
+        breaks.sort();
+        breaks.dedup();
+
+        // Lay out the pieces from left to right in visual order, keeping the layout for each one along with where it starts in the text
+        let start_x         = self.x_off;
+        let start_y         = self.y_off;
+        let mut pieces      = vec![];
+        let bidi_info       = BidiInfo::new(&pending, Some(paragraph_level));
+
+        for paragraph in bidi_info.paragraphs.iter() {
+            let (levels, runs) = bidi_info.visual_runs(paragraph, paragraph.range.clone());
+
+            for run in runs {
+                let run_rtl     = levels[run.start].is_rtl();
+                let starts      = iter::once(run.start).chain(breaks.iter().copied().filter(|pos| *pos > run.start && *pos < run.end)).collect::<Vec<_>>();
+                let mut ranges  = starts.iter().zip(starts.iter().skip(1).chain(iter::once(&run.end))).map(|(start, end)| *start..*end).collect::<Vec<_>>();
+                if run_rtl { ranges.reverse(); }
+
+                for range in ranges {
+                    // Advances move the position without drawing anything
+                    let advance = actions.iter().find_map(|(offset, action)| match action {
+                        PendingAction::Advance(x, y) if *offset == range.start && &pending[range.clone()] == ADVANCE_STR => Some((*x, *y)),
+                        _                                                                                                   => None
+                    });
+
+                    if let Some((x, y)) = advance {
+                        self.x_off += x;
+                        self.y_off += y;
+                        continue;
+                    }
+
+                    // Right-to-left runs use the mirrored forms of characters like brackets
+                    let span        = spans.iter().rev().find(|span| span.start <= range.start).unwrap_or(&spans[0]);
+                    let text        = if run_rtl { pending[range.clone()].chars().map(mirrored_char).collect::<String>() } else { pending[range.clone()].to_string() };
+                    let language    = span.language.unwrap_or(tag::DFLT);
+                    let mut layout  = vec![];
+
+                    // Shape each part of the piece using the script it's in
+                    let mut parts   = if let Some(script) = span.script { vec![(script, &text[..])] } else { script_runs(&text) };
+                    if run_rtl { parts.reverse(); }
+
+                    for (script, text) in parts {
+                        // Characters missing from the main font are laid out using the fallback fonts
+                        let mut font_runs = span.font_runs(text);
+                        if run_rtl { font_runs.reverse(); }
 
-        // Shape the pending text
-        let ttf_font        = self.font.ttf_font();
-        let mut shaper      = self.font.allsorts_font();
-        let glyphs          = shaper.map_glyphs(&pending, tag::LATN, MatchingPresentation::NotRequired);
-        let shape           = shaper.shape(glyphs, tag::LATN, Some(tag::DFLT), &gsub::Features::Mask(gsub::FeatureMask::default()), true).ok()
+                        for (fallback, text) in font_runs {
+                            layout.extend(self.layout_run(span, fallback, text, script, language, run_rtl));
+                        }
+                    }
+
+                    pieces.push((range.start, layout));
+                }
+            }
+        }
+
+        // Right-to-left paragraphs continue to the left of the text laid out so far
+        if right_to_left {
+            let width = self.x_off - start_x;
+
+            pieces.iter_mut()
+                .flat_map(|(_, layout)| layout.iter_mut())
+                .for_each(|action| {
+                    match action {
+                        LayoutAction::Glyph(glyph)                                      => { glyph.location.0 -= width; }
+                        LayoutAction::Draw(Draw::Font(_, FontOp::DrawGlyphs(glyphs)))   => { glyphs.iter_mut().for_each(|glyph| glyph.location.0 -= width); }
+                        _                                                               => { }
+                    }
+                });
+            self.x_off = start_x - width;
+        }
+
+        // Add the pieces and the drawing instructions to the layout in logical order
+        pieces.sort_by_key(|(start, _)| *start);
+
+        let mut drawing = actions.into_iter()
+            .filter_map(|(offset, action)| match action {
+                PendingAction::Draw(drawing)    => Some((offset, drawing)),
+                PendingAction::Advance(_, _)    => None,
+            })
+            .peekable();
+
+        for (start, layout) in pieces {
+            while let Some((_, drawing)) = drawing.next_if(|(offset, _)| *offset <= start) {
+                self.layout.push(LayoutAction::Draw(drawing));
+            }
+
+            self.layout.extend(layout);
+        }
+
+        self.layout.extend(drawing.map(|(_, drawing)| LayoutAction::Draw(drawing)));
+
+        // The inner bounds just uses the x, y offsets to amend the bounding box
+        let (min_x, max_x) = (start_x.min(self.x_off), start_x.max(self.x_off));
+        let (min_y, max_y) = (start_y.min(self.y_off), start_y.max(self.y_off));
+
+        self.metrics.inner_bounds = self.metrics.inner_bounds.union_bounds((Coord2(min_x as _, min_y as _), Coord2(max_x as _, max_y as _)));
+    }
+
+    ///
+    /// Shapes and lays out a run of text in a single script, direction and font, starting at the current position
+    ///
+    /// The font is either the span's font (when `fallback` is None) or the span's fallback font with the specified index.
+    /// Returns the layout actions for the run.
+    ///
+    fn layout_run(&mut self, span: &LineSpan, fallback: Option<usize>, text: &str, script: u32, language: u32, right_to_left: bool) -> Vec<LayoutAction> {
+        let font            = match fallback {
+            None            => Arc::clone(&span.font),
+            Some(fallback)  => Arc::clone(&span.fallback_fonts[fallback].1)
+        };
+
+        // Shape the text (allsorts doesn't support variation axes, so GPOS adjustments are for the default instance, but the advances come from the varied TTF font)
+        let ttf_font        = font.ttf_font();
+        let mut shaper      = font.allsorts_font();
+        let (features, kern) = shaping_features(&font);
+        let glyphs          = shaper.map_glyphs(text, script, MatchingPresentation::NotRequired);
+        let shape           = shaper.shape(glyphs, script, Some(language), &features, kern).ok()
             .unwrap_or_else(|| vec![]);
 
         // The scale factor is used to convert between font units and screen units
-        let scale_factor    = self.em_size / self.units_per_em;
-
-        // Generate the glyph positions
-        for glyph in shape {
-            // Fetch information about this glyph
-            let glyph_index     = ttf_parser::GlyphId(glyph.glyph.glyph_index as _);
-            let advance_x       = ttf_font.glyph_hor_advance(glyph_index);
-            let advance_y       = ttf_font.glyph_ver_advance(glyph_index);
-            let advance_x       = if let Some(advance) = advance_x { advance } else { 0 };
-            let advance_y       = if let Some(advance) = advance_y { advance } else { 0 };
-
-            // Adjust by any requested offset
-            let (off_x, off_y)  = match glyph.placement {
-                gpos::Placement::None                       => (0.0, 0.0),
-                gpos::Placement::Distance(x, y)             => (x as f32, y as f32),
-                gpos::Placement::MarkAnchor(_ ,_, _)        => (0.0, 0.0), // TODO
-                gpos::Placement::CursiveAnchor(_ ,_, _, _)  => (0.0, 0.0), // TODO: https://docs.microsoft.com/en-us/typography/opentype/spec/gpos#lookup-type-3-cursive-attachment-positioning-subtable
-                gpos::Placement::MarkOverprint(_)           => (0.0, 0.0), // TODO
-            };
-            let off_x           = off_x * scale_factor;
-            let off_y           = off_y * scale_factor;
-
-            // Push this glyph
-            let glyph_pos       = GlyphPosition {
-                id:         GlyphId(glyph.glyph.glyph_index as _),
-                location:   (self.x_off + off_x, self.y_off + off_y),
-                em_size:    self.em_size
-            };
-            self.layout.push(LayoutAction::Glyph(glyph_pos));
+        let units_per_em    = ttf_font.units_per_em() as f32;
+        let scale_factor    = span.em_size / units_per_em;
 
-            // Move to the next position
-            let advance_x       = (advance_x as f32) + (glyph.kerning as f32);
-            let advance_y       = advance_y as f32;
-            let advance_x       = advance_x * scale_factor;
-            let advance_y       = advance_y * scale_factor;
+        // Fetch the advance of each glyph (in font units) and position the glyphs relative to the start of the run
+        let (glyph_ids, glyphs): (Vec<_>, Vec<_>) = shape.into_iter()
+            .map(|glyph| {
+                let glyph_index = ttf_parser::GlyphId(glyph.glyph.glyph_index as _);
+                let advance_x   = ttf_font.glyph_hor_advance(glyph_index).unwrap_or(0) as f32;
+                let advance_y   = ttf_font.glyph_ver_advance(glyph_index).unwrap_or(0) as f32;
 
-            let last_x          = self.x_off;
-            let last_y          = self.y_off;
+                (GlyphId(glyph.glyph.glyph_index as _), ((advance_x + (glyph.kerning as f32), advance_y), glyph.placement))
+            })
+            .unzip();
+        let (positions, pen) = position_glyphs(&glyphs, scale_factor, right_to_left);
 
-            self.x_off          += advance_x + off_x;
-            self.y_off          += advance_y + off_y;
+        // Place the run at the current position
+        let width   = if right_to_left { -pen.0 } else { pen.0 };
+        let start_x = if right_to_left { self.x_off + width } else { self.x_off };
+        let start_y = self.y_off;
 
-            // The inner bounds just uses the x, y offsets to amend the bounding box
-            self.metrics.inner_bounds = self.metrics.inner_bounds.union_bounds((Coord2(last_x as _, last_y as _), Coord2(self.x_off as _, self.y_off as _)));
-        }
+        let em_size = span.em_size;
+        let glyphs  = glyph_ids.into_iter().zip(positions.into_iter())
+            .map(|(glyph_id, (x, y))| GlyphPosition {
+                id:         glyph_id,
+                location:   (start_x + x, start_y + y),
+                em_size:    em_size
+            });
+
+        let layout  = match (fallback, span.font_id) {
+            (Some(fallback), _)     => {
+                // The fallback font might be taller than the main font
+                let ascent                      = (ttf_font.ascender() as f32) * scale_factor;
+                let descent                     = (ttf_font.descender() as f32) * scale_factor;
+                let (Coord2(min_x, _), _)       = self.metrics.inner_bounds;
+
+                self.metrics.inner_bounds = self.metrics.inner_bounds.union_bounds((Coord2(min_x, descent as _), Coord2(min_x, ascent as _)));
+
+                // Glyphs from fallback fonts are drawn using the fallback font's ID
+                let fallback_id = span.fallback_fonts[fallback].0;
+                vec![LayoutAction::Draw(Draw::Font(fallback_id, FontOp::DrawGlyphs(glyphs.collect())))]
+            }
+
+            // Spans in fonts that were replaced by `continue_with_new_font()` are drawn using the ID of the font they were in
+            (None, Some(font_id))   => vec![LayoutAction::Draw(Draw::Font(font_id, FontOp::DrawGlyphs(glyphs.collect())))],
+            (None, None)            => glyphs.map(|glyph| LayoutAction::Glyph(glyph)).collect(),
+        };
+
+        self.x_off += width;
+        self.y_off += pen.1;
+
+        layout
     }
 }
 
 impl GraphicsContext for CanvasFontLineLayout {
     #[inline] fn draw(&mut self, drawing: Draw) { 
-        self.layout_pending();
-        self.layout.push(LayoutAction::Draw(drawing));
+        self.add_drawing(drawing);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::color::*;
+
+    #[test]
+    fn single_script_run() {
+        assert!(script_runs("Hello, world") == vec![(LATN, "Hello, world")]);
+    }
+
+    #[test]
+    fn common_characters_join_runs() {
+        let runs = script_runs("12 Hello \u{05e9}\u{05dc}\u{05d5}\u{05dd}!");
+
+        assert!(runs == vec![(LATN, "12 Hello "), (HEBR, "\u{05e9}\u{05dc}\u{05d5}\u{05dd}!")], "{:?}", runs);
+    }
+
+    #[test]
+    fn combining_marks_stay_in_run() {
+        let runs = script_runs("\u{0915}\u{093f}e\u{0301}");
+
+        assert!(runs == vec![(DEVA, "\u{0915}\u{093f}"), (LATN, "e\u{0301}")], "{:?}", runs);
+    }
+
+    #[test]
+    fn marks_do_not_advance() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+
+        let mut plain   = CanvasFontLineLayout::new(&lato, 12.0);
+        plain.add_text("e");
+        let plain       = plain.measure();
+
+        let mut marked  = CanvasFontLineLayout::new(&lato, 12.0);
+        marked.add_text("e\u{0301}");
+        let marked      = marked.measure();
+
+        assert!((plain.pos.0 - marked.pos.0).abs() < 0.001, "{:?} {:?}", plain.pos, marked.pos);
+    }
+
+    #[test]
+    fn mark_anchor_is_placed_on_base_anchor() {
+        use allsorts::layout::Anchor;
+
+        let glyphs = vec![
+            ((600.0, 0.0), gpos::Placement::None),
+            ((0.0, 0.0), gpos::Placement::MarkAnchor(0, Anchor { x: 300, y: 700 }, Anchor { x: -200, y: 500 })),
+            ((400.0, 0.0), gpos::Placement::None),
+        ];
+
+        let (positions, pen) = position_glyphs(&glyphs, 0.01, false);
+
+        // The mark is placed so its anchor is on the base's anchor, and the glyph after it follows on from the base
+        assert!(positions == vec![(0.0, 0.0), (5.0, 2.0), (6.0, 0.0)], "{:?}", positions);
+        assert!(pen == (10.0, 0.0), "{:?}", pen);
+    }
+
+    #[test]
+    fn cursive_anchor_joins_glyphs() {
+        use allsorts::layout::Anchor;
+
+        let glyphs = vec![
+            ((600.0, 0.0), gpos::Placement::None),
+            ((500.0, 0.0), gpos::Placement::CursiveAnchor(0, false, Anchor { x: 550, y: 100 }, Anchor { x: 50, y: 0 })),
+        ];
+
+        let (positions, pen) = position_glyphs(&glyphs, 0.01, false);
+
+        // The second glyph's entry anchor is on the first glyph's exit anchor, which replaces the first glyph's advance
+        assert!(positions == vec![(0.0, 0.0), (5.0, 1.0)], "{:?}", positions);
+        assert!(pen == (10.0, 0.0), "{:?}", pen);
+    }
+
+    #[test]
+    fn cursive_right_to_left_flag_keeps_last_glyph_on_baseline() {
+        use allsorts::layout::Anchor;
+
+        let glyphs = vec![
+            ((600.0, 0.0), gpos::Placement::None),
+            ((0.0, 0.0), gpos::Placement::MarkAnchor(0, Anchor { x: 300, y: 700 }, Anchor { x: 0, y: 500 })),
+            ((500.0, 0.0), gpos::Placement::CursiveAnchor(0, true, Anchor { x: 550, y: 100 }, Anchor { x: 50, y: 0 })),
+        ];
+
+        let (positions, _pen) = position_glyphs(&glyphs, 0.01, false);
+
+        // The glyph before the last one in the chain (and the mark attached to it) moves down so the last glyph is on the baseline
+        assert!(positions == vec![(0.0, -1.0), (3.0, 1.0), (5.0, 0.0)], "{:?}", positions);
+    }
+
+    #[test]
+    fn mirror_brackets() {
+        assert!("(a) <b>".chars().map(mirrored_char).collect::<String>() == ")a( >b<");
+    }
+
+    #[test]
+    fn mirror_maths_symbols() {
+        assert!("\u{2208}\u{226a}\u{2e28}".chars().map(mirrored_char).collect::<String>() == "\u{220b}\u{226b}\u{2e29}");
+    }
+
+    #[test]
+    fn font_changes_do_not_split_directional_runs() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+
+        // The latin text is split between two fonts, but is still a single left-to-right run in the right-to-left paragraph
+        let mut layout  = CanvasFontLineLayout::new(&lato, 12.0);
+        layout.add_text("\u{05e9} a");
+        let mut layout  = layout.continue_with_new_font(FontId(1), &lato, 12.0);
+        layout.add_text(" b \u{05dc}");
+
+        let glyphs      = layout.to_drawing(FontId(2)).into_iter()
+            .flat_map(|draw| match draw {
+                Draw::Font(_, FontOp::DrawGlyphs(glyphs))   => glyphs,
+                _                                           => vec![]
+            })
+            .collect::<Vec<_>>();
+
+        let a_id        = lato.ttf_font().glyph_index('a').unwrap().0 as u32;
+        let b_id        = lato.ttf_font().glyph_index('b').unwrap().0 as u32;
+        let a_pos       = glyphs.iter().find(|glyph| glyph.id == GlyphId(a_id)).unwrap().location.0;
+        let b_pos       = glyphs.iter().find(|glyph| glyph.id == GlyphId(b_id)).unwrap().location.0;
+
+        assert!(a_pos < b_pos, "{} {}", a_pos, b_pos);
+    }
+
+    #[test]
+    fn advance_in_right_to_left_paragraph() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+
+        // The advance is between two words in a left-to-right run, so it should separate them without changing their order
+        let mut layout  = CanvasFontLineLayout::new(&lato, 12.0);
+        layout.add_text("\u{05e9} a");
+        layout.advance(100.0, 0.0);
+        layout.add_text("b \u{05dc}");
+        let glyphs      = layout.to_glyphs();
+
+        let a_id        = lato.ttf_font().glyph_index('a').unwrap().0 as u32;
+        let b_id        = lato.ttf_font().glyph_index('b').unwrap().0 as u32;
+        let a_pos       = glyphs.iter().find(|glyph| glyph.id == GlyphId(a_id)).unwrap().location.0;
+        let b_pos       = glyphs.iter().find(|glyph| glyph.id == GlyphId(b_id)).unwrap().location.0;
+
+        assert!(b_pos - a_pos > 100.0, "{} {}", a_pos, b_pos);
+    }
+
+    #[test]
+    fn drawing_keeps_logical_position() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+
+        // Drawing instructions don't interrupt the layout, but stay between the glyphs they were added between
+        let mut layout  = CanvasFontLineLayout::new(&lato, 12.0);
+        layout.add_text("\u{05e9} a");
+        layout.fill_color(Color::Rgba(1.0, 0.0, 0.0, 1.0));
+        layout.add_text("b \u{05dc}");
+        let drawing     = layout.to_drawing(FontId(1));
+
+        assert!(drawing.len() == 3, "{:?}", drawing);
+        assert!(if let Draw::FillColor(_) = drawing[1] { true } else { false }, "{:?}", drawing);
+    }
+
+    #[test]
+    fn right_to_left_paragraph_extends_left() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+
+        let mut layout  = CanvasFontLineLayout::new(&lato, 12.0);
+        layout.add_text("\u{05e9}\u{05dc}\u{05d5}\u{05dd} abc");
+        let metrics     = layout.measure();
+
+        assert!(layout.is_right_to_left());
+        assert!(metrics.pos.0 < 0.0, "{:?}", metrics.pos);
+        assert!((metrics.inner_bounds.1).0 <= 0.0, "{:?}", metrics.inner_bounds);
+    }
+
+    #[test]
+    fn left_to_right_runs_are_reordered() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+
+        // In a right-to-left paragraph, the latin word that comes last should be drawn to the left of the one that comes first
+        let mut layout  = CanvasFontLineLayout::new(&lato, 12.0);
+        layout.add_text("\u{05e9} a \u{05dc} b");
+        let glyphs      = layout.to_glyphs();
+
+        let a_id        = lato.ttf_font().glyph_index('a').unwrap().0 as u32;
+        let b_id        = lato.ttf_font().glyph_index('b').unwrap().0 as u32;
+        let a_pos       = glyphs.iter().find(|glyph| glyph.id == GlyphId(a_id)).unwrap().location.0;
+        let b_pos       = glyphs.iter().find(|glyph| glyph.id == GlyphId(b_id)).unwrap().location.0;
+
+        assert!(b_pos < a_pos, "{} {}", a_pos, b_pos);
+    }
+
+    #[test]
+    fn explicit_script_is_used_for_all_text() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+
+        let mut layout  = CanvasFontLineLayout::new(&lato, 12.0);
+        layout.set_script(Some(*b"latn"), Some(*b"TRK "));
+        layout.add_text("fi");
+
+        assert!(layout.script == Some(LATN));
+
+        // Lato leaves out the 'fi' ligature for Turkish (so the dotted i is kept)
+        let f_id        = lato.ttf_font().glyph_index('f').unwrap().0 as u32;
+        let i_id        = lato.ttf_font().glyph_index('i').unwrap().0 as u32;
+        let glyph_ids   = layout.to_glyphs().into_iter().map(|glyph| glyph.id).collect::<Vec<_>>();
+
+        assert!(glyph_ids == vec![GlyphId(f_id), GlyphId(i_id)], "{:?}", glyph_ids);
+    }
+
+    #[test]
+    fn marks_and_joiners_extend_clusters() {
+        assert!(extends_cluster('\u{0301}'));
+        assert!(extends_cluster('\u{200d}'));
+        assert!(extends_cluster('\u{fe0f}'));
+        assert!(extends_cluster('\u{1f3fd}'));
+        assert!(!extends_cluster('e'));
+        assert!(!extends_cluster(' '));
+    }
+
+    #[test]
+    fn disable_ligatures() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+
+        let mut layout  = CanvasFontLineLayout::new(&lato, 12.0);
+        layout.add_text("fi");
+        let ligature    = layout.to_glyphs();
+
+        let mut layout  = CanvasFontLineLayout::new(&lato.with_feature(*b"liga", false), 12.0);
+        layout.add_text("fi");
+        let separate    = layout.to_glyphs();
+
+        assert!(ligature.len() == 1, "{:?}", ligature);
+        assert!(separate.len() == 2, "{:?}", separate);
+    }
+
+    #[test]
+    fn default_features_with_kerning_off() {
+        let (features, kern) = shaping_features(&CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf")).with_feature(*b"kern", false));
+
+        assert!(!kern);
+        assert!(if let gsub::Features::Mask(_) = features { true } else { false });
+    }
+
+    #[test]
+    fn turning_off_ligatures_keeps_feature_mask() {
+        let (features, kern) = shaping_features(&CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf")).with_feature(*b"liga", false));
+
+        assert!(kern);
+
+        if let gsub::Features::Mask(mask) = features {
+            assert!(!mask.contains(gsub::FeatureMask::LIGA));
+            assert!(mask.contains(gsub::FeatureMask::CCMP));
+            assert!(mask.contains(gsub::FeatureMask::RLIG));
+        } else {
+            assert!(false, "Expected a feature mask");
+        }
+    }
+
+    #[test]
+    fn custom_features_keep_default_features() {
+        let font                = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+        let (features, kern)    = shaping_features(&font.with_feature(*b"ss01", true).with_feature(*b"liga", false));
+
+        assert!(kern);
+
+        if let gsub::Features::Custom(features) = features {
+            let tags = features.iter().map(|feature| feature.feature_tag.to_be_bytes()).collect::<Vec<_>>();
+
+            // The default features are still applied (apart from the one that was turned off), followed by the custom feature
+            assert!(tags.contains(b"ccmp"), "{:?}", tags);
+            assert!(tags.contains(b"locl"), "{:?}", tags);
+            assert!(tags.contains(b"rlig"), "{:?}", tags);
+            assert!(tags.contains(b"calt"), "{:?}", tags);
+            assert!(!tags.contains(b"liga"), "{:?}", tags);
+            assert!(tags.last() == Some(b"ss01"), "{:?}", tags);
+        } else {
+            assert!(false, "Expected a custom feature list");
+        }
+    }
+
+    #[test]
+    fn variation_changes_advances() {
+        let font        = CanvasFontFace::from_slice(include_bytes!("../test_data/VariableTest.ttf"));
+        let bold        = font.with_variation(*b"wght", 900.0);
+
+        // 'a' is 1000 units wide at the default weight and 1500 units wide at the maximum weight, with 1000 units per em
+        let mut regular = CanvasFontLineLayout::new(&font, 10.0);
+        regular.add_text("aa");
+        let mut bold    = CanvasFontLineLayout::new(&bold, 10.0);
+        bold.add_text("aa");
+
+        assert!((regular.measure().pos.0 - 20.0).abs() < 0.01, "{:?}", regular.measure());
+        assert!((bold.measure().pos.0 - 30.0).abs() < 0.01, "{:?}", bold.measure());
+    }
+
+    #[test]
+    fn covered_text_stays_in_main_font() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+
+        let mut layout  = CanvasFontLineLayout::new(&lato, 12.0);
+        layout.set_fallback_fonts(vec![(FontId(2), Arc::clone(&lato))]);
+
+        assert!(layout.font_runs("Hello, world") == vec![(None, "Hello, world")]);
+
+        layout.add_text("Hello, world");
+        let drawing     = layout.to_drawing(FontId(1));
+
+        assert!(drawing.len() == 1);
+        assert!(if let Draw::Font(FontId(1), FontOp::DrawGlyphs(_)) = &drawing[0] { true } else { false });
+    }
+
+    #[test]
+    fn missing_characters_use_main_font() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"));
+
+        // Characters that aren't in any of the fonts are drawn as the main font's missing glyph
+        let mut layout  = CanvasFontLineLayout::new(&lato, 12.0);
+        layout.set_fallback_fonts(vec![(FontId(2), Arc::clone(&lato))]);
+
+        assert!(layout.font_runs("a\u{4e2d}b") == vec![(None, "a\u{4e2d}b")]);
     }
 }
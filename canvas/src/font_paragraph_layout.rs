@@ -0,0 +1,795 @@
+use crate::draw::*;
+use crate::font::*;
+use crate::font_face::*;
+use crate::font_line_layout::*;
+
+use unicode_linebreak::{linebreaks, BreakOpportunity};
+
+use std::mem;
+use std::iter;
+use std::ops::{Range};
+use std::sync::*;
+use std::collections::{HashMap};
+
+///
+/// A font and size used by some of the text in a paragraph
+///
+#[derive(Clone)]
+struct ParagraphFont {
+    font_id:    FontId,
+    font:       Arc<CanvasFontFace>,
+    em_size:    f32,
+}
+
+///
+/// An item that has been added to a paragraph layout
+///
+#[derive(Clone)]
+enum ParagraphItem {
+    /// Text in the font with the specified index
+    Text(usize, String),
+
+    /// A drawing instruction to perform before the text that follows it
+    Draw(Draw)
+}
+
+///
+/// A word is the text between two line break opportunities, which is never split across lines
+///
+#[derive(Clone)]
+struct ParagraphWord {
+    /// The font that's active at the start of this word
+    font: usize,
+
+    /// The text and drawing instructions in this word
+    parts: Vec<ParagraphItem>,
+
+    /// True if the line must end after this word
+    mandatory_break: bool,
+
+    /// The width of this word, including any whitespace at the end
+    width: f32,
+
+    /// The width of this word without the whitespace at the end (its width when it's at the end of a line)
+    trimmed_width: f32,
+}
+
+///
+/// A line in a paragraph
+///
+#[derive(Clone, Debug)]
+struct ParagraphLine {
+    /// The words that make up this line
+    words: Range<usize>,
+
+    /// True if this is the last line before a mandatory break (the last line of a paragraph)
+    ends_paragraph: bool,
+
+    /// Distance from the top of the line to its baseline
+    ascent: f32,
+
+    /// The height of the line (not including any paragraph spacing)
+    height: f32,
+}
+
+///
+/// Lays out text in a paragraph, wrapping it into lines that fit a particular width. The `outline_fonts` feature
+/// must be enabled to use this data type.
+///
+/// Text can be added in several fonts: each line is laid out using a `CanvasFontLineLayout`, so the text in each line
+/// is shaped and ordered in the same way as text laid out on a single line.
+///
+#[derive(Clone)]
+pub struct CanvasFontParagraphLayout {
+    /// The position of the top-left corner of the paragraph
+    position: (f32, f32),
+
+    /// The style of the paragraph
+    style: ParagraphStyle,
+
+    /// The fonts that have been used by the text in this paragraph
+    fonts: Vec<ParagraphFont>,
+
+    /// The fallback fonts to use for each font ID
+    fallback_fonts: HashMap<FontId, Vec<(FontId, Arc<CanvasFontFace>)>>,
+
+    /// The text and drawing instructions that have been added to this paragraph
+    items: Vec<ParagraphItem>,
+}
+
+impl CanvasFontParagraphLayout {
+    ///
+    /// Creates a new paragraph layout with its top-left corner at the specified position
+    ///
+    pub fn new(x: f32, y: f32, style: ParagraphStyle) -> CanvasFontParagraphLayout {
+        CanvasFontParagraphLayout {
+            position:       (x, y),
+            style:          style,
+            fonts:          vec![],
+            fallback_fonts: HashMap::new(),
+            items:          vec![],
+        }
+    }
+
+    ///
+    /// Adds some text to the end of the paragraph, in the specified font
+    ///
+    /// Newlines in the text start a new paragraph.
+    ///
+    pub fn add_text(&mut self, font_id: FontId, font: &Arc<CanvasFontFace>, em_size: f32, text: &str) {
+        // Re-use the last font if it's the same as this one
+        let font_idx = match self.fonts.last() {
+            Some(last_font) if last_font.font_id == font_id && last_font.em_size == em_size && Arc::ptr_eq(&last_font.font, font) => self.fonts.len()-1,
+
+            _ => {
+                self.fonts.push(ParagraphFont { font_id: font_id, font: Arc::clone(font), em_size: em_size });
+                self.fonts.len()-1
+            }
+        };
+
+        self.items.push(ParagraphItem::Text(font_idx, text.to_string()));
+    }
+
+    ///
+    /// Sets the fonts to use, in order of preference, for characters that are missing from the font with the specified ID
+    ///
+    pub fn set_fallback_fonts(&mut self, font_id: FontId, fallback_fonts: Vec<(FontId, Arc<CanvasFontFace>)>) {
+        self.fallback_fonts.insert(font_id, fallback_fonts);
+    }
+
+    ///
+    /// Adds some drawing instructions to perform before the text that follows them
+    ///
+    pub fn draw<DrawIter: IntoIterator<Item=Draw>>(&mut self, drawing: DrawIter) {
+        self.items.extend(drawing.into_iter().map(|item| ParagraphItem::Draw(item)));
+    }
+
+    ///
+    /// Measures the paragraph that has been laid out so far
+    ///
+    pub fn measure(&self) -> ParagraphLayoutMetrics {
+        let words           = self.words();
+        let lines           = self.lines(&words);
+        let visible_lines   = self.visible_lines(&lines);
+
+        ParagraphLayoutMetrics {
+            lines:          visible_lines,
+            height:         self.height(&lines[0..visible_lines]),
+            full_height:    self.height(&lines),
+            overflowed:     visible_lines < lines.len(),
+        }
+    }
+
+    ///
+    /// Finishes the layout and returns the drawing instructions
+    ///
+    pub fn to_drawing(self) -> Vec<Draw> {
+        // Without any fonts, there's no text to draw
+        if self.fonts.is_empty() {
+            return self.items.into_iter()
+                .flat_map(|item| match item {
+                    ParagraphItem::Draw(drawing)    => Some(drawing),
+                    ParagraphItem::Text(_, _)       => None,
+                })
+                .collect();
+        }
+
+        let words           = self.words();
+        let lines           = self.lines(&words);
+        let visible_lines   = self.visible_lines(&lines);
+        let overflowed      = visible_lines < lines.len();
+        let ellipsis        = if let TextOverflow::Ellipsis(_) = self.style.overflow { overflowed } else { false };
+
+        let (x, y)          = self.position;
+        let width           = self.style.width;
+        let mut line_top    = y;
+        let mut drawing     = vec![];
+
+        for (line_idx, line) in lines.iter().take(visible_lines).enumerate() {
+            let is_last_line    = line_idx == visible_lines-1;
+            let add_ellipsis    = ellipsis && is_last_line;
+            let mut line_words  = line.words.clone();
+
+            // Remove words from the end of the line until the ellipsis fits
+            if add_ellipsis {
+                let ellipsis_width = self.line_width(&words[line_words.clone()], true) - Self::words_width(&words[line_words.clone()]);
+
+                while line_words.len() > 1 && Self::words_width(&words[line_words.clone()]) + ellipsis_width > width {
+                    line_words.end -= 1;
+                }
+            }
+
+            if let Some((mut layout, font_id)) = self.line_layout(&words[line_words.clone()], add_ellipsis, 0.0) {
+                let right_to_left   = layout.is_right_to_left();
+                let baseline        = line_top - line.ascent;
+
+                // Justified lines are laid out again with extra space between each word (except the last line of each paragraph)
+                let justify         = self.style.alignment == ParagraphAlignment::Justify && !line.ends_paragraph && !add_ellipsis && line_words.len() > 1;
+                if justify {
+                    let extra_space     = (width - Self::layout_width(&mut layout)) / ((line_words.len()-1) as f32);
+
+                    if let Some(justified) = self.line_layout(&words[line_words.clone()], false, extra_space) {
+                        layout = justified.0;
+                    }
+                }
+
+                // Left and right alignment follow the direction of the text
+                let (origin_x, align) = match (self.style.alignment, right_to_left) {
+                    (ParagraphAlignment::Left, false)       |
+                    (ParagraphAlignment::Justify, false)    => (x, TextAlignment::Left),
+                    (ParagraphAlignment::Left, true)        |
+                    (ParagraphAlignment::Justify, true)     => (x + width, TextAlignment::Left),
+                    (ParagraphAlignment::Right, false)      => (x + width, TextAlignment::Right),
+                    (ParagraphAlignment::Right, true)       => (x, TextAlignment::Right),
+                    (ParagraphAlignment::Center, _)         => (x + width/2.0, TextAlignment::Center),
+                };
+
+                layout.align(origin_x, baseline, align);
+                drawing.extend(layout.to_drawing(font_id));
+            }
+
+            // Lines are laid out downwards from the top of the paragraph
+            line_top -= line.height;
+            if line.ends_paragraph { line_top -= self.style.paragraph_spacing; }
+        }
+
+        drawing
+    }
+
+    ///
+    /// Splits the text in this paragraph into words at the points where lines can be broken
+    ///
+    fn words(&self) -> Vec<ParagraphWord> {
+        // Find the break opportunities in the text
+        let text            = self.items.iter()
+            .flat_map(|item| match item {
+                ParagraphItem::Text(_, text)    => Some(&text[..]),
+                ParagraphItem::Draw(_)          => None,
+            })
+            .collect::<String>();
+        let breaks          = linebreaks(&text).collect::<Vec<_>>();
+
+        // Divide the items up at the break opportunities
+        let mut words       = vec![];
+        let mut current     = ParagraphWord { font: 0, parts: vec![], mandatory_break: false, width: 0.0, trimmed_width: 0.0 };
+        let mut next_break  = 0;
+        let mut pos         = 0;
+
+        for item in self.items.iter() {
+            match item {
+                ParagraphItem::Draw(drawing)        => { current.parts.push(ParagraphItem::Draw(drawing.clone())); }
+
+                ParagraphItem::Text(font, text)     => {
+                    // Words start in the font of their first piece of text
+                    if !current.parts.iter().any(|part| matches!(part, ParagraphItem::Text(_, _))) {
+                        current.font = *font;
+                    }
+
+                    let end         = pos + text.len();
+                    let mut start   = pos;
+
+                    while next_break < breaks.len() && breaks[next_break].0 <= end {
+                        let (break_pos, opportunity) = breaks[next_break];
+                        next_break += 1;
+
+                        if break_pos > start {
+                            current.parts.push(ParagraphItem::Text(*font, text[(start-pos)..(break_pos-pos)].to_string()));
+                            start = break_pos;
+                        }
+
+                        current.mandatory_break = opportunity == BreakOpportunity::Mandatory;
+                        words.push(mem::replace(&mut current, ParagraphWord { font: *font, parts: vec![], mandatory_break: false, width: 0.0, trimmed_width: 0.0 }));
+                    }
+
+                    if start < end {
+                        current.parts.push(ParagraphItem::Text(*font, text[(start-pos)..].to_string()));
+                    }
+
+                    pos = end;
+                }
+            }
+        }
+
+        // Drawing instructions after the last of the text belong to the last word, rather than starting a new line
+        if !current.parts.is_empty() {
+            let has_text = current.parts.iter().any(|part| matches!(part, ParagraphItem::Text(_, _)));
+
+            match words.last_mut() {
+                Some(last_word) if !has_text    => { last_word.parts.extend(current.parts); }
+                _                               => { words.push(current); }
+            }
+        }
+
+        // Each word is measured once, so lines can be measured by adding up the widths of their words
+        for word in words.iter_mut() {
+            let (width, trimmed_width) = self.word_width(word);
+
+            word.width          = width;
+            word.trimmed_width  = trimmed_width;
+        }
+
+        words
+    }
+
+    ///
+    /// Measures a word, returning its width with and without the whitespace at its end
+    ///
+    fn word_width(&self, word: &ParagraphWord) -> (f32, f32) {
+        let (parts, whitespace)     = Self::split_end_whitespace(&word.parts);
+        let mut layout              = None;
+        let mut pending_drawing     = vec![];
+
+        self.add_parts(&mut layout, &mut pending_drawing, parts);
+        let trimmed_width           = layout.as_mut().map(|(layout, _)| Self::layout_width(layout)).unwrap_or(0.0);
+
+        self.add_parts(&mut layout, &mut pending_drawing, whitespace);
+        let width                   = layout.as_mut().map(|(layout, _)| Self::layout_width(layout)).unwrap_or(0.0);
+
+        (width, trimmed_width)
+    }
+
+    ///
+    /// Divides a set of words into lines that fit within the width of the paragraph
+    ///
+    /// A word that is too wide to fit on a line by itself is put on a line of its own.
+    ///
+    fn lines(&self, words: &[ParagraphWord]) -> Vec<ParagraphLine> {
+        let mut lines       = vec![];
+        let mut start       = 0;
+        let mut line_width  = 0.0;
+
+        for idx in 0..words.len() {
+            // Start a new line if this word makes the current one too long
+            if idx > start && line_width + words[idx].trimmed_width > self.style.width {
+                lines.push(self.line(words, start..idx, false));
+                start       = idx;
+                line_width  = 0.0;
+            }
+
+            line_width += words[idx].width;
+
+            // Mandatory breaks always end the line
+            if words[idx].mandatory_break {
+                lines.push(self.line(words, start..(idx+1), true));
+                start       = idx+1;
+                line_width  = 0.0;
+            }
+        }
+
+        if start < words.len() {
+            lines.push(self.line(words, start..words.len(), true));
+        }
+
+        lines
+    }
+
+    ///
+    /// Creates a line from a range of words, working out its height from the fonts that it uses
+    ///
+    fn line(&self, words: &[ParagraphWord], range: Range<usize>, ends_paragraph: bool) -> ParagraphLine {
+        let mut ascender    = 0.0f32;
+        let mut descender   = 0.0f32;
+        let mut line_gap    = 0.0f32;
+
+        let mut fonts       = vec![];
+
+        for word in words[range.clone()].iter() {
+            fonts.push((&self.fonts[word.font].font, self.fonts[word.font].em_size));
+
+            for part in word.parts.iter() {
+                if let ParagraphItem::Text(font, text) = part {
+                    let font = &self.fonts[*font];
+
+                    // Glyphs from fallback fonts are drawn at the size of the font they're replacing
+                    fonts.push((&font.font, font.em_size));
+                    fonts.extend(self.used_fallback_fonts(font, text).into_iter().map(|fallback| (fallback, font.em_size)));
+                }
+            }
+        }
+
+        for (font, em_size) in fonts {
+            if let Some(metrics) = font.font_metrics(em_size) {
+                ascender    = ascender.max(metrics.ascender);
+                descender   = descender.min(metrics.descender);
+                line_gap    = line_gap.max(metrics.line_gap);
+            } else {
+                // Fonts without metrics are treated as if the glyphs are one em high
+                ascender    = ascender.max(em_size);
+            }
+        }
+
+        ParagraphLine {
+            words:          range,
+            ends_paragraph: ends_paragraph,
+            ascent:         ascender,
+            height:         (ascender - descender + line_gap) * self.style.line_height,
+        }
+    }
+
+    ///
+    /// Returns the fallback fonts that will be used to draw the characters in some text that are missing from its font
+    ///
+    fn used_fallback_fonts<'a>(&'a self, font: &ParagraphFont, text: &str) -> Vec<&'a Arc<CanvasFontFace>> {
+        let fallback_fonts  = match self.fallback_fonts.get(&font.font_id) {
+            Some(fallback_fonts)    => fallback_fonts,
+            None                    => { return vec![]; }
+        };
+
+        let mut used        = vec![];
+
+        for chr in text.chars().filter(|chr| !chr.is_whitespace() && !chr.is_control()) {
+            if font.font.ttf_font().glyph_index(chr).is_some() { continue; }
+
+            if let Some((_, fallback)) = fallback_fonts.iter().find(|(_, fallback)| fallback.ttf_font().glyph_index(chr).is_some()) {
+                if !used.iter().any(|used_font| Arc::ptr_eq(used_font, fallback)) {
+                    used.push(fallback);
+                }
+            }
+        }
+
+        used
+    }
+
+    ///
+    /// Returns the number of lines that fit within the maximum height of the paragraph
+    ///
+    fn visible_lines(&self, lines: &[ParagraphLine]) -> usize {
+        let max_height = match self.style.overflow {
+            TextOverflow::Visible               => { return lines.len(); }
+            TextOverflow::Clip(max_height)      |
+            TextOverflow::Ellipsis(max_height)  => max_height
+        };
+
+        // Keep a running total of the height so each line is only measured once
+        let mut height = 0.0;
+
+        lines.iter()
+            .enumerate()
+            .take_while(|(line_idx, line)| {
+                // Paragraph spacing is only added between lines, so the spacing after the previous line is added here
+                if *line_idx > 0 && lines[line_idx-1].ends_paragraph {
+                    height += self.style.paragraph_spacing;
+                }
+                height += line.height;
+
+                height <= max_height
+            })
+            .count()
+    }
+
+    ///
+    /// Returns the height of a set of lines
+    ///
+    fn height(&self, lines: &[ParagraphLine]) -> f32 {
+        let line_height     = lines.iter().map(|line| line.height).sum::<f32>();
+        let num_paragraphs  = lines.iter().take(lines.len().saturating_sub(1)).filter(|line| line.ends_paragraph).count();
+
+        line_height + (num_paragraphs as f32) * self.style.paragraph_spacing
+    }
+
+    ///
+    /// Returns the width of a line made up of a set of words, using the width they were measured at
+    ///
+    fn words_width(words: &[ParagraphWord]) -> f32 {
+        match words.split_last() {
+            Some((last_word, words))    => words.iter().map(|word| word.width).sum::<f32>() + last_word.trimmed_width,
+            None                        => 0.0
+        }
+    }
+
+    ///
+    /// Returns the width of a line made up of a set of words
+    ///
+    fn line_width(&self, words: &[ParagraphWord], ellipsis: bool) -> f32 {
+        self.line_layout(words, ellipsis, 0.0)
+            .map(|(mut layout, _)| Self::layout_width(&mut layout))
+            .unwrap_or(0.0)
+    }
+
+    ///
+    /// Returns the width of the text in a line layout
+    ///
+    fn layout_width(layout: &mut CanvasFontLineLayout) -> f32 {
+        let (min, max) = layout.measure().inner_bounds;
+
+        (max.0 - min.0) as f32
+    }
+
+    ///
+    /// Lays out a set of words on a line, with the whitespace at the end removed
+    ///
+    /// `word_spacing` is added between each word, and the ellipsis character is added to the end of the line if `ellipsis` is true.
+    /// The result is the layout, and the ID of the font that's being used at the end of the line.
+    ///
+    fn line_layout(&self, words: &[ParagraphWord], ellipsis: bool, word_spacing: f32) -> Option<(CanvasFontLineLayout, FontId)> {
+        if words.is_empty() || self.fonts.is_empty() { return None; }
+
+        let mut layout: Option<(CanvasFontLineLayout, usize)>   = None;
+        let mut pending_drawing                                 = vec![];
+
+        for (word_idx, word) in words.iter().enumerate() {
+            // Add the extra spacing between words
+            if word_idx > 0 && word_spacing != 0.0 {
+                if let Some((layout, _)) = &mut layout {
+                    layout.advance(word_spacing, 0.0);
+                }
+            }
+
+            // The whitespace at the end of the line is never drawn
+            let parts = if word_idx == words.len()-1 { Self::split_end_whitespace(&word.parts).0 } else { word.parts.clone() };
+
+            self.add_parts(&mut layout, &mut pending_drawing, parts);
+        }
+
+        // The ellipsis is added using the last font on the line
+        if ellipsis {
+            let last_font   = layout.as_ref().map(|(_, font)| *font).unwrap_or(words[0].font);
+            let mut line    = self.continue_layout(layout.take(), last_font);
+            line.0.add_text("\u{2026}");
+
+            layout = Some(line);
+        }
+
+        // Lines with only drawing instructions still need a layout to draw them
+        let (mut layout, font) = layout.unwrap_or_else(|| self.continue_layout(None, words[0].font));
+        layout.draw(pending_drawing);
+
+        Some((layout, self.fonts[font].font_id))
+    }
+
+    ///
+    /// Adds the text and drawing instructions from a word to a line layout
+    ///
+    /// Drawing instructions that come before any text are added to `pending_drawing`, as there's no layout to add them to yet.
+    ///
+    fn add_parts(&self, layout: &mut Option<(CanvasFontLineLayout, usize)>, pending_drawing: &mut Vec<Draw>, parts: Vec<ParagraphItem>) {
+        for part in parts {
+            match part {
+                ParagraphItem::Text(font, text) => {
+                    let mut line = self.continue_layout(layout.take(), font);
+                    line.0.draw(mem::take(pending_drawing));
+                    line.0.add_text(&text);
+
+                    *layout = Some(line);
+                }
+
+                ParagraphItem::Draw(drawing)    => {
+                    if let Some((layout, _)) = layout {
+                        layout.draw(iter::once(drawing));
+                    } else {
+                        pending_drawing.push(drawing);
+                    }
+                }
+            }
+        }
+    }
+
+    ///
+    /// Continues a line layout using the specified font
+    ///
+    fn continue_layout(&self, layout: Option<(CanvasFontLineLayout, usize)>, font: usize) -> (CanvasFontLineLayout, usize) {
+        let new_font    = &self.fonts[font];
+        let mut layout  = match layout {
+            Some((layout, last_font)) if last_font == font  => { return (layout, font); }
+            Some((layout, last_font))                       => layout.continue_with_new_font(self.fonts[last_font].font_id, &new_font.font, new_font.em_size),
+            None                                            => CanvasFontLineLayout::new(&new_font.font, new_font.em_size),
+        };
+
+        layout.set_fallback_fonts(self.fallback_fonts.get(&new_font.font_id).cloned().unwrap_or_else(|| vec![]));
+
+        (layout, font)
+    }
+
+    ///
+    /// Removes the whitespace from the end of the text in a list of items, returning the trimmed items and the whitespace that was removed
+    ///
+    fn split_end_whitespace(parts: &[ParagraphItem]) -> (Vec<ParagraphItem>, Vec<ParagraphItem>) {
+        let mut parts       = parts.to_vec();
+        let mut whitespace  = vec![];
+
+        for part in parts.iter_mut().rev() {
+            if let ParagraphItem::Text(font, text) = part {
+                let trimmed_len = text.trim_end().len();
+                let removed     = text.split_off(trimmed_len);
+
+                if !removed.is_empty() { whitespace.push(ParagraphItem::Text(*font, removed)); }
+
+                if trimmed_len > 0 { break; }
+            }
+        }
+
+        whitespace.reverse();
+        (parts, whitespace)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lato() -> Arc<CanvasFontFace> {
+        CanvasFontFace::from_slice(include_bytes!("../test_data/Lato-Regular.ttf"))
+    }
+
+    fn glyph_lines(drawing: &[Draw]) -> Vec<f32> {
+        let mut baselines = drawing.iter()
+            .flat_map(|draw| match draw {
+                Draw::Font(_, FontOp::DrawGlyphs(glyphs))   => glyphs.iter().map(|glyph| glyph.location.1).collect::<Vec<_>>(),
+                _                                           => vec![]
+            })
+            .collect::<Vec<_>>();
+
+        baselines.dedup();
+        baselines
+    }
+
+    #[test]
+    fn short_text_is_one_line() {
+        let mut paragraph = CanvasFontParagraphLayout::new(0.0, 100.0, ParagraphStyle::new(500.0));
+        paragraph.add_text(FontId(1), &lato(), 12.0, "Hello, world");
+
+        let metrics = paragraph.measure();
+        assert!(metrics.lines == 1, "{:?}", metrics);
+        assert!(!metrics.overflowed);
+    }
+
+    #[test]
+    fn long_text_wraps() {
+        let mut paragraph = CanvasFontParagraphLayout::new(0.0, 100.0, ParagraphStyle::new(100.0));
+        paragraph.add_text(FontId(1), &lato(), 12.0, "The quick brown fox jumps over the lazy dog");
+
+        let metrics = paragraph.measure();
+        assert!(metrics.lines > 1, "{:?}", metrics);
+
+        // Each line is drawn below the last one, and the first line is below the top of the paragraph
+        let baselines = glyph_lines(&paragraph.to_drawing());
+        assert!(baselines.len() == metrics.lines, "{:?}", baselines);
+        assert!(baselines[0] < 100.0);
+        assert!(baselines.windows(2).all(|pair| pair[1] < pair[0]), "{:?}", baselines);
+
+        // All the glyphs are within the width of the paragraph
+        let mut paragraph = CanvasFontParagraphLayout::new(0.0, 100.0, ParagraphStyle::new(100.0));
+        paragraph.add_text(FontId(1), &lato(), 12.0, "The quick brown fox jumps over the lazy dog");
+        let words = paragraph.words();
+        for line in paragraph.lines(&words) {
+            assert!(paragraph.line_width(&words[line.words], false) <= 100.0);
+        }
+    }
+
+    #[test]
+    fn newlines_start_new_lines() {
+        let mut paragraph = CanvasFontParagraphLayout::new(0.0, 100.0, ParagraphStyle::new(500.0).with_paragraph_spacing(10.0));
+        paragraph.add_text(FontId(1), &lato(), 12.0, "One\nTwo");
+
+        let metrics = paragraph.measure();
+        let words   = paragraph.words();
+        let lines   = paragraph.lines(&words);
+
+        assert!(metrics.lines == 2, "{:?}", metrics);
+        assert!((metrics.height - (lines[0].height + lines[1].height + 10.0)).abs() < 0.001, "{:?}", metrics);
+    }
+
+    #[test]
+    fn justified_lines_fill_width() {
+        let style           = ParagraphStyle::new(150.0).with_alignment(ParagraphAlignment::Justify);
+        let mut paragraph   = CanvasFontParagraphLayout::new(0.0, 0.0, style);
+        paragraph.add_text(FontId(1), &lato(), 12.0, "The quick brown fox jumps over the lazy dog and keeps on running");
+
+        let words           = paragraph.words();
+        let lines           = paragraph.lines(&words);
+        let first_line      = &lines[0];
+        let last_line       = &lines[lines.len()-1];
+        let last_width      = paragraph.line_width(&words[last_line.words.clone()], false);
+
+        // The first line ends at the edge of the paragraph, but the last line is left-aligned
+        let drawing         = paragraph.to_drawing();
+        let glyphs          = drawing.iter()
+            .flat_map(|draw| match draw {
+                Draw::Font(_, FontOp::DrawGlyphs(glyphs))   => glyphs.clone(),
+                _                                           => vec![]
+            })
+            .collect::<Vec<_>>();
+        let first_baseline  = glyphs[0].location.1;
+        let first_line_end  = glyphs.iter().filter(|glyph| glyph.location.1 == first_baseline).map(|glyph| glyph.location.0).fold(0.0f32, f32::max);
+        let last_baseline   = glyphs[glyphs.len()-1].location.1;
+        let last_line_end   = glyphs.iter().filter(|glyph| glyph.location.1 == last_baseline).map(|glyph| glyph.location.0).fold(0.0f32, f32::max);
+
+        assert!(lines.len() > 2);
+        assert!(!first_line.ends_paragraph && last_line.ends_paragraph);
+        assert!(first_line_end > 150.0 - 12.0 && first_line_end <= 150.0, "{}", first_line_end);
+        assert!(last_line_end < last_width, "{} {}", last_line_end, last_width);
+    }
+
+    #[test]
+    fn clip_removes_lines() {
+        let style           = ParagraphStyle::new(100.0).with_overflow(TextOverflow::Clip(20.0));
+        let mut paragraph   = CanvasFontParagraphLayout::new(0.0, 100.0, style);
+        paragraph.add_text(FontId(1), &lato(), 12.0, "The quick brown fox jumps over the lazy dog");
+
+        let metrics = paragraph.measure();
+
+        assert!(metrics.lines == 1, "{:?}", metrics);
+        assert!(metrics.overflowed);
+        assert!(metrics.height <= 20.0 && metrics.full_height > 20.0, "{:?}", metrics);
+        assert!(glyph_lines(&paragraph.to_drawing()).len() == 1);
+    }
+
+    #[test]
+    fn ellipsis_is_added_to_last_line() {
+        let lato            = lato();
+        let ellipsis_id     = lato.ttf_font().glyph_index('\u{2026}').unwrap().0 as u32;
+
+        let style           = ParagraphStyle::new(100.0).with_overflow(TextOverflow::Ellipsis(20.0));
+        let mut paragraph   = CanvasFontParagraphLayout::new(0.0, 100.0, style);
+        paragraph.add_text(FontId(1), &lato, 12.0, "The quick brown fox jumps over the lazy dog");
+
+        let drawing         = paragraph.to_drawing();
+        let glyphs          = drawing.iter()
+            .flat_map(|draw| match draw {
+                Draw::Font(_, FontOp::DrawGlyphs(glyphs))   => glyphs.clone(),
+                _                                           => vec![]
+            })
+            .collect::<Vec<_>>();
+
+        assert!(glyphs[glyphs.len()-1].id == GlyphId(ellipsis_id));
+        assert!(glyphs.iter().all(|glyph| glyph.location.0 <= 100.0));
+    }
+
+    #[test]
+    fn fill_color_is_kept_with_text() {
+        let mut paragraph = CanvasFontParagraphLayout::new(0.0, 100.0, ParagraphStyle::new(500.0));
+        paragraph.add_text(FontId(1), &lato(), 12.0, "Hello, ");
+        paragraph.draw(iter::once(Draw::FillColor(crate::color::Color::Rgba(1.0, 0.0, 0.0, 1.0))));
+        paragraph.add_text(FontId(1), &lato(), 12.0, "world");
+
+        let drawing = paragraph.to_drawing();
+
+        assert!(drawing.len() == 3, "{:?}", drawing);
+        assert!(if let Draw::FillColor(_) = drawing[1] { true } else { false });
+    }
+
+    #[test]
+    fn drawing_after_text_does_not_add_line() {
+        let mut paragraph = CanvasFontParagraphLayout::new(0.0, 100.0, ParagraphStyle::new(500.0));
+        paragraph.add_text(FontId(1), &lato(), 12.0, "Hello, world");
+        paragraph.draw(iter::once(Draw::FillColor(crate::color::Color::Rgba(1.0, 0.0, 0.0, 1.0))));
+
+        let metrics = paragraph.measure();
+        let drawing = paragraph.to_drawing();
+
+        assert!(metrics.lines == 1, "{:?}", metrics);
+        assert!(if let Some(Draw::FillColor(_)) = drawing.last() { true } else { false }, "{:?}", drawing);
+    }
+
+    #[test]
+    fn fallback_fonts_change_line_height() {
+        let dejavu          = CanvasFontFace::from_slice(include_bytes!("../test_data/DejaVuSans-Hebrew.ttf"));
+        let style           = ParagraphStyle::new(500.0);
+
+        // Only the line that uses the fallback font is affected (DejaVu Sans has a lower descender than Lato)
+        let mut paragraph   = CanvasFontParagraphLayout::new(0.0, 100.0, style);
+        paragraph.set_fallback_fonts(FontId(1), vec![(FontId(2), dejavu)]);
+        paragraph.add_text(FontId(1), &lato(), 12.0, "Hello\n\u{05e9}\u{05dc}\u{05d5}\u{05dd}");
+
+        let words           = paragraph.words();
+        let lines           = paragraph.lines(&words);
+        let lato_metrics    = lato().font_metrics(12.0).unwrap();
+
+        assert!(lines.len() == 2, "{:?}", lines);
+        assert!((lines[0].height - (lato_metrics.ascender - lato_metrics.descender + lato_metrics.line_gap)).abs() < 0.001, "{:?}", lines);
+        assert!(lines[1].height > lines[0].height, "{:?}", lines);
+    }
+
+    #[test]
+    fn word_widths_add_up_to_line_width() {
+        let mut paragraph = CanvasFontParagraphLayout::new(0.0, 100.0, ParagraphStyle::new(500.0));
+        paragraph.add_text(FontId(1), &lato(), 12.0, "The quick brown fox jumps over the lazy dog");
+
+        let words           = paragraph.words();
+        let words_width     = CanvasFontParagraphLayout::words_width(&words);
+        let line_width      = paragraph.line_width(&words, false);
+
+        assert!(words.len() == 9);
+        assert!((words_width - line_width).abs() < 0.01, "{} {}", words_width, line_width);
+    }
+}
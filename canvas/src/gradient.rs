@@ -10,6 +10,22 @@ use std::cmp::{Ordering};
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct GradientId(pub u64);
 
+///
+/// How a gradient is extended beyond its first and last stops
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum GradientSpread {
+    /// The colours of the first and last stops are extended indefinitely
+    #[default]
+    Pad,
+
+    /// The gradient repeats from the start after the last stop
+    Repeat,
+
+    /// The gradient is reflected back on itself after the last stop
+    Reflect
+}
+
 ///
 /// Operations that can be applied to a gradient
 ///
@@ -19,7 +35,10 @@ pub enum GradientOp {
     Create(Color),
 
     /// Adds a new gradient stop of the specified colour
-    AddStop(f32, Color)
+    AddStop(f32, Color),
+
+    /// Sets how the gradient is drawn outside of the range of its stops (gradients are padded by default)
+    Spread(GradientSpread)
 }
 
 ///
@@ -58,9 +77,10 @@ pub fn gradient_scale<GradientIter: IntoIterator<Item=GradientOp>, const N: usiz
 
     // Create a list of colour stops by position
     let mut stops = description.into_iter()
-        .map(|op| match op {
-            GradientOp::Create(col)         => (0.0, col.to_rgba_components()),
-            GradientOp::AddStop(pos, col)   => (pos, col.to_rgba_components())
+        .flat_map(|op| match op {
+            GradientOp::Create(col)         => Some((0.0, col.to_rgba_components())),
+            GradientOp::AddStop(pos, col)   => Some((pos, col.to_rgba_components())),
+            GradientOp::Spread(_)           => None
         })
         .collect::<Vec<_>>();
 
@@ -124,6 +144,18 @@ pub fn gradient_scale<GradientIter: IntoIterator<Item=GradientOp>, const N: usiz
     }
 }
 
+///
+/// Returns the spread mode set by a set of gradient operations
+///
+pub fn gradient_spread<'a, GradientIter: IntoIterator<Item=&'a GradientOp>>(description: GradientIter) -> GradientSpread {
+    description.into_iter()
+        .fold(GradientSpread::Pad, |spread, op| match op {
+            GradientOp::Create(_)       => GradientSpread::Pad,
+            GradientOp::AddStop(_, _)   => spread,
+            GradientOp::Spread(spread)  => *spread
+        })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -269,6 +301,55 @@ mod test {
         assert!(scale[14] == [0, 0, 0, 238]);
     }
 
+    #[test]
+    fn spread_ops_do_not_affect_scale() {
+        let padded = gradient_scale::<_, 16>(vec![
+            GradientOp::Create(Color::Rgba(0.0, 0.0, 0.0, 0.0)), 
+            GradientOp::AddStop(1.0, Color::Rgba(1.0, 1.0, 1.0, 1.0))
+        ]);
+        let repeated = gradient_scale::<_, 16>(vec![
+            GradientOp::Create(Color::Rgba(0.0, 0.0, 0.0, 0.0)), 
+            GradientOp::Spread(GradientSpread::Repeat),
+            GradientOp::AddStop(1.0, Color::Rgba(1.0, 1.0, 1.0, 1.0))
+        ]);
+
+        assert!(padded == repeated);
+    }
+
+    #[test]
+    fn default_spread_is_pad() {
+        let spread = gradient_spread(&vec![
+            GradientOp::Create(Color::Rgba(0.0, 0.0, 0.0, 0.0)), 
+            GradientOp::AddStop(1.0, Color::Rgba(1.0, 1.0, 1.0, 1.0))
+        ]);
+
+        assert!(spread == GradientSpread::Pad);
+    }
+
+    #[test]
+    fn last_spread_is_used() {
+        let spread = gradient_spread(&vec![
+            GradientOp::Create(Color::Rgba(0.0, 0.0, 0.0, 0.0)), 
+            GradientOp::Spread(GradientSpread::Repeat),
+            GradientOp::AddStop(1.0, Color::Rgba(1.0, 1.0, 1.0, 1.0)),
+            GradientOp::Spread(GradientSpread::Reflect),
+        ]);
+
+        assert!(spread == GradientSpread::Reflect);
+    }
+
+    #[test]
+    fn create_resets_spread() {
+        let spread = gradient_spread(&vec![
+            GradientOp::Create(Color::Rgba(0.0, 0.0, 0.0, 0.0)), 
+            GradientOp::Spread(GradientSpread::Repeat),
+            GradientOp::Create(Color::Rgba(0.0, 0.0, 0.0, 0.0)), 
+            GradientOp::AddStop(1.0, Color::Rgba(1.0, 1.0, 1.0, 1.0)),
+        ]);
+
+        assert!(spread == GradientSpread::Pad);
+    }
+
     #[test]
     fn generate_two_stop_scale() {
         let scale = gradient_scale::<_, 17>(vec![
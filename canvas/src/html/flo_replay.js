@@ -0,0 +1,658 @@
+//
+// Replays a drawing exported by flo_canvas's HtmlWriter on a HTML canvas element
+//
+// The drawing is a list of instructions, each of which is an array whose first element names the operation (see
+// html_writer.rs for the list). Fonts are supplied as base64-encoded font files and textures as data URIs: these are
+// loaded before the drawing is replayed, so the function returns a promise that resolves once the canvas is drawn.
+//
+function flo_replay(canvas, width, height, fonts, instructions) {
+    "use strict";
+
+    // Load the fonts and images used by the drawing
+    const loading   = [];
+    const images    = new Map();
+
+    fonts.forEach(function (data, index) {
+        const bytes = Uint8Array.from(atob(data), function (chr) { return chr.charCodeAt(0); });
+        const face  = new FontFace("flo_font" + index, bytes);
+
+        document.fonts.add(face);
+        loading.push(face.load().catch(function () { }));
+    });
+
+    instructions.forEach(function (instruction) {
+        if (instruction[0] === "tb" && !images.has(instruction[6])) {
+            const image = new Image();
+            image.src   = instruction[6];
+
+            images.set(instruction[6], image);
+            loading.push(image.decode().catch(function () { }));
+        }
+    });
+
+    return Promise.all(loading).then(function () { replay(); });
+
+    function replay() {
+        // The canvas is scaled so it's drawn at the resolution of the display
+        const ratio         = window.devicePixelRatio || 1;
+        const pixel_width   = Math.max(1, Math.round(width * ratio));
+        const pixel_height  = Math.max(1, Math.round(height * ratio));
+
+        canvas.width        = pixel_width;
+        canvas.height       = pixel_height;
+        canvas.style.width  = width + "px";
+        canvas.style.height = height + "px";
+
+        // Canvas coordinates are 2.0 units high, with (0, 0) at the center and y pointing upwards
+        const base      = new DOMMatrix([height/2*ratio, 0, 0, -height/2*ratio, width/2*ratio, height/2*ratio]);
+        const measure   = document.createElement("canvas").getContext("2d");
+
+        // Letters from these scripts start a right-to-left line
+        const rtl_script    = /[\p{sc=Hebrew}\p{sc=Arabic}\p{sc=Syriac}\p{sc=Thaana}\p{sc=Nko}]/u;
+
+        // The state of the drawing
+        let layers, layer_order, sprites, textures, gradients, font_definitions, font_fallbacks, background;
+        let target, state, target_states, transform, layer_transform, path, state_stack, layout;
+
+        function create_canvas(canvas_width, canvas_height) {
+            const new_canvas    = document.createElement("canvas");
+            new_canvas.width    = Math.max(1, Math.round(canvas_width));
+            new_canvas.height   = Math.max(1, Math.round(canvas_height));
+
+            return new_canvas;
+        }
+
+        function default_state() {
+            return {
+                fill:               { color: "rgba(0,0,0,1)" },
+                fill_transform:     new DOMMatrix(),
+                stroke:             { color: "rgba(0,0,0,1)" },
+                line_width:         1,
+                line_width_pixels:  false,
+                line_join:          "round",
+                line_cap:           "butt",
+                miter_limit:        4,
+                dash:               [],
+                dash_offset:        0,
+                winding:            "nonzero",
+                blend:              "source-over",
+                sprite_transform:   new DOMMatrix(),
+                clip:               []
+            };
+        }
+
+        function copy_state(old_state) {
+            return Object.assign({}, old_state, { dash: old_state.dash.slice(), clip: old_state.clip.slice() });
+        }
+
+        function reset() {
+            layers              = new Map();
+            layer_order         = [];
+            sprites             = new Map();
+            textures            = new Map();
+            gradients           = new Map();
+            font_definitions    = new Map();
+            font_fallbacks      = new Map();
+            background          = null;
+            target              = { layer: 0 };
+            state               = default_state();
+            target_states       = new Map();
+            transform           = new DOMMatrix();
+            layer_transform     = new DOMMatrix();
+            path                = new Path2D();
+            state_stack         = [];
+            layout              = null;
+
+            layer(0);
+        }
+
+        function clamp(val) {
+            return Math.max(0, Math.min(1, val));
+        }
+
+        function matrix_scale(matrix) {
+            return Math.sqrt(Math.abs(matrix.a*matrix.d - matrix.b*matrix.c)) || 1;
+        }
+
+        // Layers are drawn on offscreen canvases, which are combined when the drawing is finished
+        function layer(layer_id) {
+            let existing = layers.get(layer_id);
+
+            if (!existing) {
+                const layer_canvas  = create_canvas(pixel_width, pixel_height);
+                existing            = { canvas: layer_canvas, context: layer_canvas.getContext("2d"), alpha: 1, blend: "source-over", stored: null };
+
+                layers.set(layer_id, existing);
+            }
+
+            // Layers are ordered by ID unless they've been swapped
+            if (layer_order.indexOf(layer_id) < 0) {
+                let pos = layer_order.findIndex(function (other_id) { return other_id > layer_id; });
+                if (pos < 0) { pos = layer_order.length; }
+
+                layer_order.splice(pos, 0, layer_id);
+            }
+
+            return existing;
+        }
+
+        function clear_layer(existing) {
+            existing.context.setTransform(1, 0, 0, 1, 0, 0);
+            existing.context.clearRect(0, 0, pixel_width, pixel_height);
+        }
+
+        function target_key(some_target) {
+            return some_target.sprite !== undefined ? "s" + some_target.sprite : "l" + some_target.layer;
+        }
+
+        function select_target(new_target) {
+            target_states.set(target_key(target), state);
+
+            state   = target_states.get(target_key(new_target)) || default_state();
+            target  = new_target;
+        }
+
+        // Elements are functions that draw on a context, given the transform from canvas coordinates to the context's coordinates
+        // Layers draw their elements straight away, and sprites store them so they can be drawn whenever the sprite is
+        function add_element(element) {
+            if (target.sprite !== undefined) {
+                if (!sprites.has(target.sprite)) { sprites.set(target.sprite, []); }
+                sprites.get(target.sprite).push(element);
+            } else {
+                element(layer(target.layer).context, base);
+            }
+        }
+
+        function begin_element(context, outer, element_state) {
+            context.save();
+
+            element_state.clip.forEach(function (clip) {
+                context.setTransform(outer.multiply(clip.transform));
+                context.clip(clip.path, clip.winding);
+            });
+
+            // Sprites are drawn with the blend mode they were drawn with, unless their elements have their own blend mode
+            if (element_state.blend !== "source-over") {
+                context.globalCompositeOperation = element_state.blend;
+            }
+        }
+
+        function make_style(context, paint, paint_transform) {
+            if (paint.color !== undefined) {
+                return { style: paint.color, alpha: 1 };
+            }
+
+            if (paint.stops !== undefined) {
+                const coords    = paint.coords;
+                let gradient    = null;
+
+                if (paint.kind === "linear") {
+                    const start = paint_transform.transformPoint({ x: coords[0], y: coords[1] });
+                    const end   = paint_transform.transformPoint({ x: coords[2], y: coords[3] });
+                    gradient    = context.createLinearGradient(start.x, start.y, end.x, end.y);
+                } else if (paint.kind === "radial") {
+                    const center    = paint_transform.transformPoint({ x: coords[0], y: coords[1] });
+                    const focus     = paint_transform.transformPoint({ x: coords[3], y: coords[4] });
+                    gradient        = context.createRadialGradient(focus.x, focus.y, 0, center.x, center.y, Math.abs(coords[2]) * matrix_scale(paint_transform));
+                } else if (context.createConicGradient) {
+                    const center    = paint_transform.transformPoint({ x: coords[0], y: coords[1] });
+                    gradient        = context.createConicGradient(coords[2], center.x, center.y);
+                } else {
+                    return { style: paint.stops[0][1], alpha: 1 };
+                }
+
+                paint.stops.forEach(function (stop) { gradient.addColorStop(clamp(stop[0]), stop[1]); });
+                return { style: gradient, alpha: 1 };
+            }
+
+            // Textures are patterns, with the top-left corner of the texture at (x1, y2)
+            const texture   = paint.texture;
+            const coords    = paint.coords;
+            update_dynamic_texture(texture);
+
+            const pattern           = context.createPattern(texture.canvas, "repeat");
+            const texture_width     = texture.canvas.width;
+            const texture_height    = texture.canvas.height;
+            pattern.setTransform(paint_transform.multiply(new DOMMatrix([(coords[2]-coords[0])/texture_width, 0, 0, (coords[1]-coords[3])/texture_height, coords[0], coords[3]])));
+
+            return { style: pattern, alpha: texture.alpha };
+        }
+
+        function fill_element(fill_path, element_state, element_transform) {
+            return function (context, outer) {
+                begin_element(context, outer, element_state);
+
+                const matrix        = outer.multiply(element_transform);
+                let paint_transform = element_state.fill_transform;
+                let shape           = fill_path;
+                context.setTransform(matrix);
+
+                if (element_state.fill.stops !== undefined && !paint_transform.isIdentity) {
+                    // Gradients are positioned using the current transform, so the path is moved into the coordinates of the fill transform instead
+                    context.setTransform(matrix.multiply(paint_transform));
+
+                    shape = new Path2D();
+                    shape.addPath(fill_path, paint_transform.inverse());
+                    paint_transform = new DOMMatrix();
+                }
+
+                const style             = make_style(context, element_state.fill, paint_transform);
+                context.fillStyle       = style.style;
+                context.globalAlpha    *= style.alpha;
+                context.fill(shape, element_state.winding);
+
+                context.restore();
+            };
+        }
+
+        function stroke_element(stroke_path, element_state, element_transform) {
+            return function (context, outer) {
+                begin_element(context, outer, element_state);
+
+                const matrix = outer.multiply(element_transform);
+                context.setTransform(matrix);
+
+                const style             = make_style(context, element_state.stroke, new DOMMatrix());
+                context.strokeStyle     = style.style;
+                context.globalAlpha    *= style.alpha;
+                context.lineWidth       = element_state.line_width_pixels ? element_state.line_width * ratio / matrix_scale(matrix) : element_state.line_width;
+                context.lineJoin        = element_state.line_join;
+                context.lineCap         = element_state.line_cap;
+                context.miterLimit      = element_state.miter_limit;
+
+                if (element_state.dash.some(function (length) { return length > 0; })) {
+                    context.setLineDash(element_state.dash);
+                    context.lineDashOffset = element_state.dash_offset;
+                }
+
+                context.stroke(stroke_path);
+                context.restore();
+            };
+        }
+
+        function text_font(font_id) {
+            // Fallback fonts are added to the list of families, so the browser uses them for missing characters
+            const font      = Object.assign({}, font_definitions.get(font_id));
+            const fallbacks = font_fallbacks.get(font_id) || [];
+
+            fallbacks.forEach(function (fallback_id) {
+                if (font_definitions.has(fallback_id)) {
+                    font.family += ", " + font_definitions.get(fallback_id).family;
+                }
+            });
+
+            return font;
+        }
+
+        function text_element(text, font, x, y, element_state, element_transform) {
+            return function (context, outer) {
+                begin_element(context, outer, element_state);
+
+                // Text is drawn with y pointing downwards, so it's flipped around its baseline
+                context.setTransform(outer.multiply(element_transform).translate(x, y).scale(1, -1));
+
+                const placement_inverse = new DOMMatrix([1, 0, 0, -1, -x, y]);
+                const style             = make_style(context, element_state.fill, placement_inverse.multiply(element_state.fill_transform));
+                context.fillStyle       = style.style;
+                context.globalAlpha    *= style.alpha;
+                context.font            = font.size + "px " + font.family;
+                context.fillText(text, 0, 0);
+
+                context.restore();
+            };
+        }
+
+        function apply_filters(context, filters, scale) {
+            const css_filters = [];
+
+            filters.forEach(function (filter) {
+                if (filter[0] === "blur")   { css_filters.push("blur(" + (filter[1] / 4 * scale) + "px)"); }
+                if (filter[0] === "alpha")  { context.globalAlpha *= filter[1]; }
+            });
+
+            if (css_filters.length > 0) {
+                context.filter = css_filters.join(" ");
+            }
+        }
+
+        function sprite_element(sprite_id, filters, element_state, element_transform) {
+            return function (context, outer) {
+                const elements = sprites.get(sprite_id);
+                if (!elements) { return; }
+
+                begin_element(context, outer, element_state);
+
+                const matrix = outer.multiply(element_transform).multiply(element_state.sprite_transform);
+
+                if (filters.length === 0) {
+                    elements.forEach(function (element) { element(context, matrix); });
+                } else {
+                    // Filters apply to the sprite as a whole, so it's drawn on its own canvas first
+                    const offscreen         = create_canvas(context.canvas.width, context.canvas.height);
+                    const offscreen_context = offscreen.getContext("2d");
+                    elements.forEach(function (element) { element(offscreen_context, matrix); });
+
+                    context.setTransform(1, 0, 0, 1, 0, 0);
+                    apply_filters(context, filters, matrix_scale(matrix) / ratio);
+                    context.drawImage(offscreen, 0, 0);
+                }
+
+                context.restore();
+            };
+        }
+
+        // Textures are drawn on canvases, which are used as patterns
+        function draw_sprite_on_texture(texture, sprite_id, bounds) {
+            const texture_width     = texture.canvas.width;
+            const texture_height    = texture.canvas.height;
+            const context           = texture.canvas.getContext("2d");
+
+            context.setTransform(1, 0, 0, 1, 0, 0);
+            context.clearRect(0, 0, texture_width, texture_height);
+            if (bounds[2] === 0 || bounds[3] === 0) { return; }
+
+            // Map the sprite bounds onto the texture (sprites have y pointing upwards, and textures have y pointing downwards)
+            const matrix = new DOMMatrix([texture_width/bounds[2], 0, 0, -texture_height/bounds[3], -bounds[0]*texture_width/bounds[2], texture_height + bounds[1]*texture_height/bounds[3]]);
+            (sprites.get(sprite_id) || []).forEach(function (element) { element(context, matrix); });
+        }
+
+        function update_dynamic_texture(texture) {
+            if (texture.dynamic) {
+                draw_sprite_on_texture(texture, texture.dynamic.sprite, texture.dynamic.bounds);
+            }
+        }
+
+        function filter_texture(texture, filters) {
+            const source = create_canvas(texture.canvas.width, texture.canvas.height);
+            source.getContext("2d").drawImage(texture.canvas, 0, 0);
+
+            const context = texture.canvas.getContext("2d");
+            context.save();
+            context.setTransform(1, 0, 0, 1, 0, 0);
+            context.clearRect(0, 0, texture.canvas.width, texture.canvas.height);
+            apply_filters(context, filters, 1);
+            context.drawImage(source, 0, 0);
+            context.restore();
+        }
+
+        function texture_paint(args) {
+            const texture = textures.get(args[1]);
+            return texture ? { texture: texture, coords: args.slice(2) } : null;
+        }
+
+        function gradient_paint(kind, args) {
+            const stops = gradients.get(args[1]);
+            return stops ? { kind: kind, stops: stops.slice(), coords: args.slice(2) } : null;
+        }
+
+        function compose() {
+            const context = canvas.getContext("2d");
+
+            context.setTransform(1, 0, 0, 1, 0, 0);
+            context.globalAlpha                 = 1;
+            context.globalCompositeOperation    = "source-over";
+            context.clearRect(0, 0, pixel_width, pixel_height);
+
+            if (background) {
+                context.fillStyle = background;
+                context.fillRect(0, 0, pixel_width, pixel_height);
+            }
+
+            layer_order.forEach(function (layer_id) {
+                const existing = layers.get(layer_id);
+                if (!existing) { return; }
+
+                context.globalAlpha                 = clamp(existing.alpha);
+                context.globalCompositeOperation    = existing.blend;
+                context.drawImage(existing.canvas, 0, 0);
+            });
+        }
+
+        reset();
+
+        instructions.forEach(function (args) {
+            let paint, existing, texture;
+
+            switch (args[0]) {
+                // Paths
+                case "np":  path = new Path2D(); break;
+                case "m":   path.moveTo(args[1], args[2]); break;
+                case "l":   path.lineTo(args[1], args[2]); break;
+                case "c":   path.bezierCurveTo(args[1], args[2], args[3], args[4], args[5], args[6]); break;
+                case "q":   path.quadraticCurveTo(args[1], args[2], args[3], args[4]); break;
+                case "a":   path.ellipse(args[1], args[2], Math.abs(args[3]), Math.abs(args[4]), args[5], args[6], args[6] + args[7], args[7] < 0); break;
+                case "z":   path.closePath(); break;
+
+                // Drawing
+                case "f":   add_element(fill_element(new Path2D(path), copy_state(state), transform)); break;
+                case "s":   add_element(stroke_element(new Path2D(path), copy_state(state), transform)); break;
+                case "cl":  state.clip = state.clip.concat([{ path: new Path2D(path), transform: transform, winding: state.winding }]); break;
+                case "uc":  state.clip = []; break;
+
+                case "lw":  state.line_width = args[1]; state.line_width_pixels = false; break;
+                case "lwp": state.line_width = args[1]; state.line_width_pixels = true; break;
+                case "lj":  state.line_join = args[1]; break;
+                case "ml":  state.miter_limit = args[1]; break;
+                case "lc":  state.line_cap = args[1]; break;
+                case "nd":  state.dash = []; state.dash_offset = 0; break;
+                case "dl":  state.dash.push(args[1]); break;
+                case "do":  state.dash_offset = args[1]; break;
+                case "wr":  state.winding = args[1]; break;
+                case "bm":  state.blend = args[1]; break;
+
+                case "fc":  state.fill = { color: args[1] }; state.fill_transform = new DOMMatrix(); break;
+                case "sc":  state.stroke = { color: args[1] }; break;
+                case "ft":  state.fill_transform = state.fill_transform.multiply(new DOMMatrix(args.slice(1))); break;
+
+                case "fx":  paint = texture_paint(args); if (paint) { state.fill = paint; state.fill_transform = new DOMMatrix(); } break;
+                case "sx":  paint = texture_paint(args); if (paint) { state.stroke = paint; } break;
+                case "fg":  paint = gradient_paint("linear", args); if (paint) { state.fill = paint; state.fill_transform = new DOMMatrix(); } break;
+                case "sg":  paint = gradient_paint("linear", args); if (paint) { state.stroke = paint; } break;
+                case "frg": paint = gradient_paint("radial", args); if (paint) { state.fill = paint; state.fill_transform = new DOMMatrix(); } break;
+                case "fcg": paint = gradient_paint("conic", args); if (paint) { state.fill = paint; state.fill_transform = new DOMMatrix(); } break;
+
+                // Transforms
+                case "it":  transform = new DOMMatrix(); break;
+                case "mt":  transform = transform.multiply(new DOMMatrix(args.slice(1))); break;
+
+                case "ch":
+                    // Sprites aren't affected by the canvas height
+                    if (target.sprite === undefined) {
+                        const scale = 2 / Math.max(1, args[1]);
+                        transform   = new DOMMatrix([scale, 0, 0, scale, 0, 0]);
+                    }
+                    break;
+
+                case "cr":
+                    // Move the point at the center of the canvas to the center of the region
+                    if (target.sprite === undefined && transform.isInvertible !== false) {
+                        const center = transform.inverse().transformPoint({ x: 0, y: 0 });
+
+                        if (isFinite(center.x) && isFinite(center.y)) {
+                            transform = transform.translate(-((args[1]+args[3])/2 - center.x), -((args[2]+args[4])/2 - center.y));
+                        }
+                    }
+                    break;
+
+                // State
+                case "ps":  state_stack.push({ transform: transform, state: copy_state(state), path: new Path2D(path) }); break;
+                case "pp":
+                    existing = state_stack.pop();
+                    if (existing) {
+                        transform   = existing.transform;
+                        state       = existing.state;
+                        path        = existing.path;
+                    }
+                    break;
+
+                case "st":
+                    if (target.sprite === undefined) {
+                        existing        = layer(target.layer);
+                        existing.stored = create_canvas(pixel_width, pixel_height);
+                        existing.stored.getContext("2d").drawImage(existing.canvas, 0, 0);
+                    }
+                    break;
+
+                case "rs":
+                    if (target.sprite === undefined) {
+                        existing = layer(target.layer);
+
+                        if (existing.stored) {
+                            clear_layer(existing);
+                            existing.context.drawImage(existing.stored, 0, 0);
+                        }
+                    }
+                    break;
+
+                case "fsb": if (target.sprite === undefined) { layer(target.layer).stored = null; } break;
+                case "cc":  reset(); background = args[1]; break;
+
+                // Layers
+                case "ly":
+                    if (target.sprite !== undefined) { transform = layer_transform; }
+
+                    select_target({ layer: args[1] });
+                    layer(args[1]);
+                    break;
+
+                case "lb":  layer(args[1]).blend = args[2]; break;
+                case "la":  layer(args[1]).alpha = args[2]; break;
+                case "cly": if (target.sprite !== undefined) { sprites.set(target.sprite, []); } else { clear_layer(layer(target.layer)); } break;
+                case "cal": layers.forEach(clear_layer); break;
+
+                case "sw":
+                    layer(args[1]);
+                    layer(args[2]);
+
+                    flo_swap_layers(layer_order, args[1], args[2]);
+                    break;
+
+                // Sprites
+                case "spr":
+                    // Sprites are drawn in their own coordinate scheme
+                    if (target.sprite === undefined) { layer_transform = transform; }
+                    transform = new DOMMatrix();
+
+                    select_target({ sprite: args[1] });
+                    break;
+
+                case "msf":
+                    if (target.sprite !== undefined && target.sprite !== args[1]) {
+                        sprites.set(target.sprite, sprites.get(args[1]) || []);
+                        sprites.delete(args[1]);
+                    }
+                    break;
+
+                case "sti": state.sprite_transform = new DOMMatrix(); break;
+                case "stm": state.sprite_transform = new DOMMatrix(args.slice(1)).multiply(state.sprite_transform); break;
+                case "ds":  add_element(sprite_element(args[1], args[2], copy_state(state), transform)); break;
+
+                // Textures
+                case "tc":  textures.set(args[1], { canvas: create_canvas(args[2], args[3]), alpha: 1, dynamic: null }); break;
+                case "tf":  textures.delete(args[1]); break;
+
+                case "tb":
+                    texture = textures.get(args[1]);
+
+                    if (texture) {
+                        const context = texture.canvas.getContext("2d");
+                        context.clearRect(args[2], args[3], args[4], args[5]);
+                        context.drawImage(images.get(args[6]), args[2], args[3]);
+                    }
+                    break;
+
+                case "tsp":
+                    texture = textures.get(args[1]);
+                    if (texture) { draw_sprite_on_texture(texture, args[2], args.slice(3, 7)); }
+                    break;
+
+                case "tds": textures.set(args[1], { canvas: create_canvas(args[7], args[8]), alpha: 1, dynamic: { sprite: args[2], bounds: args.slice(3, 7) } }); break;
+                case "ta":  texture = textures.get(args[1]); if (texture) { texture.alpha = args[2]; } break;
+
+                case "tcp":
+                    texture = textures.get(args[1]);
+
+                    if (texture) {
+                        const copy = create_canvas(texture.canvas.width, texture.canvas.height);
+                        copy.getContext("2d").drawImage(texture.canvas, 0, 0);
+                        textures.set(args[2], { canvas: copy, alpha: texture.alpha, dynamic: texture.dynamic });
+                    }
+                    break;
+
+                case "tfl":
+                    texture = textures.get(args[1]);
+                    if (texture) { update_dynamic_texture(texture); filter_texture(texture, args[2]); }
+                    break;
+
+                // Gradients
+                case "gc":  gradients.set(args[1], [[0, args[2]]]); break;
+                case "gs":  if (gradients.has(args[1])) { gradients.get(args[1]).push([args[2], args[3]]); } break;
+
+                // Text
+                case "fd":  font_definitions.set(args[1], { family: "flo_font" + args[2], size: 12 }); break;
+                case "fsz": if (font_definitions.has(args[1])) { font_definitions.get(args[1]).size = args[2]; } break;
+                case "ffb": font_fallbacks.set(args[1], args[2]); break;
+                case "ll":  layout = { x: args[1], y: args[2], alignment: args[3], width: args[4] || 0, spans: [] }; break;
+
+                case "lt":
+                    if (layout && font_definitions.has(args[1])) {
+                        layout.spans.push({ text: args[2], font: text_font(args[1]) });
+                    }
+                    break;
+
+                case "dlt":
+                    if (layout) {
+                        // Measure the spans so the text can be aligned
+                        const widths = layout.spans.map(function (span) {
+                            measure.font = span.font.size + "px " + span.font.family;
+                            return measure.measureText(span.text).width;
+                        });
+                        const total_width = widths.reduce(function (total, span_width) { return total + span_width; }, 0);
+
+                        // The direction of the line is set by its first letter, and 'start' and 'end' alignment is relative to that
+                        const first_letter  = layout.spans.map(function (span) { return span.text; }).join("").match(/\p{L}/u);
+                        const right_to_left = first_letter !== null && rtl_script.test(first_letter[0]);
+
+                        let x = layout.x;
+                        if (layout.alignment === "center")                          { x += (layout.width - total_width) / 2; }
+                        else if ((layout.alignment === "end") !== right_to_left)    { x += layout.width - total_width; }
+
+                        // Spans run from right to left in a right-to-left line
+                        const order = layout.spans.map(function (span, index) { return index; });
+                        if (right_to_left) { order.reverse(); }
+
+                        order.forEach(function (index) {
+                            add_element(text_element(layout.spans[index].text, layout.spans[index].font, x, layout.y, copy_state(state), transform));
+                            x += widths[index];
+                        });
+
+                        layout = null;
+                    }
+                    break;
+
+                case "dt":
+                    if (font_definitions.has(args[1])) {
+                        add_element(text_element(args[2], text_font(args[1]), args[3], args[4], copy_state(state), transform));
+                    }
+                    break;
+            }
+        });
+
+        compose();
+    }
+}
+
+//
+// Swaps the positions of two layer IDs in the order that layers are drawn in
+//
+function flo_swap_layers(layer_order, first_id, second_id) {
+    "use strict";
+
+    const first_pos     = layer_order.indexOf(first_id);
+    const second_pos    = layer_order.indexOf(second_id);
+
+    if (first_pos >= 0 && second_pos >= 0) {
+        layer_order[first_pos]  = second_id;
+        layer_order[second_pos] = first_id;
+    }
+}
@@ -0,0 +1,152 @@
+use crate::draw::*;
+use crate::color::*;
+use crate::transform2d::*;
+
+///
+/// Formats a number for use in a JavaScript literal
+///
+pub (super) fn js_number(val: f32) -> String {
+    if val == 0.0 || !val.is_finite() {
+        "0".to_string()
+    } else {
+        format!("{}", val)
+    }
+}
+
+///
+/// Formats a string as a JavaScript string literal (which is also safe to include in a `<script>` element)
+///
+pub (super) fn js_string(text: &str) -> String {
+    let mut literal = String::with_capacity(text.len() + 2);
+    literal.push('"');
+
+    for chr in text.chars() {
+        match chr {
+            '"'     => literal.push_str("\\\""),
+            '\\'    => literal.push_str("\\\\"),
+            '\n'    => literal.push_str("\\n"),
+            '\r'    => literal.push_str("\\r"),
+            '\t'    => literal.push_str("\\t"),
+
+            // '<' is escaped so the string can't close the script element it's in
+            '<'     => literal.push_str("\\u003c"),
+
+            // Line and paragraph separators end statements in older JavaScript engines
+            chr if chr < ' ' || chr == '\u{2028}' || chr == '\u{2029}' => literal.push_str(&format!("\\u{:04x}", chr as u32)),
+
+            chr     => literal.push(chr),
+        }
+    }
+
+    literal.push('"');
+    literal
+}
+
+///
+/// Escapes text for use in a HTML document
+///
+pub (super) fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for chr in text.chars() {
+        match chr {
+            '&'     => escaped.push_str("&amp;"),
+            '<'     => escaped.push_str("&lt;"),
+            '>'     => escaped.push_str("&gt;"),
+            '"'     => escaped.push_str("&quot;"),
+            chr     => escaped.push(chr),
+        }
+    }
+
+    escaped
+}
+
+///
+/// Returns the CSS colour for a canvas colour, as a JavaScript string literal
+///
+pub (super) fn js_color(color: &Color) -> String {
+    let (r, g, b, a)    = color.to_rgba_components();
+    let component       = |val: f32| (val.max(0.0).min(1.0) * 255.0).round() as u8;
+
+    format!("\"rgba({},{},{},{})\"", component(r), component(g), component(b), js_number(a.max(0.0).min(1.0)))
+}
+
+///
+/// Returns the elements of a transform in the order used by `DOMMatrix` and `CanvasRenderingContext2D.setTransform()`
+///
+pub (super) fn js_matrix(transform: &Transform2D) -> String {
+    let Transform2D(a) = transform;
+
+    format!("{},{},{},{},{},{}", js_number(a[0][0]), js_number(a[1][0]), js_number(a[0][1]), js_number(a[1][1]), js_number(a[0][2]), js_number(a[1][2]))
+}
+
+///
+/// Returns the `globalCompositeOperation` that matches a blend mode
+///
+pub (super) fn composite_operation(blend_mode: BlendMode) -> &'static str {
+    use self::BlendMode::*;
+
+    match blend_mode {
+        SourceOver      => "source-over",
+        SourceIn        => "source-in",
+        SourceOut       => "source-out",
+        DestinationOver => "destination-over",
+        DestinationIn   => "destination-in",
+        DestinationOut  => "destination-out",
+        SourceAtop      => "source-atop",
+        DestinationAtop => "destination-atop",
+
+        Multiply        => "multiply",
+        Screen          => "screen",
+        Darken          => "darken",
+        Lighten         => "lighten",
+        Overlay         => "overlay",
+        ColorDodge      => "color-dodge",
+        ColorBurn       => "color-burn",
+        HardLight       => "hard-light",
+        SoftLight       => "soft-light",
+        Difference      => "difference",
+        Exclusion       => "exclusion",
+        Hue             => "hue",
+        Saturation      => "saturation",
+        Color           => "color",
+        Luminosity      => "luminosity",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn format_numbers() {
+        assert!(js_number(0.0) == "0");
+        assert!(js_number(-0.0) == "0");
+        assert!(js_number(-2.5) == "-2.5");
+        assert!(js_number(f32::NAN) == "0");
+        assert!(js_number(f32::INFINITY) == "0");
+    }
+
+    #[test]
+    fn format_strings() {
+        assert!(js_string("Hello") == "\"Hello\"");
+        assert!(js_string("\"a\"\\b\n") == "\"\\\"a\\\"\\\\b\\n\"");
+        assert!(js_string("</script>") == "\"\\u003c/script>\"");
+        assert!(js_string("\u{1}") == "\"\\u0001\"");
+    }
+
+    #[test]
+    fn format_color() {
+        assert!(js_color(&Color::Rgba(1.0, 0.0, 0.5, 0.25)) == "\"rgba(255,0,128,0.25)\"");
+    }
+
+    #[test]
+    fn format_matrix() {
+        assert!(js_matrix(&Transform2D::translate(10.0, 20.0)) == "1,0,0,1,10,20");
+    }
+
+    #[test]
+    fn escape_html() {
+        assert!(html_escape("<a & \"b\">") == "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}
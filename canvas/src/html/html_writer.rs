@@ -0,0 +1,809 @@
+use super::html_format::*;
+
+use crate::draw::*;
+use crate::path::*;
+use crate::font::*;
+use crate::sprite::*;
+use crate::context::*;
+use crate::texture::*;
+use crate::gradient::*;
+use crate::namespace::*;
+use crate::font_face::*;
+use crate::transform2d::*;
+use crate::data_uri::*;
+
+use crate::conversion_streams::combine_paths;
+#[cfg(feature = "outline-fonts")]
+use crate::conversion_streams::{drawing_with_laid_out_text, drawing_with_text_as_paths};
+
+use futures::prelude::*;
+
+use std::sync::*;
+use std::hash::Hash;
+use std::fmt::Write;
+use std::collections::HashMap;
+
+/// The script that replays the instructions written by `HtmlWriter` on a canvas element
+const REPLAY_SCRIPT: &str = include_str!("flo_replay.js");
+
+///
+/// Options for generating a HTML document from a drawing
+///
+#[derive(Clone, PartialEq, Debug)]
+pub struct HtmlOptions {
+    /// The width of the canvas in CSS pixels
+    pub width: f32,
+
+    /// The height of the canvas in CSS pixels
+    pub height: f32,
+
+    /// The title of the document
+    pub title: String,
+}
+
+impl HtmlOptions {
+    ///
+    /// Creates the options for a document containing a canvas of the specified size
+    ///
+    pub fn new(width: f32, height: f32) -> HtmlOptions {
+        HtmlOptions {
+            width:  width,
+            height: height,
+            title:  "Drawing".to_string(),
+        }
+    }
+
+    ///
+    /// Changes the title of the document
+    ///
+    pub fn with_title(mut self, title: &str) -> HtmlOptions {
+        self.title = title.to_string();
+        self
+    }
+}
+
+///
+/// Generates a self-contained HTML document that replays a drawing on a `<canvas>` element using the Canvas2D API
+///
+/// The drawing is written as a list of instructions for a small script that's embedded in the document. Layers are drawn
+/// on offscreen canvases, sprites are stored as lists of paths that are replayed whenever they're drawn, and textures and
+/// fonts are embedded as data. Like the canvas, the document is 2.0 units high with the origin at the center.
+///
+/// Text is drawn using `fillText()`, which lays it out in the browser using the fallback fonts but not the OpenType settings, and
+/// `DrawGlyphs` instructions are ignored. When the `outline-fonts` feature is available, `drawing_to_html()` lays out all of
+/// the text in a drawing and converts the glyphs to paths before it's written, so the text looks the same as it does on a canvas.
+///
+#[derive(Clone)]
+pub struct HtmlWriter {
+    /// The size of the canvas in CSS pixels
+    width:              f32,
+    height:             f32,
+
+    /// The title of the document
+    title:              String,
+
+    /// The namespace that resource IDs are in
+    namespace:          NamespaceId,
+
+    /// The current path and the path saved by `StorePath` (these are tracked so that `CombinePath` can be written as a path)
+    current_path:       Vec<PathOp>,
+    stored_path:        Vec<PathOp>,
+
+    /// The winding rule used when combining paths
+    winding_rule:       WindingRule,
+
+    /// The paths, winding rules and namespaces stored by `PushState`
+    state_stack:        Vec<(Vec<PathOp>, WindingRule, NamespaceId)>,
+
+    /// The IDs used by the script for each resource (resources in different namespaces are given different IDs)
+    sprites:            HashMap<(NamespaceId, SpriteId), usize>,
+    textures:           HashMap<(NamespaceId, TextureId), usize>,
+    gradients:          HashMap<(NamespaceId, GradientId), usize>,
+    fonts:              HashMap<(NamespaceId, FontId), usize>,
+
+    /// The font faces that are embedded in the document
+    font_faces:         Vec<Arc<CanvasFontFace>>,
+
+    /// The instructions for the script, as JavaScript array literals
+    instructions:       Vec<String>,
+}
+
+///
+/// Returns the ID used by the script for a resource, allocating a new one if the resource hasn't been seen before
+///
+fn resource_id<TId: Copy+Eq+Hash>(ids: &mut HashMap<(NamespaceId, TId), usize>, namespace: NamespaceId, id: TId) -> usize {
+    let next_id = ids.len();
+
+    *ids.entry((namespace, id)).or_insert(next_id)
+}
+
+///
+/// Returns the instructions for a list of texture filters (filters that can't be represented using the Canvas2D API are left out)
+///
+fn filter_list(filters: &[TextureFilter]) -> String {
+    let filters = filters.iter()
+        .filter_map(|filter| match filter {
+            TextureFilter::GaussianBlur(radius) => Some(format!("[\"blur\",{}]", js_number(*radius))),
+            TextureFilter::AlphaBlend(alpha)    => Some(format!("[\"alpha\",{}]", js_number(*alpha))),
+
+            TextureFilter::Mask(_)                  |
+            TextureFilter::DisplacementMap(_, _, _) => None,
+        })
+        .collect::<Vec<_>>();
+
+    format!("[{}]", filters.join(","))
+}
+
+///
+/// Returns the instruction that adds a path operation to the current path
+///
+fn path_instruction(path_op: &PathOp) -> String {
+    match path_op {
+        PathOp::NewPath                                             => "[\"np\"]".to_string(),
+        PathOp::Move(x, y)                                          => format!("[\"m\",{},{}]", js_number(*x), js_number(*y)),
+        PathOp::Line(x, y)                                          => format!("[\"l\",{},{}]", js_number(*x), js_number(*y)),
+        PathOp::BezierCurve(((cp1x, cp1y), (cp2x, cp2y)), (x, y))   => format!("[\"c\",{},{},{},{},{},{}]", js_number(*cp1x), js_number(*cp1y), js_number(*cp2x), js_number(*cp2y), js_number(*x), js_number(*y)),
+        PathOp::QuadraticCurve((cpx, cpy), (x, y))                  => format!("[\"q\",{},{},{},{}]", js_number(*cpx), js_number(*cpy), js_number(*x), js_number(*y)),
+        PathOp::Arc((cx, cy), (rx, ry), rotation, start, sweep)     => format!("[\"a\",{},{},{},{},{},{},{}]", js_number(*cx), js_number(*cy), js_number(*rx), js_number(*ry), js_number(*rotation), js_number(*start), js_number(*sweep)),
+        PathOp::ClosePath                                           => "[\"z\"]".to_string(),
+    }
+}
+
+impl HtmlWriter {
+    ///
+    /// Creates a new HTML writer for a canvas of the specified size (in CSS pixels)
+    ///
+    pub fn new(width: f32, height: f32) -> HtmlWriter {
+        HtmlWriter {
+            width:          width,
+            height:         height,
+            title:          "Drawing".to_string(),
+            namespace:      NamespaceId::default(),
+            current_path:   vec![],
+            stored_path:    vec![],
+            winding_rule:   WindingRule::NonZero,
+            state_stack:    vec![],
+            sprites:        HashMap::new(),
+            textures:       HashMap::new(),
+            gradients:      HashMap::new(),
+            fonts:          HashMap::new(),
+            font_faces:     vec![],
+            instructions:   vec![],
+        }
+    }
+
+    ///
+    /// Creates a HTML writer using a set of options
+    ///
+    pub fn with_options(options: &HtmlOptions) -> HtmlWriter {
+        let mut writer  = HtmlWriter::new(options.width, options.height);
+        writer.title    = options.title.clone();
+
+        writer
+    }
+
+    ///
+    /// Returns the HTML document for everything that's been drawn so far
+    ///
+    pub fn document(&self) -> String {
+        let fonts = self.font_faces.iter()
+            .map(|font_face| format!("\"{}\"", base64(font_face.font_data())))
+            .collect::<Vec<_>>();
+        let instructions = if self.instructions.is_empty() {
+            "[]".to_string()
+        } else {
+            format!("[\n{}\n]", self.instructions.join(",\n"))
+        };
+
+        let mut html = String::new();
+
+        writeln!(html, "<!DOCTYPE html>").ok();
+        writeln!(html, "<html>").ok();
+        writeln!(html, "<head>").ok();
+        writeln!(html, "<meta charset=\"utf-8\">").ok();
+        writeln!(html, "<title>{}</title>", html_escape(&self.title)).ok();
+        writeln!(html, "</head>").ok();
+        writeln!(html, "<body>").ok();
+        writeln!(html, "<canvas id=\"flo_canvas\" width=\"{}\" height=\"{}\"></canvas>", js_number(self.width), js_number(self.height)).ok();
+        writeln!(html, "<script>\n{}</script>", REPLAY_SCRIPT).ok();
+        writeln!(html, "<script>").ok();
+        writeln!(html, "flo_replay(document.getElementById(\"flo_canvas\"), {}, {}, [{}], {});", js_number(self.width), js_number(self.height), fonts.join(","), instructions).ok();
+        writeln!(html, "</script>").ok();
+        writeln!(html, "</body>").ok();
+        writeln!(html, "</html>").ok();
+
+        html
+    }
+
+    ///
+    /// Adds an instruction to the script
+    ///
+    #[inline]
+    fn emit(&mut self, instruction: String) {
+        self.instructions.push(instruction);
+    }
+
+    #[inline] fn sprite_id(&mut self, sprite_id: SpriteId) -> usize        { resource_id(&mut self.sprites, self.namespace, sprite_id) }
+    #[inline] fn texture_id(&mut self, texture_id: TextureId) -> usize     { resource_id(&mut self.textures, self.namespace, texture_id) }
+    #[inline] fn gradient_id(&mut self, gradient_id: GradientId) -> usize  { resource_id(&mut self.gradients, self.namespace, gradient_id) }
+    #[inline] fn font_id(&mut self, font_id: FontId) -> usize              { resource_id(&mut self.fonts, self.namespace, font_id) }
+
+    ///
+    /// Writes the instructions for a texture operation
+    ///
+    fn texture_op(&mut self, texture_id: TextureId, op: TextureOp) {
+        let id = self.texture_id(texture_id);
+
+        match op {
+            TextureOp::Create(TextureSize(width, height), _format)  => self.emit(format!("[\"tc\",{},{},{}]", id, width, height)),
+            TextureOp::Free                                         => self.emit(format!("[\"tf\",{}]", id)),
+            TextureOp::FillTransparency(alpha)                      => self.emit(format!("[\"ta\",{},{}]", id, js_number(alpha))),
+            TextureOp::Filter(filter)                               => self.emit(format!("[\"tfl\",{},{}]", id, filter_list(&[filter]))),
+
+            TextureOp::SetBytes(TexturePosition(x, y), size, bytes) => {
+                if let Some(data_uri) = png_data_uri(size, &bytes) {
+                    let TextureSize(width, height) = size;
+                    self.emit(format!("[\"tb\",{},{},{},{},{},{}]", id, x, y, width, height, js_string(&data_uri)));
+                }
+            }
+
+            TextureOp::SetFromSprite(sprite_id, SpriteBounds(SpritePosition(x, y), SpriteSize(w, h))) => {
+                let sprite_id = self.sprite_id(sprite_id);
+                self.emit(format!("[\"tsp\",{},{},{},{},{},{}]", id, sprite_id, js_number(x), js_number(y), js_number(w), js_number(h)));
+            }
+
+            TextureOp::CreateDynamicSprite(sprite_id, SpriteBounds(SpritePosition(x, y), SpriteSize(w, h)), CanvasSize(canvas_width, canvas_height)) => {
+                let sprite_id = self.sprite_id(sprite_id);
+                self.emit(format!("[\"tds\",{},{},{},{},{},{},{},{}]", id, sprite_id, js_number(x), js_number(y), js_number(w), js_number(h), js_number(canvas_width), js_number(canvas_height)));
+            }
+
+            TextureOp::Copy(target_id) => {
+                let target_id = self.texture_id(target_id);
+                self.emit(format!("[\"tcp\",{},{}]", id, target_id));
+            }
+        }
+    }
+
+    ///
+    /// Writes the instructions for a font operation
+    ///
+    fn font_op(&mut self, font_id: FontId, op: FontOp) {
+        let id = self.font_id(font_id);
+
+        match op {
+            FontOp::UseFontDefinition(font_face) => {
+                // Each font face is embedded once, however many font IDs it's used with
+                let face_index = if let Some(index) = self.font_faces.iter().position(|existing| Arc::ptr_eq(existing, &font_face)) {
+                    index
+                } else {
+                    self.font_faces.push(font_face);
+                    self.font_faces.len() - 1
+                };
+
+                self.emit(format!("[\"fd\",{},{}]", id, face_index));
+            }
+
+            FontOp::FontSize(size)      => self.emit(format!("[\"fsz\",{},{}]", id, js_number(size))),
+            FontOp::LayoutText(text)    => self.emit(format!("[\"lt\",{},{}]", id, js_string(&text))),
+
+            FontOp::UseFallbackFonts(fallback_fonts) => {
+                let fallback_ids = fallback_fonts.into_iter().map(|fallback_id| self.font_id(fallback_id).to_string()).collect::<Vec<_>>();
+                self.emit(format!("[\"ffb\",{},[{}]]", id, fallback_ids.join(",")));
+            }
+
+            // Glyphs can't be drawn using the Canvas2D API (drawing_to_html() converts them to paths)
+            FontOp::DrawGlyphs(_)       => { }
+
+            // The Canvas2D API can't change the OpenType settings of a font (drawing_to_html() lays out text using them before converting it to paths)
+            FontOp::FontFeature(_, _)   |
+            FontOp::FontVariation(_, _) => { }
+        }
+    }
+
+    ///
+    /// Writes the instructions for a single drawing instruction
+    ///
+    fn process(&mut self, drawing: Draw) {
+        use self::Draw::*;
+
+        match drawing {
+            // The document shows the final state of the drawing, so frames have no effect
+            StartFrame | ShowFrame | ResetFrame => { }
+
+            Path(PathOp::NewPath) => {
+                self.current_path.clear();
+                self.emit(path_instruction(&PathOp::NewPath));
+            }
+
+            Path(path_op) => {
+                self.emit(path_instruction(&path_op));
+                self.current_path.push(path_op);
+            }
+
+            StorePath => {
+                self.stored_path = self.current_path.clone();
+            }
+
+            CombinePath(operation) => {
+                // The script has no path arithmetic, so the combined path replaces the current path
+                self.current_path = combine_paths(operation, self.winding_rule, &self.stored_path, &self.current_path);
+
+                self.emit(path_instruction(&PathOp::NewPath));
+                let instructions = self.current_path.iter().map(path_instruction).collect::<Vec<_>>();
+                self.instructions.extend(instructions);
+            }
+
+            Fill                        => self.emit("[\"f\"]".to_string()),
+            Stroke                      => self.emit("[\"s\"]".to_string()),
+            Clip                        => self.emit("[\"cl\"]".to_string()),
+            Unclip                      => self.emit("[\"uc\"]".to_string()),
+
+            LineWidth(width)            => self.emit(format!("[\"lw\",{}]", js_number(width))),
+            LineWidthPixels(width)      => self.emit(format!("[\"lwp\",{}]", js_number(width))),
+            MiterLimit(limit)           => self.emit(format!("[\"ml\",{}]", js_number(limit))),
+            NewDashPattern              => self.emit("[\"nd\"]".to_string()),
+            DashLength(length)          => self.emit(format!("[\"dl\",{}]", js_number(length))),
+            DashOffset(offset)          => self.emit(format!("[\"do\",{}]", js_number(offset))),
+
+            LineJoin(join) => {
+                let join = match join {
+                    crate::draw::LineJoin::Miter    => "miter",
+                    crate::draw::LineJoin::Round    => "round",
+                    crate::draw::LineJoin::Bevel    => "bevel",
+                };
+
+                self.emit(format!("[\"lj\",\"{}\"]", join));
+            }
+
+            LineCap(cap) => {
+                let cap = match cap {
+                    crate::draw::LineCap::Butt      => "butt",
+                    crate::draw::LineCap::Round     => "round",
+                    crate::draw::LineCap::Square    => "square",
+                };
+
+                self.emit(format!("[\"lc\",\"{}\"]", cap));
+            }
+
+            WindingRule(rule) => {
+                self.winding_rule = rule;
+
+                let rule = match rule {
+                    crate::draw::WindingRule::NonZero   => "nonzero",
+                    crate::draw::WindingRule::EvenOdd   => "evenodd",
+                };
+
+                self.emit(format!("[\"wr\",\"{}\"]", rule));
+            }
+
+            BlendMode(blend_mode)       => self.emit(format!("[\"bm\",\"{}\"]", composite_operation(blend_mode))),
+
+            FillColor(color)            => self.emit(format!("[\"fc\",{}]", js_color(&color))),
+            StrokeColor(color)          => self.emit(format!("[\"sc\",{}]", js_color(&color))),
+            FillTransform(transform)    => self.emit(format!("[\"ft\",{}]", js_matrix(&transform))),
+
+            FillTexture(texture_id, (x1, y1), (x2, y2)) => {
+                let id = self.texture_id(texture_id);
+                self.emit(format!("[\"fx\",{},{},{},{},{}]", id, js_number(x1), js_number(y1), js_number(x2), js_number(y2)));
+            }
+
+            StrokeTexture(texture_id, (x1, y1), (x2, y2)) => {
+                let id = self.texture_id(texture_id);
+                self.emit(format!("[\"sx\",{},{},{},{},{}]", id, js_number(x1), js_number(y1), js_number(x2), js_number(y2)));
+            }
+
+            FillGradient(gradient_id, (x1, y1), (x2, y2)) => {
+                let id = self.gradient_id(gradient_id);
+                self.emit(format!("[\"fg\",{},{},{},{},{}]", id, js_number(x1), js_number(y1), js_number(x2), js_number(y2)));
+            }
+
+            StrokeGradient(gradient_id, (x1, y1), (x2, y2)) => {
+                let id = self.gradient_id(gradient_id);
+                self.emit(format!("[\"sg\",{},{},{},{},{}]", id, js_number(x1), js_number(y1), js_number(x2), js_number(y2)));
+            }
+
+            FillRadialGradient(gradient_id, (x, y), radius, focus) => {
+                let id              = self.gradient_id(gradient_id);
+                let (fx, fy)        = focus.unwrap_or((x, y));
+                self.emit(format!("[\"frg\",{},{},{},{},{},{}]", id, js_number(x), js_number(y), js_number(radius), js_number(fx), js_number(fy)));
+            }
+
+            FillConicGradient(gradient_id, (x, y), angle) => {
+                let id = self.gradient_id(gradient_id);
+                self.emit(format!("[\"fcg\",{},{},{},{}]", id, js_number(x), js_number(y), js_number(angle)));
+            }
+
+            IdentityTransform                   => self.emit("[\"it\"]".to_string()),
+            CanvasHeight(height)                => self.emit(format!("[\"ch\",{}]", js_number(height))),
+            CenterRegion((x1, y1), (x2, y2))    => self.emit(format!("[\"cr\",{},{},{},{}]", js_number(x1), js_number(y1), js_number(x2), js_number(y2))),
+            MultiplyTransform(transform)        => self.emit(format!("[\"mt\",{}]", js_matrix(&transform))),
+
+            PushState => {
+                self.state_stack.push((self.current_path.clone(), self.winding_rule, self.namespace));
+                self.emit("[\"ps\"]".to_string());
+            }
+
+            PopState => {
+                if let Some((path, winding_rule, namespace)) = self.state_stack.pop() {
+                    self.current_path   = path;
+                    self.winding_rule   = winding_rule;
+                    self.namespace      = namespace;
+                }
+
+                self.emit("[\"pp\"]".to_string());
+            }
+
+            Store                       => self.emit("[\"st\"]".to_string()),
+            Restore                     => self.emit("[\"rs\"]".to_string()),
+            FreeStoredBuffer            => self.emit("[\"fsb\"]".to_string()),
+
+            ClearCanvas(color) => {
+                // Clearing the canvas removes everything, so any instructions before it have no effect on the document
+                self.instructions.clear();
+                self.current_path.clear();
+                self.stored_path.clear();
+                self.state_stack.clear();
+                self.winding_rule = crate::draw::WindingRule::NonZero;
+                self.namespace = NamespaceId::default();
+
+                self.emit(format!("[\"cc\",{}]", js_color(&color)));
+            }
+
+            Layer(LayerId(layer_id))                    => self.emit(format!("[\"ly\",{}]", layer_id)),
+            LayerBlend(LayerId(layer_id), blend_mode)   => self.emit(format!("[\"lb\",{},\"{}\"]", layer_id, composite_operation(blend_mode))),
+            LayerAlpha(LayerId(layer_id), alpha)        => self.emit(format!("[\"la\",{},{}]", layer_id, js_number(alpha))),
+            ClearLayer | ClearSprite                    => self.emit("[\"cly\"]".to_string()),
+            ClearAllLayers                              => self.emit("[\"cal\"]".to_string()),
+            SwapLayers(LayerId(layer1), LayerId(layer2)) => self.emit(format!("[\"sw\",{},{}]", layer1, layer2)),
+
+            Sprite(sprite_id) => {
+                let id = self.sprite_id(sprite_id);
+                self.emit(format!("[\"spr\",{}]", id));
+            }
+
+            MoveSpriteFrom(sprite_id) => {
+                let id = self.sprite_id(sprite_id);
+                self.emit(format!("[\"msf\",{}]", id));
+            }
+
+            SpriteTransform(crate::draw::SpriteTransform::Identity) => self.emit("[\"sti\"]".to_string()),
+            SpriteTransform(transform) => {
+                let transform = Transform2D::from(transform);
+                self.emit(format!("[\"stm\",{}]", js_matrix(&transform)));
+            }
+
+            DrawSprite(sprite_id) => {
+                let id = self.sprite_id(sprite_id);
+                self.emit(format!("[\"ds\",{},[]]", id));
+            }
+
+            DrawSpriteWithFilters(sprite_id, filters) => {
+                let id = self.sprite_id(sprite_id);
+                self.emit(format!("[\"ds\",{},{}]", id, filter_list(&filters)));
+            }
+
+            Texture(texture_id, op)     => self.texture_op(texture_id, op),
+            Font(font_id, op)           => self.font_op(font_id, op),
+
+            BeginLineLayout(x, y, alignment) => {
+                // Left and right alignment are relative to the direction of the text, like 'start' and 'end' in CSS
+                let alignment = match alignment {
+                    TextAlignment::Left     => "start",
+                    TextAlignment::Right    => "end",
+                    TextAlignment::Center   => "center",
+                };
+
+                self.emit(format!("[\"ll\",{},{},\"{}\"]", js_number(x), js_number(y), alignment));
+            }
+
+            // Canvas2D can't wrap text, so paragraphs are replayed as a single line aligned within the paragraph width
+            BeginParagraphLayout(x, y, style) => {
+                let alignment = match style.alignment {
+                    ParagraphAlignment::Left    |
+                    ParagraphAlignment::Justify => "start",
+                    ParagraphAlignment::Center  => "center",
+                    ParagraphAlignment::Right   => "end",
+                };
+
+                self.emit(format!("[\"ll\",{},{},\"{}\",{}]", js_number(x), js_number(y), alignment, js_number(style.width)));
+            }
+
+            DrawLaidOutText             => self.emit("[\"dlt\"]".to_string()),
+
+            DrawText(font_id, text, x, y) => {
+                let id = self.font_id(font_id);
+                self.emit(format!("[\"dt\",{},{},{},{}]", id, js_string(&text), js_number(x), js_number(y)));
+            }
+
+            Gradient(gradient_id, GradientOp::Create(color)) => {
+                let id = self.gradient_id(gradient_id);
+                self.emit(format!("[\"gc\",{},{}]", id, js_color(&color)));
+            }
+
+            Gradient(gradient_id, GradientOp::AddStop(pos, color)) => {
+                let id = self.gradient_id(gradient_id);
+                self.emit(format!("[\"gs\",{},{},{}]", id, js_number(pos), js_color(&color)));
+            }
+
+            // Canvas2D gradients always pad
+            Gradient(_, GradientOp::Spread(_)) => { }
+
+            Namespace(namespace) => {
+                self.namespace = namespace;
+            }
+        }
+    }
+}
+
+impl GraphicsContext for HtmlWriter {
+    #[inline]
+    fn draw(&mut self, drawing: Draw) {
+        self.process(drawing);
+    }
+}
+
+///
+/// Converts a stream of drawing instructions to a HTML document that replays them on a `<canvas>` element
+///
+/// When the `outline-fonts` feature is enabled, text is laid out (using any fallback fonts and OpenType settings) and converted
+/// to paths. Otherwise, text is drawn by the browser using `fillText()` and glyphs are left out of the document.
+///
+pub fn drawing_to_html<InStream: 'static+Send+Unpin+Stream<Item=Draw>>(draw_stream: InStream, options: HtmlOptions) -> impl Send+Future<Output=String> {
+    async move {
+        let mut writer      = HtmlWriter::with_options(&options);
+
+        #[cfg(feature = "outline-fonts")]
+        let mut draw_stream = drawing_with_text_as_paths(drawing_with_laid_out_text(draw_stream));
+        #[cfg(not(feature = "outline-fonts"))]
+        let mut draw_stream = draw_stream;
+
+        while let Some(drawing) = draw_stream.next().await {
+            writer.draw(drawing);
+        }
+
+        writer.document()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::color::*;
+    use crate::primitives::*;
+
+    use futures::stream;
+    use futures::executor;
+
+    fn writer_for(drawing: Vec<Draw>) -> HtmlWriter {
+        let mut writer = HtmlWriter::new(200.0, 100.0);
+
+        for draw in drawing {
+            writer.draw(draw);
+        }
+
+        writer
+    }
+
+    #[test]
+    fn empty_document() {
+        let html = writer_for(vec![]).document();
+
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html.contains("<canvas id=\"flo_canvas\" width=\"200\" height=\"100\"></canvas>"));
+        assert!(html.contains("function flo_replay("));
+        assert!(html.contains("flo_replay(document.getElementById(\"flo_canvas\"), 200, 100, [], []);"));
+    }
+
+    #[test]
+    fn fill_rectangle() {
+        let mut drawing = vec![];
+        drawing.canvas_height(100.0);
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 20.0);
+        drawing.fill_color(Color::Rgba(1.0, 0.0, 0.0, 0.5));
+        drawing.winding_rule(WindingRule::EvenOdd);
+        drawing.fill();
+
+        let html = writer_for(drawing).document();
+
+        assert!(html.contains("[\n[\"ch\",100],\n[\"np\"],\n[\"m\",0,0],\n[\"l\",0,20],\n[\"l\",10,20],\n[\"l\",10,0],\n[\"l\",0,0],\n[\"z\"],\n[\"fc\",\"rgba(255,0,0,0.5)\"],\n[\"wr\",\"evenodd\"],\n[\"f\"]\n]"), "{}", html);
+    }
+
+    #[test]
+    fn layers_and_sprites() {
+        let mut drawing = vec![];
+        drawing.sprite(SpriteId(3));
+        drawing.new_path();
+        drawing.circle(0.0, 0.0, 1.0);
+        drawing.fill();
+        drawing.layer(LayerId(1));
+        drawing.layer_alpha(LayerId(1), 0.5);
+        drawing.layer_blend(LayerId(1), BlendMode::Multiply);
+        drawing.sprite_transform(SpriteTransform::Translate(2.0, 3.0));
+        drawing.draw_sprite(SpriteId(3));
+        drawing.draw_sprite_with_filters(SpriteId(3), vec![TextureFilter::GaussianBlur(4.0), TextureFilter::Mask(TextureId(1))]);
+
+        let html = writer_for(drawing).document();
+
+        assert!(html.contains("[\"spr\",0]"));
+        assert!(html.contains("[\"ly\",1]"));
+        assert!(html.contains("[\"la\",1,0.5]"));
+        assert!(html.contains("[\"lb\",1,\"multiply\"]"));
+        assert!(html.contains("[\"stm\",1,0,0,1,2,3]"));
+        assert!(html.contains("[\"ds\",0,[]]"));
+        assert!(html.contains("[\"ds\",0,[[\"blur\",4]]]"));
+    }
+
+    #[test]
+    fn namespaces_have_separate_ids() {
+        let namespace = NamespaceId::new();
+
+        let mut drawing = vec![];
+        drawing.sprite(SpriteId(1));
+        drawing.push(Draw::Namespace(namespace));
+        drawing.sprite(SpriteId(1));
+        drawing.push(Draw::Namespace(NamespaceId::default()));
+        drawing.sprite(SpriteId(1));
+
+        let html = writer_for(drawing).document();
+
+        assert!(html.contains("[\"spr\",0],\n[\"spr\",1],\n[\"spr\",0]"), "{}", html);
+    }
+
+    #[test]
+    fn texture_is_data_uri() {
+        let mut drawing = vec![];
+        drawing.create_texture(TextureId(5), 2, 2, TextureFormat::Rgba);
+        drawing.set_texture_bytes(TextureId(5), 0, 0, 2, 2, Arc::new(vec![255; 16]));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill_texture(TextureId(5), 0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+
+        let html = writer_for(drawing).document();
+
+        assert!(html.contains("[\"tc\",0,2,2]"));
+        assert!(html.contains("[\"tb\",0,0,0,2,2,\"data:image/png;base64,"));
+        assert!(html.contains("[\"fx\",0,0,0,1,1]"));
+    }
+
+    #[test]
+    fn font_is_embedded_once() {
+        let font_data   = include_bytes!("../../test_data/Lato-Regular.ttf");
+        let font        = CanvasFontFace::from_slice(font_data);
+
+        let mut drawing = vec![];
+        drawing.define_font_data(FontId(1), Arc::clone(&font));
+        drawing.define_font_data(FontId(2), Arc::clone(&font));
+        drawing.set_font_size(FontId(2), 16.0);
+        drawing.draw_text(FontId(2), "</script>".to_string(), 10.0, 20.0);
+
+        let html = writer_for(drawing).document();
+
+        assert!(html.contains(&format!(", 200, 100, [\"{}\"], [", base64(font_data))));
+        assert!(html.contains("[\"fd\",0,0]"));
+        assert!(html.contains("[\"fd\",1,0]"));
+        assert!(html.contains("[\"fsz\",1,16]"));
+        assert!(html.contains("[\"dt\",1,\"\\u003c/script>\",10,20]"));
+        assert!(!html.contains("\"</script>\""));
+    }
+
+    #[test]
+    fn combined_path_is_written_as_path() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 10.0);
+        drawing.store_path();
+        drawing.new_path();
+        drawing.rect(5.0, 5.0, 15.0, 15.0);
+        drawing.combine_path(PathArithmetic::Union);
+        drawing.fill();
+
+        let html    = writer_for(drawing).document();
+        let last_np = html.rfind("[\"np\"]").unwrap();
+
+        assert!(html[last_np..].contains("[\"m\","));
+        assert!(html[last_np..].contains("[\"f\"]"));
+    }
+
+    #[test]
+    fn clear_canvas_removes_earlier_drawing() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.fill();
+        drawing.clear_canvas(Color::Rgba(1.0, 1.0, 1.0, 1.0));
+
+        let html = writer_for(drawing).document();
+
+        assert!(html.contains("[\n[\"cc\",\"rgba(255,255,255,1)\"]\n]"), "{}", html);
+    }
+
+    #[test]
+    fn text_alignment_is_relative_to_direction() {
+        let mut drawing = vec![];
+        drawing.begin_line_layout(10.0, 20.0, TextAlignment::Left);
+        drawing.begin_line_layout(10.0, 20.0, TextAlignment::Right);
+        drawing.begin_paragraph_layout(10.0, 20.0, ParagraphStyle::new(100.0).with_alignment(ParagraphAlignment::Right));
+
+        let html = writer_for(drawing).document();
+
+        assert!(html.contains("[\"ll\",10,20,\"start\"]"), "{}", html);
+        assert!(html.contains("[\"ll\",10,20,\"end\"]"), "{}", html);
+        assert!(html.contains("[\"ll\",10,20,\"end\",100]"), "{}", html);
+    }
+
+    #[test]
+    fn title_is_escaped() {
+        let html = HtmlWriter::with_options(&HtmlOptions::new(10.0, 10.0).with_title("A & B")).document();
+
+        assert!(html.contains("<title>A &amp; B</title>"));
+    }
+
+    #[test]
+    fn html_from_stream() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.circle(0.0, 0.0, 0.5);
+        drawing.stroke();
+
+        let html = executor::block_on(drawing_to_html(stream::iter(drawing), HtmlOptions::new(200.0, 100.0)));
+
+        assert!(html.contains("[\"s\"]"));
+        assert!(html.contains("width=\"200\" height=\"100\""));
+    }
+
+    #[cfg(feature = "outline-fonts")]
+    #[test]
+    fn text_from_stream_is_laid_out_as_paths() {
+        let font = CanvasFontFace::from_slice(include_bytes!("../../test_data/Lato-Regular.ttf"));
+
+        let mut drawing = vec![];
+        drawing.define_font_data(FontId(1), font);
+        drawing.set_font_size(FontId(1), 16.0);
+        drawing.draw_text(FontId(1), "Text".to_string(), 10.0, 20.0);
+
+        let html = executor::block_on(drawing_to_html(stream::iter(drawing), HtmlOptions::new(200.0, 100.0)));
+
+        assert!(!html.contains("[\"dt\","), "{}", html);
+        assert!(html.contains("[\"f\"]"), "{}", html);
+    }
+
+    #[cfg(feature = "outline-fonts")]
+    #[test]
+    fn paragraphs_from_stream_are_wrapped() {
+        let font = CanvasFontFace::from_slice(include_bytes!("../../test_data/Lato-Regular.ttf"));
+
+        let mut drawing = vec![];
+        drawing.define_font_data(FontId(1), font);
+        drawing.set_font_size(FontId(1), 16.0);
+        drawing.begin_paragraph_layout(10.0, 90.0, ParagraphStyle::new(60.0));
+        drawing.layout_text(FontId(1), "The quick brown fox jumps over the lazy dog".to_string());
+        drawing.draw_text_layout();
+
+        let html = executor::block_on(drawing_to_html(stream::iter(drawing), HtmlOptions::new(200.0, 100.0)));
+
+        // The paragraph is laid out as glyphs rather than being replayed as a single line of text
+        assert!(!html.contains("[\"ll\","), "{}", html);
+        assert!(!html.contains("[\"dlt\"]"), "{}", html);
+        assert!(html.contains("[\"f\"]"), "{}", html);
+    }
+
+    #[test]
+    fn replay_swaps_layers_in_either_order() {
+        use std::process::{Command};
+
+        // The replay script needs a browser to draw anything, but the layer ordering can be checked with node (skipped if it isn't installed)
+        let script = format!("{}
+            const forward = [1, 2, 3]; flo_swap_layers(forward, 1, 3);
+            const reverse = [1, 2, 3]; flo_swap_layers(reverse, 3, 1);
+            console.log(JSON.stringify([forward, reverse]));", REPLAY_SCRIPT);
+
+        let output = match Command::new("node").arg("-e").arg(script).output() {
+            Ok(output)  => output,
+            Err(_)      => { return; }
+        };
+
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert!(String::from_utf8_lossy(&output.stdout).trim() == "[[3,2,1],[3,2,1]]", "{}", String::from_utf8_lossy(&output.stdout));
+    }
+}
@@ -0,0 +1,12 @@
+//!
+//! # HTML export
+//!
+//! `HtmlWriter` turns a stream of `Draw` instructions into a self-contained HTML document, which replays the drawing on a
+//! `<canvas>` element using the Canvas2D API, and `drawing_to_html()` does the same for a stream of instructions. Layers
+//! are drawn on offscreen canvases, sprites are cached as paths, and textures and fonts are embedded as data.
+//!
+
+mod html_format;
+mod html_writer;
+
+pub use self::html_writer::*;
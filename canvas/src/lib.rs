@@ -7,7 +7,10 @@
 //! The main features that this library supports are the set of primitives in the `Draw` enum, the
 //! `Canvas` type for streaming drawing instructions elsewhere, and the encoding and decoding
 //! functions that can be used to send canvas instructions over a byte stream. Encoding uses MIME64
-//! characters, so it's easy to embed encoded canvases in other protocols.
+//! characters, so it's easy to embed encoded canvases in other protocols. There's also a more compact
+//! binary encoding (`BinaryCanvasEncoder` and `BinaryCanvasDecoder`), which is better suited to
+//! recording drawings to files. `CanvasFileWriter` and `CanvasFileReader` add a versioned header
+//! to either encoding so recordings can be checked for compatibility when they are read back.
 //!
 //! By itself, `flo_canvas` is an excellent way to describe how a 2D scene should be rendered without
 //! needing to depend on a system-specific library.
@@ -32,6 +35,15 @@
 //!   with an outline rendering of the font (useful for rendering back-ends that don't have native
 //!   font support or for generating vector files that don't require particular fonts to be installed)
 //!
+//! * `svg` - provides `svg::SvgWriter` and `svg::drawing_to_svg()`, which write a drawing out as an SVG
+//!   document, and `svg::drawing_from_svg()`, which converts an SVG document to Draw instructions
+//!
+//! * `pdf` - provides `pdf::PdfWriter` and `pdf::drawing_to_pdf()`, which write a drawing out as a PDF
+//!   document with a page for each frame (this also enables `outline-fonts`, which is used to embed fonts)
+//!
+//! * `html` - provides `html::HtmlWriter` and `html::drawing_to_html()`, which write a drawing out as a HTML
+//!   document that replays it on a `<canvas>` element using the Canvas2D API
+//!
 #![warn(bare_trait_objects)]
 
 #[macro_use]
@@ -51,6 +63,9 @@ mod context;
 mod texture;
 mod encoding;
 mod decoding;
+mod binary_encoding;
+mod binary_decoding;
+mod canvas_file;
 mod gradient;
 mod namespace;
 mod font_face;
@@ -62,7 +77,12 @@ mod drawing_target;
 mod conversion_streams;
 
 #[cfg(feature = "outline-fonts")] mod font_line_layout;
+#[cfg(feature = "outline-fonts")] mod font_paragraph_layout;
+#[cfg(any(feature = "svg", feature = "html"))] mod data_uri;
 #[cfg(feature = "scenery")] pub mod scenery;
+#[cfg(feature = "svg")] pub mod svg;
+#[cfg(feature = "pdf")] pub mod pdf;
+#[cfg(feature = "html")] pub mod html;
 
 pub use self::draw::*;
 pub use self::path::*;
@@ -74,6 +94,9 @@ pub use self::context::*;
 pub use self::texture::*;
 pub use self::encoding::*;
 pub use self::decoding::*;
+pub use self::binary_encoding::*;
+pub use self::binary_decoding::*;
+pub use self::canvas_file::*;
 pub use self::gradient::*;
 pub use self::namespace::*;
 pub use self::font_face::*;
@@ -84,6 +107,7 @@ pub use self::drawing_target::*;
 pub use self::conversion_streams::*;
 
 #[cfg(feature = "outline-fonts")] pub use self::font_line_layout::*;
+#[cfg(feature = "outline-fonts")] pub use self::font_paragraph_layout::*;
 
 pub use flo_curves as curves;
 pub use flo_curves::geo::{Coordinate2D, Coord2};
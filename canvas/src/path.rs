@@ -17,6 +17,15 @@ pub enum PathOp {
     /// Bezier curve to point
     BezierCurve(((f32, f32), (f32, f32)), (f32, f32)),
 
+    /// Quadratic curve to point (control point, end point)
+    QuadraticCurve((f32, f32), (f32, f32)),
+
+    /// Elliptical arc (center, radii, rotation, start angle, sweep angle)
+    ///
+    /// Angles are in radians. A line is drawn from the current point to the start of the arc, or the arc starts a new
+    /// subpath if there's no current point.
+    Arc((f32, f32), (f32, f32), f32, f32, f32),
+
     /// Closes the current subpath
     ClosePath,
 }
@@ -27,3 +36,117 @@ impl Into<Draw> for PathOp {
         Draw::Path(self)
     }
 }
+
+///
+/// Returns the control points of the bezier curve that is equivalent to a quadratic curve
+///
+pub fn quadratic_to_bezier((x1, y1): (f32, f32), (cpx, cpy): (f32, f32), (x2, y2): (f32, f32)) -> ((f32, f32), (f32, f32)) {
+    let cp1 = (x1 + (cpx-x1) * (2.0/3.0), y1 + (cpy-y1) * (2.0/3.0));
+    let cp2 = (x2 + (cpx-x2) * (2.0/3.0), y2 + (cpy-y2) * (2.0/3.0));
+
+    (cp1, cp2)
+}
+
+///
+/// Returns the point at the specified angle on an elliptical arc
+///
+pub fn arc_point((cx, cy): (f32, f32), (rx, ry): (f32, f32), rotation: f32, angle: f32) -> (f32, f32) {
+    let (sin_rot, cos_rot)  = rotation.sin_cos();
+    let (sin_a, cos_a)      = angle.sin_cos();
+
+    let x = rx * cos_a;
+    let y = ry * sin_a;
+
+    (cx + x*cos_rot - y*sin_rot, cy + x*sin_rot + y*cos_rot)
+}
+
+///
+/// Converts an elliptical arc into a series of bezier curves, as `(cp1, cp2, end_point)`
+///
+/// The first curve starts at `arc_point(center, radii, rotation, start_angle)`. Each curve covers at most a quarter turn.
+///
+pub fn arc_to_bezier_curves(center: (f32, f32), radii: (f32, f32), rotation: f32, start_angle: f32, sweep_angle: f32) -> Vec<((f32, f32), (f32, f32), (f32, f32))> {
+    if sweep_angle == 0.0 || !sweep_angle.is_finite() {
+        return vec![];
+    }
+
+    // Split into quarter turns or smaller
+    let num_curves  = (sweep_angle.abs() / (std::f32::consts::PI / 2.0)).ceil().max(1.0) as usize;
+    let curve_sweep = sweep_angle / (num_curves as f32);
+
+    // Length of the control point tangents for a unit circle
+    let k           = (4.0/3.0) * (curve_sweep / 4.0).tan();
+
+    // Work out the points on a unit circle, then map them onto the ellipse
+    let (cx, cy)                = center;
+    let (rx, ry)                = radii;
+    let (sin_rot, cos_rot)      = rotation.sin_cos();
+    let to_ellipse              = |(x, y): (f32, f32)| {
+        let x = x * rx;
+        let y = y * ry;
+
+        (cx + x*cos_rot - y*sin_rot, cy + x*sin_rot + y*cos_rot)
+    };
+
+    (0..num_curves)
+        .map(|idx| {
+            let a1              = start_angle + curve_sweep * (idx as f32);
+            let a2              = a1 + curve_sweep;
+
+            let (sin_a1, cos_a1) = a1.sin_cos();
+            let (sin_a2, cos_a2) = a2.sin_cos();
+
+            let cp1             = (cos_a1 - k*sin_a1, sin_a1 + k*cos_a1);
+            let cp2             = (cos_a2 + k*sin_a2, sin_a2 - k*cos_a2);
+            let end_point       = (cos_a2, sin_a2);
+
+            (to_ellipse(cp1), to_ellipse(cp2), to_ellipse(end_point))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quadratic_control_points() {
+        let (cp1, cp2) = quadratic_to_bezier((0.0, 0.0), (30.0, 60.0), (60.0, 0.0));
+
+        assert!((cp1.0-20.0).abs() < 0.001 && (cp1.1-40.0).abs() < 0.001);
+        assert!((cp2.0-40.0).abs() < 0.001 && (cp2.1-40.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn full_circle_arc_is_four_curves() {
+        let curves = arc_to_bezier_curves((0.0, 0.0), (10.0, 10.0), 0.0, 0.0, std::f32::consts::PI * 2.0);
+
+        assert!(curves.len() == 4);
+
+        let (_, _, end_point) = curves[3];
+        assert!((end_point.0-10.0).abs() < 0.001 && end_point.1.abs() < 0.001);
+    }
+
+    #[test]
+    fn arc_curves_stay_on_ellipse() {
+        let curves = arc_to_bezier_curves((5.0, 5.0), (20.0, 10.0), 0.3, 0.2, 2.0);
+
+        // Each end point should be on the ellipse
+        for (idx, (_, _, end_point)) in curves.iter().enumerate() {
+            let angle       = 0.2 + 2.0 * ((idx+1) as f32) / (curves.len() as f32);
+            let expected    = arc_point((5.0, 5.0), (20.0, 10.0), 0.3, angle);
+
+            assert!((end_point.0-expected.0).abs() < 0.001 && (end_point.1-expected.1).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn negative_sweep_arc() {
+        let curves = arc_to_bezier_curves((0.0, 0.0), (10.0, 10.0), 0.0, 0.0, -std::f32::consts::PI / 2.0);
+
+        assert!(curves.len() == 1);
+
+        let (_, _, end_point) = curves[0];
+        assert!(end_point.0.abs() < 0.001 && (end_point.1+10.0).abs() < 0.001);
+    }
+}
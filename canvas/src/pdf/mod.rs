@@ -0,0 +1,13 @@
+//!
+//! # PDF export
+//!
+//! `PdfWriter` turns a stream of `Draw` instructions into a PDF document, with a page for every frame that's shown (a
+//! `ShowFrame` outside of any frame acts as a page break), and `drawing_to_pdf()` does the same for a stream of instructions,
+//! laying out any text and converting dashed lines first.
+//! Paths stay as vectors, text is written using the fonts it was drawn with, and textures are embedded as images.
+//!
+
+mod pdf_format;
+mod pdf_writer;
+
+pub use self::pdf_writer::*;
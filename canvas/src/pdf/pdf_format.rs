@@ -0,0 +1,330 @@
+use crate::draw::*;
+use crate::path::*;
+use crate::color::*;
+use crate::transform2d::*;
+
+use std::fmt::Write;
+
+///
+/// Formats a number for use in a PDF content stream (PDF doesn't allow exponents, and rounds to 4 decimal places, which is well below the resolution of any printer)
+///
+pub (super) fn pdf_number(val: f32) -> String {
+    if !val.is_finite() {
+        return "0".to_string();
+    }
+
+    let rounded = (val * 10000.0).round() / 10000.0;
+
+    if rounded == 0.0 {
+        "0".to_string()
+    } else {
+        format!("{}", rounded)
+    }
+}
+
+///
+/// Formats a matrix entry for a PDF content stream
+///
+/// Matrix entries can scale other values by a large amount (eg, the transform set up by `CanvasHeight` can be very small), so
+/// these are written with the full precision of an `f32` rather than rounded like other numbers.
+///
+pub (super) fn pdf_matrix_number(val: f32) -> String {
+    if !val.is_finite() || val == 0.0 {
+        "0".to_string()
+    } else {
+        format!("{}", val)
+    }
+}
+
+///
+/// Returns the red, green and blue components of a colour (clamped to the range 0-1) as a string, along with its alpha value
+///
+pub (super) fn pdf_color(color: &Color) -> (String, f32) {
+    let (r, g, b, a)    = color.to_rgba_components();
+    let component       = |val: f32| pdf_number(val.max(0.0).min(1.0));
+
+    (format!("{} {} {}", component(r), component(g), component(b)), a.max(0.0).min(1.0))
+}
+
+///
+/// Formats a transform as the six numbers of a PDF matrix
+///
+pub (super) fn pdf_matrix(transform: &Transform2D) -> String {
+    let Transform2D(a) = transform;
+
+    format!("{} {} {} {} {} {}", pdf_matrix_number(a[0][0]), pdf_matrix_number(a[1][0]), pdf_matrix_number(a[0][1]), pdf_matrix_number(a[1][1]), pdf_matrix_number(a[0][2]), pdf_matrix_number(a[1][2]))
+}
+
+///
+/// Returns the content stream operator that multiplies the current transformation matrix by a transform (or an empty string for the identity transform)
+///
+pub (super) fn pdf_transform(transform: &Transform2D) -> String {
+    if transform == &Transform2D::identity() {
+        String::new()
+    } else {
+        format!("{} cm\n", pdf_matrix(transform))
+    }
+}
+
+///
+/// Returns the amount a transform scales lengths by (used for line widths set in pixels)
+///
+pub (super) fn transform_scale(transform: &Transform2D) -> f32 {
+    let Transform2D(a) = transform;
+
+    (a[0][0]*a[1][1] - a[0][1]*a[1][0]).abs().sqrt()
+}
+
+///
+/// Converts a path to the operators that define it in a PDF content stream, applying a transform to its coordinates
+///
+pub (super) fn pdf_path_data(path: &[PathOp], transform: &Transform2D) -> String {
+    let mut data        = String::new();
+    let mut last_point  = None;
+    let mut start_point = None;
+    let point           = |x: f32, y: f32| { let (x, y) = transform.transform_point(x, y); format!("{} {}", pdf_number(x), pdf_number(y)) };
+
+    // PDF paths always begin with a move, so segments that don't follow a point need to start with one
+    let segment_start = |data: &mut String, last_point: &mut Option<(f32, f32)>, start_point: &mut Option<(f32, f32)>, default_start: (f32, f32)| {
+        if last_point.is_none() {
+            writeln!(data, "{} m", point(default_start.0, default_start.1)).ok();
+
+            *last_point     = Some(default_start);
+            *start_point    = Some(default_start);
+        }
+    };
+
+    for path_op in path.iter() {
+        match path_op {
+            PathOp::NewPath => {
+                data.clear();
+                last_point  = None;
+                start_point = None;
+            }
+
+            PathOp::Move(x, y) => {
+                writeln!(data, "{} m", point(*x, *y)).ok();
+                last_point  = Some((*x, *y));
+                start_point = Some((*x, *y));
+            }
+
+            PathOp::Line(x, y) => {
+                segment_start(&mut data, &mut last_point, &mut start_point, (*x, *y));
+                writeln!(data, "{} l", point(*x, *y)).ok();
+                last_point = Some((*x, *y));
+            }
+
+            PathOp::BezierCurve(((cp1x, cp1y), (cp2x, cp2y)), (x, y)) => {
+                segment_start(&mut data, &mut last_point, &mut start_point, (*x, *y));
+                writeln!(data, "{} {} {} c", point(*cp1x, *cp1y), point(*cp2x, *cp2y), point(*x, *y)).ok();
+                last_point = Some((*x, *y));
+            }
+
+            PathOp::QuadraticCurve((cpx, cpy), (x, y)) => {
+                // PDF only has cubic curves
+                segment_start(&mut data, &mut last_point, &mut start_point, (*x, *y));
+
+                let (start_x, start_y)  = last_point.unwrap_or((*x, *y));
+                let cp1                 = (start_x + (cpx - start_x) * 2.0/3.0, start_y + (cpy - start_y) * 2.0/3.0);
+                let cp2                 = (x + (cpx - x) * 2.0/3.0, y + (cpy - y) * 2.0/3.0);
+
+                writeln!(data, "{} {} {} c", point(cp1.0, cp1.1), point(cp2.0, cp2.1), point(*x, *y)).ok();
+                last_point = Some((*x, *y));
+            }
+
+            PathOp::Arc(center, radii, rotation, start_angle, sweep_angle) => {
+                // Arcs are written as bezier curves, joined to the existing path by a line
+                let (start_x, start_y) = arc_point(*center, *radii, *rotation, *start_angle);
+
+                if last_point.is_none() {
+                    segment_start(&mut data, &mut last_point, &mut start_point, (start_x, start_y));
+                } else if last_point != Some((start_x, start_y)) {
+                    writeln!(data, "{} l", point(start_x, start_y)).ok();
+                }
+
+                last_point = Some((start_x, start_y));
+
+                for ((cp1x, cp1y), (cp2x, cp2y), (x, y)) in arc_to_bezier_curves(*center, *radii, *rotation, *start_angle, *sweep_angle) {
+                    writeln!(data, "{} {} {} c", point(cp1x, cp1y), point(cp2x, cp2y), point(x, y)).ok();
+                    last_point = Some((x, y));
+                }
+            }
+
+            PathOp::ClosePath => {
+                if last_point.is_some() {
+                    data.push_str("h\n");
+                    last_point = start_point;
+                }
+            }
+        }
+    }
+
+    data
+}
+
+///
+/// Returns the PDF name for a blend mode (or None for the default blend mode or blend modes that can't be represented in PDF)
+///
+pub (super) fn pdf_blend_mode(blend_mode: BlendMode) -> Option<&'static str> {
+    use self::BlendMode::*;
+
+    match blend_mode {
+        SourceOver      |
+        SourceIn        |
+        SourceOut       |
+        DestinationOver |
+        DestinationIn   |
+        DestinationOut  |
+        SourceAtop      |
+        DestinationAtop => None,
+
+        Multiply        => Some("Multiply"),
+        Screen          => Some("Screen"),
+        Darken          => Some("Darken"),
+        Lighten         => Some("Lighten"),
+        Overlay         => Some("Overlay"),
+        ColorDodge      => Some("ColorDodge"),
+        ColorBurn       => Some("ColorBurn"),
+        HardLight       => Some("HardLight"),
+        SoftLight       => Some("SoftLight"),
+        Difference      => Some("Difference"),
+        Exclusion       => Some("Exclusion"),
+        Hue             => Some("Hue"),
+        Saturation      => Some("Saturation"),
+        Color           => Some("Color"),
+        Luminosity      => Some("Luminosity"),
+    }
+}
+
+///
+/// Returns the function dictionary that interpolates between the stops of a gradient
+///
+/// A gradient with no stops is drawn as if it had a single transparent black stop.
+///
+pub (super) fn pdf_gradient_function(stops: &[(f32, Color)]) -> String {
+    // Stops must be in order, and cover the whole range from 0 to 1
+    let mut stops = stops.to_vec();
+    if stops.is_empty() { stops.push((0.0, Color::Rgba(0.0, 0.0, 0.0, 0.0))); }
+    stops.sort_by(|(pos1, _), (pos2, _)| pos1.partial_cmp(pos2).unwrap_or(std::cmp::Ordering::Equal));
+    stops.iter_mut().for_each(|(pos, _)| *pos = pos.max(0.0).min(1.0));
+
+    if let Some((first_pos, first_color)) = stops.first().copied() {
+        if first_pos > 0.0 { stops.insert(0, (0.0, first_color)); }
+    }
+    if let Some((last_pos, last_color)) = stops.last().copied() {
+        if last_pos < 1.0 { stops.push((1.0, last_color)); }
+    }
+
+    // Each pair of stops is joined by a linear interpolation
+    let segments = stops.windows(2)
+        .map(|stops| format!("<< /FunctionType 2 /Domain [0 1] /C0 [{}] /C1 [{}] /N 1 >>", pdf_color(&stops[0].1).0, pdf_color(&stops[1].1).0))
+        .collect::<Vec<_>>();
+
+    if segments.len() == 1 {
+        segments[0].clone()
+    } else {
+        let bounds = stops[1..(stops.len()-1)].iter().map(|(pos, _)| pdf_number(*pos)).collect::<Vec<_>>();
+        let encode = segments.iter().map(|_| "0 1").collect::<Vec<_>>();
+
+        format!("<< /FunctionType 3 /Domain [0 1] /Functions [{}] /Bounds [{}] /Encode [{}] >>", segments.join(" "), bounds.join(" "), encode.join(" "))
+    }
+}
+
+///
+/// Compresses data for a stream with the `FlateDecode` filter
+///
+pub (super) fn pdf_compress(data: &[u8]) -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec_zlib(data, 6)
+}
+
+///
+/// Returns a compressed stream object, with the specified entries in its dictionary
+///
+pub (super) fn pdf_stream(dictionary: &str, data: &[u8]) -> Vec<u8> {
+    let compressed  = pdf_compress(data);
+    let mut stream  = if dictionary.is_empty() {
+        format!("<< /Filter /FlateDecode /Length {} >>\nstream\n", compressed.len())
+    } else {
+        format!("<< {} /Filter /FlateDecode /Length {} >>\nstream\n", dictionary, compressed.len())
+    }.into_bytes();
+
+    stream.extend(compressed);
+    stream.extend(b"\nendstream");
+
+    stream
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn format_numbers() {
+        assert!(pdf_number(0.0) == "0");
+        assert!(pdf_number(-0.00001) == "0");
+        assert!(pdf_number(1.5) == "1.5");
+        assert!(pdf_number(1.0/3.0) == "0.3333");
+        assert!(pdf_number(0.0000001) == "0");
+        assert!(pdf_number(f32::INFINITY) == "0");
+    }
+
+    #[test]
+    fn format_color() {
+        assert!(pdf_color(&Color::Rgba(1.0, 0.0, 0.5, 0.25)) == ("1 0 0.5".to_string(), 0.25));
+    }
+
+    #[test]
+    fn format_path() {
+        let path = vec![
+            PathOp::Move(0.0, 0.0),
+            PathOp::Line(10.0, 0.0),
+            PathOp::BezierCurve(((10.0, 5.0), (5.0, 10.0)), (0.0, 10.0)),
+            PathOp::ClosePath,
+        ];
+
+        assert!(pdf_path_data(&path, &Transform2D::identity()) == "0 0 m\n10 0 l\n10 5 5 10 0 10 c\nh\n");
+        assert!(pdf_path_data(&path[0..2], &Transform2D::scale(2.0, 3.0)) == "0 0 m\n20 0 l\n");
+    }
+
+    #[test]
+    fn format_transform() {
+        assert!(pdf_transform(&Transform2D::identity()) == "");
+        assert!(pdf_transform(&Transform2D::translate(10.0, 20.0)) == "1 0 0 1 10 20 cm\n");
+    }
+
+    #[test]
+    fn matrix_keeps_precision() {
+        // The transforms set up by CanvasHeight are too small to round to 4 decimal places
+        for height in [768.0f32, 50000.0f32].iter() {
+            let scale   = 2.0 / height;
+            let matrix  = pdf_matrix(&Transform2D::scale(scale, scale));
+            let values  = matrix.split(' ').map(|val| val.parse::<f32>().unwrap()).collect::<Vec<_>>();
+
+            assert!(values == vec![scale, 0.0, 0.0, scale, 0.0, 0.0], "{}", matrix);
+            assert!(!matrix.contains('e'), "{}", matrix);
+        }
+    }
+
+    #[test]
+    fn two_stop_gradient() {
+        let function = pdf_gradient_function(&[(0.0, Color::Rgba(1.0, 0.0, 0.0, 1.0)), (1.0, Color::Rgba(0.0, 0.0, 1.0, 1.0))]);
+
+        assert!(function == "<< /FunctionType 2 /Domain [0 1] /C0 [1 0 0] /C1 [0 0 1] /N 1 >>");
+    }
+
+    #[test]
+    fn gradient_without_stops() {
+        let function = pdf_gradient_function(&[]);
+
+        assert!(function == "<< /FunctionType 2 /Domain [0 1] /C0 [0 0 0] /C1 [0 0 0] /N 1 >>");
+    }
+
+    #[test]
+    fn stitched_gradient() {
+        let function = pdf_gradient_function(&[(0.5, Color::Rgba(0.0, 1.0, 0.0, 1.0)), (0.25, Color::Rgba(1.0, 0.0, 0.0, 1.0))]);
+
+        assert!(function.starts_with("<< /FunctionType 3"));
+        assert!(function.contains("/Bounds [0.25 0.5]"));
+        assert!(function.contains("/Encode [0 1 0 1 0 1]"));
+    }
+}
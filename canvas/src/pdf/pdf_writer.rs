@@ -0,0 +1,1599 @@
+use super::pdf_format::*;
+
+use crate::draw::*;
+use crate::path::*;
+use crate::font::*;
+use crate::color::*;
+use crate::sprite::*;
+use crate::context::*;
+use crate::texture::*;
+use crate::gradient::*;
+use crate::namespace::*;
+use crate::font_face::*;
+use crate::transform2d::*;
+
+use crate::conversion_streams::{combine_paths, CanvasTransform, drawing_without_dashed_lines, drawing_with_laid_out_text, drawing_with_text_as_paths, glyph_outline};
+
+use futures::prelude::*;
+
+use std::mem;
+use std::sync::*;
+use std::fmt::Write;
+use std::collections::{HashMap, BTreeSet};
+
+/// The largest number of bytes that a bitmap texture can use (textures that are larger than this are not created)
+const MAX_TEXTURE_BYTES: usize = 16384 * 16384 * 4;
+
+///
+/// How text is written to a PDF document
+///
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PdfText {
+    /// Text is written using the fonts it was drawn with, which are embedded in the document
+    Fonts,
+
+    /// Text is converted to paths (so the document doesn't need to embed any fonts)
+    Outlines,
+}
+
+///
+/// Options for generating a PDF document from a drawing
+///
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PdfOptions {
+    /// The width of each page in points
+    pub width: f32,
+
+    /// The height of each page in points
+    pub height: f32,
+
+    /// How text should be written to the document
+    pub text: PdfText,
+}
+
+impl PdfOptions {
+    ///
+    /// Creates the options for a document with pages of the specified size, with text written using embedded fonts
+    ///
+    pub fn new(width: f32, height: f32) -> PdfOptions {
+        PdfOptions {
+            width:  width,
+            height: height,
+            text:   PdfText::Fonts,
+        }
+    }
+
+    ///
+    /// Changes how text is written to the document
+    ///
+    pub fn with_text(mut self, text: PdfText) -> PdfOptions {
+        self.text = text;
+        self
+    }
+}
+
+///
+/// Where the drawing instructions are currently being sent
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum PdfTarget {
+    Layer(LayerId),
+    Sprite(NamespaceId, SpriteId),
+}
+
+///
+/// How a shape is filled or stroked
+///
+#[derive(Clone, Debug)]
+enum PdfPaint {
+    /// A solid colour
+    Color(Color),
+
+    /// A linear gradient (with the stops it had when it was selected)
+    LinearGradient { stops: Vec<(f32, Color)>, start: (f32, f32), end: (f32, f32) },
+
+    /// A radial gradient
+    RadialGradient { stops: Vec<(f32, Color)>, center: (f32, f32), radius: f32, focus: Option<(f32, f32)> },
+
+    /// A texture, with the content stream that draws it into a 1x1 cell
+    Texture { content: String, lower_left: (f32, f32), upper_right: (f32, f32) },
+}
+
+///
+/// The drawing state for a layer or a sprite
+///
+#[derive(Clone, Debug)]
+struct PdfState {
+    fill:               PdfPaint,
+    fill_transform:     Transform2D,
+    stroke:             PdfPaint,
+    line_width:         f32,
+    line_width_pixels:  bool,
+    line_join:          LineJoin,
+    line_cap:           LineCap,
+    miter_limit:        f32,
+    dash_pattern:       Vec<f32>,
+    dash_offset:        f32,
+    winding_rule:       WindingRule,
+    blend_mode:         BlendMode,
+    sprite_transform:   Transform2D,
+
+    /// The operators that set the clip paths that apply to shapes drawn in this state
+    clip:               Vec<String>,
+}
+
+impl Default for PdfState {
+    fn default() -> PdfState {
+        PdfState {
+            fill:               PdfPaint::Color(Color::Rgba(0.0, 0.0, 0.0, 1.0)),
+            fill_transform:     Transform2D::identity(),
+            stroke:             PdfPaint::Color(Color::Rgba(0.0, 0.0, 0.0, 1.0)),
+            line_width:         1.0,
+            line_width_pixels:  false,
+            line_join:          LineJoin::Round,
+            line_cap:           LineCap::Butt,
+            miter_limit:        4.0,
+            dash_pattern:       vec![],
+            dash_offset:        0.0,
+            winding_rule:       WindingRule::NonZero,
+            blend_mode:         BlendMode::SourceOver,
+            sprite_transform:   Transform2D::identity(),
+            clip:               vec![],
+        }
+    }
+}
+
+///
+/// The elements that make up a sprite
+///
+#[derive(Clone, Debug, Default)]
+struct PdfSprite {
+    /// The content stream fragments for the elements in this sprite
+    elements:   Vec<String>,
+
+    /// The name of the form XObject that contains the current elements (None if the sprite has changed since it was last drawn)
+    form:       Option<String>,
+}
+
+///
+/// The content of a texture
+///
+#[derive(Clone, Debug)]
+enum PdfTextureContent {
+    /// A bitmap texture, and the name of the image XObject that contains the current pixels
+    Bitmap { size: TextureSize, pixels: Vec<u8>, image: Option<String> },
+
+    /// A texture rendered from a sprite (dynamic textures follow the sprite as it changes, otherwise the form is the sprite when the texture was set)
+    Sprite { sprite: (NamespaceId, SpriteId), bounds: SpriteBounds, form: Option<String>, dynamic: bool },
+}
+
+///
+/// A texture defined on the canvas
+///
+#[derive(Clone, Debug)]
+struct PdfTexture {
+    content:    PdfTextureContent,
+    alpha:      f32,
+}
+
+///
+/// A font face embedded in the document, and the glyphs that have been drawn with it
+///
+#[derive(Clone)]
+struct PdfFontFace {
+    font_face:  Arc<CanvasFontFace>,
+    glyphs:     BTreeSet<u16>,
+}
+
+///
+/// The objects that make up a PDF document
+///
+/// Objects are kept when the canvas is cleared, so pages written before a `ClearCanvas` can still refer to them.
+///
+#[derive(Clone, Default)]
+struct PdfObjects {
+    /// The data for each object: object 1 is the resources dictionary, which is generated with the document, so the first object in this list is object 2
+    objects:        Vec<Vec<u8>>,
+    object_numbers: HashMap<Vec<u8>, usize>,
+
+    /// The resources that content streams can use, as the category, name and object number
+    resources:      Vec<(&'static str, String, usize)>,
+    resource_names: HashMap<usize, String>,
+
+    /// The font faces embedded in the document (font `n` is named `Fn` in the resources)
+    font_faces:     Vec<PdfFontFace>,
+}
+
+impl PdfObjects {
+    ///
+    /// Adds an object to the document, returning its object number (identical objects share a number)
+    ///
+    fn add_object(&mut self, object: Vec<u8>) -> usize {
+        if let Some(number) = self.object_numbers.get(&object) {
+            return *number;
+        }
+
+        let number = self.objects.len() + 2;
+        self.object_numbers.insert(object.clone(), number);
+        self.objects.push(object);
+
+        number
+    }
+
+    ///
+    /// Adds an object that content streams can refer to by name, returning the name
+    ///
+    fn add_resource(&mut self, category: &'static str, name_prefix: &str, object: Vec<u8>) -> String {
+        let number = self.add_object(object);
+
+        if let Some(name) = self.resource_names.get(&number) {
+            return name.clone();
+        }
+
+        let name = format!("{}{}", name_prefix, number);
+        self.resources.push((category, name.clone(), number));
+        self.resource_names.insert(number, name.clone());
+
+        name
+    }
+
+    ///
+    /// Returns the operator that sets the alpha values and blend mode used for painting (or an empty string if these are the defaults)
+    ///
+    fn graphics_state(&mut self, fill_alpha: f32, stroke_alpha: f32, blend_mode: Option<&str>) -> String {
+        let mut dictionary = String::new();
+
+        if fill_alpha < 1.0             { write!(dictionary, " /ca {}", pdf_number(fill_alpha.max(0.0))).ok(); }
+        if stroke_alpha < 1.0           { write!(dictionary, " /CA {}", pdf_number(stroke_alpha.max(0.0))).ok(); }
+        if let Some(blend_mode) = blend_mode { write!(dictionary, " /BM /{}", blend_mode).ok(); }
+
+        if dictionary.is_empty() {
+            String::new()
+        } else {
+            let name = self.add_resource("ExtGState", "GS", format!("<< /Type /ExtGState{} >>", dictionary).into_bytes());
+            format!("/{} gs\n", name)
+        }
+    }
+
+    ///
+    /// Adds an image XObject containing a bitmap, returning its name (or None if the image is empty)
+    ///
+    fn add_image(&mut self, TextureSize(width, height): TextureSize, pixels: &[u8]) -> Option<String> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        // PDF images can't have an alpha channel, so transparent images are given a soft mask
+        let rgb     = pixels.chunks(4).flat_map(|pixel| pixel.iter().take(3).copied()).collect::<Vec<_>>();
+        let alpha   = pixels.chunks(4).map(|pixel| pixel.get(3).copied().unwrap_or(255)).collect::<Vec<_>>();
+
+        let soft_mask = if alpha.iter().any(|alpha| *alpha < 255) {
+            let number = self.add_object(pdf_stream(&format!("/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceGray /BitsPerComponent 8", width, height), &alpha));
+            format!(" /SMask {} 0 R", number)
+        } else {
+            String::new()
+        };
+
+        Some(self.add_resource("XObject", "Im", pdf_stream(&format!("/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8{}", width, height, soft_mask), &rgb)))
+    }
+}
+
+///
+/// Generates a PDF document from a stream of drawing instructions
+///
+/// Drawing instructions are sent to the writer using the `GraphicsContext` trait, and `document()` returns the PDF document for
+/// everything drawn so far. Each page is the size passed to `new()` in points, and is laid out the same way as a window of that
+/// size: `IdentityTransform` makes the page 2.0 units high with (0, 0) at the center, and `CanvasHeight` and `CenterRegion`
+/// behave the same way as they do for any other canvas.
+///
+/// A page is added to the document every time a frame is shown (when the `ShowFrame` matching the outermost `StartFrame` is
+/// received) if anything has been drawn since the last page. A `ShowFrame` outside of any frame is a page break, so this is how a
+/// stream of instructions passed to `drawing_to_pdf()` starts a new page. Code that uses the writer directly can also call
+/// `new_page()`, which adds a page even if nothing has changed. Each page contains everything on the canvas at the time it was
+/// added, so use `ClearCanvas` or `ClearLayer` to start a page from scratch.
+///
+/// Paths are written as vectors, sprites become form XObjects, gradients become shadings and textures are embedded as images.
+/// Layer alpha and blend modes are written as transparency groups. Glyphs drawn with `DrawGlyphs` are written as text using the
+/// font they were drawn with, but `DrawText` and text layout instructions are ignored: use `drawing_to_pdf()` to lay out text
+/// before it's written. Blend modes with no PDF equivalent, texture filters, gradient spread modes and gradient stop alpha values
+/// are ignored, conic gradients are filled with the colour of their first stop, and `Restore` ignores any change to the clip path.
+///
+#[derive(Clone)]
+pub struct PdfWriter {
+    /// The size of each page in points
+    width:              f32,
+    height:             f32,
+
+    /// Where the drawing is going and its state
+    namespace:          NamespaceId,
+    target:             PdfTarget,
+    state:              PdfState,
+    target_state:       HashMap<PdfTarget, PdfState>,
+
+    /// The transform from drawing coordinates to canvas coordinates
+    canvas_transform:   CanvasTransform,
+
+    /// The current path and the path saved by `StorePath`
+    current_path:       Vec<PathOp>,
+    stored_path:        Vec<PathOp>,
+
+    /// The states stored by `PushState`
+    state_stack:        Vec<(Transform2D, PdfState, Vec<PathOp>, NamespaceId)>,
+
+    /// The content stream fragments drawn on each layer, and the order the layers are drawn in
+    layers:             HashMap<LayerId, Vec<String>>,
+    layer_order:        Vec<LayerId>,
+    layer_blend:        HashMap<LayerId, BlendMode>,
+    layer_alpha:        HashMap<LayerId, f32>,
+    stored_layers:      HashMap<LayerId, Vec<String>>,
+
+    /// The resources defined on the canvas
+    sprites:            HashMap<(NamespaceId, SpriteId), PdfSprite>,
+    textures:           HashMap<(NamespaceId, TextureId), PdfTexture>,
+    gradients:          HashMap<(NamespaceId, GradientId), Vec<(f32, Color)>>,
+    fonts:              HashMap<(NamespaceId, FontId), usize>,
+
+    /// The colour set by the last `ClearCanvas`
+    background:         Option<Color>,
+
+    /// The number of `StartFrame` instructions that are waiting for a `ShowFrame`
+    frame_count:        usize,
+
+    /// True if the canvas has changed since the last page was added
+    changed:            bool,
+
+    /// The content streams for the pages in the document, and the objects they use
+    pages:              Vec<String>,
+    objects:            PdfObjects,
+}
+
+impl PdfWriter {
+    ///
+    /// Creates a new PDF writer that will write a document with pages of the specified size (in points)
+    ///
+    pub fn new(width: f32, height: f32) -> PdfWriter {
+        PdfWriter {
+            width:              width,
+            height:             height,
+            namespace:          NamespaceId::default(),
+            target:             PdfTarget::Layer(LayerId(0)),
+            state:              PdfState::default(),
+            target_state:       HashMap::new(),
+            canvas_transform:   CanvasTransform::new(),
+            current_path:       vec![],
+            stored_path:        vec![],
+            state_stack:        vec![],
+            layers:             HashMap::new(),
+            layer_order:        vec![LayerId(0)],
+            layer_blend:        HashMap::new(),
+            layer_alpha:        HashMap::new(),
+            stored_layers:      HashMap::new(),
+            sprites:            HashMap::new(),
+            textures:           HashMap::new(),
+            gradients:          HashMap::new(),
+            fonts:              HashMap::new(),
+            background:         None,
+            frame_count:        0,
+            changed:            false,
+            pages:              vec![],
+            objects:            PdfObjects::default(),
+        }
+    }
+
+    ///
+    /// Adds a page containing everything that's currently on the canvas to the document
+    ///
+    pub fn new_page(&mut self) {
+        let mut content = String::new();
+
+        // Background
+        if let Some(background) = &self.background {
+            let (color, alpha) = pdf_color(background);
+
+            if alpha > 0.0 {
+                let state = self.objects.graphics_state(alpha, 1.0, None);
+                writeln!(content, "q\n{}{} rg\n0 0 {} {} re\nf\nQ", state, color, pdf_number(self.width), pdf_number(self.height)).ok();
+            }
+        }
+
+        // The canvas coordinates are 2.0 units high, with (0, 0) at the center of the page
+        let page_transform = pdf_transform(&self.page_transform());
+
+        for layer_id in self.layer_order.iter() {
+            let elements = if let Some(elements) = self.layers.get(layer_id) { elements } else { continue; };
+            if elements.is_empty() { continue; }
+
+            let alpha       = self.layer_alpha.get(layer_id).copied().unwrap_or(1.0).max(0.0).min(1.0);
+            let blend_mode  = self.layer_blend.get(layer_id).and_then(|blend_mode| pdf_blend_mode(*blend_mode));
+
+            if alpha >= 1.0 && blend_mode.is_none() {
+                writeln!(content, "q\n{}{}Q", page_transform, elements.concat()).ok();
+            } else {
+                // The alpha and blend mode apply to the layer as a whole, so it's drawn as a transparency group
+                let group_content   = format!("{}{}", page_transform, elements.concat());
+                let group           = self.objects.add_resource("XObject", "Fm", pdf_stream(&format!("/Type /XObject /Subtype /Form /BBox [0 0 {} {}] /Group << /S /Transparency >> /Resources 1 0 R", pdf_number(self.width), pdf_number(self.height)), group_content.as_bytes()));
+                let state           = self.objects.graphics_state(alpha, alpha, blend_mode);
+
+                writeln!(content, "q\n{}/{} Do\nQ", state, group).ok();
+            }
+        }
+
+        self.pages.push(content);
+        self.changed = false;
+    }
+
+    ///
+    /// Returns the transform from canvas coordinates to page coordinates
+    ///
+    fn page_transform(&self) -> Transform2D {
+        let scale = self.height / 2.0;
+
+        Transform2D([
+            [scale, 0.0,   self.width / 2.0],
+            [0.0,   scale, self.height / 2.0],
+            [0.0,   0.0,   1.0]
+        ])
+    }
+
+    ///
+    /// Returns the transform from canvas coordinates to the coordinates of the content stream that's being drawn
+    ///
+    fn base_transform(&self) -> Transform2D {
+        match self.target {
+            PdfTarget::Layer(_)     => self.page_transform(),
+            PdfTarget::Sprite(_, _) => Transform2D::identity(),
+        }
+    }
+
+    ///
+    /// Adds an element to the layer or sprite that's being drawn (the content is drawn with the current clip path)
+    ///
+    fn add_element(&mut self, content: String) {
+        let element = format!("q\n{}{}Q\n", self.state.clip.concat(), content);
+
+        match self.target {
+            PdfTarget::Layer(layer_id)                  => { self.layers.entry(layer_id).or_default().push(element); }
+            PdfTarget::Sprite(namespace_id, sprite_id)  => {
+                let sprite = self.sprites.entry((namespace_id, sprite_id)).or_default();
+
+                sprite.elements.push(element);
+                sprite.form = None;
+            }
+        }
+
+        self.changed = true;
+    }
+
+    ///
+    /// Removes everything from the layer or sprite that's being drawn
+    ///
+    fn clear_target(&mut self) {
+        match self.target {
+            PdfTarget::Layer(layer_id)                  => { self.layers.remove(&layer_id); }
+            PdfTarget::Sprite(namespace_id, sprite_id)  => { self.sprites.remove(&(namespace_id, sprite_id)); }
+        }
+
+        self.changed = true;
+    }
+
+    ///
+    /// Adds a layer to the layer order if it's not already there (layers are ordered by ID unless they've been swapped)
+    ///
+    fn add_layer(&mut self, layer_id: LayerId) {
+        if !self.layer_order.contains(&layer_id) {
+            let pos = self.layer_order.iter().position(|existing| existing.0 > layer_id.0).unwrap_or(self.layer_order.len());
+            self.layer_order.insert(pos, layer_id);
+        }
+    }
+
+    ///
+    /// Returns the name of the form XObject containing the current elements for a sprite (or None if the sprite is empty)
+    ///
+    fn sprite_form(&mut self, sprite: (NamespaceId, SpriteId)) -> Option<String> {
+        let content = match self.sprites.get(&sprite) {
+            None                                            => { return None; }
+            Some(sprite) if sprite.elements.is_empty()      => { return None; }
+            Some(PdfSprite { form: Some(form), .. })        => { return Some(form.clone()); }
+            Some(sprite)                                    => sprite.elements.concat(),
+        };
+
+        // Sprites aren't clipped to any particular region
+        let form = self.objects.add_resource("XObject", "Fm", pdf_stream("/Type /XObject /Subtype /Form /BBox [-100000 -100000 100000 100000] /Resources 1 0 R", content.as_bytes()));
+        if let Some(sprite) = self.sprites.get_mut(&sprite) {
+            sprite.form = Some(form.clone());
+        }
+
+        Some(form)
+    }
+
+    ///
+    /// Returns the content stream that draws a texture into a 1x1 cell, with the bottom-left corner of the texture at (0, 0)
+    ///
+    fn texture_content(&mut self, texture: (NamespaceId, TextureId)) -> Option<String> {
+        let (content, alpha) = self.textures.get(&texture).map(|texture| (texture.content.clone(), texture.alpha))?;
+
+        let content = match content {
+            PdfTextureContent::Bitmap { image: Some(image), .. }        => format!("/{} Do\n", image),
+            PdfTextureContent::Bitmap { size, pixels, image: None }     => {
+                let image = self.objects.add_image(size, &pixels)?;
+
+                if let Some(PdfTexture { content: PdfTextureContent::Bitmap { image: texture_image, .. }, .. }) = self.textures.get_mut(&texture) {
+                    *texture_image = Some(image.clone());
+                }
+
+                format!("/{} Do\n", image)
+            }
+
+            PdfTextureContent::Sprite { sprite, bounds, form, dynamic } => {
+                let form = if dynamic { self.sprite_form(sprite)? } else { form? };
+
+                // Map the sprite bounds onto the cell
+                let SpriteBounds(SpritePosition(x, y), SpriteSize(w, h)) = bounds;
+                if w == 0.0 || h == 0.0 { return None; }
+
+                let sprite_to_cell = Transform2D([
+                    [1.0/w, 0.0,    -x/w],
+                    [0.0,   1.0/h,  -y/h],
+                    [0.0,   0.0,    1.0]
+                ]);
+
+                format!("{}/{} Do\n", pdf_transform(&sprite_to_cell), form)
+            }
+        };
+
+        Some(format!("{}{}", self.objects.graphics_state(alpha, 1.0, None), content))
+    }
+
+    ///
+    /// Returns the paint to use for a texture fill or stroke
+    ///
+    fn texture_paint(&mut self, texture_id: TextureId, lower_left: (f32, f32), upper_right: (f32, f32)) -> Option<PdfPaint> {
+        let content = self.texture_content((self.namespace, texture_id))?;
+
+        Some(PdfPaint::Texture { content: content, lower_left: lower_left, upper_right: upper_right })
+    }
+
+    ///
+    /// Returns the stops for a gradient
+    ///
+    fn gradient(&self, gradient_id: GradientId) -> Option<Vec<(f32, Color)>> {
+        self.gradients.get(&(self.namespace, gradient_id)).cloned()
+    }
+
+    ///
+    /// Returns the operators that select a paint for filling or stroking, and the alpha value that the paint should use
+    ///
+    /// The pattern transform maps the paint's coordinates to the coordinates of the content stream that's being drawn.
+    ///
+    fn paint_operators(&mut self, paint: &PdfPaint, pattern_transform: &Transform2D, stroke: bool) -> (String, f32) {
+        let pattern = match paint {
+            PdfPaint::Color(color) => {
+                let (color, alpha) = pdf_color(color);
+                return (format!("{} {}\n", color, if stroke { "RG" } else { "rg" }), alpha);
+            }
+
+            PdfPaint::LinearGradient { stops, start, end } => {
+                let shading = format!("<< /ShadingType 2 /ColorSpace /DeviceRGB /Coords [{} {} {} {}] /Function {} /Extend [true true] >>",
+                    pdf_number(start.0), pdf_number(start.1), pdf_number(end.0), pdf_number(end.1), pdf_gradient_function(stops));
+
+                format!("<< /Type /Pattern /PatternType 2 /Matrix [{}] /Shading {} >>", pdf_matrix(pattern_transform), shading).into_bytes()
+            }
+
+            PdfPaint::RadialGradient { stops, center, radius, focus } => {
+                let (focus_x, focus_y)  = focus.unwrap_or(*center);
+                let shading             = format!("<< /ShadingType 3 /ColorSpace /DeviceRGB /Coords [{} {} 0 {} {} {}] /Function {} /Extend [true true] >>",
+                    pdf_number(focus_x), pdf_number(focus_y), pdf_number(center.0), pdf_number(center.1), pdf_number(radius.abs()), pdf_gradient_function(stops));
+
+                format!("<< /Type /Pattern /PatternType 2 /Matrix [{}] /Shading {} >>", pdf_matrix(pattern_transform), shading).into_bytes()
+            }
+
+            PdfPaint::Texture { content, lower_left, upper_right } => {
+                // The texture is drawn into a 1x1 cell, which is mapped onto the texture rectangle
+                let (x1, y1)        = *lower_left;
+                let (x2, y2)        = *upper_right;
+                let cell_to_texture = Transform2D([
+                    [x2-x1, 0.0,   x1],
+                    [0.0,   y2-y1, y1],
+                    [0.0,   0.0,   1.0]
+                ]);
+
+                pdf_stream(&format!("/Type /Pattern /PatternType 1 /PaintType 1 /TilingType 1 /BBox [0 0 1 1] /XStep 1 /YStep 1 /Resources 1 0 R /Matrix [{}]", pdf_matrix(&(*pattern_transform * cell_to_texture))), content.as_bytes())
+            }
+        };
+
+        let pattern = self.objects.add_resource("Pattern", "P", pattern);
+
+        if stroke {
+            (format!("/Pattern CS /{} SCN\n", pattern), 1.0)
+        } else {
+            (format!("/Pattern cs /{} scn\n", pattern), 1.0)
+        }
+    }
+
+    ///
+    /// Returns the operators that set the graphics state for an element drawn with the current state
+    ///
+    fn element_state(&mut self, fill_alpha: f32, stroke_alpha: f32) -> String {
+        let blend_mode = pdf_blend_mode(self.state.blend_mode);
+
+        self.objects.graphics_state(fill_alpha, stroke_alpha, blend_mode)
+    }
+
+    ///
+    /// Fills the current path
+    ///
+    fn fill(&mut self) {
+        let path = pdf_path_data(&self.current_path, &Transform2D::identity());
+        if path.is_empty() { return; }
+
+        let fill                = self.state.fill.clone();
+        let pattern_transform   = self.base_transform() * self.canvas_transform.transform() * self.state.fill_transform;
+        let (paint, alpha)      = self.paint_operators(&fill, &pattern_transform, false);
+
+        let mut content         = self.element_state(alpha, 1.0);
+        content.push_str(&pdf_transform(&self.canvas_transform.transform()));
+        content.push_str(&paint);
+        content.push_str(&path);
+        content.push_str(if self.state.winding_rule == WindingRule::EvenOdd { "f*\n" } else { "f\n" });
+
+        self.add_element(content);
+    }
+
+    ///
+    /// Strokes the current path
+    ///
+    fn stroke(&mut self) {
+        let path = pdf_path_data(&self.current_path, &Transform2D::identity());
+        if path.is_empty() { return; }
+
+        let stroke              = self.state.stroke.clone();
+        let pattern_transform   = self.base_transform() * self.canvas_transform.transform();
+        let (paint, alpha)      = self.paint_operators(&stroke, &pattern_transform, true);
+
+        // Line widths in pixels are measured in points on the page
+        let line_width          = if self.state.line_width_pixels {
+            let scale = transform_scale(&(self.page_transform() * self.canvas_transform.transform()));
+            if scale > 0.0 { self.state.line_width / scale } else { self.state.line_width }
+        } else {
+            self.state.line_width
+        };
+
+        let mut content         = self.element_state(1.0, alpha);
+        content.push_str(&pdf_transform(&self.canvas_transform.transform()));
+        content.push_str(&paint);
+        writeln!(content, "{} w", pdf_number(line_width)).ok();
+
+        match self.state.line_join {
+            LineJoin::Miter => { writeln!(content, "0 j\n{} M", pdf_number(self.state.miter_limit.max(1.0))).ok(); }
+            LineJoin::Round => { content.push_str("1 j\n"); }
+            LineJoin::Bevel => { content.push_str("2 j\n"); }
+        }
+
+        match self.state.line_cap {
+            LineCap::Butt   => { content.push_str("0 J\n"); }
+            LineCap::Round  => { content.push_str("1 J\n"); }
+            LineCap::Square => { content.push_str("2 J\n"); }
+        }
+
+        if self.state.dash_pattern.iter().any(|length| *length > 0.0) {
+            let dash_pattern = self.state.dash_pattern.iter().map(|length| pdf_number(length.max(0.0))).collect::<Vec<_>>();
+            writeln!(content, "[{}] {} d", dash_pattern.join(" "), pdf_number(self.state.dash_offset)).ok();
+        }
+
+        content.push_str(&path);
+        content.push_str("S\n");
+
+        self.add_element(content);
+    }
+
+    ///
+    /// Intersects the clip path with the current path
+    ///
+    fn clip(&mut self) {
+        // Clipping to an empty path hides everything, so a single point is used in place of an empty path
+        let mut clip = pdf_path_data(&self.current_path, &self.canvas_transform.transform());
+        if clip.is_empty() { clip = "0 0 m\n".to_string(); }
+
+        clip.push_str(if self.state.winding_rule == WindingRule::EvenOdd { "W* n\n" } else { "W n\n" });
+
+        self.state.clip.push(clip);
+    }
+
+    ///
+    /// Draws a sprite
+    ///
+    fn draw_sprite(&mut self, sprite_id: SpriteId) {
+        let form = if let Some(form) = self.sprite_form((self.namespace, sprite_id)) { form } else { return; };
+
+        let transform   = self.canvas_transform.transform() * self.state.sprite_transform;
+        let mut content = self.element_state(1.0, 1.0);
+        content.push_str(&pdf_transform(&transform));
+        writeln!(content, "/{} Do", form).ok();
+
+        self.add_element(content);
+    }
+
+    ///
+    /// Draws a set of glyphs using the font they were laid out with
+    ///
+    fn draw_glyphs(&mut self, font_id: FontId, glyphs: Vec<GlyphPosition>) {
+        let font_index = if let Some(font_index) = self.fonts.get(&(self.namespace, font_id)) { *font_index } else { return; };
+        if glyphs.is_empty() { return; }
+
+        // The embedded font data can't be varied, so glyphs from faces with variation settings are drawn as paths
+        let font_face = Arc::clone(&self.objects.font_faces[font_index].font_face);
+        if !font_face.variations().is_empty() {
+            let path            = mem::take(&mut self.current_path);
+            self.current_path   = glyphs.iter().flat_map(|glyph| glyph_outline(&font_face, glyph)).collect();
+            self.fill();
+            self.current_path   = path;
+
+            return;
+        }
+
+        let fill                = self.state.fill.clone();
+        let pattern_transform   = self.base_transform() * self.canvas_transform.transform() * self.state.fill_transform;
+        let (paint, alpha)      = self.paint_operators(&fill, &pattern_transform, false);
+
+        let mut content         = self.element_state(alpha, 1.0);
+        content.push_str(&pdf_transform(&self.canvas_transform.transform()));
+        content.push_str(&paint);
+        writeln!(content, "BT\n/F{} 1 Tf", font_index).ok();
+
+        // Fonts use the Identity-H encoding, so glyphs are drawn using their IDs
+        for glyph in glyphs.iter() {
+            let glyph_id    = glyph.id.0.min(0xffff) as u16;
+            let em_size     = pdf_number(glyph.em_size);
+            let (x, y)      = glyph.location;
+
+            writeln!(content, "{} 0 0 {} {} {} Tm <{:04x}> Tj", em_size, em_size, pdf_number(x), pdf_number(y), glyph_id).ok();
+            self.objects.font_faces[font_index].glyphs.insert(glyph_id);
+        }
+
+        content.push_str("ET\n");
+
+        self.add_element(content);
+    }
+
+    ///
+    /// Performs an operation on a texture
+    ///
+    fn texture(&mut self, texture_id: TextureId, texture_op: TextureOp) {
+        let key = (self.namespace, texture_id);
+
+        match texture_op {
+            TextureOp::Create(size, _format) => {
+                let TextureSize(width, height) = size;
+
+                // Textures that are too large to store are left out of the document
+                let num_bytes = (width as usize).checked_mul(height as usize).and_then(|pixels| pixels.checked_mul(4));
+                let num_bytes = match num_bytes {
+                    Some(num_bytes) if num_bytes <= MAX_TEXTURE_BYTES   => num_bytes,
+                    _                                                   => { return; }
+                };
+
+                self.textures.insert(key, PdfTexture {
+                    content:    PdfTextureContent::Bitmap { size: size, pixels: vec![0; num_bytes], image: None },
+                    alpha:      1.0,
+                });
+            }
+
+            TextureOp::Free => {
+                self.textures.remove(&key);
+            }
+
+            TextureOp::SetBytes(TexturePosition(x, y), TextureSize(width, height), bytes) => {
+                if let Some(PdfTexture { content: PdfTextureContent::Bitmap { size: TextureSize(texture_width, texture_height), pixels, image }, .. }) = self.textures.get_mut(&key) {
+                    // Copy the rows that are inside the texture
+                    let (x, y)                          = (x as usize, y as usize);
+                    let (width, height)                 = (width as usize, height as usize);
+                    let (texture_width, texture_height) = (*texture_width as usize, *texture_height as usize);
+
+                    if x < texture_width {
+                        let row_length = width.min(texture_width - x) * 4;
+
+                        for row in 0..height {
+                            let source_start    = row * width * 4;
+                            let target_start    = ((y + row) * texture_width + x) * 4;
+
+                            if y + row >= texture_height || source_start + row_length > bytes.len() { break; }
+
+                            pixels[target_start..(target_start + row_length)].copy_from_slice(&bytes[source_start..(source_start + row_length)]);
+                        }
+                    }
+
+                    *image = None;
+                }
+            }
+
+            TextureOp::SetFromSprite(sprite_id, bounds) => {
+                let form = self.sprite_form((self.namespace, sprite_id));
+
+                if let Some(texture) = self.textures.get_mut(&key) {
+                    texture.content = PdfTextureContent::Sprite { sprite: (key.0, sprite_id), bounds: bounds, form: form, dynamic: false };
+                }
+            }
+
+            TextureOp::CreateDynamicSprite(sprite_id, bounds, _size) => {
+                self.textures.insert(key, PdfTexture {
+                    content:    PdfTextureContent::Sprite { sprite: (key.0, sprite_id), bounds: bounds, form: None, dynamic: true },
+                    alpha:      1.0,
+                });
+            }
+
+            TextureOp::FillTransparency(alpha) => {
+                if let Some(texture) = self.textures.get_mut(&key) {
+                    texture.alpha = alpha;
+                }
+            }
+
+            TextureOp::Copy(target_id) => {
+                if let Some(texture) = self.textures.get(&key).cloned() {
+                    self.textures.insert((key.0, target_id), texture);
+                }
+            }
+
+            // Filters can't be applied to images in PDF
+            TextureOp::Filter(_) => { }
+        }
+    }
+
+    ///
+    /// Performs an operation on a font
+    ///
+    fn font(&mut self, font_id: FontId, font_op: FontOp) {
+        match font_op {
+            FontOp::UseFontDefinition(font_face) => {
+                // Each font face is embedded in the document once
+                let font_faces  = &mut self.objects.font_faces;
+                let font_index  = if let Some(font_index) = font_faces.iter().position(|existing| Arc::ptr_eq(&existing.font_face, &font_face)) {
+                    font_index
+                } else {
+                    font_faces.push(PdfFontFace { font_face: font_face, glyphs: BTreeSet::new() });
+                    font_faces.len() - 1
+                };
+
+                self.fonts.insert((self.namespace, font_id), font_index);
+            }
+
+            FontOp::FontVariation(tag, value) => {
+                // The varied font face is stored as a separate face (which is never embedded, as its glyphs are drawn as paths)
+                let key = (self.namespace, font_id);
+
+                if let Some(font_index) = self.fonts.get(&key) {
+                    let font_faces  = &mut self.objects.font_faces;
+                    let font_face   = font_faces[*font_index].font_face.with_variation(tag, value);
+
+                    font_faces.push(PdfFontFace { font_face: font_face, glyphs: BTreeSet::new() });
+                    self.fonts.insert(key, font_faces.len() - 1);
+                }
+            }
+
+            FontOp::DrawGlyphs(glyphs) => { self.draw_glyphs(font_id, glyphs); }
+
+            // Glyphs specify their own size, text has to be laid out before it can be written, and features are applied when the text is shaped
+            FontOp::FontSize(_)         |
+            FontOp::UseFallbackFonts(_) |
+            FontOp::FontFeature(_, _)   |
+            FontOp::LayoutText(_)       => { }
+        }
+    }
+
+    ///
+    /// Processes a drawing instruction
+    ///
+    fn process(&mut self, drawing: Draw) {
+        use self::Draw::*;
+
+        // Transforms are applied to each element as it's written out
+        if self.canvas_transform.update(&drawing) {
+            return;
+        }
+
+        match drawing {
+            StartFrame                              => { self.frame_count += 1; }
+            ResetFrame                              => { self.frame_count = 0; }
+            ShowFrame                               => {
+                self.frame_count = self.frame_count.saturating_sub(1);
+
+                if self.frame_count == 0 && self.changed {
+                    self.new_page();
+                }
+            }
+
+            Path(PathOp::NewPath)                   => { self.current_path = vec![]; }
+            Path(path_op)                           => { self.current_path.push(path_op); }
+            StorePath                               => { self.stored_path = self.current_path.clone(); }
+            CombinePath(operation)                  => { self.current_path = combine_paths(operation, self.state.winding_rule, &self.stored_path, &self.current_path); }
+
+            Fill                                    => { self.fill(); }
+            Stroke                                  => { self.stroke(); }
+            Clip                                    => { self.clip(); }
+            Unclip                                  => { self.state.clip = vec![]; }
+
+            LineWidth(width)                        => { self.state.line_width = width; self.state.line_width_pixels = false; }
+            LineWidthPixels(width)                  => { self.state.line_width = width; self.state.line_width_pixels = true; }
+            LineJoin(join)                          => { self.state.line_join = join; }
+            MiterLimit(limit)                       => { self.state.miter_limit = limit; }
+            LineCap(cap)                            => { self.state.line_cap = cap; }
+            NewDashPattern                          => { self.state.dash_pattern = vec![]; self.state.dash_offset = 0.0; }
+            DashLength(length)                      => { self.state.dash_pattern.push(length); }
+            DashOffset(offset)                      => { self.state.dash_offset = offset; }
+            WindingRule(winding_rule)               => { self.state.winding_rule = winding_rule; }
+            BlendMode(blend_mode)                   => { self.state.blend_mode = blend_mode; }
+
+            FillColor(color)                        => { self.state.fill = PdfPaint::Color(color); self.state.fill_transform = Transform2D::identity(); }
+            StrokeColor(color)                      => { self.state.stroke = PdfPaint::Color(color); }
+            FillTransform(transform)                => { self.state.fill_transform = self.state.fill_transform * transform; }
+
+            FillTexture(texture_id, lower_left, upper_right) => {
+                if let Some(paint) = self.texture_paint(texture_id, lower_left, upper_right) {
+                    self.state.fill             = paint;
+                    self.state.fill_transform   = Transform2D::identity();
+                }
+            }
+
+            StrokeTexture(texture_id, lower_left, upper_right) => {
+                if let Some(paint) = self.texture_paint(texture_id, lower_left, upper_right) {
+                    self.state.stroke = paint;
+                }
+            }
+
+            FillGradient(gradient_id, start, end)   => {
+                if let Some(stops) = self.gradient(gradient_id) {
+                    self.state.fill             = PdfPaint::LinearGradient { stops: stops, start: start, end: end };
+                    self.state.fill_transform   = Transform2D::identity();
+                }
+            }
+
+            StrokeGradient(gradient_id, start, end) => {
+                if let Some(stops) = self.gradient(gradient_id) {
+                    self.state.stroke = PdfPaint::LinearGradient { stops: stops, start: start, end: end };
+                }
+            }
+
+            FillRadialGradient(gradient_id, center, radius, focus) => {
+                if let Some(stops) = self.gradient(gradient_id) {
+                    self.state.fill             = PdfPaint::RadialGradient { stops: stops, center: center, radius: radius, focus: focus };
+                    self.state.fill_transform   = Transform2D::identity();
+                }
+            }
+
+            FillConicGradient(gradient_id, _, _)    => {
+                // PDF has no conic shadings, so these are filled with the colour of the first stop
+                if let Some(stops) = self.gradient(gradient_id) {
+                    if let Some((_, color)) = stops.first() {
+                        self.state.fill             = PdfPaint::Color(*color);
+                        self.state.fill_transform   = Transform2D::identity();
+                    }
+                }
+            }
+
+            // State
+            PushState                               => { self.state_stack.push((self.canvas_transform.transform(), self.state.clone(), self.current_path.clone(), self.namespace)); }
+            PopState                                => {
+                if let Some((transform, state, path, namespace)) = self.state_stack.pop() {
+                    self.canvas_transform.set_transform(transform);
+                    self.state          = state;
+                    self.current_path   = path;
+                    self.namespace      = namespace;
+                }
+            }
+
+            Store                                   => {
+                if let PdfTarget::Layer(layer_id) = self.target {
+                    let elements = self.layers.get(&layer_id).cloned().unwrap_or_default();
+                    self.stored_layers.insert(layer_id, elements);
+                }
+            }
+
+            Restore                                 => {
+                if let PdfTarget::Layer(layer_id) = self.target {
+                    if let Some(elements) = self.stored_layers.get(&layer_id) {
+                        self.layers.insert(layer_id, elements.clone());
+                        self.changed = true;
+                    }
+                }
+            }
+
+            FreeStoredBuffer                        => {
+                if let PdfTarget::Layer(layer_id) = self.target {
+                    self.stored_layers.remove(&layer_id);
+                }
+            }
+
+            ClearCanvas(color)                      => {
+                // The pages and objects that have already been written are kept
+                let pages           = mem::take(&mut self.pages);
+                let objects         = mem::take(&mut self.objects);
+                let frame_count     = self.frame_count;
+
+                *self               = PdfWriter::new(self.width, self.height);
+                self.pages          = pages;
+                self.objects        = objects;
+                self.frame_count    = frame_count;
+                self.background     = Some(color);
+                self.changed        = true;
+            }
+
+            // Layers
+            Layer(layer_id)                         => {
+                let new_state = self.target_state.remove(&PdfTarget::Layer(layer_id)).unwrap_or_default();
+                self.target_state.insert(self.target, mem::replace(&mut self.state, new_state));
+                self.target = PdfTarget::Layer(layer_id);
+
+                self.add_layer(layer_id);
+            }
+
+            LayerBlend(layer_id, blend_mode)        => { self.layer_blend.insert(layer_id, blend_mode); self.changed = true; }
+            LayerAlpha(layer_id, alpha)             => { self.layer_alpha.insert(layer_id, alpha); self.changed = true; }
+            ClearLayer                              => { self.clear_target(); }
+            ClearAllLayers                          => { self.layers.clear(); self.changed = true; }
+
+            SwapLayers(layer1, layer2)              => {
+                self.add_layer(layer1);
+                self.add_layer(layer2);
+
+                let pos1 = self.layer_order.iter().position(|existing| existing == &layer1).unwrap();
+                let pos2 = self.layer_order.iter().position(|existing| existing == &layer2).unwrap();
+                self.layer_order.swap(pos1, pos2);
+                self.changed = true;
+            }
+
+            // Sprites
+            Sprite(sprite_id)                       => {
+                let target      = PdfTarget::Sprite(self.namespace, sprite_id);
+                let new_state   = self.target_state.remove(&target).unwrap_or_default();
+                self.target_state.insert(self.target, mem::replace(&mut self.state, new_state));
+                self.target     = target;
+            }
+
+            MoveSpriteFrom(sprite_id)               => {
+                if let PdfTarget::Sprite(namespace_id, current_sprite_id) = self.target {
+                    if (namespace_id, current_sprite_id) != (self.namespace, sprite_id) {
+                        let sprite = self.sprites.remove(&(self.namespace, sprite_id)).unwrap_or_default();
+                        self.sprites.insert((namespace_id, current_sprite_id), sprite);
+                    }
+                }
+            }
+
+            ClearSprite                             => { self.clear_target(); }
+
+            SpriteTransform(self::SpriteTransform::Identity)    => { self.state.sprite_transform = Transform2D::identity(); }
+            SpriteTransform(sprite_transform)                   => { self.state.sprite_transform = Transform2D::from(sprite_transform) * self.state.sprite_transform; }
+
+            DrawSprite(sprite_id)                   => { self.draw_sprite(sprite_id); }
+            DrawSpriteWithFilters(sprite_id, _)     => { self.draw_sprite(sprite_id); }
+
+            // Resources
+            Texture(texture_id, texture_op)         => { self.texture(texture_id, texture_op); }
+            Font(font_id, font_op)                  => { self.font(font_id, font_op); }
+            Namespace(namespace_id)                 => { self.namespace = namespace_id; }
+
+            Gradient(gradient_id, gradient_op)      => {
+                let key = (self.namespace, gradient_id);
+
+                match gradient_op {
+                    GradientOp::Create(color)           => { self.gradients.insert(key, vec![(0.0, color)]); }
+                    GradientOp::AddStop(pos, color)     => { if let Some(stops) = self.gradients.get_mut(&key) { stops.push((pos, color)); } }
+
+                    // PDF shadings can only extend the colours at the ends of the gradient
+                    GradientOp::Spread(_)               => { }
+                }
+            }
+
+            // Text has to be laid out as glyphs before it can be written
+            BeginLineLayout(_, _, _)                |
+            BeginParagraphLayout(_, _, _)           |
+            DrawLaidOutText                         |
+            DrawText(_, _, _, _)                    => { }
+        }
+    }
+
+    ///
+    /// Returns the PDF document for everything that has been drawn so far
+    ///
+    /// If anything has been drawn since the last page was added, the document ends with a page showing the current state of the canvas.
+    ///
+    pub fn document(&self) -> Vec<u8> {
+        self.clone().into_document()
+    }
+
+    ///
+    /// Finishes the document that's being written by this writer
+    ///
+    fn into_document(mut self) -> Vec<u8> {
+        if self.changed || self.pages.is_empty() {
+            self.new_page();
+        }
+
+        let PdfObjects { mut objects, mut resources, font_faces, .. } = self.objects;
+
+        // Fonts are written at the end as they need to know which glyphs were used (fonts that weren't used are left out)
+        for (font_index, font_face) in font_faces.iter().enumerate() {
+            if font_face.glyphs.is_empty() { continue; }
+
+            let font_name   = format!("F{}", font_index);
+            let font        = add_font_objects(&mut objects, &font_name, font_face);
+
+            resources.push(("Font", font_name, font));
+        }
+
+        // Object 1 is the resources dictionary, which is shared by all of the content streams in the document
+        let mut resource_dictionary = String::from("<< /ProcSet [/PDF /Text /ImageB /ImageC]");
+        for category in ["ExtGState", "Pattern", "XObject", "Font"].iter() {
+            let entries = resources.iter()
+                .filter(|(resource_category, _, _)| resource_category == category)
+                .map(|(_, name, number)| format!("/{} {} 0 R", name, number))
+                .collect::<Vec<_>>();
+
+            if !entries.is_empty() {
+                write!(resource_dictionary, " /{} << {} >>", category, entries.join(" ")).ok();
+            }
+        }
+        resource_dictionary.push_str(" >>");
+
+        // Pages refer to the page tree, so its object number is reserved before they're written
+        let page_tree   = objects.len() + 2;
+        let mut kids    = vec![];
+        objects.push(vec![]);
+
+        for page_content in self.pages.iter() {
+            objects.push(pdf_stream("", page_content.as_bytes()));
+            let content = objects.len() + 1;
+
+            objects.push(format!("<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources 1 0 R /Contents {} 0 R /Group << /S /Transparency /CS /DeviceRGB >> >>",
+                page_tree, pdf_number(self.width), pdf_number(self.height), content).into_bytes());
+            kids.push(format!("{} 0 R", objects.len() + 1));
+        }
+
+        objects[page_tree - 2] = format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), kids.len()).into_bytes();
+
+        objects.push(format!("<< /Type /Catalog /Pages {} 0 R >>", page_tree).into_bytes());
+        let catalog = objects.len() + 1;
+
+        // Write out the objects, followed by the cross-reference table
+        let mut document    = b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets     = vec![];
+
+        for (index, object) in Some(resource_dictionary.into_bytes()).into_iter().chain(objects).enumerate() {
+            offsets.push(document.len());
+
+            document.extend(format!("{} 0 obj\n", index + 1).bytes());
+            document.extend(object);
+            document.extend(b"\nendobj\n");
+        }
+
+        let xref = document.len();
+        document.extend(format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).bytes());
+        for offset in offsets.iter() {
+            document.extend(format!("{:010} 00000 n \n", offset).bytes());
+        }
+
+        document.extend(format!("trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n", offsets.len() + 1, catalog, xref).bytes());
+
+        document
+    }
+}
+
+impl GraphicsContext for PdfWriter {
+    #[inline]
+    fn draw(&mut self, drawing: Draw) {
+        self.process(drawing);
+    }
+}
+
+///
+/// Adds the objects for an embedded font to a document, returning the object number of the font dictionary
+///
+/// Fonts are embedded as composite fonts using the Identity-H encoding, so the glyphs in the content streams are glyph IDs.
+///
+fn add_font_objects(objects: &mut Vec<Vec<u8>>, font_name: &str, font: &PdfFontFace) -> usize {
+    let font_data       = font.font_face.font_data();
+    let face            = font.font_face.ttf_font();
+    let units_per_em    = f32::max(1.0, face.units_per_em() as f32);
+    let font_units      = |units: f32| pdf_number(units * 1000.0 / units_per_em);
+
+    // Fonts with CFF outlines are embedded as OpenType fonts
+    let (file_key, file_dictionary, subtype, cid_to_gid) = if font_data.starts_with(b"OTTO") {
+        ("FontFile3", "/Subtype /OpenType".to_string(), "CIDFontType0", "")
+    } else {
+        ("FontFile2", format!("/Length1 {}", font_data.len()), "CIDFontType2", " /CIDToGIDMap /Identity")
+    };
+
+    objects.push(pdf_stream(&file_dictionary, font_data));
+    let font_file = objects.len() + 1;
+
+    let bounds      = face.global_bounding_box();
+    let ascender    = face.ascender() as f32;
+    let descender   = face.descender() as f32;
+    let cap_height  = face.capital_height().map(|height| height as f32).unwrap_or(ascender);
+
+    objects.push(format!("<< /Type /FontDescriptor /FontName /{} /Flags 4 /FontBBox [{} {} {} {}] /ItalicAngle 0 /Ascent {} /Descent {} /CapHeight {} /StemV 80 /{} {} 0 R >>",
+        font_name, font_units(bounds.x_min as f32), font_units(bounds.y_min as f32), font_units(bounds.x_max as f32), font_units(bounds.y_max as f32),
+        font_units(ascender), font_units(descender), font_units(cap_height), file_key, font_file).into_bytes());
+    let descriptor = objects.len() + 1;
+
+    // The widths of the glyphs that were used in the document
+    let widths = font.glyphs.iter()
+        .map(|glyph_id| format!("{} [{}]", glyph_id, font_units(face.glyph_hor_advance(ttf_parser::GlyphId(*glyph_id)).unwrap_or(0) as f32)))
+        .collect::<Vec<_>>();
+
+    objects.push(format!("<< /Type /Font /Subtype /{} /BaseFont /{} /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> /FontDescriptor {} 0 R /W [{}]{} >>",
+        subtype, font_name, descriptor, widths.join(" "), cid_to_gid).into_bytes());
+    let cid_font = objects.len() + 1;
+
+    objects.push(format!("<< /Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /Identity-H /DescendantFonts [{} 0 R] >>", font_name, cid_font).into_bytes());
+
+    objects.len() + 1
+}
+
+///
+/// Generates a PDF document from a stream of drawing instructions
+///
+/// See `PdfWriter` for details of how the drawing is converted. Text is laid out and dashed lines are converted to paths before
+/// the drawing is written, and if the options request that text is written as outlines, it's converted to paths as well.
+///
+pub fn drawing_to_pdf<InStream: 'static+Send+Unpin+Stream<Item=Draw>>(draw_stream: InStream, options: PdfOptions) -> impl Send+Future<Output=Vec<u8>> {
+    async move {
+        let mut writer      = PdfWriter::new(options.width, options.height);
+        let draw_stream     = drawing_without_dashed_lines(drawing_with_laid_out_text(draw_stream));
+
+        match options.text {
+            PdfText::Fonts => {
+                let mut draw_stream = draw_stream;
+
+                while let Some(drawing) = draw_stream.next().await {
+                    writer.draw(drawing);
+                }
+            }
+
+            PdfText::Outlines => {
+                let mut draw_stream = drawing_with_text_as_paths(draw_stream);
+
+                while let Some(drawing) = draw_stream.next().await {
+                    writer.draw(drawing);
+                }
+            }
+        }
+
+        writer.into_document()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::primitives::*;
+
+    use futures::stream;
+    use futures::executor;
+
+    fn writer_for(drawing: Vec<Draw>) -> PdfWriter {
+        let mut writer = PdfWriter::new(200.0, 100.0);
+
+        for draw in drawing {
+            writer.draw(draw);
+        }
+
+        writer
+    }
+
+    fn contains(document: &[u8], text: &str) -> bool {
+        document.windows(text.len()).any(|window| window == text.as_bytes())
+    }
+
+    #[test]
+    fn empty_document() {
+        let pdf = writer_for(vec![]).document();
+
+        assert!(pdf.starts_with(b"%PDF-1.7\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        assert!(contains(&pdf, "/Type /Pages /Kids [4 0 R] /Count 1"));
+        assert!(contains(&pdf, "/MediaBox [0 0 200 100]"));
+        assert!(contains(&pdf, "/Type /Catalog"));
+    }
+
+    #[test]
+    fn fill_rectangle() {
+        let mut drawing = vec![];
+        drawing.canvas_height(100.0);
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 20.0);
+        drawing.fill_color(Color::Rgba(1.0, 0.0, 0.0, 0.5));
+        drawing.winding_rule(WindingRule::EvenOdd);
+        drawing.fill();
+
+        let mut writer = writer_for(drawing);
+        writer.new_page();
+
+        assert!(writer.pages[0] == "q\n50 0 0 50 100 50 cm\nq\n/GS2 gs\n0.02 0 0 0.02 0 0 cm\n1 0 0 rg\n0 0 m\n0 20 l\n10 20 l\n10 0 l\n0 0 l\nh\nf*\nQ\nQ\n", "{}", writer.pages[0]);
+        assert!(contains(&writer.document(), "<< /Type /ExtGState /ca 0.5 >>"));
+    }
+
+    #[test]
+    fn stroke_with_dashes() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.move_to(0.0, 0.0);
+        drawing.line_to(1.0, 0.0);
+        drawing.stroke_color(Color::Rgba(0.0, 0.0, 1.0, 1.0));
+        drawing.line_width(0.25);
+        drawing.line_join(LineJoin::Miter);
+        drawing.line_cap(LineCap::Square);
+        drawing.new_dash_pattern();
+        drawing.dash_length(0.5);
+        drawing.dash_length(0.25);
+        drawing.dash_offset(0.125);
+        drawing.stroke();
+
+        let mut writer = writer_for(drawing);
+        writer.new_page();
+
+        assert!(writer.pages[0].contains("0 0 1 RG\n0.25 w\n0 j\n4 M\n2 J\n[0.5 0.25] 0.125 d\n0 0 m\n1 0 l\nS\n"), "{}", writer.pages[0]);
+    }
+
+    #[test]
+    fn stroke_in_pixels() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.move_to(0.0, 0.0);
+        drawing.line_to(1.0, 0.0);
+        drawing.line_width_pixels(5.0);
+        drawing.stroke();
+
+        let mut writer = writer_for(drawing);
+        writer.new_page();
+
+        assert!(writer.pages[0].contains("0.1 w\n"), "{}", writer.pages[0]);
+    }
+
+    #[test]
+    fn each_frame_is_a_page() {
+        let mut drawing = vec![];
+        drawing.start_frame();
+        drawing.start_frame();
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+        drawing.show_frame();
+        drawing.show_frame();
+
+        // Frames where nothing changes don't add a page
+        drawing.start_frame();
+        drawing.show_frame();
+
+        drawing.start_frame();
+        drawing.clear_canvas(Color::Rgba(1.0, 1.0, 1.0, 1.0));
+        drawing.show_frame();
+
+        let writer = writer_for(drawing);
+
+        assert!(writer.pages.len() == 2);
+        assert!(writer.pages[0].contains("0 0 m\n0 1 l\n"));
+        assert!(writer.pages[1] == "q\n1 1 1 rg\n0 0 200 100 re\nf\nQ\n", "{}", writer.pages[1]);
+        assert!(contains(&writer.document(), "/Count 2"));
+    }
+
+    #[test]
+    fn explicit_page_breaks() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+
+        let mut writer = writer_for(drawing.clone());
+        writer.new_page();
+        for draw in drawing {
+            writer.draw(draw);
+        }
+
+        let pdf = writer.document();
+
+        assert!(contains(&pdf, "/Count 2"));
+        assert!(writer.pages.len() == 1);
+    }
+
+    #[test]
+    fn show_frame_outside_frame_is_page_break() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+        drawing.show_frame();
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 2.0, 2.0);
+        drawing.fill();
+
+        let pdf = executor::block_on(drawing_to_pdf(stream::iter(drawing), PdfOptions::new(100.0, 100.0)));
+
+        assert!(contains(&pdf, "/Count 2"));
+    }
+
+    #[test]
+    fn layer_alpha_is_transparency_group() {
+        let mut drawing = vec![];
+        drawing.layer(LayerId(1));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+        drawing.layer_alpha(LayerId(1), 0.5);
+        drawing.layer(LayerId(2));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 2.0, 2.0);
+        drawing.fill();
+        drawing.layer_blend(LayerId(2), BlendMode::Multiply);
+
+        let pdf = writer_for(drawing).document();
+
+        assert!(contains(&pdf, "/Subtype /Form /BBox [0 0 200 100] /Group << /S /Transparency >>"));
+        assert!(contains(&pdf, "<< /Type /ExtGState /ca 0.5 /CA 0.5 >>"));
+        assert!(contains(&pdf, "<< /Type /ExtGState /BM /Multiply >>"));
+    }
+
+    #[test]
+    fn clip_paths_are_intersected() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.clip();
+        drawing.new_path();
+        drawing.rect(0.5, 0.5, 2.0, 2.0);
+        drawing.clip();
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 3.0, 3.0);
+        drawing.fill();
+        drawing.unclip();
+        drawing.fill();
+
+        let mut writer = writer_for(drawing);
+        writer.new_page();
+
+        assert!(writer.pages[0].contains("q\n0 0 m\n0 1 l\n1 1 l\n1 0 l\n0 0 l\nh\nW n\n0.5 0.5 m\n0.5 2 l\n2 2 l\n2 0.5 l\n0.5 0.5 l\nh\nW n\n0 0 0 rg\n"), "{}", writer.pages[0]);
+        assert!(writer.pages[0].contains("Q\nq\n0 0 0 rg\n"), "{}", writer.pages[0]);
+    }
+
+    #[test]
+    fn linear_gradient_fill() {
+        let mut drawing = vec![];
+        drawing.create_gradient(GradientId(1), Color::Rgba(1.0, 0.0, 0.0, 1.0));
+        drawing.gradient_stop(GradientId(1), 1.0, Color::Rgba(0.0, 0.0, 1.0, 1.0));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill_gradient(GradientId(1), 0.0, 0.0, 1.0, 0.0);
+        drawing.fill();
+
+        let mut writer  = writer_for(drawing);
+        let pdf         = writer.document();
+        writer.new_page();
+
+        assert!(contains(&pdf, "<< /Type /Pattern /PatternType 2 /Matrix [50 0 0 50 100 50] /Shading << /ShadingType 2 /ColorSpace /DeviceRGB /Coords [0 0 1 0] /Function << /FunctionType 2 /Domain [0 1] /C0 [1 0 0] /C1 [0 0 1] /N 1 >> /Extend [true true] >> >>"));
+        assert!(writer.pages[0].contains("/Pattern cs /P2 scn\n"), "{}", writer.pages[0]);
+    }
+
+    #[test]
+    fn texture_fill_is_image() {
+        let mut drawing = vec![];
+        drawing.create_texture(TextureId(1), 2, 2, TextureFormat::Rgba);
+        drawing.set_texture_bytes(TextureId(1), 0, 0, 2, 2, Arc::new(vec![255; 16]));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill_texture(TextureId(1), 0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+
+        let pdf = writer_for(drawing).document();
+
+        assert!(contains(&pdf, "/Type /XObject /Subtype /Image /Width 2 /Height 2 /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter"));
+        assert!(contains(&pdf, "/PatternType 1 /PaintType 1 /TilingType 1 /BBox [0 0 1 1] /XStep 1 /YStep 1 /Resources 1 0 R /Matrix [50 0 0 50 100 50]"));
+        assert!(!contains(&pdf, "/SMask"));
+    }
+
+    #[test]
+    fn huge_texture_is_left_out() {
+        let mut drawing = vec![];
+        drawing.create_texture(TextureId(1), u32::MAX, u32::MAX, TextureFormat::Rgba);
+        drawing.set_texture_bytes(TextureId(1), 0, 0, 2, 2, Arc::new(vec![255; 16]));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill_texture(TextureId(1), 0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+
+        let pdf = writer_for(drawing).document();
+
+        assert!(!contains(&pdf, "/Subtype /Image"));
+    }
+
+    #[test]
+    fn transparent_texture_has_soft_mask() {
+        let mut drawing = vec![];
+        drawing.create_texture(TextureId(1), 2, 2, TextureFormat::Rgba);
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill_texture(TextureId(1), 0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+
+        let pdf = writer_for(drawing).document();
+
+        assert!(contains(&pdf, "/ColorSpace /DeviceGray"));
+        assert!(contains(&pdf, "/SMask 2 0 R"));
+    }
+
+    #[test]
+    fn sprites_are_forms() {
+        let mut drawing = vec![];
+        drawing.sprite(SpriteId(1));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+        drawing.layer(LayerId(0));
+        drawing.sprite_transform(SpriteTransform::Translate(2.0, 3.0));
+        drawing.draw_sprite(SpriteId(1));
+        drawing.sprite_transform(SpriteTransform::Translate(1.0, 0.0));
+        drawing.draw_sprite(SpriteId(1));
+
+        let mut writer  = writer_for(drawing);
+        let pdf         = writer.document();
+        writer.new_page();
+
+        assert!(writer.pages[0].contains("q\n1 0 0 1 2 3 cm\n/Fm2 Do\nQ\n"), "{}", writer.pages[0]);
+        assert!(writer.pages[0].contains("q\n1 0 0 1 3 3 cm\n/Fm2 Do\nQ\n"), "{}", writer.pages[0]);
+        assert!(contains(&pdf, "/XObject << /Fm2 2 0 R >>"));
+    }
+
+    #[test]
+    fn glyphs_use_embedded_font() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../../test_data/Lato-Regular.ttf"));
+
+        let mut drawing = vec![];
+        drawing.define_font_data(FontId(1), Arc::clone(&lato));
+        drawing.set_font_size(FontId(1), 16.0);
+        drawing.draw_text(FontId(1), "Hello".to_string(), 10.0, 20.0);
+
+        let pdf = executor::block_on(drawing_to_pdf(stream::iter(drawing), PdfOptions::new(100.0, 100.0)));
+
+        assert!(contains(&pdf, "/Subtype /Type0 /BaseFont /F0 /Encoding /Identity-H"));
+        assert!(contains(&pdf, "/Subtype /CIDFontType2"));
+        assert!(contains(&pdf, "/FontFile2"));
+        assert!(contains(&pdf, "/Font << /F0"));
+    }
+
+    #[test]
+    fn varied_font_glyphs_are_drawn_as_paths() {
+        let variable_test   = CanvasFontFace::from_slice(include_bytes!("../../test_data/VariableTest.ttf"));
+
+        let mut drawing     = vec![];
+        drawing.define_font_data(FontId(1), Arc::clone(&variable_test));
+        drawing.set_font_size(FontId(1), 100.0);
+        drawing.set_font_variation(FontId(1), *b"wght", 650.0);
+        drawing.draw_text(FontId(1), "a".to_string(), 10.0, 20.0);
+
+        let pdf = executor::block_on(drawing_to_pdf(stream::iter(drawing.clone()), PdfOptions::new(100.0, 100.0)));
+
+        assert!(!contains(&pdf, "/Type0"));
+        assert!(!contains(&pdf, "/FontFile2"));
+
+        // 'a' is a rectangle whose right-hand edge moves from 900 to 1150 units at this weight, so the path uses the varied outline
+        let laid_out        = executor::block_on(drawing_with_laid_out_text(stream::iter(drawing)).collect::<Vec<_>>());
+        let mut writer      = writer_for(laid_out);
+        writer.new_page();
+
+        assert!(writer.pages[0].contains("125 90 l\n"), "{}", writer.pages[0]);
+        assert!(!writer.pages[0].contains("100 90 l\n"), "{}", writer.pages[0]);
+    }
+
+    #[test]
+    fn text_as_outlines() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../../test_data/Lato-Regular.ttf"));
+
+        let mut drawing = vec![];
+        drawing.define_font_data(FontId(1), Arc::clone(&lato));
+        drawing.set_font_size(FontId(1), 0.5);
+        drawing.draw_text(FontId(1), "Hello".to_string(), 0.0, 0.0);
+
+        let pdf = executor::block_on(drawing_to_pdf(stream::iter(drawing), PdfOptions::new(100.0, 100.0).with_text(PdfText::Outlines)));
+
+        assert!(!contains(&pdf, "/Type0"));
+        assert!(!contains(&pdf, "/FontFile2"));
+        assert!(contains(&pdf, "/Count 1"));
+    }
+}
@@ -40,6 +40,54 @@ pub trait GraphicsPrimitives : GraphicsContext {
         }
     }
 
+    ///
+    /// Draws an ellipse at a particular point, with the specified radii and rotation (in radians)
+    ///
+    fn ellipse(&mut self, center_x: f32, center_y: f32, radius_x: f32, radius_y: f32, rotation: f32) {
+        for d in draw_ellipse(center_x, center_y, radius_x, radius_y, rotation) {
+            self.draw(d);
+        }
+    }
+
+    ///
+    /// Draws a rectangle with rounded corners
+    ///
+    /// The radii are for the corners at (min x, min y), (max x, min y), (max x, max y) and (min x, max y) in that order. They
+    /// are scaled down if they're too large to fit in the rectangle.
+    ///
+    fn rounded_rect(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, corner_radii: (f32, f32, f32, f32)) {
+        for d in draw_rounded_rect(x1, y1, x2, y2, corner_radii) {
+            self.draw(d);
+        }
+    }
+
+    ///
+    /// Draws a circular arc between two angles (in radians) as a new subpath
+    ///
+    fn arc(&mut self, center_x: f32, center_y: f32, radius: f32, start_angle: f32, end_angle: f32) {
+        for d in draw_arc(center_x, center_y, radius, start_angle, end_angle) {
+            self.draw(d);
+        }
+    }
+
+    ///
+    /// Draws a pie segment (an arc with lines connecting it to the center point)
+    ///
+    fn pie_segment(&mut self, center_x: f32, center_y: f32, radius: f32, start_angle: f32, end_angle: f32) {
+        for d in draw_pie_segment(center_x, center_y, radius, start_angle, end_angle) {
+            self.draw(d);
+        }
+    }
+
+    ///
+    /// Draws a regular polygon with the specified number of sides, with the first point at the specified angle (in radians)
+    ///
+    fn regular_polygon(&mut self, center_x: f32, center_y: f32, radius: f32, num_sides: usize, rotation: f32) {
+        for d in draw_regular_polygon(center_x, center_y, radius, num_sides, rotation) {
+            self.draw(d);
+        }
+    }
+
     ///
     /// Draws a bezier path
     ///
@@ -92,6 +140,30 @@ pub trait GraphicsPrimitives : GraphicsContext {
                         Draw::Fill,
                     ],
 
+                    PathAttribute::FillRadialGradient(gradient, center, radius, focus, Some(transform)) => smallvec![
+                        Draw::FillRadialGradient(*gradient, *center, *radius, *focus),
+                        Draw::FillTransform(*transform),
+                        Draw::Fill,
+                    ],
+
+                    PathAttribute::FillRadialGradient(gradient, center, radius, focus, None) => smallvec![
+                        Draw::FillRadialGradient(*gradient, *center, *radius, *focus),
+                        Draw::FillTransform(Transform2D::identity()),
+                        Draw::Fill,
+                    ],
+
+                    PathAttribute::FillConicGradient(gradient, center, angle, Some(transform)) => smallvec![
+                        Draw::FillConicGradient(*gradient, *center, *angle),
+                        Draw::FillTransform(*transform),
+                        Draw::Fill,
+                    ],
+
+                    PathAttribute::FillConicGradient(gradient, center, angle, None) => smallvec![
+                        Draw::FillConicGradient(*gradient, *center, *angle),
+                        Draw::FillTransform(Transform2D::identity()),
+                        Draw::Fill,
+                    ],
+
                     PathAttribute::FillTexture(texture, (x1, y1), (x2, y2), Some(transform)) => smallvec![
                         Draw::FillTexture(*texture, (*x1, *y1), (*x2, *y2)),
                         Draw::FillTransform(*transform),
@@ -210,6 +282,121 @@ pub fn draw_circle(center_x: f32, center_y: f32, radius: f32) -> Vec<Draw> {
     path.collect()
 }
 
+///
+/// Returns the drawing commands for an ellipse
+///
+pub fn draw_ellipse(center_x: f32, center_y: f32, radius_x: f32, radius_y: f32, rotation: f32) -> Vec<Draw> {
+    use self::Draw::*;
+    use self::PathOp::*;
+
+    let center              = (center_x, center_y);
+    let radii               = (radius_x, radius_y);
+    let (start_x, start_y)  = arc_point(center, radii, rotation, 0.0);
+
+    vec![
+        Path(Move(start_x, start_y)),
+        Path(Arc(center, radii, rotation, 0.0, std::f32::consts::PI * 2.0)),
+        Path(ClosePath)
+    ]
+}
+
+///
+/// Returns the drawing commands for a rectangle with rounded corners
+///
+pub fn draw_rounded_rect(x1: f32, y1: f32, x2: f32, y2: f32, corner_radii: (f32, f32, f32, f32)) -> Vec<Draw> {
+    use self::Draw::*;
+    use self::PathOp::*;
+
+    let (min_x, max_x)  = (f32::min(x1, x2), f32::max(x1, x2));
+    let (min_y, max_y)  = (f32::min(y1, y2), f32::max(y1, y2));
+    let width           = max_x - min_x;
+    let height          = max_y - min_y;
+
+    // Scale the radii so that the corners on each side don't overlap
+    let (r1, r2, r3, r4)    = corner_radii;
+    let (r1, r2, r3, r4)    = (r1.max(0.0), r2.max(0.0), r3.max(0.0), r4.max(0.0));
+    let scale_for           = |length: f32, ra: f32, rb: f32| if ra + rb > length { length / (ra + rb) } else { 1.0 };
+    let scale               = scale_for(width, r1, r2)
+        .min(scale_for(width, r3, r4))
+        .min(scale_for(height, r2, r3))
+        .min(scale_for(height, r1, r4));
+    let (r1, r2, r3, r4)    = (r1*scale, r2*scale, r3*scale, r4*scale);
+
+    // Each corner is either a quarter turn of an arc or a line to the corner
+    let quarter_turn    = std::f32::consts::PI / 2.0;
+    let corner          = |(x, y): (f32, f32), (cx, cy): (f32, f32), radius: f32, start_angle: f32| {
+        if radius > 0.0 {
+            Path(Arc((cx, cy), (radius, radius), 0.0, start_angle, quarter_turn))
+        } else {
+            Path(Line(x, y))
+        }
+    };
+
+    vec![
+        Path(Move(min_x + r1, min_y)),
+        corner((max_x, min_y), (max_x - r2, min_y + r2), r2, -quarter_turn),
+        corner((max_x, max_y), (max_x - r3, max_y - r3), r3, 0.0),
+        corner((min_x, max_y), (min_x + r4, max_y - r4), r4, quarter_turn),
+        corner((min_x, min_y), (min_x + r1, min_y + r1), r1, quarter_turn * 2.0),
+        Path(ClosePath)
+    ]
+}
+
+///
+/// Returns the drawing commands for a circular arc
+///
+pub fn draw_arc(center_x: f32, center_y: f32, radius: f32, start_angle: f32, end_angle: f32) -> Vec<Draw> {
+    use self::Draw::*;
+    use self::PathOp::*;
+
+    let center              = (center_x, center_y);
+    let radii               = (radius, radius);
+    let (start_x, start_y)  = arc_point(center, radii, 0.0, start_angle);
+
+    vec![
+        Path(Move(start_x, start_y)),
+        Path(Arc(center, radii, 0.0, start_angle, end_angle - start_angle)),
+    ]
+}
+
+///
+/// Returns the drawing commands for a pie segment
+///
+pub fn draw_pie_segment(center_x: f32, center_y: f32, radius: f32, start_angle: f32, end_angle: f32) -> Vec<Draw> {
+    use self::Draw::*;
+    use self::PathOp::*;
+
+    vec![
+        Path(Move(center_x, center_y)),
+        Path(Arc((center_x, center_y), (radius, radius), 0.0, start_angle, end_angle - start_angle)),
+        Path(ClosePath)
+    ]
+}
+
+///
+/// Returns the drawing commands for a regular polygon
+///
+pub fn draw_regular_polygon(center_x: f32, center_y: f32, radius: f32, num_sides: usize, rotation: f32) -> Vec<Draw> {
+    use self::Draw::*;
+    use self::PathOp::*;
+
+    // Need at least 3 sides to make a polygon
+    if num_sides < 3 {
+        return vec![];
+    }
+
+    let points = (0..num_sides)
+        .map(|side| {
+            let angle = rotation + (side as f32) * (std::f32::consts::PI * 2.0) / (num_sides as f32);
+            arc_point((center_x, center_y), (radius, radius), 0.0, angle)
+        });
+
+    points.enumerate()
+        .map(|(idx, (x, y))| if idx == 0 { Path(Move(x, y)) } else { Path(Line(x, y)) })
+        .chain(iter::once(Path(ClosePath)))
+        .collect()
+}
+
 impl<'a, Curve: BezierCurve> From<&'a Curve> for Draw
 where Curve::Point: Coordinate2D {
     fn from(curve: &'a Curve) -> Draw {
@@ -12,6 +12,9 @@
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct SpriteId(pub u64);
 
+/// The maximum depth of sprites that are followed when a sprite is drawn inline (sprites that draw themselves are not followed past this depth)
+pub (crate) const MAX_SPRITE_DEPTH: usize = 32;
+
 ///
 /// A position within a sprite
 ///
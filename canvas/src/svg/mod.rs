@@ -0,0 +1,19 @@
+//!
+//! # SVG import and export
+//!
+//! `SvgWriter` turns a stream of `Draw` instructions into an SVG document, and `drawing_to_svg()` does the same for a
+//! stream of instructions, optionally converting any text to outlines first. This makes it possible to save a canvas
+//! as a vector image instead of rendering it to a bitmap.
+//!
+//! `drawing_from_svg()` goes the other way, reading an SVG document and converting it to a list of `Draw` instructions.
+//! Parts of the document that can't be represented on a canvas (such as text or filters) are reported in the result
+//! rather than causing the import to fail.
+//!
+
+mod svg_format;
+mod svg_parse;
+mod svg_writer;
+mod svg_import;
+
+pub use self::svg_writer::*;
+pub use self::svg_import::*;
@@ -0,0 +1,269 @@
+use crate::draw::*;
+use crate::path::*;
+use crate::color::*;
+use crate::transform2d::*;
+
+pub (super) use crate::data_uri::{base64, png_data_uri};
+
+use std::fmt::Write;
+
+///
+/// Formats a number for use in an SVG attribute
+///
+pub (super) fn svg_number(val: f32) -> String {
+    if val == 0.0 || !val.is_finite() {
+        "0".to_string()
+    } else {
+        format!("{}", val)
+    }
+}
+
+///
+/// Returns the hex colour and the opacity to use for a colour in an SVG document
+///
+pub (super) fn svg_color(color: &Color) -> (String, f32) {
+    let (r, g, b, a)    = color.to_rgba_components();
+    let component       = |val: f32| (val.max(0.0).min(1.0) * 255.0).round() as u8;
+
+    (format!("#{:02x}{:02x}{:02x}", component(r), component(g), component(b)), a.max(0.0).min(1.0))
+}
+
+///
+/// Returns the attributes that set a paint attribute (`fill`, `stroke` or `stop-color`) to a colour, along with its opacity
+///
+pub (super) fn svg_color_attributes(attribute: &str, opacity_attribute: &str, color: &Color) -> String {
+    let (color, alpha) = svg_color(color);
+
+    if alpha < 1.0 {
+        format!(" {}=\"{}\" {}=\"{}\"", attribute, color, opacity_attribute, svg_number(alpha))
+    } else {
+        format!(" {}=\"{}\"", attribute, color)
+    }
+}
+
+///
+/// Formats a transform as an SVG `matrix()` (or returns None for the identity transform)
+///
+pub (super) fn svg_matrix(transform: &Transform2D) -> Option<String> {
+    if transform == &Transform2D::identity() {
+        None
+    } else {
+        let Transform2D(a) = transform;
+
+        Some(format!("matrix({} {} {} {} {} {})", svg_number(a[0][0]), svg_number(a[1][0]), svg_number(a[0][1]), svg_number(a[1][1]), svg_number(a[0][2]), svg_number(a[1][2])))
+    }
+}
+
+///
+/// Returns a `transform` attribute for a transform, or an empty string for the identity transform
+///
+pub (super) fn svg_transform_attribute(attribute: &str, transform: &Transform2D) -> String {
+    svg_matrix(transform)
+        .map(|matrix| format!(" {}=\"{}\"", attribute, matrix))
+        .unwrap_or_default()
+}
+
+///
+/// Converts a path to SVG path data
+///
+pub (super) fn svg_path_data(path: &[PathOp]) -> String {
+    let mut data        = String::new();
+    let mut last_point  = None;
+    let mut start_point = None;
+
+    // SVG paths always begin with a move, so segments that don't follow a point need to start with one
+    let segment_start = |data: &mut String, last_point: &mut Option<(f32, f32)>, start_point: &mut Option<(f32, f32)>, default_start: (f32, f32)| {
+        if last_point.is_none() {
+            let (x, y) = default_start;
+            write!(data, "M{} {}", svg_number(x), svg_number(y)).ok();
+
+            *last_point     = Some(default_start);
+            *start_point    = Some(default_start);
+        }
+    };
+
+    for path_op in path.iter() {
+        match path_op {
+            PathOp::NewPath => {
+                data.clear();
+                last_point  = None;
+                start_point = None;
+            }
+
+            PathOp::Move(x, y) => {
+                write!(data, "M{} {}", svg_number(*x), svg_number(*y)).ok();
+                last_point  = Some((*x, *y));
+                start_point = Some((*x, *y));
+            }
+
+            PathOp::Line(x, y) => {
+                segment_start(&mut data, &mut last_point, &mut start_point, (*x, *y));
+                write!(data, "L{} {}", svg_number(*x), svg_number(*y)).ok();
+                last_point = Some((*x, *y));
+            }
+
+            PathOp::BezierCurve(((cp1x, cp1y), (cp2x, cp2y)), (x, y)) => {
+                segment_start(&mut data, &mut last_point, &mut start_point, (*x, *y));
+                write!(data, "C{} {} {} {} {} {}", svg_number(*cp1x), svg_number(*cp1y), svg_number(*cp2x), svg_number(*cp2y), svg_number(*x), svg_number(*y)).ok();
+                last_point = Some((*x, *y));
+            }
+
+            PathOp::QuadraticCurve((cpx, cpy), (x, y)) => {
+                segment_start(&mut data, &mut last_point, &mut start_point, (*x, *y));
+                write!(data, "Q{} {} {} {}", svg_number(*cpx), svg_number(*cpy), svg_number(*x), svg_number(*y)).ok();
+                last_point = Some((*x, *y));
+            }
+
+            PathOp::Arc(center, radii, rotation, start_angle, sweep_angle) => {
+                // Arcs are written as bezier curves, joined to the existing path by a line
+                let (start_x, start_y) = arc_point(*center, *radii, *rotation, *start_angle);
+
+                if last_point.is_none() {
+                    segment_start(&mut data, &mut last_point, &mut start_point, (start_x, start_y));
+                } else if last_point != Some((start_x, start_y)) {
+                    write!(data, "L{} {}", svg_number(start_x), svg_number(start_y)).ok();
+                }
+
+                last_point = Some((start_x, start_y));
+
+                for ((cp1x, cp1y), (cp2x, cp2y), (x, y)) in arc_to_bezier_curves(*center, *radii, *rotation, *start_angle, *sweep_angle) {
+                    write!(data, "C{} {} {} {} {} {}", svg_number(cp1x), svg_number(cp1y), svg_number(cp2x), svg_number(cp2y), svg_number(x), svg_number(y)).ok();
+                    last_point = Some((x, y));
+                }
+            }
+
+            PathOp::ClosePath => {
+                if last_point.is_some() {
+                    data.push('Z');
+                    last_point = start_point;
+                }
+            }
+        }
+    }
+
+    data
+}
+
+///
+/// Returns the CSS blend mode that matches a canvas blend mode (or None for the default blend mode or blend modes that can't be represented in SVG)
+///
+pub (super) fn svg_blend_mode(blend_mode: BlendMode) -> Option<&'static str> {
+    use self::BlendMode::*;
+
+    match blend_mode {
+        SourceOver      |
+        SourceIn        |
+        SourceOut       |
+        DestinationOver |
+        DestinationIn   |
+        DestinationOut  |
+        SourceAtop      |
+        DestinationAtop => None,
+
+        Multiply        => Some("multiply"),
+        Screen          => Some("screen"),
+        Darken          => Some("darken"),
+        Lighten         => Some("lighten"),
+        Overlay         => Some("overlay"),
+        ColorDodge      => Some("color-dodge"),
+        ColorBurn       => Some("color-burn"),
+        HardLight       => Some("hard-light"),
+        SoftLight       => Some("soft-light"),
+        Difference      => Some("difference"),
+        Exclusion       => Some("exclusion"),
+        Hue             => Some("hue"),
+        Saturation      => Some("saturation"),
+        Color           => Some("color"),
+        Luminosity      => Some("luminosity"),
+    }
+}
+
+///
+/// Escapes a string so it can be used as text or as an attribute value in an SVG document
+///
+pub (super) fn svg_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for chr in text.chars() {
+        match chr {
+            '&'     => escaped.push_str("&amp;"),
+            '<'     => escaped.push_str("&lt;"),
+            '>'     => escaped.push_str("&gt;"),
+            '"'     => escaped.push_str("&quot;"),
+            '\''    => escaped.push_str("&apos;"),
+
+            // Control characters aren't allowed in XML documents
+            chr if chr < ' ' && chr != '\t' && chr != '\n' && chr != '\r' => { }
+
+            chr     => escaped.push(chr),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn format_numbers() {
+        assert!(svg_number(0.0) == "0");
+        assert!(svg_number(-0.0) == "0");
+        assert!(svg_number(1.0) == "1");
+        assert!(svg_number(-2.5) == "-2.5");
+        assert!(svg_number(f32::NAN) == "0");
+    }
+
+    #[test]
+    fn format_color() {
+        assert!(svg_color(&Color::Rgba(1.0, 0.0, 0.5, 1.0)) == ("#ff0080".to_string(), 1.0));
+        assert!(svg_color_attributes("fill", "fill-opacity", &Color::Rgba(0.0, 0.0, 0.0, 0.5)) == " fill=\"#000000\" fill-opacity=\"0.5\"");
+    }
+
+    #[test]
+    fn format_matrix() {
+        assert!(svg_matrix(&Transform2D::identity()).is_none());
+        assert!(svg_matrix(&Transform2D::translate(10.0, 20.0)) == Some("matrix(1 0 0 1 10 20)".to_string()));
+        assert!(svg_matrix(&Transform2D::scale(2.0, 3.0)) == Some("matrix(2 0 0 3 0 0)".to_string()));
+    }
+
+    #[test]
+    fn format_path() {
+        let path = vec![
+            PathOp::Move(0.0, 0.0),
+            PathOp::Line(10.0, 0.0),
+            PathOp::BezierCurve(((10.0, 5.0), (5.0, 10.0)), (0.0, 10.0)),
+            PathOp::ClosePath,
+        ];
+
+        assert!(svg_path_data(&path) == "M0 0L10 0C10 5 5 10 0 10Z");
+    }
+
+    #[test]
+    fn path_without_initial_move() {
+        let path = vec![
+            PathOp::Line(10.0, 0.0),
+            PathOp::Line(10.0, 10.0),
+        ];
+
+        assert!(svg_path_data(&path) == "M10 0L10 0L10 10");
+    }
+
+    #[test]
+    fn format_arc() {
+        let path = vec![
+            PathOp::Move(0.0, 0.0),
+            PathOp::Arc((0.0, 0.0), (10.0, 10.0), 0.0, 0.0, std::f32::consts::PI),
+        ];
+        let data = svg_path_data(&path);
+
+        assert!(data.starts_with("M0 0L10 0C"));
+        assert!(data.matches('C').count() == 2);
+    }
+
+    #[test]
+    fn escape_text() {
+        assert!(svg_escape("<a & 'b'>") == "&lt;a &amp; &apos;b&apos;&gt;");
+    }
+}
@@ -0,0 +1,1625 @@
+use super::svg_parse::*;
+
+use crate::draw::*;
+use crate::path::*;
+use crate::color::*;
+use crate::texture::*;
+use crate::gradient::*;
+use crate::namespace::*;
+use crate::transform2d::*;
+
+use roxmltree::{Document, Node, ParsingOptions};
+
+use std::sync::*;
+use std::collections::{HashMap};
+
+/// The namespace used for SVG elements
+const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+
+/// The namespace used for the older `xlink:href` attribute
+const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+
+/// How many `<use>` elements can be nested inside each other before the importer gives up (stops documents with circular references from looping forever)
+const MAX_USE_DEPTH: usize = 16;
+
+/// How long a chain of gradients inheriting from each other via `href` can be
+const MAX_HREF_DEPTH: usize = 16;
+
+/// How many elements can be drawn before the importer gives up (stops documents with many nested `<use>` elements from expanding exponentially)
+const MAX_DRAWN_ELEMENTS: usize = 1_000_000;
+
+/// Properties that are recognised but can't be imported
+const UNSUPPORTED_PROPERTIES: &[&str] = &["filter", "mask", "marker", "marker-start", "marker-mid", "marker-end"];
+
+///
+/// Part of an SVG document that could not be converted to drawing instructions
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum SvgUnsupported {
+    /// An element that can't be drawn (eg, `text`), along with its name
+    Element(String),
+
+    /// An attribute or property that was ignored (the element it was found on and the attribute name)
+    Attribute(String, String),
+
+    /// A reference to an element that doesn't exist or can't be used (the element the reference was found on and the reference)
+    Reference(String, String),
+
+    /// An image that couldn't be loaded (only PNG images embedded as data URIs can be imported)
+    Image(String),
+}
+
+///
+/// Errors that prevent an SVG document from being imported at all
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum SvgImportError {
+    /// The document is not valid XML (with a description of the problem)
+    InvalidXml(String),
+
+    /// The root element of the document is not `<svg>`
+    NotAnSvgDocument,
+
+    /// Drawing the document would mean drawing more elements than the importer allows (usually because of nested `<use>` elements)
+    TooManyElements,
+}
+
+///
+/// An SVG document that has been converted to drawing instructions
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportedSvg {
+    /// The size of the document, in pixels
+    pub size: (f32, f32),
+
+    /// The instructions that draw the document
+    pub drawing: Vec<Draw>,
+
+    /// The parts of the document that were left out of the drawing
+    pub unsupported: Vec<SvgUnsupported>,
+}
+
+///
+/// A value of the `fill` or `stroke` properties
+///
+#[derive(Clone, Debug, PartialEq)]
+enum SvgPaint {
+    /// Nothing is painted
+    None,
+
+    /// Paint with a colour
+    Color(Color),
+
+    /// Paint with the value of the `color` property
+    CurrentColor,
+
+    /// Paint using the element with the specified ID, with a fallback colour for if the element can't be used
+    Url(String, Option<Color>),
+}
+
+///
+/// Whether a paint is being set up for filling or stroking a path
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SvgPaintTarget {
+    Fill,
+    Stroke,
+}
+
+///
+/// The bounding box of an element, as the minimum and maximum coordinates
+///
+type SvgBounds = ((f32, f32), (f32, f32));
+
+///
+/// The style properties that apply to an element
+///
+#[derive(Clone, Debug, PartialEq)]
+struct SvgStyle {
+    fill:               SvgPaint,
+    fill_opacity:       f32,
+    fill_rule:          WindingRule,
+    stroke:             SvgPaint,
+    stroke_opacity:     f32,
+    stroke_width:       f32,
+    line_cap:           LineCap,
+    line_join:          LineJoin,
+    miter_limit:        f32,
+    dash_array:         Vec<f32>,
+    dash_offset:        f32,
+    clip_rule:          WindingRule,
+    color:              Color,
+    visible:            bool,
+
+    /// The opacity of the element multiplied by the opacity of the groups it's in
+    ///
+    /// SVG draws groups with an opacity to an offscreen image, which we approximate by applying the opacity to the
+    /// fill and stroke of every element in the group (this looks the same unless the elements overlap).
+    opacity:            f32,
+}
+
+impl Default for SvgStyle {
+    fn default() -> SvgStyle {
+        SvgStyle {
+            fill:           SvgPaint::Color(Color::Rgba(0.0, 0.0, 0.0, 1.0)),
+            fill_opacity:   1.0,
+            fill_rule:      WindingRule::NonZero,
+            stroke:         SvgPaint::None,
+            stroke_opacity: 1.0,
+            stroke_width:   1.0,
+            line_cap:       LineCap::Butt,
+            line_join:      LineJoin::Miter,
+            miter_limit:    4.0,
+            dash_array:     vec![],
+            dash_offset:    0.0,
+            clip_rule:      WindingRule::NonZero,
+            color:          Color::Rgba(0.0, 0.0, 0.0, 1.0),
+            visible:        true,
+            opacity:        1.0,
+        }
+    }
+}
+
+///
+/// Converts the elements of an SVG document into drawing instructions
+///
+struct SvgImporter<'a, 'input> {
+    /// The elements in the document that have an ID
+    elements: HashMap<&'a str, Node<'a, 'input>>,
+
+    /// The size of the current viewport (which percentage lengths are relative to)
+    viewport: (f32, f32),
+
+    /// The drawing instructions generated so far
+    drawing: Vec<Draw>,
+
+    /// The parts of the document that could not be imported
+    unsupported: Vec<SvgUnsupported>,
+
+    /// The gradients that have been defined so far, indexed by element ID, the stops they resolved to and the opacity they're drawn with
+    ///
+    /// The stops are part of the key because stops that use `currentColor` can resolve to a different colour every time the gradient is used.
+    gradients: HashMap<(String, Vec<[u32; 5]>, u32), GradientId>,
+
+    /// The ID to assign to the next gradient
+    next_gradient_id: u64,
+
+    /// The ID to assign to the next texture
+    next_texture_id: u64,
+
+    /// How many `<use>` elements are being expanded
+    use_depth: usize,
+
+    /// How many elements have been drawn so far (including the elements drawn by `<use>` elements)
+    drawn_elements: usize,
+}
+
+///
+/// Reads an SVG document and converts it to a set of drawing instructions
+///
+/// Paths, basic shapes, transforms, fills and strokes, linear and radial gradients, clip paths, groups with opacity, `<use>`
+/// elements and embedded PNG images are converted to the equivalent drawing instructions. Anything that can't be converted
+/// (for example text, filters, masks or patterns) is left out of the drawing and listed in the `unsupported` field of the result.
+///
+/// The drawing is in pixels, with the origin at the bottom-left of the document and the y axis pointing upwards, matching
+/// the result of `canvas_height()` and `center_region()` with the document size. It's surrounded by `PushState` and `PopState`,
+/// and any gradients and textures are created in a new namespace so they won't replace anything already on the canvas.
+///
+/// Documents that would draw more than a million elements (usually because `<use>` elements are nested inside each other
+/// to repeat the same content many times) are rejected with `SvgImportError::TooManyElements`.
+///
+pub fn drawing_from_svg(svg: &str) -> Result<ImportedSvg, SvgImportError> {
+    let options     = ParsingOptions { allow_dtd: true, ..ParsingOptions::default() };
+    let document    = Document::parse_with_options(svg, options).map_err(|err| SvgImportError::InvalidXml(err.to_string()))?;
+    let root        = document.root_element();
+
+    if !is_svg_element(root) || root.tag_name().name() != "svg" {
+        return Err(SvgImportError::NotAnSvgDocument);
+    }
+
+    // The size of the document defaults to the size of the view box, keeping its aspect ratio if only one of the width or height is set
+    let view_box        = parse_view_box(root);
+    let default_size    = view_box.map(|(_, _, width, height)| (width, height)).unwrap_or((100.0, 100.0));
+    let width           = root.attribute("width").and_then(|width| parse_length(width, default_size.0)).filter(|width| *width > 0.0);
+    let height          = root.attribute("height").and_then(|height| parse_length(height, default_size.1)).filter(|height| *height > 0.0);
+    let aspect_ratio    = default_size.0 / default_size.1;
+
+    let (width, height) = match (width, height) {
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None)         => (width, width / aspect_ratio),
+        (None, Some(height))        => (height * aspect_ratio, height),
+        (None, None)                => default_size,
+    };
+
+    // Import the document
+    let mut importer    = SvgImporter::new(&document, (width, height));
+    let style           = importer.element_style(root, &SvgStyle::default());
+
+    importer.draw_viewport(root, &style, (0.0, 0.0, width, height));
+
+    if importer.drawn_elements > MAX_DRAWN_ELEMENTS {
+        return Err(SvgImportError::TooManyElements);
+    }
+
+    // SVG documents have the origin at the top-left, so flip the drawing so it's the right way up
+    let flip            = Transform2D([[1.0, 0.0, 0.0], [0.0, -1.0, height], [0.0, 0.0, 1.0]]);
+    let mut drawing     = vec![Draw::PushState];
+
+    if importer.next_gradient_id > 0 || importer.next_texture_id > 0 {
+        drawing.push(Draw::Namespace(NamespaceId::new()));
+    }
+
+    drawing.push(Draw::MultiplyTransform(flip));
+    drawing.extend(importer.drawing);
+    drawing.push(Draw::PopState);
+
+    Ok(ImportedSvg {
+        size:           (width, height),
+        drawing:        drawing,
+        unsupported:    importer.unsupported,
+    })
+}
+
+///
+/// True if a node is an element in the SVG namespace (or an element without a namespace, which is common in hand-written documents)
+///
+fn is_svg_element(node: Node) -> bool {
+    node.is_element() && match node.tag_name().namespace() {
+        None | Some(SVG_NAMESPACE)  => true,
+        Some(_)                     => false,
+    }
+}
+
+///
+/// Reads a property of an element, from its `style` attribute or from the presentation attribute with the same name
+///
+fn property<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    let from_style = node.attribute("style")
+        .and_then(|style| parse_style(style).into_iter().rev().find(|(property_name, _)| *property_name == name))
+        .map(|(_, value)| value);
+
+    from_style.or_else(|| node.attribute(name))
+}
+
+///
+/// Reads the `href` attribute of an element (or the older `xlink:href` attribute)
+///
+fn href<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+    node.attribute("href")
+        .or_else(|| node.attribute((XLINK_NAMESPACE, "href")))
+        .map(|href| href.trim())
+}
+
+///
+/// Returns the ID from a `url(#id)` reference
+///
+fn url_reference(value: &str) -> Option<&str> {
+    let reference = value.trim().strip_prefix("url(")?;
+    let reference = &reference[..reference.find(')')?];
+    let reference = reference.trim().trim_matches(|chr| chr == '"' || chr == '\'');
+
+    Some(reference.strip_prefix('#').unwrap_or(reference))
+}
+
+///
+/// Parses the value of the `fill` or `stroke` properties
+///
+fn parse_paint(value: &str) -> Option<SvgPaint> {
+    let value = value.trim();
+
+    if value == "none" {
+        Some(SvgPaint::None)
+    } else if value.eq_ignore_ascii_case("currentColor") {
+        Some(SvgPaint::CurrentColor)
+    } else if value.starts_with("url(") {
+        let fallback = value[(value.find(')')?+1)..].trim();
+
+        Some(SvgPaint::Url(url_reference(value)?.to_string(), parse_color(fallback)))
+    } else {
+        parse_color(value).map(SvgPaint::Color)
+    }
+}
+
+///
+/// Parses the `viewBox` attribute of an element
+///
+fn parse_view_box(node: Node) -> Option<(f32, f32, f32, f32)> {
+    let view_box = parse_number_list(node.attribute("viewBox")?);
+
+    if view_box.len() == 4 && view_box[2] > 0.0 && view_box[3] > 0.0 {
+        Some((view_box[0], view_box[1], view_box[2], view_box[3]))
+    } else {
+        None
+    }
+}
+
+///
+/// Returns the transform that maps a view box onto a viewport according to a `preserveAspectRatio` attribute, and whether or
+/// not the view box is sliced (so parts of it are outside of the viewport)
+///
+fn view_box_transform((view_x, view_y, view_width, view_height): (f32, f32, f32, f32), (x, y, width, height): (f32, f32, f32, f32), preserve_aspect_ratio: Option<&str>) -> (Transform2D, bool) {
+    let mut parts   = preserve_aspect_ratio.unwrap_or("").split_whitespace().filter(|part| *part != "defer");
+    let align       = parts.next().unwrap_or("xMidYMid");
+    let slice       = parts.next() == Some("slice");
+    let scale_x     = width / view_width;
+    let scale_y     = height / view_height;
+
+    if align == "none" {
+        return (Transform2D::translate(x, y) * Transform2D::scale(scale_x, scale_y) * Transform2D::translate(-view_x, -view_y), false);
+    }
+
+    let scale       = if slice { scale_x.max(scale_y) } else { scale_x.min(scale_y) };
+    let align_x     = if align.starts_with("xMin") { 0.0 } else if align.starts_with("xMax") { 1.0 } else { 0.5 };
+    let align_y     = if align.ends_with("YMin") { 0.0 } else if align.ends_with("YMax") { 1.0 } else { 0.5 };
+    let offset_x    = x + (width - view_width * scale) * align_x;
+    let offset_y    = y + (height - view_height * scale) * align_y;
+
+    (Transform2D::translate(offset_x, offset_y) * Transform2D::scale(scale, scale) * Transform2D::translate(-view_x, -view_y), slice)
+}
+
+///
+/// Returns the path for a rectangle
+///
+fn rect_path(x: f32, y: f32, width: f32, height: f32) -> Vec<PathOp> {
+    vec![
+        PathOp::Move(x, y),
+        PathOp::Line(x + width, y),
+        PathOp::Line(x + width, y + height),
+        PathOp::Line(x, y + height),
+        PathOp::ClosePath,
+    ]
+}
+
+///
+/// Returns the path for a rectangle with rounded corners
+///
+fn rounded_rect_path(x: f32, y: f32, width: f32, height: f32, rx: f32, ry: f32) -> Vec<PathOp> {
+    use std::f32::consts::PI;
+
+    vec![
+        PathOp::Move(x + rx, y),
+        PathOp::Line(x + width - rx, y),
+        PathOp::Arc((x + width - rx, y + ry), (rx, ry), 0.0, -PI/2.0, PI/2.0),
+        PathOp::Line(x + width, y + height - ry),
+        PathOp::Arc((x + width - rx, y + height - ry), (rx, ry), 0.0, 0.0, PI/2.0),
+        PathOp::Line(x + rx, y + height),
+        PathOp::Arc((x + rx, y + height - ry), (rx, ry), 0.0, PI/2.0, PI/2.0),
+        PathOp::Line(x, y + ry),
+        PathOp::Arc((x + rx, y + ry), (rx, ry), 0.0, PI, PI/2.0),
+        PathOp::ClosePath,
+    ]
+}
+
+///
+/// Returns the path for an ellipse
+///
+fn ellipse_path(cx: f32, cy: f32, rx: f32, ry: f32) -> Vec<PathOp> {
+    vec![
+        PathOp::Move(cx + rx, cy),
+        PathOp::Arc((cx, cy), (rx, ry), 0.0, 0.0, 2.0 * std::f32::consts::PI),
+        PathOp::ClosePath,
+    ]
+}
+
+///
+/// Returns the bounding box of a path (None if the path is empty)
+///
+fn path_bounds(path: &[PathOp]) -> Option<SvgBounds> {
+    let mut bounds: Option<SvgBounds>   = None;
+    let mut add_point                   = |(x, y): (f32, f32)| {
+        bounds = Some(match bounds {
+            None                                    => ((x, y), (x, y)),
+            Some(((min_x, min_y), (max_x, max_y)))  => ((min_x.min(x), min_y.min(y)), (max_x.max(x), max_y.max(y))),
+        });
+    };
+
+    for op in path.iter() {
+        match op {
+            PathOp::NewPath | PathOp::ClosePath         => { }
+            PathOp::Move(x, y) | PathOp::Line(x, y)     => add_point((*x, *y)),
+            PathOp::BezierCurve((cp1, cp2), end)        => { add_point(*cp1); add_point(*cp2); add_point(*end); }
+            PathOp::QuadraticCurve(cp, end)             => { add_point(*cp); add_point(*end); }
+
+            PathOp::Arc(center, radii, rotation, start_angle, sweep_angle) => {
+                add_point(arc_point(*center, *radii, *rotation, *start_angle));
+
+                for (cp1, cp2, end) in arc_to_bezier_curves(*center, *radii, *rotation, *start_angle, *sweep_angle) {
+                    add_point(cp1);
+                    add_point(cp2);
+                    add_point(end);
+                }
+            }
+        }
+    }
+
+    bounds
+}
+
+///
+/// Returns the transform that maps the unit square onto a bounding box (used for `objectBoundingBox` units), or None if the bounding box has no area
+///
+fn bounding_box_transform(bounds: Option<SvgBounds>) -> Option<Transform2D> {
+    let ((min_x, min_y), (max_x, max_y)) = bounds?;
+
+    if max_x > min_x && max_y > min_y {
+        Some(Transform2D([[max_x - min_x, 0.0, min_x], [0.0, max_y - min_y, min_y], [0.0, 0.0, 1.0]]))
+    } else {
+        None
+    }
+}
+
+///
+/// Applies a transform to a path
+///
+fn transform_path(path: Vec<PathOp>, transform: &Transform2D) -> Vec<PathOp> {
+    if transform == &Transform2D::identity() {
+        return path;
+    }
+
+    let point           = |(x, y): (f32, f32)| transform.transform_point(x, y);
+    let mut result      = Vec::with_capacity(path.len());
+    let mut has_point   = false;
+
+    for op in path {
+        match op {
+            PathOp::NewPath                         => { has_point = false; result.push(PathOp::NewPath); }
+            PathOp::ClosePath                       => result.push(PathOp::ClosePath),
+            PathOp::Move(x, y)                      => { let (x, y) = point((x, y)); has_point = true; result.push(PathOp::Move(x, y)); }
+            PathOp::Line(x, y)                      => { let (x, y) = point((x, y)); has_point = true; result.push(PathOp::Line(x, y)); }
+            PathOp::BezierCurve((cp1, cp2), end)    => { has_point = true; result.push(PathOp::BezierCurve((point(cp1), point(cp2)), point(end))); }
+            PathOp::QuadraticCurve(cp, end)         => { has_point = true; result.push(PathOp::QuadraticCurve(point(cp), point(end))); }
+
+            PathOp::Arc(center, radii, rotation, start_angle, sweep_angle) => {
+                // Arcs can't always be transformed (eg, by a skew), so they're converted to bezier curves
+                let (start_x, start_y) = point(arc_point(center, radii, rotation, start_angle));
+                result.push(if has_point { PathOp::Line(start_x, start_y) } else { PathOp::Move(start_x, start_y) });
+                has_point = true;
+
+                for (cp1, cp2, end) in arc_to_bezier_curves(center, radii, rotation, start_angle, sweep_angle) {
+                    result.push(PathOp::BezierCurve((point(cp1), point(cp2)), point(end)));
+                }
+            }
+        }
+    }
+
+    result
+}
+
+///
+/// Returns the instructions to set the fill or stroke to a colour
+///
+fn color_paint(color: Color, opacity: f32, target: SvgPaintTarget) -> Vec<Draw> {
+    let (_, _, _, alpha)    = color.to_rgba_components();
+    let color               = color.with_alpha(alpha * opacity);
+
+    match target {
+        SvgPaintTarget::Fill    => vec![Draw::FillColor(color)],
+        SvgPaintTarget::Stroke  => vec![Draw::StrokeColor(color)],
+    }
+}
+
+///
+/// Decodes a PNG image embedded in a data URI, returning its size and its pixels in RGBA format
+///
+fn load_image(href: &str) -> Option<(u32, u32, Vec<u8>)> {
+    // Data URIs have the format 'data:image/png;base64,<data>'
+    let data                = href.strip_prefix("data:")?;
+    let (header, data)      = data.split_at(data.find(',')?);
+    let mut header_parts    = header.split(';').map(|part| part.trim());
+
+    if !header_parts.next()?.eq_ignore_ascii_case("image/png") || !header_parts.any(|part| part == "base64") {
+        return None;
+    }
+
+    let png_data = decode_base64(&data[1..])?;
+
+    // Decode the PNG data to 8-bit colour
+    let mut decoder = png::Decoder::new(&png_data[..]);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+    let mut reader  = decoder.read_info().ok()?;
+    let mut buffer  = vec![0; reader.output_buffer_size()];
+    let info        = reader.next_frame(&mut buffer).ok()?;
+    let pixels      = &buffer[..info.buffer_size()];
+
+    let rgba = match info.color_type {
+        png::ColorType::Rgba            => pixels.to_vec(),
+        png::ColorType::Rgb             => pixels.chunks(3).flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha  => pixels.chunks(2).flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]]).collect(),
+        png::ColorType::Grayscale       => pixels.iter().flat_map(|grey| [*grey, *grey, *grey, 255]).collect(),
+        png::ColorType::Indexed         => { return None; }
+    };
+
+    if info.width == 0 || info.height == 0 || rgba.len() != (info.width as usize) * (info.height as usize) * 4 {
+        return None;
+    }
+
+    Some((info.width, info.height, rgba))
+}
+
+impl<'a, 'input> SvgImporter<'a, 'input> {
+    ///
+    /// Creates a new importer for a document
+    ///
+    fn new(document: &'a Document<'input>, viewport: (f32, f32)) -> SvgImporter<'a, 'input> {
+        let elements = document.descendants()
+            .filter_map(|node| node.attribute("id").map(|id| (id, node)))
+            .collect();
+
+        SvgImporter {
+            elements:           elements,
+            viewport:           viewport,
+            drawing:            vec![],
+            unsupported:        vec![],
+            gradients:          HashMap::new(),
+            next_gradient_id:   0,
+            next_texture_id:    0,
+            use_depth:          0,
+            drawn_elements:     0,
+        }
+    }
+
+    ///
+    /// Records that part of the document couldn't be imported
+    ///
+    fn report(&mut self, unsupported: SvgUnsupported) {
+        if !self.unsupported.contains(&unsupported) {
+            self.unsupported.push(unsupported);
+        }
+    }
+
+    ///
+    /// The length that percentages are relative to for lengths that aren't horizontal or vertical (eg, radii)
+    ///
+    fn viewport_diagonal(&self) -> f32 {
+        let (width, height) = self.viewport;
+
+        ((width*width + height*height) / 2.0).sqrt()
+    }
+
+    ///
+    /// Reads a length attribute from an element, returning the default value if it's not present or not valid
+    ///
+    fn length(&self, node: Node, name: &str, percent_of: f32, default: f32) -> f32 {
+        node.attribute(name)
+            .and_then(|length| parse_length(length, percent_of))
+            .unwrap_or(default)
+    }
+
+    ///
+    /// Works out the style of an element from its properties and the style of its parent element
+    ///
+    fn element_style(&self, node: Node, parent: &SvgStyle) -> SvgStyle {
+        let mut style = parent.clone();
+
+        // Invalid values are ignored, which also means that 'inherit' will leave the parent value in place
+        if let Some(color) = property(node, "color").and_then(parse_color)                  { style.color = color; }
+        if let Some(fill) = property(node, "fill").and_then(parse_paint)                    { style.fill = fill; }
+        if let Some(opacity) = property(node, "fill-opacity").and_then(parse_opacity)       { style.fill_opacity = opacity; }
+        if let Some(stroke) = property(node, "stroke").and_then(parse_paint)                { style.stroke = stroke; }
+        if let Some(opacity) = property(node, "stroke-opacity").and_then(parse_opacity)     { style.stroke_opacity = opacity; }
+        if let Some(opacity) = property(node, "opacity").and_then(parse_opacity)            { style.opacity *= opacity; }
+
+        if let Some(width) = property(node, "stroke-width").and_then(|width| parse_length(width, self.viewport_diagonal())).filter(|width| *width >= 0.0) {
+            style.stroke_width = width;
+        }
+
+        if let Some(limit) = property(node, "stroke-miterlimit").and_then(parse_number).filter(|limit| *limit >= 1.0) {
+            style.miter_limit = limit;
+        }
+
+        if let Some(offset) = property(node, "stroke-dashoffset").and_then(|offset| parse_length(offset, self.viewport_diagonal())) {
+            style.dash_offset = offset;
+        }
+
+        match property(node, "fill-rule").map(str::trim) {
+            Some("nonzero")     => style.fill_rule = WindingRule::NonZero,
+            Some("evenodd")     => style.fill_rule = WindingRule::EvenOdd,
+            _                   => { }
+        }
+
+        match property(node, "clip-rule").map(str::trim) {
+            Some("nonzero")     => style.clip_rule = WindingRule::NonZero,
+            Some("evenodd")     => style.clip_rule = WindingRule::EvenOdd,
+            _                   => { }
+        }
+
+        match property(node, "stroke-linecap").map(str::trim) {
+            Some("butt")        => style.line_cap = LineCap::Butt,
+            Some("round")       => style.line_cap = LineCap::Round,
+            Some("square")      => style.line_cap = LineCap::Square,
+            _                   => { }
+        }
+
+        match property(node, "stroke-linejoin").map(str::trim) {
+            Some("miter")       |
+            Some("miter-clip")  |
+            Some("arcs")        => style.line_join = LineJoin::Miter,
+            Some("round")       => style.line_join = LineJoin::Round,
+            Some("bevel")       => style.line_join = LineJoin::Bevel,
+            _                   => { }
+        }
+
+        match property(node, "visibility").map(str::trim) {
+            Some("visible")     => style.visible = true,
+            Some("hidden")      |
+            Some("collapse")    => style.visible = false,
+            _                   => { }
+        }
+
+        match property(node, "stroke-dasharray").map(str::trim) {
+            Some("none")        => style.dash_array = vec![],
+            Some(dash_array)    => {
+                // A list of non-negative lengths: odd-length lists are repeated, and lists that add up to 0 are the same as 'none'
+                let diagonal    = self.viewport_diagonal();
+                let dashes      = dash_array.split(|chr: char| chr == ',' || chr.is_whitespace())
+                    .filter(|dash| !dash.is_empty())
+                    .map(|dash| parse_length(dash, diagonal).filter(|dash| *dash >= 0.0))
+                    .collect::<Option<Vec<_>>>();
+
+                if let Some(mut dashes) = dashes {
+                    if dashes.len() % 2 == 1 {
+                        dashes.extend(dashes.clone());
+                    }
+
+                    style.dash_array = if dashes.iter().sum::<f32>() > 0.0 { dashes } else { vec![] };
+                }
+            }
+            None                => { }
+        }
+
+        style
+    }
+
+    ///
+    /// Sets up the transform and clip path for an element, returning true if the state was pushed and `end_element()` needs to restore it
+    ///
+    /// The extra transform is applied after the element's own transform (eg, for the position of a `<use>` element). The
+    /// bounds are the bounding box of the element, which are needed for clip paths that use `objectBoundingBox` units.
+    ///
+    fn begin_element(&mut self, node: Node<'a, 'input>, extra_transform: Transform2D, bounds: Option<SvgBounds>) -> bool {
+        let name = node.tag_name().name();
+
+        // Read the transform for this element
+        let transform = match node.attribute("transform") {
+            Some(transform) => parse_transform(transform).unwrap_or_else(|| {
+                self.report(SvgUnsupported::Attribute(name.to_string(), "transform".to_string()));
+                Transform2D::identity()
+            }),
+
+            None            => Transform2D::identity(),
+        };
+        let transform = transform * extra_transform;
+
+        // Some properties can't be represented with the canvas instructions
+        for property_name in UNSUPPORTED_PROPERTIES.iter() {
+            if property(node, property_name).map(|value| value.trim() != "none").unwrap_or(false) {
+                self.report(SvgUnsupported::Attribute(name.to_string(), property_name.to_string()));
+            }
+        }
+
+        // Set up the state for this element
+        let clip_path = property(node, "clip-path").map(str::trim).filter(|clip_path| *clip_path != "none");
+
+        if transform == Transform2D::identity() && clip_path.is_none() {
+            return false;
+        }
+
+        self.drawing.push(Draw::PushState);
+
+        if transform != Transform2D::identity() {
+            self.drawing.push(Draw::MultiplyTransform(transform));
+        }
+
+        if let Some(clip_path) = clip_path {
+            self.clip_to(node, clip_path, bounds);
+        }
+
+        true
+    }
+
+    ///
+    /// Restores the state after drawing an element
+    ///
+    fn end_element(&mut self, pushed_state: bool) {
+        if pushed_state {
+            self.drawing.push(Draw::PopState);
+        }
+    }
+
+    ///
+    /// Draws an element and its children
+    ///
+    fn draw_element(&mut self, node: Node<'a, 'input>, parent_style: &SvgStyle) {
+        // Text, comments and elements from other namespaces (eg, editor metadata) are skipped
+        if !is_svg_element(node) {
+            return;
+        }
+
+        if property(node, "display").map(|display| display.trim() == "none").unwrap_or(false) {
+            return;
+        }
+
+        // Once too many elements have been drawn, the import fails and nothing else is drawn
+        self.drawn_elements += 1;
+        if self.drawn_elements > MAX_DRAWN_ELEMENTS {
+            return;
+        }
+
+        let style   = self.element_style(node, parent_style);
+        let name    = node.tag_name().name();
+
+        match name {
+            "g" | "a"           => {
+                let pushed_state = self.begin_element(node, Transform2D::identity(), None);
+                self.draw_children(node, &style);
+                self.end_element(pushed_state);
+            }
+
+            "svg"               => {
+                let (width, height) = self.viewport;
+                let x               = self.length(node, "x", width, 0.0);
+                let y               = self.length(node, "y", height, 0.0);
+                let viewport_width  = self.length(node, "width", width, width);
+                let viewport_height = self.length(node, "height", height, height);
+
+                self.draw_viewport(node, &style, (x, y, viewport_width, viewport_height));
+            }
+
+            "switch"            => {
+                // Only the first child that the renderer supports is drawn: we assume that's any element that doesn't require an extension
+                let child = node.children().find(|child| is_svg_element(*child) && child.attribute("requiredExtensions").is_none());
+
+                if let Some(child) = child {
+                    let pushed_state = self.begin_element(node, Transform2D::identity(), None);
+                    self.draw_element(child, &style);
+                    self.end_element(pushed_state);
+                }
+            }
+
+            "path"              |
+            "rect"              |
+            "circle"            |
+            "ellipse"           |
+            "line"              |
+            "polyline"          |
+            "polygon"           => self.draw_shape(node, &style),
+
+            "image"             => self.draw_image(node, &style),
+            "use"               => self.draw_use(node, &style),
+
+            // Elements that are only drawn when they're referenced by another element, or which don't draw anything
+            "defs"              |
+            "symbol"            |
+            "linearGradient"    |
+            "radialGradient"    |
+            "clipPath"          |
+            "mask"              |
+            "filter"            |
+            "pattern"           |
+            "marker"            |
+            "title"             |
+            "desc"              |
+            "metadata"          => { }
+
+            // Stylesheets can't be imported (only style attributes are supported)
+            "style"             => {
+                if node.text().map(|text| !text.trim().is_empty()).unwrap_or(false) {
+                    self.report(SvgUnsupported::Element(name.to_string()));
+                }
+            }
+
+            _                   => self.report(SvgUnsupported::Element(name.to_string())),
+        }
+    }
+
+    ///
+    /// Draws the child elements of an element
+    ///
+    fn draw_children(&mut self, node: Node<'a, 'input>, style: &SvgStyle) {
+        for child in node.children() {
+            self.draw_element(child, style);
+        }
+    }
+
+    ///
+    /// Draws the content of an element that creates a new viewport (the root `<svg>` element, a nested `<svg>` element or a `<symbol>`)
+    ///
+    fn draw_viewport(&mut self, node: Node<'a, 'input>, style: &SvgStyle, (x, y, width, height): (f32, f32, f32, f32)) {
+        if width <= 0.0 || height <= 0.0 {
+            return;
+        }
+
+        // Map the view box to the viewport (content outside of the viewport is not clipped)
+        let (transform, viewport) = match parse_view_box(node) {
+            Some(view_box)  => (view_box_transform(view_box, (x, y, width, height), node.attribute("preserveAspectRatio")).0, (view_box.2, view_box.3)),
+            None            => (Transform2D::translate(x, y), (width, height)),
+        };
+
+        let parent_viewport = self.viewport;
+        self.viewport       = viewport;
+
+        let pushed_state = self.begin_element(node, transform, None);
+        self.draw_children(node, style);
+        self.end_element(pushed_state);
+
+        self.viewport = parent_viewport;
+    }
+
+    ///
+    /// Reads the path for a shape element (`path`, `rect`, `circle`, `ellipse`, `line`, `polyline` or `polygon`)
+    ///
+    fn shape_path(&mut self, node: Node<'a, 'input>) -> Vec<PathOp> {
+        let (width, height) = self.viewport;
+        let diagonal        = self.viewport_diagonal();
+        let name            = node.tag_name().name();
+
+        match name {
+            "path"      => {
+                let mut path = vec![];
+
+                if let Some(data) = node.attribute("d") {
+                    // The path is drawn up to the first error, like in a browser
+                    if !parse_path_data(data, &mut path) {
+                        self.report(SvgUnsupported::Attribute(name.to_string(), "d".to_string()));
+                    }
+                }
+
+                path
+            }
+
+            "rect"      => {
+                let x           = self.length(node, "x", width, 0.0);
+                let y           = self.length(node, "y", height, 0.0);
+                let rect_width  = self.length(node, "width", width, 0.0);
+                let rect_height = self.length(node, "height", height, 0.0);
+                let rx          = node.attribute("rx").and_then(|rx| parse_length(rx, width)).filter(|rx| *rx >= 0.0);
+                let ry          = node.attribute("ry").and_then(|ry| parse_length(ry, height)).filter(|ry| *ry >= 0.0);
+
+                // If only one radius is specified, it's used for both
+                let (rx, ry)    = match (rx, ry) {
+                    (Some(rx), Some(ry))    => (rx, ry),
+                    (Some(rx), None)        => (rx, rx),
+                    (None, Some(ry))        => (ry, ry),
+                    (None, None)            => (0.0, 0.0),
+                };
+                let rx          = rx.min(rect_width / 2.0);
+                let ry          = ry.min(rect_height / 2.0);
+
+                if rect_width <= 0.0 || rect_height <= 0.0 {
+                    vec![]
+                } else if rx > 0.0 && ry > 0.0 {
+                    rounded_rect_path(x, y, rect_width, rect_height, rx, ry)
+                } else {
+                    rect_path(x, y, rect_width, rect_height)
+                }
+            }
+
+            "circle"    => {
+                let cx  = self.length(node, "cx", width, 0.0);
+                let cy  = self.length(node, "cy", height, 0.0);
+                let r   = self.length(node, "r", diagonal, 0.0);
+
+                if r > 0.0 { ellipse_path(cx, cy, r, r) } else { vec![] }
+            }
+
+            "ellipse"   => {
+                let cx  = self.length(node, "cx", width, 0.0);
+                let cy  = self.length(node, "cy", height, 0.0);
+                let rx  = node.attribute("rx").and_then(|rx| parse_length(rx, width));
+                let ry  = node.attribute("ry").and_then(|ry| parse_length(ry, height));
+
+                match (rx.or(ry), ry.or(rx)) {
+                    (Some(rx), Some(ry)) if rx > 0.0 && ry > 0.0    => ellipse_path(cx, cy, rx, ry),
+                    _                                               => vec![],
+                }
+            }
+
+            "line"      => {
+                let x1 = self.length(node, "x1", width, 0.0);
+                let y1 = self.length(node, "y1", height, 0.0);
+                let x2 = self.length(node, "x2", width, 0.0);
+                let y2 = self.length(node, "y2", height, 0.0);
+
+                vec![PathOp::Move(x1, y1), PathOp::Line(x2, y2)]
+            }
+
+            "polyline"  |
+            "polygon"   => {
+                let points  = parse_points(node.attribute("points").unwrap_or(""));
+                let mut path = vec![];
+
+                for (idx, (x, y)) in points.into_iter().enumerate() {
+                    path.push(if idx == 0 { PathOp::Move(x, y) } else { PathOp::Line(x, y) });
+                }
+
+                if name == "polygon" && !path.is_empty() {
+                    path.push(PathOp::ClosePath);
+                }
+
+                path
+            }
+
+            _           => vec![],
+        }
+    }
+
+    ///
+    /// Draws a shape element
+    ///
+    fn draw_shape(&mut self, node: Node<'a, 'input>, style: &SvgStyle) {
+        let path = self.shape_path(node);
+        if path.is_empty() {
+            return;
+        }
+
+        let bounds          = path_bounds(&path);
+        let pushed_state    = self.begin_element(node, Transform2D::identity(), bounds);
+
+        if style.visible {
+            // Lines have no area so they're never filled
+            let fill    = if node.tag_name().name() == "line" { None } else { self.paint(node, &style.fill, style, style.fill_opacity, bounds, SvgPaintTarget::Fill) };
+            let stroke  = if style.stroke_width > 0.0 { self.paint(node, &style.stroke, style, style.stroke_opacity, bounds, SvgPaintTarget::Stroke) } else { None };
+
+            if fill.is_some() || stroke.is_some() {
+                self.drawing.push(Draw::Path(PathOp::NewPath));
+                self.drawing.extend(path.into_iter().map(Draw::Path));
+            }
+
+            if let Some(fill) = fill {
+                self.drawing.extend(fill);
+                self.drawing.push(Draw::WindingRule(style.fill_rule));
+                self.drawing.push(Draw::Fill);
+            }
+
+            if let Some(stroke) = stroke {
+                self.drawing.extend(stroke);
+                self.drawing.push(Draw::LineWidth(style.stroke_width));
+                self.drawing.push(Draw::LineJoin(style.line_join));
+                self.drawing.push(Draw::LineCap(style.line_cap));
+                self.drawing.push(Draw::MiterLimit(style.miter_limit));
+                self.drawing.push(Draw::NewDashPattern);
+
+                if !style.dash_array.is_empty() {
+                    self.drawing.extend(style.dash_array.iter().map(|dash| Draw::DashLength(*dash)));
+                    self.drawing.push(Draw::DashOffset(style.dash_offset));
+                }
+
+                self.drawing.push(Draw::Stroke);
+            }
+        }
+
+        self.end_element(pushed_state);
+    }
+
+    ///
+    /// Returns the instructions that set up the fill or stroke for a paint (None if nothing should be drawn)
+    ///
+    fn paint(&mut self, node: Node<'a, 'input>, paint: &SvgPaint, style: &SvgStyle, opacity: f32, bounds: Option<SvgBounds>, target: SvgPaintTarget) -> Option<Vec<Draw>> {
+        let opacity = opacity * style.opacity;
+
+        match paint {
+            SvgPaint::None                  => None,
+            SvgPaint::Color(color)          => Some(color_paint(*color, opacity, target)),
+            SvgPaint::CurrentColor          => Some(color_paint(style.color, opacity, target)),
+
+            SvgPaint::Url(id, fallback)     => {
+                let element = self.elements.get(id.as_str()).copied();
+
+                match element.map(|element| element.tag_name().name()) {
+                    Some("linearGradient")  |
+                    Some("radialGradient")  => self.gradient_paint(node, element.unwrap(), style, opacity, bounds, target),
+
+                    Some("pattern")         => {
+                        self.report(SvgUnsupported::Element("pattern".to_string()));
+                        fallback.map(|color| color_paint(color, opacity, target))
+                    }
+
+                    _                       => {
+                        self.report(SvgUnsupported::Reference(node.tag_name().name().to_string(), id.clone()));
+                        fallback.map(|color| color_paint(color, opacity, target))
+                    }
+                }
+            }
+        }
+    }
+
+    ///
+    /// Reads an attribute of a gradient, following `href` links to the gradients it inherits from if it's not set on the gradient itself
+    ///
+    fn gradient_attribute(&self, gradient: Node<'a, 'input>, name: &str) -> Option<&'a str> {
+        let mut node = gradient;
+
+        for _ in 0..MAX_HREF_DEPTH {
+            if let Some(value) = node.attribute(name) {
+                return Some(value);
+            }
+
+            node = href(node).and_then(|href| href.strip_prefix('#')).and_then(|id| self.elements.get(id).copied())?;
+        }
+
+        None
+    }
+
+    ///
+    /// Reads the stops for a gradient (which might be inherited from another gradient)
+    ///
+    fn gradient_stops(&self, gradient: Node<'a, 'input>, style: &SvgStyle) -> Vec<(f32, Color)> {
+        let is_stop     = |node: &Node| is_svg_element(*node) && node.tag_name().name() == "stop";
+        let mut node    = Some(gradient);
+
+        // Use the stops from the first gradient in the chain that has any
+        for _ in 0..MAX_HREF_DEPTH {
+            match node {
+                Some(gradient) if !gradient.children().any(|child| is_stop(&child)) => {
+                    node = href(gradient).and_then(|href| href.strip_prefix('#')).and_then(|id| self.elements.get(id).copied());
+                }
+
+                _ => { break; }
+            }
+        }
+
+        let node = if let Some(node) = node { node } else { return vec![]; };
+
+        // Offsets are clamped to the range 0-1 and can't be lower than the previous stop
+        let mut stops       = vec![];
+        let mut last_offset = 0.0f32;
+
+        for stop in node.children().filter(is_stop) {
+            let offset  = stop.attribute("offset").and_then(parse_opacity).unwrap_or(0.0).max(last_offset);
+            let color   = match property(stop, "stop-color").map(str::trim) {
+                Some("currentColor")    => property(stop, "color").and_then(parse_color).unwrap_or(style.color),
+                Some(color)             => parse_color(color).unwrap_or(Color::Rgba(0.0, 0.0, 0.0, 1.0)),
+                None                    => Color::Rgba(0.0, 0.0, 0.0, 1.0),
+            };
+            let opacity = property(stop, "stop-opacity").and_then(parse_opacity).unwrap_or(1.0);
+
+            let (_, _, _, alpha) = color.to_rgba_components();
+            stops.push((offset, color.with_alpha(alpha * opacity)));
+
+            last_offset = offset;
+        }
+
+        stops
+    }
+
+    ///
+    /// Returns the instructions that set up a gradient fill or stroke, defining the gradient if it hasn't been used before
+    ///
+    fn gradient_paint(&mut self, node: Node<'a, 'input>, gradient: Node<'a, 'input>, style: &SvgStyle, opacity: f32, bounds: Option<SvgBounds>, target: SvgPaintTarget) -> Option<Vec<Draw>> {
+        let element_name    = node.tag_name().name();
+        let stops           = self.gradient_stops(gradient, style);
+
+        // Gradients with no stops aren't painted, and gradients with one stop are a solid colour
+        match stops.len() {
+            0 => { return None; }
+            1 => { return Some(color_paint(stops[0].1, opacity, target)); }
+            _ => { }
+        }
+
+        // Work out the coordinate scheme for the gradient
+        let bounding_box_units  = self.gradient_attribute(gradient, "gradientUnits").map(str::trim) != Some("userSpaceOnUse");
+        let units_transform     = if bounding_box_units {
+            // Nothing is painted if the element has no area (SVG says this should be an error)
+            bounding_box_transform(bounds)?
+        } else {
+            Transform2D::identity()
+        };
+
+        let gradient_transform  = match self.gradient_attribute(gradient, "gradientTransform") {
+            Some(transform) => parse_transform(transform).unwrap_or_else(|| {
+                self.report(SvgUnsupported::Attribute(gradient.tag_name().name().to_string(), "gradientTransform".to_string()));
+                Transform2D::identity()
+            }),
+            None            => Transform2D::identity(),
+        };
+        let transform           = units_transform * gradient_transform;
+
+        // Radial gradients can only be used for fills, so strokes are drawn using the first colour instead
+        let is_radial           = gradient.tag_name().name() == "radialGradient";
+
+        if is_radial && target == SvgPaintTarget::Stroke {
+            self.report(SvgUnsupported::Attribute(element_name.to_string(), "stroke".to_string()));
+            return Some(color_paint(stops[0].1, opacity, target));
+        }
+
+        let (width, height)     = if bounding_box_units { (1.0, 1.0) } else { self.viewport };
+        let diagonal            = if bounding_box_units { 1.0 } else { self.viewport_diagonal() };
+        let length              = |name: &str, percent_of: f32, default: f32| {
+            self.gradient_attribute(gradient, name)
+                .and_then(|length| parse_length(length, percent_of))
+                .unwrap_or(default * percent_of)
+        };
+
+        // Read the shape of the gradient
+        let start       = (length("x1", width, 0.0), length("y1", height, 0.0));
+        let end         = (length("x2", width, 1.0), length("y2", height, 0.0));
+        let center      = (length("cx", width, 0.5), length("cy", height, 0.5));
+        let radius      = length("r", diagonal, 0.5);
+        let fx          = self.gradient_attribute(gradient, "fx").and_then(|fx| parse_length(fx, width));
+        let fy          = self.gradient_attribute(gradient, "fy").and_then(|fy| parse_length(fy, height));
+        let focus       = if fx.is_some() || fy.is_some() { Some((fx.unwrap_or(center.0), fy.unwrap_or(center.1))) } else { None };
+
+        let spread      = match self.gradient_attribute(gradient, "spreadMethod").map(str::trim) {
+            Some("reflect") => GradientSpread::Reflect,
+            Some("repeat")  => GradientSpread::Repeat,
+            _               => GradientSpread::Pad,
+        };
+
+        // Define the gradient the first time it's used with these stops and this opacity
+        let stop_bits   = stops.iter().map(|(offset, color)| { let (r, g, b, a) = color.to_rgba_components(); [offset.to_bits(), r.to_bits(), g.to_bits(), b.to_bits(), a.to_bits()] }).collect();
+        let key         = (gradient.attribute("id").unwrap_or("").to_string(), stop_bits, opacity.to_bits());
+        let gradient_id = if let Some(gradient_id) = self.gradients.get(&key) {
+            *gradient_id
+        } else {
+            let gradient_id = GradientId(self.next_gradient_id);
+            self.next_gradient_id += 1;
+
+            let stop_color = |color: &Color| { let (_, _, _, alpha) = color.to_rgba_components(); color.with_alpha(alpha * opacity) };
+
+            self.drawing.push(Draw::Gradient(gradient_id, GradientOp::Create(stop_color(&stops[0].1))));
+            for (offset, color) in stops.iter() {
+                self.drawing.push(Draw::Gradient(gradient_id, GradientOp::AddStop(*offset, stop_color(color))));
+            }
+
+            if spread != GradientSpread::Pad {
+                self.drawing.push(Draw::Gradient(gradient_id, GradientOp::Spread(spread)));
+            }
+
+            self.gradients.insert(key, gradient_id);
+            gradient_id
+        };
+
+        // Set the fill or stroke
+        match (target, is_radial) {
+            (SvgPaintTarget::Fill, false)   => {
+                let mut paint = vec![Draw::FillGradient(gradient_id, start, end)];
+                if transform != Transform2D::identity() { paint.push(Draw::FillTransform(transform)); }
+
+                Some(paint)
+            }
+
+            (SvgPaintTarget::Fill, true)    => {
+                let mut paint = vec![Draw::FillRadialGradient(gradient_id, center, radius, focus)];
+                if transform != Transform2D::identity() { paint.push(Draw::FillTransform(transform)); }
+
+                Some(paint)
+            }
+
+            (SvgPaintTarget::Stroke, _)     => {
+                // Stroke gradients can't be transformed, so the transform is applied to the end points instead
+                let start   = transform.transform_point(start.0, start.1);
+                let end     = transform.transform_point(end.0, end.1);
+
+                Some(vec![Draw::StrokeGradient(gradient_id, start, end)])
+            }
+        }
+    }
+
+    ///
+    /// Clips to the `<clipPath>` element referenced by an element's `clip-path` property
+    ///
+    fn clip_to(&mut self, node: Node<'a, 'input>, clip_path: &str, bounds: Option<SvgBounds>) {
+        let element_name    = node.tag_name().name();
+        let clip_node       = url_reference(clip_path)
+            .and_then(|id| self.elements.get(id).copied())
+            .filter(|clip_node| clip_node.tag_name().name() == "clipPath");
+
+        let clip_node = if let Some(clip_node) = clip_node {
+            clip_node
+        } else {
+            self.report(SvgUnsupported::Reference(element_name.to_string(), clip_path.to_string()));
+            return;
+        };
+
+        // Work out the coordinates used by the clip path
+        let mut transform = match clip_node.attribute("transform").map(parse_transform) {
+            Some(Some(transform))   => transform,
+            Some(None)              => { self.report(SvgUnsupported::Attribute("clipPath".to_string(), "transform".to_string())); Transform2D::identity() }
+            None                    => Transform2D::identity(),
+        };
+
+        if clip_node.attribute("clipPathUnits").map(str::trim) == Some("objectBoundingBox") {
+            if let Some(bounding_box) = bounding_box_transform(bounds) {
+                transform = transform * bounding_box;
+            } else {
+                self.report(SvgUnsupported::Attribute(element_name.to_string(), "clip-path".to_string()));
+                return;
+            }
+        }
+
+        if property(clip_node, "clip-path").is_some() {
+            self.report(SvgUnsupported::Attribute("clipPath".to_string(), "clip-path".to_string()));
+        }
+
+        // Read the shapes that make up the clip path
+        let clip_style      = self.element_style(clip_node, &SvgStyle::default());
+        let mut paths       = vec![];
+        let mut clip_rule   = None;
+
+        for child in clip_node.children() {
+            if !is_svg_element(child) || property(child, "display").map(|display| display.trim() == "none").unwrap_or(false) {
+                continue;
+            }
+
+            let child_style = self.element_style(child, &clip_style);
+            if !child_style.visible {
+                continue;
+            }
+
+            let child_transform = match child.attribute("transform").map(parse_transform) {
+                Some(Some(child_transform)) => transform * child_transform,
+                Some(None)                  => { self.report(SvgUnsupported::Attribute(child.tag_name().name().to_string(), "transform".to_string())); transform }
+                None                        => transform,
+            };
+
+            // Clip paths can contain shapes or `<use>` elements that refer directly to shapes
+            let (shape, shape_transform) = match child.tag_name().name() {
+                "path" | "rect" | "circle" | "ellipse" | "line" | "polyline" | "polygon" => (child, child_transform),
+
+                "use" => {
+                    let target = href(child).and_then(|href| href.strip_prefix('#')).and_then(|id| self.elements.get(id).copied());
+
+                    match target {
+                        Some(target) if matches!(target.tag_name().name(), "path" | "rect" | "circle" | "ellipse" | "line" | "polyline" | "polygon") => {
+                            let (width, height) = self.viewport;
+                            let x               = self.length(child, "x", width, 0.0);
+                            let y               = self.length(child, "y", height, 0.0);
+                            let target_transform = target.attribute("transform").and_then(parse_transform).unwrap_or_else(Transform2D::identity);
+
+                            (target, child_transform * Transform2D::translate(x, y) * target_transform)
+                        }
+
+                        _ => {
+                            self.report(SvgUnsupported::Reference("clipPath".to_string(), href(child).unwrap_or("").to_string()));
+                            continue;
+                        }
+                    }
+                }
+
+                "title" | "desc" | "metadata" => { continue; }
+
+                name => {
+                    self.report(SvgUnsupported::Element(name.to_string()));
+                    continue;
+                }
+            };
+
+            let path = self.shape_path(shape);
+            if !path.is_empty() {
+                clip_rule = clip_rule.or(Some(self.element_style(shape, &child_style).clip_rule));
+                paths.push(transform_path(path, &shape_transform));
+            }
+        }
+
+        // Combine the shapes into a single path and clip to it (an empty clip path hides the element entirely)
+        self.drawing.push(Draw::Path(PathOp::NewPath));
+
+        for (idx, path) in paths.into_iter().enumerate() {
+            if idx > 0 {
+                self.drawing.push(Draw::StorePath);
+                self.drawing.push(Draw::Path(PathOp::NewPath));
+            }
+
+            self.drawing.extend(path.into_iter().map(Draw::Path));
+
+            if idx > 0 {
+                self.drawing.push(Draw::CombinePath(PathArithmetic::Union));
+            }
+        }
+
+        self.drawing.push(Draw::WindingRule(clip_rule.unwrap_or(WindingRule::NonZero)));
+        self.drawing.push(Draw::Clip);
+        self.drawing.push(Draw::Path(PathOp::NewPath));
+    }
+
+    ///
+    /// Draws an `<image>` element
+    ///
+    fn draw_image(&mut self, node: Node<'a, 'input>, style: &SvgStyle) {
+        if !style.visible {
+            return;
+        }
+
+        // Load the image
+        let image_href = href(node).unwrap_or("");
+
+        let (image_width, image_height, pixels) = if let Some(image) = load_image(image_href) {
+            image
+        } else {
+            // Describe data URIs by their type rather than including all of the data
+            let description = if image_href.starts_with("data:") {
+                image_href.split(|chr| chr == ',' || chr == ';').next().unwrap_or("data:").to_string()
+            } else {
+                image_href.to_string()
+            };
+
+            self.report(SvgUnsupported::Image(description));
+            return;
+        };
+
+        // Work out where the image should go
+        let (width, height) = self.viewport;
+        let x               = self.length(node, "x", width, 0.0);
+        let y               = self.length(node, "y", height, 0.0);
+        let width           = self.length(node, "width", width, image_width as f32);
+        let height          = self.length(node, "height", height, image_height as f32);
+
+        if width <= 0.0 || height <= 0.0 {
+            return;
+        }
+
+        let (placement, slice)  = view_box_transform((0.0, 0.0, image_width as f32, image_height as f32), (x, y, width, height), node.attribute("preserveAspectRatio"));
+        let (x1, y1)            = placement.transform_point(0.0, 0.0);
+        let (x2, y2)            = placement.transform_point(image_width as f32, image_height as f32);
+
+        // Create a texture for the image
+        let texture_id = TextureId(self.next_texture_id);
+        self.next_texture_id += 1;
+
+        self.drawing.push(Draw::Texture(texture_id, TextureOp::Create(TextureSize(image_width, image_height), TextureFormat::Rgba)));
+        self.drawing.push(Draw::Texture(texture_id, TextureOp::SetBytes(TexturePosition(0, 0), TextureSize(image_width, image_height), Arc::new(pixels))));
+
+        if style.opacity < 1.0 {
+            self.drawing.push(Draw::Texture(texture_id, TextureOp::FillTransparency(style.opacity)));
+        }
+
+        // Draw the image (SVG images have their first row at the top, which has the lower y coordinate)
+        let pushed_state = self.begin_element(node, Transform2D::identity(), Some(((x, y), (x + width, y + height))));
+
+        if slice {
+            self.drawing.push(Draw::PushState);
+            self.drawing.push(Draw::Path(PathOp::NewPath));
+            self.drawing.extend(rect_path(x, y, width, height).into_iter().map(Draw::Path));
+            self.drawing.push(Draw::Clip);
+        }
+
+        self.drawing.push(Draw::Path(PathOp::NewPath));
+        self.drawing.extend(rect_path(x1, y1, x2 - x1, y2 - y1).into_iter().map(Draw::Path));
+        self.drawing.push(Draw::FillTexture(texture_id, (x1, y2), (x2, y1)));
+        self.drawing.push(Draw::Fill);
+
+        if slice {
+            self.drawing.push(Draw::PopState);
+        }
+
+        self.end_element(pushed_state);
+    }
+
+    ///
+    /// Draws a `<use>` element
+    ///
+    fn draw_use(&mut self, node: Node<'a, 'input>, style: &SvgStyle) {
+        let reference   = href(node).unwrap_or("");
+        let target      = reference.strip_prefix('#').and_then(|id| self.elements.get(id).copied());
+
+        // Elements that are missing, or which contain the use element (and so would be drawn forever) can't be drawn
+        let target = match target {
+            Some(target) if self.use_depth < MAX_USE_DEPTH && !node.ancestors().any(|ancestor| ancestor == target) => target,
+
+            _ => {
+                self.report(SvgUnsupported::Reference(node.tag_name().name().to_string(), reference.to_string()));
+                return;
+            }
+        };
+
+        let (width, height) = self.viewport;
+        let x               = self.length(node, "x", width, 0.0);
+        let y               = self.length(node, "y", height, 0.0);
+
+        self.use_depth += 1;
+        let pushed_state = self.begin_element(node, Transform2D::translate(x, y), None);
+
+        if target.tag_name().name() == "symbol" && is_svg_element(target) {
+            // Symbols are drawn into a viewport the size of the use element
+            let symbol_width    = node.attribute("width").or_else(|| target.attribute("width")).and_then(|symbol_width| parse_length(symbol_width, width)).unwrap_or(width);
+            let symbol_height   = node.attribute("height").or_else(|| target.attribute("height")).and_then(|symbol_height| parse_length(symbol_height, height)).unwrap_or(height);
+            let symbol_style    = self.element_style(target, style);
+
+            self.draw_viewport(target, &symbol_style, (0.0, 0.0, symbol_width, symbol_height));
+        } else {
+            self.draw_element(target, style);
+        }
+
+        self.end_element(pushed_state);
+        self.use_depth -= 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::svg_format::*;
+
+    fn contains_sequence(drawing: &[Draw], sequence: &[Draw]) -> bool {
+        drawing.windows(sequence.len()).any(|window| window == sequence)
+    }
+
+    #[test]
+    fn not_an_svg_document() {
+        assert!(drawing_from_svg("<html></html>") == Err(SvgImportError::NotAnSvgDocument));
+        assert!(matches!(drawing_from_svg("<svg"), Err(SvgImportError::InvalidXml(_))));
+    }
+
+    #[test]
+    fn document_is_flipped() {
+        let svg = drawing_from_svg(r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100"></svg>"#).unwrap();
+
+        assert!(svg.size == (200.0, 100.0));
+        assert!(svg.drawing == vec![Draw::PushState, Draw::MultiplyTransform(Transform2D([[1.0, 0.0, 0.0], [0.0, -1.0, 100.0], [0.0, 0.0, 1.0]])), Draw::PopState]);
+        assert!(svg.unsupported.is_empty());
+    }
+
+    #[test]
+    fn view_box_sets_size() {
+        let svg = drawing_from_svg(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 20" width="100" height="100"></svg>"#).unwrap();
+
+        assert!(svg.size == (100.0, 100.0));
+        assert!(svg.drawing.contains(&Draw::MultiplyTransform(Transform2D::translate(25.0, 0.0) * Transform2D::scale(5.0, 5.0) * Transform2D::translate(0.0, 0.0))));
+
+        let svg = drawing_from_svg(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 20" width="100"></svg>"#).unwrap();
+
+        assert!(svg.size == (100.0, 200.0));
+    }
+
+    #[test]
+    fn fill_rect() {
+        let svg = drawing_from_svg(r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100"><rect x="10" y="20" width="30" height="40" fill="red" /></svg>"#).unwrap();
+
+        assert!(contains_sequence(&svg.drawing, &[
+            Draw::Path(PathOp::NewPath),
+            Draw::Path(PathOp::Move(10.0, 20.0)),
+            Draw::Path(PathOp::Line(40.0, 20.0)),
+            Draw::Path(PathOp::Line(40.0, 60.0)),
+            Draw::Path(PathOp::Line(10.0, 60.0)),
+            Draw::Path(PathOp::ClosePath),
+            Draw::FillColor(Color::Rgba(1.0, 0.0, 0.0, 1.0)),
+            Draw::WindingRule(WindingRule::NonZero),
+            Draw::Fill,
+        ]));
+        assert!(!svg.drawing.contains(&Draw::Stroke));
+    }
+
+    #[test]
+    fn stroke_line() {
+        let svg = drawing_from_svg(r#"<svg xmlns="http://www.w3.org/2000/svg"><line x1="0" y1="0" x2="10" y2="10" style="stroke: blue; stroke-width: 2; stroke-dasharray: 1 2" /></svg>"#).unwrap();
+
+        assert!(contains_sequence(&svg.drawing, &[
+            Draw::StrokeColor(Color::Rgba(0.0, 0.0, 1.0, 1.0)),
+            Draw::LineWidth(2.0),
+            Draw::LineJoin(LineJoin::Miter),
+            Draw::LineCap(LineCap::Butt),
+            Draw::MiterLimit(4.0),
+            Draw::NewDashPattern,
+            Draw::DashLength(1.0),
+            Draw::DashLength(2.0),
+            Draw::DashOffset(0.0),
+            Draw::Stroke,
+        ]));
+        assert!(!svg.drawing.contains(&Draw::Fill));
+    }
+
+    #[test]
+    fn group_transform_and_opacity() {
+        let svg = drawing_from_svg(r##"<svg xmlns="http://www.w3.org/2000/svg"><g transform="translate(5, 5)" opacity="0.5"><circle r="1" fill="#000" /></g></svg>"##).unwrap();
+
+        assert!(contains_sequence(&svg.drawing, &[Draw::PushState, Draw::MultiplyTransform(Transform2D::translate(5.0, 5.0))]));
+        assert!(svg.drawing.contains(&Draw::FillColor(Color::Rgba(0.0, 0.0, 0.0, 0.5))));
+        assert!(svg.drawing.iter().filter(|draw| **draw == Draw::PopState).count() == 2);
+    }
+
+    #[test]
+    fn linear_gradient() {
+        let svg = drawing_from_svg(r##"<svg xmlns="http://www.w3.org/2000/svg">
+                <defs>
+                    <linearGradient id="gradient">
+                        <stop offset="0" stop-color="red" />
+                        <stop offset="100%" stop-color="blue" />
+                    </linearGradient>
+                </defs>
+                <rect x="10" y="10" width="20" height="10" fill="url(#gradient)" />
+                <rect x="10" y="10" width="20" height="10" fill="url(#gradient)" />
+            </svg>"##).unwrap();
+
+        assert!(matches!(svg.drawing[1], Draw::Namespace(_)));
+        assert!(svg.drawing.contains(&Draw::Gradient(GradientId(0), GradientOp::AddStop(1.0, Color::Rgba(0.0, 0.0, 1.0, 1.0)))));
+        assert!(contains_sequence(&svg.drawing, &[
+            Draw::FillGradient(GradientId(0), (0.0, 0.0), (1.0, 0.0)),
+            Draw::FillTransform(Transform2D([[20.0, 0.0, 10.0], [0.0, 10.0, 10.0], [0.0, 0.0, 1.0]])),
+        ]));
+
+        // The gradient is only defined once
+        assert!(svg.drawing.iter().filter(|draw| matches!(draw, Draw::Gradient(_, GradientOp::Create(_)))).count() == 1);
+    }
+
+    #[test]
+    fn gradient_with_current_color() {
+        let svg = drawing_from_svg(r##"<svg xmlns="http://www.w3.org/2000/svg">
+                <defs>
+                    <linearGradient id="gradient">
+                        <stop offset="0" stop-color="currentColor" />
+                        <stop offset="100%" stop-color="blue" />
+                    </linearGradient>
+                </defs>
+                <rect width="20" height="10" fill="url(#gradient)" color="red" />
+                <rect width="20" height="10" fill="url(#gradient)" color="lime" />
+                <rect width="20" height="10" fill="url(#gradient)" color="red" />
+            </svg>"##).unwrap();
+
+        // The gradient is defined once for each colour
+        assert!(svg.drawing.iter().filter(|draw| matches!(draw, Draw::Gradient(_, GradientOp::Create(_)))).count() == 2);
+        assert!(svg.drawing.contains(&Draw::Gradient(GradientId(0), GradientOp::Create(Color::Rgba(1.0, 0.0, 0.0, 1.0)))));
+        assert!(svg.drawing.contains(&Draw::Gradient(GradientId(1), GradientOp::Create(Color::Rgba(0.0, 1.0, 0.0, 1.0)))));
+        assert!(svg.drawing.iter().filter(|draw| matches!(draw, Draw::FillGradient(GradientId(0), _, _))).count() == 2);
+    }
+
+    #[test]
+    fn clip_path() {
+        let svg = drawing_from_svg(r#"<svg xmlns="http://www.w3.org/2000/svg">
+                <clipPath id="clip">
+                    <rect width="10" height="10" />
+                    <circle cx="10" cy="10" r="5" />
+                </clipPath>
+                <rect width="20" height="20" clip-path="url(#clip)" />
+            </svg>"#).unwrap();
+
+        assert!(contains_sequence(&svg.drawing, &[Draw::Path(PathOp::ClosePath), Draw::StorePath, Draw::Path(PathOp::NewPath)]));
+        assert!(contains_sequence(&svg.drawing, &[Draw::CombinePath(PathArithmetic::Union), Draw::WindingRule(WindingRule::NonZero), Draw::Clip]));
+        assert!(svg.unsupported.is_empty());
+    }
+
+    #[test]
+    fn embedded_image() {
+        let image   = png_data_uri(TextureSize(2, 2), &[255; 16]).unwrap();
+        let svg     = drawing_from_svg(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"><image width="10" height="10" xlink:href="{}" /></svg>"#, image)).unwrap();
+
+        assert!(svg.drawing.contains(&Draw::Texture(TextureId(0), TextureOp::Create(TextureSize(2, 2), TextureFormat::Rgba))));
+        assert!(svg.drawing.contains(&Draw::Texture(TextureId(0), TextureOp::SetBytes(TexturePosition(0, 0), TextureSize(2, 2), Arc::new(vec![255; 16])))));
+        assert!(contains_sequence(&svg.drawing, &[Draw::FillTexture(TextureId(0), (0.0, 10.0), (10.0, 0.0)), Draw::Fill]));
+        assert!(svg.unsupported.is_empty());
+    }
+
+    #[test]
+    fn use_element() {
+        let svg = drawing_from_svg(r##"<svg xmlns="http://www.w3.org/2000/svg">
+                <defs><rect id="square" width="1" height="1" /></defs>
+                <use href="#square" x="5" />
+                <g id="loop"><use href="#loop" /></g>
+            </svg>"##).unwrap();
+
+        assert!(contains_sequence(&svg.drawing, &[Draw::PushState, Draw::MultiplyTransform(Transform2D::translate(5.0, 0.0)), Draw::Path(PathOp::NewPath), Draw::Path(PathOp::Move(0.0, 0.0))]));
+        assert!(svg.unsupported == vec![SvgUnsupported::Reference("use".to_string(), "#loop".to_string())]);
+    }
+
+    #[test]
+    fn nested_use_elements_are_limited() {
+        // Each group draws the one before it ten times, so the last group would draw 10^8 elements
+        let mut groups = "<g id=\"g0\" />".to_string();
+        for idx in 1..=8 {
+            groups.push_str(&format!("<g id=\"g{}\">{}</g>", idx, (0..10).map(|_| format!("<use href=\"#g{}\" />", idx-1)).collect::<String>()));
+        }
+
+        let svg = format!(r##"<svg xmlns="http://www.w3.org/2000/svg"><defs>{}</defs><use href="#g8" /></svg>"##, groups);
+
+        assert!(drawing_from_svg(&svg) == Err(SvgImportError::TooManyElements));
+    }
+
+    #[test]
+    fn report_unsupported() {
+        let svg = drawing_from_svg(r##"<svg xmlns="http://www.w3.org/2000/svg">
+                <filter id="blur"><feGaussianBlur stdDeviation="2" /></filter>
+                <text x="0" y="10">Hello</text>
+                <rect width="10" height="10" filter="url(#blur)" />
+                <rect width="10" height="10" fill="url(#missing) green" />
+                <image width="10" height="10" href="picture.jpg" />
+            </svg>"##).unwrap();
+
+        assert!(svg.unsupported == vec![
+            SvgUnsupported::Element("text".to_string()),
+            SvgUnsupported::Attribute("rect".to_string(), "filter".to_string()),
+            SvgUnsupported::Reference("rect".to_string(), "missing".to_string()),
+            SvgUnsupported::Image("picture.jpg".to_string()),
+        ]);
+        assert!(svg.drawing.contains(&Draw::FillColor(Color::Rgba(0.0, 128.0/255.0, 0.0, 1.0))));
+    }
+}
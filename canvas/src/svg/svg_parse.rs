@@ -0,0 +1,670 @@
+use crate::path::*;
+use crate::color::*;
+use crate::transform2d::*;
+
+use std::f64::consts::PI;
+
+///
+/// The named colours that can be used in an SVG document
+///
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff), ("antiquewhite", 0xfaebd7), ("aqua", 0x00ffff), ("aquamarine", 0x7fffd4), ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc), ("bisque", 0xffe4c4), ("black", 0x000000), ("blanchedalmond", 0xffebcd), ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2), ("brown", 0xa52a2a), ("burlywood", 0xdeb887), ("cadetblue", 0x5f9ea0), ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e), ("coral", 0xff7f50), ("cornflowerblue", 0x6495ed), ("cornsilk", 0xfff8dc), ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff), ("darkblue", 0x00008b), ("darkcyan", 0x008b8b), ("darkgoldenrod", 0xb8860b), ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400), ("darkgrey", 0xa9a9a9), ("darkkhaki", 0xbdb76b), ("darkmagenta", 0x8b008b), ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00), ("darkorchid", 0x9932cc), ("darkred", 0x8b0000), ("darksalmon", 0xe9967a), ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b), ("darkslategray", 0x2f4f4f), ("darkslategrey", 0x2f4f4f), ("darkturquoise", 0x00ced1), ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493), ("deepskyblue", 0x00bfff), ("dimgray", 0x696969), ("dimgrey", 0x696969), ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222), ("floralwhite", 0xfffaf0), ("forestgreen", 0x228b22), ("fuchsia", 0xff00ff), ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff), ("gold", 0xffd700), ("goldenrod", 0xdaa520), ("gray", 0x808080), ("grey", 0x808080),
+    ("green", 0x008000), ("greenyellow", 0xadff2f), ("honeydew", 0xf0fff0), ("hotpink", 0xff69b4), ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082), ("ivory", 0xfffff0), ("khaki", 0xf0e68c), ("lavender", 0xe6e6fa), ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00), ("lemonchiffon", 0xfffacd), ("lightblue", 0xadd8e6), ("lightcoral", 0xf08080), ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2), ("lightgray", 0xd3d3d3), ("lightgreen", 0x90ee90), ("lightgrey", 0xd3d3d3), ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a), ("lightseagreen", 0x20b2aa), ("lightskyblue", 0x87cefa), ("lightslategray", 0x778899), ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de), ("lightyellow", 0xffffe0), ("lime", 0x00ff00), ("limegreen", 0x32cd32), ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff), ("maroon", 0x800000), ("mediumaquamarine", 0x66cdaa), ("mediumblue", 0x0000cd), ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db), ("mediumseagreen", 0x3cb371), ("mediumslateblue", 0x7b68ee), ("mediumspringgreen", 0x00fa9a), ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585), ("midnightblue", 0x191970), ("mintcream", 0xf5fffa), ("mistyrose", 0xffe4e1), ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead), ("navy", 0x000080), ("oldlace", 0xfdf5e6), ("olive", 0x808000), ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500), ("orangered", 0xff4500), ("orchid", 0xda70d6), ("palegoldenrod", 0xeee8aa), ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee), ("palevioletred", 0xdb7093), ("papayawhip", 0xffefd5), ("peachpuff", 0xffdab9), ("peru", 0xcd853f),
+    ("pink", 0xffc0cb), ("plum", 0xdda0dd), ("powderblue", 0xb0e0e6), ("purple", 0x800080), ("rebeccapurple", 0x663399),
+    ("red", 0xff0000), ("rosybrown", 0xbc8f8f), ("royalblue", 0x4169e1), ("saddlebrown", 0x8b4513), ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460), ("seagreen", 0x2e8b57), ("seashell", 0xfff5ee), ("sienna", 0xa0522d), ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb), ("slateblue", 0x6a5acd), ("slategray", 0x708090), ("slategrey", 0x708090), ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f), ("steelblue", 0x4682b4), ("tan", 0xd2b48c), ("teal", 0x008080), ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347), ("turquoise", 0x40e0d0), ("violet", 0xee82ee), ("wheat", 0xf5deb3), ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5), ("yellow", 0xffff00), ("yellowgreen", 0x9acd32),
+];
+
+///
+/// Reads the numbers (and flags and commands) from an SVG attribute such as a path or a list of points
+///
+pub (super) struct SvgNumbers<'a> {
+    /// The bytes making up the attribute
+    bytes: &'a [u8],
+
+    /// The position of the next byte to read
+    pos: usize,
+}
+
+impl<'a> SvgNumbers<'a> {
+    ///
+    /// Reads the numbers from a string
+    ///
+    pub (super) fn new(data: &'a str) -> SvgNumbers<'a> {
+        SvgNumbers {
+            bytes:  data.as_bytes(),
+            pos:    0,
+        }
+    }
+
+    ///
+    /// Skips any whitespace and commas that separate the values in the attribute
+    ///
+    fn skip_separators(&mut self) {
+        while self.pos < self.bytes.len() && (self.bytes[self.pos].is_ascii_whitespace() || self.bytes[self.pos] == b',') {
+            self.pos += 1;
+        }
+    }
+
+    ///
+    /// True if there's nothing left to read in the attribute
+    ///
+    pub (super) fn at_end(&mut self) -> bool {
+        self.skip_separators();
+        self.pos >= self.bytes.len()
+    }
+
+    ///
+    /// Returns the next character if it's a letter (a path command or a unit)
+    ///
+    pub (super) fn peek_letter(&mut self) -> Option<u8> {
+        self.skip_separators();
+        self.bytes.get(self.pos).copied().filter(|chr| chr.is_ascii_alphabetic())
+    }
+
+    ///
+    /// Reads the next character, which must be a letter
+    ///
+    pub (super) fn letter(&mut self) -> Option<u8> {
+        let letter = self.peek_letter()?;
+        self.pos += 1;
+
+        Some(letter)
+    }
+
+    ///
+    /// Reads the next number from the attribute
+    ///
+    pub (super) fn number(&mut self) -> Option<f32> {
+        self.skip_separators();
+
+        let bytes       = self.bytes;
+        let start       = self.pos;
+        let mut pos     = self.pos;
+        let digits      = |pos: &mut usize| { let start = *pos; while *pos < bytes.len() && bytes[*pos].is_ascii_digit() { *pos += 1; } *pos > start };
+
+        if pos < bytes.len() && (bytes[pos] == b'+' || bytes[pos] == b'-') {
+            pos += 1;
+        }
+
+        let mut has_digits = digits(&mut pos);
+
+        if pos < bytes.len() && bytes[pos] == b'.' {
+            pos += 1;
+            has_digits = digits(&mut pos) || has_digits;
+        }
+
+        if !has_digits {
+            return None;
+        }
+
+        // The exponent is only read if there are digits following it (so units like 'em' and 'ex' are left alone)
+        if pos < bytes.len() && (bytes[pos] == b'e' || bytes[pos] == b'E') {
+            let mut exponent_pos = pos + 1;
+
+            if exponent_pos < bytes.len() && (bytes[exponent_pos] == b'+' || bytes[exponent_pos] == b'-') {
+                exponent_pos += 1;
+            }
+
+            if digits(&mut exponent_pos) {
+                pos = exponent_pos;
+            }
+        }
+
+        let number = std::str::from_utf8(&bytes[start..pos]).ok()?.parse::<f32>().ok()?;
+        self.pos = pos;
+
+        Some(number)
+    }
+
+    ///
+    /// Reads a flag (a single '0' or '1' character, used by the arc command in paths)
+    ///
+    pub (super) fn flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+
+        match self.bytes.get(self.pos) {
+            Some(b'0')  => { self.pos += 1; Some(false) }
+            Some(b'1')  => { self.pos += 1; Some(true) }
+            _           => None
+        }
+    }
+
+    ///
+    /// Reads a pair of numbers
+    ///
+    pub (super) fn point(&mut self) -> Option<(f32, f32)> {
+        let x = self.number()?;
+        let y = self.number()?;
+
+        Some((x, y))
+    }
+
+    ///
+    /// Returns the rest of the attribute as a string
+    ///
+    pub (super) fn remaining(&mut self) -> &'a str {
+        self.skip_separators();
+        std::str::from_utf8(&self.bytes[self.pos..]).unwrap_or("")
+    }
+}
+
+///
+/// Parses an attribute containing a single number
+///
+pub (super) fn parse_number(value: &str) -> Option<f32> {
+    let mut numbers = SvgNumbers::new(value);
+    let number      = numbers.number()?;
+
+    if numbers.at_end() { Some(number) } else { None }
+}
+
+///
+/// Parses an attribute containing a list of numbers (stopping at the first thing that isn't a number)
+///
+pub (super) fn parse_number_list(value: &str) -> Vec<f32> {
+    let mut numbers = SvgNumbers::new(value);
+    let mut result  = vec![];
+
+    while let Some(number) = numbers.number() {
+        result.push(number);
+    }
+
+    result
+}
+
+///
+/// Parses a length, returning the value in pixels. Percentages are relative to the `percent_of` value.
+///
+pub (super) fn parse_length(value: &str, percent_of: f32) -> Option<f32> {
+    let mut numbers = SvgNumbers::new(value);
+    let number      = numbers.number()?;
+    let unit        = numbers.remaining().trim();
+
+    if unit == "%" {
+        return Some(number * percent_of / 100.0);
+    }
+
+    let scale = match unit {
+        "" | "px"   => 1.0,
+        "pt"        => 96.0 / 72.0,
+        "pc"        => 16.0,
+        "in"        => 96.0,
+        "cm"        => 96.0 / 2.54,
+        "mm"        => 96.0 / 25.4,
+        "em"        => 16.0,
+        "ex"        => 8.0,
+        _           => { return None; }
+    };
+
+    Some(number * scale)
+}
+
+///
+/// Parses an opacity value (a number or a percentage), clamped to the range 0-1
+///
+pub (super) fn parse_opacity(value: &str) -> Option<f32> {
+    parse_length(value, 1.0).map(|opacity| opacity.max(0.0).min(1.0))
+}
+
+///
+/// Parses a colour value ('#rgb', '#rrggbb', 'rgb()', 'rgba()' or a colour name)
+///
+pub (super) fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim();
+
+    if let Some(hex) = value.strip_prefix('#') {
+        // Hex colours can have 3, 4, 6 or 8 digits
+        let digits = hex.chars().map(|chr| chr.to_digit(16)).collect::<Option<Vec<_>>>()?;
+
+        let (r, g, b, a) = match digits.len() {
+            3 => (digits[0]*17, digits[1]*17, digits[2]*17, 255),
+            4 => (digits[0]*17, digits[1]*17, digits[2]*17, digits[3]*17),
+            6 => (digits[0]*16 + digits[1], digits[2]*16 + digits[3], digits[4]*16 + digits[5], 255),
+            8 => (digits[0]*16 + digits[1], digits[2]*16 + digits[3], digits[4]*16 + digits[5], digits[6]*16 + digits[7]),
+            _ => { return None; }
+        };
+
+        Some(Color::Rgba(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a as f32 / 255.0))
+    } else if let Some(arguments) = value.strip_prefix("rgba(").or_else(|| value.strip_prefix("rgb(")) {
+        // Components are numbers from 0-255 or percentages, and the alpha component is a number from 0-1 or a percentage
+        let arguments   = arguments.strip_suffix(')')?;
+        let components  = arguments.split(|chr| chr == ',' || chr == '/' || char::is_whitespace(chr))
+            .filter(|component| !component.is_empty())
+            .collect::<Vec<_>>();
+
+        if components.len() != 3 && components.len() != 4 {
+            return None;
+        }
+
+        let r = parse_length(components[0], 255.0)? / 255.0;
+        let g = parse_length(components[1], 255.0)? / 255.0;
+        let b = parse_length(components[2], 255.0)? / 255.0;
+        let a = if components.len() == 4 { parse_opacity(components[3])? } else { 1.0 };
+
+        Some(Color::Rgba(r.max(0.0).min(1.0), g.max(0.0).min(1.0), b.max(0.0).min(1.0), a))
+    } else if value.eq_ignore_ascii_case("transparent") {
+        Some(Color::Rgba(0.0, 0.0, 0.0, 0.0))
+    } else {
+        let name = value.to_ascii_lowercase();
+
+        NAMED_COLORS.iter()
+            .find(|(color_name, _)| *color_name == name)
+            .map(|(_, rgb)| Color::Rgba(((rgb>>16)&0xff) as f32 / 255.0, ((rgb>>8)&0xff) as f32 / 255.0, (rgb&0xff) as f32 / 255.0, 1.0))
+    }
+}
+
+///
+/// Parses the declarations in a `style` attribute into a list of property names and values
+///
+pub (super) fn parse_style(style: &str) -> Vec<(&str, &str)> {
+    style.split(';')
+        .filter_map(|declaration| {
+            let mut parts   = declaration.splitn(2, ':');
+            let name        = parts.next()?.trim();
+            let value       = parts.next()?.trim();
+            let value       = value.strip_suffix("!important").unwrap_or(value).trim();
+
+            if name.is_empty() { None } else { Some((name, value)) }
+        })
+        .collect()
+}
+
+///
+/// Parses a `transform` attribute, returning None if it's not valid
+///
+pub (super) fn parse_transform(value: &str) -> Option<Transform2D> {
+    let mut transform   = Transform2D::identity();
+    let mut remaining   = value.trim();
+
+    while !remaining.is_empty() {
+        // Each transform is a function name followed by a list of arguments in brackets
+        let open        = remaining.find('(')?;
+        let close       = remaining.find(')')?;
+        if close < open { return None; }
+
+        let name        = remaining[..open].trim();
+        let arguments   = parse_number_list(&remaining[(open+1)..close]);
+
+        let next = match (name, arguments.len()) {
+            ("matrix", 6)       => Transform2D([[arguments[0], arguments[2], arguments[4]], [arguments[1], arguments[3], arguments[5]], [0.0, 0.0, 1.0]]),
+            ("translate", 1)    => Transform2D::translate(arguments[0], 0.0),
+            ("translate", 2)    => Transform2D::translate(arguments[0], arguments[1]),
+            ("scale", 1)        => Transform2D::scale(arguments[0], arguments[0]),
+            ("scale", 2)        => Transform2D::scale(arguments[0], arguments[1]),
+            ("rotate", 1)       => Transform2D::rotate_degrees(arguments[0]),
+            ("rotate", 3)       => Transform2D::translate(arguments[1], arguments[2]) * Transform2D::rotate_degrees(arguments[0]) * Transform2D::translate(-arguments[1], -arguments[2]),
+            ("skewX", 1)        => Transform2D([[1.0, arguments[0].to_radians().tan(), 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]),
+            ("skewY", 1)        => Transform2D([[1.0, 0.0, 0.0], [arguments[0].to_radians().tan(), 1.0, 0.0], [0.0, 0.0, 1.0]]),
+            _                   => { return None; }
+        };
+
+        transform = transform * next;
+        remaining = remaining[(close+1)..].trim_start_matches(|chr: char| chr.is_whitespace() || chr == ',');
+    }
+
+    Some(transform)
+}
+
+///
+/// Parses a `points` attribute into a list of points
+///
+pub (super) fn parse_points(value: &str) -> Vec<(f32, f32)> {
+    let mut numbers = SvgNumbers::new(value);
+    let mut points  = vec![];
+
+    while let Some(point) = numbers.point() {
+        points.push(point);
+    }
+
+    points
+}
+
+///
+/// Converts an SVG arc (specified by its end points) to a `PathOp::Arc` (specified by its center)
+///
+fn arc_from_endpoints(start: (f32, f32), radii: (f32, f32), x_rotation: f32, large_arc: bool, sweep: bool, end: (f32, f32)) -> PathOp {
+    let (x1, y1)        = (start.0 as f64, start.1 as f64);
+    let (x2, y2)        = (end.0 as f64, end.1 as f64);
+    let (mut rx, mut ry) = ((radii.0 as f64).abs(), (radii.1 as f64).abs());
+    let phi             = (x_rotation as f64).to_radians();
+    let (sin_phi, cos_phi) = phi.sin_cos();
+
+    // Degenerate arcs are straight lines
+    if rx == 0.0 || ry == 0.0 {
+        return PathOp::Line(end.0, end.1);
+    }
+
+    // Move the start point into the coordinate space of the ellipse
+    let dx              = (x1 - x2) / 2.0;
+    let dy              = (y1 - y2) / 2.0;
+    let x1p             = cos_phi * dx + sin_phi * dy;
+    let y1p             = -sin_phi * dx + cos_phi * dy;
+
+    // Radii that are too small are scaled up until the arc fits
+    let lambda          = (x1p*x1p)/(rx*rx) + (y1p*y1p)/(ry*ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+
+    // Find the center
+    let numerator       = rx*rx*ry*ry - rx*rx*y1p*y1p - ry*ry*x1p*x1p;
+    let denominator     = rx*rx*y1p*y1p + ry*ry*x1p*x1p;
+    let sign            = if large_arc != sweep { 1.0 } else { -1.0 };
+    let coefficient     = sign * (numerator / denominator).max(0.0).sqrt();
+    let cxp             = coefficient * rx * y1p / ry;
+    let cyp             = -coefficient * ry * x1p / rx;
+    let cx              = cos_phi * cxp - sin_phi * cyp + (x1 + x2) / 2.0;
+    let cy              = sin_phi * cxp + cos_phi * cyp + (y1 + y2) / 2.0;
+
+    // Find the angles
+    let start_angle     = ((y1p - cyp) / ry).atan2((x1p - cxp) / rx);
+    let end_angle       = ((-y1p - cyp) / ry).atan2((-x1p - cxp) / rx);
+    let mut sweep_angle = (end_angle - start_angle) % (2.0 * PI);
+
+    if !sweep && sweep_angle > 0.0 {
+        sweep_angle -= 2.0 * PI;
+    } else if sweep && sweep_angle < 0.0 {
+        sweep_angle += 2.0 * PI;
+    }
+
+    PathOp::Arc((cx as f32, cy as f32), (rx as f32, ry as f32), phi as f32, start_angle as f32, sweep_angle as f32)
+}
+
+///
+/// Parses SVG path data into a list of path operations
+///
+/// As for SVG renderers, the path is read up to the first error in the data. The return value is false if there was
+/// an error in the path data.
+///
+pub (super) fn parse_path_data(data: &str, path: &mut Vec<PathOp>) -> bool {
+    read_path_data(data, path).is_some()
+}
+
+///
+/// Reads path data into a list of path operations, returning None if there's an error in the path
+///
+fn read_path_data(data: &str, path: &mut Vec<PathOp>) -> Option<()> {
+    let mut numbers         = SvgNumbers::new(data);
+    let mut command         = None;
+    let mut current         = (0.0, 0.0);
+    let mut start           = (0.0, 0.0);
+    let mut last_cubic      = None;
+    let mut last_quadratic  = None;
+
+    while !numbers.at_end() {
+        // Commands can be repeated by leaving out the letter
+        if let Some(letter) = numbers.letter() {
+            command = Some(letter);
+        }
+
+        let letter      = command?;
+        let relative    = letter.is_ascii_lowercase();
+        let offset      = |(x, y): (f32, f32)| if relative { (x + current.0, y + current.1) } else { (x, y) };
+
+        let mut next_cubic      = None;
+        let mut next_quadratic  = None;
+
+        match letter.to_ascii_uppercase() {
+            b'M' => {
+                let point = offset(numbers.point()?);
+                path.push(PathOp::Move(point.0, point.1));
+
+                current = point;
+                start   = point;
+
+                // Any further points after a move are lines
+                command = Some(if relative { b'l' } else { b'L' });
+            }
+
+            b'L' => {
+                let point = offset(numbers.point()?);
+                path.push(PathOp::Line(point.0, point.1));
+                current = point;
+            }
+
+            b'H' => {
+                let x = numbers.number()?;
+                let x = if relative { x + current.0 } else { x };
+
+                path.push(PathOp::Line(x, current.1));
+                current = (x, current.1);
+            }
+
+            b'V' => {
+                let y = numbers.number()?;
+                let y = if relative { y + current.1 } else { y };
+
+                path.push(PathOp::Line(current.0, y));
+                current = (current.0, y);
+            }
+
+            b'C' => {
+                let cp1 = offset(numbers.point()?);
+                let cp2 = offset(numbers.point()?);
+                let end = offset(numbers.point()?);
+                path.push(PathOp::BezierCurve((cp1, cp2), end));
+
+                current     = end;
+                next_cubic  = Some(cp2);
+            }
+
+            b'S' => {
+                let cp2 = offset(numbers.point()?);
+                let end = offset(numbers.point()?);
+
+                // The first control point is the reflection of the second control point of the previous curve
+                let cp1 = last_cubic.map(|(x, y): (f32, f32)| (2.0*current.0 - x, 2.0*current.1 - y)).unwrap_or(current);
+                path.push(PathOp::BezierCurve((cp1, cp2), end));
+
+                current     = end;
+                next_cubic  = Some(cp2);
+            }
+
+            b'Q' => {
+                let cp  = offset(numbers.point()?);
+                let end = offset(numbers.point()?);
+                path.push(PathOp::QuadraticCurve(cp, end));
+
+                current         = end;
+                next_quadratic  = Some(cp);
+            }
+
+            b'T' => {
+                let end = offset(numbers.point()?);
+
+                let cp = last_quadratic.map(|(x, y): (f32, f32)| (2.0*current.0 - x, 2.0*current.1 - y)).unwrap_or(current);
+                path.push(PathOp::QuadraticCurve(cp, end));
+
+                current         = end;
+                next_quadratic  = Some(cp);
+            }
+
+            b'A' => {
+                let radii       = numbers.point()?;
+                let x_rotation  = numbers.number()?;
+                let large_arc   = numbers.flag()?;
+                let sweep       = numbers.flag()?;
+                let end         = offset(numbers.point()?);
+
+                if end != current {
+                    path.push(arc_from_endpoints(current, radii, x_rotation, large_arc, sweep, end));
+                }
+
+                current = end;
+            }
+
+            b'Z' => {
+                path.push(PathOp::ClosePath);
+                current = start;
+
+                // Close path takes no arguments, so it can't be repeated
+                command = None;
+            }
+
+            _ => { return None; }
+        }
+
+        last_cubic      = next_cubic;
+        last_quadratic  = next_quadratic;
+    }
+
+    Some(())
+}
+
+///
+/// Decodes base64 data (as found in a data URI), ignoring any whitespace
+///
+pub (super) fn decode_base64(data: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(data.len() / 4 * 3);
+    let mut value   = 0u32;
+    let mut bits    = 0;
+
+    for byte in data.bytes() {
+        let digit = match byte {
+            b'A'..=b'Z'     => byte - b'A',
+            b'a'..=b'z'     => byte - b'a' + 26,
+            b'0'..=b'9'     => byte - b'0' + 52,
+            b'+' | b'-'     => 62,
+            b'/' | b'_'     => 63,
+            b'='            => { break; }
+            byte if byte.is_ascii_whitespace() => { continue; }
+            _               => { return None; }
+        };
+
+        value   = (value << 6) | (digit as u32);
+        bits    += 6;
+
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push(((value >> bits) & 0xff) as u8);
+        }
+    }
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_numbers() {
+        assert!(parse_number_list("1,2 -3.5e2.5-.5") == vec![1.0, 2.0, -350.0, 0.5, -0.5]);
+        assert!(parse_number(" 12 ") == Some(12.0));
+        assert!(parse_number("12px").is_none());
+    }
+
+    #[test]
+    fn read_lengths() {
+        assert!(parse_length("10", 100.0) == Some(10.0));
+        assert!(parse_length("1in", 100.0) == Some(96.0));
+        assert!(parse_length("50%", 200.0) == Some(100.0));
+        assert!(parse_length("2em", 100.0) == Some(32.0));
+        assert!(parse_length("10furlongs", 100.0).is_none());
+    }
+
+    #[test]
+    fn read_colors() {
+        assert!(parse_color("#f00") == Some(Color::Rgba(1.0, 0.0, 0.0, 1.0)));
+        assert!(parse_color("#0000ff") == Some(Color::Rgba(0.0, 0.0, 1.0, 1.0)));
+        assert!(parse_color("rgb(255, 0, 0)") == Some(Color::Rgba(1.0, 0.0, 0.0, 1.0)));
+        assert!(parse_color("rgba(0, 255, 0, 50%)") == Some(Color::Rgba(0.0, 1.0, 0.0, 0.5)));
+        assert!(parse_color("White") == Some(Color::Rgba(1.0, 1.0, 1.0, 1.0)));
+        assert!(parse_color("notacolor").is_none());
+    }
+
+    #[test]
+    fn read_style() {
+        assert!(parse_style("fill: red; stroke:blue !important;") == vec![("fill", "red"), ("stroke", "blue")]);
+    }
+
+    #[test]
+    fn read_transforms() {
+        assert!(parse_transform("translate(10, 20)") == Some(Transform2D::translate(10.0, 20.0)));
+        assert!(parse_transform("translate(10 20) scale(2)") == Some(Transform2D::translate(10.0, 20.0) * Transform2D::scale(2.0, 2.0)));
+        assert!(parse_transform("matrix(1 0 0 1 5 6)") == Some(Transform2D::translate(5.0, 6.0)));
+        assert!(parse_transform("spin(10)").is_none());
+    }
+
+    #[test]
+    fn read_simple_path() {
+        let mut path = vec![];
+
+        assert!(parse_path_data("M10 20 30 40h10v-10z", &mut path));
+        assert!(path == vec![PathOp::Move(10.0, 20.0), PathOp::Line(30.0, 40.0), PathOp::Line(40.0, 40.0), PathOp::Line(40.0, 30.0), PathOp::ClosePath]);
+    }
+
+    #[test]
+    fn read_relative_curves() {
+        let mut path = vec![];
+
+        assert!(parse_path_data("m0,0c1,1 2,1 3,0s2,-1 3,0", &mut path));
+        assert!(path == vec![
+            PathOp::Move(0.0, 0.0),
+            PathOp::BezierCurve(((1.0, 1.0), (2.0, 1.0)), (3.0, 0.0)),
+            PathOp::BezierCurve(((4.0, -1.0), (5.0, -1.0)), (6.0, 0.0)),
+        ]);
+    }
+
+    #[test]
+    fn read_path_up_to_error() {
+        let mut path = vec![];
+
+        assert!(!parse_path_data("M0 0L10 10L20", &mut path));
+        assert!(path == vec![PathOp::Move(0.0, 0.0), PathOp::Line(10.0, 10.0)]);
+    }
+
+    #[test]
+    fn read_arc() {
+        let mut path = vec![];
+
+        assert!(parse_path_data("M0 0A10 10 0 0 1 20 0", &mut path));
+
+        if let PathOp::Arc((cx, cy), (rx, ry), _, start_angle, sweep_angle) = path[1] {
+            assert!((cx-10.0).abs() < 0.001 && cy.abs() < 0.001);
+            assert!((rx-10.0).abs() < 0.001 && (ry-10.0).abs() < 0.001);
+            assert!((start_angle.abs() - std::f32::consts::PI).abs() < 0.001);
+            assert!((sweep_angle - std::f32::consts::PI).abs() < 0.001);
+
+            let (end_x, end_y) = arc_point((cx, cy), (rx, ry), 0.0, start_angle + sweep_angle);
+            assert!((end_x-20.0).abs() < 0.001 && end_y.abs() < 0.001);
+        } else {
+            assert!(false, "Expected an arc, got {:?}", path[1]);
+        }
+    }
+
+    #[test]
+    fn read_base64() {
+        assert!(decode_base64("Zm9vYmFy") == Some(b"foobar".to_vec()));
+        assert!(decode_base64("Zm8=") == Some(b"fo".to_vec()));
+        assert!(decode_base64("Zg =\n=") == Some(b"f".to_vec()));
+        assert!(decode_base64("Z*g").is_none());
+    }
+}
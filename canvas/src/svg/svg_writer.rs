@@ -0,0 +1,1549 @@
+use super::svg_format::*;
+
+#[cfg(feature = "outline-fonts")] use crate::conversion_streams::{drawing_with_text_as_paths, drawing_with_laid_out_text};
+
+use crate::draw::*;
+use crate::path::*;
+use crate::font::*;
+use crate::color::*;
+use crate::sprite::*;
+use crate::context::*;
+use crate::texture::*;
+use crate::gradient::*;
+use crate::namespace::*;
+use crate::font_face::*;
+use crate::transform2d::*;
+
+use crate::conversion_streams::{combine_paths, CanvasTransform};
+
+use futures::prelude::*;
+
+use std::mem;
+use std::sync::*;
+use std::fmt::Write;
+use std::collections::{HashMap};
+
+/// The largest number of bytes that a bitmap texture can use (textures that are larger than this are not created)
+const MAX_TEXTURE_BYTES: usize = 16384 * 16384 * 4;
+
+///
+/// How text is written to an SVG document
+///
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SvgText {
+    /// Text is written as `<text>` elements, with the fonts embedded in the document
+    Elements,
+
+    /// Text is converted to paths (so the document looks the same in viewers that don't support embedded fonts)
+    #[cfg(feature = "outline-fonts")]
+    Outlines,
+}
+
+///
+/// Options for generating an SVG document from a drawing
+///
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SvgOptions {
+    /// The width of the document in pixels
+    pub width: f32,
+
+    /// The height of the document in pixels
+    pub height: f32,
+
+    /// How text should be written to the document
+    pub text: SvgText,
+}
+
+impl SvgOptions {
+    ///
+    /// Creates the options for a document with the specified size, with text written as `<text>` elements
+    ///
+    pub fn new(width: f32, height: f32) -> SvgOptions {
+        SvgOptions {
+            width:  width,
+            height: height,
+            text:   SvgText::Elements,
+        }
+    }
+
+    ///
+    /// Changes how text is written to the document
+    ///
+    pub fn with_text(mut self, text: SvgText) -> SvgOptions {
+        self.text = text;
+        self
+    }
+}
+
+///
+/// Where the drawing instructions are currently being sent
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum SvgTarget {
+    Layer(LayerId),
+    Sprite(NamespaceId, SpriteId),
+}
+
+///
+/// How a shape is filled or stroked
+///
+#[derive(Clone, Debug)]
+enum SvgPaint {
+    /// A solid colour
+    Color(Color),
+
+    /// A linear gradient (with the stops and spread it had when it was selected)
+    LinearGradient { stops: Vec<(f32, Color)>, spread: GradientSpread, start: (f32, f32), end: (f32, f32) },
+
+    /// A radial gradient
+    RadialGradient { stops: Vec<(f32, Color)>, spread: GradientSpread, center: (f32, f32), radius: f32, focus: Option<(f32, f32)> },
+
+    /// A texture, with the markup that draws it into a 1x1 tile
+    Texture { content: String, lower_left: (f32, f32), upper_right: (f32, f32) },
+}
+
+///
+/// The drawing state for a layer or a sprite
+///
+#[derive(Clone, Debug)]
+struct SvgState {
+    fill:               SvgPaint,
+    fill_transform:     Transform2D,
+    stroke:             SvgPaint,
+    line_width:         f32,
+    line_width_pixels:  bool,
+    line_join:          LineJoin,
+    line_cap:           LineCap,
+    miter_limit:        f32,
+    dash_pattern:       Vec<f32>,
+    dash_offset:        f32,
+    winding_rule:       WindingRule,
+    blend_mode:         BlendMode,
+    sprite_transform:   Transform2D,
+
+    /// The ID of the `<clipPath>` that applies to shapes drawn in this state
+    clip:               Option<String>,
+}
+
+impl Default for SvgState {
+    fn default() -> SvgState {
+        SvgState {
+            fill:               SvgPaint::Color(Color::Rgba(0.0, 0.0, 0.0, 1.0)),
+            fill_transform:     Transform2D::identity(),
+            stroke:             SvgPaint::Color(Color::Rgba(0.0, 0.0, 0.0, 1.0)),
+            line_width:         1.0,
+            line_width_pixels:  false,
+            line_join:          LineJoin::Round,
+            line_cap:           LineCap::Butt,
+            miter_limit:        4.0,
+            dash_pattern:       vec![],
+            dash_offset:        0.0,
+            winding_rule:       WindingRule::NonZero,
+            blend_mode:         BlendMode::SourceOver,
+            sprite_transform:   Transform2D::identity(),
+            clip:               None,
+        }
+    }
+}
+
+///
+/// An element drawn on a layer or a sprite
+///
+#[derive(Clone, Debug)]
+struct SvgElement {
+    /// The clip path that applies to this element
+    clip:   Option<String>,
+
+    /// The markup for this element
+    markup: String,
+}
+
+///
+/// The elements that make up a sprite
+///
+#[derive(Clone, Debug, Default)]
+struct SvgSprite {
+    elements:   Vec<SvgElement>,
+
+    /// The ID of the `<symbol>` that contains the current elements (None if the sprite has changed since it was last drawn)
+    symbol:     Option<String>,
+}
+
+///
+/// The content of a texture
+///
+#[derive(Clone, Debug)]
+enum SvgTextureContent {
+    /// A bitmap texture, and the ID of the `<image>` that contains the current pixels
+    Bitmap { size: TextureSize, pixels: Vec<u8>, image: Option<String> },
+
+    /// A texture rendered from a sprite (dynamic textures follow the sprite as it changes, otherwise the symbol is the sprite when the texture was set)
+    Sprite { sprite: (NamespaceId, SpriteId), bounds: SpriteBounds, symbol: Option<String>, dynamic: bool },
+}
+
+///
+/// A texture defined on the canvas
+///
+#[derive(Clone, Debug)]
+struct SvgTexture {
+    content:    SvgTextureContent,
+    alpha:      f32,
+}
+
+///
+/// A font defined on the canvas
+///
+#[derive(Clone, Debug)]
+struct SvgFont {
+    family:     String,
+    size:       f32,
+    features:   Vec<([u8; 4], bool)>,
+    variations: Vec<([u8; 4], f32)>,
+}
+
+///
+/// Text being laid out by `BeginLineLayout`
+///
+#[derive(Clone, Debug)]
+struct SvgLineLayout {
+    position:   (f32, f32),
+    alignment:  TextAlignment,
+
+    /// The `<tspan>` elements for the text laid out so far
+    spans:      String,
+}
+
+///
+/// Generates an SVG document from a stream of drawing instructions
+///
+/// Drawing instructions are sent to the writer using the `GraphicsContext` trait, and `document()` returns the SVG document for
+/// everything drawn so far. The document is the size passed to `new()` in pixels, and is laid out the same way as a window of that
+/// size: `IdentityTransform` makes the document 2.0 units high with (0, 0) at the center, and `CanvasHeight` and `CenterRegion`
+/// behave the same way as they do for any other canvas.
+///
+/// Layers become groups (with their alpha and blend mode), sprites become `<symbol>` elements drawn with `<use>`, and gradients,
+/// textures and clip paths are written as definitions. Text is written as `<text>` elements using the fonts embedded in the document:
+/// use `drawing_to_svg()` to write text as outlines instead. Blend modes and texture filters that have no SVG equivalent are ignored,
+/// as are conic gradients (which are filled with the colour of their first stop), and `Restore` ignores any change to the clip path.
+///
+pub struct SvgWriter {
+    /// The size of the document in pixels
+    width:              f32,
+    height:             f32,
+
+    /// Where the drawing is going and its state
+    namespace:          NamespaceId,
+    target:             SvgTarget,
+    state:              SvgState,
+    target_state:       HashMap<SvgTarget, SvgState>,
+
+    /// The transform from drawing coordinates to canvas coordinates
+    canvas_transform:   CanvasTransform,
+
+    /// The current path and the path saved by `StorePath`
+    current_path:       Vec<PathOp>,
+    stored_path:        Vec<PathOp>,
+
+    /// The states stored by `PushState`
+    state_stack:        Vec<(Transform2D, SvgState, Vec<PathOp>, NamespaceId)>,
+
+    /// The elements drawn on each layer, and the order the layers are drawn in
+    layers:             HashMap<LayerId, Vec<SvgElement>>,
+    layer_order:        Vec<LayerId>,
+    layer_blend:        HashMap<LayerId, BlendMode>,
+    layer_alpha:        HashMap<LayerId, f32>,
+    stored_layers:      HashMap<LayerId, Vec<SvgElement>>,
+
+    /// The resources defined on the canvas
+    sprites:            HashMap<(NamespaceId, SpriteId), SvgSprite>,
+    textures:           HashMap<(NamespaceId, TextureId), SvgTexture>,
+    gradients:          HashMap<(NamespaceId, GradientId), (Vec<(f32, Color)>, GradientSpread)>,
+    fonts:              HashMap<(NamespaceId, FontId), SvgFont>,
+    font_fallbacks:     HashMap<(NamespaceId, FontId), Vec<FontId>>,
+
+    /// The fonts embedded in the document, and the family names they were given
+    font_faces:         Vec<(Arc<CanvasFontFace>, String)>,
+
+    /// The text that's being laid out
+    line_layout:        Option<SvgLineLayout>,
+
+    /// The colour set by the last `ClearCanvas`
+    background:         Option<Color>,
+
+    /// The markup for the definitions in the document, and the IDs assigned to each one
+    definitions:        Vec<String>,
+    definition_ids:     HashMap<String, String>,
+    next_id:            usize,
+}
+
+impl SvgWriter {
+    ///
+    /// Creates a new SVG writer that will write a document of the specified size (in pixels)
+    ///
+    pub fn new(width: f32, height: f32) -> SvgWriter {
+        SvgWriter {
+            width:              width,
+            height:             height,
+            namespace:          NamespaceId::default(),
+            target:             SvgTarget::Layer(LayerId(0)),
+            state:              SvgState::default(),
+            target_state:       HashMap::new(),
+            canvas_transform:   CanvasTransform::new(),
+            current_path:       vec![],
+            stored_path:        vec![],
+            state_stack:        vec![],
+            layers:             HashMap::new(),
+            layer_order:        vec![LayerId(0)],
+            layer_blend:        HashMap::new(),
+            layer_alpha:        HashMap::new(),
+            stored_layers:      HashMap::new(),
+            sprites:            HashMap::new(),
+            textures:           HashMap::new(),
+            gradients:          HashMap::new(),
+            fonts:              HashMap::new(),
+            font_fallbacks:     HashMap::new(),
+            font_faces:         vec![],
+            line_layout:        None,
+            background:         None,
+            definitions:        vec![],
+            definition_ids:     HashMap::new(),
+            next_id:            0,
+        }
+    }
+
+    ///
+    /// Adds a definition to the document, returning its ID (identical definitions share an ID)
+    ///
+    fn define(&mut self, element: &str, id_prefix: &str, attributes: &str, content: &str) -> String {
+        let key = format!("<{}{}>{}", element, attributes, content);
+
+        if let Some(id) = self.definition_ids.get(&key) {
+            return id.clone();
+        }
+
+        let id = format!("{}{}", id_prefix, self.next_id);
+        self.next_id += 1;
+
+        self.definitions.push(format!("<{} id=\"{}\"{}>{}</{}>", element, id, attributes, content, element));
+        self.definition_ids.insert(key, id.clone());
+
+        id
+    }
+
+    ///
+    /// Adds an element to the layer or sprite that's being drawn
+    ///
+    fn add_element(&mut self, markup: String) {
+        let element = SvgElement { clip: self.state.clip.clone(), markup: markup };
+
+        match self.target {
+            SvgTarget::Layer(layer_id)                  => { self.layers.entry(layer_id).or_default().push(element); }
+            SvgTarget::Sprite(namespace_id, sprite_id)  => {
+                let sprite = self.sprites.entry((namespace_id, sprite_id)).or_default();
+
+                sprite.elements.push(element);
+                sprite.symbol = None;
+            }
+        }
+    }
+
+    ///
+    /// Removes everything from the layer or sprite that's being drawn
+    ///
+    fn clear_target(&mut self) {
+        match self.target {
+            SvgTarget::Layer(layer_id)                  => { self.layers.remove(&layer_id); }
+            SvgTarget::Sprite(namespace_id, sprite_id)  => { self.sprites.remove(&(namespace_id, sprite_id)); }
+        }
+    }
+
+    ///
+    /// Adds a layer to the layer order if it's not already there (layers are ordered by ID unless they've been swapped)
+    ///
+    fn add_layer(&mut self, layer_id: LayerId) {
+        if !self.layer_order.contains(&layer_id) {
+            let pos = self.layer_order.iter().position(|existing| existing.0 > layer_id.0).unwrap_or(self.layer_order.len());
+            self.layer_order.insert(pos, layer_id);
+        }
+    }
+
+    ///
+    /// Returns the ID of the `<symbol>` containing the current elements for a sprite (or None if the sprite is empty)
+    ///
+    fn sprite_symbol(&mut self, sprite: (NamespaceId, SpriteId)) -> Option<String> {
+        let content = match self.sprites.get(&sprite) {
+            None                                            => { return None; }
+            Some(sprite) if sprite.elements.is_empty()      => { return None; }
+            Some(SvgSprite { symbol: Some(symbol), .. })    => { return Some(symbol.clone()); }
+            Some(sprite)                                    => {
+                let mut content = String::new();
+                write_elements(&sprite.elements, &mut content);
+                content
+            }
+        };
+
+        let symbol = self.define("symbol", "sprite", " overflow=\"visible\"", &content);
+        if let Some(sprite) = self.sprites.get_mut(&sprite) {
+            sprite.symbol = Some(symbol.clone());
+        }
+
+        Some(symbol)
+    }
+
+    ///
+    /// Returns the markup that draws a texture into a 1x1 tile, with the top-left corner of the texture at (0, 0)
+    ///
+    fn texture_content(&mut self, texture: (NamespaceId, TextureId)) -> Option<String> {
+        let (content, alpha)    = self.textures.get(&texture).map(|texture| (texture.content.clone(), texture.alpha))?;
+        let opacity             = if alpha < 1.0 { format!(" opacity=\"{}\"", svg_number(alpha.max(0.0))) } else { String::new() };
+
+        match content {
+            SvgTextureContent::Bitmap { image: Some(image), .. }        => Some(format!("<use xlink:href=\"#{}\"{}/>", image, opacity)),
+            SvgTextureContent::Bitmap { size, pixels, image: None }     => {
+                // Encode the texture as a PNG image
+                let data_uri    = png_data_uri(size, &pixels)?;
+                let image       = self.define("image", "image", &format!(" width=\"1\" height=\"1\" preserveAspectRatio=\"none\" xlink:href=\"{}\"", data_uri), "");
+
+                if let Some(SvgTexture { content: SvgTextureContent::Bitmap { image: texture_image, .. }, .. }) = self.textures.get_mut(&texture) {
+                    *texture_image = Some(image.clone());
+                }
+
+                Some(format!("<use xlink:href=\"#{}\"{}/>", image, opacity))
+            }
+
+            SvgTextureContent::Sprite { sprite, bounds, symbol, dynamic } => {
+                let symbol = if dynamic { self.sprite_symbol(sprite)? } else { symbol? };
+
+                // Map the sprite bounds onto the tile (sprites have y pointing upwards, and the tile has y pointing downwards)
+                let SpriteBounds(SpritePosition(x, y), SpriteSize(w, h)) = bounds;
+                if w == 0.0 || h == 0.0 { return None; }
+
+                let sprite_to_tile = Transform2D([
+                    [1.0/w, 0.0,     -x/w],
+                    [0.0,   -1.0/h,  (y+h)/h],
+                    [0.0,   0.0,     1.0]
+                ]);
+
+                Some(format!("<use xlink:href=\"#{}\"{}{}/>", symbol, svg_transform_attribute("transform", &sprite_to_tile), opacity))
+            }
+        }
+    }
+
+    ///
+    /// Returns the paint to use for a texture fill or stroke
+    ///
+    fn texture_paint(&mut self, texture_id: TextureId, lower_left: (f32, f32), upper_right: (f32, f32)) -> Option<SvgPaint> {
+        let content = self.texture_content((self.namespace, texture_id))?;
+
+        Some(SvgPaint::Texture { content: content, lower_left: lower_left, upper_right: upper_right })
+    }
+
+    ///
+    /// Returns the stops and spread for a gradient
+    ///
+    fn gradient(&self, gradient_id: GradientId) -> Option<(Vec<(f32, Color)>, GradientSpread)> {
+        self.gradients.get(&(self.namespace, gradient_id)).cloned()
+    }
+
+    ///
+    /// Returns the attributes that set a fill or a stroke to a paint
+    ///
+    /// The paint transform maps the paint's coordinates to the coordinates of the element that's being painted.
+    ///
+    fn paint_attributes(&mut self, attribute: &str, paint: &SvgPaint, paint_transform: &Transform2D) -> String {
+        match paint {
+            SvgPaint::Color(color) => {
+                svg_color_attributes(attribute, &format!("{}-opacity", attribute), color)
+            }
+
+            SvgPaint::LinearGradient { stops, spread, start, end } => {
+                let attributes  = format!(" gradientUnits=\"userSpaceOnUse\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"{}{}",
+                    svg_number(start.0), svg_number(start.1), svg_number(end.0), svg_number(end.1),
+                    spread_attribute(*spread), svg_transform_attribute("gradientTransform", paint_transform));
+                let gradient    = self.define("linearGradient", "gradient", &attributes, &gradient_stops(stops));
+
+                format!(" {}=\"url(#{})\"", attribute, gradient)
+            }
+
+            SvgPaint::RadialGradient { stops, spread, center, radius, focus } => {
+                let focus       = focus.map(|(fx, fy)| format!(" fx=\"{}\" fy=\"{}\"", svg_number(fx), svg_number(fy))).unwrap_or_default();
+                let attributes  = format!(" gradientUnits=\"userSpaceOnUse\" cx=\"{}\" cy=\"{}\" r=\"{}\"{}{}{}",
+                    svg_number(center.0), svg_number(center.1), svg_number(radius.abs()), focus,
+                    spread_attribute(*spread), svg_transform_attribute("gradientTransform", paint_transform));
+                let gradient    = self.define("radialGradient", "gradient", &attributes, &gradient_stops(stops));
+
+                format!(" {}=\"url(#{})\"", attribute, gradient)
+            }
+
+            SvgPaint::Texture { content, lower_left, upper_right } => {
+                // The texture is drawn into a 1x1 tile, which is mapped onto the texture rectangle (the lower-left corner is the bottom of the image)
+                let (x1, y1)        = *lower_left;
+                let (x2, y2)        = *upper_right;
+                let tile_to_texture = Transform2D([
+                    [x2-x1, 0.0,   x1],
+                    [0.0,   y1-y2, y2],
+                    [0.0,   0.0,   1.0]
+                ]);
+
+                let attributes      = format!(" patternUnits=\"userSpaceOnUse\" width=\"1\" height=\"1\"{}", svg_transform_attribute("patternTransform", &(*paint_transform * tile_to_texture)));
+                let pattern         = self.define("pattern", "pattern", &attributes, content);
+
+                format!(" {}=\"url(#{})\"", attribute, pattern)
+            }
+        }
+    }
+
+    ///
+    /// Returns the attributes that apply to every shape drawn in the current state (the transform and the blend mode)
+    ///
+    fn shape_attributes(&self, transform: &Transform2D) -> String {
+        let mut attributes = svg_transform_attribute("transform", transform);
+
+        if let Some(blend_mode) = svg_blend_mode(self.state.blend_mode) {
+            write!(attributes, " style=\"mix-blend-mode:{}\"", blend_mode).ok();
+        }
+
+        attributes
+    }
+
+    ///
+    /// Returns the attributes that apply to text elements using the current state
+    ///
+    fn text_attributes(&mut self, placement: &Transform2D) -> String {
+        let transform       = self.canvas_transform.transform() * *placement;
+        let paint_transform = placement.invert().unwrap_or_else(Transform2D::identity) * self.state.fill_transform;
+        let fill            = self.state.fill.clone();
+
+        format!("{}{} xml:space=\"preserve\"", self.shape_attributes(&transform), self.paint_attributes("fill", &fill, &paint_transform))
+    }
+
+    ///
+    /// Returns the font attributes for a font
+    ///
+    fn font_attributes(&self, font_id: FontId) -> Option<String> {
+        let font        = self.fonts.get(&(self.namespace, font_id))?;
+
+        // Any fallback fonts are added to the font family list
+        let mut family  = font.family.clone();
+        for fallback_id in self.font_fallbacks.get(&(self.namespace, font_id)).into_iter().flatten() {
+            if let Some(fallback) = self.fonts.get(&(self.namespace, *fallback_id)) {
+                family.push_str(", ");
+                family.push_str(&fallback.family);
+            }
+        }
+
+        let mut attributes = format!(" font-family=\"{}\" font-size=\"{}\"", family, svg_number(font.size));
+
+        // OpenType features and variation axes are set using CSS properties
+        let features    = font.features.iter().map(|(tag, enabled)| format!("'{}' {}", svg_escape(&String::from_utf8_lossy(tag)), if *enabled { 1 } else { 0 })).collect::<Vec<_>>();
+        let variations  = font.variations.iter().map(|(tag, value)| format!("'{}' {}", svg_escape(&String::from_utf8_lossy(tag)), svg_number(*value))).collect::<Vec<_>>();
+        let mut style   = vec![];
+
+        if !features.is_empty()     { style.push(format!("font-feature-settings: {}", features.join(", "))); }
+        if !variations.is_empty()   { style.push(format!("font-variation-settings: {}", variations.join(", "))); }
+        if !style.is_empty()        { write!(attributes, " style=\"{}\"", style.join("; ")).ok(); }
+
+        Some(attributes)
+    }
+
+    ///
+    /// Fills the current path
+    ///
+    fn fill(&mut self) {
+        if self.current_path.is_empty() { return; }
+
+        let fill            = self.state.fill.clone();
+        let fill_transform  = self.state.fill_transform;
+
+        let mut markup      = format!("<path d=\"{}\"", svg_path_data(&self.current_path));
+        markup.push_str(&self.paint_attributes("fill", &fill, &fill_transform));
+        if self.state.winding_rule == WindingRule::EvenOdd { markup.push_str(" fill-rule=\"evenodd\""); }
+        markup.push_str(&self.shape_attributes(&self.canvas_transform.transform()));
+        markup.push_str("/>");
+
+        self.add_element(markup);
+    }
+
+    ///
+    /// Strokes the current path
+    ///
+    fn stroke(&mut self) {
+        if self.current_path.is_empty() { return; }
+
+        let stroke          = self.state.stroke.clone();
+
+        let mut markup      = format!("<path d=\"{}\" fill=\"none\"", svg_path_data(&self.current_path));
+        markup.push_str(&self.paint_attributes("stroke", &stroke, &Transform2D::identity()));
+        write!(markup, " stroke-width=\"{}\"", svg_number(self.state.line_width)).ok();
+
+        match self.state.line_join {
+            LineJoin::Miter => { write!(markup, " stroke-linejoin=\"miter\" stroke-miterlimit=\"{}\"", svg_number(self.state.miter_limit.max(1.0))).ok(); }
+            LineJoin::Round => { markup.push_str(" stroke-linejoin=\"round\""); }
+            LineJoin::Bevel => { markup.push_str(" stroke-linejoin=\"bevel\""); }
+        }
+
+        match self.state.line_cap {
+            LineCap::Butt   => { }
+            LineCap::Round  => { markup.push_str(" stroke-linecap=\"round\""); }
+            LineCap::Square => { markup.push_str(" stroke-linecap=\"square\""); }
+        }
+
+        if self.state.dash_pattern.iter().any(|length| *length > 0.0) {
+            let dash_pattern = self.state.dash_pattern.iter().map(|length| svg_number(length.max(0.0))).collect::<Vec<_>>();
+            write!(markup, " stroke-dasharray=\"{}\"", dash_pattern.join(" ")).ok();
+
+            if self.state.dash_offset != 0.0 {
+                write!(markup, " stroke-dashoffset=\"{}\"", svg_number(self.state.dash_offset)).ok();
+            }
+        }
+
+        // Line widths in pixels are measured in the coordinates of the document, which are pixels
+        if self.state.line_width_pixels {
+            markup.push_str(" vector-effect=\"non-scaling-stroke\"");
+        }
+
+        markup.push_str(&self.shape_attributes(&self.canvas_transform.transform()));
+        markup.push_str("/>");
+
+        self.add_element(markup);
+    }
+
+    ///
+    /// Intersects the clip path with the current path
+    ///
+    fn clip(&mut self) {
+        let mut content = format!("<path d=\"{}\"{}", svg_path_data(&self.current_path), svg_transform_attribute("transform", &self.canvas_transform.transform()));
+        if self.state.winding_rule == WindingRule::EvenOdd { content.push_str(" clip-rule=\"evenodd\""); }
+        content.push_str("/>");
+
+        // Clip paths that are already in effect are intersected with the new one
+        let attributes = match &self.state.clip {
+            Some(parent_clip)   => format!(" clipPathUnits=\"userSpaceOnUse\" clip-path=\"url(#{})\"", parent_clip),
+            None                => " clipPathUnits=\"userSpaceOnUse\"".to_string(),
+        };
+
+        self.state.clip = Some(self.define("clipPath", "clip", &attributes, &content));
+    }
+
+    ///
+    /// Draws a sprite, optionally with a set of filters
+    ///
+    fn draw_sprite(&mut self, sprite_id: SpriteId, filters: &[TextureFilter]) {
+        let symbol = if let Some(symbol) = self.sprite_symbol((self.namespace, sprite_id)) { symbol } else { return; };
+
+        // Filters without an SVG equivalent are left out
+        let mut filter_content = String::new();
+        for filter in filters.iter() {
+            match filter {
+                TextureFilter::GaussianBlur(radius) => { write!(filter_content, "<feGaussianBlur stdDeviation=\"{}\"/>", svg_number(radius.abs() / 4.0)).ok(); }
+                TextureFilter::AlphaBlend(alpha)    => { write!(filter_content, "<feComponentTransfer><feFuncA type=\"linear\" slope=\"{}\"/></feComponentTransfer>", svg_number(*alpha)).ok(); }
+
+                TextureFilter::Mask(_)                  |
+                TextureFilter::DisplacementMap(_, _, _) => { }
+            }
+        }
+
+        let filter = if !filter_content.is_empty() {
+            let filter = self.define("filter", "filter", " x=\"-50%\" y=\"-50%\" width=\"200%\" height=\"200%\"", &filter_content);
+            format!(" filter=\"url(#{})\"", filter)
+        } else {
+            String::new()
+        };
+
+        let transform   = self.canvas_transform.transform() * self.state.sprite_transform;
+        let markup      = format!("<use xlink:href=\"#{}\"{}{}/>", symbol, self.shape_attributes(&transform), filter);
+
+        self.add_element(markup);
+    }
+
+    ///
+    /// Performs an operation on a texture
+    ///
+    fn texture(&mut self, texture_id: TextureId, texture_op: TextureOp) {
+        let key = (self.namespace, texture_id);
+
+        match texture_op {
+            TextureOp::Create(size, _format) => {
+                let TextureSize(width, height) = size;
+
+                // Textures that are too large to store are left out of the document
+                let num_bytes = (width as usize).checked_mul(height as usize).and_then(|pixels| pixels.checked_mul(4));
+                let num_bytes = match num_bytes {
+                    Some(num_bytes) if num_bytes <= MAX_TEXTURE_BYTES   => num_bytes,
+                    _                                                   => { return; }
+                };
+
+                self.textures.insert(key, SvgTexture {
+                    content:    SvgTextureContent::Bitmap { size: size, pixels: vec![0; num_bytes], image: None },
+                    alpha:      1.0,
+                });
+            }
+
+            TextureOp::Free => {
+                self.textures.remove(&key);
+            }
+
+            TextureOp::SetBytes(TexturePosition(x, y), TextureSize(width, height), bytes) => {
+                if let Some(SvgTexture { content: SvgTextureContent::Bitmap { size: TextureSize(texture_width, texture_height), pixels, image }, .. }) = self.textures.get_mut(&key) {
+                    // Copy the rows that are inside the texture
+                    let (x, y)                          = (x as usize, y as usize);
+                    let (width, height)                 = (width as usize, height as usize);
+                    let (texture_width, texture_height) = (*texture_width as usize, *texture_height as usize);
+
+                    if x < texture_width {
+                        let row_length = width.min(texture_width - x) * 4;
+
+                        for row in 0..height {
+                            let source_start    = row * width * 4;
+                            let target_start    = ((y + row) * texture_width + x) * 4;
+
+                            if y + row >= texture_height || source_start + row_length > bytes.len() { break; }
+
+                            pixels[target_start..(target_start + row_length)].copy_from_slice(&bytes[source_start..(source_start + row_length)]);
+                        }
+                    }
+
+                    *image = None;
+                }
+            }
+
+            TextureOp::SetFromSprite(sprite_id, bounds) => {
+                let symbol = self.sprite_symbol((self.namespace, sprite_id));
+
+                if let Some(texture) = self.textures.get_mut(&key) {
+                    texture.content = SvgTextureContent::Sprite { sprite: (key.0, sprite_id), bounds: bounds, symbol: symbol, dynamic: false };
+                }
+            }
+
+            TextureOp::CreateDynamicSprite(sprite_id, bounds, _size) => {
+                self.textures.insert(key, SvgTexture {
+                    content:    SvgTextureContent::Sprite { sprite: (key.0, sprite_id), bounds: bounds, symbol: None, dynamic: true },
+                    alpha:      1.0,
+                });
+            }
+
+            TextureOp::FillTransparency(alpha) => {
+                if let Some(texture) = self.textures.get_mut(&key) {
+                    texture.alpha = alpha;
+                }
+            }
+
+            TextureOp::Copy(target_id) => {
+                if let Some(texture) = self.textures.get(&key).cloned() {
+                    self.textures.insert((key.0, target_id), texture);
+                }
+            }
+
+            // Filters can't be applied to textures in SVG
+            TextureOp::Filter(_) => { }
+        }
+    }
+
+    ///
+    /// Performs an operation on a font
+    ///
+    fn font(&mut self, font_id: FontId, font_op: FontOp) {
+        let key = (self.namespace, font_id);
+
+        match font_op {
+            FontOp::UseFontDefinition(font_face) => {
+                // Each font face is embedded in the document once
+                let family = if let Some((_, family)) = self.font_faces.iter().find(|(existing, _)| Arc::ptr_eq(existing, &font_face)) {
+                    family.clone()
+                } else {
+                    let family = format!("font{}", self.next_id);
+                    self.next_id += 1;
+
+                    self.font_faces.push((font_face, family.clone()));
+                    family
+                };
+
+                self.fonts.insert(key, SvgFont { family: family, size: 12.0, features: vec![], variations: vec![] });
+            }
+
+            FontOp::FontSize(size) => {
+                if let Some(font) = self.fonts.get_mut(&key) {
+                    font.size = size;
+                }
+            }
+
+            FontOp::UseFallbackFonts(fallback_fonts) => {
+                self.font_fallbacks.insert(key, fallback_fonts);
+            }
+
+            FontOp::FontFeature(tag, enabled) => {
+                if let Some(font) = self.fonts.get_mut(&key) {
+                    font.features.retain(|(feature, _)| feature != &tag);
+                    font.features.push((tag, enabled));
+                }
+            }
+
+            FontOp::FontVariation(tag, value) => {
+                if let Some(font) = self.fonts.get_mut(&key) {
+                    font.variations.retain(|(axis, _)| axis != &tag);
+                    font.variations.push((tag, value));
+                }
+            }
+
+            FontOp::LayoutText(text) => {
+                if let Some(font_attributes) = self.font_attributes(font_id) {
+                    if let Some(line_layout) = &mut self.line_layout {
+                        write!(line_layout.spans, "<tspan{}>{}</tspan>", font_attributes, svg_escape(&text)).ok();
+                    }
+                }
+            }
+
+            // Glyphs can only be drawn as outlines
+            FontOp::DrawGlyphs(_) => { }
+        }
+    }
+
+    ///
+    /// Draws the text that has been laid out since the last `BeginLineLayout`
+    ///
+    fn draw_laid_out_text(&mut self) {
+        if let Some(line_layout) = self.line_layout.take() {
+            if line_layout.spans.is_empty() { return; }
+
+            let (x, y)      = line_layout.position;
+            let placement   = Transform2D::translate(x, y) * Transform2D::scale(1.0, -1.0);
+            let anchor      = match line_layout.alignment {
+                TextAlignment::Left     => "",
+                TextAlignment::Center   => " text-anchor=\"middle\"",
+                TextAlignment::Right    => " text-anchor=\"end\"",
+            };
+
+            let markup      = format!("<text{}{}>{}</text>", self.text_attributes(&placement), anchor, line_layout.spans);
+            self.add_element(markup);
+        }
+    }
+
+    ///
+    /// Processes a drawing instruction
+    ///
+    fn process(&mut self, drawing: Draw) {
+        use self::Draw::*;
+
+        // Transforms are applied to each element as it's written out
+        if self.canvas_transform.update(&drawing) {
+            return;
+        }
+
+        match drawing {
+            StartFrame                              |
+            ShowFrame                               |
+            ResetFrame                              => { }
+
+            Path(PathOp::NewPath)                   => { self.current_path = vec![]; }
+            Path(path_op)                           => { self.current_path.push(path_op); }
+            StorePath                               => { self.stored_path = self.current_path.clone(); }
+            CombinePath(operation)                  => { self.current_path = combine_paths(operation, self.state.winding_rule, &self.stored_path, &self.current_path); }
+
+            Fill                                    => { self.fill(); }
+            Stroke                                  => { self.stroke(); }
+            Clip                                    => { self.clip(); }
+            Unclip                                  => { self.state.clip = None; }
+
+            LineWidth(width)                        => { self.state.line_width = width; self.state.line_width_pixels = false; }
+            LineWidthPixels(width)                  => { self.state.line_width = width; self.state.line_width_pixels = true; }
+            LineJoin(join)                          => { self.state.line_join = join; }
+            MiterLimit(limit)                       => { self.state.miter_limit = limit; }
+            LineCap(cap)                            => { self.state.line_cap = cap; }
+            NewDashPattern                          => { self.state.dash_pattern = vec![]; self.state.dash_offset = 0.0; }
+            DashLength(length)                      => { self.state.dash_pattern.push(length); }
+            DashOffset(offset)                      => { self.state.dash_offset = offset; }
+            WindingRule(winding_rule)               => { self.state.winding_rule = winding_rule; }
+            BlendMode(blend_mode)                   => { self.state.blend_mode = blend_mode; }
+
+            FillColor(color)                        => { self.state.fill = SvgPaint::Color(color); self.state.fill_transform = Transform2D::identity(); }
+            StrokeColor(color)                      => { self.state.stroke = SvgPaint::Color(color); }
+            FillTransform(transform)                => { self.state.fill_transform = self.state.fill_transform * transform; }
+
+            FillTexture(texture_id, lower_left, upper_right) => {
+                if let Some(paint) = self.texture_paint(texture_id, lower_left, upper_right) {
+                    self.state.fill             = paint;
+                    self.state.fill_transform   = Transform2D::identity();
+                }
+            }
+
+            StrokeTexture(texture_id, lower_left, upper_right) => {
+                if let Some(paint) = self.texture_paint(texture_id, lower_left, upper_right) {
+                    self.state.stroke = paint;
+                }
+            }
+
+            FillGradient(gradient_id, start, end)   => {
+                if let Some((stops, spread)) = self.gradient(gradient_id) {
+                    self.state.fill             = SvgPaint::LinearGradient { stops: stops, spread: spread, start: start, end: end };
+                    self.state.fill_transform   = Transform2D::identity();
+                }
+            }
+
+            StrokeGradient(gradient_id, start, end) => {
+                if let Some((stops, spread)) = self.gradient(gradient_id) {
+                    self.state.stroke = SvgPaint::LinearGradient { stops: stops, spread: spread, start: start, end: end };
+                }
+            }
+
+            FillRadialGradient(gradient_id, center, radius, focus) => {
+                if let Some((stops, spread)) = self.gradient(gradient_id) {
+                    self.state.fill             = SvgPaint::RadialGradient { stops: stops, spread: spread, center: center, radius: radius, focus: focus };
+                    self.state.fill_transform   = Transform2D::identity();
+                }
+            }
+
+            FillConicGradient(gradient_id, _, _)    => {
+                // SVG has no conic gradients, so these are filled with the colour of the first stop
+                if let Some((stops, _)) = self.gradient(gradient_id) {
+                    if let Some((_, color)) = stops.first() {
+                        self.state.fill             = SvgPaint::Color(*color);
+                        self.state.fill_transform   = Transform2D::identity();
+                    }
+                }
+            }
+
+            // State
+            PushState                               => { self.state_stack.push((self.canvas_transform.transform(), self.state.clone(), self.current_path.clone(), self.namespace)); }
+            PopState                                => {
+                if let Some((transform, state, path, namespace)) = self.state_stack.pop() {
+                    self.canvas_transform.set_transform(transform);
+                    self.state          = state;
+                    self.current_path   = path;
+                    self.namespace      = namespace;
+                }
+            }
+
+            Store                                   => {
+                if let SvgTarget::Layer(layer_id) = self.target {
+                    let elements = self.layers.get(&layer_id).cloned().unwrap_or_default();
+                    self.stored_layers.insert(layer_id, elements);
+                }
+            }
+
+            Restore                                 => {
+                if let SvgTarget::Layer(layer_id) = self.target {
+                    if let Some(elements) = self.stored_layers.get(&layer_id) {
+                        self.layers.insert(layer_id, elements.clone());
+                    }
+                }
+            }
+
+            FreeStoredBuffer                        => {
+                if let SvgTarget::Layer(layer_id) = self.target {
+                    self.stored_layers.remove(&layer_id);
+                }
+            }
+
+            ClearCanvas(color)                      => {
+                *self               = SvgWriter::new(self.width, self.height);
+                self.background     = Some(color);
+            }
+
+            // Layers
+            Layer(layer_id)                         => {
+                let new_state = self.target_state.remove(&SvgTarget::Layer(layer_id)).unwrap_or_default();
+                self.target_state.insert(self.target, mem::replace(&mut self.state, new_state));
+                self.target = SvgTarget::Layer(layer_id);
+
+                self.add_layer(layer_id);
+            }
+
+            LayerBlend(layer_id, blend_mode)        => { self.layer_blend.insert(layer_id, blend_mode); }
+            LayerAlpha(layer_id, alpha)             => { self.layer_alpha.insert(layer_id, alpha); }
+            ClearLayer                              => { self.clear_target(); }
+            ClearAllLayers                          => { self.layers.clear(); }
+
+            SwapLayers(layer1, layer2)              => {
+                self.add_layer(layer1);
+                self.add_layer(layer2);
+
+                let pos1 = self.layer_order.iter().position(|existing| existing == &layer1).unwrap();
+                let pos2 = self.layer_order.iter().position(|existing| existing == &layer2).unwrap();
+                self.layer_order.swap(pos1, pos2);
+            }
+
+            // Sprites
+            Sprite(sprite_id)                       => {
+                let target      = SvgTarget::Sprite(self.namespace, sprite_id);
+                let new_state   = self.target_state.remove(&target).unwrap_or_default();
+                self.target_state.insert(self.target, mem::replace(&mut self.state, new_state));
+                self.target     = target;
+            }
+
+            MoveSpriteFrom(sprite_id)               => {
+                if let SvgTarget::Sprite(namespace_id, current_sprite_id) = self.target {
+                    if (namespace_id, current_sprite_id) != (self.namespace, sprite_id) {
+                        let sprite = self.sprites.remove(&(self.namespace, sprite_id)).unwrap_or_default();
+                        self.sprites.insert((namespace_id, current_sprite_id), sprite);
+                    }
+                }
+            }
+
+            ClearSprite                             => { self.clear_target(); }
+
+            SpriteTransform(self::SpriteTransform::Identity)    => { self.state.sprite_transform = Transform2D::identity(); }
+            SpriteTransform(sprite_transform)                   => { self.state.sprite_transform = Transform2D::from(sprite_transform) * self.state.sprite_transform; }
+
+            DrawSprite(sprite_id)                   => { self.draw_sprite(sprite_id, &[]); }
+            DrawSpriteWithFilters(sprite_id, filters) => { self.draw_sprite(sprite_id, &filters); }
+
+            // Resources
+            Texture(texture_id, texture_op)         => { self.texture(texture_id, texture_op); }
+            Font(font_id, font_op)                  => { self.font(font_id, font_op); }
+            Namespace(namespace_id)                 => { self.namespace = namespace_id; }
+
+            Gradient(gradient_id, gradient_op)      => {
+                let key = (self.namespace, gradient_id);
+
+                match gradient_op {
+                    GradientOp::Create(color)           => { self.gradients.insert(key, (vec![(0.0, color)], GradientSpread::Pad)); }
+                    GradientOp::AddStop(pos, color)     => { if let Some((stops, _)) = self.gradients.get_mut(&key) { stops.push((pos, color)); } }
+                    GradientOp::Spread(spread)          => { if let Some((_, old_spread)) = self.gradients.get_mut(&key) { *old_spread = spread; } }
+                }
+            }
+
+            // Text
+            BeginLineLayout(x, y, alignment)        => { self.line_layout = Some(SvgLineLayout { position: (x, y), alignment: alignment, spans: String::new() }); }
+
+            // SVG text elements can't wrap, so paragraphs are written as a single line (use `SvgText::Outlines` to wrap the text)
+            BeginParagraphLayout(x, y, style)       => {
+                let (x, alignment) = match style.alignment {
+                    ParagraphAlignment::Left    |
+                    ParagraphAlignment::Justify => (x, TextAlignment::Left),
+                    ParagraphAlignment::Center  => (x + style.width/2.0, TextAlignment::Center),
+                    ParagraphAlignment::Right   => (x + style.width, TextAlignment::Right),
+                };
+
+                self.line_layout = Some(SvgLineLayout { position: (x, y), alignment: alignment, spans: String::new() });
+            }
+            DrawLaidOutText                         => { self.draw_laid_out_text(); }
+
+            DrawText(font_id, text, x, y)           => {
+                if let Some(font_attributes) = self.font_attributes(font_id) {
+                    let placement   = Transform2D::translate(x, y) * Transform2D::scale(1.0, -1.0);
+                    let markup      = format!("<text{}{}>{}</text>", self.text_attributes(&placement), font_attributes, svg_escape(&text));
+
+                    self.add_element(markup);
+                }
+            }
+        }
+    }
+
+    ///
+    /// Returns the SVG document for everything that has been drawn so far
+    ///
+    pub fn document(&self) -> String {
+        let width   = svg_number(self.width);
+        let height  = svg_number(self.height);
+        let mut svg = String::new();
+
+        writeln!(svg, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").ok();
+        writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" version=\"1.1\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">", width, height, width, height).ok();
+
+        // Definitions
+        if !self.definitions.is_empty() || !self.font_faces.is_empty() {
+            writeln!(svg, "<defs>").ok();
+
+            if !self.font_faces.is_empty() {
+                writeln!(svg, "<style>").ok();
+                for (font_face, family) in self.font_faces.iter() {
+                    writeln!(svg, "@font-face {{ font-family: \"{}\"; src: url(data:font/ttf;base64,{}); }}", family, base64(font_face.font_data())).ok();
+                }
+                writeln!(svg, "</style>").ok();
+            }
+
+            for definition in self.definitions.iter() {
+                writeln!(svg, "{}", definition).ok();
+            }
+
+            writeln!(svg, "</defs>").ok();
+        }
+
+        // Background
+        if let Some(background) = &self.background {
+            if svg_color(background).1 > 0.0 {
+                writeln!(svg, "<rect width=\"{}\" height=\"{}\"{}/>", width, height, svg_color_attributes("fill", "fill-opacity", background)).ok();
+            }
+        }
+
+        // The canvas coordinates are 2.0 units high, with (0, 0) at the center of the document and y pointing upwards
+        let scale = self.height / 2.0;
+        writeln!(svg, "<g transform=\"matrix({} 0 0 {} {} {})\">", svg_number(scale), svg_number(-scale), svg_number(self.width / 2.0), svg_number(self.height / 2.0)).ok();
+
+        for layer_id in self.layer_order.iter() {
+            let elements = if let Some(elements) = self.layers.get(layer_id) { elements } else { continue; };
+            if elements.is_empty() { continue; }
+
+            svg.push_str("<g");
+            if let Some(alpha) = self.layer_alpha.get(layer_id) {
+                if *alpha < 1.0 { write!(svg, " opacity=\"{}\"", svg_number(alpha.max(0.0))).ok(); }
+            }
+            if let Some(blend_mode) = self.layer_blend.get(layer_id).and_then(|blend_mode| svg_blend_mode(*blend_mode)) {
+                write!(svg, " style=\"mix-blend-mode:{}\"", blend_mode).ok();
+            }
+            svg.push_str(">\n");
+
+            write_elements(elements, &mut svg);
+
+            svg.push_str("</g>\n");
+        }
+
+        writeln!(svg, "</g>").ok();
+        writeln!(svg, "</svg>").ok();
+
+        svg
+    }
+}
+
+impl GraphicsContext for SvgWriter {
+    #[inline]
+    fn draw(&mut self, drawing: Draw) {
+        self.process(drawing);
+    }
+}
+
+///
+/// Writes out the markup for a set of elements, grouping elements that share a clip path
+///
+fn write_elements(elements: &[SvgElement], svg: &mut String) {
+    let mut current_clip: Option<&String> = None;
+
+    for element in elements.iter() {
+        if element.clip.as_ref() != current_clip {
+            if current_clip.is_some() { svg.push_str("</g>\n"); }
+            if let Some(clip) = &element.clip { writeln!(svg, "<g clip-path=\"url(#{})\">", clip).ok(); }
+
+            current_clip = element.clip.as_ref();
+        }
+
+        svg.push_str(&element.markup);
+        svg.push('\n');
+    }
+
+    if current_clip.is_some() { svg.push_str("</g>\n"); }
+}
+
+///
+/// Returns the `spreadMethod` attribute for a gradient spread
+///
+fn spread_attribute(spread: GradientSpread) -> &'static str {
+    match spread {
+        GradientSpread::Pad     => "",
+        GradientSpread::Repeat  => " spreadMethod=\"repeat\"",
+        GradientSpread::Reflect => " spreadMethod=\"reflect\"",
+    }
+}
+
+///
+/// Returns the `<stop>` elements for a gradient (SVG requires these to be in order)
+///
+fn gradient_stops(stops: &[(f32, Color)]) -> String {
+    let mut stops = stops.to_vec();
+    stops.sort_by(|(pos1, _), (pos2, _)| pos1.partial_cmp(pos2).unwrap_or(std::cmp::Ordering::Equal));
+
+    stops.iter()
+        .map(|(pos, color)| format!("<stop offset=\"{}\"{}/>", svg_number(*pos), svg_color_attributes("stop-color", "stop-opacity", color)))
+        .collect()
+}
+
+///
+/// Generates an SVG document from a stream of drawing instructions
+///
+/// See `SvgWriter` for details of how the drawing is converted. If the options request that text is written as outlines, it's
+/// converted to paths before it's written to the document.
+///
+pub fn drawing_to_svg<InStream: 'static+Send+Unpin+Stream<Item=Draw>>(draw_stream: InStream, options: SvgOptions) -> impl Send+Future<Output=String> {
+    async move {
+        let mut writer = SvgWriter::new(options.width, options.height);
+
+        match options.text {
+            SvgText::Elements => {
+                let mut draw_stream = draw_stream;
+
+                while let Some(drawing) = draw_stream.next().await {
+                    writer.draw(drawing);
+                }
+            }
+
+            #[cfg(feature = "outline-fonts")]
+            SvgText::Outlines => {
+                let mut draw_stream = drawing_with_text_as_paths(drawing_with_laid_out_text(draw_stream));
+
+                while let Some(drawing) = draw_stream.next().await {
+                    writer.draw(drawing);
+                }
+            }
+        }
+
+        writer.document()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::primitives::*;
+
+    use futures::stream;
+    use futures::executor;
+
+    fn svg_for(drawing: Vec<Draw>) -> String {
+        let mut writer = SvgWriter::new(200.0, 100.0);
+
+        for draw in drawing {
+            writer.draw(draw);
+        }
+
+        writer.document()
+    }
+
+    #[test]
+    fn empty_document() {
+        let svg = svg_for(vec![]);
+
+        assert!(svg.contains("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.contains("width=\"200\" height=\"100\" viewBox=\"0 0 200 100\""));
+        assert!(svg.contains("<g transform=\"matrix(50 0 0 -50 100 50)\">"));
+        assert!(!svg.contains("<defs>"));
+        assert!(!svg.contains("<path"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn fill_rectangle() {
+        let mut drawing = vec![];
+        drawing.canvas_height(100.0);
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 10.0, 20.0);
+        drawing.fill_color(Color::Rgba(1.0, 0.0, 0.0, 0.5));
+        drawing.winding_rule(WindingRule::EvenOdd);
+        drawing.fill();
+
+        let svg = svg_for(drawing);
+
+        assert!(svg.contains("<path d=\"M0 0L0 20L10 20L10 0L0 0Z\" fill=\"#ff0000\" fill-opacity=\"0.5\" fill-rule=\"evenodd\" transform=\"matrix(0.02 0 0 0.02 0 0)\"/>"), "{}", svg);
+    }
+
+    #[test]
+    fn stroke_with_dashes() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.move_to(0.0, 0.0);
+        drawing.line_to(1.0, 0.0);
+        drawing.stroke_color(Color::Rgba(0.0, 0.0, 1.0, 1.0));
+        drawing.line_width(0.25);
+        drawing.line_join(LineJoin::Miter);
+        drawing.line_cap(LineCap::Square);
+        drawing.new_dash_pattern();
+        drawing.dash_length(0.5);
+        drawing.dash_length(0.25);
+        drawing.dash_offset(0.125);
+        drawing.stroke();
+
+        let svg = svg_for(drawing);
+
+        assert!(svg.contains("<path d=\"M0 0L1 0\" fill=\"none\" stroke=\"#0000ff\" stroke-width=\"0.25\" stroke-linejoin=\"miter\" stroke-miterlimit=\"4\" stroke-linecap=\"square\" stroke-dasharray=\"0.5 0.25\" stroke-dashoffset=\"0.125\"/>"), "{}", svg);
+    }
+
+    #[test]
+    fn stroke_in_pixels() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.move_to(0.0, 0.0);
+        drawing.line_to(1.0, 0.0);
+        drawing.line_width_pixels(2.0);
+        drawing.stroke();
+
+        let svg = svg_for(drawing);
+
+        assert!(svg.contains("stroke-width=\"2\""));
+        assert!(svg.contains("vector-effect=\"non-scaling-stroke\""));
+    }
+
+    #[test]
+    fn layers_are_groups_in_order() {
+        let mut drawing = vec![];
+        drawing.layer(LayerId(2));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 2.0, 2.0);
+        drawing.fill();
+        drawing.layer(LayerId(1));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+        drawing.layer_alpha(LayerId(1), 0.5);
+        drawing.layer_blend(LayerId(2), BlendMode::Multiply);
+
+        let svg = svg_for(drawing);
+
+        let layer1 = svg.find("<g opacity=\"0.5\">\n<path d=\"M0 0L0 1L1 1L1 0L0 0Z\"").expect("layer 1");
+        let layer2 = svg.find("<g style=\"mix-blend-mode:multiply\">\n<path d=\"M0 0L0 2L2 2L2 0L0 0Z\"").expect("layer 2");
+
+        assert!(layer1 < layer2);
+    }
+
+    #[test]
+    fn swapped_layers() {
+        let mut drawing = vec![];
+        drawing.layer(LayerId(1));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+        drawing.layer(LayerId(2));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 2.0, 2.0);
+        drawing.fill();
+        drawing.swap_layers(LayerId(1), LayerId(2));
+
+        let svg = svg_for(drawing);
+
+        assert!(svg.find("L2 2").unwrap() < svg.find("L1 1").unwrap());
+    }
+
+    #[test]
+    fn clip_paths_are_intersected() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.clip();
+        drawing.new_path();
+        drawing.rect(0.5, 0.5, 2.0, 2.0);
+        drawing.clip();
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 3.0, 3.0);
+        drawing.fill();
+        drawing.unclip();
+        drawing.fill();
+
+        let svg = svg_for(drawing);
+
+        assert!(svg.contains("<clipPath id=\"clip0\" clipPathUnits=\"userSpaceOnUse\"><path d=\"M0 0L0 1L1 1L1 0L0 0Z\"/></clipPath>"), "{}", svg);
+        assert!(svg.contains("<clipPath id=\"clip1\" clipPathUnits=\"userSpaceOnUse\" clip-path=\"url(#clip0)\">"), "{}", svg);
+        assert!(svg.contains("<g clip-path=\"url(#clip1)\">\n<path d=\"M0 0L0 3L3 3L3 0L0 0Z\" fill=\"#000000\"/>\n</g>\n<path d=\"M0 0L0 3L3 3L3 0L0 0Z\" fill=\"#000000\"/>"), "{}", svg);
+    }
+
+    #[test]
+    fn linear_gradient_fill() {
+        let mut drawing = vec![];
+        drawing.create_gradient(GradientId(1), Color::Rgba(1.0, 0.0, 0.0, 1.0));
+        drawing.gradient_stop(GradientId(1), 1.0, Color::Rgba(0.0, 0.0, 1.0, 0.5));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill_gradient(GradientId(1), 0.0, 0.0, 1.0, 0.0);
+        drawing.fill_transform(Transform2D::translate(0.5, 0.0));
+        drawing.fill();
+
+        let svg = svg_for(drawing);
+
+        assert!(svg.contains("<linearGradient id=\"gradient0\" gradientUnits=\"userSpaceOnUse\" x1=\"0\" y1=\"0\" x2=\"1\" y2=\"0\" gradientTransform=\"matrix(1 0 0 1 0.5 0)\"><stop offset=\"0\" stop-color=\"#ff0000\"/><stop offset=\"1\" stop-color=\"#0000ff\" stop-opacity=\"0.5\"/></linearGradient>"), "{}", svg);
+        assert!(svg.contains("fill=\"url(#gradient0)\""));
+    }
+
+    #[test]
+    fn identical_gradients_are_defined_once() {
+        let mut drawing = vec![];
+        drawing.create_gradient(GradientId(1), Color::Rgba(1.0, 0.0, 0.0, 1.0));
+        drawing.gradient_stop(GradientId(1), 1.0, Color::Rgba(0.0, 0.0, 1.0, 1.0));
+
+        for _ in 0..3 {
+            drawing.new_path();
+            drawing.rect(0.0, 0.0, 1.0, 1.0);
+            drawing.fill_gradient(GradientId(1), 0.0, 0.0, 1.0, 0.0);
+            drawing.fill();
+        }
+
+        let svg = svg_for(drawing);
+
+        assert!(svg.matches("<linearGradient").count() == 1);
+        assert!(svg.matches("fill=\"url(#gradient0)\"").count() == 3);
+    }
+
+    #[test]
+    fn texture_fill_is_embedded_image() {
+        let mut drawing = vec![];
+        drawing.create_texture(TextureId(1), 2, 2, TextureFormat::Rgba);
+        drawing.set_texture_bytes(TextureId(1), 0, 0, 2, 2, Arc::new(vec![255; 16]));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill_texture(TextureId(1), 0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+
+        let svg = svg_for(drawing);
+
+        assert!(svg.contains("<image id=\"image0\" width=\"1\" height=\"1\" preserveAspectRatio=\"none\" xlink:href=\"data:image/png;base64,"), "{}", svg);
+        assert!(svg.contains("<pattern id=\"pattern1\" patternUnits=\"userSpaceOnUse\" width=\"1\" height=\"1\" patternTransform=\"matrix(1 0 0 -1 0 1)\"><use xlink:href=\"#image0\"/></pattern>"), "{}", svg);
+        assert!(svg.contains("fill=\"url(#pattern1)\""));
+    }
+
+    #[test]
+    fn huge_texture_is_left_out() {
+        let mut drawing = vec![];
+        drawing.create_texture(TextureId(1), u32::MAX, u32::MAX, TextureFormat::Rgba);
+        drawing.set_texture_bytes(TextureId(1), 0, 0, 2, 2, Arc::new(vec![255; 16]));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill_texture(TextureId(1), 0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+
+        let svg = svg_for(drawing);
+
+        assert!(!svg.contains("<image"), "{}", svg);
+    }
+
+    #[test]
+    fn sprites_are_symbols() {
+        let mut drawing = vec![];
+        drawing.sprite(SpriteId(1));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+        drawing.layer(LayerId(0));
+        drawing.sprite_transform(SpriteTransform::Translate(2.0, 3.0));
+        drawing.draw_sprite(SpriteId(1));
+        drawing.sprite_transform(SpriteTransform::Translate(1.0, 0.0));
+        drawing.draw_sprite(SpriteId(1));
+
+        let svg = svg_for(drawing);
+
+        assert!(svg.contains("<symbol id=\"sprite0\" overflow=\"visible\"><path d=\"M0 0L0 1L1 1L1 0L0 0Z\" fill=\"#000000\"/>\n</symbol>"), "{}", svg);
+        assert!(svg.contains("<use xlink:href=\"#sprite0\" transform=\"matrix(1 0 0 1 2 3)\"/>"), "{}", svg);
+        assert!(svg.contains("<use xlink:href=\"#sprite0\" transform=\"matrix(1 0 0 1 3 3)\"/>"), "{}", svg);
+        assert!(svg.matches("<symbol").count() == 1);
+    }
+
+    #[test]
+    fn changed_sprite_gets_new_symbol() {
+        let mut drawing = vec![];
+        drawing.sprite(SpriteId(1));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+        drawing.layer(LayerId(0));
+        drawing.draw_sprite(SpriteId(1));
+        drawing.sprite(SpriteId(1));
+        drawing.clear_sprite();
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 2.0, 2.0);
+        drawing.fill();
+        drawing.layer(LayerId(0));
+        drawing.draw_sprite(SpriteId(1));
+
+        let svg = svg_for(drawing);
+
+        assert!(svg.matches("<symbol").count() == 2);
+        assert!(svg.contains("<use xlink:href=\"#sprite0\"/>"));
+        assert!(svg.contains("<use xlink:href=\"#sprite1\"/>"));
+    }
+
+    #[test]
+    fn sprite_with_blur() {
+        let mut drawing = vec![];
+        drawing.sprite(SpriteId(1));
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+        drawing.layer(LayerId(0));
+        drawing.draw_sprite_with_filters(SpriteId(1), vec![TextureFilter::GaussianBlur(4.0)]);
+
+        let svg = svg_for(drawing);
+
+        assert!(svg.contains("<feGaussianBlur stdDeviation=\"1\"/>"));
+        assert!(svg.contains("<use xlink:href=\"#sprite0\" filter=\"url(#filter1)\"/>"), "{}", svg);
+    }
+
+    #[test]
+    fn clear_canvas_sets_background() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.rect(0.0, 0.0, 1.0, 1.0);
+        drawing.fill();
+        drawing.clear_canvas(Color::Rgba(1.0, 1.0, 1.0, 1.0));
+
+        let svg = svg_for(drawing);
+
+        assert!(svg.contains("<rect width=\"200\" height=\"100\" fill=\"#ffffff\"/>"));
+        assert!(!svg.contains("<path"));
+    }
+
+    #[test]
+    fn text_elements() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../../test_data/Lato-Regular.ttf"));
+
+        let mut drawing = vec![];
+        drawing.define_font_data(FontId(1), Arc::clone(&lato));
+        drawing.set_font_size(FontId(1), 16.0);
+        drawing.draw_text(FontId(1), "A < B".to_string(), 10.0, 20.0);
+
+        let svg = svg_for(drawing);
+
+        assert!(svg.contains("@font-face { font-family: \"font0\"; src: url(data:font/ttf;base64,"));
+        assert!(svg.contains("<text transform=\"matrix(1 0 0 -1 10 20)\" fill=\"#000000\" xml:space=\"preserve\" font-family=\"font0\" font-size=\"16\">A &lt; B</text>"), "{}", svg);
+    }
+
+    #[test]
+    fn text_with_font_features() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../../test_data/Lato-Regular.ttf"));
+
+        let mut drawing = vec![];
+        drawing.define_font_data(FontId(1), Arc::clone(&lato));
+        drawing.set_font_size(FontId(1), 16.0);
+        drawing.set_font_feature(FontId(1), *b"liga", false);
+        drawing.set_font_feature(FontId(1), *b"tnum", true);
+        drawing.set_font_variation(FontId(1), *b"wght", 700.0);
+        drawing.draw_text(FontId(1), "fi 10".to_string(), 10.0, 20.0);
+
+        let svg = svg_for(drawing);
+
+        assert!(svg.contains("font-size=\"16\" style=\"font-feature-settings: 'liga' 0, 'tnum' 1; font-variation-settings: 'wght' 700\">fi 10</text>"), "{}", svg);
+    }
+
+    #[test]
+    fn laid_out_text_elements() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../../test_data/Lato-Regular.ttf"));
+
+        let mut drawing = vec![];
+        drawing.define_font_data(FontId(1), Arc::clone(&lato));
+        drawing.define_font_data(FontId(2), Arc::clone(&lato));
+        drawing.set_font_size(FontId(2), 24.0);
+        drawing.begin_line_layout(0.0, 0.0, TextAlignment::Center);
+        drawing.layout_text(FontId(1), "Hello, ".to_string());
+        drawing.layout_text(FontId(2), "world".to_string());
+        drawing.draw_text_layout();
+
+        let svg = svg_for(drawing);
+
+        // The same font face is only embedded once
+        assert!(svg.matches("@font-face").count() == 1);
+        assert!(svg.contains("text-anchor=\"middle\"><tspan font-family=\"font0\" font-size=\"12\">Hello, </tspan><tspan font-family=\"font0\" font-size=\"24\">world</tspan></text>"), "{}", svg);
+    }
+
+    #[test]
+    fn drawing_to_svg_stream() {
+        let mut drawing = vec![];
+        drawing.new_path();
+        drawing.circle(0.0, 0.0, 0.5);
+        drawing.fill();
+
+        let svg = executor::block_on(drawing_to_svg(stream::iter(drawing), SvgOptions::new(100.0, 100.0)));
+
+        assert!(svg.contains("<path d=\"M"));
+        assert!(svg.contains("width=\"100\" height=\"100\""));
+    }
+
+    #[cfg(feature = "outline-fonts")]
+    #[test]
+    fn text_as_outlines() {
+        let lato        = CanvasFontFace::from_slice(include_bytes!("../../test_data/Lato-Regular.ttf"));
+
+        let mut drawing = vec![];
+        drawing.define_font_data(FontId(1), Arc::clone(&lato));
+        drawing.set_font_size(FontId(1), 0.5);
+        drawing.draw_text(FontId(1), "Hello".to_string(), 0.0, 0.0);
+
+        let svg = executor::block_on(drawing_to_svg(stream::iter(drawing), SvgOptions::new(100.0, 100.0).with_text(SvgText::Outlines)));
+
+        assert!(!svg.contains("<text"));
+        assert!(!svg.contains("@font-face"));
+        assert!(svg.contains("<path d=\"M"));
+    }
+}
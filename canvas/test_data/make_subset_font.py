@@ -0,0 +1,230 @@
+#!/usr/bin/env python3
+#
+# Generates DejaVuSans-Hebrew.ttf: the glyphs from DejaVu Sans for the Hebrew block and the space character, used to
+# test fallback fonts without needing the whole of DejaVu Sans
+#
+# The subset keeps the outlines, metrics and names of the original font. The hinting instructions and the OpenType
+# layout tables are removed, as the tests don't use them.
+#
+# Run this script from the test_data directory, passing the path to the full DejaVuSans.ttf, to regenerate the font.
+#
+
+import struct
+import sys
+
+# The characters to keep in the subset
+CHARACTERS      = [0x20] + list(range(0x0590, 0x0600))
+
+# The tables copied from the original font (the others are generated or left out)
+COPIED_TABLES   = [b'OS/2', b'hhea', b'maxp', b'name']
+
+def read_tables(data):
+    num_tables  = struct.unpack('>H', data[4:6])[0]
+    tables      = {}
+
+    for idx in range(num_tables):
+        tag, _, offset, length = struct.unpack('>4sIII', data[12 + idx*16:28 + idx*16])
+        tables[tag] = data[offset:offset + length]
+
+    return tables
+
+def read_cmap(cmap):
+    # Uses the Windows Unicode BMP subtable, which is in format 4
+    num_subtables = struct.unpack('>H', cmap[2:4])[0]
+
+    for idx in range(num_subtables):
+        platform, encoding, offset = struct.unpack('>HHI', cmap[4 + idx*8:12 + idx*8])
+        if (platform, encoding) == (3, 1) and struct.unpack('>H', cmap[offset:offset+2])[0] == 4:
+            break
+    else:
+        raise Exception('No format 4 cmap subtable')
+
+    subtable        = cmap[offset:]
+    seg_count       = struct.unpack('>H', subtable[6:8])[0] // 2
+    end_codes       = struct.unpack('>%dH' % seg_count, subtable[14:14 + seg_count*2])
+    start_codes     = struct.unpack('>%dH' % seg_count, subtable[16 + seg_count*2:16 + seg_count*4])
+    id_deltas       = struct.unpack('>%dh' % seg_count, subtable[16 + seg_count*4:16 + seg_count*6])
+    range_offsets   = 16 + seg_count*6
+    mapping         = {}
+
+    for seg in range(seg_count):
+        id_range_offset = struct.unpack('>H', subtable[range_offsets + seg*2:range_offsets + seg*2 + 2])[0]
+
+        for code in range(start_codes[seg], end_codes[seg] + 1):
+            if code == 0xffff:
+                continue
+
+            if id_range_offset == 0:
+                glyph = (code + id_deltas[seg]) & 0xffff
+            else:
+                glyph_offset    = range_offsets + seg*2 + id_range_offset + (code - start_codes[seg])*2
+                glyph           = struct.unpack('>H', subtable[glyph_offset:glyph_offset+2])[0]
+                if glyph != 0:
+                    glyph = (glyph + id_deltas[seg]) & 0xffff
+
+            if glyph != 0:
+                mapping[code] = glyph
+
+    return mapping
+
+def read_glyphs(tables):
+    num_glyphs  = struct.unpack('>H', tables[b'maxp'][4:6])[0]
+    long_loca   = struct.unpack('>h', tables[b'head'][50:52])[0] == 1
+
+    if long_loca:
+        offsets = struct.unpack('>%dI' % (num_glyphs+1), tables[b'loca'][:(num_glyphs+1)*4])
+    else:
+        offsets = [offset*2 for offset in struct.unpack('>%dH' % (num_glyphs+1), tables[b'loca'][:(num_glyphs+1)*2])]
+
+    return [tables[b'glyf'][offsets[idx]:offsets[idx+1]] for idx in range(num_glyphs)]
+
+def components(glyph):
+    # Returns the offsets of the glyph IDs in a composite glyph, and the offset of its instructions
+    result  = []
+    pos     = 10
+
+    while True:
+        flags, glyph_id = struct.unpack('>HH', glyph[pos:pos+4])
+        result.append((pos + 2, glyph_id))
+        pos += 4
+        pos += 4 if flags & 0x0001 else 2       # ARG_1_AND_2_ARE_WORDS
+
+        if flags & 0x0008: pos += 2             # WE_HAVE_A_SCALE
+        elif flags & 0x0040: pos += 4           # WE_HAVE_AN_X_AND_Y_SCALE
+        elif flags & 0x0080: pos += 8           # WE_HAVE_A_TWO_BY_TWO
+
+        if not flags & 0x0020:                  # MORE_COMPONENTS
+            return result, pos
+
+def subset_glyph(glyph, new_ids):
+    # Removes the instructions from a glyph, and renumbers the components of composite glyphs
+    if len(glyph) == 0:
+        return glyph
+
+    num_contours = struct.unpack('>h', glyph[0:2])[0]
+
+    if num_contours >= 0:
+        instructions_pos    = 10 + num_contours*2
+        instructions_len    = struct.unpack('>H', glyph[instructions_pos:instructions_pos+2])[0]
+        glyph               = glyph[:instructions_pos] + struct.pack('>H', 0) + glyph[instructions_pos + 2 + instructions_len:]
+    else:
+        glyph_ids, end  = components(glyph)
+        glyph           = bytearray(glyph[:end])
+
+        for pos, glyph_id in glyph_ids:
+            flags = struct.unpack('>H', glyph[pos-2:pos])[0] & ~0x0100         # WE_HAVE_INSTRUCTIONS
+            glyph[pos-2:pos+2] = struct.pack('>HH', flags, new_ids[glyph_id])
+
+        glyph = bytes(glyph)
+
+    return glyph + b'\0' * (-len(glyph) % 4)
+
+def metrics(tables, num_glyphs):
+    num_metrics = struct.unpack('>H', tables[b'hhea'][34:36])[0]
+    hmtx        = tables[b'hmtx']
+    result      = []
+
+    for idx in range(num_glyphs):
+        if idx < num_metrics:
+            result.append(struct.unpack('>Hh', hmtx[idx*4:idx*4+4]))
+        else:
+            lsb_pos = num_metrics*4 + (idx - num_metrics)*2
+            result.append((result[num_metrics-1][0], struct.unpack('>h', hmtx[lsb_pos:lsb_pos+2])[0]))
+
+    return result
+
+def cmap(mapping):
+    # Format 4 subtable with a segment for each character and the final 0xffff segment
+    codes       = sorted(mapping.keys()) + [0xffff]
+    seg_count   = len(codes)
+    selector    = seg_count.bit_length() - 1
+    subtable    = struct.pack('>HHHHHHH', 4, 16 + seg_count*8, 0, seg_count*2, (1 << selector)*2, selector, seg_count*2 - (1 << selector)*2)
+    subtable   += struct.pack('>%dH' % seg_count, *codes)
+    subtable   += struct.pack('>H', 0)
+    subtable   += struct.pack('>%dH' % seg_count, *codes)
+    subtable   += struct.pack('>%dh' % seg_count, *[((mapping.get(code, 1) - code + 0x8000) & 0xffff) - 0x8000 for code in codes])
+    subtable   += struct.pack('>%dH' % seg_count, *([0] * seg_count))
+
+    return struct.pack('>HHHHI', 0, 1, 3, 1, 12) + subtable
+
+def checksum(data):
+    data = data + b'\0' * (-len(data) % 4)
+    return sum(struct.unpack('>%dI' % (len(data)//4), data)) & 0xffffffff
+
+def font(tables):
+    # Table records are sorted by tag
+    tables      = sorted(tables.items())
+    num_tables  = len(tables)
+    offset      = 12 + num_tables*16
+    selector    = num_tables.bit_length() - 1
+    directory   = struct.pack('>IHHHH', 0x00010000, num_tables, (1 << selector)*16, selector, num_tables*16 - (1 << selector)*16)
+    data        = b''
+
+    for tag, table in tables:
+        directory  += struct.pack('>4sIII', tag, checksum(table), offset + len(data), len(table))
+        data       += table + b'\0' * (-len(table) % 4)
+
+    # The head table stores an adjustment that makes the checksum of the whole font 0xB1B0AFBA
+    result          = bytearray(directory + data)
+    head_offset     = [struct.unpack('>I', result[12 + idx*16 + 8:12 + idx*16 + 12])[0] for idx in range(num_tables) if result[12 + idx*16:16 + idx*16] == b'head'][0]
+    result[head_offset+8:head_offset+12] = struct.pack('>I', (0xB1B0AFBA - checksum(bytes(result))) & 0xffffffff)
+
+    return bytes(result)
+
+def subset(data):
+    tables      = read_tables(data)
+    mapping     = read_cmap(tables[b'cmap'])
+    glyphs      = read_glyphs(tables)
+    hmetrics    = metrics(tables, len(glyphs))
+
+    # Keep .notdef, the glyphs for the characters and the glyphs used as components by those glyphs
+    keep        = [0]
+    for code in CHARACTERS:
+        if code in mapping and mapping[code] not in keep:
+            keep.append(mapping[code])
+
+    idx = 0
+    while idx < len(keep):
+        glyph = glyphs[keep[idx]]
+        if len(glyph) > 0 and struct.unpack('>h', glyph[0:2])[0] < 0:
+            for _, component in components(glyph)[0]:
+                if component not in keep:
+                    keep.append(component)
+        idx += 1
+
+    new_ids     = { old_id: new_id for new_id, old_id in enumerate(keep) }
+
+    # Generate the glyph data
+    glyf        = b''
+    loca        = []
+    for old_id in keep:
+        loca.append(len(glyf))
+        glyf += subset_glyph(glyphs[old_id], new_ids)
+    loca.append(len(glyf))
+
+    new_tables              = { tag: tables[tag] for tag in COPIED_TABLES }
+    new_tables[b'glyf']     = glyf
+    new_tables[b'loca']     = struct.pack('>%dI' % len(loca), *loca)
+    new_tables[b'hmtx']     = b''.join(struct.pack('>Hh', *hmetrics[old_id]) for old_id in keep)
+    new_tables[b'cmap']     = cmap({ code: new_ids[mapping[code]] for code in CHARACTERS if code in mapping })
+    new_tables[b'head']     = tables[b'head'][:8] + struct.pack('>I', 0) + tables[b'head'][12:50] + struct.pack('>h', 1) + tables[b'head'][52:]
+    new_tables[b'hhea']     = tables[b'hhea'][:34] + struct.pack('>H', len(keep))
+    new_tables[b'post']     = struct.pack('>I', 0x00030000) + tables[b'post'][4:32]
+
+    # The glyphs have no instructions any more
+    maxp                    = bytearray(tables[b'maxp'])
+    maxp[4:6]               = struct.pack('>H', len(keep))
+    maxp[26:28]             = struct.pack('>H', 0)
+    new_tables[b'maxp']     = bytes(maxp)
+
+    return font(new_tables)
+
+if len(sys.argv) != 2:
+    print('Usage: make_subset_font.py <path to DejaVuSans.ttf>')
+    sys.exit(1)
+
+with open(sys.argv[1], 'rb') as source:
+    subset_font = subset(source.read())
+
+with open('DejaVuSans-Hebrew.ttf', 'wb') as output:
+    output.write(subset_font)
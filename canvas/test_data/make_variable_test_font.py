@@ -0,0 +1,163 @@
+#!/usr/bin/env python3
+#
+# Generates VariableTest.ttf: a minimal variable font used to test how flo_canvas handles variation axes
+#
+# The font maps 'a' to glyph 1, which is a rectangle from (100, 0) to (900, 700) with an advance of 1000 units
+# (with 1000 units per em) at the default weight. It has a single 'wght' axis (100-400-900): at the maximum weight,
+# the gvar table moves the right-hand edge of the rectangle 500 units to the right and the HVAR table adds 500 units
+# to the advance (so the advance is 1250 units at a weight of 650).
+#
+# Run this script from the test_data directory to regenerate the font.
+#
+
+import struct
+
+def head():
+    return struct.pack('>HHiIIHHqqhhhhHHhhh',
+        1, 0,               # version
+        0x00010000,         # fontRevision
+        0,                  # checksumAdjustment
+        0x5F0F3CF5,         # magicNumber
+        0,                  # flags
+        1000,               # unitsPerEm
+        0, 0,               # created, modified
+        0, 0, 1400, 700,    # xMin, yMin, xMax, yMax
+        0, 8, 2,            # macStyle, lowestRecPPEM, fontDirectionHint
+        0, 0)               # indexToLocFormat, glyphDataFormat
+
+def hhea():
+    return struct.pack('>HHhhhHhhhhhhhhhhhH',
+        1, 0,               # version
+        800, -200, 0,       # ascender, descender, lineGap
+        1500, 0, 100, 1400, # advanceWidthMax, minLeftSideBearing, minRightSideBearing, xMaxExtent
+        1, 0, 0,            # caretSlopeRise, caretSlopeRun, caretOffset
+        0, 0, 0, 0,         # reserved
+        0,                  # metricDataFormat
+        2)                  # numberOfHMetrics
+
+def maxp():
+    return struct.pack('>IHHHHHHHHHHHHHH',
+        0x00010000, 2,      # version, numGlyphs
+        4, 1,               # maxPoints, maxContours
+        0, 0,               # maxCompositePoints, maxCompositeContours
+        2, 0, 0, 0, 0, 0,   # maxZones, maxTwilightPoints, maxStorage, maxFunctionDefs, maxInstructionDefs, maxStackElements
+        0, 0, 0)            # maxSizeOfInstructions, maxComponentElements, maxComponentDepth
+
+def hmtx():
+    return struct.pack('>HhHh', 500, 0, 1000, 100)
+
+def cmap():
+    # Format 4 subtable with a segment for 'a' and the final 0xffff segment
+    seg_count   = 2
+    subtable    = struct.pack('>HHHHHHH', 4, 16 + seg_count*8, 0, seg_count*2, 4, 1, 0)
+    subtable   += struct.pack('>HH', 0x61, 0xffff)          # endCode
+    subtable   += struct.pack('>H', 0)                      # reservedPad
+    subtable   += struct.pack('>HH', 0x61, 0xffff)          # startCode
+    subtable   += struct.pack('>hh', 1 - 0x61, 1)           # idDelta
+    subtable   += struct.pack('>HH', 0, 0)                  # idRangeOffset
+
+    return struct.pack('>HHHHI', 0, 1, 3, 1, 12) + subtable
+
+# The corners of the rectangle for glyph 1 (glyph 0 has no outline)
+RECTANGLE = [(100, 0), (100, 700), (900, 700), (900, 0)]
+
+def glyf():
+    glyph   = struct.pack('>hhhhh', 1, 100, 0, 900, 700)    # numberOfContours, xMin, yMin, xMax, yMax
+    glyph  += struct.pack('>HH', len(RECTANGLE)-1, 0)       # endPtsOfContours, instructionLength
+    glyph  += bytes([0x01] * len(RECTANGLE))                # flags: every point is on the curve and has 16-bit coordinates
+
+    # Coordinates are stored as differences from the previous point
+    for axis in range(2):
+        last = 0
+        for point in RECTANGLE:
+            glyph  += struct.pack('>h', point[axis] - last)
+            last    = point[axis]
+
+    return glyph + b'\0' * (-len(glyph) % 4)
+
+def loca():
+    # Glyph 0 is empty (the table is in the short format, storing offsets divided by two)
+    return struct.pack('>HHH', 0, 0, len(glyf()) // 2)
+
+def post():
+    return struct.pack('>IihhIIIII', 0x00030000, 0, -100, 50, 0, 0, 0, 0, 0)
+
+def name():
+    # The family name and the name of the weight axis, as Windows Unicode strings
+    names   = [(1, 'VariableTest'), (2, 'Regular'), (256, 'Weight')]
+    strings = b''
+    records = b''
+
+    for name_id, text in names:
+        encoded  = text.encode('utf-16-be')
+        records += struct.pack('>HHHHHH', 3, 1, 0x409, name_id, len(encoded), len(strings))
+        strings += encoded
+
+    return struct.pack('>HHH', 0, len(names), 6 + len(records)) + records + strings
+
+def gvar():
+    # One tuple for glyph 1, which peaks at the maximum weight and applies to all of its points (the four corners of the
+    # rectangle followed by the four phantom points): the right-hand corners and the advance phantom point move 500 units right
+    x_deltas    = [0, 0, 500, 500, 0, 500, 0, 0]
+    deltas      = bytes([0x00])                                                 # shared point numbers: all points
+    deltas     += bytes([0x40 | (len(x_deltas)-1)]) + struct.pack('>%dh' % len(x_deltas), *x_deltas)
+    deltas     += bytes([0x80 | (len(x_deltas)-1)])                             # the y deltas are all 0
+
+    tuple_data  = struct.pack('>HH', 0x8000 | 1, 4 + 6)                         # SHARED_POINT_NUMBERS, one tuple, offset to the data
+    tuple_data += struct.pack('>HHh', len(deltas) - 1, 0x8000, 0x4000)          # size of the deltas, EMBEDDED_PEAK_TUPLE, peak at 1.0
+    tuple_data += deltas
+    tuple_data += b'\0' * (len(tuple_data) % 2)
+
+    return struct.pack('>HHHHIHHI', 1, 0, 1, 0, 26, 2, 0, 26) + struct.pack('>HHH', 0, 0, len(tuple_data) // 2) + tuple_data
+
+def fvar():
+    header  = struct.pack('>HHHHHHHH', 1, 0, 16, 2, 1, 20, 0, 8)
+    axis    = struct.pack('>4siiiHH', b'wght', 100 << 16, 400 << 16, 900 << 16, 0, 256)
+
+    return header + axis
+
+def hvar():
+    # Header: the item variation store follows directly, and advances are mapped directly from glyph IDs
+    header          = struct.pack('>HHIIII', 1, 0, 20, 0, 0, 0)
+
+    # One region that peaks at the maximum value of the weight axis
+    region_list     = struct.pack('>HHhhh', 1, 1, 0, 0x4000, 0x4000)
+
+    # One 16-bit delta per glyph
+    variation_data  = struct.pack('>HHHH', 2, 1, 1, 0) + struct.pack('>hh', 0, 500)
+
+    store           = struct.pack('>HIHI', 1, 12, 1, 12 + len(region_list)) + region_list + variation_data
+
+    return header + store
+
+def font(tables):
+    # Table records are sorted by tag
+    tables      = sorted(tables.items())
+    num_tables  = len(tables)
+    offset      = 12 + num_tables*16
+    selector    = num_tables.bit_length() - 1
+    directory   = struct.pack('>IHHHH', 0x00010000, num_tables, (1 << selector)*16, selector, num_tables*16 - (1 << selector)*16)
+    data        = b''
+
+    for tag, table in tables:
+        checksum    = sum(struct.unpack('>%dI' % ((len(table)+3)//4), table + b'\0' * (-len(table) % 4))) & 0xffffffff
+        directory  += struct.pack('>4sIII', tag, checksum, offset + len(data), len(table))
+        data       += table + b'\0' * (-len(table) % 4)
+
+    return directory + data
+
+with open('VariableTest.ttf', 'wb') as output:
+    output.write(font({
+        b'head': head(),
+        b'hhea': hhea(),
+        b'maxp': maxp(),
+        b'hmtx': hmtx(),
+        b'cmap': cmap(),
+        b'loca': loca(),
+        b'glyf': glyf(),
+        b'post': post(),
+        b'name': name(),
+        b'gvar': gvar(),
+        b'fvar': fvar(),
+        b'HVAR': hvar(),
+    }))
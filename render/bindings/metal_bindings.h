@@ -26,5 +26,31 @@ typedef enum FragmentInputIndex {
     FragmentIndexClipMaskTexture    = 2,
 
     /// The alpha value to use for the fragment
-    FragmentAlpha                   = 3
+    FragmentAlpha                   = 3,
+
+    /// How a gradient is extended outside of the range 0-1 (one of the GradientSpreadValue values)
+    FragmentGradientSpread          = 4,
+
+    /// The focal point of a radial gradient, in texture coordinates
+    FragmentFocalPoint              = 5,
+
+    /// The copy of the render target that a blend shader reads from
+    FragmentIndexBackdropTexture    = 6,
+
+    /// The blend function to use in the blend shaders (see BlendMode::shader_blend_index())
+    FragmentBlendMode               = 7
 } FragmentInputIndex;
+
+///
+/// The ways a gradient can be extended outside of the range 0-1
+///
+typedef enum GradientSpreadValue {
+    /// The colours at the end of the gradient are extended indefinitely
+    GradientSpreadPad               = 0,
+
+    /// The gradient repeats
+    GradientSpreadRepeat            = 1,
+
+    /// The gradient is mirrored each time it repeats
+    GradientSpreadReflect           = 2
+} GradientSpreadValue;
@@ -70,7 +70,8 @@ fn compile_metal() {
     compile_metal_shader("shaders/simple/postprocessing.metal", "postprocessing.air");
     compile_metal_shader("shaders/texture/gradient_fragment.metal", "gradient_fragment.air");
     compile_metal_shader("shaders/texture/texture_fragment.metal", "texture_fragment.air");
-    link_metal_shaders(vec!["simple.air", "texture_fragment.air", "gradient_fragment.air", "clip_mask.air", "postprocessing.air"], "flo.metallib");
+    compile_metal_shader("shaders/texture/blend_fragment.metal", "blend_fragment.air");
+    link_metal_shaders(vec!["simple.air", "texture_fragment.air", "gradient_fragment.air", "blend_fragment.air", "clip_mask.air", "postprocessing.air"], "flo.metallib");
 
     // Generate .rs files from the binding headers
     println!("cargo:rerun-if-changed=bindings");
@@ -0,0 +1,15 @@
+@group(1)
+@binding(0)
+var backdrop_texture: texture_multisampled_2d<f32>;
+
+fn backdrop_color(position: vec4<f32>) -> vec4<f32> {
+    let backdrop_pos    = vec2<i32>(i32(position[0]), i32(position[1]));
+    let num_samples     = i32(textureNumSamples(backdrop_texture));
+
+    var sample_totals   = vec4<f32>();
+    for (var sample_num = i32(0); sample_num < num_samples; sample_num++) {
+        sample_totals += textureLoad(backdrop_texture, backdrop_pos, sample_num);
+    }
+
+    return sample_totals / f32(num_samples);
+}
@@ -0,0 +1,9 @@
+@group(1)
+@binding(0)
+var backdrop_texture: texture_2d<f32>;
+
+fn backdrop_color(position: vec4<f32>) -> vec4<f32> {
+    let backdrop_pos    = vec2<i32>(i32(position[0]), i32(position[1]));
+
+    return textureLoad(backdrop_texture, backdrop_pos, 0);
+}
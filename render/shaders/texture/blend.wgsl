@@ -0,0 +1,126 @@
+// Blend functions for the blend modes that need to read from the destination. These are supplied as the 'clip' function
+// for the texture shader: the backdrop_color function must also be defined, along with the blend_mode constant, which 
+// uses the same numbering as BlendMode::shader_blend_index()
+
+fn lum(c: vec3<f32>) -> f32 {
+    return dot(c, vec3<f32>(0.3, 0.59, 0.11));
+}
+
+fn clip_color(c: vec3<f32>) -> vec3<f32> {
+    let l           = lum(c);
+    let n           = min(c.r, min(c.g, c.b));
+    let x           = max(c.r, max(c.g, c.b));
+    var result      = c;
+
+    if (n < 0.0) {
+        result = l + (((result - l) * l) / (l - n));
+    }
+    if (x > 1.0) {
+        result = l + (((result - l) * (1.0 - l)) / (x - l));
+    }
+
+    return result;
+}
+
+fn set_lum(c: vec3<f32>, l: f32) -> vec3<f32> {
+    return clip_color(c + (l - lum(c)));
+}
+
+fn sat(c: vec3<f32>) -> f32 {
+    return max(c.r, max(c.g, c.b)) - min(c.r, min(c.g, c.b));
+}
+
+fn set_sat(c: vec3<f32>, s: f32) -> vec3<f32> {
+    let n           = min(c.r, min(c.g, c.b));
+    let x           = max(c.r, max(c.g, c.b));
+
+    if (x > n) {
+        return ((c - n) * s) / (x - n);
+    } else {
+        return vec3<f32>(0.0);
+    }
+}
+
+fn color_dodge(cb: f32, cs: f32) -> f32 {
+    if (cb <= 0.0) {
+        return 0.0;
+    } else if (cs >= 1.0) {
+        return 1.0;
+    } else {
+        return min(1.0, cb / (1.0 - cs));
+    }
+}
+
+fn color_burn(cb: f32, cs: f32) -> f32 {
+    if (cb >= 1.0) {
+        return 1.0;
+    } else if (cs <= 0.0) {
+        return 0.0;
+    } else {
+        return 1.0 - min(1.0, (1.0 - cb) / cs);
+    }
+}
+
+fn hard_light(cb: f32, cs: f32) -> f32 {
+    if (cs <= 0.5) {
+        return cb * 2.0 * cs;
+    } else {
+        let s = 2.0 * cs - 1.0;
+        return cb + s - (cb * s);
+    }
+}
+
+fn soft_light(cb: f32, cs: f32) -> f32 {
+    if (cs <= 0.5) {
+        return cb - (1.0 - 2.0 * cs) * cb * (1.0 - cb);
+    } else {
+        var d: f32;
+        if (cb <= 0.25) {
+            d = ((16.0 * cb - 12.0) * cb + 4.0) * cb;
+        } else {
+            d = sqrt(cb);
+        }
+
+        return cb + (2.0 * cs - 1.0) * (d - cb);
+    }
+}
+
+fn blend(cb: vec3<f32>, cs: vec3<f32>) -> vec3<f32> {
+    var result = cs;
+
+    switch (blend_mode) {
+        case 0u:    { result = min(cb, cs); }
+        case 1u:    { result = max(cb, cs); }
+        case 2u:    { result = vec3<f32>(hard_light(cs.r, cb.r), hard_light(cs.g, cb.g), hard_light(cs.b, cb.b)); }
+        case 3u:    { result = vec3<f32>(color_dodge(cb.r, cs.r), color_dodge(cb.g, cs.g), color_dodge(cb.b, cs.b)); }
+        case 4u:    { result = vec3<f32>(color_burn(cb.r, cs.r), color_burn(cb.g, cs.g), color_burn(cb.b, cs.b)); }
+        case 5u:    { result = vec3<f32>(hard_light(cb.r, cs.r), hard_light(cb.g, cs.g), hard_light(cb.b, cs.b)); }
+        case 6u:    { result = vec3<f32>(soft_light(cb.r, cs.r), soft_light(cb.g, cs.g), soft_light(cb.b, cs.b)); }
+        case 7u:    { result = abs(cb - cs); }
+        case 8u:    { result = cb + cs - 2.0 * cb * cs; }
+        case 9u:    { result = set_lum(set_sat(cs, sat(cb)), lum(cb)); }
+        case 10u:   { result = set_lum(set_sat(cb, sat(cs)), lum(cb)); }
+        case 11u:   { result = set_lum(cs, lum(cb)); }
+        case 12u:   { result = set_lum(cb, lum(cs)); }
+        default:    { }
+    }
+
+    return result;
+}
+
+fn clip(color: vec4<f32>, position: vec4<f32>) -> vec4<f32> {
+    // Both the source and the backdrop have pre-multiplied alpha
+    let src         = color;
+    let dst         = backdrop_color(position);
+
+    // Blend functions are defined in terms of the non-premultiplied colours
+    let cs          = select(vec3<f32>(0.0), src.rgb / src.a, src.a > 0.0);
+    let cb          = select(vec3<f32>(0.0), dst.rgb / dst.a, dst.a > 0.0);
+
+    // Composite using source-over with the blended colour where the two layers overlap
+    let blended     = clamp(blend(cb, cs), vec3<f32>(0.0), vec3<f32>(1.0));
+    let result_rgb  = src.rgb * (1.0 - dst.a) + dst.rgb * (1.0 - src.a) + (src.a * dst.a) * blended;
+    let result_a    = src.a + dst.a * (1.0 - src.a);
+
+    return vec4<f32>(result_rgb, result_a);
+}
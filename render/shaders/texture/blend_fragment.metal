@@ -0,0 +1,185 @@
+#include <metal_stdlib>
+
+#import "./bindings/metal_vertex2d.h"
+#import "../simple/rasterizer.metal"
+
+// Blend functions for the blend modes that need to read from the destination. The blend mode uses the same numbering as
+// BlendMode::shader_blend_index(), and the backdrop is a copy of the render target made before the frame buffer is drawn
+
+float lum(float3 c) {
+    return metal::dot(c, float3(0.3, 0.59, 0.11));
+}
+
+float3 clip_color(float3 c) {
+    const float l   = lum(c);
+    const float n   = metal::min(c.r, metal::min(c.g, c.b));
+    const float x   = metal::max(c.r, metal::max(c.g, c.b));
+    float3 result   = c;
+
+    if (n < 0.0) {
+        result = l + (((result - l) * l) / (l - n));
+    }
+    if (x > 1.0) {
+        result = l + (((result - l) * (1.0 - l)) / (x - l));
+    }
+
+    return result;
+}
+
+float3 set_lum(float3 c, float l) {
+    return clip_color(c + (l - lum(c)));
+}
+
+float sat(float3 c) {
+    return metal::max(c.r, metal::max(c.g, c.b)) - metal::min(c.r, metal::min(c.g, c.b));
+}
+
+float3 set_sat(float3 c, float s) {
+    const float n   = metal::min(c.r, metal::min(c.g, c.b));
+    const float x   = metal::max(c.r, metal::max(c.g, c.b));
+
+    if (x > n) {
+        return ((c - n) * s) / (x - n);
+    } else {
+        return float3(0.0);
+    }
+}
+
+float color_dodge(float cb, float cs) {
+    if (cb <= 0.0) {
+        return 0.0;
+    } else if (cs >= 1.0) {
+        return 1.0;
+    } else {
+        return metal::min(1.0, cb / (1.0 - cs));
+    }
+}
+
+float color_burn(float cb, float cs) {
+    if (cb >= 1.0) {
+        return 1.0;
+    } else if (cs <= 0.0) {
+        return 0.0;
+    } else {
+        return 1.0 - metal::min(1.0, (1.0 - cb) / cs);
+    }
+}
+
+float hard_light(float cb, float cs) {
+    if (cs <= 0.5) {
+        return cb * 2.0 * cs;
+    } else {
+        const float s = 2.0 * cs - 1.0;
+        return cb + s - (cb * s);
+    }
+}
+
+float soft_light(float cb, float cs) {
+    if (cs <= 0.5) {
+        return cb - (1.0 - 2.0 * cs) * cb * (1.0 - cb);
+    } else {
+        float d;
+        if (cb <= 0.25) {
+            d = ((16.0 * cb - 12.0) * cb + 4.0) * cb;
+        } else {
+            d = metal::sqrt(cb);
+        }
+
+        return cb + (2.0 * cs - 1.0) * (d - cb);
+    }
+}
+
+float3 blend(float3 cb, float3 cs, int blend_mode) {
+    switch (blend_mode) {
+        case 0:     return metal::min(cb, cs);
+        case 1:     return metal::max(cb, cs);
+        case 2:     return float3(hard_light(cs.r, cb.r), hard_light(cs.g, cb.g), hard_light(cs.b, cb.b));
+        case 3:     return float3(color_dodge(cb.r, cs.r), color_dodge(cb.g, cs.g), color_dodge(cb.b, cs.b));
+        case 4:     return float3(color_burn(cb.r, cs.r), color_burn(cb.g, cs.g), color_burn(cb.b, cs.b));
+        case 5:     return float3(hard_light(cb.r, cs.r), hard_light(cb.g, cs.g), hard_light(cb.b, cs.b));
+        case 6:     return float3(soft_light(cb.r, cs.r), soft_light(cb.g, cs.g), soft_light(cb.b, cs.b));
+        case 7:     return metal::abs(cb - cs);
+        case 8:     return cb + cs - 2.0 * cb * cs;
+        case 9:     return set_lum(set_sat(cs, sat(cb)), lum(cb));
+        case 10:    return set_lum(set_sat(cb, sat(cs)), lum(cb));
+        case 11:    return set_lum(cs, lum(cb));
+        case 12:    return set_lum(cb, lum(cs));
+        default:    return cs;
+    }
+}
+
+float4 blend_with_backdrop(float4 src, float4 dst, int blend_mode) {
+    // Both the source and the backdrop have pre-multiplied alpha, but the blend functions are defined in terms of the non-premultiplied colours
+    const float3 cs         = src.a > 0.0 ? src.rgb / src.a : float3(0.0);
+    const float3 cb         = dst.a > 0.0 ? dst.rgb / dst.a : float3(0.0);
+
+    // Composite using source-over with the blended colour where the two layers overlap
+    const float3 blended    = metal::clamp(blend(cb, cs, blend_mode), float3(0.0), float3(1.0));
+    const float3 result_rgb = src.rgb * (1.0 - dst.a) + dst.rgb * (1.0 - src.a) + (src.a * dst.a) * blended;
+    const float result_a    = src.a + dst.a * (1.0 - src.a);
+
+    return float4(result_rgb, result_a);
+}
+
+float4 read_pixel(metal::texture2d<half> texture, uint2 pos) {
+    return float4(texture.read(pos));
+}
+
+float4 read_pixel(metal::texture2d_ms<half> texture, uint2 pos) {
+    const uint num_samples      = texture.get_num_samples();
+    half4 color_totals          = half4(0,0,0,0);
+
+    for (uint sample_num=0; sample_num<num_samples; ++sample_num) {
+        color_totals            += texture.read(pos, sample_num);
+    }
+
+    return float4(color_totals) / float(num_samples);
+}
+
+fragment float4 blend_fragment(
+      RasterizerData              in [[stage_in]],
+      constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *blend_mode [[ buffer(FragmentBlendMode) ]],
+      metal::texture2d<half>      texture [[ texture(FragmentIndexTexture) ]],
+      metal::texture2d<half>      backdrop_texture [[ texture(FragmentIndexBackdropTexture) ]]) {
+    const float4 src    = read_pixel(texture, uint2(in.v_TexCoord)) * *texture_alpha;
+    const float4 dst    = read_pixel(backdrop_texture, uint2(in.v_Position.xy));
+
+    return blend_with_backdrop(src, dst, *blend_mode);
+}
+
+fragment float4 blend_multisample_fragment(
+      RasterizerData              in [[stage_in]],
+      constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *blend_mode [[ buffer(FragmentBlendMode) ]],
+      metal::texture2d_ms<half>   texture [[ texture(FragmentIndexTexture) ]],
+      metal::texture2d<half>      backdrop_texture [[ texture(FragmentIndexBackdropTexture) ]]) {
+    const float4 src    = read_pixel(texture, uint2(in.v_TexCoord)) * *texture_alpha;
+    const float4 dst    = read_pixel(backdrop_texture, uint2(in.v_Position.xy));
+
+    return blend_with_backdrop(src, dst, *blend_mode);
+}
+
+fragment float4 blend_multisample_backdrop_fragment(
+      RasterizerData              in [[stage_in]],
+      constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *blend_mode [[ buffer(FragmentBlendMode) ]],
+      metal::texture2d<half>      texture [[ texture(FragmentIndexTexture) ]],
+      metal::texture2d_ms<half>   backdrop_texture [[ texture(FragmentIndexBackdropTexture) ]]) {
+    const float4 src    = read_pixel(texture, uint2(in.v_TexCoord)) * *texture_alpha;
+    const float4 dst    = read_pixel(backdrop_texture, uint2(in.v_Position.xy));
+
+    return blend_with_backdrop(src, dst, *blend_mode);
+}
+
+fragment float4 blend_multisample_multisample_backdrop_fragment(
+      RasterizerData              in [[stage_in]],
+      constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *blend_mode [[ buffer(FragmentBlendMode) ]],
+      metal::texture2d_ms<half>   texture [[ texture(FragmentIndexTexture) ]],
+      metal::texture2d_ms<half>   backdrop_texture [[ texture(FragmentIndexBackdropTexture) ]]) {
+    const float4 src    = read_pixel(texture, uint2(in.v_TexCoord)) * *texture_alpha;
+    const float4 dst    = read_pixel(backdrop_texture, uint2(in.v_Position.xy));
+
+    return blend_with_backdrop(src, dst, *blend_mode);
+}
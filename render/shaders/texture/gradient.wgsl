@@ -6,7 +6,9 @@ struct RasterData {
 
 struct TextureSettings {
     @location(0)    transform:  mat4x4<f32>,
-    @location(1)    alpha:      f32
+    @location(1)    alpha:      f32,
+    @location(2)    focal_x:    f32,
+    @location(3)    focal_y:    f32
 }
 
 @group(0)
@@ -50,7 +52,10 @@ fn gradient_vertex_shader(
 
 @fragment
 fn gradient_fragment_shader(vertex: RasterData) -> @location(0) vec4<f32> {
-    var color = textureSample(f_texture, f_sampler, vertex.tex_coord[0]);
+    let focal_point = vec2<f32>(texture_settings.focal_x, texture_settings.focal_y);
+    let gradient_t  = gradient_position(vertex.tex_coord, focal_point);
+
+    var color = textureSample(f_texture, f_sampler, gradient_t);
     color = alpha_blend(color, texture_settings.alpha);
 
     color = clip(color, vertex.pos);
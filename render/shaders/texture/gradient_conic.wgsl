@@ -0,0 +1,11 @@
+fn gradient_position(tex_coord: vec2<f32>, focal_point: vec2<f32>) -> f32 {
+    // The texture coordinates are transformed so the gradient is centered on the origin and starts along the x axis
+    // The gradient position is the angle around the origin, as a proportion of a full turn
+    let angle = atan2(tex_coord[1], tex_coord[0]) / 6.283185307179586;
+
+    if (angle < 0.0) {
+        return angle + 1.0;
+    }
+
+    return angle;
+}
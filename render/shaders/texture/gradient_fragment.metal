@@ -5,7 +5,7 @@
 
 typedef struct {
     float4 v_Position [[position]];
-    float v_TexCoord;
+    float2 v_TexCoord;
     float2 v_PaperCoord;
 } GradientData;
 
@@ -21,19 +21,67 @@ vertex GradientData gradient_vertex(
     GradientData data;
 
     data.v_Position     = position;
-    data.v_TexCoord     = tex_coord[0];
+    data.v_TexCoord     = float2(tex_coord[0], tex_coord[1]);
     data.v_PaperCoord   = paper_coord;
 
     return data;
 }
 
+///
+/// Maps a position along a gradient to the texture coordinate to sample, according to how the gradient is spread
+///
+float spread_position(float position, int spread) {
+    switch (spread) {
+        case GradientSpreadRepeat:  return metal::fract(position);
+        case GradientSpreadReflect: return 1.0 - metal::abs(metal::fract(position * 0.5) * 2.0 - 1.0);
+        default:                    return metal::clamp(position, 0.0, 1.0);
+    }
+}
+
+///
+/// Returns the position along a radial gradient for a texture coordinate
+///
+/// The texture coordinates are transformed so the circle is centered on the origin with a radius of 1. The position is how far along
+/// the ray from the focal point to the edge of the circle the texture coordinate is.
+///
+float radial_gradient_position(float2 tex_coord, float2 focal_point) {
+    const float2 ray          = tex_coord - focal_point;
+    const float a             = metal::dot(ray, ray);
+    const float b             = metal::dot(focal_point, ray);
+    const float c             = metal::dot(focal_point, focal_point) - 1.0;
+    const float denominator   = metal::sqrt(metal::max(b*b - a*c, 0.0)) - b;
+
+    if (denominator <= 0.0) {
+        return 0.0;
+    }
+
+    return a / denominator;
+}
+
+///
+/// Returns the position along a conic gradient for a texture coordinate
+///
+/// The texture coordinates are transformed so the gradient is centered on the origin and starts along the x axis. The position is the
+/// angle around the origin, as a proportion of a full turn.
+///
+float conic_gradient_position(float2 tex_coord) {
+    const float angle = metal::atan2(tex_coord[1], tex_coord[0]) / 6.283185307179586;
+
+    if (angle < 0.0) {
+        return angle + 1.0;
+    }
+
+    return angle;
+}
+
 fragment float4 gradient_fragment(
       GradientData                in [[stage_in]],
       constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *gradient_spread [[ buffer(FragmentGradientSpread) ]],
       metal::texture1d<half>      texture [[ texture(FragmentIndexTexture) ]]) {
     constexpr metal::sampler texture_sampler (metal::mag_filter::linear, metal::min_filter::linear);
 
-    const half4 color_sample = texture.sample(texture_sampler, in.v_TexCoord);
+    const half4 color_sample = texture.sample(texture_sampler, spread_position(in.v_TexCoord[0], *gradient_spread));
 
     float4 color  = float4(color_sample);
     color[3]      *= *texture_alpha;
@@ -44,11 +92,12 @@ fragment float4 gradient_fragment(
 fragment float4 gradient_clip_mask_multisample_fragment(
       GradientData                in [[stage_in]],
       constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *gradient_spread [[ buffer(FragmentGradientSpread) ]],
       metal::texture1d<half>      texture [[ texture(FragmentIndexTexture) ]],
       metal::texture2d_ms<half>   clip_mask_texture [[ texture(FragmentIndexClipMaskTexture) ]]) {
     // Color from the gradient
     constexpr metal::sampler texture_sampler (metal::mag_filter::linear, metal::min_filter::linear);
-    const half4 color_sample    = texture.sample(texture_sampler, in.v_TexCoord);
+    const half4 color_sample    = texture.sample(texture_sampler, spread_position(in.v_TexCoord[0], *gradient_spread));
 
     // Apply the clip mask
     float4 color  = apply_clip_mask(static_cast<float4>(color_sample), in.v_PaperCoord, clip_mask_texture);
@@ -60,10 +109,11 @@ fragment float4 gradient_clip_mask_multisample_fragment(
 fragment float4 gradient_fragment_invert_color_alpha(
       GradientData                in [[stage_in]],
       constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *gradient_spread [[ buffer(FragmentGradientSpread) ]],
       metal::texture1d<half>      texture [[ texture(FragmentIndexTexture) ]]) {
     constexpr metal::sampler texture_sampler (metal::mag_filter::linear, metal::min_filter::linear);
 
-    const half4 color_sample = texture.sample(texture_sampler, in.v_TexCoord);
+    const half4 color_sample = texture.sample(texture_sampler, spread_position(in.v_TexCoord[0], *gradient_spread));
 
     float4 color  = float4(color_sample);
     color[3]      *= *texture_alpha;
@@ -74,11 +124,144 @@ fragment float4 gradient_fragment_invert_color_alpha(
 fragment float4 gradient_clip_mask_multisample_fragment_invert_color_alpha(
       GradientData                in [[stage_in]],
       constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *gradient_spread [[ buffer(FragmentGradientSpread) ]],
+      metal::texture1d<half>      texture [[ texture(FragmentIndexTexture) ]],
+      metal::texture2d_ms<half>   clip_mask_texture [[ texture(FragmentIndexClipMaskTexture) ]]) {
+    // Color from the gradient
+    constexpr metal::sampler texture_sampler (metal::mag_filter::linear, metal::min_filter::linear);
+    const half4 color_sample    = texture.sample(texture_sampler, spread_position(in.v_TexCoord[0], *gradient_spread));
+
+    // Apply the clip mask
+    float4 color  = apply_clip_mask(static_cast<float4>(color_sample), in.v_PaperCoord, clip_mask_texture);
+    color[3]      *= *texture_alpha;
+
+    return invert_color_alpha(color);
+}
+
+fragment float4 radial_gradient_fragment(
+      GradientData                in [[stage_in]],
+      constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *gradient_spread [[ buffer(FragmentGradientSpread) ]],
+      constant float2             *focal_point [[ buffer(FragmentFocalPoint) ]],
+      metal::texture1d<half>      texture [[ texture(FragmentIndexTexture) ]]) {
+    constexpr metal::sampler texture_sampler (metal::mag_filter::linear, metal::min_filter::linear);
+
+    const half4 color_sample = texture.sample(texture_sampler, spread_position(radial_gradient_position(in.v_TexCoord, *focal_point), *gradient_spread));
+
+    float4 color  = float4(color_sample);
+    color[3]      *= *texture_alpha;
+
+    return color;
+}
+
+fragment float4 radial_gradient_clip_mask_multisample_fragment(
+      GradientData                in [[stage_in]],
+      constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *gradient_spread [[ buffer(FragmentGradientSpread) ]],
+      constant float2             *focal_point [[ buffer(FragmentFocalPoint) ]],
+      metal::texture1d<half>      texture [[ texture(FragmentIndexTexture) ]],
+      metal::texture2d_ms<half>   clip_mask_texture [[ texture(FragmentIndexClipMaskTexture) ]]) {
+    // Color from the gradient
+    constexpr metal::sampler texture_sampler (metal::mag_filter::linear, metal::min_filter::linear);
+    const half4 color_sample    = texture.sample(texture_sampler, spread_position(radial_gradient_position(in.v_TexCoord, *focal_point), *gradient_spread));
+
+    // Apply the clip mask
+    float4 color  = apply_clip_mask(static_cast<float4>(color_sample), in.v_PaperCoord, clip_mask_texture);
+    color[3]      *= *texture_alpha;
+
+    return color;
+}
+
+fragment float4 radial_gradient_fragment_invert_color_alpha(
+      GradientData                in [[stage_in]],
+      constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *gradient_spread [[ buffer(FragmentGradientSpread) ]],
+      constant float2             *focal_point [[ buffer(FragmentFocalPoint) ]],
+      metal::texture1d<half>      texture [[ texture(FragmentIndexTexture) ]]) {
+    constexpr metal::sampler texture_sampler (metal::mag_filter::linear, metal::min_filter::linear);
+
+    const half4 color_sample = texture.sample(texture_sampler, spread_position(radial_gradient_position(in.v_TexCoord, *focal_point), *gradient_spread));
+
+    float4 color  = float4(color_sample);
+    color[3]      *= *texture_alpha;
+
+    return invert_color_alpha(color);
+}
+
+fragment float4 radial_gradient_clip_mask_multisample_fragment_invert_color_alpha(
+      GradientData                in [[stage_in]],
+      constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *gradient_spread [[ buffer(FragmentGradientSpread) ]],
+      constant float2             *focal_point [[ buffer(FragmentFocalPoint) ]],
+      metal::texture1d<half>      texture [[ texture(FragmentIndexTexture) ]],
+      metal::texture2d_ms<half>   clip_mask_texture [[ texture(FragmentIndexClipMaskTexture) ]]) {
+    // Color from the gradient
+    constexpr metal::sampler texture_sampler (metal::mag_filter::linear, metal::min_filter::linear);
+    const half4 color_sample    = texture.sample(texture_sampler, spread_position(radial_gradient_position(in.v_TexCoord, *focal_point), *gradient_spread));
+
+    // Apply the clip mask
+    float4 color  = apply_clip_mask(static_cast<float4>(color_sample), in.v_PaperCoord, clip_mask_texture);
+    color[3]      *= *texture_alpha;
+
+    return invert_color_alpha(color);
+}
+
+fragment float4 conic_gradient_fragment(
+      GradientData                in [[stage_in]],
+      constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *gradient_spread [[ buffer(FragmentGradientSpread) ]],
+      metal::texture1d<half>      texture [[ texture(FragmentIndexTexture) ]]) {
+    constexpr metal::sampler texture_sampler (metal::mag_filter::linear, metal::min_filter::linear);
+
+    const half4 color_sample = texture.sample(texture_sampler, spread_position(conic_gradient_position(in.v_TexCoord), *gradient_spread));
+
+    float4 color  = float4(color_sample);
+    color[3]      *= *texture_alpha;
+
+    return color;
+}
+
+fragment float4 conic_gradient_clip_mask_multisample_fragment(
+      GradientData                in [[stage_in]],
+      constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *gradient_spread [[ buffer(FragmentGradientSpread) ]],
+      metal::texture1d<half>      texture [[ texture(FragmentIndexTexture) ]],
+      metal::texture2d_ms<half>   clip_mask_texture [[ texture(FragmentIndexClipMaskTexture) ]]) {
+    // Color from the gradient
+    constexpr metal::sampler texture_sampler (metal::mag_filter::linear, metal::min_filter::linear);
+    const half4 color_sample    = texture.sample(texture_sampler, spread_position(conic_gradient_position(in.v_TexCoord), *gradient_spread));
+
+    // Apply the clip mask
+    float4 color  = apply_clip_mask(static_cast<float4>(color_sample), in.v_PaperCoord, clip_mask_texture);
+    color[3]      *= *texture_alpha;
+
+    return color;
+}
+
+fragment float4 conic_gradient_fragment_invert_color_alpha(
+      GradientData                in [[stage_in]],
+      constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *gradient_spread [[ buffer(FragmentGradientSpread) ]],
+      metal::texture1d<half>      texture [[ texture(FragmentIndexTexture) ]]) {
+    constexpr metal::sampler texture_sampler (metal::mag_filter::linear, metal::min_filter::linear);
+
+    const half4 color_sample = texture.sample(texture_sampler, spread_position(conic_gradient_position(in.v_TexCoord), *gradient_spread));
+
+    float4 color  = float4(color_sample);
+    color[3]      *= *texture_alpha;
+
+    return invert_color_alpha(color);
+}
+
+fragment float4 conic_gradient_clip_mask_multisample_fragment_invert_color_alpha(
+      GradientData                in [[stage_in]],
+      constant float              *texture_alpha [[ buffer(FragmentAlpha) ]],
+      constant int                *gradient_spread [[ buffer(FragmentGradientSpread) ]],
       metal::texture1d<half>      texture [[ texture(FragmentIndexTexture) ]],
       metal::texture2d_ms<half>   clip_mask_texture [[ texture(FragmentIndexClipMaskTexture) ]]) {
     // Color from the gradient
     constexpr metal::sampler texture_sampler (metal::mag_filter::linear, metal::min_filter::linear);
-    const half4 color_sample    = texture.sample(texture_sampler, in.v_TexCoord);
+    const half4 color_sample    = texture.sample(texture_sampler, spread_position(conic_gradient_position(in.v_TexCoord), *gradient_spread));
 
     // Apply the clip mask
     float4 color  = apply_clip_mask(static_cast<float4>(color_sample), in.v_PaperCoord, clip_mask_texture);
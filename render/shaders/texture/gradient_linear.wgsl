@@ -0,0 +1,3 @@
+fn gradient_position(tex_coord: vec2<f32>, focal_point: vec2<f32>) -> f32 {
+    return tex_coord[0];
+}
@@ -0,0 +1,15 @@
+fn gradient_position(tex_coord: vec2<f32>, focal_point: vec2<f32>) -> f32 {
+    // The texture coordinates are transformed so the circle is centered on the origin with a radius of 1
+    // The gradient position is how far along the ray from the focal point to the edge of the circle this point is
+    let ray         = tex_coord - focal_point;
+    let a           = dot(ray, ray);
+    let b           = dot(focal_point, ray);
+    let c           = dot(focal_point, focal_point) - 1.0;
+    let denominator = sqrt(max(b*b - a*c, 0.0)) - b;
+
+    if (denominator <= 0.0) {
+        return 0.0;
+    }
+
+    return a / denominator;
+}
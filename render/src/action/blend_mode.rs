@@ -1,6 +1,10 @@
 ///
 /// The blending modes that the renderer must support (most of the Porter-Duff modes)
 ///
+/// The modes after `AllChannelAlphaDestinationOver` can't be expressed using fixed-function blending: they're implemented
+/// by a shader that reads from a copy of the destination. These are only applied by the `DrawFrameBuffer` action: other
+/// drawing actions will use `SourceOver` when one of these modes is selected.
+///
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum BlendMode {
     SourceOver,
@@ -16,5 +20,67 @@ pub enum BlendMode {
     Multiply,
 
     AllChannelAlphaSourceOver,
-    AllChannelAlphaDestinationOver
+    AllChannelAlphaDestinationOver,
+
+    Darken,
+    Lighten,
+    Overlay,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+impl BlendMode {
+    ///
+    /// True if this blend mode needs to read from the destination in a shader (ie, can only be used with `DrawFrameBuffer`)
+    ///
+    pub fn is_shader_blend(&self) -> bool {
+        self.shader_blend_index().is_some()
+    }
+
+    ///
+    /// For blend modes that are implemented in a shader, the index of the blend function used in that shader
+    ///
+    /// These indexes match up with the cases in the `blend` function defined in the blend shaders
+    ///
+    pub fn shader_blend_index(&self) -> Option<u32> {
+        use self::BlendMode::*;
+
+        match self {
+            SourceOver                      |
+            DestinationOver                 |
+            SourceIn                        |
+            DestinationIn                   |
+            SourceOut                       |
+            DestinationOut                  |
+            SourceATop                      |
+            DestinationATop                 |
+            Screen                          |
+            Multiply                        |
+            AllChannelAlphaSourceOver       |
+            AllChannelAlphaDestinationOver  => None,
+
+            Darken                          => Some(0),
+            Lighten                         => Some(1),
+            Overlay                         => Some(2),
+            ColorDodge                      => Some(3),
+            ColorBurn                       => Some(4),
+            HardLight                       => Some(5),
+            SoftLight                       => Some(6),
+            Difference                      => Some(7),
+            Exclusion                       => Some(8),
+            Hue                             => Some(9),
+            Saturation                      => Some(10),
+            Color                           => Some(11),
+            Luminosity                      => Some(12),
+        }
+    }
 }
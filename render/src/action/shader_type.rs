@@ -2,6 +2,21 @@ use super::identities::*;
 
 use crate::buffer::*;
 
+///
+/// How a gradient texture is sampled outside of the range 0-1
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GradientSpread {
+    /// The colours at the end of the gradient are extended indefinitely
+    Pad,
+
+    /// The gradient repeats
+    Repeat,
+
+    /// The gradient is mirrored each time it repeats
+    Reflect
+}
+
 ///
 /// The shaders that can be chosen for the renderer
 ///
@@ -18,7 +33,16 @@ pub enum ShaderType {
     Texture { texture: TextureId, texture_transform: Matrix, repeat: bool, alpha: f32, clip_texture: Option<TextureId> },
 
     /// Colour derived from a 1D texture using a transform mapping (used for rendering linear gradients)
-    LinearGradient { texture: TextureId, texture_transform: Matrix, repeat: bool, alpha: f32, clip_texture: Option<TextureId> }
+    LinearGradient { texture: TextureId, texture_transform: Matrix, spread: GradientSpread, alpha: f32, clip_texture: Option<TextureId> },
+
+    /// Colour derived from a 1D texture using the distance from a focal point to the edge of a circle. The texture transform maps
+    /// canvas coordinates to a space where the circle is centered on the origin with a radius of 1, and the focal point is in the
+    /// same coordinate space
+    RadialGradient { texture: TextureId, texture_transform: Matrix, focal_point: (f32, f32), spread: GradientSpread, alpha: f32, clip_texture: Option<TextureId> },
+
+    /// Colour derived from a 1D texture using the angle around a point. The texture transform maps canvas coordinates to a space
+    /// where the center of the gradient is at the origin and the gradient starts along the x axis
+    ConicGradient { texture: TextureId, texture_transform: Matrix, spread: GradientSpread, alpha: f32, clip_texture: Option<TextureId> }
 }
 
 impl ShaderType {
@@ -29,10 +53,12 @@ impl ShaderType {
         use self::ShaderType::*;
 
         match self {
-            Simple { clip_texture: _ }                                                      => Simple           { clip_texture: new_clip_mask_texture },
-            DashedLine { dash_texture, clip_texture: _ }                                    => DashedLine       { dash_texture: dash_texture, clip_texture: new_clip_mask_texture },
-            Texture { texture, texture_transform, repeat, alpha, clip_texture: _ }          => Texture          { texture: texture, texture_transform: texture_transform, repeat, alpha, clip_texture: new_clip_mask_texture },
-            LinearGradient { texture, texture_transform, repeat, alpha, clip_texture: _ }   => LinearGradient   { texture: texture, texture_transform: texture_transform, repeat, alpha, clip_texture: new_clip_mask_texture }
+            Simple { clip_texture: _ }                                                                 => Simple           { clip_texture: new_clip_mask_texture },
+            DashedLine { dash_texture, clip_texture: _ }                                               => DashedLine       { dash_texture: dash_texture, clip_texture: new_clip_mask_texture },
+            Texture { texture, texture_transform, repeat, alpha, clip_texture: _ }                     => Texture          { texture: texture, texture_transform: texture_transform, repeat, alpha, clip_texture: new_clip_mask_texture },
+            LinearGradient { texture, texture_transform, spread, alpha, clip_texture: _ }              => LinearGradient   { texture: texture, texture_transform: texture_transform, spread, alpha, clip_texture: new_clip_mask_texture },
+            RadialGradient { texture, texture_transform, focal_point, spread, alpha, clip_texture: _ } => RadialGradient   { texture: texture, texture_transform: texture_transform, focal_point, spread, alpha, clip_texture: new_clip_mask_texture },
+            ConicGradient { texture, texture_transform, spread, alpha, clip_texture: _ }               => ConicGradient    { texture: texture, texture_transform: texture_transform, spread, alpha, clip_texture: new_clip_mask_texture }
         }
     }
 }
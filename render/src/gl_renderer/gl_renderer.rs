@@ -51,6 +51,9 @@ pub struct GlRenderer {
     /// The render targets assigned to this renderer
     render_targets: Vec<Option<RenderTarget>>,
 
+    /// Copy of the pixels underneath a frame buffer that's being drawn with a shader blend mode (kept so it can be reused between draws)
+    blend_backdrop: Option<RenderTarget>,
+
     /// The shader programs
     shader_programs: ShaderCollection<StandardShaderProgram, ShaderUniform>,
 
@@ -76,6 +79,7 @@ impl GlRenderer {
             source_is_premultiplied:        false,
             transform_matrix:               None,
             render_targets:                 vec![],
+            blend_backdrop:                 None,
             shader_programs:                shader_programs,
 
             #[cfg(feature="profile")]
@@ -333,6 +337,9 @@ impl GlRenderer {
 
                     AllChannelAlphaSourceOver       => gl::BlendFuncSeparate(gl::ONE, gl::ONE_MINUS_SRC_COLOR, gl::ONE, gl::ONE_MINUS_SRC_ALPHA),
                     AllChannelAlphaDestinationOver  => gl::BlendFuncSeparate(gl::ONE_MINUS_DST_COLOR, gl::ONE, gl::ONE_MINUS_DST_ALPHA, gl::ONE),
+
+                    // Shader blend modes are only applied by draw_frame_buffer: other operations use source over
+                    _                               => gl::BlendFuncSeparate(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA, gl::ONE, gl::ONE_MINUS_SRC_ALPHA),
                 }
             } else {
                 // Source is already pre-multiplied
@@ -356,6 +363,9 @@ impl GlRenderer {
 
                     AllChannelAlphaSourceOver       => gl::BlendFuncSeparate(gl::ONE, gl::ONE_MINUS_SRC_COLOR, gl::ONE, gl::ONE_MINUS_SRC_ALPHA),
                     AllChannelAlphaDestinationOver  => gl::BlendFuncSeparate(gl::ONE_MINUS_DST_COLOR, gl::ONE, gl::ONE_MINUS_DST_ALPHA, gl::ONE),
+
+                    // Shader blend modes are only applied by draw_frame_buffer: other operations use source over
+                    _                               => gl::BlendFuncSeparate(gl::ONE, gl::ONE_MINUS_SRC_ALPHA, gl::ONE, gl::ONE_MINUS_SRC_ALPHA),
                 }
             }
 
@@ -846,6 +856,11 @@ impl GlRenderer {
     /// Draws a frame buffer at a location
     ///
     fn draw_frame_buffer(&mut self, RenderTargetId(source_buffer): RenderTargetId, region: FrameBufferRegion, alpha: f64) {
+        // Blend modes that need to read from the destination are drawn using a separate shader (falling back to source over if the shader can't be used)
+        if self.blend_mode.is_shader_blend() && self.draw_frame_buffer_with_shader_blend(RenderTargetId(source_buffer), region, alpha) {
+            return;
+        }
+
         let post_process        = self.post_processing_for_blend_mode(self.blend_mode, true);
         let was_premultiplied   = self.source_is_premultiplied;
 
@@ -873,35 +888,8 @@ impl GlRenderer {
                             gl::Uniform1i(source_texture, 0);
                         });
 
-                    // Create the vertices for the two triangles making up the screen
-                    let min_x               = region.min_x();
-                    let min_y               = region.min_y();
-                    let max_x               = region.max_x();
-                    let max_y               = region.max_y();
-
-                    let vertices            = vec![
-                        Vertex2D::with_pos(min_x, min_y), Vertex2D::with_pos(max_x, min_y), Vertex2D::with_pos(min_x, max_y),
-                        Vertex2D::with_pos(max_x, min_y), Vertex2D::with_pos(min_x, max_y), Vertex2D::with_pos(max_x, max_y),
-                    ];
-                    let mut buffer          = Buffer::new();
-                    let vertex_array        = VertexArray::new();
-                    buffer.static_draw(&vertices);
-
-                    // Bind a vertex array object to it
-                    gl::BindVertexArray(*vertex_array);
-                    gl::BindBuffer(gl::ARRAY_BUFFER, *buffer);
-
-                    Vertex2D::define_attributes();
-
-                    // Clear the bindings
-                    gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-                    gl::BindVertexArray(0);
-
-                    // Render a quad filling the screen
-                    gl::BindVertexArray(*vertex_array);
-                    gl::DrawArrays(gl::TRIANGLES, 0, 6);
-
-                    gl::BindVertexArray(0);
+                    // Render a quad filling the region
+                    Self::draw_frame_buffer_quad(&region);
                 } else {
                     // Blit the framebuffer if we're using a renderbuffer directly instead of a backing texture (won't blend or obey the alpha value)
                     let (x, y)          = (0, 0);
@@ -923,6 +911,138 @@ impl GlRenderer {
         panic_on_gl_error("Draw frame buffer");
     }
 
+    ///
+    /// Draws a frame buffer using one of the blend modes that are implemented in a shader
+    ///
+    /// These blend modes need to read the pixels they're drawing over, so the current render target is copied to a backdrop texture first.
+    /// The shader then writes the blended result directly to the render target.
+    ///
+    /// Returns false if the frame buffer could not be drawn this way (eg, because the source is a renderbuffer with no texture), in
+    /// which case nothing is drawn.
+    ///
+    fn draw_frame_buffer_with_shader_blend(&mut self, RenderTargetId(source_buffer): RenderTargetId, region: FrameBufferRegion, alpha: f64) -> bool {
+        let blend_index = if let Some(blend_index) = self.blend_mode.shader_blend_index() { blend_index } else { return false; };
+
+        // Read the size of the render target that's being drawn on
+        let target_size = match self.active_render_target {
+            Some(RenderTargetId(target_id)) => self.render_targets[target_id].as_ref().map(|target| target.get_size()),
+            None                            => self.default_render_target.as_ref().map(|target| target.get_size()),
+        };
+        let (width, height) = if let Some(target_size) = target_size { target_size } else { return false; };
+
+        // Only render targets with a backing texture can be blended this way
+        let source_texture  = self.render_targets[source_buffer].as_ref().and_then(|source_buffer| source_buffer.texture());
+        let source_texture  = if let Some(source_texture) = source_texture { source_texture } else { return false; };
+
+        // The program depends on whether or not the source needs to be resolved
+        let (program, source_target) = if source_texture.texture_target == gl::TEXTURE_2D_MULTISAMPLE {
+            (StandardShaderProgram::MsaaBlend(source_texture.num_samples as _), gl::TEXTURE_2D_MULTISAMPLE)
+        } else {
+            (StandardShaderProgram::TextureBlend, gl::TEXTURE_2D)
+        };
+
+        // Reuse the backdrop from the last blend if it's the right size
+        if self.blend_backdrop.as_ref().map(|backdrop| backdrop.get_size() != (width, height)).unwrap_or(true) {
+            self.blend_backdrop = Some(RenderTarget::new(width, height, RenderTargetType::Standard));
+        }
+
+        let backdrop            = self.blend_backdrop.as_ref().unwrap();
+        let backdrop_texture    = backdrop.texture().unwrap();
+
+        unsafe {
+            // Copy the current render target to the backdrop (blitting also resolves the samples if the target is multisampled)
+            let mut current_frame_buffer = 0;
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut current_frame_buffer);
+
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, current_frame_buffer as _);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, **backdrop);
+            gl::BlitFramebuffer(0, 0, width as _, height as _, 0, 0, width as _, height as _, gl::COLOR_BUFFER_BIT, gl::NEAREST);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, current_frame_buffer as _);
+
+            panic_on_gl_error("Copy blend backdrop");
+
+            // The shader generates the final pixels, so blending is turned off
+            gl::Disable(gl::BLEND);
+
+            let shader = self.shader_programs.use_program(program);
+
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(source_target, *source_texture);
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_2D, *backdrop_texture);
+            gl::ActiveTexture(gl::TEXTURE0);
+
+            shader.uniform_location(ShaderUniform::MsaaAlpha, "t_Alpha")
+                .map(|t_alpha| {
+                    gl::Uniform1f(t_alpha, alpha as _);
+                });
+
+            shader.uniform_location(ShaderUniform::MsaaTexture, "t_SourceTexture")
+                .map(|source_texture| {
+                    gl::Uniform1i(source_texture, 0);
+                });
+
+            shader.uniform_location(ShaderUniform::MsaaBackdropTexture, "t_BackdropTexture")
+                .map(|backdrop_texture| {
+                    gl::Uniform1i(backdrop_texture, 1);
+                });
+
+            shader.uniform_location(ShaderUniform::MsaaBlendMode, "t_BlendMode")
+                .map(|blend_mode| {
+                    gl::Uniform1i(blend_mode, blend_index as _);
+                });
+
+            // Render a quad filling the region
+            Self::draw_frame_buffer_quad(&region);
+
+            // Restore the blending state
+            gl::Enable(gl::BLEND);
+        }
+
+        // We always revert to the simple shader after this operation
+        self.shader_programs.use_program(StandardShaderProgram::default());
+
+        // Finish up by checking for errors
+        panic_on_gl_error("Draw frame buffer with shader blend");
+
+        true
+    }
+
+    ///
+    /// Draws a quad covering a region of the frame buffer using the current shader program
+    ///
+    unsafe fn draw_frame_buffer_quad(region: &FrameBufferRegion) {
+        // Create the vertices for the two triangles making up the region
+        let min_x               = region.min_x();
+        let min_y               = region.min_y();
+        let max_x               = region.max_x();
+        let max_y               = region.max_y();
+
+        let vertices            = vec![
+            Vertex2D::with_pos(min_x, min_y), Vertex2D::with_pos(max_x, min_y), Vertex2D::with_pos(min_x, max_y),
+            Vertex2D::with_pos(max_x, min_y), Vertex2D::with_pos(min_x, max_y), Vertex2D::with_pos(max_x, max_y),
+        ];
+        let mut buffer          = Buffer::new();
+        let vertex_array        = VertexArray::new();
+        buffer.static_draw(&vertices);
+
+        // Bind a vertex array object to it
+        gl::BindVertexArray(*vertex_array);
+        gl::BindBuffer(gl::ARRAY_BUFFER, *buffer);
+
+        Vertex2D::define_attributes();
+
+        // Clear the bindings
+        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        gl::BindVertexArray(0);
+
+        // Render a quad filling the region
+        gl::BindVertexArray(*vertex_array);
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+
+        gl::BindVertexArray(0);
+    }
+
     ///
     /// Releases an existing render target
     ///
@@ -982,6 +1102,12 @@ impl GlRenderer {
             Some(LinearGradient { clip_texture: None, .. })             => Some(StandardShaderProgram::LinearGradient(StandardShaderVariant::NoClipping, post_processing)),
             Some(LinearGradient { clip_texture: Some(_), .. })          => Some(StandardShaderProgram::LinearGradient(StandardShaderVariant::ClippingMask, post_processing)),
 
+            Some(RadialGradient { clip_texture: None, .. })             => Some(StandardShaderProgram::RadialGradient(StandardShaderVariant::NoClipping, post_processing)),
+            Some(RadialGradient { clip_texture: Some(_), .. })          => Some(StandardShaderProgram::RadialGradient(StandardShaderVariant::ClippingMask, post_processing)),
+
+            Some(ConicGradient { clip_texture: None, .. })              => Some(StandardShaderProgram::ConicGradient(StandardShaderVariant::NoClipping, post_processing)),
+            Some(ConicGradient { clip_texture: Some(_), .. })           => Some(StandardShaderProgram::ConicGradient(StandardShaderVariant::ClippingMask, post_processing)),
+
             None                                                        => None
         }
     }
@@ -1091,7 +1217,7 @@ impl GlRenderer {
                 panic_on_gl_error("Set texture shader");
             }
 
-            LinearGradient { texture, texture_transform, repeat, alpha, clip_texture } => {
+            LinearGradient { texture, texture_transform, spread, alpha, clip_texture } => {
                 let textures            = &self.textures;
                 let TextureId(texture)  = texture;
                 let texture             = if texture < self.textures.len() { self.textures[texture].as_ref() } else { None };
@@ -1112,13 +1238,14 @@ impl GlRenderer {
                         gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as _);
                         gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
 
-                        if repeat {
-                            gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_WRAP_S, gl::REPEAT as _);
-                            gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_WRAP_T, gl::REPEAT as _);
-                        } else {
-                            gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as _);
-                            gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as _);
-                        }
+                        let wrap_mode = match spread {
+                            GradientSpread::Pad     => gl::CLAMP_TO_EDGE,
+                            GradientSpread::Repeat  => gl::REPEAT,
+                            GradientSpread::Reflect => gl::MIRRORED_REPEAT
+                        };
+
+                        gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_WRAP_S, wrap_mode as _);
+                        gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_WRAP_T, wrap_mode as _);
 
                         // Set in the program uniform
                         program.uniform_location(ShaderUniform::Texture, "t_Texture")
@@ -1141,6 +1268,114 @@ impl GlRenderer {
 
                 panic_on_gl_error("Set linear gradient shader");
             }
+
+            RadialGradient { texture, texture_transform, focal_point, spread, alpha, clip_texture } => {
+                let textures            = &self.textures;
+                let TextureId(texture)  = texture;
+                let texture             = if texture < self.textures.len() { self.textures[texture].as_ref() } else { None };
+                let clip_texture        = clip_texture.and_then(|TextureId(texture_id)| textures[texture_id].as_ref());
+                let variant             = if clip_texture.is_some() { StandardShaderVariant::ClippingMask } else { StandardShaderVariant::NoClipping };
+                let texture_transform   = texture_transform.to_opengl_matrix();
+
+                let program             = self.shader_programs.use_program(StandardShaderProgram::RadialGradient(variant, premultiply));
+                if let Some(clip_texture) = clip_texture { program.use_texture(ShaderUniform::ClipTexture, "t_ClipMask", clip_texture, 2); }
+
+                // Set up the texture program
+                if let Some(texture) = texture {
+                    unsafe {
+                        // Bind the texture to texture 0
+                        gl::ActiveTexture(gl::TEXTURE0);
+                        gl::BindTexture(gl::TEXTURE_1D, **texture);
+
+                        gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as _);
+                        gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
+
+                        let wrap_mode = match spread {
+                            GradientSpread::Pad     => gl::CLAMP_TO_EDGE,
+                            GradientSpread::Repeat  => gl::REPEAT,
+                            GradientSpread::Reflect => gl::MIRRORED_REPEAT
+                        };
+
+                        gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_WRAP_S, wrap_mode as _);
+                        gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_WRAP_T, wrap_mode as _);
+
+                        // Set in the program uniform
+                        program.uniform_location(ShaderUniform::Texture, "t_Texture")
+                            .map(|texture_uniform| {
+                                gl::Uniform1i(texture_uniform, 0);
+                            });
+                        program.uniform_location(ShaderUniform::TextureTransform, "texture_transform")
+                            .map(|transform_uniform| {
+                                gl::UniformMatrix4fv(transform_uniform, 1, gl::FALSE, texture_transform.as_ptr());
+                            });
+                        program.uniform_location(ShaderUniform::TextureAlpha, "texture_alpha")
+                            .map(|alpha_uniform| {
+                                gl::Uniform1f(alpha_uniform, alpha);
+                            });
+                        program.uniform_location(ShaderUniform::GradientFocalPoint, "focal_point")
+                            .map(|focal_point_uniform| {
+                                gl::Uniform2f(focal_point_uniform, focal_point.0, focal_point.1);
+                            });
+                    }
+                } else {
+                    // Texture not found: revert to the simple shader
+                    self.shader_programs.use_program(StandardShaderProgram::default());
+                }
+
+                panic_on_gl_error("Set radial gradient shader");
+            }
+
+            ConicGradient { texture, texture_transform, spread, alpha, clip_texture } => {
+                let textures            = &self.textures;
+                let TextureId(texture)  = texture;
+                let texture             = if texture < self.textures.len() { self.textures[texture].as_ref() } else { None };
+                let clip_texture        = clip_texture.and_then(|TextureId(texture_id)| textures[texture_id].as_ref());
+                let variant             = if clip_texture.is_some() { StandardShaderVariant::ClippingMask } else { StandardShaderVariant::NoClipping };
+                let texture_transform   = texture_transform.to_opengl_matrix();
+
+                let program             = self.shader_programs.use_program(StandardShaderProgram::ConicGradient(variant, premultiply));
+                if let Some(clip_texture) = clip_texture { program.use_texture(ShaderUniform::ClipTexture, "t_ClipMask", clip_texture, 2); }
+
+                // Set up the texture program
+                if let Some(texture) = texture {
+                    unsafe {
+                        // Bind the texture to texture 0
+                        gl::ActiveTexture(gl::TEXTURE0);
+                        gl::BindTexture(gl::TEXTURE_1D, **texture);
+
+                        gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as _);
+                        gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
+
+                        let wrap_mode = match spread {
+                            GradientSpread::Pad     => gl::CLAMP_TO_EDGE,
+                            GradientSpread::Repeat  => gl::REPEAT,
+                            GradientSpread::Reflect => gl::MIRRORED_REPEAT
+                        };
+
+                        gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_WRAP_S, wrap_mode as _);
+                        gl::TexParameteri(gl::TEXTURE_1D, gl::TEXTURE_WRAP_T, wrap_mode as _);
+
+                        // Set in the program uniform
+                        program.uniform_location(ShaderUniform::Texture, "t_Texture")
+                            .map(|texture_uniform| {
+                                gl::Uniform1i(texture_uniform, 0);
+                            });
+                        program.uniform_location(ShaderUniform::TextureTransform, "texture_transform")
+                            .map(|transform_uniform| {
+                                gl::UniformMatrix4fv(transform_uniform, 1, gl::FALSE, texture_transform.as_ptr());
+                            });
+                        program.uniform_location(ShaderUniform::TextureAlpha, "texture_alpha")
+                            .map(|alpha_uniform| {
+                                gl::Uniform1f(alpha_uniform, alpha);
+                            });
+                    }
+                } else {
+                    // Texture not found: revert to the simple shader
+                    self.shader_programs.use_program(StandardShaderProgram::default());
+                }
+
+                panic_on_gl_error("Set conic gradient shader");
+            }
         }
 
         // Set the transform for the newly selected shader
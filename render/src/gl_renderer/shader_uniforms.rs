@@ -21,12 +21,21 @@ pub enum ShaderUniform {
     /// The alpha adjustment applied to the texture colour
     TextureAlpha,
 
+    /// The focal point of a radial gradient
+    GradientFocalPoint,
+
     /// The texture for a MSAA shader
     MsaaTexture,
 
     /// The alpha value to use for a MSAA shader
     MsaaAlpha,
 
+    /// The copy of the target texture that a MSAA blend shader blends against
+    MsaaBackdropTexture,
+
+    /// The blend function used by a MSAA blend shader
+    MsaaBlendMode,
+
     /// The weights for the gaussian blur shader
     BlurWeights,
 
@@ -67,12 +67,24 @@ pub enum StandardShaderProgram {
     /// Uses a 1D texture input to render a linear gradient fill
     LinearGradient(StandardShaderVariant, ColorPostProcessingStep),
 
+    /// Uses a 1D texture input to render a radial gradient fill
+    RadialGradient(StandardShaderVariant, ColorPostProcessingStep),
+
+    /// Uses a 1D texture input to render a conic (sweep) gradient fill
+    ConicGradient(StandardShaderVariant, ColorPostProcessingStep),
+
     /// Uses a 1D texture to draw dashed lines
     DashedLine(StandardShaderVariant, ColorPostProcessingStep),
 
     /// Texture renderer that resolves MSAA textures 1-to-1 with the given number of samples
     MsaaResolve(u8, ColorPostProcessingStep),
 
+    /// Resolves MSAA textures 1-to-1 with the given number of samples and blends them against a backdrop texture using one of the shader blend modes
+    MsaaBlend(u8),
+
+    /// Blends a non-MSAA texture 1-to-1 against a backdrop texture using one of the shader blend modes
+    TextureBlend,
+
     /// Turns a texture without pre-multiplied alpha into one with pre-multiplied alpha
     PremultiplyAlpha,
 
@@ -190,6 +202,7 @@ impl StandardShaderProgram {
         let gradient_fragment       = String::from_utf8(include_bytes!["../../shaders/texture/gradient.glslf"].to_vec()).unwrap();
         let msaa_vertex             = String::from_utf8(include_bytes!["../../shaders/simple/resolve.glslv"].to_vec()).unwrap();
         let msaa4_resolve           = String::from_utf8(include_bytes!["../../shaders/simple/multisample_resolve_4.glslf"].to_vec()).unwrap();
+        let texture_blend           = String::from_utf8(include_bytes!["../../shaders/simple/texture_blend.glslf"].to_vec()).unwrap();
         let filter_vertex           = String::from_utf8(include_bytes!["../../shaders/simple/resolve.glslv"].to_vec()).unwrap();
         let premultiply             = String::from_utf8(include_bytes!["../../shaders/filters/premultiply.glslf"].to_vec()).unwrap();
         let blur9                   = String::from_utf8(include_bytes!["../../shaders/filters/blur_9.glslf"].to_vec()).unwrap();
@@ -208,10 +221,14 @@ impl StandardShaderProgram {
                 Simple(variant, post_process)               => { Self::load_shader(&simple_vertex, &vec!["a_Pos", "a_Color", "a_TexCoord"], &simple_fragment, &vec![], &variant.defines().into_iter().chain(post_process.defines()).collect()) }
                 Texture(variant, alpha_mode, post_process)  => { Self::load_shader(&texture_vertex, &vec!["a_Pos", "a_Color", "a_TexCoord"], &texture_fragment, &vec![], &variant.defines().into_iter().chain(post_process.defines()).chain(alpha_mode.defines()).collect()) }
                 LinearGradient(variant, post_process)       => { Self::load_shader(&gradient_vertex, &vec!["a_Pos", "a_Color", "a_TexCoord"], &gradient_fragment, &vec![], &variant.defines().into_iter().chain(post_process.defines()).collect()) }
+                RadialGradient(variant, post_process)       => { Self::load_shader(&gradient_vertex, &vec!["a_Pos", "a_Color", "a_TexCoord"], &gradient_fragment, &vec![], &variant.defines().into_iter().chain(post_process.defines()).chain(vec!["RADIAL_GRADIENT"]).collect()) }
+                ConicGradient(variant, post_process)        => { Self::load_shader(&gradient_vertex, &vec!["a_Pos", "a_Color", "a_TexCoord"], &gradient_fragment, &vec![], &variant.defines().into_iter().chain(post_process.defines()).chain(vec!["CONIC_GRADIENT"]).collect()) }
                 DashedLine(variant, post_process)           => { Self::load_shader(&simple_vertex, &vec!["a_Pos", "a_Color", "a_TexCoord"], &dashed_line_fragment, &vec![], &variant.defines().into_iter().chain(post_process.defines()).collect()) }
 
                 MsaaResolve(4, post_process)                => { Self::load_shader(&msaa_vertex, &vec![], &msaa4_resolve, &vec![], &post_process.defines()) }
                 MsaaResolve(_num_samples, _post_process)    => { unimplemented!() }
+                MsaaBlend(num_samples)                      => { let num_samples = format!("NUM_SAMPLES {}", num_samples); Self::load_shader(&msaa_vertex, &vec![], &texture_blend, &vec![], &vec!["MULTISAMPLE_SOURCE", &num_samples]) }
+                TextureBlend                                => { Self::load_shader(&msaa_vertex, &vec![], &texture_blend, &vec![], &vec![]) }
 
                 PremultiplyAlpha                            => { Self::load_shader(&filter_vertex, &vec![], &premultiply, &vec![], &vec![]) }
                 Blur9Horizontal                             => { Self::load_shader(&filter_vertex, &vec![], &blur9, &vec![], &vec!["FILTER_HORIZ"]) }
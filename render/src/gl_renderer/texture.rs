@@ -27,7 +27,7 @@ pub struct Texture {
     pub (super) premultiplied:  bool,
     pub (super) texture_target: gl::types::GLuint,
     pub (super) texture_format: gl::types::GLuint,
-    num_samples:                usize, 
+    pub (super) num_samples:    usize,
     pub (super) width:          gl::types::GLsizei,
     pub (super) height:         gl::types::GLsizei,
 }
@@ -44,7 +44,10 @@ pub struct MetalRenderer {
     textures: Vec<Option<metal::Texture>>,
 
     /// The cache of render pipeline states used by this renderer
-    pipeline_states: HashMap<PipelineConfiguration, metal::RenderPipelineState>
+    pipeline_states: HashMap<PipelineConfiguration, metal::RenderPipelineState>,
+
+    /// Set once we've warned that a shader blend mode couldn't be used with the current render target
+    warned_blend_fallback: bool
 }
 
 ///
@@ -72,6 +75,15 @@ struct RenderState<'a> {
     /// The alpha value to apply to the texture
     texture_alpha: Option<f64>,
 
+    /// How the current gradient is extended outside of the range 0-1
+    gradient_spread: Option<GradientSpread>,
+
+    /// The focal point of the current radial gradient
+    focal_point: Option<(f32, f32)>,
+
+    /// The blend mode that was last requested (the shader blend modes are only used when drawing frame buffers)
+    blend_mode: BlendMode,
+
     /// The active pipeline configuration
     pipeline_config: PipelineConfiguration,
 
@@ -95,15 +107,16 @@ impl MetalRenderer {
         let shader_library  = device.new_library_with_data(include_bytes![concat!(env!("OUT_DIR"), "/flo.metallib")]).unwrap();
 
         MetalRenderer {
-            device:             device,
-            flip_y:             false,
-            command_queue:      command_queue,
-            vertex_buffers:     vec![],
-            index_buffers:      vec![],
-            render_targets:     vec![],
-            textures:           vec![],
-            shader_library:     shader_library,
-            pipeline_states:    HashMap::new()
+            device:                 device,
+            flip_y:                 false,
+            command_queue:          command_queue,
+            vertex_buffers:         vec![],
+            index_buffers:          vec![],
+            render_targets:         vec![],
+            textures:               vec![],
+            shader_library:         shader_library,
+            pipeline_states:        HashMap::new(),
+            warned_blend_fallback:  false
         }
     }
 
@@ -116,15 +129,16 @@ impl MetalRenderer {
         let shader_library  = device.new_library_with_data(include_bytes![concat!(env!("OUT_DIR"), "/flo.metallib")]).unwrap();
 
         MetalRenderer {
-            device:             device,
-            flip_y:             flip_y,
-            command_queue:      command_queue,
-            vertex_buffers:     vec![],
-            index_buffers:      vec![],
-            render_targets:     vec![],
-            textures:           vec![],
-            shader_library:     shader_library,
-            pipeline_states:    HashMap::new()
+            device:                 device,
+            flip_y:                 flip_y,
+            command_queue:          command_queue,
+            vertex_buffers:         vec![],
+            index_buffers:          vec![],
+            render_targets:         vec![],
+            textures:               vec![],
+            shader_library:         shader_library,
+            pipeline_states:        HashMap::new(),
+            warned_blend_fallback:  false
         }
     }
 
@@ -207,6 +221,21 @@ impl MetalRenderer {
             let alpha = alpha.to_ne_bytes();
             state.command_encoder.set_fragment_bytes(FragmentInputIndex_FragmentAlpha as u64, 4, alpha.as_ptr() as _);
         }
+
+        if let Some(gradient_spread) = &state.gradient_spread {
+            let spread = match gradient_spread {
+                GradientSpread::Pad     => GradientSpreadValue_GradientSpreadPad,
+                GradientSpread::Repeat  => GradientSpreadValue_GradientSpreadRepeat,
+                GradientSpread::Reflect => GradientSpreadValue_GradientSpreadReflect
+            } as i32;
+            let spread = spread.to_ne_bytes();
+            state.command_encoder.set_fragment_bytes(FragmentInputIndex_FragmentGradientSpread as u64, 4, spread.as_ptr() as _);
+        }
+
+        if let Some((x, y)) = &state.focal_point {
+            let focal_point = [*x, *y];
+            state.command_encoder.set_fragment_bytes(FragmentInputIndex_FragmentFocalPoint as u64, 8, focal_point.as_ptr() as _);
+        }
     }
 
     ///
@@ -230,6 +259,9 @@ impl MetalRenderer {
             matrix:                 matrix,
             texture_transform:      None,
             texture_alpha:          None,
+            gradient_spread:        None,
+            focal_point:            None,
+            blend_mode:             BlendMode::SourceOver,
             pipeline_config:        pipeline_config,
             pipeline_state:         pipeline_state,
             command_buffer:         command_buffer,
@@ -365,7 +397,9 @@ impl MetalRenderer {
     /// Updates the blend mode for a render state
     ///
     fn blend_mode(&mut self, blend_mode: BlendMode, state: &mut RenderState) {
-        state.pipeline_config.blend_mode    = blend_mode;
+        // Other drawing actions use source over for the blend modes that need a shader
+        state.blend_mode                    = blend_mode;
+        state.pipeline_config.blend_mode    = if blend_mode.is_shader_blend() { BlendMode::SourceOver } else { blend_mode };
         state.pipeline_state                = self.get_pipeline_state(&state.pipeline_config);
         state.command_encoder.set_render_pipeline_state(&state.pipeline_state);
     }
@@ -449,6 +483,18 @@ impl MetalRenderer {
     /// Renders a frame buffer to another texture (resolving multi-sampling if there is any)
     ///
     fn draw_frame_buffer(&mut self, RenderTargetId(source_buffer): RenderTargetId, region: FrameBufferRegion, alpha: f64, state: &mut RenderState) {
+        // Blend modes that can't be performed by the fixed-function blender need a copy of the current target to read from
+        let shader_blend    = Some(state.blend_mode).filter(|blend_mode| blend_mode.is_shader_blend());
+        let backdrop        = if shader_blend.is_some() { self.copy_backdrop(state) } else { None };
+
+        if let (Some(blend_mode), None) = (shader_blend, &backdrop) {
+            // The target can't be copied from (eg, a framebuffer-only drawable), so this is drawn using source over instead
+            if !self.warned_blend_fallback {
+                println!("Blend mode {:?} is not available for this render target: using SourceOver instead", blend_mode);
+                self.warned_blend_fallback = true;
+            }
+        }
+
         let render_targets  = &self.render_targets;
 
         if let Some(source_buffer) = &render_targets[source_buffer] {
@@ -466,6 +512,15 @@ impl MetalRenderer {
             config.source_is_premultiplied  = true;
             config.fragment_shader          = if source_buffer.is_multisampled() { String::from("texture_multisample_fragment") } else { String::from("texture_fragment") };
 
+            if let (Some(blend_mode), Some(backdrop)) = (shader_blend, &backdrop) {
+                // The blend shader replaces the target pixels with the result of blending against the backdrop
+                let source_type             = if source_buffer.is_multisampled() { "_multisample" } else { "" };
+                let backdrop_type           = if backdrop.sample_count() > 1 { "_multisample_backdrop" } else { "" };
+
+                config.blend_mode           = blend_mode;
+                config.fragment_shader      = format!("blend{}{}_fragment", source_type, backdrop_type);
+            }
+
             // Convert to a pipeline state
             let pipeline_state              = self.get_pipeline_state(&config);
 
@@ -516,17 +571,66 @@ impl MetalRenderer {
             let alpha = alpha.to_ne_bytes();
             state.command_encoder.set_fragment_bytes(FragmentInputIndex_FragmentAlpha as u64, 4, alpha.as_ptr() as _);
 
+            if let (Some(blend_mode), Some(backdrop)) = (shader_blend, &backdrop) {
+                let blend_index = blend_mode.shader_blend_index().unwrap_or(0) as i32;
+                let blend_index = blend_index.to_ne_bytes();
+
+                state.command_encoder.set_fragment_texture(FragmentInputIndex_FragmentIndexBackdropTexture as u64, Some(backdrop));
+                state.command_encoder.set_fragment_bytes(FragmentInputIndex_FragmentBlendMode as u64, 4, blend_index.as_ptr() as _);
+            }
+
             // Draw the texture
             state.command_encoder.draw_primitives(metal::MTLPrimitiveType::TriangleStrip, 0, 4);
 
             // Reset the pipeline state to the one in the render state
             state.command_encoder.set_fragment_texture(FragmentInputIndex_FragmentIndexTexture as u64, None);
+            state.command_encoder.set_fragment_texture(FragmentInputIndex_FragmentIndexBackdropTexture as u64, None);
 
             state.command_encoder.set_render_pipeline_state(&state.pipeline_state);
             self.setup_command_encoder(state);
         }
     }
 
+    ///
+    /// Copies the current render target into a new texture, so it can be used as the backdrop for the blend shaders
+    ///
+    /// Returns None if the current render target can't be copied from (eg, a drawable that's framebuffer-only)
+    ///
+    fn copy_backdrop(&mut self, state: &mut RenderState) -> Option<metal::Texture> {
+        let target = state.target_texture.clone();
+
+        if target.framebuffer_only() {
+            return None;
+        }
+
+        // Create a texture with the same format as the target
+        let texture_descriptor  = metal::TextureDescriptor::new();
+
+        texture_descriptor.set_texture_type(target.texture_type());
+        texture_descriptor.set_width(target.width());
+        texture_descriptor.set_height(target.height());
+        texture_descriptor.set_pixel_format(target.pixel_format());
+        texture_descriptor.set_sample_count(target.sample_count());
+        texture_descriptor.set_storage_mode(metal::MTLStorageMode::Private);
+        texture_descriptor.set_usage(metal::MTLTextureUsage::ShaderRead);
+
+        let backdrop            = self.device.new_texture(&texture_descriptor);
+
+        // Finish the current render pass and copy the target using a blit encoder
+        state.command_encoder.end_encoding();
+
+        let blit_encoder        = self.get_blit_command_encoder(state.command_buffer);
+        blit_encoder.copy_from_texture(&target, 0, 0, metal::MTLOrigin { x: 0, y: 0, z: 0 }, metal::MTLSize { width: target.width(), height: target.height(), depth: 1 },
+            &backdrop, 0, 0, metal::MTLOrigin { x: 0, y: 0, z: 0 });
+        blit_encoder.end_encoding();
+
+        // Carry on rendering to the target
+        state.command_encoder = self.get_command_encoder(state.command_buffer, &state.target_texture);
+        self.setup_command_encoder(state);
+
+        Some(backdrop)
+    }
+
     ///
     /// Stores a texture with the specified texture ID
     ///
@@ -797,6 +901,8 @@ impl MetalRenderer {
         state.fill_texture                  = None;
         state.clip_texture                  = None;
         state.texture_transform             = None;
+        state.gradient_spread               = None;
+        state.focal_point                   = None;
 
         // Update the state according to the shader type
         match shader_type {
@@ -833,20 +939,66 @@ impl MetalRenderer {
                 state.clip_texture                      = self.textures[clip_texture].clone();
             }
 
-            ShaderType::LinearGradient { texture: TextureId(gradient_texture), texture_transform, repeat, alpha, clip_texture: None } => { 
+            ShaderType::LinearGradient { texture: TextureId(gradient_texture), texture_transform, spread, alpha, clip_texture: None } => { 
                 state.pipeline_config.vertex_shader     = String::from("gradient_vertex");
                 state.pipeline_config.fragment_shader   = String::from("gradient_fragment");
                 state.texture_transform                 = Some(MatrixBuffer::from_matrix(&self.device, texture_transform));
                 state.texture_alpha                     = Some(alpha as _);
+                state.gradient_spread                   = Some(spread);
 
                 state.fill_texture                      = self.textures[gradient_texture].clone();
             }
 
-            ShaderType::LinearGradient { texture: TextureId(gradient_texture), texture_transform, repeat, alpha, clip_texture: Some(TextureId(clip_texture)) } => { 
+            ShaderType::LinearGradient { texture: TextureId(gradient_texture), texture_transform, spread, alpha, clip_texture: Some(TextureId(clip_texture)) } => { 
                 state.pipeline_config.vertex_shader     = String::from("gradient_vertex");
                 state.pipeline_config.fragment_shader   = String::from("gradient_clip_mask_multisample_fragment");
                 state.texture_transform                 = Some(MatrixBuffer::from_matrix(&self.device, texture_transform));
                 state.texture_alpha                     = Some(alpha as _);
+                state.gradient_spread                   = Some(spread);
+
+                state.fill_texture                      = self.textures[gradient_texture].clone();
+                state.clip_texture                      = self.textures[clip_texture].clone();
+            }
+
+            ShaderType::RadialGradient { texture: TextureId(gradient_texture), texture_transform, focal_point, spread, alpha, clip_texture: None } => { 
+                state.pipeline_config.vertex_shader     = String::from("gradient_vertex");
+                state.pipeline_config.fragment_shader   = String::from("radial_gradient_fragment");
+                state.texture_transform                 = Some(MatrixBuffer::from_matrix(&self.device, texture_transform));
+                state.texture_alpha                     = Some(alpha as _);
+                state.gradient_spread                   = Some(spread);
+                state.focal_point                       = Some(focal_point);
+
+                state.fill_texture                      = self.textures[gradient_texture].clone();
+            }
+
+            ShaderType::RadialGradient { texture: TextureId(gradient_texture), texture_transform, focal_point, spread, alpha, clip_texture: Some(TextureId(clip_texture)) } => { 
+                state.pipeline_config.vertex_shader     = String::from("gradient_vertex");
+                state.pipeline_config.fragment_shader   = String::from("radial_gradient_clip_mask_multisample_fragment");
+                state.texture_transform                 = Some(MatrixBuffer::from_matrix(&self.device, texture_transform));
+                state.texture_alpha                     = Some(alpha as _);
+                state.gradient_spread                   = Some(spread);
+                state.focal_point                       = Some(focal_point);
+
+                state.fill_texture                      = self.textures[gradient_texture].clone();
+                state.clip_texture                      = self.textures[clip_texture].clone();
+            }
+
+            ShaderType::ConicGradient { texture: TextureId(gradient_texture), texture_transform, spread, alpha, clip_texture: None } => { 
+                state.pipeline_config.vertex_shader     = String::from("gradient_vertex");
+                state.pipeline_config.fragment_shader   = String::from("conic_gradient_fragment");
+                state.texture_transform                 = Some(MatrixBuffer::from_matrix(&self.device, texture_transform));
+                state.texture_alpha                     = Some(alpha as _);
+                state.gradient_spread                   = Some(spread);
+
+                state.fill_texture                      = self.textures[gradient_texture].clone();
+            }
+
+            ShaderType::ConicGradient { texture: TextureId(gradient_texture), texture_transform, spread, alpha, clip_texture: Some(TextureId(clip_texture)) } => { 
+                state.pipeline_config.vertex_shader     = String::from("gradient_vertex");
+                state.pipeline_config.fragment_shader   = String::from("conic_gradient_clip_mask_multisample_fragment");
+                state.texture_transform                 = Some(MatrixBuffer::from_matrix(&self.device, texture_transform));
+                state.texture_alpha                     = Some(alpha as _);
+                state.gradient_spread                   = Some(spread);
 
                 state.fill_texture                      = self.textures[gradient_texture].clone();
                 state.clip_texture                      = self.textures[clip_texture].clone();
@@ -127,10 +127,25 @@ impl PipelineConfiguration {
 
             (AllChannelAlphaSourceOver, true)           => (One, OneMinusSourceColor, One, OneMinusSourceAlpha),
             (AllChannelAlphaDestinationOver, true)      => (OneMinusDestinationColor, One, OneMinusDestinationAlpha, One),
+
+            // The blend shaders replace the target with the result of blending against a copy of it, so blending is turned off for these modes
+            (Darken, _)                                 |
+            (Lighten, _)                                |
+            (Overlay, _)                                |
+            (ColorDodge, _)                             |
+            (ColorBurn, _)                              |
+            (HardLight, _)                              |
+            (SoftLight, _)                              |
+            (Difference, _)                             |
+            (Exclusion, _)                              |
+            (Hue, _)                                    |
+            (Saturation, _)                             |
+            (Color, _)                                  |
+            (Luminosity, _)                             => (One, Zero, One, Zero),
         };
 
         descriptor.color_attachments().object_at(0).unwrap().set_pixel_format(self.pixel_format);
-        descriptor.color_attachments().object_at(0).unwrap().set_blending_enabled(true);
+        descriptor.color_attachments().object_at(0).unwrap().set_blending_enabled(!self.blend_mode.is_shader_blend());
         descriptor.color_attachments().object_at(0).unwrap().set_source_rgb_blend_factor(src_rgb);
         descriptor.color_attachments().object_at(0).unwrap().set_destination_rgb_blend_factor(dst_rgb);
         descriptor.color_attachments().object_at(0).unwrap().set_source_alpha_blend_factor(src_alpha);
@@ -85,7 +85,10 @@ impl Pipeline {
 
         let bind_layout             = match config.shader_module {
             WgpuShader::LinearGradient(..)                      => vec![&matrix_bind_layout, &clip_bind_layout, &linear_gradient_layout],
+            WgpuShader::RadialGradient(..)                      => vec![&matrix_bind_layout, &clip_bind_layout, &linear_gradient_layout],
+            WgpuShader::ConicGradient(..)                       => vec![&matrix_bind_layout, &clip_bind_layout, &linear_gradient_layout],
             WgpuShader::Texture(..)                             => vec![&matrix_bind_layout, &clip_bind_layout, &texture_layout],
+            WgpuShader::Blend(..)                               => vec![&matrix_bind_layout, &clip_bind_layout, &texture_layout],
             WgpuShader::Simple(..)                              => vec![&matrix_bind_layout, &clip_bind_layout],
             WgpuShader::Filter(FilterShader::AlphaBlend(..))    => vec![&alpha_blend_layout],
             WgpuShader::Filter(FilterShader::BlurFixed(..))     => vec![&blur_fixed_layout],
@@ -193,9 +196,12 @@ impl Pipeline {
     pub fn bind_clip_mask(&self, device: &wgpu::Device, clip_texture: Option<&wgpu::Texture>) -> wgpu::BindGroup {
         match (&self.shader_module, clip_texture) {
             (WgpuShader::LinearGradient(StandardShaderVariant::ClippingMask, _, _, _), Some(clip_texture))  |
+            (WgpuShader::RadialGradient(StandardShaderVariant::ClippingMask, _, _, _), Some(clip_texture))  |
+            (WgpuShader::ConicGradient(StandardShaderVariant::ClippingMask, _, _, _), Some(clip_texture))   |
             (WgpuShader::Texture(StandardShaderVariant::ClippingMask, _, _, _, _), Some(clip_texture))      |
-            (WgpuShader::Simple(StandardShaderVariant::ClippingMask, _), Some(clip_texture))                => {
-                // Create a view of the texture
+            (WgpuShader::Simple(StandardShaderVariant::ClippingMask, _), Some(clip_texture))                |
+            (WgpuShader::Blend(_, _, _), Some(clip_texture))                                                => {
+                // Create a view of the texture (the blend shaders use the clip texture as the backdrop)
                 let view = clip_texture.create_view(&wgpu::TextureViewDescriptor::default());
 
                 // Bind to group 1
@@ -214,6 +220,8 @@ impl Pipeline {
             (_, None)                                                                   |
             (WgpuShader::Filter(_), _)                                                  |
             (WgpuShader::LinearGradient(StandardShaderVariant::NoClipping, _, _, _), _) |
+            (WgpuShader::RadialGradient(StandardShaderVariant::NoClipping, _, _, _), _) |
+            (WgpuShader::ConicGradient(StandardShaderVariant::NoClipping, _, _, _), _)  |
             (WgpuShader::Texture(StandardShaderVariant::NoClipping, _, _, _, _), _)     |
             (WgpuShader::Simple(StandardShaderVariant::NoClipping, _), _)               => {
                 // Group 1 is bound to an empty set if clipping is off or no texture is defined
@@ -238,7 +246,9 @@ impl Pipeline {
         let texture_settings_binding = wgpu::BindingResource::Buffer(texture_settings_binding);
 
         match (self.shader_module, texture, sampler) {
-            (WgpuShader::LinearGradient(..), Some(texture), Some(sampler)) => {
+            (WgpuShader::LinearGradient(..), Some(texture), Some(sampler))  |
+            (WgpuShader::RadialGradient(..), Some(texture), Some(sampler))  |
+            (WgpuShader::ConicGradient(..), Some(texture), Some(sampler))   => {
                 // Create a view of the texture
                 let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
@@ -264,7 +274,8 @@ impl Pipeline {
                 })
             },
 
-            (WgpuShader::Texture(_, InputTextureType::Sampler, _, _, _), Some(texture), Some(sampler)) => {
+            (WgpuShader::Texture(_, InputTextureType::Sampler, _, _, _), Some(texture), Some(sampler)) |
+            (WgpuShader::Blend(InputTextureType::Sampler, _, _), Some(texture), Some(sampler))          => {
                 // Create a view of the texture
                 let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
@@ -290,7 +301,8 @@ impl Pipeline {
                 })
             }
 
-            (WgpuShader::Texture(_, InputTextureType::Multisampled, _, _, _), Some(texture), _) => {
+            (WgpuShader::Texture(_, InputTextureType::Multisampled, _, _, _), Some(texture), _) |
+            (WgpuShader::Blend(InputTextureType::Multisampled, _, _), Some(texture), _)         => {
                 // Create a view of the texture
                 let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
@@ -313,7 +325,10 @@ impl Pipeline {
             }
 
             (WgpuShader::LinearGradient(..), _, None)                           |
-            (WgpuShader::Texture(_, InputTextureType::Sampler, ..), _, None)    => {
+            (WgpuShader::RadialGradient(..), _, None)                           |
+            (WgpuShader::ConicGradient(..), _, None)                            |
+            (WgpuShader::Texture(_, InputTextureType::Sampler, ..), _, None)    |
+            (WgpuShader::Blend(InputTextureType::Sampler, ..), _, None)         => {
                 // Group 2 is bound to an empty set if no texture is defined (or the sampler is missing when it was expected)
                 device.create_bind_group(&wgpu::BindGroupDescriptor {
                     label:      Some("bind_input_texture_no_sampler"),
@@ -146,6 +146,9 @@ impl PipelineConfiguration {
 
                 Some(AllChannelAlphaSourceOver)         => Some(create_add_blend_state(One, OneMinusDst, One, OneMinusSrcAlpha)),
                 Some(AllChannelAlphaDestinationOver)    => Some(create_add_blend_state(OneMinusDst, One, OneMinusDstAlpha, One)),
+
+                // Shader blend modes are only applied by draw_frame_buffer: other operations use source over
+                Some(_)                 => Some(create_add_blend_state(SrcAlpha, OneMinusSrcAlpha, One, OneMinusSrcAlpha)),
             }
         } else {
             // Shader output is pre-multiplied
@@ -168,6 +171,9 @@ impl PipelineConfiguration {
 
                 Some(AllChannelAlphaSourceOver)         => Some(create_add_blend_state(One, OneMinusSrc, One, OneMinusSrcAlpha)),
                 Some(AllChannelAlphaDestinationOver)    => Some(create_add_blend_state(OneMinusDst, One, OneMinusDstAlpha, One)),
+
+                // Shader blend modes are only applied by draw_frame_buffer: other operations use source over
+                Some(_)                 => Some(create_add_blend_state(One, OneMinusSrcAlpha, One, OneMinusSrcAlpha)),
             }
 
         }
@@ -300,20 +306,44 @@ impl PipelineConfiguration {
                 }
             },
         ];
+        static WITH_BACKDROP:   [wgpu::BindGroupLayoutEntry; 1] = [
+            wgpu::BindGroupLayoutEntry {
+                binding:            0,
+                visibility:         wgpu::ShaderStages::FRAGMENT,
+                count:              None,
+                ty:                 wgpu::BindingType::Texture {
+                    sample_type:    wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled:   false,
+                }
+            },
+        ];
 
         // The type of binding that's in use depends on if the shader module has a clipping mask or not
         match self.shader_module {
             WgpuShader::LinearGradient(StandardShaderVariant::ClippingMask, _, _, _)    |
+            WgpuShader::RadialGradient(StandardShaderVariant::ClippingMask, _, _, _)    |
+            WgpuShader::ConicGradient(StandardShaderVariant::ClippingMask, _, _, _)     |
             WgpuShader::Texture(StandardShaderVariant::ClippingMask, _, _, _, _)        |
-            WgpuShader::Simple(StandardShaderVariant::ClippingMask, _)                  => {
+            WgpuShader::Simple(StandardShaderVariant::ClippingMask, _)                  |
+            WgpuShader::Blend(_, BackdropTextureType::Multisampled, _)                  => {
                 wgpu::BindGroupLayoutDescriptor {
                     label:      Some("clip_mask_bind_group_layout_with_clip_mask"),
                     entries:    &WITH_CLIP_MASK,
                 }
             }
 
+            WgpuShader::Blend(_, BackdropTextureType::Standard, _)                      => {
+                wgpu::BindGroupLayoutDescriptor {
+                    label:      Some("clip_mask_bind_group_layout_with_backdrop"),
+                    entries:    &WITH_BACKDROP,
+                }
+            }
+
             WgpuShader::Filter(_)                                                   |
             WgpuShader::LinearGradient(StandardShaderVariant::NoClipping, _, _, _)  |
+            WgpuShader::RadialGradient(StandardShaderVariant::NoClipping, _, _, _)  |
+            WgpuShader::ConicGradient(StandardShaderVariant::NoClipping, _, _, _)   |
             WgpuShader::Texture(StandardShaderVariant::NoClipping, _, _, _, _)      |
             WgpuShader::Simple(StandardShaderVariant::NoClipping, _)                => {
                 wgpu::BindGroupLayoutDescriptor {
@@ -390,14 +420,16 @@ impl PipelineConfiguration {
         ];
 
         match self.shader_module {
-            WgpuShader::Texture(_, InputTextureType::Sampler, _, _, _) => {
+            WgpuShader::Texture(_, InputTextureType::Sampler, _, _, _)  |
+            WgpuShader::Blend(InputTextureType::Sampler, _, _)          => {
                 wgpu::BindGroupLayoutDescriptor {
                     label:      Some("texture_bind_group_layout_sampler"),
                     entries:    &WITH_SAMPLER,
                 }
             },
 
-            WgpuShader::Texture(_, InputTextureType::Multisampled, _, _, _) => {
+            WgpuShader::Texture(_, InputTextureType::Multisampled, _, _, _) |
+            WgpuShader::Blend(InputTextureType::Multisampled, _, _)         => {
                 wgpu::BindGroupLayoutDescriptor {
                     label:      Some("texture_bind_group_layout_multisampled"),
                     entries:    &WITH_MULTISAMPLE,
@@ -406,6 +438,8 @@ impl PipelineConfiguration {
 
             WgpuShader::Filter(_)                   |
             WgpuShader::LinearGradient(_, _, _, _)  |
+            WgpuShader::RadialGradient(_, _, _, _)  |
+            WgpuShader::ConicGradient(_, _, _, _)   |
             WgpuShader::Simple(_, _)                => {
                 wgpu::BindGroupLayoutDescriptor {
                     label:      Some("texture_bind_group_layout_not_texture_shader"),
@@ -456,7 +490,9 @@ impl PipelineConfiguration {
         ];
 
         match self.shader_module {
-            WgpuShader::LinearGradient(_, _, _, _) => {
+            WgpuShader::LinearGradient(_, _, _, _) |
+            WgpuShader::RadialGradient(_, _, _, _) |
+            WgpuShader::ConicGradient(_, _, _, _)  => {
                 wgpu::BindGroupLayoutDescriptor {
                     label:      Some("texture_bind_group_layout_sampler"),
                     entries:    &WITH_SAMPLER,
@@ -465,6 +501,7 @@ impl PipelineConfiguration {
 
             WgpuShader::Filter(_)               |
             WgpuShader::Texture(_, _, _, _, _)  |
+            WgpuShader::Blend(_, _, _)          |
             WgpuShader::Simple(_, _)            => {
                 wgpu::BindGroupLayoutDescriptor {
                     label:      Some("texture_bind_group_layout_not_texture_shader"),
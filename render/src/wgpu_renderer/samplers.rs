@@ -1,3 +1,5 @@
+use crate::action::*;
+
 use wgpu;
 
 use std::sync::*;
@@ -12,8 +14,11 @@ pub (crate) struct Samplers {
     /// Sampler that doesn't repeat
     non_repeating_sampler: Arc<wgpu::Sampler>,
 
-    /// The sampler used for rendering gradients
-    gradient_sampler: Arc<wgpu::Sampler>,
+    /// The sampler used for rendering gradients, which mirrors the gradient when it repeats
+    reflecting_gradient_sampler: Arc<wgpu::Sampler>,
+
+    /// The sampler used for rendering gradients that repeat
+    repeating_gradient_sampler: Arc<wgpu::Sampler>,
 
     /// The sampler used for rendering gradients, which clamps rather than repeating
     non_repeating_gradient_sampler: Arc<wgpu::Sampler>,
@@ -54,7 +59,7 @@ impl Samplers {
             border_color:       None,
         });
 
-        let gradient_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        let reflecting_gradient_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("gradient_sampler"),
             address_mode_u:     wgpu::AddressMode::MirrorRepeat,
             address_mode_v:     wgpu::AddressMode::MirrorRepeat,
@@ -69,6 +74,21 @@ impl Samplers {
             border_color:       None,
         });
 
+        let repeating_gradient_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("gradient_sampler"),
+            address_mode_u:     wgpu::AddressMode::Repeat,
+            address_mode_v:     wgpu::AddressMode::Repeat,
+            address_mode_w:     wgpu::AddressMode::Repeat,
+            mag_filter:         wgpu::FilterMode::Linear,
+            min_filter:         wgpu::FilterMode::Linear,
+            mipmap_filter:      wgpu::FilterMode::Linear,
+            lod_min_clamp:      0.0,
+            lod_max_clamp:      0.0,
+            compare:            None,
+            anisotropy_clamp:   1,
+            border_color:       None,
+        });
+
         let non_repeating_gradient_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("gradient_sampler"),
             address_mode_u:     wgpu::AddressMode::ClampToEdge,
@@ -87,7 +107,8 @@ impl Samplers {
         Samplers {
             default_sampler:                Arc::new(default_sampler),
             non_repeating_sampler:          Arc::new(non_repeating_sampler),
-            gradient_sampler:               Arc::new(gradient_sampler),
+            reflecting_gradient_sampler:    Arc::new(reflecting_gradient_sampler),
+            repeating_gradient_sampler:     Arc::new(repeating_gradient_sampler),
             non_repeating_gradient_sampler: Arc::new(non_repeating_gradient_sampler),
         }
     }
@@ -100,11 +121,11 @@ impl Samplers {
         Arc::clone(&self.non_repeating_sampler)
     } 
 
-    #[inline] pub fn gradient_sampler(&self) -> Arc<wgpu::Sampler> {
-        Arc::clone(&self.gradient_sampler)
-    } 
-
-    #[inline] pub fn non_repeating_gradient_sampler(&self) -> Arc<wgpu::Sampler> {
-        Arc::clone(&self.non_repeating_gradient_sampler)
+    #[inline] pub fn gradient_sampler(&self, spread: GradientSpread) -> Arc<wgpu::Sampler> {
+        match spread {
+            GradientSpread::Pad     => Arc::clone(&self.non_repeating_gradient_sampler),
+            GradientSpread::Repeat  => Arc::clone(&self.repeating_gradient_sampler),
+            GradientSpread::Reflect => Arc::clone(&self.reflecting_gradient_sampler),
+        }
     } 
 }
@@ -4,7 +4,8 @@
 #[derive(Clone, Copy, PartialEq, Debug, Default)]
 #[repr(C, packed)]
 pub struct TextureSettings {
-    pub transform:  [[f32; 4]; 4],
-    pub alpha:      f32,
-    pub _padding:   [u32; 3]
+    pub transform:      [[f32; 4]; 4],
+    pub alpha:          f32,
+    pub focal_point:    [f32; 2],
+    pub _padding:       u32
 }
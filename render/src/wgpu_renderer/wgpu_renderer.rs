@@ -102,6 +102,9 @@ pub struct WgpuRenderer {
     /// The currently active blend mode
     active_blend_mode: Option<BlendMode>,
 
+    /// Set once we've warned that a shader blend mode couldn't be used with the current render target
+    warned_blend_fallback: bool,
+
     /// The texture samplers used by this renderer
     samplers: Samplers,
 
@@ -141,6 +144,7 @@ impl WgpuRenderer {
             active_render_target:   None,
             active_shader:          Some(ShaderType::Simple { clip_texture: None }),
             active_blend_mode:      Some(BlendMode::SourceOver),
+            warned_blend_fallback:  false,
             samplers:               Samplers::new(&*device),
 
             #[cfg(feature="profile")]
@@ -176,6 +180,7 @@ impl WgpuRenderer {
             active_render_target:   None,
             active_shader:          Some(ShaderType::Simple { clip_texture: None }),
             active_blend_mode:      Some(BlendMode::SourceOver),
+            warned_blend_fallback:  false,
             samplers:               Samplers::new(&*device),
 
             #[cfg(feature="profile")]
@@ -204,8 +209,12 @@ impl WgpuRenderer {
             let actual_format       = possible_formats.iter().filter(|format| !format.is_srgb()).next().copied();
             let actual_format       = actual_format.unwrap_or(possible_formats[0]);
 
+            // The shader blend modes need to be able to copy from the surface if possible
+            let possible_usages     = target_surface.get_capabilities(&*self.adapter).usages;
+            let usage               = if possible_usages.contains(wgpu::TextureUsages::COPY_SRC) { wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC } else { wgpu::TextureUsages::RENDER_ATTACHMENT };
+
             let surface_config      = wgpu::SurfaceConfiguration {
-                usage:          wgpu::TextureUsages::RENDER_ATTACHMENT,
+                usage:          usage,
                 format:         actual_format,
                 width:          width,
                 height:         height,
@@ -626,6 +635,18 @@ impl WgpuRenderer {
     /// Blits a frame buffer to the current render target
     ///
     fn draw_frame_buffer(&mut self, RenderTargetId(source_buffer): RenderTargetId, region: FrameBufferRegion, alpha: f64, state: &mut RendererState) {
+        // Blend modes that can't be performed by the fixed-function blender need a copy of the current target to read from
+        let shader_blend    = self.active_blend_mode.filter(|blend_mode| blend_mode.is_shader_blend());
+        let backdrop        = if shader_blend.is_some() { self.copy_backdrop(state) } else { None };
+
+        if let (Some(blend_mode), None) = (shader_blend, &backdrop) {
+            // The target can't be copied from (eg, a surface without COPY_SRC), so this is drawn using source over instead
+            if !self.warned_blend_fallback {
+                println!("Blend mode {:?} is not available for this render target: using SourceOver instead", blend_mode);
+                self.warned_blend_fallback = true;
+            }
+        }
+
         // Fetch the corresponding render target
         let render_target = if let Some(Some(render_target)) = self.render_targets.get(source_buffer) { 
             render_target
@@ -646,14 +667,24 @@ impl WgpuRenderer {
         let old_matrix              = state.active_matrix;
         let old_texture_settings    = state.texture_settings;
         let old_pipeline_config     = state.pipeline_configuration.clone();
+        let old_clip_texture        = state.clip_texture.clone();
 
         // Configure for rendering the frame buffer
         let texture_type = if samples.is_none() { InputTextureType::Sampler } else { InputTextureType::Multisampled };
 
         state.input_texture                                     = Some(texture);
         state.sampler                                           = Some(self.samplers.default_sampler());
-        state.pipeline_configuration.shader_module              = WgpuShader::Texture(StandardShaderVariant::NoClipping, texture_type, TexturePosition::Separate, AlphaBlendStep::Premultiply, ColorPostProcessingStep::NoPostProcessing);
-        state.pipeline_configuration.blending_mode              = Some(BlendMode::SourceOver);
+
+        if let (Some(blend_mode), Some((backdrop, backdrop_type))) = (shader_blend, backdrop) {
+            // The blend shader replaces the target pixels with the result of blending against the backdrop (which is bound in place of the clip mask)
+            state.clip_texture                                  = Some(backdrop);
+            state.pipeline_configuration.shader_module          = WgpuShader::Blend(texture_type, backdrop_type, blend_mode);
+            state.pipeline_configuration.blending_mode          = None;
+        } else {
+            state.pipeline_configuration.shader_module          = WgpuShader::Texture(StandardShaderVariant::NoClipping, texture_type, TexturePosition::Separate, AlphaBlendStep::Premultiply, ColorPostProcessingStep::NoPostProcessing);
+            state.pipeline_configuration.blending_mode          = Some(BlendMode::SourceOver);
+        }
+
         state.pipeline_configuration.source_is_premultiplied    = true;
         state.pipeline_config_changed                           = true;
         state.pipeline_bindings_changed                         = true;
@@ -732,6 +763,7 @@ impl WgpuRenderer {
         // Restore the render state
         state.input_texture             = old_texture;
         state.sampler                   = old_sampler;
+        state.clip_texture              = old_clip_texture;
         state.active_matrix             = old_matrix;
         state.texture_settings          = old_texture_settings;
         state.pipeline_configuration    = old_pipeline_config;
@@ -739,6 +771,53 @@ impl WgpuRenderer {
         state.pipeline_bindings_changed = true;
     }
 
+    ///
+    /// Copies the current render target into a new texture, so it can be used as the backdrop for the blend shaders
+    ///
+    /// Returns None if the current render target can't be copied from (eg, a surface that doesn't support COPY_SRC)
+    ///
+    fn copy_backdrop(&self, state: &mut RendererState) -> Option<(Arc<wgpu::Texture>, BackdropTextureType)> {
+        // Find the texture that's currently being rendered to
+        let render_target_texture;
+        let target: &wgpu::Texture = if let Some(RenderTargetId(render_id)) = self.active_render_target {
+            render_target_texture = self.render_targets.get(render_id)?.as_ref()?.texture();
+            &*render_target_texture
+        } else if let Some(surface_texture) = &self.target_surface_texture {
+            &surface_texture.texture
+        } else {
+            &**self.target_texture.as_ref()?
+        };
+
+        if !target.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            return None;
+        }
+
+        // Finish the current render pass so the target is up to date (making sure that any pending clear is performed)
+        if state.render_pass.is_empty() && state.render_pass_resources.clear.is_some() {
+            state.render_pass.push(Box::new(|_resources, _render_pass| { }));
+        }
+        state.run_render_pass();
+
+        // Create a texture with the same format as the target
+        let size        = target.size();
+        let backdrop    = self.device.create_texture(&wgpu::TextureDescriptor {
+            label:              Some("copy_backdrop"),
+            size:               size,
+            mip_level_count:    1,
+            sample_count:       target.sample_count(),
+            dimension:          wgpu::TextureDimension::D2,
+            format:             target.format(),
+            usage:              wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats:       &[],
+        });
+
+        // Copy the target into it
+        state.encoder.copy_texture_to_texture(target.as_image_copy(), backdrop.as_image_copy(), size);
+
+        let backdrop_type = if target.sample_count() > 1 { BackdropTextureType::Multisampled } else { BackdropTextureType::Standard };
+        Some((Arc::new(backdrop), backdrop_type))
+    }
+
     ///
     /// Displays the current frame buffer to the screen
     ///
@@ -1308,7 +1387,7 @@ impl WgpuRenderer {
                 }
             }
 
-            LinearGradient { texture, texture_transform, repeat, alpha, clip_texture } => {
+            LinearGradient { texture, texture_transform, spread, alpha, clip_texture } => {
                 let TextureId(texture_id)   = texture;
                 let texture                 = if let Some(Some(texture)) = self.textures.get(texture_id) {
                     Some(texture)
@@ -1339,14 +1418,94 @@ impl WgpuRenderer {
                 state.texture_settings  = TextureSettings { transform: texture_transform.0, alpha: alpha as _, ..Default::default() };
                 state.clip_texture      = clip_texture;
                 state.input_texture     = texture.map(|t| Arc::clone(&t.texture));
-                if repeat {
-                    state.sampler       = Some(self.samplers.gradient_sampler());
+                state.sampler           = Some(self.samplers.gradient_sampler(spread));
+
+                if let Some(texture) = &texture {
+                    state.pipeline_configuration.shader_module              = WgpuShader::LinearGradient(variant, TexturePosition::InputPosition, alpha_blend, post_processing);
+                    state.pipeline_configuration.source_is_premultiplied    = texture.is_premultiplied;
+                } else {
+                    state.pipeline_configuration.shader_module              = WgpuShader::Simple(variant, post_processing);
+                    state.pipeline_configuration.source_is_premultiplied    = false;
+                }
+            }
+
+            RadialGradient { texture, texture_transform, focal_point, spread, alpha, clip_texture } => {
+                let TextureId(texture_id)   = texture;
+                let texture                 = if let Some(Some(texture)) = self.textures.get(texture_id) {
+                    Some(texture)
+                } else {
+                    None
+                };
+
+                // Work out which clip texture to use (and the corresponding shader variant)
+                let clip_texture    = if let Some(TextureId(clip_texture)) = clip_texture {
+                    if let Some(Some(texture)) = self.textures.get(clip_texture) {
+                        Some(Arc::clone(&texture.texture))
+                    } else {
+                        None
+                    }
                 } else {
-                    state.sampler       = Some(self.samplers.non_repeating_gradient_sampler());    
+                    None
+                };
+                let variant         = if clip_texture.is_some() { StandardShaderVariant::ClippingMask } else { StandardShaderVariant::NoClipping };
+
+                // Alpha blend step depends on if the texture is pre-multiplied
+                let alpha_blend = if let Some(true) = texture.map(|t| t.is_premultiplied) { 
+                    AlphaBlendStep::Premultiply
+                } else {
+                    AlphaBlendStep::NoPremultiply
+                };
+
+                // Set up the state
+                state.texture_settings  = TextureSettings { transform: texture_transform.0, alpha: alpha as _, focal_point: [focal_point.0, focal_point.1], ..Default::default() };
+                state.clip_texture      = clip_texture;
+                state.input_texture     = texture.map(|t| Arc::clone(&t.texture));
+                state.sampler           = Some(self.samplers.gradient_sampler(spread));
+
+                if let Some(texture) = &texture {
+                    state.pipeline_configuration.shader_module              = WgpuShader::RadialGradient(variant, TexturePosition::InputPosition, alpha_blend, post_processing);
+                    state.pipeline_configuration.source_is_premultiplied    = texture.is_premultiplied;
+                } else {
+                    state.pipeline_configuration.shader_module              = WgpuShader::Simple(variant, post_processing);
+                    state.pipeline_configuration.source_is_premultiplied    = false;
                 }
+            }
+
+            ConicGradient { texture, texture_transform, spread, alpha, clip_texture } => {
+                let TextureId(texture_id)   = texture;
+                let texture                 = if let Some(Some(texture)) = self.textures.get(texture_id) {
+                    Some(texture)
+                } else {
+                    None
+                };
+
+                // Work out which clip texture to use (and the corresponding shader variant)
+                let clip_texture    = if let Some(TextureId(clip_texture)) = clip_texture {
+                    if let Some(Some(texture)) = self.textures.get(clip_texture) {
+                        Some(Arc::clone(&texture.texture))
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                };
+                let variant         = if clip_texture.is_some() { StandardShaderVariant::ClippingMask } else { StandardShaderVariant::NoClipping };
+
+                // Alpha blend step depends on if the texture is pre-multiplied
+                let alpha_blend = if let Some(true) = texture.map(|t| t.is_premultiplied) { 
+                    AlphaBlendStep::Premultiply
+                } else {
+                    AlphaBlendStep::NoPremultiply
+                };
+
+                // Set up the state
+                state.texture_settings  = TextureSettings { transform: texture_transform.0, alpha: alpha as _, ..Default::default() };
+                state.clip_texture      = clip_texture;
+                state.input_texture     = texture.map(|t| Arc::clone(&t.texture));
+                state.sampler           = Some(self.samplers.gradient_sampler(spread));
 
                 if let Some(texture) = &texture {
-                    state.pipeline_configuration.shader_module              = WgpuShader::LinearGradient(variant, TexturePosition::InputPosition, alpha_blend, post_processing);
+                    state.pipeline_configuration.shader_module              = WgpuShader::ConicGradient(variant, TexturePosition::InputPosition, alpha_blend, post_processing);
                     state.pipeline_configuration.source_is_premultiplied    = texture.is_premultiplied;
                 } else {
                     state.pipeline_configuration.shader_module              = WgpuShader::Simple(variant, post_processing);
@@ -1,6 +1,8 @@
 use super::texture::*;
 use super::shader_cache::*;
 
+use crate::action::{BlendMode};
+
 use wgpu;
 
 use std::sync::*;
@@ -81,6 +83,18 @@ pub enum InputTextureType {
     Multisampled,
 }
 
+///
+/// The type of texture used as the backdrop for a blend shader (this is a copy of the render target, so has the same number of samples)
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BackdropTextureType {
+    /// A texture with a single sample per pixel
+    Standard,
+
+    /// A multi-sampled texture that needs to be resolved
+    Multisampled,
+}
+
 ///
 /// Size of a fixed-size shader kernel
 ///
@@ -139,6 +153,15 @@ pub enum WgpuShader {
     /// Renders a linear gradient
     LinearGradient(StandardShaderVariant, TexturePosition, AlphaBlendStep, ColorPostProcessingStep),
 
+    /// Renders a radial gradient
+    RadialGradient(StandardShaderVariant, TexturePosition, AlphaBlendStep, ColorPostProcessingStep),
+
+    /// Renders a conic (sweep) gradient
+    ConicGradient(StandardShaderVariant, TexturePosition, AlphaBlendStep, ColorPostProcessingStep),
+
+    /// Blends a texture against a backdrop texture using one of the blend modes that are implemented in a shader
+    Blend(InputTextureType, BackdropTextureType, BlendMode),
+
     /// Runs a texture-to-texture filter
     Filter(FilterShader),
 }
@@ -198,6 +221,15 @@ impl TexturePosition {
     }
 }
 
+impl BackdropTextureType {
+    fn shader_function(&self) -> &'static str {
+        match self {
+            BackdropTextureType::Standard       => include_str!("../../shaders/texture/backdrop_texture.wgsl"),
+            BackdropTextureType::Multisampled   => include_str!("../../shaders/texture/backdrop_multisample.wgsl"),
+        }
+    }
+}
+
 impl FilterSourceFormat {
     pub (crate) fn from_texture(texture: &WgpuTexture) -> FilterSourceFormat {
         if texture.is_premultiplied {
@@ -257,8 +289,9 @@ impl WgpuShaderLoader for WgpuShader {
                 let base_module = include_str!("../../shaders/texture/gradient.wgsl");
 
                 // Amend the base module with the appropriate variant and colour post-processing functions
-                let base_module = format!("{}\n\n{}\n\n{}\n\n{}\n\n{}", 
+                let base_module = format!("{}\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}", 
                     variant.shader_function(), 
+                    include_str!("../../shaders/texture/gradient_linear.wgsl"), 
                     texture_position.shader_function(), 
                     alpha_blend.shader_function(), 
                     color_post_processing.shader_function(),
@@ -273,6 +306,75 @@ impl WgpuShaderLoader for WgpuShader {
                 (Arc::new(shader_module), "gradient_vertex_shader".to_string(), "gradient_fragment_shader".to_string())
             },
 
+            WgpuShader::RadialGradient(variant, texture_position, alpha_blend, color_post_processing) => {
+                // The base module contains the shader program in terms of the variant and post-procesing functions
+                let base_module = include_str!("../../shaders/texture/gradient.wgsl");
+
+                // Amend the base module with the appropriate variant and colour post-processing functions
+                let base_module = format!("{}\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}", 
+                    variant.shader_function(), 
+                    include_str!("../../shaders/texture/gradient_radial.wgsl"), 
+                    texture_position.shader_function(), 
+                    alpha_blend.shader_function(), 
+                    color_post_processing.shader_function(),
+                    base_module);
+
+                // Load the shader
+                let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label:  Some("WgpuShader::RadialGradient"),
+                    source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(&base_module)),
+                });
+
+                (Arc::new(shader_module), "gradient_vertex_shader".to_string(), "gradient_fragment_shader".to_string())
+            },
+
+            WgpuShader::ConicGradient(variant, texture_position, alpha_blend, color_post_processing) => {
+                // The base module contains the shader program in terms of the variant and post-procesing functions
+                let base_module = include_str!("../../shaders/texture/gradient.wgsl");
+
+                // Amend the base module with the appropriate variant and colour post-processing functions
+                let base_module = format!("{}\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}", 
+                    variant.shader_function(), 
+                    include_str!("../../shaders/texture/gradient_conic.wgsl"), 
+                    texture_position.shader_function(), 
+                    alpha_blend.shader_function(), 
+                    color_post_processing.shader_function(),
+                    base_module);
+
+                // Load the shader
+                let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label:  Some("WgpuShader::ConicGradient"),
+                    source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(&base_module)),
+                });
+
+                (Arc::new(shader_module), "gradient_vertex_shader".to_string(), "gradient_fragment_shader".to_string())
+            },
+
+            WgpuShader::Blend(input_type, backdrop_type, blend_mode) => {
+                // The blend shader is the texture shader, with a 'clip' function that blends against the backdrop
+                let base_module = include_str!("../../shaders/texture/texture.wgsl");
+                let blend_index = blend_mode.shader_blend_index().unwrap_or(0);
+
+                // Amend the base module with the blend functions and the texture functions
+                let base_module = format!("const blend_mode: u32 = {}u;\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}", 
+                    blend_index,
+                    backdrop_type.shader_function(),
+                    include_str!("../../shaders/texture/blend.wgsl"),
+                    TexturePosition::Separate.shader_function(), 
+                    AlphaBlendStep::Premultiply.shader_function(), 
+                    input_type.shader_function(), 
+                    ColorPostProcessingStep::NoPostProcessing.shader_function(),
+                    base_module);
+
+                // Load the shader
+                let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label:  Some("WgpuShader::Blend"),
+                    source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(&base_module)),
+                });
+
+                (Arc::new(shader_module), "texture_vertex_shader".to_string(), "texture_fragment_shader".to_string())
+            },
+
             WgpuShader::Filter(FilterShader::AlphaBlend(source_format)) => {
                 // The base module contains the shader program in terms of the variant and post-procesing functions
                 let base_module = include_str!("../../shaders/filters/alpha_blend.wgsl");
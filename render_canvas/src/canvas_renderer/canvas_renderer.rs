@@ -285,7 +285,11 @@ impl CanvasRenderer {
                     Path(Move(x, y))                            => path_state.tes_move(x, y),
                     Path(Line(x, y))                            => path_state.tes_line(x, y),
                     Path(BezierCurve((cp1, cp2), p))            => path_state.tes_bezier_curve(cp1, cp2, p),
+                    Path(QuadraticCurve(cp, p))                 => path_state.tes_quadratic_curve(cp, p),
+                    Path(Arc(center, radii, rot, start, sweep)) => path_state.tes_arc(center, radii, rot, start, sweep),
                     Path(ClosePath)                             => path_state.tes_close_path(),
+                    StorePath                                   => path_state.tes_store_path(),
+                    CombinePath(operation)                      => path_state.tes_combine_path(operation, self.tes_current_winding_rule()),
 
                     Fill                                        => self.tes_fill(&mut path_state, &mut job_publisher, &mut pending_jobs).await,
                     Stroke                                      => self.tes_stroke(&mut path_state, &mut job_publisher, &mut pending_jobs).await,
@@ -293,6 +297,7 @@ impl CanvasRenderer {
                     LineWidth(width)                            => self.tes_line_width(width),
                     LineWidthPixels(pixel_width)                => self.tes_line_width_pixels(pixel_width),
                     LineJoin(join_type)                         => self.tes_line_join(join_type),
+                    MiterLimit(limit)                           => self.tes_miter_limit(limit),
                     LineCap(cap_type)                           => self.tes_line_cap(cap_type),
                     WindingRule(winding_rule)                   => self.tes_winding_rule(winding_rule),
                     NewDashPattern                              => self.tes_new_dash_pattern(),
@@ -301,8 +306,12 @@ impl CanvasRenderer {
                     FillColor(color)                            => self.tes_fill_color(color),
                     FillTexture(texture_id, min, max)           => self.tes_fill_texture(self.current_namespace, texture_id, min, max),
                     FillGradient(gradient_id, min, max)         => self.tes_fill_gradient(self.current_namespace, gradient_id, min, max),
+                    FillRadialGradient(id, center, r, focus)    => self.tes_fill_radial_gradient(self.current_namespace, id, center, r, focus),
+                    FillConicGradient(id, center, angle)        => self.tes_fill_conic_gradient(self.current_namespace, id, center, angle),
                     FillTransform(transform)                    => self.tes_fill_transform(transform),
                     StrokeColor(color)                          => self.tes_stroke_color(color),
+                    StrokeTexture(texture_id, min, max)         => self.tes_stroke_texture(self.current_namespace, texture_id, min, max),
+                    StrokeGradient(gradient_id, min, max)       => self.tes_stroke_gradient(self.current_namespace, gradient_id, min, max),
                     BlendMode(blend_mode)                       => self.tes_blend_mode(blend_mode),
 
                     IdentityTransform                           => self.tes_identity_transform(), 
@@ -341,6 +350,7 @@ impl CanvasRenderer {
                     Font(font_id, font_op)                      => self.tes_font(font_id, font_op),
                     DrawText(font_id, text, x, y)               => self.tes_draw_text(font_id, text, x, y),
                     BeginLineLayout(x, y, alignment)            => self.tes_begin_line_layout(x, y, alignment),
+                    BeginParagraphLayout(x, y, style)           => self.tes_begin_paragraph_layout(x, y, style),
                     DrawLaidOutText                             => self.tes_draw_laid_out_text(),
                 }
             }
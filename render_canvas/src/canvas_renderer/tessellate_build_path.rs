@@ -1,5 +1,7 @@
 use crate::fill_state::*;
 
+use flo_canvas as canvas;
+
 use lyon::path;
 use lyon::math::{point};
 
@@ -13,6 +15,9 @@ pub (super) struct PathState {
 
     pub (super) fill_state:     FillState,
     pub (super) dash_pattern:   Vec<f32>,
+
+    /// The path saved by the last `StorePath` instruction
+    pub (super) stored_path:    Vec<canvas::PathOp>,
 }
 
 impl Default for PathState {
@@ -23,6 +28,7 @@ impl Default for PathState {
             path_builder:   None,
             fill_state:     FillState::None,
             dash_pattern:   vec![],
+            stored_path:    vec![],
         }
     }
 }
@@ -82,6 +88,31 @@ impl PathState {
         }
     }
 
+    /// Quadratic curve to point
+    #[inline]
+    pub (super) fn tes_quadratic_curve(&mut self, (cpx, cpy): (f32, f32), (px, py): (f32, f32)) {
+        if self.in_subpath {
+            self.path_builder.get_or_insert_with(|| path::Path::builder())
+                .quadratic_bezier_to(point(cpx, cpy), point(px, py));
+        } else {
+            self.path_builder.get_or_insert_with(|| path::Path::builder())
+                .begin(point(px, py));
+            self.in_subpath = true;
+        }
+    }
+
+    /// Elliptical arc (converted to bezier curves)
+    pub (super) fn tes_arc(&mut self, center: (f32, f32), radii: (f32, f32), rotation: f32, start_angle: f32, sweep_angle: f32) {
+        // Line to the start of the arc, or begin a new subpath there
+        let (x, y) = canvas::arc_point(center, radii, rotation, start_angle);
+        self.tes_line(x, y);
+
+        // Add the curves that make up the arc
+        for (cp1, cp2, end_point) in canvas::arc_to_bezier_curves(center, radii, rotation, start_angle, sweep_angle) {
+            self.tes_bezier_curve(cp1, cp2, end_point);
+        }
+    }
+
     /// Closes the current path
     #[inline]
     pub (super) fn tes_close_path(&mut self) {
@@ -89,4 +120,64 @@ impl PathState {
             .end(true);
         self.in_subpath = false;
     }
+
+    /// Returns the operations that make up the current path
+    fn path_ops(&mut self) -> Vec<canvas::PathOp> {
+        use self::path::Event;
+
+        self.build();
+
+        let mut path_ops = vec![];
+
+        if let Some(current_path) = &self.current_path {
+            for event in current_path.iter() {
+                match event {
+                    Event::Begin { at }                     => path_ops.push(canvas::PathOp::Move(at.x, at.y)),
+                    Event::Line { to, .. }                  => path_ops.push(canvas::PathOp::Line(to.x, to.y)),
+                    Event::Quadratic { ctrl, to, .. }       => path_ops.push(canvas::PathOp::QuadraticCurve((ctrl.x, ctrl.y), (to.x, to.y))),
+                    Event::Cubic { ctrl1, ctrl2, to, .. }   => path_ops.push(canvas::PathOp::BezierCurve(((ctrl1.x, ctrl1.y), (ctrl2.x, ctrl2.y)), (to.x, to.y))),
+                    Event::End { close: true, .. }          => path_ops.push(canvas::PathOp::ClosePath),
+                    Event::End { close: false, .. }         => { }
+                }
+            }
+        }
+
+        path_ops
+    }
+
+    /// Replaces the current path with a new set of path operations
+    fn replace_path(&mut self, path_ops: &[canvas::PathOp]) {
+        use canvas::PathOp::*;
+
+        self.tes_new_path();
+
+        for path_op in path_ops.iter() {
+            match *path_op {
+                NewPath                                 => self.tes_new_path(),
+                Move(x, y)                              => self.tes_move(x, y),
+                Line(x, y)                              => self.tes_line(x, y),
+                BezierCurve((cp1, cp2), p)              => self.tes_bezier_curve(cp1, cp2, p),
+                QuadraticCurve(cp, p)                   => self.tes_quadratic_curve(cp, p),
+                Arc(center, radii, rot, start, sweep)   => self.tes_arc(center, radii, rot, start, sweep),
+                ClosePath                               => self.tes_close_path(),
+            }
+        }
+    }
+
+    /// Saves a copy of the current path
+    pub (super) fn tes_store_path(&mut self) {
+        let path_ops = self.path_ops();
+
+        // Building the path finishes it, so recreate it in case more operations are added
+        self.replace_path(&path_ops);
+        self.stored_path = path_ops;
+    }
+
+    /// Replaces the current path by combining it with the stored path
+    pub (super) fn tes_combine_path(&mut self, operation: canvas::PathArithmetic, winding_rule: canvas::WindingRule) {
+        let path_ops        = self.path_ops();
+        let combined_path   = canvas::combine_paths(operation, winding_rule, &self.stored_path, &path_ops);
+
+        self.replace_path(&combined_path);
+    }
 }
@@ -18,6 +18,12 @@ impl CanvasRenderer {
     #[inline]
     pub (super) fn tes_begin_line_layout(&mut self, _x: f32, _y: f32, _aligment: canvas::TextAlignment) { }
 
+    ///
+    /// Begins laying out a paragraph of text: the coordinates specify the top-left corner of the paragraph
+    ///
+    #[inline]
+    pub (super) fn tes_begin_paragraph_layout(&mut self, _x: f32, _y: f32, _style: canvas::ParagraphStyle) { }
+
     ///
     /// Renders the text in the current layout
     ///
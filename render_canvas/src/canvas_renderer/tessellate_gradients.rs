@@ -15,6 +15,7 @@ impl CanvasRenderer {
         match op {
             Create(initial_colour)      => self.tes_gradient_create(namespace_id, gradient_id, initial_colour),
             AddStop(pos, stop_colour)   => self.tes_gradient_add_stop(namespace_id, gradient_id, pos, stop_colour),
+            Spread(spread)              => self.tes_gradient_spread(namespace_id, gradient_id, spread),
         }
     }
 
@@ -51,4 +52,21 @@ impl CanvasRenderer {
             }
         });
     }
+
+    ///
+    /// Sets how an existing gradient is spread beyond its first and last stops
+    ///
+    pub (super) fn tes_gradient_spread(&mut self, namespace_id: usize, gradient_id: canvas::GradientId, spread: canvas::GradientSpread) {
+        self.core.sync(move |core| {
+            use canvas::GradientOp::Spread;
+
+            match core.canvas_gradients.get_mut(&(namespace_id, gradient_id)) {
+                // The spread doesn't change the texture, so it can be updated in place even if the gradient is ready to render
+                Some(RenderGradient::Defined(defn))     |
+                Some(RenderGradient::Ready(_, defn))    => defn.push(Spread(spread)),
+
+                None => { }
+            }
+        });
+    }
 }
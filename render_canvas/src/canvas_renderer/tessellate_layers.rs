@@ -30,6 +30,7 @@ impl CanvasRenderer {
                 fill_color:         FillState::Color(render::Rgba8([0, 0, 0, 255])),
                 winding_rule:       FillRule::NonZero,
                 stroke_settings:    StrokeSettings::new(),
+                stroke_fill:        FillState::None,
                 current_matrix:     canvas::Transform2D::identity(),
                 sprite_matrix:      canvas::Transform2D::identity(),
                 scale_factor:       0.002,                              // Canvas height of approximately 768 (1.0 will tessellate at far too fine a detail for these coordinate schemes, so we default to 0.002 as a safety net)
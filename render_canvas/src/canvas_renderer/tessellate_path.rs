@@ -1,5 +1,7 @@
 use crate::fill_state::*;
+use crate::layer_handle::*;
 use crate::render_entity::*;
+use crate::renderer_core::*;
 use crate::renderer_worker::*;
 
 use super::canvas_renderer::*;
@@ -14,6 +16,53 @@ use std::mem;
 const BATCH_SIZE: usize = 20;
 
 impl CanvasRenderer {
+    ///
+    /// Adds the render entity that selects the shader for a fill state to a layer
+    ///
+    fn select_fill_state(core: &mut RenderCore, layer_id: LayerHandle, fill_state: &FillState) {
+        match *fill_state {
+            FillState::None | FillState::Color(_) => { 
+                core.layer(layer_id).render_order.push(RenderEntity::SetFlatColor);
+            }
+
+            FillState::Texture(render_texture, _canvas_texture, matrix, repeat, alpha) => {
+                // Increase the usage count for this texture
+                core.used_textures.get_mut(&render_texture)
+                    .map(|usage_count| *usage_count += 1);
+
+                // Add to the layer
+                core.layer(layer_id).render_order.push(RenderEntity::SetFillTexture(render_texture, matrix, repeat, alpha));
+            }
+
+            FillState::LinearGradient(gradient_texture, _canvas_texture, matrix, spread, alpha) => {
+                // Increase the usage count for the texture
+                core.used_textures.get_mut(&gradient_texture)
+                    .map(|usage_count| *usage_count += 1);
+
+                // Add to the layer
+                core.layer(layer_id).render_order.push(RenderEntity::SetFillGradient(gradient_texture, matrix, spread, alpha));
+            }
+
+            FillState::RadialGradient(gradient_texture, _canvas_texture, matrix, focal_point, spread, alpha) => {
+                // Increase the usage count for the texture
+                core.used_textures.get_mut(&gradient_texture)
+                    .map(|usage_count| *usage_count += 1);
+
+                // Add to the layer
+                core.layer(layer_id).render_order.push(RenderEntity::SetFillRadialGradient(gradient_texture, matrix, focal_point, spread, alpha));
+            }
+
+            FillState::ConicGradient(gradient_texture, _canvas_texture, matrix, spread, alpha) => {
+                // Increase the usage count for the texture
+                core.used_textures.get_mut(&gradient_texture)
+                    .map(|usage_count| *usage_count += 1);
+
+                // Add to the layer
+                core.layer(layer_id).render_order.push(RenderEntity::SetFillConicGradient(gradient_texture, matrix, spread, alpha));
+            }
+        }
+    }
+
     ///
     /// Fill the current path
     ///
@@ -45,29 +94,8 @@ impl CanvasRenderer {
                 // If the shader state has changed, generate the operations needed to use that shader state
                 if path_state.fill_state != layer.state.fill_color {
                     // Update the active fill state to match that of the layer
-                    match layer.state.fill_color {
-                        FillState::None | FillState::Color(_) => { 
-                            layer.render_order.push(RenderEntity::SetFlatColor);
-                        }
-
-                        FillState::Texture(render_texture, _canvas_texture, matrix, repeat, alpha) => {
-                            // Increase the usage count for this texture
-                            core.used_textures.get_mut(&render_texture)
-                                .map(|usage_count| *usage_count += 1);
-
-                            // Add to the layer
-                            core.layer(layer_id).render_order.push(RenderEntity::SetFillTexture(render_texture, matrix, repeat, alpha));
-                        }
-
-                        FillState::LinearGradient(gradient_texture, _canvas_texture, matrix, repeat, alpha) => {
-                            // Increase the usage count for the texture
-                            core.used_textures.get_mut(&gradient_texture)
-                                .map(|usage_count| *usage_count += 1);
-
-                            // Add to the layer
-                            core.layer(layer_id).render_order.push(RenderEntity::SetFillGradient(gradient_texture, matrix, repeat, alpha));
-                        }
-                    }
+                    let fill_state = layer.state.fill_color.clone();
+                    Self::select_fill_state(core, layer_id, &fill_state);
 
                     path_state.dash_pattern = vec![];
                     path_state.fill_state   = core.layer(layer_id).state.fill_color.clone();
@@ -135,27 +163,56 @@ impl CanvasRenderer {
                 // Update the transformation matrix
                 layer.update_transform(active_transform);
 
-                // Reset the fill state to 'flat colour' if needed
-                match fill_state {
-                    FillState::None     | 
-                    FillState::Color(_) => { }
-                    _                   => { layer.render_order.push(RenderEntity::SetFlatColor) }
-                }
+                // Dash patterns use their own shader, which only supports flat colours: strokes with a texture or gradient are split into dashes when they're tessellated instead
+                let stroke_fill     = layer.state.stroke_fill.clone();
+                let split_dashes    = match stroke_fill {
+                    FillState::None     |
+                    FillState::Color(_) => false,
+                    _                   => !layer.state.stroke_settings.dash_pattern.is_empty(),
+                };
+
+                match stroke_fill {
+                    FillState::None     |
+                    FillState::Color(_) => {
+                        // Reset the fill state to 'flat colour' if needed
+                        match fill_state {
+                            FillState::None     | 
+                            FillState::Color(_) => { }
+                            _                   => { layer.render_order.push(RenderEntity::SetFlatColor) }
+                        }
 
-                *fill_state = FillState::None;
+                        *fill_state = FillState::None;
 
-                // Apply the dash pattern, if it's different
-                if *dash_pattern != layer.state.stroke_settings.dash_pattern {
-                    layer.render_order.push(RenderEntity::SetDashPattern(layer.state.stroke_settings.dash_pattern.clone()));
-                    *dash_pattern = layer.state.stroke_settings.dash_pattern.clone();
+                        // Apply the dash pattern, if it's different
+                        if *dash_pattern != layer.state.stroke_settings.dash_pattern {
+                            layer.render_order.push(RenderEntity::SetDashPattern(layer.state.stroke_settings.dash_pattern.clone()));
+                            *dash_pattern = layer.state.stroke_settings.dash_pattern.clone();
+                        }
+                    }
+
+                    stroke_fill         => {
+                        // Use the texture or gradient shader for the stroke
+                        if *fill_state != stroke_fill {
+                            Self::select_fill_state(core, layer_id, &stroke_fill);
+                            *fill_state = stroke_fill;
+                        }
+
+                        *dash_pattern = vec![];
+                    }
                 }
 
                 // Create the render entity in the tessellating state
+                let layer               = core.layer(layer_id);
                 let scale_factor        = layer.state.tolerance_scale_factor(viewport_height);
                 let mut stroke_options  = layer.state.stroke_settings.clone();
                 let entity_index        = layer.render_order.len();
                 let transform           = layer.state.current_matrix;
 
+                // The tessellator only applies the dash pattern when the shader can't
+                if !split_dashes {
+                    stroke_options.dash_pattern = vec![];
+                }
+
                 // When drawing to the erase layer (DesintationOut blend mode), all colour components are alpha components
                 let color                   = stroke_options.stroke_color;
                 stroke_options.stroke_color = if layer.state.blend_mode == canvas::BlendMode::DestinationOut { render::Rgba8([color.0[3], color.0[3], color.0[3], color.0[3]]) } else { color };
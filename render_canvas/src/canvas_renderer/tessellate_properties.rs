@@ -57,6 +57,12 @@ impl CanvasRenderer {
         self.core.sync(|core| core.layer(self.current_layer).state.stroke_settings.join = join_type);
     }
 
+    /// The miter limit for mitered line joins
+    #[inline]
+    pub (super) fn tes_miter_limit(&mut self, limit: f32) {
+        self.core.sync(|core| core.layer(self.current_layer).state.stroke_settings.miter_limit = limit);
+    }
+
     /// The cap to use on lines
     #[inline]
     pub (super) fn tes_line_cap(&mut self, cap_type: canvas::LineCap) {
@@ -75,6 +81,15 @@ impl CanvasRenderer {
         
     }
 
+    /// The winding rule that's currently used when filling areas
+    #[inline]
+    pub (super) fn tes_current_winding_rule(&mut self) -> canvas::WindingRule {
+        match self.core.sync(|core| core.layer(self.current_layer).state.winding_rule) {
+            FillRule::EvenOdd   => canvas::WindingRule::EvenOdd,
+            FillRule::NonZero   => canvas::WindingRule::NonZero,
+        }
+    }
+
     /// Resets the dash pattern to empty (which is a solid line)
     #[inline]
     pub (super) fn tes_new_dash_pattern(&mut self) {
@@ -123,9 +138,41 @@ impl CanvasRenderer {
             let render_gradient  = core.gradient_for_rendering(namespace_id, gradient_id);
             if let Some(render_gradient) = render_gradient {
                 // Choose this gradient
+                let spread              = core.canvas_gradients.get(&(namespace_id, gradient_id)).map(|gradient| gradient.spread()).unwrap_or(render::GradientSpread::Pad);
+                let layer               = core.layer(self.current_layer);
+
+                layer.state.fill_color  = FillState::linear_gradient_fill(render_gradient, gradient_id, x1, y1, x2, y2, spread);
+            }
+        });
+    }
+
+    /// Set a radial fill gradient
+    #[inline]
+    pub (super) fn tes_fill_radial_gradient(&mut self, namespace_id: usize, gradient_id: canvas::GradientId, center: (f32, f32), radius: f32, focus: Option<(f32, f32)>) {
+        self.core.sync(|core| {
+            // Check that the texture is ready for rendering (this also commits it at the point it's selected)
+            let render_gradient  = core.gradient_for_rendering(namespace_id, gradient_id);
+            if let Some(render_gradient) = render_gradient {
+                // Choose this gradient
+                let spread              = core.canvas_gradients.get(&(namespace_id, gradient_id)).map(|gradient| gradient.spread()).unwrap_or(render::GradientSpread::Pad);
                 let layer               = core.layer(self.current_layer);
 
-                layer.state.fill_color  = FillState::linear_gradient_fill(render_gradient, gradient_id, x1, y1, x2, y2);
+                layer.state.fill_color  = FillState::radial_gradient_fill(render_gradient, gradient_id, center, radius, focus, spread);
+            }
+        });
+    }
+
+    /// Set a conic fill gradient
+    #[inline]
+    pub (super) fn tes_fill_conic_gradient(&mut self, namespace_id: usize, gradient_id: canvas::GradientId, center: (f32, f32), start_angle: f32) {
+        self.core.sync(|core| {
+            // Check that the texture is ready for rendering (this also commits it at the point it's selected)
+            let render_gradient  = core.gradient_for_rendering(namespace_id, gradient_id);
+            if let Some(render_gradient) = render_gradient {
+                // Choose this gradient
+                let layer               = core.layer(self.current_layer);
+
+                layer.state.fill_color  = FillState::conic_gradient_fill(render_gradient, gradient_id, center, start_angle);
             }
         });
     }
@@ -144,7 +191,44 @@ impl CanvasRenderer {
     // Set the line color
     #[inline]
     pub (super) fn tes_stroke_color(&mut self, color: canvas::Color) {
-        self.core.sync(|core| core.layer(self.current_layer).state.stroke_settings.stroke_color = Self::render_color(color));
+        self.core.sync(|core| {
+            let layer = core.layer(self.current_layer);
+
+            layer.state.stroke_settings.stroke_color    = Self::render_color(color);
+            layer.state.stroke_fill                     = FillState::None;
+        });
+    }
+
+    /// Set a stroke texture
+    #[inline]
+    pub (super) fn tes_stroke_texture(&mut self, namespace_id: usize, texture_id: canvas::TextureId, (x1, y1): (f32, f32), (x2, y2): (f32, f32)) {
+        self.core.sync(|core| {
+            // Check that the texture is ready for rendering (this also commits it at the point it's selected)
+            let render_texture  = core.texture_for_rendering(namespace_id, texture_id);
+            if let Some(render_texture) = render_texture {
+                // Choose this texture
+                let alpha               = core.texture_alpha.get(&(namespace_id, texture_id)).cloned().unwrap_or(1.0);
+                let layer               = core.layer(self.current_layer);
+
+                layer.state.stroke_fill = FillState::texture_fill(render_texture, texture_id, x1, y1, x2, y2, alpha)
+            }
+        });
+    }
+
+    /// Set a stroke gradient
+    #[inline]
+    pub (super) fn tes_stroke_gradient(&mut self, namespace_id: usize, gradient_id: canvas::GradientId, (x1, y1): (f32, f32), (x2, y2): (f32, f32)) {
+        self.core.sync(|core| {
+            // Check that the texture is ready for rendering (this also commits it at the point it's selected)
+            let render_gradient  = core.gradient_for_rendering(namespace_id, gradient_id);
+            if let Some(render_gradient) = render_gradient {
+                // Choose this gradient
+                let spread              = core.canvas_gradients.get(&(namespace_id, gradient_id)).map(|gradient| gradient.spread()).unwrap_or(render::GradientSpread::Pad);
+                let layer               = core.layer(self.current_layer);
+
+                layer.state.stroke_fill = FillState::linear_gradient_fill(render_gradient, gradient_id, x1, y1, x2, y2, spread);
+            }
+        });
     }
 
     /// Set how future renderings are blended with one another
@@ -167,9 +251,20 @@ impl CanvasRenderer {
                 Multiply        => render::BlendMode::Multiply,
                 Screen          => render::BlendMode::Screen,
 
-                // TODO: these are not supported yet (they might require explicit shader support)
-                Darken          => render::BlendMode::SourceOver,
-                Lighten         => render::BlendMode::SourceOver,
+                // These are implemented by the renderer using a shader
+                Darken          => render::BlendMode::Darken,
+                Lighten         => render::BlendMode::Lighten,
+                Overlay         => render::BlendMode::Overlay,
+                ColorDodge      => render::BlendMode::ColorDodge,
+                ColorBurn       => render::BlendMode::ColorBurn,
+                HardLight       => render::BlendMode::HardLight,
+                SoftLight       => render::BlendMode::SoftLight,
+                Difference      => render::BlendMode::Difference,
+                Exclusion       => render::BlendMode::Exclusion,
+                Hue             => render::BlendMode::Hue,
+                Saturation      => render::BlendMode::Saturation,
+                Color           => render::BlendMode::Color,
+                Luminosity      => render::BlendMode::Luminosity,
             };
 
             core.layer(self.current_layer).render_order.push(RenderEntity::SetBlendMode(blend_mode));
@@ -241,6 +241,10 @@ impl CanvasRenderer {
             if layer.state.fill_color.texture_id() == Some(texture_id) {
                 layer.state.fill_color  = layer.state.fill_color.with_texture_alpha(alpha);
             }
+
+            if layer.state.stroke_fill.texture_id() == Some(texture_id) {
+                layer.state.stroke_fill = layer.state.stroke_fill.with_texture_alpha(alpha);
+            }
         });
     }
 
@@ -26,7 +26,17 @@ pub enum FillState {
     ///
     /// Fill with a particular gradient
     ///
-    LinearGradient(render::TextureId, canvas::GradientId, render::Matrix, bool, f32)
+    LinearGradient(render::TextureId, canvas::GradientId, render::Matrix, render::GradientSpread, f32),
+
+    ///
+    /// Fill with a radial gradient (the matrix maps to a unit circle, and the focal point is relative to that circle)
+    ///
+    RadialGradient(render::TextureId, canvas::GradientId, render::Matrix, (f32, f32), render::GradientSpread, f32),
+
+    ///
+    /// Fill with a conic gradient (the matrix maps to a space where the gradient is centered on the origin and starts along the x axis)
+    ///
+    ConicGradient(render::TextureId, canvas::GradientId, render::Matrix, render::GradientSpread, f32)
 }
 
 impl FillState {
@@ -38,7 +48,9 @@ impl FillState {
             FillState::None                             => render::Rgba8([0, 0, 0, 255]),
             FillState::Color(color)                     => *color,
             FillState::Texture(_, _, _, _, _)           => render::Rgba8([0, 0, 0, 255]),
-            FillState::LinearGradient(_, _, _, _, _)    => render::Rgba8([0, 0, 0, 255])
+            FillState::LinearGradient(_, _, _, _, _)    => render::Rgba8([0, 0, 0, 255]),
+            FillState::RadialGradient(_, _, _, _, _, _) => render::Rgba8([0, 0, 0, 255]),
+            FillState::ConicGradient(_, _, _, _, _)     => render::Rgba8([0, 0, 0, 255])
         }
     }
 
@@ -73,7 +85,7 @@ impl FillState {
     ///
     /// Creates a linear gradient fill
     ///
-    pub fn linear_gradient_fill(render_texture: render::TextureId, canvas_gradient: canvas::GradientId, x1: f32, y1: f32, x2: f32, y2: f32) -> FillState {
+    pub fn linear_gradient_fill(render_texture: render::TextureId, canvas_gradient: canvas::GradientId, x1: f32, y1: f32, x2: f32, y2: f32, spread: render::GradientSpread) -> FillState {
         // Avoid division by zero
         let x2 = if x2 == x1 { x1 + 0.0000001 } else { x2 };
         let y2 = if y2 == y1 { y1 + 0.0000001 } else { y2 };
@@ -104,7 +116,66 @@ impl FillState {
         ]);
 
         // Create the fill-state for this matrix
-        FillState::LinearGradient(render_texture, canvas_gradient, matrix, false, 1.0)
+        FillState::LinearGradient(render_texture, canvas_gradient, matrix, spread, 1.0)
+    }
+
+    ///
+    /// Creates a radial gradient fill
+    ///
+    pub fn radial_gradient_fill(render_texture: render::TextureId, canvas_gradient: canvas::GradientId, (cx, cy): (f32, f32), radius: f32, focus: Option<(f32, f32)>, spread: render::GradientSpread) -> FillState {
+        // Avoid division by zero
+        let radius  = if radius == 0.0 { 0.0000001 } else { radius };
+
+        // Generate a matrix that maps the circle to a unit circle around the origin
+        let a       = 1.0/radius;
+        let b       = 0.0;
+        let c       = -cx * a;
+
+        let d       = 0.0;
+        let e       = 1.0/radius;
+        let f       = -cy * e;
+
+        let matrix  = render::Matrix([
+            [a,   b,   0.0, c  ],
+            [d,   e,   0.0, f  ],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ]);
+
+        // The focal point is in the same coordinate scheme, and must be inside the circle (we move it just inside the edge if it's outside)
+        let (fx, fy)    = focus.map(|(fx, fy)| ((fx-cx)*a, (fy-cy)*e)).unwrap_or((0.0, 0.0));
+        let focal_len   = f32::sqrt(fx*fx + fy*fy);
+        let focal_point = if focal_len > 0.99 { (fx * 0.99/focal_len, fy * 0.99/focal_len) } else { (fx, fy) };
+
+        // Create the fill-state for this matrix
+        FillState::RadialGradient(render_texture, canvas_gradient, matrix, focal_point, spread, 1.0)
+    }
+
+    ///
+    /// Creates a conic gradient fill
+    ///
+    pub fn conic_gradient_fill(render_texture: render::TextureId, canvas_gradient: canvas::GradientId, (cx, cy): (f32, f32), start_angle: f32) -> FillState {
+        // Generate a matrix that moves the center to the origin and rotates the start angle onto the x axis
+        let cos     = f32::cos(-start_angle);
+        let sin     = f32::sin(-start_angle);
+
+        let a       = cos;
+        let b       = -sin;
+        let d       = sin;
+        let e       = cos;
+
+        let c       = -cx * a - cy * b;
+        let f       = -cx * d - cy * e;
+
+        let matrix  = render::Matrix([
+            [a,   b,   0.0, c  ],
+            [d,   e,   0.0, f  ],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ]);
+
+        // Create the fill-state for this matrix (the gradient position is always between 0 and 1, so conic gradients are always padded)
+        FillState::ConicGradient(render_texture, canvas_gradient, matrix, render::GradientSpread::Pad, 1.0)
     }
 
     ///
@@ -115,7 +186,9 @@ impl FillState {
             FillState::None                             => None,
             FillState::Color(_)                         => None,
             FillState::Texture(_, texture_id, _, _, _)  => Some(*texture_id),
-            FillState::LinearGradient(_, _, _, _, _)    => None
+            FillState::LinearGradient(_, _, _, _, _)    => None,
+            FillState::RadialGradient(_, _, _, _, _, _) => None,
+            FillState::ConicGradient(_, _, _, _, _)     => None
         }
     }
 
@@ -127,7 +200,9 @@ impl FillState {
             FillState::None                                                         => self.clone(),
             FillState::Color(_)                                                     => self.clone(),
             FillState::Texture(render_texture, canvas_texture, matrix, repeat, _)   => FillState::Texture(*render_texture, *canvas_texture, *matrix, *repeat, new_alpha),
-            FillState::LinearGradient(_, _, _, _, _)                                => self.clone()
+            FillState::LinearGradient(_, _, _, _, _)                                => self.clone(),
+            FillState::RadialGradient(_, _, _, _, _, _)                             => self.clone(),
+            FillState::ConicGradient(_, _, _, _, _)                                 => self.clone()
         }
     }
 
@@ -141,7 +216,12 @@ impl FillState {
             FillState::None                                                                     => self.clone(),
             FillState::Color(_)                                                                 => self.clone(),
             FillState::Texture(render_texture, canvas_texture, matrix, repeat, alpha)           => FillState::Texture(*render_texture, *canvas_texture, (*matrix).multiply(transform_matrix), *repeat, *alpha),
-            FillState::LinearGradient(render_texture, canvas_gradient, matrix, repeat, alpha)   => FillState::LinearGradient(*render_texture, *canvas_gradient, (*matrix).multiply(transform_matrix), *repeat, *alpha)
+            FillState::LinearGradient(render_texture, canvas_gradient, matrix, spread, alpha)   => FillState::LinearGradient(*render_texture, *canvas_gradient, (*matrix).multiply(transform_matrix), *spread, *alpha),
+
+            // The focal point is relative to the gradient circle, so it's not affected by the transformation
+            FillState::RadialGradient(render_texture, canvas_gradient, matrix, focal_point, spread, alpha)  => FillState::RadialGradient(*render_texture, *canvas_gradient, (*matrix).multiply(transform_matrix), *focal_point, *spread, *alpha),
+
+            FillState::ConicGradient(render_texture, canvas_gradient, matrix, spread, alpha)    => FillState::ConicGradient(*render_texture, *canvas_gradient, (*matrix).multiply(transform_matrix), *spread, *alpha)
         }
     }
 }
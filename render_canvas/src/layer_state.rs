@@ -28,6 +28,9 @@ pub struct LayerState {
     /// The settings for the next brush stroke
     pub stroke_settings: StrokeSettings,
 
+    /// The texture or gradient to use for the next brush stroke (or `FillState::None` to use the stroke colour)
+    pub stroke_fill: FillState,
+
     /// Where the canvas's rendering should be rolled back to on the next 'restore' operation
     pub restore_point: Option<usize>,
 
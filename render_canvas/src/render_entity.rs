@@ -54,7 +54,13 @@ pub enum RenderEntity {
     SetFillTexture(render::TextureId, render::Matrix, bool, f32),
 
     /// Sets the gradient texture to use for the following rendering
-    SetFillGradient(render::TextureId, render::Matrix, bool, f32),
+    SetFillGradient(render::TextureId, render::Matrix, render::GradientSpread, f32),
+
+    /// Sets the radial gradient texture and focal point to use for the following rendering
+    SetFillRadialGradient(render::TextureId, render::Matrix, (f32, f32), render::GradientSpread, f32),
+
+    /// Sets the conic gradient texture to use for the following rendering
+    SetFillConicGradient(render::TextureId, render::Matrix, render::GradientSpread, f32),
 
     /// Use the specified vertex buffer to define a clipping mask
     EnableClipping(render::VertexBufferId, render::IndexBufferId, usize),
@@ -9,3 +9,21 @@ pub enum RenderGradient {
     Defined(Vec<canvas::GradientOp>),
     Ready(render::TextureId, Vec<canvas::GradientOp>)
 }
+
+impl RenderGradient {
+    ///
+    /// Returns the spread mode to use when rendering this gradient
+    ///
+    pub fn spread(&self) -> render::GradientSpread {
+        let definition = match self {
+            RenderGradient::Defined(definition)     => definition,
+            RenderGradient::Ready(_, definition)    => definition
+        };
+
+        match canvas::gradient_spread(definition) {
+            canvas::GradientSpread::Pad     => render::GradientSpread::Pad,
+            canvas::GradientSpread::Repeat  => render::GradientSpread::Repeat,
+            canvas::GradientSpread::Reflect => render::GradientSpread::Reflect
+        }
+    }
+}
@@ -129,6 +129,12 @@ impl RenderCore {
                     .map(|usage_count| *usage_count -= 1);
             }
 
+            SetFillRadialGradient(texture_id, ..)   |
+            SetFillConicGradient(texture_id, ..)    => { 
+                self.used_textures.get_mut(&texture_id)
+                    .map(|usage_count| *usage_count -= 1);
+            }
+
             RenderSpriteWithFilters(_, _, _, filters) => { 
                 let textures = filters.iter().flat_map(|filter| filter.used_textures());
                 for texture_id in textures {
@@ -169,14 +175,19 @@ impl RenderCore {
             .map(|(texture_id, _count)| *texture_id)
             .collect::<HashSet<_>>();
 
-        // Remove any texture that's selected as the fill state from the unused list (these still count as 'used')
+        // Remove any texture that's selected as the fill or stroke state from the unused list (these still count as 'used')
         for layer_handle in self.layers.iter() {
             let state = &self.layer_readonly(*layer_handle).state;
-            match &state.fill_color {
-                FillState::Texture(texture_id, _, _, _, _)          => { unused_textures.remove(texture_id); }
-                FillState::LinearGradient(texture_id, _, _, _, _)   => { unused_textures.remove(texture_id); }
 
-                _ => { }
+            for fill_state in [&state.fill_color, &state.stroke_fill] {
+                match fill_state {
+                    FillState::Texture(texture_id, _, _, _, _)          => { unused_textures.remove(texture_id); }
+                    FillState::LinearGradient(texture_id, _, _, _, _)   => { unused_textures.remove(texture_id); }
+                    FillState::RadialGradient(texture_id, ..)           => { unused_textures.remove(texture_id); }
+                    FillState::ConicGradient(texture_id, ..)            => { unused_textures.remove(texture_id); }
+
+                    _ => { }
+                }
             }
         }
 
@@ -517,6 +528,7 @@ impl RenderCore {
                 fill_color:         FillState::Color(render::Rgba8([0, 0, 0, 255])),
                 winding_rule:       FillRule::NonZero,
                 stroke_settings:    StrokeSettings::new(),
+                stroke_fill:        FillState::None,
                 current_matrix:     canvas::Transform2D::identity(),
                 sprite_matrix:      canvas::Transform2D::identity(),
                 scale_factor:       1.0,
@@ -46,7 +46,13 @@ enum ShaderModifier {
     Texture(render::TextureId, render::Matrix, bool, f32),
 
     /// Shader should use a gradient
-    Gradient(render::TextureId, render::Matrix, bool, f32),
+    Gradient(render::TextureId, render::Matrix, render::GradientSpread, f32),
+
+    /// Shader should use a radial gradient
+    RadialGradient(render::TextureId, render::Matrix, (f32, f32), render::GradientSpread, f32),
+
+    /// Shader should use a conic gradient
+    ConicGradient(render::TextureId, render::Matrix, render::GradientSpread, f32),
 }
 
 ///
@@ -295,7 +301,9 @@ impl RenderStreamState {
                     ShaderModifier::Simple                                      => render::ShaderType::Simple { clip_texture: clip },
                     ShaderModifier::DashPattern(_)                              => render::ShaderType::DashedLine { dash_texture: DASH_TEXTURE, clip_texture: clip },
                     ShaderModifier::Texture(texture_id, matrix, repeat, alpha)  => render::ShaderType::Texture { texture: *texture_id, texture_transform: *matrix, repeat: *repeat, alpha: *alpha, clip_texture: clip },
-                    ShaderModifier::Gradient(texture_id, matrix, repeat, alpha) => render::ShaderType::LinearGradient { texture: *texture_id, texture_transform: *matrix, repeat: *repeat, alpha: *alpha, clip_texture: clip }
+                    ShaderModifier::Gradient(texture_id, matrix, spread, alpha) => render::ShaderType::LinearGradient { texture: *texture_id, texture_transform: *matrix, spread: *spread, alpha: *alpha, clip_texture: clip },
+                    ShaderModifier::RadialGradient(texture_id, matrix, focal_point, spread, alpha) => render::ShaderType::RadialGradient { texture: *texture_id, texture_transform: *matrix, focal_point: *focal_point, spread: *spread, alpha: *alpha, clip_texture: clip },
+                    ShaderModifier::ConicGradient(texture_id, matrix, spread, alpha) => render::ShaderType::ConicGradient { texture: *texture_id, texture_transform: *matrix, spread: *spread, alpha: *alpha, clip_texture: clip },
                 };
 
                 // Add to the updates
@@ -309,6 +317,8 @@ impl RenderStreamState {
                     ShaderModifier::DashPattern(new_dash_pattern)   => { updates.extend(self.generate_dash_pattern(new_dash_pattern).into_iter().rev()); }
                     ShaderModifier::Texture(_, _, _, _)             => { }
                     ShaderModifier::Gradient(_, _, _, _)            => { }
+                    ShaderModifier::RadialGradient(_, _, _, _, _)   => { }
+                    ShaderModifier::ConicGradient(_, _, _, _)       => { }
                 }
             }
         }
@@ -397,6 +407,9 @@ impl RenderCore {
         let initial_invalid_bounds      = initial_state.invalid_bounds;
         let is_sprite                   = layer.state.is_sprite;
 
+        // Blend modes that need a shader are drawn to a separate render target, which is blended with the layer when the blend mode changes
+        let mut shader_blend: Option<(render::BlendMode, render::RenderTargetId, render::TextureId)> = None;
+
         render_state.transform          = Some(viewport_transform);
         render_state.blend_mode         = Some(render::BlendMode::SourceOver);
         render_state.render_target      = Some(render_target);
@@ -463,8 +476,9 @@ impl RenderCore {
                         // The items from before the sprite should be rendered using the current state
                         let old_state               = render_state.clone();
 
-                        // Render the layer associated with the sprite
-                        let render_sprite           = core.render_layer(combined_transform, sprite_layer_handle, render_target, render_state);
+                        // Render the layer associated with the sprite (to the shader blend target if one is active)
+                        let sprite_render_target    = render_state.render_target.unwrap_or(render_target);
+                        let render_sprite           = core.render_layer(combined_transform, sprite_layer_handle, sprite_render_target, render_state);
 
                         // Render the sprite
                         render_order.extend(render_sprite);
@@ -613,14 +627,42 @@ impl RenderCore {
                 },
 
                 SetBlendMode(new_blend_mode) => {
-                    let old_state               = render_state.clone();
+                    let new_blend_mode          = *new_blend_mode;
+                    let mut old_state           = render_state.clone();
 
-                    // Render to the main buffer
-                    render_state.blend_mode     = Some(*new_blend_mode);
-                    render_state.render_target  = Some(render_target);
+                    // Blend anything drawn using the previous blend mode with the layer
+                    if let Some((blend_mode, blend_render_target, blend_texture)) = shader_blend.take() {
+                        render_order.extend(core.finish_shader_blend(render_target, blend_mode, blend_render_target, blend_texture, render_state.invalid_bounds));
 
-                    // Update to the new state
-                    render_order.extend(render_state.update_from_state(&old_state));
+                        // The blend target has been freed and the renderer state is unknown after drawing the frame buffer
+                        old_state.render_target = None;
+                    }
+
+                    if new_blend_mode.is_shader_blend() {
+                        // Render to a separate buffer using the standard blend mode
+                        let blend_texture       = core.allocate_texture();
+                        let blend_render_target = core.allocate_render_target();
+                        let viewport_size       = render_state.viewport_size;
+
+                        render_order.push(render::RenderAction::CreateRenderTarget(blend_render_target, blend_texture, viewport_size, render::RenderTargetType::MultisampledTexture));
+
+                        render_state.blend_mode     = Some(render::BlendMode::SourceOver);
+                        render_state.render_target  = Some(blend_render_target);
+                        render_order.extend(render_state.update_from_state(&old_state));
+                        render_order.push(render::RenderAction::Clear(render::Rgba8([0,0,0,0])));
+
+                        shader_blend = Some((new_blend_mode, blend_render_target, blend_texture));
+                    } else {
+                        // Render to the main buffer
+                        render_state.blend_mode     = Some(new_blend_mode);
+                        render_state.render_target  = Some(render_target);
+
+                        // Update to the new state
+                        render_order.extend(render_state.update_from_state(&old_state));
+                    }
+
+                    // Reborrow the layer
+                    layer                   = core.layer(layer_handle);
                 },
 
                 EnableClipping(vertex_buffer, index_buffer, buffer_size) => {
@@ -674,10 +716,28 @@ impl RenderCore {
                     render_order.extend(render_state.update_from_state(&old_state));
                 }
 
-                SetFillGradient(texture_id, matrix, repeat, alpha) => {
+                SetFillGradient(texture_id, matrix, spread, alpha) => {
                     // Set the shader modifier to use the gradient texture (overriding any other shader modifier)
                     let old_state                   = render_state.clone();
-                    render_state.shader_modifier    = Some(ShaderModifier::Gradient(*texture_id, *matrix, *repeat, *alpha));
+                    render_state.shader_modifier    = Some(ShaderModifier::Gradient(*texture_id, *matrix, *spread, *alpha));
+
+                    // Update to the new state
+                    render_order.extend(render_state.update_from_state(&old_state));
+                }
+
+                SetFillRadialGradient(texture_id, matrix, focal_point, spread, alpha) => {
+                    // Set the shader modifier to use the radial gradient texture (overriding any other shader modifier)
+                    let old_state                   = render_state.clone();
+                    render_state.shader_modifier    = Some(ShaderModifier::RadialGradient(*texture_id, *matrix, *focal_point, *spread, *alpha));
+
+                    // Update to the new state
+                    render_order.extend(render_state.update_from_state(&old_state));
+                }
+
+                SetFillConicGradient(texture_id, matrix, spread, alpha) => {
+                    // Set the shader modifier to use the conic gradient texture (overriding any other shader modifier)
+                    let old_state                   = render_state.clone();
+                    render_state.shader_modifier    = Some(ShaderModifier::ConicGradient(*texture_id, *matrix, *spread, *alpha));
 
                     // Update to the new state
                     render_order.extend(render_state.update_from_state(&old_state));
@@ -685,6 +745,19 @@ impl RenderCore {
             }
         }
 
+        // Blend any drawing still in the shader blend target with the layer
+        if let Some((blend_mode, blend_render_target, blend_texture)) = shader_blend.take() {
+            render_order.extend(core.finish_shader_blend(render_target, blend_mode, blend_render_target, blend_texture, render_state.invalid_bounds));
+
+            // The shader and transform need to be set again for the next layer
+            render_state.render_target      = Some(render_target);
+            render_state.blend_mode         = Some(blend_mode);
+            render_state.shader_modifier    = None;
+            render_state.transform          = None;
+
+            layer = core.layer(layer_handle);
+        }
+
         // If the layer has 'commit after rendering' and the next layer does not have 'commit before rendering', then commit what we just rendered
         if layer.commit_after_rendering && !render_state.invalid_bounds.is_undefined() && !is_sprite {
             // Work out the invalid region of the current layer
@@ -703,8 +776,19 @@ impl RenderCore {
                 canvas::BlendMode::DestinationAtop  => render::BlendMode::DestinationATop,
                 canvas::BlendMode::Multiply         => render::BlendMode::Multiply,
                 canvas::BlendMode::Screen           => render::BlendMode::Screen,
-                canvas::BlendMode::Darken           => render::BlendMode::SourceOver,
-                canvas::BlendMode::Lighten          => render::BlendMode::SourceOver,
+                canvas::BlendMode::Darken           => render::BlendMode::Darken,
+                canvas::BlendMode::Lighten          => render::BlendMode::Lighten,
+                canvas::BlendMode::Overlay          => render::BlendMode::Overlay,
+                canvas::BlendMode::ColorDodge       => render::BlendMode::ColorDodge,
+                canvas::BlendMode::ColorBurn        => render::BlendMode::ColorBurn,
+                canvas::BlendMode::HardLight        => render::BlendMode::HardLight,
+                canvas::BlendMode::SoftLight        => render::BlendMode::SoftLight,
+                canvas::BlendMode::Difference       => render::BlendMode::Difference,
+                canvas::BlendMode::Exclusion        => render::BlendMode::Exclusion,
+                canvas::BlendMode::Hue              => render::BlendMode::Hue,
+                canvas::BlendMode::Saturation       => render::BlendMode::Saturation,
+                canvas::BlendMode::Color            => render::BlendMode::Color,
+                canvas::BlendMode::Luminosity       => render::BlendMode::Luminosity,
             };
 
             render_order.extend(vec![
@@ -729,6 +813,25 @@ impl RenderCore {
         return render_order;
     }
 
+    ///
+    /// Draws the contents of a shader blend render target onto the layer render target using the specified blend mode, then frees it
+    ///
+    fn finish_shader_blend(&mut self, render_target: render::RenderTargetId, blend_mode: render::BlendMode, blend_render_target: render::RenderTargetId, blend_texture: render::TextureId, invalid_bounds: LayerBounds) -> Vec<render::RenderAction> {
+        use render::RenderAction::*;
+
+        self.free_render_target(blend_render_target);
+        self.free_texture(blend_texture);
+
+        vec![
+            SelectRenderTarget(render_target),
+            BlendMode(blend_mode),
+            DrawFrameBuffer(blend_render_target, invalid_bounds.into(), render::Alpha(1.0)),
+
+            FreeRenderTarget(blend_render_target),
+            FreeTexture(blend_texture),
+        ]
+    }
+
 
     ///
     /// Given a texture to use as a render target, renders a layer to it
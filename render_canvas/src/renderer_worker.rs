@@ -7,7 +7,11 @@ use super::render_entity_details::*;
 use flo_render as render;
 use flo_canvas as canvas;
 
+use canvas::{Coord2, Coordinate2D};
+use canvas::curves::bezier::path::{SimpleBezierPath};
+
 use lyon::path;
+use lyon::math::{point, Point};
 use lyon::tessellation;
 use lyon::tessellation::{VertexBuffers, BuffersBuilder, Side, StrokeVertex, StrokeOptions, FillVertex, FillOptions, FillRule};
 
@@ -162,6 +166,7 @@ impl CanvasWorker {
             canvas::LineJoin::Bevel => tessellation::LineJoin::Bevel,
             canvas::LineJoin::Round => tessellation::LineJoin::Round
         };
+        stroke_options.miter_limit  = f32::max(StrokeOptions::MINIMUM_MITER_LIMIT, stroke_settings.miter_limit);
 
         stroke_options
     }
@@ -198,10 +203,58 @@ impl CanvasWorker {
         geometry
     }
 
+    ///
+    /// Splits a path into the dashes of a dash pattern
+    ///
+    fn dashed_path(path: &path::Path, dash_pattern: &[f32], dash_offset: f32) -> path::Path {
+        use self::path::Event;
+
+        // Convert to bezier paths (lines become curves with their control points along the line)
+        let coord       = |pos: Point| Coord2(pos.x as _, pos.y as _);
+        let line        = |from: Coord2, to: Coord2| ((to - from) * (1.0/3.0) + from, (to - from) * (2.0/3.0) + from, to);
+        let mut paths   = Vec::<SimpleBezierPath>::new();
+
+        for event in path.iter() {
+            let curve = match event {
+                Event::Begin { at }                         => { paths.push((coord(at), vec![])); continue; }
+                Event::Line { from, to }                    => line(coord(from), coord(to)),
+                Event::Quadratic { from, ctrl, to }         => ((coord(ctrl) - coord(from)) * (2.0/3.0) + coord(from), (coord(ctrl) - coord(to)) * (2.0/3.0) + coord(to), coord(to)),
+                Event::Cubic { ctrl1, ctrl2, to, .. }       => (coord(ctrl1), coord(ctrl2), coord(to)),
+                Event::End { last, first, close: true }     => { if last == first { continue; } line(coord(last), coord(first)) }
+                Event::End { close: false, .. }             => { continue; }
+            };
+
+            if let Some((_, curves)) = paths.last_mut() {
+                curves.push(curve);
+            }
+        }
+
+        // Each subpath starts at the beginning of the dash pattern
+        let mut builder = path::Path::builder();
+
+        for subpath in paths.iter() {
+            for (start_point, curves) in canvas::path_to_dashed_lines::<_, SimpleBezierPath, _>(subpath, dash_pattern.iter().map(|length| *length as f64), dash_offset as f64) {
+                builder.begin(point(start_point.x() as _, start_point.y() as _));
+
+                for (cp1, cp2, end_point) in curves {
+                    builder.cubic_bezier_to(point(cp1.x() as _, cp1.y() as _), point(cp2.x() as _, cp2.y() as _), point(end_point.x() as _, end_point.y() as _));
+                }
+
+                builder.end(false);
+            }
+        }
+
+        builder.build()
+    }
+
     ///
     /// Strokes a path and returns the resulting render entity
     ///
+    /// If the stroke settings have a dash pattern, the path is split into dashes before it's stroked (the canvas renderer only
+    /// leaves the dash pattern in the settings for strokes that can't use the dashed line shader)
+    ///
     fn stroke(&mut self, path: path::Path, stroke_options: StrokeSettings, scale_factor: f64, transform: canvas::Transform2D, entity: LayerEntityRef) -> (LayerEntityRef, RenderEntity, RenderEntityDetails) {
+        let path        = if stroke_options.dash_pattern.is_empty() { path } else { Self::dashed_path(&path, &stroke_options.dash_pattern, stroke_options.dash_offset) };
         let geometry    = self.stroke_geometry(path, stroke_options, scale_factor);
         let details     = RenderEntityDetails::from_vertices(&geometry.vertices, &transform);
 
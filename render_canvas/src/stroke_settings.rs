@@ -8,6 +8,7 @@ use flo_render as render;
 pub struct StrokeSettings {
     pub stroke_color:   render::Rgba8,
     pub join:           canvas::LineJoin,
+    pub miter_limit:    f32,
     pub cap:            canvas::LineCap,
     pub dash_pattern:   Vec<f32>,
     pub dash_offset:    f32,
@@ -22,6 +23,7 @@ impl StrokeSettings {
         StrokeSettings {
             stroke_color:   render::Rgba8([0, 0, 0, 255]),
             join:           canvas::LineJoin::Round,
+            miter_limit:    4.0,
             cap:            canvas::LineCap::Butt,
             dash_pattern:   vec![],
             dash_offset:    0.0,
@@ -258,3 +258,200 @@ fn clip_rect() {
         // Remaining instructions finish the render
     })
 }
+
+#[test]
+fn stroke_with_gradient() {
+    // Draw a line using a gradient
+    let mut gradient_line = vec![];
+    gradient_line.create_gradient(GradientId(1), Color::Rgba(0.0, 0.0, 0.0, 1.0));
+    gradient_line.gradient_stop(GradientId(1), 1.0, Color::Rgba(1.0, 1.0, 1.0, 1.0));
+
+    gradient_line.new_path();
+    gradient_line.move_to(0.0, 0.0);
+    gradient_line.line_to(100.0, 100.0);
+    gradient_line.stroke_gradient(GradientId(1), 0.0, 0.0, 100.0, 100.0);
+    gradient_line.stroke();
+
+    executor::block_on(async {
+        // Create the renderer
+        let mut renderer    = CanvasRenderer::new();
+
+        // Render the line
+        let rendering       = renderer.draw(gradient_line.into_iter()).collect::<Vec<_>>().await;
+
+        println!("{:?}", rendering);
+
+        // The line should be drawn using the gradient shader
+        assert!(rendering.iter().any(|action| match action { RenderAction::UseShader(render::ShaderType::LinearGradient { .. }) => true, _ => false }));
+    })
+}
+
+#[test]
+fn dashed_stroke_with_gradient() {
+    // Draw a line using a gradient, with and without a dash pattern
+    let mut gradient_line = vec![];
+    gradient_line.create_gradient(GradientId(1), Color::Rgba(0.0, 0.0, 0.0, 1.0));
+    gradient_line.gradient_stop(GradientId(1), 1.0, Color::Rgba(1.0, 1.0, 1.0, 1.0));
+
+    gradient_line.new_path();
+    gradient_line.move_to(0.0, 0.0);
+    gradient_line.line_to(100.0, 0.0);
+    gradient_line.stroke_gradient(GradientId(1), 0.0, 0.0, 100.0, 0.0);
+
+    let solid_line      = gradient_line.iter().cloned().chain(vec![Draw::Stroke]).collect::<Vec<_>>();
+
+    gradient_line.new_dash_pattern();
+    gradient_line.dash_length(10.0);
+    gradient_line.dash_length(10.0);
+    gradient_line.stroke();
+
+    let vertex_count    = |rendering: &Vec<RenderAction>| rendering.iter().map(|action| match action { RenderAction::CreateVertex2DBuffer(_, vertices) => vertices.len(), _ => 0 }).sum::<usize>();
+
+    executor::block_on(async {
+        // Render the lines
+        let solid_rendering     = CanvasRenderer::new().draw(solid_line.into_iter()).collect::<Vec<_>>().await;
+        let dashed_rendering    = CanvasRenderer::new().draw(gradient_line.into_iter()).collect::<Vec<_>>().await;
+
+        println!("{:?}", dashed_rendering);
+
+        // The dashed line should still be drawn using the gradient shader, with the dashes tessellated separately
+        assert!(dashed_rendering.iter().any(|action| match action { RenderAction::UseShader(render::ShaderType::LinearGradient { .. }) => true, _ => false }));
+        assert!(!dashed_rendering.iter().any(|action| match action { RenderAction::UseShader(render::ShaderType::DashedLine { .. }) => true, _ => false }));
+        assert!(vertex_count(&dashed_rendering) > vertex_count(&solid_rendering), "{} {}", vertex_count(&dashed_rendering), vertex_count(&solid_rendering));
+    })
+}
+
+#[test]
+fn stroke_color_resets_gradient() {
+    // Set a gradient for the stroke, then replace it with a colour
+    let mut colored_line = vec![];
+    colored_line.create_gradient(GradientId(1), Color::Rgba(0.0, 0.0, 0.0, 1.0));
+    colored_line.gradient_stop(GradientId(1), 1.0, Color::Rgba(1.0, 1.0, 1.0, 1.0));
+
+    colored_line.new_path();
+    colored_line.move_to(0.0, 0.0);
+    colored_line.line_to(100.0, 100.0);
+    colored_line.stroke_gradient(GradientId(1), 0.0, 0.0, 100.0, 100.0);
+    colored_line.stroke_color(Color::Rgba(1.0, 0.0, 0.0, 1.0));
+    colored_line.stroke();
+
+    executor::block_on(async {
+        // Create the renderer
+        let mut renderer    = CanvasRenderer::new();
+
+        // Render the line
+        let rendering       = renderer.draw(colored_line.into_iter()).collect::<Vec<_>>().await;
+
+        println!("{:?}", rendering);
+
+        // The line should be drawn using a flat colour
+        assert!(!rendering.iter().any(|action| match action { RenderAction::UseShader(render::ShaderType::LinearGradient { .. }) => true, _ => false }));
+    })
+}
+
+#[test]
+fn fill_with_shader_blend_mode() {
+    // Fill a circle using a blend mode that needs to be implemented in a shader
+    let mut overlay_circle = vec![];
+    overlay_circle.blend_mode(BlendMode::Overlay);
+    overlay_circle.new_path();
+    overlay_circle.circle(100.0, 100.0, 50.0);
+    overlay_circle.fill();
+
+    executor::block_on(async {
+        // Create the renderer
+        let mut renderer    = CanvasRenderer::new();
+
+        // Render the circle
+        let rendering       = renderer.draw(overlay_circle.into_iter()).collect::<Vec<_>>().await;
+
+        println!("{:?}", rendering);
+
+        // The circle should be drawn to a separate render target, which is then drawn to the layer using the overlay blend mode
+        let blend_pos       = rendering.iter().position(|action| match action { RenderAction::BlendMode(render::BlendMode::Overlay) => true, _ => false });
+        assert!(blend_pos.is_some());
+
+        let blend_pos       = blend_pos.unwrap();
+        assert!(rendering.iter().take(blend_pos).any(|action| match action { RenderAction::DrawIndexedTriangles(_, _, _) => true, _ => false }));
+        assert!(match rendering[blend_pos+1] { RenderAction::DrawFrameBuffer(render_target, _, _) => render_target != RenderTargetId(0), _ => false });
+    })
+}
+
+#[test]
+fn layer_with_shader_blend_mode() {
+    // Draw on a layer that's blended using the difference blend mode
+    let mut difference_layer = vec![];
+    difference_layer.layer(LayerId(1));
+    difference_layer.layer_blend(LayerId(1), BlendMode::Difference);
+    difference_layer.new_path();
+    difference_layer.circle(100.0, 100.0, 50.0);
+    difference_layer.fill();
+
+    executor::block_on(async {
+        // Create the renderer
+        let mut renderer    = CanvasRenderer::new();
+
+        // Render the layer
+        let rendering       = renderer.draw(difference_layer.into_iter()).collect::<Vec<_>>().await;
+
+        println!("{:?}", rendering);
+
+        // The layer should be drawn to the frame buffer using the difference blend mode
+        let blend_pos       = rendering.iter().position(|action| match action { RenderAction::BlendMode(render::BlendMode::Difference) => true, _ => false });
+        assert!(blend_pos.is_some());
+
+        let blend_pos       = blend_pos.unwrap();
+        assert!(match rendering[blend_pos+1] { RenderAction::DrawFrameBuffer(RenderTargetId(0), _, _) => true, _ => false });
+    })
+}
+
+#[test]
+fn fill_ellipse_and_rounded_rect() {
+    // Fill shapes made up of arcs and quadratic curves
+    let mut shapes = vec![];
+    shapes.new_path();
+    shapes.ellipse(100.0, 100.0, 50.0, 25.0, 0.5);
+    shapes.rounded_rect(200.0, 200.0, 300.0, 250.0, (10.0, 0.0, 20.0, 5.0));
+    shapes.move_to(0.0, 0.0);
+    shapes.quadratic_curve_to(100.0, 0.0, 50.0, 50.0);
+    shapes.fill();
+
+    executor::block_on(async {
+        // Create the renderer
+        let mut renderer    = CanvasRenderer::new();
+
+        // Render the shapes
+        let rendering       = renderer.draw(shapes.into_iter()).collect::<Vec<_>>().await;
+
+        println!("{:?}", rendering);
+
+        // The shapes should be tessellated and drawn
+        assert!(rendering.iter().any(|action| match action { RenderAction::CreateVertex2DBuffer(_, vertices) => vertices.len() > 0, _ => false }));
+        assert!(rendering.iter().any(|action| match action { RenderAction::DrawIndexedTriangles(_, _, _) => true, _ => false }));
+    })
+}
+
+#[test]
+fn fill_combined_path() {
+    // Fill the intersection of two rectangles
+    let mut shapes = vec![];
+    shapes.new_path();
+    shapes.rect(0.0, 0.0, 100.0, 100.0);
+    shapes.store_path();
+    shapes.new_path();
+    shapes.rect(50.0, 50.0, 150.0, 150.0);
+    shapes.combine_path(PathArithmetic::Intersection);
+    shapes.fill();
+
+    executor::block_on(async {
+        // Create the renderer
+        let mut renderer    = CanvasRenderer::new();
+
+        // Render the shapes
+        let rendering       = renderer.draw(shapes.into_iter()).collect::<Vec<_>>().await;
+
+        // The combined path should be tessellated and drawn
+        assert!(rendering.iter().any(|action| match action { RenderAction::CreateVertex2DBuffer(_, vertices) => vertices.len() > 0, _ => false }));
+        assert!(rendering.iter().any(|action| match action { RenderAction::DrawIndexedTriangles(_, _, _) => true, _ => false }));
+    })
+}